@@ -401,6 +401,19 @@ pub fn path_all_saves(name: &MapName, edits_name: &str, run_name: &str) -> Strin
     ))
 }
 
+pub fn path_checkpoint(name: &MapName, edits_name: &str, checkpoint: &str) -> String {
+    path(format!(
+        "player/checkpoints/{}/{}/{}/{}/{}.bin",
+        name.city.country, name.city.city, name.map, edits_name, checkpoint
+    ))
+}
+pub fn path_all_checkpoints(name: &MapName, edits_name: &str) -> String {
+    path(format!(
+        "player/checkpoints/{}/{}/{}/{}",
+        name.city.country, name.city.city, name.map, edits_name
+    ))
+}
+
 pub fn path_trips(name: &MapName) -> String {
     path(format!(
         "player/routes/{}/{}/{}.json",
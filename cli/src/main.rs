@@ -37,6 +37,10 @@ enum Command {
         /// The name of the scenario to generate
         #[structopt(long)]
         scenario_name: String,
+        /// A JSON file describing when people visit schools, restaurants, and other amenities. If
+        /// omitted, a default profile with school and meal rushes is used.
+        #[structopt(long)]
+        demand_profile: Option<String>,
     },
     /// Modifies the schedule of every person in an existing scenario.
     AugmentScenario {
@@ -176,6 +180,9 @@ enum Command {
         /// Downgrade crosswalks not matching a `highway=crossing` OSM node into unmarked crossings.
         #[structopt(long)]
         filter_crosswalks: bool,
+        /// Import driveways as narrow roads, so vehicles reach buildings along them.
+        #[structopt(long)]
+        include_driveways: bool,
         #[structopt(flatten)]
         opts: map_model::RawToMapOptions,
     },
@@ -226,7 +233,8 @@ async fn main() -> Result<()> {
             rng_seed,
             map,
             scenario_name,
-        } => random_scenario(rng_seed, map, scenario_name),
+            demand_profile,
+        } => random_scenario(rng_seed, map, scenario_name, demand_profile)?,
         Command::AugmentScenario {
             input_scenario,
             add_return_trips,
@@ -277,8 +285,16 @@ async fn main() -> Result<()> {
             clip_path,
             drive_on_left,
             filter_crosswalks,
+            include_driveways,
+            opts,
+        } => importer::oneshot(
+            osm_input,
+            clip_path,
+            drive_on_left,
+            filter_crosswalks,
+            include_driveways,
             opts,
-        } => importer::oneshot(osm_input, clip_path, drive_on_left, filter_crosswalks, opts),
+        ),
         Command::RegenerateEverything {
             shard_num,
             num_shards,
@@ -310,20 +326,30 @@ fn dump_json(path: String) {
     );
 }
 
-fn random_scenario(rng_seed: u64, map: String, scenario_name: String) {
+fn random_scenario(
+    rng_seed: u64,
+    map: String,
+    scenario_name: String,
+    demand_profile: Option<String>,
+) -> Result<()> {
     use rand::SeedableRng;
     use rand_xorshift::XorShiftRng;
 
+    let demand = match demand_profile {
+        Some(path) => sim::DemandProfile::load(path)?,
+        None => sim::DemandProfile::default(),
+    };
     let mut rng = XorShiftRng::seed_from_u64(rng_seed);
     let map = map_model::Map::load_synchronously(map, &mut Timer::throwaway());
     let mut scenario =
-        sim::ScenarioGenerator::proletariat_robot(&map, &mut rng, &mut Timer::throwaway());
+        sim::ScenarioGenerator::proletariat_robot(&map, &demand, &mut rng, &mut Timer::throwaway());
     scenario.scenario_name = scenario_name;
     scenario.save();
     println!(
         "Wrote {}",
         abstio::path_scenario(&scenario.map_name, &scenario.scenario_name)
     );
+    Ok(())
 }
 
 fn import_json_map(input: String, output: String) {
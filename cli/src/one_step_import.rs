@@ -83,6 +83,7 @@ pub async fn run(
         Some("boundary0.poly".to_string()),
         !drive_on_left,
         filter_crosswalks,
+        false,
         map_model::RawToMapOptions::default(),
     );
 
@@ -1,17 +1,31 @@
 use std::collections::BTreeMap;
 
+use anyhow::Result;
+
 use abstutil::Timer;
 use geom::{PolyLine, Ring};
 use map_model::raw::{OriginalRoad, RawMap};
 use map_model::{osm, IntersectionType};
 
+/// Remove everything outside of the map's boundary polygon. Roads crossing the boundary are trimmed
+/// and end at new border intersections. Fails if the boundary has holes, or if a road crosses it
+/// in a way that can't be trimmed cleanly; the map may be partly clipped by then.
 // TODO This needs to update turn restrictions too
-pub fn clip_map(map: &mut RawMap, timer: &mut Timer) {
+pub fn clip_map(map: &mut RawMap, timer: &mut Timer) -> Result<()> {
     timer.start("clipping map to boundary");
+    let result = clip(map);
+    timer.stop("clipping map to boundary");
+    result
+}
+
+fn clip(map: &mut RawMap) -> Result<()> {
+    if map.boundary_polygon.has_holes() {
+        bail!("The boundary polygon has holes");
+    }
 
     // So we can use retain without borrowing issues
     let boundary_polygon = map.boundary_polygon.clone();
-    let boundary_ring = Ring::must_new(boundary_polygon.points().clone());
+    let boundary_ring = Ring::new(boundary_polygon.points().clone())?;
 
     // This is kind of indirect and slow, but first pass -- just remove roads that start or end
     // outside the boundary polygon.
@@ -68,12 +82,15 @@ pub fn clip_map(map: &mut RawMap, timer: &mut Timer) {
 
         // Now trim it.
         let mut mut_r = map.roads.remove(&id).unwrap();
-        let center = PolyLine::must_new(mut_r.center_points.clone());
-        let border_pt = boundary_ring.all_intersections(&center)[0];
+        let center = PolyLine::new(mut_r.center_points.clone())?;
+        let border_pt = match boundary_ring.all_intersections(&center).get(0) {
+            Some(pt) => *pt,
+            None => bail!("{} starts outside the boundary, but never crosses it", id),
+        };
         if let Some(pl) = center.reversed().get_slice_ending_at(border_pt) {
             mut_r.center_points = pl.reversed().into_points();
         } else {
-            panic!("{} interacts with border strangely", id);
+            bail!("{} interacts with border strangely", id);
         }
         i.point = mut_r.center_points[0];
         map.roads.insert(
@@ -123,12 +140,15 @@ pub fn clip_map(map: &mut RawMap, timer: &mut Timer) {
 
         // Now trim it.
         let mut mut_r = map.roads.remove(&id).unwrap();
-        let center = PolyLine::must_new(mut_r.center_points.clone());
-        let border_pt = boundary_ring.all_intersections(&center.reversed())[0];
+        let center = PolyLine::new(mut_r.center_points.clone())?;
+        let border_pt = match boundary_ring.all_intersections(&center.reversed()).get(0) {
+            Some(pt) => *pt,
+            None => bail!("{} ends outside the boundary, but never crosses it", id),
+        };
         if let Some(pl) = center.get_slice_ending_at(border_pt) {
             mut_r.center_points = pl.into_points();
         } else {
-            panic!("{} interacts with border strangely", id);
+            bail!("{} interacts with border strangely", id);
         }
         i.point = *mut_r.center_points.last().unwrap();
         map.roads.insert(
@@ -162,8 +182,7 @@ pub fn clip_map(map: &mut RawMap, timer: &mut Timer) {
     // The boundary should be manually adjusted.
 
     if map.roads.is_empty() {
-        panic!("There are no roads inside the clipping polygon");
+        bail!("There are no roads inside the clipping polygon");
     }
-
-    timer.stop("clipping map to boundary");
+    Ok(())
 }
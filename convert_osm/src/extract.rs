@@ -10,6 +10,7 @@ use map_model::raw::{RawArea, RawBuilding, RawMap, RawParkingLot, RawRoad, Restr
 use map_model::{osm, Amenity, AreaType, Direction, DrivingSide, NamePerLanguage};
 
 use crate::osm_geom::{get_multipolygon_members, glue_multipolygon, multipoly_geometry};
+use crate::reader::Way;
 use crate::Options;
 
 pub struct OsmExtract {
@@ -17,6 +18,8 @@ pub struct OsmExtract {
     pub roads: Vec<(WayID, RawRoad)>,
     /// Traffic signals to the direction they apply
     pub traffic_signals: HashMap<HashablePt2D, Direction>,
+    /// Nodes tagged as an all-way stop
+    pub all_way_stops: HashSet<HashablePt2D>,
     pub osm_node_ids: HashMap<HashablePt2D, NodeID>,
     /// (ID, restriction type, from way ID, via node ID, to way ID)
     pub simple_turn_restrictions: Vec<(RestrictionType, WayID, NodeID, WayID)>,
@@ -26,6 +29,9 @@ pub struct OsmExtract {
     pub amenities: Vec<(Pt2D, Amenity)>,
     /// Crosswalks located at these points, which should be on a RawRoad's center line
     pub crosswalks: HashSet<HashablePt2D>,
+    /// Crossings or curbs without a curb ramp located at these points, which should be on a
+    /// RawRoad's center line
+    pub raised_curbs: HashSet<HashablePt2D>,
 }
 
 pub fn extract_osm(
@@ -59,11 +65,13 @@ pub fn extract_osm(
     let mut out = OsmExtract {
         roads: Vec::new(),
         traffic_signals: HashMap::new(),
+        all_way_stops: HashSet::new(),
         osm_node_ids: HashMap::new(),
         simple_turn_restrictions: Vec::new(),
         complicated_turn_restrictions: Vec::new(),
         amenities: Vec::new(),
         crosswalks: HashSet::new(),
+        raised_curbs: HashSet::new(),
     };
 
     timer.start_iter("processing OSM nodes", doc.nodes.len());
@@ -79,9 +87,17 @@ pub fn extract_osm(
             };
             out.traffic_signals.insert(node.pt.to_hashable(), dir);
         }
+        if node.tags.is(osm::HIGHWAY, "stop") && node.tags.is("stop", "all") {
+            out.all_way_stops.insert(node.pt.to_hashable());
+        }
         if node.tags.is(osm::HIGHWAY, "crossing") {
             out.crosswalks.insert(node.pt.to_hashable());
         }
+        if (node.tags.is(osm::HIGHWAY, "crossing") || node.tags.is("barrier", "kerb"))
+            && (node.tags.is("kerb", "raised") || node.tags.is("wheelchair", "no"))
+        {
+            out.raised_curbs.insert(node.pt.to_hashable());
+        }
         for amenity in get_bldg_amenities(&node.tags) {
             out.amenities.push((node.pt, amenity));
         }
@@ -98,6 +114,7 @@ pub fn extract_osm(
         let id = *id;
 
         way.tags.insert(osm::OSM_WAY_ID, id.0.to_string());
+        infer_roundabout(way, opts.map_config.driving_side);
 
         if is_road(&mut way.tags, opts) {
             // TODO Hardcoding these overrides. OSM is correct, these don't have
@@ -430,11 +447,13 @@ fn is_road(tags: &mut Tags, opts: &Options) -> bool {
         return false;
     }
 
-    // Import most service roads. Always ignore driveways, golf cart paths, and always reserve
-    // parking_aisles for parking lots.
+    // Import most service roads. Ignore driveways unless asked for, always ignore golf cart
+    // paths, and always reserve parking_aisles for parking lots.
     if highway == "service" && tags.is_any("service", vec!["driveway", "parking_aisle"]) {
         // An exception -- keep driveways signed for bikes
-        if !(tags.is("service", "driveway") && tags.is("bicycle", "designated")) {
+        if !(tags.is("service", "driveway")
+            && (opts.include_driveways || tags.is("bicycle", "designated")))
+        {
             return false;
         }
     }
@@ -513,6 +532,70 @@ fn is_road(tags: &mut Tags, opts: &Options) -> bool {
     true
 }
 
+/// Not every roundabout is tagged with `junction=roundabout`. Recognize small, closed, roughly
+/// circular roads by their shape and tag them, so they're treated the same way. Unless the way is
+/// tagged `oneway=yes`, assume traffic circulates in the usual direction for the driving side.
+fn infer_roundabout(way: &mut Way, driving_side: DrivingSide) {
+    if way.tags.contains_key("junction")
+        || way.tags.is("area", "yes")
+        || way.tags.is_any("oneway", vec!["no", "-1", "reversible"])
+        || !way.tags.is_any(
+            osm::HIGHWAY,
+            vec![
+                "motorway",
+                "motorway_link",
+                "trunk",
+                "trunk_link",
+                "primary",
+                "primary_link",
+                "secondary",
+                "secondary_link",
+                "tertiary",
+                "tertiary_link",
+                "unclassified",
+                "residential",
+                "living_street",
+            ],
+        )
+    {
+        return;
+    }
+    // A closed square block has its corners all the same distance from the center, so insist on
+    // enough points to tell that apart from a circle.
+    if way.pts.len() < 6 || way.pts[0] != *way.pts.last().unwrap() {
+        return;
+    }
+
+    let center = Pt2D::center(&way.pts[1..]);
+    let mut min_radius = f64::MAX;
+    let mut max_radius = 0.0_f64;
+    for pt in &way.pts {
+        let radius = pt.dist_to(center).inner_meters();
+        min_radius = min_radius.min(radius);
+        max_radius = max_radius.max(radius);
+    }
+    if max_radius > 30.0 || min_radius < 0.75 * max_radius {
+        return;
+    }
+
+    if !way.tags.is("oneway", "yes") {
+        // The y-axis points south, so the shoelace formula is positive for loops that go clockwise
+        // on the ground.
+        let area: f64 = way
+            .pts
+            .windows(2)
+            .map(|pair| pair[0].x() * pair[1].y() - pair[1].x() * pair[0].y())
+            .sum();
+        let clockwise = area > 0.0;
+        // Roundabouts go counter-clockwise when driving on the right
+        if clockwise == (driving_side == DrivingSide::Right) {
+            way.pts.reverse();
+            way.nodes.reverse();
+        }
+    }
+    way.tags.insert("junction", "roundabout");
+}
+
 fn is_bldg(tags: &Tags) -> bool {
     // Sorry, the towers at Gasworks don't count. :)
     tags.contains_key("building") && !tags.contains_key("abandoned:man_made")
@@ -623,3 +706,83 @@ fn service_road_crosses_parking_lot(map: &RawMap, road: &RawRoad, candidates: Ve
     }
     false
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn closed_way(pts: Vec<Pt2D>, highway: &str) -> Way {
+        let mut tags = Tags::empty();
+        tags.insert(osm::HIGHWAY, highway);
+        Way {
+            nodes: (0..pts.len()).map(|i| NodeID(i as i64)).collect(),
+            pts,
+            tags,
+        }
+    }
+
+    fn circle(radius: f64, clockwise: bool) -> Vec<Pt2D> {
+        let mut pts: Vec<Pt2D> = (0..12)
+            .map(|i| {
+                let angle = std::f64::consts::PI * 2.0 * (i as f64) / 12.0;
+                // Remember the y-axis points south
+                let dy = if clockwise { angle.sin() } else { -angle.sin() };
+                Pt2D::new(100.0 + radius * angle.cos(), 100.0 + radius * dy)
+            })
+            .collect();
+        pts.push(pts[0]);
+        pts
+    }
+
+    #[test]
+    fn test_infer_roundabout() {
+        // A small untagged circle is a roundabout, turned to go counter-clockwise on the ground
+        let mut way = closed_way(circle(15.0, true), "residential");
+        infer_roundabout(&mut way, DrivingSide::Right);
+        assert!(way.tags.is("junction", "roundabout"));
+        assert_eq!(
+            way.pts,
+            circle(15.0, true).into_iter().rev().collect::<Vec<_>>()
+        );
+
+        // Already going the right way
+        let mut way = closed_way(circle(15.0, false), "residential");
+        infer_roundabout(&mut way, DrivingSide::Right);
+        assert!(way.tags.is("junction", "roundabout"));
+        assert_eq!(way.pts, circle(15.0, false));
+
+        // The mapped direction wins
+        let mut way = closed_way(circle(15.0, true), "residential");
+        way.tags.insert("oneway", "yes");
+        infer_roundabout(&mut way, DrivingSide::Right);
+        assert_eq!(way.pts, circle(15.0, true));
+
+        // Too big
+        let mut way = closed_way(circle(80.0, false), "residential");
+        infer_roundabout(&mut way, DrivingSide::Right);
+        assert!(!way.tags.contains_key("junction"));
+
+        // Not a street
+        let mut way = closed_way(circle(15.0, false), "footway");
+        infer_roundabout(&mut way, DrivingSide::Right);
+        assert!(!way.tags.contains_key("junction"));
+
+        // A loop around a block isn't round
+        let mut way = closed_way(
+            vec![
+                Pt2D::new(0.0, 0.0),
+                Pt2D::new(10.0, 0.0),
+                Pt2D::new(20.0, 0.0),
+                Pt2D::new(20.0, 10.0),
+                Pt2D::new(20.0, 20.0),
+                Pt2D::new(10.0, 20.0),
+                Pt2D::new(0.0, 20.0),
+                Pt2D::new(0.0, 10.0),
+                Pt2D::new(0.0, 0.0),
+            ],
+            "residential",
+        );
+        infer_roundabout(&mut way, DrivingSide::Right);
+        assert!(!way.tags.contains_key("junction"));
+    }
+}
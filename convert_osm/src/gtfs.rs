@@ -0,0 +1,349 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::File;
+
+use anyhow::Result;
+use serde::Deserialize;
+
+use geom::{Duration, LonLat, Time};
+use map_model::raw::{RawMap, RawTransitRoute, RawTransitStop, RawTransitType};
+
+/// Read a static GTFS feed from `data/input/$city/gtfs/` and add transit stops inside the map
+/// boundary and the routes visiting them to the RawMap. Each distinct sequence of stops that a
+/// route takes becomes its own RawTransitRoute, with the departures of one typical weekday.
+pub fn import(map: &mut RawMap) -> Result<()> {
+    let dir = map.name.city.input_path("gtfs/");
+    import_dir(map, &dir)
+}
+
+fn import_dir(map: &mut RawMap, dir: &str) -> Result<()> {
+    for rec in csv::Reader::from_reader(File::open(format!("{}stops.txt", dir))?).deserialize() {
+        let rec: Stop = rec?;
+        let position = LonLat::new(rec.stop_lon, rec.stop_lat).to_pt(&map.gps_bounds);
+        if map.boundary_polygon.contains_pt(position) {
+            map.transit_stops.insert(
+                rec.stop_id.clone(),
+                RawTransitStop {
+                    gtfs_id: rec.stop_id,
+                    position,
+                    name: rec.stop_name,
+                },
+            );
+        }
+    }
+
+    let mut routes: HashMap<String, Route> = HashMap::new();
+    for rec in csv::Reader::from_reader(File::open(format!("{}routes.txt", dir))?).deserialize() {
+        let rec: Route = rec?;
+        // https://developers.google.com/transit/gtfs/reference#routestxt. Skip ferries, cable
+        // cars, and other things that don't use the road or rail network.
+        if matches!(rec.route_type, 0 | 1 | 2 | 3 | 12) {
+            routes.insert(rec.route_id.clone(), rec);
+        }
+    }
+
+    let weekday_services = read_weekday_services(dir)?;
+    // trip_id to route_id
+    let mut trips: HashMap<String, String> = HashMap::new();
+    for rec in csv::Reader::from_reader(File::open(format!("{}trips.txt", dir))?).deserialize() {
+        let rec: Trip = rec?;
+        if routes.contains_key(&rec.route_id)
+            && weekday_services
+                .as_ref()
+                .map(|services| services.contains(&rec.service_id))
+                .unwrap_or(true)
+        {
+            trips.insert(rec.trip_id, rec.route_id);
+        }
+    }
+
+    // For every trip, when it begins, and the stops it visits inside the map
+    let mut trip_start: HashMap<String, (usize, Time)> = HashMap::new();
+    let mut trip_stops: HashMap<String, Vec<(usize, String, Option<Time>)>> = HashMap::new();
+    for rec in csv::Reader::from_reader(File::open(format!("{}stop_times.txt", dir))?).deserialize()
+    {
+        let rec: StopTime = rec?;
+        if !trips.contains_key(&rec.trip_id) {
+            continue;
+        }
+        // Intermediate stops may not be timed
+        let time = if rec.departure_time.is_empty() {
+            None
+        } else {
+            Some(Time::parse(&rec.departure_time)?)
+        };
+        if let Some(time) = time {
+            let entry = trip_start
+                .entry(rec.trip_id.clone())
+                .or_insert((rec.stop_sequence, time));
+            if rec.stop_sequence < entry.0 {
+                *entry = (rec.stop_sequence, time);
+            }
+        }
+        if map.transit_stops.contains_key(&rec.stop_id) {
+            trip_stops
+                .entry(rec.trip_id)
+                .or_insert_with(Vec::new)
+                .push((rec.stop_sequence, rec.stop_id, time));
+        }
+    }
+
+    // Some trips repeat on a schedule, instead of listing every departure
+    let mut frequencies: HashMap<String, Vec<Frequency>> = HashMap::new();
+    if let Ok(file) = File::open(format!("{}frequencies.txt", dir)) {
+        for rec in csv::Reader::from_reader(file).deserialize() {
+            let rec: Frequency = rec?;
+            frequencies
+                .entry(rec.trip_id.clone())
+                .or_insert_with(Vec::new)
+                .push(rec);
+        }
+    }
+
+    // Group trips of the same route that visit the same stops. Use the earliest trip's timing
+    // between stops for the whole pattern.
+    let mut patterns: BTreeMap<(String, Vec<String>), Pattern> = BTreeMap::new();
+    for (trip_id, mut stops) in trip_stops {
+        stops.sort_by_key(|(seq, _, _)| *seq);
+        // The departure time from the first stop in the map, or as close as we know
+        let first_time = match stops.iter().find_map(|(_, _, time)| *time) {
+            Some(time) => time,
+            None => continue,
+        };
+        let departures = if let Some(freqs) = frequencies.get(&trip_id) {
+            // The times in stop_times are just a template, relative to the trip's start
+            let offset = first_time - trip_start[&trip_id].1;
+            let mut departures = Vec::new();
+            for freq in freqs {
+                let start = Time::parse(&freq.start_time)?;
+                let end = Time::parse(&freq.end_time)?;
+                let headway = Duration::seconds(freq.headway_secs as f64);
+                if headway == Duration::ZERO {
+                    continue;
+                }
+                let mut time = start;
+                while time < end {
+                    departures.push(time + offset);
+                    time += headway;
+                }
+            }
+            departures
+        } else {
+            vec![first_time]
+        };
+
+        // Untimed stops get the time of the previous timed stop
+        let mut stop_offsets = Vec::new();
+        let mut last_offset = Duration::ZERO;
+        for (_, _, time) in &stops {
+            if let Some(time) = time {
+                last_offset = *time - first_time;
+            }
+            stop_offsets.push(last_offset);
+        }
+
+        let route_id = trips[&trip_id].clone();
+        let pattern = patterns
+            .entry((route_id, stops.into_iter().map(|(_, id, _)| id).collect()))
+            .or_insert_with(|| Pattern {
+                spawn_times: Vec::new(),
+                earliest_trip: first_time,
+                stop_offsets: stop_offsets.clone(),
+            });
+        pattern.spawn_times.extend(departures);
+        if first_time < pattern.earliest_trip {
+            pattern.earliest_trip = first_time;
+            pattern.stop_offsets = stop_offsets;
+        }
+    }
+
+    let mut pattern_count: HashMap<String, usize> = HashMap::new();
+    for ((route_id, stops), pattern) in patterns {
+        let mut spawn_times = pattern.spawn_times;
+        spawn_times.sort();
+        spawn_times.dedup();
+        let route = &routes[&route_id];
+        let count = pattern_count.entry(route_id.clone()).or_insert(0);
+        *count += 1;
+        map.transit_routes.push(RawTransitRoute {
+            long_name: route.route_long_name.clone(),
+            short_name: route.route_short_name.clone(),
+            gtfs_id: format!("{}:{}", route_id, count),
+            stops,
+            route_type: if route.route_type == 3 {
+                RawTransitType::Bus
+            } else {
+                RawTransitType::Train
+            },
+            spawn_times,
+            stop_offsets: pattern.stop_offsets,
+        });
+    }
+
+    // Only keep stops that some route visits
+    let used_stops: HashSet<String> = map
+        .transit_routes
+        .iter()
+        .flat_map(|r| r.stops.clone())
+        .collect();
+    map.transit_stops.retain(|id, _| used_stops.contains(id));
+
+    info!(
+        "Imported {} transit routes and {} stops from GTFS",
+        map.transit_routes.len(),
+        map.transit_stops.len()
+    );
+    Ok(())
+}
+
+struct Pattern {
+    spawn_times: Vec<Time>,
+    earliest_trip: Time,
+    stop_offsets: Vec<Duration>,
+}
+
+/// The services running on a typical Wednesday, or None if the feed doesn't say.
+fn read_weekday_services(dir: &str) -> Result<Option<HashSet<String>>> {
+    // Some feeds only use calendar_dates.txt for exceptions; then just use all services
+    let file = match File::open(format!("{}calendar.txt", dir)) {
+        Ok(file) => file,
+        Err(_) => {
+            return Ok(None);
+        }
+    };
+    let mut services = HashSet::new();
+    for rec in csv::Reader::from_reader(file).deserialize() {
+        let rec: Calendar = rec?;
+        if rec.wednesday == 1 {
+            services.insert(rec.service_id);
+        }
+    }
+    Ok(Some(services))
+}
+
+#[derive(Deserialize)]
+struct Stop {
+    stop_id: String,
+    #[serde(default)]
+    stop_name: String,
+    stop_lat: f64,
+    stop_lon: f64,
+}
+
+#[derive(Deserialize)]
+struct Route {
+    route_id: String,
+    #[serde(default)]
+    route_short_name: String,
+    #[serde(default)]
+    route_long_name: String,
+    route_type: usize,
+}
+
+#[derive(Deserialize)]
+struct Trip {
+    route_id: String,
+    service_id: String,
+    trip_id: String,
+}
+
+#[derive(Deserialize)]
+struct StopTime {
+    trip_id: String,
+    #[serde(default)]
+    departure_time: String,
+    stop_id: String,
+    stop_sequence: usize,
+}
+
+#[derive(Deserialize)]
+struct Frequency {
+    trip_id: String,
+    start_time: String,
+    end_time: String,
+    headway_secs: usize,
+}
+
+#[derive(Deserialize)]
+struct Calendar {
+    service_id: String,
+    wednesday: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use abstio::MapName;
+    use geom::GPSBounds;
+
+    use super::*;
+
+    fn time(x: &str) -> Time {
+        Time::parse(x).unwrap()
+    }
+
+    #[test]
+    fn test_import_fixture() {
+        let mut map = RawMap::blank(MapName::new("zz", "gtfs_test", "fixture"));
+        map.gps_bounds =
+            GPSBounds::from(vec![LonLat::new(-122.0, 47.0), LonLat::new(-121.99, 47.01)]);
+        map.boundary_polygon = map.gps_bounds.to_bounds().get_rectangle();
+        import_dir(
+            &mut map,
+            &format!("{}/fixtures/gtfs/", env!("CARGO_MANIFEST_DIR")),
+        )
+        .unwrap();
+
+        // The stop outside the map is dropped
+        assert_eq!(
+            map.transit_stops.keys().cloned().collect::<Vec<_>>(),
+            vec!["A", "B", "C"]
+        );
+        // The ferry and the Sunday-only trip are skipped
+        assert_eq!(map.transit_routes.len(), 3);
+        let route = |gtfs_id: &str| {
+            map.transit_routes
+                .iter()
+                .find(|r| r.gtfs_id == gtfs_id)
+                .unwrap()
+        };
+
+        // A late-night trip that only visits A and B. Times past midnight stay on the same day.
+        let late = route("R1:1");
+        assert_eq!(late.stops, vec!["A", "B"]);
+        assert_eq!(late.spawn_times, vec![time("24:10:00")]);
+        assert_eq!(
+            late.stop_offsets,
+            vec![Duration::ZERO, Duration::minutes(2)]
+        );
+
+        // Two trips share one pattern. The first stop is outside the map, so departures are from
+        // A. B isn't timed, so it gets A's offset.
+        let day = route("R1:2");
+        assert_eq!(day.stops, vec!["A", "B", "C"]);
+        assert_eq!(day.route_type, RawTransitType::Bus);
+        assert_eq!(day.short_name, "1");
+        assert_eq!(day.spawn_times, vec![time("08:05:00"), time("09:05:00")]);
+        assert_eq!(
+            day.stop_offsets,
+            vec![Duration::ZERO, Duration::ZERO, Duration::minutes(10)]
+        );
+
+        // Departures come from frequencies.txt, shifted by the 2 minutes from the template trip's
+        // start outside the map to A. One window runs past midnight.
+        let rail = route("R2:1");
+        assert_eq!(rail.stops, vec!["A", "C"]);
+        assert_eq!(rail.route_type, RawTransitType::Train);
+        assert_eq!(
+            rail.spawn_times,
+            vec![
+                time("06:02:00"),
+                time("06:32:00"),
+                time("23:32:00"),
+                time("24:32:00")
+            ]
+        );
+        assert_eq!(
+            rail.stop_offsets,
+            vec![Duration::ZERO, Duration::minutes(8)]
+        );
+    }
+}
@@ -14,9 +14,12 @@ use map_model::raw::{OriginalRoad, RawMap};
 use map_model::{osm, raw, Amenity, MapConfig};
 use serde::{Deserialize, Serialize};
 
+pub use self::clip::clip_map;
+
 mod clip;
 mod elevation;
 mod extract;
+mod gtfs;
 pub mod osm_geom;
 mod parking;
 pub mod reader;
@@ -38,6 +41,9 @@ pub struct Options {
     pub skip_local_roads: bool,
     /// Only include crosswalks that match a `highway=crossing` OSM node.
     pub filter_crosswalks: bool,
+    /// Import `service=driveway` ways as narrow access roads. Buildings next to a driveway or
+    /// alley will be reached by driving along it.
+    pub include_driveways: bool,
     /// Configure public transit using this URL to a static GTFS feed in .zip format.
     pub gtfs_url: Option<String>,
 }
@@ -94,8 +100,9 @@ pub fn convert(
     }
 
     let extract = extract::extract_osm(&mut map, &osm_input_path, clip_path, &opts, timer);
-    let (amenities, crosswalks, pt_to_road) = split_ways::split_up_roads(&mut map, extract, timer);
-    clip::clip_map(&mut map, timer);
+    let (amenities, crosswalks, raised_curbs, pt_to_road) =
+        split_ways::split_up_roads(&mut map, extract, timer);
+    clip::clip_map(&mut map, timer).unwrap();
 
     // Need to do a first pass of removing cul-de-sacs here, or we wind up with loop PolyLines when
     // doing the parking hint matching.
@@ -111,10 +118,18 @@ pub fn convert(
         error!("No elevation data: {}", err);
     }
     timer.stop("add elevation data");
+    if opts.gtfs_url.is_some() {
+        timer.start("import GTFS");
+        if let Err(err) = gtfs::import(&mut map) {
+            error!("Couldn't import GTFS: {}", err);
+        }
+        timer.stop("import GTFS");
+    }
     if let Some(ref path) = opts.extra_buildings {
         add_extra_buildings(&mut map, path).unwrap();
     }
 
+    mark_raised_curbs(&mut map, raised_curbs, &pt_to_road, timer);
     if opts.filter_crosswalks {
         filter_crosswalks(&mut map, crosswalks, pt_to_road, timer);
     }
@@ -167,6 +182,33 @@ fn add_extra_buildings(map: &mut RawMap, path: &str) -> Result<()> {
     Ok(())
 }
 
+/// Record crossings without curb ramps on the end of the road they're closest to, so wheelchair
+/// routing can avoid them.
+fn mark_raised_curbs(
+    map: &mut RawMap,
+    raised_curbs: HashSet<HashablePt2D>,
+    pt_to_road: &HashMap<HashablePt2D, OriginalRoad>,
+    timer: &mut Timer,
+) {
+    timer.start_iter("mark raised curbs", raised_curbs.len());
+    for pt in raised_curbs {
+        timer.next();
+        if let Some(road) = pt_to_road.get(&pt).and_then(|r| map.roads.get_mut(r)) {
+            if let Ok(pl) = PolyLine::new(road.center_points.clone()) {
+                // Like crosswalks, snap to the nearest end of the road
+                if let Some((dist, _)) = pl.dist_along_of_point(pt.to_pt2d()) {
+                    let key = if dist / pl.length() <= 0.5 {
+                        osm::NO_CURB_RAMP_BACK
+                    } else {
+                        osm::NO_CURB_RAMP_FWD
+                    };
+                    road.osm_tags.insert(key, "true");
+                }
+            }
+        }
+    }
+}
+
 fn filter_crosswalks(
     map: &mut RawMap,
     crosswalks: HashSet<HashablePt2D>,
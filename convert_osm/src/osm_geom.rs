@@ -147,18 +147,31 @@ pub fn multipoly_geometry(rel_id: RelationID, rel: &Relation, doc: &Document) ->
             }
         }
     }
-    // TODO Handle multiple outers with holes
-    if outer.is_empty() || outer.len() > 1 && !inner.is_empty() {
-        bail!(
-            "Multipolygon {} has {} outer, {} inner. Huh?",
-            rel_id,
-            outer.len(),
-            inner.len()
-        );
+    if outer.is_empty() {
+        bail!("Multipolygon {} has no outer", rel_id);
+    }
+    if outer.len() > 1 && !inner.is_empty() {
+        // Merge the outer pieces, then cut out each hole
+        let mut outer_polys = Vec::new();
+        for pts in outer {
+            outer_polys.push(Ring::new(pts)?.into_polygon());
+        }
+        let mut pieces = Polygon::union_all(outer_polys);
+        for pts in inner {
+            let hole = Ring::new(pts)?.into_polygon();
+            pieces = pieces
+                .into_iter()
+                .flat_map(|p| p.difference(&hole))
+                .collect();
+        }
+        if pieces.is_empty() {
+            bail!("Multipolygon {} is entirely covered by its holes", rel_id);
+        }
+        return Ok(Polygon::concat_all(pieces));
     }
     if inner.is_empty() {
         if outer.len() > 1 {
-            Ok(Polygon::union_all(
+            Ok(Polygon::concat_all(
                 outer.into_iter().map(Polygon::buggy_new).collect(),
             ))
         } else {
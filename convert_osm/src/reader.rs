@@ -44,9 +44,16 @@ pub struct Relation {
 pub fn read(path: &str, input_gps_bounds: &GPSBounds, timer: &mut Timer) -> Result<Document> {
     timer.start(format!("read {}", path));
     let bytes = slurp_file(path)?;
-    let raw_string = std::str::from_utf8(&bytes)?;
-    let tree = roxmltree::Document::parse(raw_string)?;
     timer.stop(format!("read {}", path));
+    parse(&bytes, input_gps_bounds, timer)
+}
+
+/// Like `read`, but for OSM XML that's already in memory, such as a response from Overpass.
+pub fn parse(bytes: &[u8], input_gps_bounds: &GPSBounds, timer: &mut Timer) -> Result<Document> {
+    timer.start("parse XML");
+    let raw_string = std::str::from_utf8(bytes)?;
+    let tree = roxmltree::Document::parse(raw_string)?;
+    timer.stop("parse XML");
 
     let mut doc = Document {
         gps_bounds: input_gps_bounds.clone(),
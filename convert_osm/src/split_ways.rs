@@ -1,9 +1,9 @@
-use std::collections::{hash_map::Entry, BTreeMap, HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 use abstutil::{Counter, Timer};
 use geom::{Distance, HashablePt2D, Pt2D};
 use map_model::raw::{OriginalRoad, RawIntersection, RawMap, RawRoad};
-use map_model::{osm, Amenity, Direction, IntersectionType};
+use map_model::{osm, Amenity, Direction, IntersectionType, StopSignPlacement};
 
 use crate::extract::OsmExtract;
 
@@ -16,12 +16,13 @@ pub fn split_up_roads(
 ) -> (
     Vec<(Pt2D, Amenity)>,
     HashSet<HashablePt2D>,
+    HashSet<HashablePt2D>,
     HashMap<HashablePt2D, OriginalRoad>,
 ) {
     timer.start("splitting up roads");
 
     let mut roundabout_centers: HashMap<osm::NodeID, Pt2D> = HashMap::new();
-    let mut pt_to_intersection: HashMap<HashablePt2D, osm::NodeID> = HashMap::new();
+    let mut roundabout_pts: HashMap<HashablePt2D, osm::NodeID> = HashMap::new();
 
     {
         let mut roads = std::mem::take(&mut input.roads);
@@ -32,7 +33,7 @@ pub fn split_up_roads(
                 let id = input.osm_node_ids[&r.center_points[0].to_hashable()];
                 roundabout_centers.insert(id, Pt2D::center(&r.center_points));
                 for pt in &r.center_points {
-                    pt_to_intersection.insert(pt.to_hashable(), id);
+                    roundabout_pts.insert(pt.to_hashable(), id);
                 }
 
                 false
@@ -43,35 +44,35 @@ pub fn split_up_roads(
         input.roads = roads;
     }
 
-    let mut counts_per_pt = Counter::new();
-    for (_, r) in &input.roads {
-        for (idx, raw_pt) in r.center_points.iter().enumerate() {
-            let pt = raw_pt.to_hashable();
-            let count = counts_per_pt.inc(pt);
+    let pt_to_intersection = find_intersections(&input, &roundabout_pts);
 
-            // All start and endpoints of ways are also intersections.
-            if count == 2 || idx == 0 || idx == r.center_points.len() - 1 {
-                if let Entry::Vacant(e) = pt_to_intersection.entry(pt) {
-                    let id = input.osm_node_ids[&pt];
-                    e.insert(id);
-                }
-            }
+    let mut intersection_pts: BTreeMap<osm::NodeID, HashablePt2D> = BTreeMap::new();
+    for ((pt, _), id) in &pt_to_intersection {
+        if !roundabout_centers.contains_key(id) {
+            intersection_pts.insert(*id, *pt);
         }
     }
-
-    for (pt, id) in &pt_to_intersection {
+    for (id, pt) in intersection_pts {
+        // Where layers cross, only the one keeping the OSM node's ID has its tagged control
+        let tagged = input.osm_node_ids[&pt] == id;
         map.intersections.insert(
-            *id,
+            id,
             RawIntersection {
                 point: pt.to_pt2d(),
-                intersection_type: if input.traffic_signals.remove(pt).is_some() {
+                intersection_type: if tagged && input.traffic_signals.remove(&pt).is_some() {
                     IntersectionType::TrafficSignal
                 } else {
                     IntersectionType::StopSign
                 },
+                stop_sign_placement: if tagged && input.all_way_stops.contains(&pt) {
+                    StopSignPlacement::AllWay
+                } else {
+                    StopSignPlacement::Automatic
+                },
                 // Filled out later
                 elevation: Distance::ZERO,
                 trim_roads_for_merging: BTreeMap::new(),
+                midblock_crossing: false,
             },
         );
     }
@@ -83,9 +84,11 @@ pub fn split_up_roads(
             RawIntersection {
                 point,
                 intersection_type: IntersectionType::StopSign,
+                stop_sign_placement: StopSignPlacement::Automatic,
                 // Filled out later
                 elevation: Distance::ZERO,
                 trim_roads_for_merging: BTreeMap::new(),
+                midblock_crossing: false,
             },
         );
     }
@@ -98,8 +101,12 @@ pub fn split_up_roads(
         timer.next();
         let mut r = orig_road.clone();
         let mut pts = Vec::new();
-        let endpt1 = pt_to_intersection[&orig_road.center_points[0].to_hashable()];
-        let endpt2 = pt_to_intersection[&orig_road.center_points.last().unwrap().to_hashable()];
+        let zorder = orig_road.get_zorder();
+        let endpt1 = pt_to_intersection[&(orig_road.center_points[0].to_hashable(), zorder)];
+        let endpt2 = pt_to_intersection[&(
+            orig_road.center_points.last().unwrap().to_hashable(),
+            zorder,
+        )];
         let mut i1 = endpt1;
 
         for pt in &orig_road.center_points {
@@ -107,7 +114,7 @@ pub fn split_up_roads(
             if pts.len() == 1 {
                 continue;
             }
-            if let Some(i2) = pt_to_intersection.get(&pt.to_hashable()) {
+            if let Some(i2) = pt_to_intersection.get(&(pt.to_hashable(), zorder)) {
                 if i1 == endpt1 {
                     r.osm_tags
                         .insert(osm::ENDPT_BACK.to_string(), "true".to_string());
@@ -228,7 +235,92 @@ pub fn split_up_roads(
     timer.stop("match traffic signals to intersections");
 
     timer.stop("splitting up roads");
-    (input.amenities, input.crosswalks, pt_to_road)
+    (
+        input.amenities,
+        input.crosswalks,
+        input.raised_curbs,
+        pt_to_road,
+    )
+}
+
+/// Finds the intersection at every point where ways meet, keyed by the point and the layer of the
+/// way. Ways on different layers sometimes share a node where one passes over the other. That's not
+/// an intersection, so ways are counted per layer. When ways continue through one point on two
+/// layers, each layer gets its own intersection there. When some way ends at a point -- like a
+/// bridge landing partway along a ground street -- every layer there shares one intersection.
+/// Otherwise, ways only passing over an intersection on another layer aren't split.
+fn find_intersections(
+    input: &OsmExtract,
+    roundabout_pts: &HashMap<HashablePt2D, osm::NodeID>,
+) -> HashMap<(HashablePt2D, isize), osm::NodeID> {
+    let mut counts_per_pt = Counter::new();
+    let mut endpoints = HashSet::new();
+    for (_, r) in &input.roads {
+        let zorder = r.get_zorder();
+        for (idx, raw_pt) in r.center_points.iter().enumerate() {
+            let pt = raw_pt.to_hashable();
+            counts_per_pt.inc((pt, zorder));
+            if idx == 0 || idx == r.center_points.len() - 1 {
+                endpoints.insert((pt, zorder));
+            }
+        }
+    }
+
+    // Every layer with a way touching each point, and how many ways touch it there
+    let mut layers_per_pt: BTreeMap<HashablePt2D, Vec<(isize, usize)>> = BTreeMap::new();
+    for ((pt, zorder), count) in counts_per_pt.consume() {
+        layers_per_pt
+            .entry(pt)
+            .or_insert_with(Vec::new)
+            .push((zorder, count));
+    }
+
+    let used_ids: HashSet<osm::NodeID> = input.osm_node_ids.values().cloned().collect();
+    let mut next_id = -1;
+    let mut pt_to_intersection = HashMap::new();
+    for (pt, mut layers) in layers_per_pt {
+        if let Some(id) = roundabout_pts.get(&pt) {
+            for (zorder, _) in layers {
+                pt_to_intersection.insert((pt, zorder), *id);
+            }
+            continue;
+        }
+
+        let has_endpoint = layers
+            .iter()
+            .any(|(zorder, _)| endpoints.contains(&(pt, *zorder)));
+        let num_crossing_layers = layers.iter().filter(|(_, count)| *count >= 2).count();
+        if !has_endpoint && num_crossing_layers == 0 {
+            // Every way just passes through this point
+            continue;
+        }
+
+        let id = input.osm_node_ids[&pt];
+        if num_crossing_layers < 2 {
+            // The layers really connect here, unless a way only passes over an intersection on
+            // another layer
+            for (zorder, count) in layers {
+                if has_endpoint || count >= 2 {
+                    pt_to_intersection.insert((pt, zorder), id);
+                }
+            }
+            continue;
+        }
+        // Ways pass through on more than one layer, so each of those gets its own intersection.
+        // Ways on other layers only passing through aren't split.
+        layers.retain(|(zorder, count)| *count >= 2 || endpoints.contains(&(pt, *zorder)));
+        // The layer closest to the ground keeps the OSM node's ID
+        layers.sort_by_key(|(zorder, _)| zorder.abs());
+        pt_to_intersection.insert((pt, layers[0].0), id);
+        for (zorder, _) in layers.into_iter().skip(1) {
+            while used_ids.contains(&osm::NodeID(next_id)) {
+                next_id -= 1;
+            }
+            pt_to_intersection.insert((pt, zorder), osm::NodeID(next_id));
+            next_id -= 1;
+        }
+    }
+    pt_to_intersection
 }
 
 // TODO Consider doing this in PolyLine::new always. extend() there does this too.
@@ -259,3 +351,121 @@ fn should_collapse_roundabout(r: &RawRoad) -> bool {
         && r.center_points[0] == *r.center_points.last().unwrap()
         && r.length() < Distance::meters(50.0)
 }
+
+#[cfg(test)]
+mod tests {
+    use abstio::MapName;
+    use abstutil::Tags;
+    use map_model::osm::{NodeID, WayID};
+
+    use super::*;
+
+    fn way(pts: Vec<(f64, f64)>, kv: Vec<&str>) -> RawRoad {
+        let mut osm_tags = Tags::empty();
+        osm_tags.insert(osm::HIGHWAY, "residential");
+        for pair in kv {
+            let parts = pair.split('=').collect::<Vec<_>>();
+            osm_tags.insert(parts[0], parts[1]);
+        }
+        RawRoad {
+            center_points: pts.into_iter().map(|(x, y)| Pt2D::new(x, y)).collect(),
+            osm_tags,
+            turn_restrictions: Vec::new(),
+            complicated_turn_restrictions: Vec::new(),
+            percent_incline: 0.0,
+            crosswalk_forward: false,
+            crosswalk_backward: false,
+        }
+    }
+
+    #[test]
+    fn test_bridge_over_ground_intersection() {
+        // Two ground streets cross at (100, 100). A bridge passes over that point, sharing the
+        // node, and lands at (200, 200), where it meets another ground street. Another bridge lands
+        // at (400, 100), partway along a ground street.
+        let roads = vec![
+            (
+                WayID(1),
+                way(vec![(0.0, 100.0), (100.0, 100.0), (200.0, 100.0)], vec![]),
+            ),
+            (
+                WayID(2),
+                way(vec![(100.0, 0.0), (100.0, 100.0), (100.0, 200.0)], vec![]),
+            ),
+            (
+                WayID(3),
+                way(
+                    vec![(0.0, 0.0), (100.0, 100.0), (200.0, 200.0)],
+                    vec!["bridge=yes"],
+                ),
+            ),
+            (WayID(4), way(vec![(200.0, 200.0), (300.0, 200.0)], vec![])),
+            (
+                WayID(5),
+                way(vec![(400.0, 0.0), (400.0, 100.0), (400.0, 200.0)], vec![]),
+            ),
+            (
+                WayID(6),
+                way(vec![(300.0, 100.0), (400.0, 100.0)], vec!["bridge=yes"]),
+            ),
+        ];
+        let mut osm_node_ids = HashMap::new();
+        for (_, r) in &roads {
+            for pt in &r.center_points {
+                let next_id = NodeID(osm_node_ids.len() as i64 + 1);
+                osm_node_ids.entry(pt.to_hashable()).or_insert(next_id);
+            }
+        }
+        let crossing = osm_node_ids[&Pt2D::new(100.0, 100.0).to_hashable()];
+        let bridge_end = osm_node_ids[&Pt2D::new(200.0, 200.0).to_hashable()];
+        let bridge_landing = osm_node_ids[&Pt2D::new(400.0, 100.0).to_hashable()];
+        let input = OsmExtract {
+            roads,
+            traffic_signals: HashMap::new(),
+            all_way_stops: HashSet::new(),
+            osm_node_ids,
+            simple_turn_restrictions: Vec::new(),
+            complicated_turn_restrictions: Vec::new(),
+            amenities: Vec::new(),
+            crosswalks: HashSet::new(),
+            raised_curbs: HashSet::new(),
+        };
+
+        let mut map = RawMap::blank(MapName::new("zz", "test", "bridge"));
+        split_up_roads(&mut map, input, &mut Timer::throwaway());
+
+        // The bridge isn't split where it passes over the ground streets
+        let mut crossing_ways: Vec<WayID> = map
+            .roads_per_intersection(crossing)
+            .into_iter()
+            .map(|r| r.osm_way_id)
+            .collect();
+        crossing_ways.sort();
+        assert_eq!(crossing_ways, vec![WayID(1), WayID(1), WayID(2), WayID(2)]);
+        assert_eq!(
+            map.roads
+                .keys()
+                .filter(|r| r.osm_way_id == WayID(3))
+                .count(),
+            1
+        );
+
+        // But it does connect to the ground street where it ends
+        let mut bridge_end_ways: Vec<WayID> = map
+            .roads_per_intersection(bridge_end)
+            .into_iter()
+            .map(|r| r.osm_way_id)
+            .collect();
+        bridge_end_ways.sort();
+        assert_eq!(bridge_end_ways, vec![WayID(3), WayID(4)]);
+
+        // The ground street is split where the other bridge lands partway along it
+        let mut landing_ways: Vec<WayID> = map
+            .roads_per_intersection(bridge_landing)
+            .into_iter()
+            .map(|r| r.osm_way_id)
+            .collect();
+        landing_ways.sort();
+        assert_eq!(landing_ways, vec![WayID(5), WayID(5), WayID(6)]);
+    }
+}
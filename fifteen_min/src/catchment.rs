@@ -0,0 +1,186 @@
+use abstutil::prettyprint_usize;
+use geom::{Duration, QuadTree};
+use map_gui::tools::ColorLegend;
+use map_gui::ID;
+use map_model::BuildingID;
+use widgetry::{
+    Choice, Drawable, EventCtx, GfxCtx, HorizontalAlignment, Line, Outcome, Panel, State, Text,
+    TextExt, Transition, VerticalAlignment, Widget,
+};
+
+use crate::isochrone::{time_limit_choices, Isochrone, Options};
+use crate::viewer::{draw_star, index_buildings, mouseover_building};
+use crate::App;
+
+/// The reverse of the main viewer: pick a business, then see the area and number of residents that
+/// can reach it within a time limit.
+pub struct Catchment {
+    panel: Panel,
+    options: Options,
+    time_limit: Duration,
+    bldgs: QuadTree<BuildingID>,
+    catchment: Option<Isochrone>,
+    highlight_target: Drawable,
+    hovering: Option<(BuildingID, Text)>,
+}
+
+impl Catchment {
+    pub fn new_state(
+        ctx: &mut EventCtx,
+        app: &App,
+        options: Options,
+        time_limit: Duration,
+    ) -> Box<dyn State<App>> {
+        let mut state = Catchment {
+            panel: Panel::empty(ctx),
+            options,
+            time_limit,
+            bldgs: index_buildings(app, app.map.all_buildings().iter().map(|b| b.id)),
+            catchment: None,
+            highlight_target: Drawable::empty(ctx),
+            hovering: None,
+        };
+        state.panel = state.make_panel(ctx, app);
+        Box::new(state)
+    }
+
+    fn make_panel(&self, ctx: &mut EventCtx, app: &App) -> Panel {
+        let mut col = vec![Widget::row(vec![
+            Line("Who can reach a business?")
+                .small_heading()
+                .into_widget(ctx),
+            ctx.style().btn_close_widget(ctx),
+        ])];
+
+        if let Some(ref catchment) = self.catchment {
+            let bldg = app.map.get_b(catchment.start[0]);
+            let mut txt = Text::from(Line(&bldg.address).secondary());
+            for amenity in &bldg.amenities {
+                txt.add_line(format!(
+                    "{} ({})",
+                    amenity.names.get(app.opts.language.as_ref()),
+                    amenity.amenity_type
+                ));
+            }
+            col.push(txt.into_widget(ctx));
+            col.push(
+                Line(format!(
+                    "{} residents within {}",
+                    prettyprint_usize(catchment.population),
+                    self.time_limit
+                ))
+                .small_heading()
+                .into_widget(ctx),
+            );
+            col.push(ColorLegend::categories(
+                ctx,
+                catchment
+                    .legend_categories()
+                    .iter()
+                    .map(|(color, label)| (*color, label.as_str()))
+                    .collect(),
+            ));
+            col.push("Click another business to compare".text_widget(ctx));
+        } else {
+            col.push("Click a business to see who can reach it".text_widget(ctx));
+        }
+
+        col.push(Widget::row(vec![
+            "Time limit:".text_widget(ctx).centered_vert(),
+            Widget::dropdown(
+                ctx,
+                "time limit",
+                self.time_limit,
+                time_limit_choices()
+                    .into_iter()
+                    .map(|x| Choice::new(x.to_string(&app.opts.units), x))
+                    .collect(),
+            ),
+        ]));
+
+        Panel::new_builder(Widget::col(col))
+            .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
+            .build(ctx)
+    }
+
+    fn recalculate(&mut self, ctx: &mut EventCtx, app: &App, target: BuildingID) {
+        self.catchment = Some(Isochrone::catchment(
+            ctx,
+            app,
+            vec![target],
+            self.options.clone(),
+            self.time_limit,
+        ));
+        let star = draw_star(ctx, app.map.get_b(target));
+        self.highlight_target = ctx.upload(star);
+        self.panel = self.make_panel(ctx, app);
+    }
+}
+
+impl State<App> for Catchment {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition<App> {
+        ctx.canvas_movement();
+
+        if ctx.redo_mouseover() {
+            self.hovering = mouseover_building(ctx, app, &self.bldgs).map(|b| {
+                let bldg = app.map.get_b(b);
+                let txt = if !bldg.amenities.is_empty() {
+                    let mut txt = Text::new();
+                    for amenity in &bldg.amenities {
+                        txt.add_line(amenity.names.get(app.opts.language.as_ref()));
+                    }
+                    txt.add_line(Line("Click to see who can reach this").secondary());
+                    txt
+                } else if let Some(time) = self
+                    .catchment
+                    .as_ref()
+                    .and_then(|c| c.time_to_reach_building.get(&b))
+                {
+                    Text::from(format!("{} away from the business", time))
+                } else {
+                    Text::from("Not a business")
+                };
+                (b, txt)
+            });
+            app.current_selection = self.hovering.as_ref().map(|(b, _)| ID::Building(*b));
+        }
+
+        // Don't call normal_left_click unless we're hovering on something in map-space; otherwise
+        // panel.event never sees clicks.
+        if let Some((b, _)) = self.hovering {
+            if !app.map.get_b(b).amenities.is_empty() && ctx.normal_left_click() {
+                self.recalculate(ctx, app, b);
+            }
+        }
+
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => {
+                    app.current_selection = None;
+                    return Transition::Pop;
+                }
+                _ => unreachable!(),
+            },
+            Outcome::Changed(_) => {
+                self.time_limit = self.panel.dropdown_value("time limit");
+                if let Some(target) = self.catchment.as_ref().map(|c| c.start[0]) {
+                    self.recalculate(ctx, app, target);
+                }
+            }
+            _ => {}
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        if let Some(ref catchment) = self.catchment {
+            g.redraw(&catchment.draw);
+        }
+        g.redraw(&self.highlight_target);
+        self.panel.draw(g);
+        if let Some((_, ref txt)) = self.hovering {
+            g.draw_mouse_tooltip(txt.clone());
+        }
+    }
+}
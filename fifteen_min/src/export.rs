@@ -0,0 +1,117 @@
+//! Export an isochrone to files that GIS tools like QGIS can open.
+
+use anyhow::Result;
+use geojson::{Feature, FeatureCollection, GeoJson};
+use map_gui::tools::{draw_isochrone, save_file};
+use map_model::AmenityType;
+
+use crate::isochrone::Isochrone;
+use crate::App;
+
+/// Writes the contours and the time to reach every building as GeoJSON, and the reachable
+/// amenities as CSV. On web, this downloads the files instead. Returns the paths written.
+pub fn export_isochrone(app: &App, isochrone: &Isochrone) -> Result<Vec<String>> {
+    let map = &app.map;
+    let prefix = format!(
+        "isochrone_{}_{}",
+        map.get_name().as_filename(),
+        map.get_b(isochrone.start[0]).orig_id.inner()
+    );
+
+    let geojson = GeoJson::from(FeatureCollection {
+        bbox: None,
+        features: make_features(app, isochrone),
+        foreign_members: None,
+    });
+    let geojson_path = save_file(
+        &format!("{}.geojson", prefix),
+        serde_json::to_string_pretty(&geojson)?,
+    )?;
+    let csv_path = save_file(
+        &format!("{}_amenities.csv", prefix),
+        make_amenities_csv(app, isochrone),
+    )?;
+    Ok(vec![geojson_path, csv_path])
+}
+
+fn make_features(app: &App, isochrone: &Isochrone) -> Vec<Feature> {
+    let map = &app.map;
+    let gps_bounds = Some(map.get_gps_bounds());
+    let mut features = Vec::new();
+
+    // Calculate each contour separately, so we know which threshold it belongs to
+    for (idx, color) in isochrone.colors.iter().enumerate() {
+        let batch = draw_isochrone(
+            map,
+            &isochrone.time_to_reach_building,
+            &isochrone.thresholds[idx..idx + 2],
+            &[*color],
+        );
+        for mut feature in batch.into_geojson(gps_bounds) {
+            let properties = feature.properties.get_or_insert_with(serde_json::Map::new);
+            properties.insert("type".to_string(), "contour".into());
+            properties.insert(
+                "max_minutes".to_string(),
+                (isochrone.thresholds[idx + 1] / 60.0).round().into(),
+            );
+            features.push(feature);
+        }
+    }
+
+    for (b, duration) in &isochrone.time_to_reach_building {
+        let bldg = map.get_b(*b);
+        let mut properties = serde_json::Map::new();
+        properties.insert("type".to_string(), "building".into());
+        properties.insert("osm_id".to_string(), bldg.orig_id.to_string().into());
+        properties.insert("address".to_string(), bldg.address.clone().into());
+        properties.insert("seconds".to_string(), duration.inner_seconds().into());
+        features.push(Feature {
+            bbox: None,
+            geometry: Some(bldg.polygon.to_geojson(gps_bounds)),
+            id: None,
+            properties: Some(properties),
+            foreign_members: None,
+        });
+    }
+
+    features
+}
+
+fn make_amenities_csv(app: &App, isochrone: &Isochrone) -> String {
+    let mut out = vec!["category,amenity_type,name,address,osm_id,seconds".to_string()];
+    for (category, bldgs) in isochrone.amenities_reachable.borrow() {
+        for b in bldgs {
+            let bldg = app.map.get_b(*b);
+            for amenity in &bldg.amenities {
+                if AmenityType::categorize(&amenity.amenity_type) != Some(*category) {
+                    continue;
+                }
+                out.push(
+                    [
+                        category.to_string(),
+                        amenity.amenity_type.clone(),
+                        amenity.names.get(app.opts.language.as_ref()).to_string(),
+                        bldg.address.clone(),
+                        bldg.orig_id.to_string(),
+                        isochrone.time_to_reach_building[b]
+                            .inner_seconds()
+                            .to_string(),
+                    ]
+                    .iter()
+                    .map(|field| escape_csv(field))
+                    .collect::<Vec<_>>()
+                    .join(","),
+                );
+            }
+        }
+    }
+    out.join("\n")
+}
+
+fn escape_csv(field: &str) -> String {
+    if field.contains(',') || field.contains('"') || field.contains('\n') {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
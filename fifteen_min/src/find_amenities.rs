@@ -1,20 +1,26 @@
+use geom::Duration;
+use geom::QuadTree;
 use map_gui::tools::{draw_isochrone, ChooseSomething, ColorLegend};
 use map_gui::ID;
-use map_model::AmenityType;
+use map_model::{AmenityType, BuildingID};
 use widgetry::{
     Cached, Choice, Color, Drawable, EventCtx, GfxCtx, HorizontalAlignment, Line, Panel,
     SimpleState, State, TextExt, Transition, VerticalAlignment, Widget,
 };
 
 use crate::isochrone::{BorderIsochrone, Isochrone, Options};
-use crate::viewer::{draw_star, HoverKey, HoverOnBuilding};
+use crate::viewer::{draw_star, index_buildings, HoverKey, HoverOnBuilding};
 use crate::App;
 
 /// Calculate isochrones around each amenity on a map and merge them together using the min value
 pub struct FindAmenity;
 
 impl FindAmenity {
-    pub fn new_state(ctx: &mut EventCtx, options: Options) -> Box<dyn State<App>> {
+    pub fn new_state(
+        ctx: &mut EventCtx,
+        options: Options,
+        time_limit: Duration,
+    ) -> Box<dyn State<App>> {
         ChooseSomething::new_state(
             ctx,
             "Choose an amenity",
@@ -23,8 +29,9 @@ impl FindAmenity {
                 .map(|at| Choice::new(at.to_string(), at))
                 .collect(),
             Box::new(move |at, ctx, app| {
-                let multi_isochrone = create_multi_isochrone(ctx, app, at, options.clone());
-                let border_isochrone = create_border_isochrone(ctx, app, options);
+                let multi_isochrone =
+                    create_multi_isochrone(ctx, app, at, options.clone(), time_limit);
+                let border_isochrone = create_border_isochrone(ctx, app, options, time_limit);
                 return Transition::Replace(Results::new_state(
                     ctx,
                     app,
@@ -43,6 +50,7 @@ fn create_multi_isochrone(
     app: &App,
     category: AmenityType,
     options: Options,
+    time_limit: Duration,
 ) -> Isochrone {
     let map = &app.map;
     // For a category, find all matching stores
@@ -52,23 +60,29 @@ fn create_multi_isochrone(
             stores.push(b.id);
         }
     }
-    Isochrone::new(ctx, app, stores, options)
+    Isochrone::new(ctx, app, stores, options, time_limit)
 }
 
 /// Draw an isochrone from every intersection border
-fn create_border_isochrone(ctx: &mut EventCtx, app: &App, options: Options) -> BorderIsochrone {
+fn create_border_isochrone(
+    ctx: &mut EventCtx,
+    app: &App,
+    options: Options,
+    time_limit: Duration,
+) -> BorderIsochrone {
     let mut all_intersections = Vec::new();
     for i in app.map.all_intersections() {
         if i.is_border() {
             all_intersections.push(i.id);
         }
     }
-    BorderIsochrone::new(ctx, app, all_intersections, options)
+    BorderIsochrone::new(ctx, app, all_intersections, options, time_limit)
 }
 
 struct Results {
     draw: Drawable,
     isochrone: Isochrone,
+    bldgs: QuadTree<BuildingID>,
     hovering_on_bldg: Cached<HoverKey, HoverOnBuilding>,
 }
 
@@ -82,7 +96,7 @@ impl Results {
     ) -> Box<dyn State<App>> {
         let panel = Panel::new_builder(Widget::col(vec![
             Widget::row(vec![
-                Line(format!("{} within 15 minutes", category))
+                Line(format!("{} within {}", category, isochrone.time_limit))
                     .small_heading()
                     .into_widget(ctx),
                 ctx.style().btn_close_widget(ctx),
@@ -90,16 +104,19 @@ impl Results {
             format!("{} matching amenities", isochrone.start.len()).text_widget(ctx),
             ColorLegend::categories(
                 ctx,
-                vec![
-                    (Color::GREEN, "5 mins"),
-                    (Color::ORANGE, "10 mins"),
-                    (Color::RED, "15 mins"),
-                ],
+                isochrone
+                    .legend_categories()
+                    .iter()
+                    .map(|(color, label)| (*color, label.as_str()))
+                    .collect(),
             ),
             ColorLegend::row(
                 ctx,
                 Color::rgb(0, 0, 0).alpha(0.3),
-                "< 15 mins from border (amenity could exist off map)",
+                format!(
+                    "< {} from border (amenity could exist off map)",
+                    border_isochrone.time_limit
+                ),
             ),
         ]))
         .aligned(HorizontalAlignment::RightInset, VerticalAlignment::TopInset)
@@ -126,6 +143,7 @@ impl Results {
             Box::new(Results {
                 draw: ctx.upload(batch),
                 isochrone,
+                bldgs: index_buildings(app, app.map.all_buildings().iter().map(|b| b.id)),
                 hovering_on_bldg: Cached::new(),
             }),
         )
@@ -146,7 +164,7 @@ impl SimpleState<App> for Results {
         if ctx.redo_mouseover() {
             let isochrone = &self.isochrone;
             self.hovering_on_bldg
-                .update(HoverOnBuilding::key(ctx, app), |key| {
+                .update(HoverOnBuilding::key(ctx, app, &self.bldgs), |key| {
                     HoverOnBuilding::value(ctx, app, key, isochrone)
                 });
             // Also update this to conveniently get an outline drawn. Note we don't want to do this
@@ -1,26 +1,32 @@
 use std::collections::HashMap;
 
 use crate::App;
-use abstutil::{prettyprint_usize, Counter, Timer};
-use geom::Percent;
-use map_gui::tools::PopupMsg;
+use abstutil::{prettyprint_usize, MultiMap, Timer};
+use geom::{Duration, Percent};
+use map_gui::tools::{ColorLegend, PopupMsg};
 use map_model::connectivity::Spot;
 use map_model::{AmenityType, BuildingID};
 use widgetry::{
-    Color, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key, Line, Panel,
-    SimpleState, State, TextExt, Toggle, Transition, VerticalAlignment, Widget,
+    Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key, Line, Panel, SimpleState,
+    State, Text, TextExt, Toggle, Transition, VerticalAlignment, Widget,
 };
 
 use crate::isochrone::Options;
+use crate::score::{describe_score, score_scale};
 
 /// Ask what types of amenities are necessary to be within a walkshed, then rank every house with
 /// how many of those needs are satisfied.
 pub struct FindHome {
     options: Options,
+    time_limit: Duration,
 }
 
 impl FindHome {
-    pub fn new_state(ctx: &mut EventCtx, options: Options) -> Box<dyn State<App>> {
+    pub fn new_state(
+        ctx: &mut EventCtx,
+        options: Options,
+        time_limit: Duration,
+    ) -> Box<dyn State<App>> {
         let panel = Panel::new_builder(Widget::col(vec![
             Widget::row(vec![
                 Line("Find your walkable home")
@@ -29,7 +35,11 @@ impl FindHome {
                 ctx.style().btn_close_widget(ctx),
             ]),
             // TODO Adjust text to say bikeshed, or otherwise reflect the options chosen
-            "Select the types of businesses you want within a 15 minute walkshed.".text_widget(ctx),
+            format!(
+                "Select the types of businesses you want within a {} walkshed.",
+                time_limit
+            )
+            .text_widget(ctx),
             Widget::custom_row(
                 AmenityType::all()
                     .into_iter()
@@ -45,7 +55,13 @@ impl FindHome {
         ]))
         .build(ctx);
 
-        <dyn SimpleState<_>>::new_state(panel, Box::new(FindHome { options }))
+        <dyn SimpleState<_>>::new_state(
+            panel,
+            Box::new(FindHome {
+                options,
+                time_limit,
+            }),
+        )
     }
 }
 
@@ -73,7 +89,13 @@ impl SimpleState<App> for FindHome {
                 }
 
                 let scores = ctx.loading_screen("search for houses", |_, timer| {
-                    score_houses(app, amenities.clone(), self.options.clone(), timer)
+                    score_houses(
+                        app,
+                        amenities.clone(),
+                        self.options.clone(),
+                        self.time_limit,
+                        timer,
+                    )
                 });
                 return Transition::Push(Results::new_state(ctx, app, scores, amenities));
             }
@@ -82,42 +104,52 @@ impl SimpleState<App> for FindHome {
     }
 }
 
-/// For every house in the map, return the percent of amenities located within a 15min walkshed. A
-/// single matching business per category is enough to count as satisfied.
+/// For every house in the map, return the access score (from 0 to 100) for the chosen amenities
+/// located within a walkshed of `time_limit`, using the weights from the session. A single
+/// matching business per category is enough to count as satisfied.
 fn score_houses(
     app: &App,
     amenities: Vec<AmenityType>,
     options: Options,
+    time_limit: Duration,
     timer: &mut Timer,
-) -> HashMap<BuildingID, Percent> {
-    let num_categories = amenities.len();
-    let mut satisfied_per_bldg: Counter<BuildingID> = Counter::new();
+) -> HashMap<BuildingID, usize> {
+    let weights = app.session.weights.only(&amenities);
+    let mut satisfied_per_bldg: MultiMap<BuildingID, AmenityType> = MultiMap::new();
 
     let map = &app.map;
-    for times in timer.parallelize("find houses close to amenities", amenities, |category| {
-        // For each category, find all matching stores
-        let mut stores = Vec::new();
-        for b in map.all_buildings() {
-            if b.has_amenity(category) {
-                stores.push(Spot::Building(b.id));
+    for (category, times) in
+        timer.parallelize("find houses close to amenities", amenities, |category| {
+            // For each category, find all matching stores
+            let mut stores = Vec::new();
+            for b in map.all_buildings() {
+                if b.has_amenity(category) {
+                    stores.push(Spot::Building(b.id));
+                }
             }
-        }
-        options.clone().times_from(map, stores)
-    }) {
+            (
+                category,
+                options.clone().times_from(map, stores, time_limit),
+            )
+        })
+    {
         for (b, _) in times {
-            satisfied_per_bldg.inc(b);
+            if map.get_b(b).bldg_type.has_residents() {
+                satisfied_per_bldg.insert(b, category);
+            }
         }
     }
 
     let mut scores = HashMap::new();
-    for (b, cnt) in satisfied_per_bldg.consume() {
-        scores.insert(b, Percent::of(cnt, num_categories));
+    for (b, categories) in satisfied_per_bldg.consume() {
+        scores.insert(b, weights.score(|at| categories.contains(&at)));
     }
     scores
 }
 
 // TODO Show the matching amenities.
 // TODO As you hover over a building, show the nearest amenity of each type
+/// Color every house by its score, and list the best ones.
 struct Results {
     draw_houses: Drawable,
 }
@@ -126,17 +158,33 @@ impl Results {
     fn new_state(
         ctx: &mut EventCtx,
         app: &App,
-        scores: HashMap<BuildingID, Percent>,
+        scores: HashMap<BuildingID, usize>,
         amenities: Vec<AmenityType>,
     ) -> Box<dyn State<App>> {
-        // TODO Show imperfect matches with different colors.
+        let scale = score_scale();
         let mut batch = GeomBatch::new();
         let mut count = 0;
-        for (b, pct) in scores {
-            if pct == Percent::int(100) {
-                batch.push(Color::RED, app.map.get_b(b).polygon.clone());
+        let mut ranked = Vec::new();
+        for (b, score) in scores {
+            batch.push(
+                scale.eval(score as f64 / 100.0),
+                app.map.get_b(b).polygon.clone(),
+            );
+            if score == 100 {
                 count += 1;
             }
+            ranked.push((score, b));
+        }
+        // Best first, breaking ties by ID to be deterministic
+        ranked.sort_by_key(|(score, b)| (std::cmp::Reverse(*score), *b));
+
+        let mut best = Text::from(Line("Best matches:").secondary());
+        for (score, b) in ranked.into_iter().take(10) {
+            best.add_line(format!(
+                "{}: {}",
+                app.map.get_b(b).address,
+                describe_score(score)
+            ));
         }
 
         let panel = Panel::new_builder(Widget::col(vec![
@@ -154,6 +202,9 @@ impl Results {
                     .join(", ")
             )
             .text_widget(ctx),
+            "Partial matches are scored using the amenity weights".text_widget(ctx),
+            ColorLegend::gradient(ctx, &scale, vec!["0", "50", "100"]),
+            best.into_widget(ctx),
             ctx.style()
                 .btn_outline
                 .text("Back")
@@ -1,11 +1,13 @@
 use std::collections::{HashMap, HashSet};
 
+use abstio::MapName;
 use abstutil::MultiMap;
 use connectivity::Spot;
 use geom::Duration;
+use lru::LruCache;
 use map_gui::tools::draw_isochrone;
 use map_model::{
-    connectivity, AmenityType, BuildingID, BuildingType, IntersectionID, LaneType, Map, Path,
+    connectivity, AmenityType, BuildingID, BuildingType, IntersectionID, Map, Path,
     PathConstraints, PathRequest,
 };
 use widgetry::{Color, Drawable, EventCtx};
@@ -18,6 +20,8 @@ pub struct Isochrone {
     pub start: Vec<BuildingID>,
     /// The options used to generate this isochrone
     pub options: Options,
+    /// Buildings further away than this aren't reachable
+    pub time_limit: Duration,
     /// Colored polygon contours, uploaded to the GPU and ready for drawing
     pub draw: Drawable,
     /// Thresholds used to draw the isochrone
@@ -36,7 +40,7 @@ pub struct Isochrone {
 }
 
 /// The constraints on how we're moving.
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub enum Options {
     Walking(connectivity::WalkingOptions),
     Biking,
@@ -44,20 +48,150 @@ pub enum Options {
 
 impl Options {
     /// Calculate the quickest time to reach buildings across the map from any of the starting
-    /// points, subject to the walking/biking settings configured in these Options.
-    pub fn times_from(self, map: &Map, starts: Vec<Spot>) -> HashMap<BuildingID, Duration> {
+    /// points, subject to the walking/biking settings configured in these Options. Buildings
+    /// further than `time_limit` away are omitted.
+    pub fn times_from(
+        self,
+        map: &Map,
+        starts: Vec<Spot>,
+        time_limit: Duration,
+    ) -> HashMap<BuildingID, Duration> {
         match self {
             Options::Walking(opts) => {
-                connectivity::all_walking_costs_from(map, starts, Duration::minutes(15), opts)
+                connectivity::all_walking_costs_from(map, starts, time_limit, opts)
+            }
+            Options::Biking => {
+                connectivity::all_vehicle_costs_from(map, starts, time_limit, PathConstraints::Bike)
             }
-            Options::Biking => connectivity::all_vehicle_costs_from(
-                map,
-                starts,
-                Duration::minutes(15),
-                PathConstraints::Bike,
-            ),
         }
     }
+
+    /// The reverse of `times_from`: calculate the quickest time from buildings across the map to
+    /// reach any of the destinations.
+    pub fn times_to(
+        self,
+        map: &Map,
+        ends: Vec<Spot>,
+        time_limit: Duration,
+    ) -> HashMap<BuildingID, Duration> {
+        match self {
+            Options::Walking(opts) => {
+                connectivity::all_walking_costs_to(map, ends, time_limit, opts)
+            }
+            Options::Biking => {
+                connectivity::all_vehicle_costs_to(map, ends, time_limit, PathConstraints::Bike)
+            }
+        }
+    }
+}
+
+/// How many searches `CostCache` remembers. Each one is just a cost per reachable building.
+const MAX_CACHED_SEARCHES: usize = 20;
+
+/// Remembers the cost to reach buildings from previous searches. Changing the time limit or the
+/// time of day, or going back to options used before, doesn't have to repeat the search. The least
+/// recently used search is forgotten first.
+pub struct CostCache {
+    map: Option<MapName>,
+    searches: LruCache<SearchKey, Search>,
+}
+
+impl Default for CostCache {
+    fn default() -> CostCache {
+        CostCache {
+            map: None,
+            searches: LruCache::new(MAX_CACHED_SEARCHES),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct SearchKey {
+    spots: Vec<Spot>,
+    reverse: bool,
+    options: OptionsKey,
+}
+
+/// `Options` can't be hashed directly, because of the walking speed
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum OptionsKey {
+    Walking {
+        allow_shoulders: bool,
+        walking_speed_bits: u64,
+        consider_elevation: bool,
+        wheelchair: bool,
+    },
+    Biking,
+}
+
+struct Search {
+    time_limit: Duration,
+    costs: HashMap<BuildingID, Duration>,
+}
+
+impl CostCache {
+    /// Like `Options::times_from`, or `Options::times_to` if `reverse`, but reusing previous
+    /// results. A search with a longer time limit also answers any shorter one. Only a search with
+    /// exactly the same options, including the walking speed, is reused.
+    pub fn get(
+        &mut self,
+        map: &Map,
+        spots: Vec<Spot>,
+        reverse: bool,
+        options: &Options,
+        time_limit: Duration,
+    ) -> HashMap<BuildingID, Duration> {
+        if self.map.as_ref() != Some(map.get_name()) {
+            self.map = Some(map.get_name().clone());
+            self.searches.clear();
+        }
+
+        let key = SearchKey {
+            spots: spots.clone(),
+            reverse,
+            options: match options {
+                Options::Walking(opts) => OptionsKey::Walking {
+                    allow_shoulders: opts.allow_shoulders,
+                    walking_speed_bits: opts.walking_speed.inner_meters_per_second().to_bits(),
+                    consider_elevation: opts.consider_elevation,
+                    wheelchair: opts.wheelchair,
+                },
+                Options::Biking => OptionsKey::Biking,
+            },
+        };
+        if let Some(search) = self.searches.get(&key) {
+            if time_limit <= search.time_limit {
+                return search
+                    .costs
+                    .iter()
+                    .filter(|(_, cost)| **cost <= time_limit)
+                    .map(|(b, cost)| (*b, *cost))
+                    .collect();
+            }
+        }
+
+        let costs = if reverse {
+            options.clone().times_to(map, spots, time_limit)
+        } else {
+            options.clone().times_from(map, spots, time_limit)
+        };
+        self.searches.put(
+            key,
+            Search {
+                time_limit,
+                costs: costs.clone(),
+            },
+        );
+        costs
+    }
+}
+
+/// The time limits that can be chosen
+pub fn time_limit_choices() -> Vec<Duration> {
+    vec![5, 10, 15, 20, 30, 45, 60]
+        .into_iter()
+        .map(Duration::minutes)
+        .collect()
 }
 
 impl Isochrone {
@@ -66,20 +200,59 @@ impl Isochrone {
         app: &App,
         start: Vec<BuildingID>,
         options: Options,
+        time_limit: Duration,
     ) -> Isochrone {
         let spot_starts = start.iter().map(|b_id| Spot::Building(*b_id)).collect();
-        let time_to_reach_building = options.clone().times_from(&app.map, spot_starts);
+        let time_to_reach_building = app.session.costs.lock().unwrap().get(
+            &app.map,
+            spot_starts,
+            false,
+            &options,
+            time_limit,
+        );
+        Isochrone::from_times(ctx, app, start, options, time_limit, time_to_reach_building)
+    }
+
+    /// The reverse of an isochrone: the area that can reach any of the `targets`. The times are
+    /// how long it takes each building to get to the targets, and the population is how many
+    /// people live within the catchment area.
+    pub fn catchment(
+        ctx: &mut EventCtx,
+        app: &App,
+        targets: Vec<BuildingID>,
+        options: Options,
+        time_limit: Duration,
+    ) -> Isochrone {
+        let spot_ends = targets.iter().map(|b_id| Spot::Building(*b_id)).collect();
+        let time_to_reach_building = app
+            .session
+            .costs
+            .lock()
+            .unwrap()
+            .get(&app.map, spot_ends, true, &options, time_limit);
+        Isochrone::from_times(
+            ctx,
+            app,
+            targets,
+            options,
+            time_limit,
+            time_to_reach_building,
+        )
+    }
 
-        let mut amenities_reachable = MultiMap::new();
+    fn from_times(
+        ctx: &mut EventCtx,
+        app: &App,
+        start: Vec<BuildingID>,
+        options: Options,
+        time_limit: Duration,
+        time_to_reach_building: HashMap<BuildingID, Duration>,
+    ) -> Isochrone {
+        let amenities_reachable = amenities_reachable(app, &time_to_reach_building);
         let mut population = 0;
         let mut all_roads = HashSet::new();
         for b in time_to_reach_building.keys() {
             let bldg = app.map.get_b(*b);
-            for amenity in &bldg.amenities {
-                if let Some(category) = AmenityType::categorize(&amenity.amenity_type) {
-                    amenities_reachable.insert(category, bldg.id);
-                }
-            }
             match bldg.bldg_type {
                 BuildingType::Residential { num_residents, .. }
                 | BuildingType::ResidentialCommercial(num_residents, _) => {
@@ -92,21 +265,16 @@ impl Isochrone {
 
         let mut onstreet_parking_spots = 0;
         for r in all_roads {
-            let r = app.map.get_r(r);
-            for l in &r.lanes {
-                if l.lane_type == LaneType::Parking {
-                    onstreet_parking_spots += l.number_parking_spots(app.map.get_config());
-                }
-            }
+            onstreet_parking_spots += app.map.get_r(r).number_parking_spots(app.map.get_config());
         }
 
         // Generate polygons covering the contour line where the cost in the grid crosses these
-        // threshold values.
+        // threshold values. Split the time limit into three equal bands.
         let thresholds = vec![
             0.1,
-            Duration::minutes(5).inner_seconds(),
-            Duration::minutes(10).inner_seconds(),
-            Duration::minutes(15).inner_seconds(),
+            (time_limit / 3.0).inner_seconds(),
+            (time_limit * 2.0 / 3.0).inner_seconds(),
+            time_limit.inner_seconds(),
         ];
 
         // And color the polygon for each threshold
@@ -119,6 +287,7 @@ impl Isochrone {
         let mut i = Isochrone {
             start,
             options,
+            time_limit,
             draw: Drawable::empty(ctx),
             thresholds,
             colors,
@@ -138,6 +307,12 @@ impl Isochrone {
         i
     }
 
+    /// Only the opening hours considered changed, so recount the amenities without repeating the
+    /// search or redrawing the contours.
+    pub fn recount_amenities(&mut self, app: &App) {
+        self.amenities_reachable = amenities_reachable(app, &self.time_to_reach_building);
+    }
+
     pub fn path_to(&self, map: &Map, to: BuildingID) -> Option<Path> {
         // Don't draw paths to places far away
         if !self.time_to_reach_building.contains_key(&to) {
@@ -157,6 +332,34 @@ impl Isochrone {
 
         all_paths.min_by_key(|path| path.total_length())
     }
+
+    /// Describe the color of each contour band, for a legend
+    pub fn legend_categories(&self) -> Vec<(Color, String)> {
+        self.colors
+            .iter()
+            .zip(self.thresholds.iter().skip(1))
+            .map(|(color, secs)| (*color, format!("{}", Duration::seconds(*secs))))
+            .collect()
+    }
+}
+
+fn amenities_reachable(
+    app: &App,
+    time_to_reach_building: &HashMap<BuildingID, Duration>,
+) -> MultiMap<AmenityType, BuildingID> {
+    let mut amenities_reachable = MultiMap::new();
+    for b in time_to_reach_building.keys() {
+        let bldg = app.map.get_b(*b);
+        for amenity in &bldg.amenities {
+            if !app.session.is_open(amenity) {
+                continue;
+            }
+            if let Some(category) = AmenityType::categorize(&amenity.amenity_type) {
+                amenities_reachable.insert(category, bldg.id);
+            }
+        }
+    }
+    amenities_reachable
 }
 
 /// Represents the area reachable from all intersections on the map border
@@ -165,6 +368,8 @@ pub struct BorderIsochrone {
     pub start: Vec<IntersectionID>,
     /// The options used to generate this isochrone
     pub options: Options,
+    /// Buildings further away than this aren't reachable
+    pub time_limit: Duration,
     /// Colored polygon contours, uploaded to the GPU and ready for drawing
     pub draw: Drawable,
     /// Thresholds used to draw the isochrone
@@ -181,12 +386,19 @@ impl BorderIsochrone {
         app: &App,
         start: Vec<IntersectionID>,
         options: Options,
+        time_limit: Duration,
     ) -> BorderIsochrone {
         let spot_starts = start.iter().map(|i_id| Spot::Border(*i_id)).collect();
-        let time_to_reach_building = options.clone().times_from(&app.map, spot_starts);
+        let time_to_reach_building = app.session.costs.lock().unwrap().get(
+            &app.map,
+            spot_starts,
+            false,
+            &options,
+            time_limit,
+        );
 
-        // Generate a single polygon showing 15 minutes from the border
-        let thresholds = vec![0.1, Duration::minutes(15).inner_seconds()];
+        // Generate a single polygon showing the time limit from the border
+        let thresholds = vec![0.1, time_limit.inner_seconds()];
 
         // Use one color for the entire polygon
         let colors = vec![Color::rgb(0, 0, 0).alpha(0.3)];
@@ -194,6 +406,7 @@ impl BorderIsochrone {
         let mut i = BorderIsochrone {
             start,
             options,
+            time_limit,
             draw: Drawable::empty(ctx),
             thresholds,
             colors,
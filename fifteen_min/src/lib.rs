@@ -1,16 +1,48 @@
 #![allow(clippy::type_complexity)]
 
+use std::sync::Mutex;
+
+use geom::Time;
+use map_model::{Amenity, Weekday};
 use widgetry::Settings;
 
 #[macro_use]
 extern crate log;
 
+mod catchment;
+mod export;
 mod find_amenities;
 mod find_home;
 mod isochrone;
+mod score;
+mod shortlist;
 mod viewer;
+mod walkability;
+
+type App = map_gui::SimpleApp<Session>;
 
-type App = map_gui::SimpleApp<()>;
+/// State shared by all of the tools
+pub struct Session {
+    pub weights: score::Weights,
+    /// Only count amenities open at this time of day. None means to ignore opening hours.
+    pub time_of_day: Option<Time>,
+    pub day: Weekday,
+    /// The name of the list of home candidates being edited
+    pub shortlist: String,
+    /// Results of previous isochrone searches. Shared, because the tools are often switched
+    /// between with the same options.
+    pub costs: Mutex<isochrone::CostCache>,
+}
+
+impl Session {
+    /// Should this amenity count, given the time being considered?
+    pub fn is_open(&self, amenity: &Amenity) -> bool {
+        match self.time_of_day {
+            Some(time) => amenity.is_open(self.day, time),
+            None => true,
+        }
+    }
+}
 
 pub fn main() {
     let settings = Settings::new("15-minute neighborhoods");
@@ -23,7 +55,14 @@ fn run(mut settings: Settings) {
         .read_svg(Box::new(abstio::slurp_bytes))
         .canvas_settings(options.canvas_settings.clone());
     widgetry::run(settings, |ctx| {
-        map_gui::SimpleApp::new(ctx, options, (), |ctx, app| {
+        let session = Session {
+            weights: score::Weights::default(),
+            time_of_day: None,
+            day: Weekday::Monday,
+            shortlist: "home candidates".to_string(),
+            costs: Mutex::new(isochrone::CostCache::default()),
+        };
+        map_gui::SimpleApp::new(ctx, options, session, |ctx, app| {
             vec![
                 map_gui::tools::TitleScreen::new_state(
                     ctx,
@@ -0,0 +1,189 @@
+//! Condense which categories of amenities are reachable into a single 0-100 access score. Each
+//! category is weighted, since a grocery store usually matters more than a bike shop.
+
+use std::collections::BTreeMap;
+
+use geom::Percent;
+use map_gui::tools::ColorScale;
+use map_model::AmenityType;
+use widgetry::{
+    Color, DrawBaselayer, EventCtx, HorizontalAlignment, Line, Panel, SimpleState, Slider, State,
+    Text, TextExt, Transition, VerticalAlignment, Widget,
+};
+
+use crate::viewer::Viewer;
+use crate::App;
+
+/// The heaviest a category can be weighted
+const MAX_WEIGHT: usize = 5;
+
+/// How much each category of amenity contributes to the access score. These live in the session,
+/// so every tool scores places the same way.
+#[derive(Clone)]
+pub struct Weights(BTreeMap<AmenityType, usize>);
+
+impl Default for Weights {
+    fn default() -> Weights {
+        Weights(AmenityType::all().into_iter().map(|at| (at, 1)).collect())
+    }
+}
+
+impl Weights {
+    /// Only count some categories, ignoring the rest.
+    pub fn only(&self, categories: &[AmenityType]) -> Weights {
+        Weights(
+            self.0
+                .iter()
+                .map(|(at, weight)| (*at, if categories.contains(at) { *weight } else { 0 }))
+                .collect(),
+        )
+    }
+
+    /// Returns a score from 0 to 100, based on the weight of the categories that're reachable. If
+    /// every weight is 0, nothing matters and the score is 0.
+    pub fn score<F: Fn(AmenityType) -> bool>(&self, reachable: F) -> usize {
+        let mut total = 0;
+        let mut satisfied = 0;
+        for (at, weight) in &self.0 {
+            total += *weight;
+            if reachable(*at) {
+                satisfied += *weight;
+            }
+        }
+        if total == 0 {
+            return 0;
+        }
+        ((100 * satisfied) as f64 / total as f64).round() as usize
+    }
+
+    /// A slider for every category
+    pub fn to_controls(&self, ctx: &mut EventCtx) -> Widget {
+        Widget::col(
+            self.0
+                .iter()
+                .map(|(at, weight)| {
+                    Widget::row(vec![
+                        at.to_string().text_widget(ctx).centered_vert(),
+                        Widget::row(vec![
+                            Slider::area(
+                                ctx,
+                                150.0,
+                                *weight as f64 / MAX_WEIGHT as f64,
+                                &slider_label(*at),
+                            ),
+                            describe_weight(ctx, *at, *weight),
+                        ])
+                        .align_right(),
+                    ])
+                })
+                .collect(),
+        )
+    }
+
+    /// Read the weights from the sliders made by `to_controls`, and update the labels next to them.
+    pub fn from_controls(ctx: &mut EventCtx, panel: &mut Panel) -> Weights {
+        let mut weights = BTreeMap::new();
+        for at in AmenityType::all() {
+            let weight = (panel.slider(&slider_label(at)).get_percent() * MAX_WEIGHT as f64).round()
+                as usize;
+            panel.replace(ctx, &value_label(at), describe_weight(ctx, at, weight));
+            weights.insert(at, weight);
+        }
+        Weights(weights)
+    }
+}
+
+fn slider_label(at: AmenityType) -> String {
+    format!("weight for {}", at)
+}
+
+fn value_label(at: AmenityType) -> String {
+    format!("weight value for {}", at)
+}
+
+fn describe_weight(ctx: &EventCtx, at: AmenityType, weight: usize) -> Widget {
+    Line(format!("{}x", weight))
+        .into_widget(ctx)
+        .centered_vert()
+        .named(value_label(at))
+}
+
+pub fn describe_score(score: usize) -> String {
+    format!("{} / 100", score)
+}
+
+/// Colors a score, after dividing it by 100
+pub fn score_scale() -> ColorScale {
+    ColorScale(vec![
+        Color::RED.alpha(0.7),
+        Color::YELLOW.alpha(0.7),
+        Color::GREEN.alpha(0.7),
+    ])
+}
+
+/// Adjust the weights in the session, previewing the access score for one set of reachable
+/// categories.
+pub struct EditWeights {
+    reachable: Vec<AmenityType>,
+}
+
+impl EditWeights {
+    pub fn new_state(
+        ctx: &mut EventCtx,
+        app: &App,
+        reachable: Vec<AmenityType>,
+    ) -> Box<dyn State<App>> {
+        let panel = Panel::new_builder(Widget::col(vec![
+            Widget::row(vec![
+                Line("Amenity weights").small_heading().into_widget(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            "How much does each type of amenity matter to you?".text_widget(ctx),
+            score_widget(ctx, app.session.weights.score(|at| reachable.contains(&at))),
+            app.session.weights.to_controls(ctx),
+        ]))
+        .aligned(HorizontalAlignment::Left, VerticalAlignment::Top)
+        .max_size(Percent::int(35), Percent::int(85))
+        .build(ctx);
+        <dyn SimpleState<_>>::new_state(panel, Box::new(EditWeights { reachable }))
+    }
+}
+
+fn score_widget(ctx: &EventCtx, score: usize) -> Widget {
+    Text::from_all(vec![
+        Line("Access score from here: ").secondary(),
+        Line(describe_score(score)),
+    ])
+    .into_widget(ctx)
+    .named("score")
+}
+
+impl SimpleState<App> for EditWeights {
+    fn on_click(&mut self, _: &mut EventCtx, _: &mut App, x: &str, _: &Panel) -> Transition<App> {
+        match x {
+            "close" => Viewer::pop_and_rescore(),
+            _ => unreachable!(),
+        }
+    }
+
+    fn panel_changed(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        panel: &mut Panel,
+    ) -> Option<Transition<App>> {
+        app.session.weights = Weights::from_controls(ctx, panel);
+        let score = app.session.weights.score(|at| self.reachable.contains(&at));
+        panel.replace(ctx, "score", score_widget(ctx, score));
+        None
+    }
+
+    fn other_event(&mut self, ctx: &mut EventCtx, _: &mut App) -> Transition<App> {
+        ctx.canvas_movement();
+        Transition::Keep
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::PreviousState
+    }
+}
@@ -0,0 +1,348 @@
+//! Named lists of buildings being considered as a home, each with a note. These are saved per map
+//! as player data, so a shortlist survives across sessions.
+
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+use abstutil::Timer;
+use geom::Duration;
+use map_gui::tools::{ChooseSomething, PromptInput};
+use map_model::connectivity::Spot;
+use map_model::osm::OsmID;
+use map_model::{AmenityType, BuildingID, Map};
+use widgetry::{
+    Choice, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Line, Panel, SimpleState,
+    State, Text, TextBox, Transition, VerticalAlignment, Widget,
+};
+
+use crate::isochrone::Options;
+use crate::score::{describe_score, score_scale};
+use crate::viewer::{draw_star, Viewer};
+use crate::{App, Session};
+
+#[derive(Serialize, Deserialize)]
+pub struct Shortlist {
+    pub name: String,
+    pub homes: Vec<Candidate>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Candidate {
+    /// Refer to the building by OSM ID, so the list still works after the map is re-imported
+    pub bldg: OsmID,
+    pub note: String,
+}
+
+impl Shortlist {
+    /// Load the list currently chosen in the session, or start an empty one.
+    pub fn load_current(app: &App) -> Shortlist {
+        Shortlist::load(app, &app.session.shortlist)
+    }
+
+    fn load(app: &App, name: &str) -> Shortlist {
+        abstio::maybe_read_json::<Shortlist>(Shortlist::path(app, name), &mut Timer::throwaway())
+            .unwrap_or_else(|_| Shortlist {
+                name: name.to_string(),
+                homes: Vec::new(),
+            })
+    }
+
+    pub fn save(&self, app: &App) {
+        abstio::write_json(Shortlist::path(app, &self.name), self);
+    }
+
+    fn dir(app: &App) -> String {
+        let name = app.map.get_name();
+        abstio::path_player(format!(
+            "fifteen_min/shortlists/{}/{}/{}",
+            name.city.country, name.city.city, name.map
+        ))
+    }
+
+    /// The name is typed in by the player, so only keep characters that are safe in a filename.
+    /// The original name is stored in the file.
+    fn path(app: &App, name: &str) -> String {
+        let filename: String = name
+            .chars()
+            .map(|c| {
+                if c.is_alphanumeric() || c == ' ' || c == '-' || c == '_' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        format!("{}/{}.json", Shortlist::dir(app), filename)
+    }
+
+    /// The names of every list saved for this map
+    fn all_names(app: &App) -> BTreeSet<String> {
+        abstio::load_all_objects::<Shortlist>(Shortlist::dir(app))
+            .into_iter()
+            .map(|(_, shortlist)| shortlist.name)
+            .collect()
+    }
+
+    pub fn contains(&self, app: &App, b: BuildingID) -> bool {
+        let orig_id = app.map.get_b(b).orig_id;
+        self.homes.iter().any(|c| c.bldg == orig_id)
+    }
+
+    pub fn add(&mut self, app: &App, b: BuildingID) {
+        if !self.contains(app, b) {
+            self.homes.push(Candidate {
+                bldg: app.map.get_b(b).orig_id,
+                note: String::new(),
+            });
+        }
+    }
+
+    pub fn remove(&mut self, app: &App, b: BuildingID) {
+        let orig_id = app.map.get_b(b).orig_id;
+        self.homes.retain(|c| c.bldg != orig_id);
+    }
+
+    /// The candidates that still exist in the current map, along with their note
+    fn buildings(&self, app: &App) -> Vec<(BuildingID, String)> {
+        self.homes
+            .iter()
+            .filter_map(|c| {
+                app.map
+                    .find_b_by_osm_id(c.bldg)
+                    .map(|b| (b, c.note.clone()))
+            })
+            .collect()
+    }
+}
+
+/// Show every home on the current shortlist with its access score, and edit their notes.
+pub struct ShortlistOverlay {
+    options: Options,
+    time_limit: Duration,
+    /// Matches the rows in the panel
+    homes: Vec<BuildingID>,
+    draw: Drawable,
+}
+
+impl ShortlistOverlay {
+    pub fn new_state(
+        ctx: &mut EventCtx,
+        app: &App,
+        options: Options,
+        time_limit: Duration,
+    ) -> Box<dyn State<App>> {
+        let shortlist = Shortlist::load_current(app);
+        let homes = shortlist.buildings(app);
+
+        let map = &app.map;
+        let session = &app.session;
+        let scores = ctx.loading_screen("score home candidates", |_, timer| {
+            timer.parallelize(
+                "score home candidates",
+                homes.iter().map(|(b, _)| *b).collect(),
+                |b| score_home(map, session, b, options.clone(), time_limit),
+            )
+        });
+
+        let scale = score_scale();
+        let mut batch = GeomBatch::new();
+        let mut rows = vec![
+            Widget::row(vec![
+                Line(format!("Home candidates: {}", shortlist.name))
+                    .small_heading()
+                    .into_widget(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            Line(format!("Access scores within a {} walkshed", time_limit))
+                .secondary()
+                .into_widget(ctx),
+        ];
+        if homes.is_empty() {
+            rows.push(
+                Text::from(Line("Star a home from the explorer to add it here").secondary())
+                    .into_widget(ctx),
+            );
+        }
+        for (idx, ((b, note), score)) in homes.iter().zip(scores.into_iter()).enumerate() {
+            let bldg = app.map.get_b(*b);
+            batch.push(scale.eval(score as f64 / 100.0), bldg.polygon.clone());
+            batch.append(draw_star(ctx, bldg));
+
+            rows.push(Widget::row(vec![
+                ctx.style()
+                    .btn_plain
+                    .text(format!("{}: {}", bldg.address, describe_score(score)))
+                    .build_widget(ctx, format!("go to {}", idx)),
+                ctx.style()
+                    .btn_plain
+                    .icon("system/assets/tools/trash.svg")
+                    .build_widget(ctx, format!("remove {}", idx))
+                    .align_right(),
+            ]));
+            rows.push(TextBox::default_widget(
+                ctx,
+                format!("note {}", idx),
+                note.clone(),
+            ));
+        }
+        rows.push(Widget::row(vec![
+            ctx.style()
+                .btn_solid_primary
+                .text("Save notes")
+                .build_def(ctx),
+            ctx.style().btn_outline.text("Switch list").build_def(ctx),
+        ]));
+
+        let panel = Panel::new_builder(Widget::col(rows))
+            .aligned(HorizontalAlignment::RightInset, VerticalAlignment::TopInset)
+            .build(ctx);
+
+        <dyn SimpleState<_>>::new_state(
+            panel,
+            Box::new(ShortlistOverlay {
+                options,
+                time_limit,
+                homes: homes.into_iter().map(|(b, _)| b).collect(),
+                draw: ctx.upload(batch),
+            }),
+        )
+    }
+
+    fn save_notes(&self, app: &App, panel: &Panel) {
+        let mut shortlist = Shortlist::load_current(app);
+        for (idx, b) in self.homes.iter().enumerate() {
+            let orig_id = app.map.get_b(*b).orig_id;
+            if let Some(c) = shortlist.homes.iter_mut().find(|c| c.bldg == orig_id) {
+                c.note = panel.text_box(&format!("note {}", idx));
+            }
+        }
+        shortlist.save(app);
+    }
+}
+
+impl SimpleState<App> for ShortlistOverlay {
+    fn on_click(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        x: &str,
+        panel: &Panel,
+    ) -> Transition<App> {
+        match x {
+            "close" => {
+                self.save_notes(app, panel);
+                Viewer::pop_and_rescore()
+            }
+            "Save notes" => {
+                self.save_notes(app, panel);
+                Transition::Keep
+            }
+            "Switch list" => {
+                self.save_notes(app, panel);
+                let mut choices = vec![Choice::new("start a new list", None)];
+                for name in Shortlist::all_names(app) {
+                    if name != app.session.shortlist {
+                        choices.push(Choice::new(name.clone(), Some(name)));
+                    }
+                }
+                let options = self.options.clone();
+                let time_limit = self.time_limit;
+                Transition::Push(ChooseSomething::new_state(
+                    ctx,
+                    "Which list of home candidates?",
+                    choices,
+                    Box::new(move |choice, ctx, app| match choice {
+                        Some(name) => {
+                            app.session.shortlist = name;
+                            Transition::Multi(vec![
+                                Transition::Pop,
+                                Transition::Replace(ShortlistOverlay::new_state(
+                                    ctx, app, options, time_limit,
+                                )),
+                            ])
+                        }
+                        None => Transition::Replace(PromptInput::new_state(
+                            ctx,
+                            "Name the new list",
+                            String::new(),
+                            Box::new(move |name, ctx, app| {
+                                if name.is_empty() {
+                                    return Transition::Pop;
+                                }
+                                // If the name maps to the same file as an existing list, just
+                                // switch to that one
+                                let shortlist = Shortlist::load(app, &name);
+                                app.session.shortlist = shortlist.name.clone();
+                                shortlist.save(app);
+                                Transition::Multi(vec![
+                                    Transition::Pop,
+                                    Transition::Replace(ShortlistOverlay::new_state(
+                                        ctx, app, options, time_limit,
+                                    )),
+                                ])
+                            }),
+                        )),
+                    }),
+                ))
+            }
+            x => {
+                if let Some(idx) = x.strip_prefix("remove ") {
+                    self.save_notes(app, panel);
+                    let b = self.homes[idx.parse::<usize>().unwrap()];
+                    let mut shortlist = Shortlist::load_current(app);
+                    shortlist.remove(app, b);
+                    shortlist.save(app);
+                    Transition::Replace(ShortlistOverlay::new_state(
+                        ctx,
+                        app,
+                        self.options.clone(),
+                        self.time_limit,
+                    ))
+                } else if let Some(idx) = x.strip_prefix("go to ") {
+                    self.save_notes(app, panel);
+                    let b = self.homes[idx.parse::<usize>().unwrap()];
+                    ctx.canvas
+                        .center_on_map_pt(app.map.get_b(b).polygon.center());
+                    Transition::Keep
+                } else {
+                    unreachable!()
+                }
+            }
+        }
+    }
+
+    fn other_event(&mut self, ctx: &mut EventCtx, _: &mut App) -> Transition<App> {
+        ctx.canvas_movement();
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        g.redraw(&self.draw);
+    }
+}
+
+/// The access score of one home, using the weights from the session
+fn score_home(
+    map: &Map,
+    session: &Session,
+    b: BuildingID,
+    options: Options,
+    time_limit: Duration,
+) -> usize {
+    let mut reachable = BTreeSet::new();
+    for other in options
+        .times_from(map, vec![Spot::Building(b)], time_limit)
+        .keys()
+    {
+        for amenity in &map.get_b(*other).amenities {
+            if !session.is_open(amenity) {
+                continue;
+            }
+            if let Some(category) = AmenityType::categorize(&amenity.amenity_type) {
+                reachable.insert(category);
+            }
+        }
+    }
+    session.weights.score(|at| reachable.contains(&at))
+}
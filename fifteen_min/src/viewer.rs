@@ -5,23 +5,30 @@
 //! See https://github.com/a-b-street/abstreet/issues/393 for more context.
 
 use abstutil::prettyprint_usize;
-use geom::{Distance, Duration};
+use geom::{Circle, Distance, Duration, QuadTree, Time};
 use map_gui::tools::{
-    draw_isochrone, open_browser, CityPicker, ColorLegend, Navigator, PopupMsg, URLManager,
+    draw_isochrone, open_browser, save_file, CityPicker, ColorLegend, Navigator, PopupMsg,
+    URLManager,
 };
 use map_gui::ID;
 use map_model::connectivity::WalkingOptions;
-use map_model::{AmenityType, Building, BuildingID, LaneType};
+use map_model::{AmenityType, Building, BuildingID, LaneType, Weekday};
 use std::str::FromStr;
-use widgetry::table::{Col, Filter, Table};
+use widgetry::table::{Col, Filter, Table, EXPORT_CSV};
 use widgetry::{
-    lctrl, Cached, Choice, Color, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key,
-    Line, Outcome, Panel, RewriteColor, State, Text, Toggle, Transition, VerticalAlignment, Widget,
+    lctrl, Cached, Choice, Color, ControlState, Drawable, EventCtx, GeomBatch, GfxCtx,
+    HorizontalAlignment, Key, Line, Outcome, Panel, RewriteColor, State, Text, TextExt, Toggle,
+    Transition, VerticalAlignment, Widget,
 };
 
+use crate::catchment::Catchment;
+use crate::export::export_isochrone;
 use crate::find_amenities::FindAmenity;
 use crate::find_home::FindHome;
-use crate::isochrone::{Isochrone, Options};
+use crate::isochrone::{time_limit_choices, Isochrone, Options};
+use crate::score::{describe_score, EditWeights};
+use crate::shortlist::{Shortlist, ShortlistOverlay};
+use crate::walkability::WalkabilityHeatmap;
 use crate::App;
 
 /// This is the UI state for exploring the isochrone/walkshed from a single building.
@@ -30,6 +37,7 @@ pub struct Viewer {
     highlight_start: Drawable,
     isochrone: Isochrone,
 
+    bldgs: QuadTree<BuildingID>,
     hovering_on_bldg: Cached<HoverKey, HoverOnBuilding>,
     // TODO Can't use Cached due to a double borrow
     hovering_on_category: Option<(AmenityType, Drawable)>,
@@ -55,21 +63,34 @@ impl Viewer {
         );
 
         let options = Options::Walking(WalkingOptions::default());
-        let start = app.map.get_b(start);
-        let isochrone = Isochrone::new(ctx, app, vec![start.id], options);
-        let highlight_start = draw_star(ctx, start);
-        let panel = build_panel(ctx, app, start, &isochrone);
+        let isochrone = Isochrone::new(ctx, app, vec![start], options, Duration::minutes(15));
+        let highlight_start = draw_stars(ctx, app, &isochrone.start);
+        let panel = build_panel(ctx, app, &isochrone);
         let draw_unwalkable_roads = draw_unwalkable_roads(ctx, app, &isochrone.options);
 
         Box::new(Viewer {
             panel,
             highlight_start: ctx.upload(highlight_start),
             isochrone,
+            bldgs: index_buildings(app, app.map.all_buildings().iter().map(|b| b.id)),
             hovering_on_bldg: Cached::new(),
             hovering_on_category: None,
             draw_unwalkable_roads,
         })
     }
+
+    /// Pop the current state and return to the viewer, whose access score may be stale if the
+    /// amenity weights changed.
+    pub fn pop_and_rescore() -> Transition<App> {
+        Transition::Multi(vec![
+            Transition::Pop,
+            Transition::ModifyState(Box::new(|state, ctx, app| {
+                if let Some(viewer) = state.downcast_mut::<Viewer>() {
+                    viewer.panel = build_panel(ctx, app, &viewer.isochrone);
+                }
+            })),
+        ])
+    }
 }
 
 impl State<App> for Viewer {
@@ -82,7 +103,7 @@ impl State<App> for Viewer {
         if ctx.redo_mouseover() {
             let isochrone = &self.isochrone;
             self.hovering_on_bldg
-                .update(HoverOnBuilding::key(ctx, app), |key| {
+                .update(HoverOnBuilding::key(ctx, app, &self.bldgs), |key| {
                     HoverOnBuilding::value(ctx, app, key, isochrone)
                 });
             // Also update this to conveniently get an outline drawn. Note we don't want to do this
@@ -117,12 +138,29 @@ impl State<App> for Viewer {
         // panel.event never sees clicks.
         if let Some((hover_id, _)) = self.hovering_on_bldg.key() {
             if ctx.normal_left_click() {
-                let start = app.map.get_b(hover_id);
-                self.isochrone =
-                    Isochrone::new(ctx, app, vec![start.id], self.isochrone.options.clone());
-                let star = draw_star(ctx, start);
-                self.highlight_start = ctx.upload(star);
-                self.panel = build_panel(ctx, app, start, &self.isochrone);
+                // Shift-click adds another starting point, or removes an existing one
+                let mut starts = if ctx.is_key_down(Key::LeftShift) {
+                    self.isochrone.start.clone()
+                } else {
+                    Vec::new()
+                };
+                if let Some(idx) = starts.iter().position(|b| *b == hover_id) {
+                    if starts.len() > 1 {
+                        starts.remove(idx);
+                    }
+                } else {
+                    starts.push(hover_id);
+                }
+                self.isochrone = Isochrone::new(
+                    ctx,
+                    app,
+                    starts,
+                    self.isochrone.options.clone(),
+                    self.isochrone.time_limit,
+                );
+                let stars = draw_stars(ctx, app, &self.isochrone.start);
+                self.highlight_start = ctx.upload(stars);
+                self.panel = build_panel(ctx, app, &self.isochrone);
                 // Any previous hover is from the perspective of the old `highlight_start`.
                 // Remove it so we don't have a dotted line to the previous isochrone's origin
                 self.hovering_on_bldg.clear();
@@ -174,14 +212,73 @@ impl State<App> for Viewer {
                     return Transition::Push(FindHome::new_state(
                         ctx,
                         self.isochrone.options.clone(),
+                        self.isochrone.time_limit,
                     ));
                 }
                 "Search by amenity" => {
                     return Transition::Push(FindAmenity::new_state(
                         ctx,
                         self.isochrone.options.clone(),
+                        self.isochrone.time_limit,
                     ));
                 }
+                "Adjust weights" => {
+                    return Transition::Push(EditWeights::new_state(
+                        ctx,
+                        app,
+                        reachable_categories(&self.isochrone),
+                    ));
+                }
+                "Who can reach a business?" => {
+                    return Transition::Push(Catchment::new_state(
+                        ctx,
+                        app,
+                        self.isochrone.options.clone(),
+                        self.isochrone.time_limit,
+                    ));
+                }
+                "Star this home" | "Unstar this home" => {
+                    let mut shortlist = Shortlist::load_current(app);
+                    let b = self.isochrone.start[0];
+                    if x == "Star this home" {
+                        shortlist.add(app, b);
+                    } else {
+                        shortlist.remove(app, b);
+                    }
+                    shortlist.save(app);
+                    self.panel = build_panel(ctx, app, &self.isochrone);
+                }
+                "Home candidates" => {
+                    return Transition::Push(ShortlistOverlay::new_state(
+                        ctx,
+                        app,
+                        self.isochrone.options.clone(),
+                        self.isochrone.time_limit,
+                    ));
+                }
+                "Walkability heatmap" => {
+                    return Transition::Push(WalkabilityHeatmap::new_state(
+                        ctx,
+                        app,
+                        self.isochrone.options.clone(),
+                        self.isochrone.time_limit,
+                    ));
+                }
+                "Export" => {
+                    return Transition::Push(match export_isochrone(app, &self.isochrone) {
+                        Ok(paths) => PopupMsg::new_state(
+                            ctx,
+                            "Data exported",
+                            paths
+                                .into_iter()
+                                .map(|path| format!("Data exported to {}", path))
+                                .collect(),
+                        ),
+                        Err(err) => {
+                            PopupMsg::new_state(ctx, "Export failed", vec![err.to_string()])
+                        }
+                    });
+                }
                 x => {
                     if let Some(category) = x.strip_prefix("businesses: ") {
                         return Transition::Push(ExploreAmenities::new_state(
@@ -197,14 +294,20 @@ impl State<App> for Viewer {
             },
             Outcome::Changed(_) => {
                 let options = options_from_controls(&self.panel);
-                self.draw_unwalkable_roads = draw_unwalkable_roads(ctx, app, &options);
-                self.isochrone = Isochrone::new(ctx, app, vec![self.isochrone.start[0]], options);
-                self.panel = build_panel(
-                    ctx,
-                    app,
-                    app.map.get_b(self.isochrone.start[0]),
-                    &self.isochrone,
-                );
+                let time_limit = self.panel.dropdown_value("time limit");
+                app.session.time_of_day = self.panel.dropdown_value("time of day");
+                app.session.day = self.panel.dropdown_value("day");
+                if options == self.isochrone.options && time_limit == self.isochrone.time_limit {
+                    // Only the opening hours changed, so the contours are still right
+                    self.isochrone.recount_amenities(app);
+                } else {
+                    if options != self.isochrone.options {
+                        self.draw_unwalkable_roads = draw_unwalkable_roads(ctx, app, &options);
+                    }
+                    self.isochrone =
+                        Isochrone::new(ctx, app, self.isochrone.start.clone(), options, time_limit);
+                }
+                self.panel = build_panel(ctx, app, &self.isochrone);
             }
             _ => {}
         }
@@ -241,12 +344,24 @@ fn options_to_controls(ctx: &mut EventCtx, opts: &Options) -> Widget {
     )];
     match opts {
         Options::Walking(ref opts) => {
+            rows.push(Toggle::switch(
+                ctx,
+                "Wheelchair: avoid stairs, steep grades, and raised curbs",
+                None,
+                opts.wheelchair,
+            ));
             rows.push(Toggle::switch(
                 ctx,
                 "Allow walking on the shoulder of the road without a sidewalk",
                 None,
                 opts.allow_shoulders,
             ));
+            rows.push(Toggle::switch(
+                ctx,
+                "Walk slower up steep hills",
+                None,
+                opts.consider_elevation,
+            ));
             rows.push(Widget::dropdown(
                 ctx,
                 "speed",
@@ -273,6 +388,12 @@ fn options_from_controls(panel: &Panel) -> Options {
             walking_speed: panel
                 .maybe_dropdown_value("speed")
                 .unwrap_or_else(WalkingOptions::default_speed),
+            consider_elevation: panel
+                .maybe_is_checked("Walk slower up steep hills")
+                .unwrap_or(true),
+            wheelchair: panel
+                .maybe_is_checked("Wheelchair: avoid stairs, steep grades, and raised curbs")
+                .unwrap_or(false),
         })
     } else {
         Options::Biking
@@ -285,14 +406,79 @@ pub fn draw_star(ctx: &mut EventCtx, b: &Building) -> GeomBatch {
         .color(RewriteColor::ChangeAll(Color::BLACK))
 }
 
-fn build_panel(ctx: &mut EventCtx, app: &App, start: &Building, isochrone: &Isochrone) -> Panel {
+fn draw_stars(ctx: &mut EventCtx, app: &App, starts: &[BuildingID]) -> GeomBatch {
+    let mut batch = GeomBatch::new();
+    for b in starts {
+        batch.append(draw_star(ctx, app.map.get_b(*b)));
+    }
+    batch
+}
+
+fn build_panel(ctx: &mut EventCtx, app: &App, isochrone: &Isochrone) -> Panel {
+    let starting_from = if isochrone.start.len() == 1 {
+        let starred = Shortlist::load_current(app).contains(app, isochrone.start[0]);
+        Widget::row(vec![
+            Text::from_all(vec![
+                Line("Starting from: ").secondary(),
+                Line(&app.map.get_b(isochrone.start[0]).address),
+            ])
+            .into_widget(ctx)
+            .centered_vert(),
+            ctx.style()
+                .btn_plain
+                .icon("system/assets/tools/star.svg")
+                .image_color(
+                    if starred {
+                        Color::YELLOW
+                    } else {
+                        ctx.style().btn_plain.fg
+                    },
+                    ControlState::Default,
+                )
+                .build_widget(
+                    ctx,
+                    if starred {
+                        "Unstar this home"
+                    } else {
+                        "Star this home"
+                    },
+                )
+                .align_right(),
+        ])
+    } else {
+        let mut txt = Text::from(Line("Starting from any of:").secondary());
+        for b in &isochrone.start {
+            txt.add_line(Line(&app.map.get_b(*b).address));
+        }
+        txt.into_widget(ctx)
+    };
+
     let mut rows = vec![
         map_gui::tools::app_header(ctx, app, "15-minute neighborhood explorer"),
+        starting_from,
         Text::from_all(vec![
-            Line("Starting from: ").secondary(),
-            Line(&start.address),
+            Key::LeftShift.txt(ctx),
+            Line(" + click to add or remove another starting point").secondary(),
         ])
         .into_widget(ctx),
+        Widget::row(vec![
+            Line(format!(
+                "Access score: {}",
+                describe_score(
+                    app.session
+                        .weights
+                        .score(|at| isochrone.amenities_reachable.borrow().contains_key(&at))
+                )
+            ))
+            .small_heading()
+            .into_widget(ctx)
+            .centered_vert(),
+            ctx.style()
+                .btn_outline
+                .text("Adjust weights")
+                .build_def(ctx)
+                .align_right(),
+        ]),
         Text::from_all(vec![
             Line("Estimated population: ").secondary(),
             Line(prettyprint_usize(isochrone.population)),
@@ -303,13 +489,43 @@ fn build_panel(ctx: &mut EventCtx, app: &App, start: &Building, isochrone: &Isoc
             Line(prettyprint_usize(isochrone.onstreet_parking_spots)),
         ])
         .into_widget(ctx),
+        Widget::row(vec![
+            "Time limit:".text_widget(ctx).centered_vert(),
+            Widget::dropdown(
+                ctx,
+                "time limit",
+                isochrone.time_limit,
+                time_limit_choices()
+                    .into_iter()
+                    .map(|x| Choice::new(x.to_string(&app.opts.units), x))
+                    .collect(),
+            ),
+        ]),
+        Widget::row(vec![
+            "Open at:".text_widget(ctx).centered_vert(),
+            Widget::dropdown(
+                ctx,
+                "time of day",
+                app.session.time_of_day,
+                time_of_day_choices(),
+            ),
+            Widget::dropdown(
+                ctx,
+                "day",
+                app.session.day,
+                Weekday::all()
+                    .into_iter()
+                    .map(|d| Choice::new(d.to_string(), d))
+                    .collect(),
+            ),
+        ]),
         ColorLegend::categories(
             ctx,
-            vec![
-                (Color::GREEN, "5 mins"),
-                (Color::ORANGE, "10 mins"),
-                (Color::RED, "15 mins"),
-            ],
+            isochrone
+                .legend_categories()
+                .iter()
+                .map(|(color, label)| (*color, label.as_str()))
+                .collect(),
         ),
     ];
 
@@ -338,6 +554,25 @@ fn build_panel(ctx: &mut EventCtx, app: &App, start: &Building, isochrone: &Isoc
             .text("Search by amenity")
             .build_def(ctx),
     );
+    rows.push(
+        ctx.style()
+            .btn_outline
+            .text("Who can reach a business?")
+            .build_def(ctx),
+    );
+    rows.push(
+        ctx.style()
+            .btn_outline
+            .text("Walkability heatmap")
+            .build_def(ctx),
+    );
+    rows.push(
+        ctx.style()
+            .btn_outline
+            .text("Home candidates")
+            .build_def(ctx),
+    );
+    rows.push(ctx.style().btn_outline.text("Export").build_def(ctx));
     rows.push(Widget::row(vec![
         ctx.style().btn_plain.text("About").build_def(ctx),
         ctx.style()
@@ -352,6 +587,47 @@ fn build_panel(ctx: &mut EventCtx, app: &App, start: &Building, isochrone: &Isoc
         .build(ctx)
 }
 
+fn time_of_day_choices() -> Vec<Choice<Option<Time>>> {
+    let mut choices = vec![Choice::new("any time", None)];
+    for hour in 0..24 {
+        let time = Time::START_OF_DAY + Duration::hours(hour);
+        choices.push(Choice::new(time.ampm_tostring(), Some(time)));
+    }
+    choices
+}
+
+fn reachable_categories(isochrone: &Isochrone) -> Vec<AmenityType> {
+    isochrone
+        .amenities_reachable
+        .borrow()
+        .keys()
+        .cloned()
+        .collect()
+}
+
+/// Index the buildings once, so hovering doesn't have to check everything else drawn on the map.
+pub fn index_buildings(app: &App, bldgs: impl Iterator<Item = BuildingID>) -> QuadTree<BuildingID> {
+    let mut quadtree = QuadTree::new(app.map.get_bounds());
+    for b in bldgs {
+        quadtree.insert(b, app.map.get_b(b).polygon.get_bounds());
+    }
+    quadtree
+}
+
+/// Finds the building under the cursor, out of the ones that were indexed.
+pub fn mouseover_building(
+    ctx: &EventCtx,
+    app: &App,
+    bldgs: &QuadTree<BuildingID>,
+) -> Option<BuildingID> {
+    let pt = ctx.canvas.get_cursor_in_map_space()?;
+    bldgs
+        .query(Circle::new(pt, Distance::meters(3.0)).get_bounds())
+        .into_iter()
+        .find(|b| app.map.get_b(**b).polygon.contains_pt(pt))
+        .copied()
+}
+
 pub struct HoverOnBuilding {
     pub tooltip: Text,
     pub drawn_route: Drawable,
@@ -360,14 +636,10 @@ pub struct HoverOnBuilding {
 pub type HoverKey = (BuildingID, f64);
 
 impl HoverOnBuilding {
-    pub fn key(ctx: &EventCtx, app: &App) -> Option<HoverKey> {
-        match app.mouseover_unzoomed_buildings(ctx) {
-            Some(ID::Building(b)) => {
-                let scale_factor = if ctx.canvas.is_zoomed() { 1.0 } else { 10.0 };
-                Some((b, scale_factor))
-            }
-            _ => None,
-        }
+    pub fn key(ctx: &EventCtx, app: &App, bldgs: &QuadTree<BuildingID>) -> Option<HoverKey> {
+        let b = mouseover_building(ctx, app, bldgs)?;
+        let scale_factor = if ctx.canvas.is_zoomed() { 1.0 } else { 10.0 };
+        Some((b, scale_factor))
     }
 
     pub fn value(
@@ -396,7 +668,7 @@ impl HoverOnBuilding {
             tooltip: if let Some(time) = isochrone.time_to_reach_building.get(&hover_id) {
                 Text::from(format!("{} away", time))
             } else {
-                Text::from("This is more than 15 minutes away")
+                Text::from(format!("This is more than {} away", isochrone.time_limit))
             },
             drawn_route: ctx.upload(batch),
         }
@@ -431,7 +703,7 @@ impl ExploreAmenities {
             &isochrone.thresholds,
             &isochrone.colors,
         );
-        batch.append(draw_star(ctx, app.map.get_b(isochrone.start[0])));
+        batch.append(draw_stars(ctx, app, &isochrone.start));
 
         let mut entries = Vec::new();
         for b in isochrone.amenities_reachable.get(category) {
@@ -461,26 +733,24 @@ impl ExploreAmenities {
             "Time to reach",
             Filter::empty(),
         );
-        table.column(
+        table.text_col(
             "Type",
-            Box::new(|ctx, _, x| Text::from(&x.amenity_type).render(ctx)),
+            Box::new(|_, x| x.amenity_type.clone()),
             Col::Sortable(Box::new(|rows| {
                 rows.sort_by_key(|x| x.amenity_type.clone())
             })),
         );
         table.static_col("Name", Box::new(|x| x.name.clone()));
         table.static_col("Address", Box::new(|x| x.address.clone()));
-        table.column(
+        table.text_col(
             "Time to reach",
-            Box::new(|ctx, app, x| {
-                Text::from(x.duration_away.to_string(&app.opts.units)).render(ctx)
-            }),
+            Box::new(|app, x| x.duration_away.to_string(&app.opts.units)),
             Col::Sortable(Box::new(|rows| rows.sort_by_key(|x| x.duration_away))),
         );
 
         let panel = Panel::new_builder(Widget::col(vec![
             Widget::row(vec![
-                Line(format!("{} within 15 minutes", category))
+                Line(format!("{} within {}", category, isochrone.time_limit))
                     .small_heading()
                     .into_widget(ctx),
                 ctx.style().btn_close_widget(ctx),
@@ -506,6 +776,18 @@ impl State<App> for ExploreAmenities {
             Outcome::Clicked(x) => {
                 if self.table.clicked(&x) {
                     self.table.replace_render(ctx, app, &mut self.panel)
+                } else if x == EXPORT_CSV {
+                    let result = save_file(&self.table.csv_filename(), self.table.to_csv(app));
+                    return Transition::Push(match result {
+                        Ok(path) => PopupMsg::new_state(
+                            ctx,
+                            "Data exported",
+                            vec![format!("Data exported to {}", path)],
+                        ),
+                        Err(err) => {
+                            PopupMsg::new_state(ctx, "Export failed", vec![err.to_string()])
+                        }
+                    });
                 } else if x == "close" {
                     return Transition::Pop;
                 } else if let Some(idx) = x.split(':').next().and_then(|x| x.parse::<usize>().ok())
@@ -541,8 +823,8 @@ impl State<App> for ExploreAmenities {
 }
 
 pub fn draw_unwalkable_roads(ctx: &mut EventCtx, app: &App, opts: &Options) -> Drawable {
-    let allow_shoulders = match opts {
-        Options::Walking(ref opts) => opts.allow_shoulders,
+    let opts = match opts {
+        Options::Walking(ref opts) => opts,
         Options::Biking => {
             return Drawable::empty(ctx);
         }
@@ -553,9 +835,13 @@ pub fn draw_unwalkable_roads(ctx: &mut EventCtx, app: &App, opts: &Options) -> D
         if road.is_light_rail() {
             continue;
         }
+        if opts.wheelchair && !road.is_wheelchair_accessible() {
+            batch.push(Color::BLUE.alpha(0.5), road.get_thick_polygon());
+            continue;
+        }
         for l in &road.lanes {
             if l.lane_type == LaneType::Sidewalk
-                || (l.lane_type == LaneType::Shoulder && allow_shoulders)
+                || (l.lane_type == LaneType::Shoulder && opts.allow_shoulders)
             {
                 continue 'ROADS;
             }
@@ -0,0 +1,218 @@
+use std::collections::{HashMap, HashSet};
+
+use abstutil::{prettyprint_usize, Timer};
+use geom::{Duration, Percent, QuadTree};
+use map_gui::tools::ColorLegend;
+use map_gui::ID;
+use map_model::connectivity::Spot;
+use map_model::{AmenityType, BuildingID};
+use widgetry::{
+    Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Line, Panel, SimpleState, State,
+    Text, TextExt, Transition, VerticalAlignment, Widget,
+};
+
+use crate::isochrone::Options;
+use crate::score::{describe_score, score_scale, Weights};
+use crate::viewer::{index_buildings, mouseover_building, Viewer};
+use crate::App;
+
+/// Score every residential building in the map by how many categories of amenities are reachable
+/// within a time limit, using the weights from the session, and draw the result as a heatmap.
+pub struct WalkabilityHeatmap {
+    /// For each category, which buildings can reach at least one matching amenity
+    reachable: HashMap<AmenityType, HashSet<BuildingID>>,
+    residences: Vec<BuildingID>,
+    /// Only residences are indexed, since nothing else has a score
+    bldgs: QuadTree<BuildingID>,
+    scores: HashMap<BuildingID, usize>,
+    draw: Drawable,
+    hovering: Option<(BuildingID, Text)>,
+}
+
+impl WalkabilityHeatmap {
+    pub fn new_state(
+        ctx: &mut EventCtx,
+        app: &App,
+        options: Options,
+        time_limit: Duration,
+    ) -> Box<dyn State<App>> {
+        let reachable = ctx.loading_screen("score every building", |_, timer| {
+            reachable_per_category(app, options, time_limit, timer)
+        });
+        let residences: Vec<BuildingID> = app
+            .map
+            .all_buildings()
+            .iter()
+            .filter(|b| b.bldg_type.has_residents())
+            .map(|b| b.id)
+            .collect();
+
+        let panel = Panel::new_builder(Widget::col(vec![
+            Widget::row(vec![
+                Line("Walkability heatmap").small_heading().into_widget(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            format!(
+                "{} residential buildings scored",
+                prettyprint_usize(residences.len())
+            )
+            .text_widget(ctx),
+            format!("Weight of each type of amenity within {}", time_limit).text_widget(ctx),
+            app.session.weights.to_controls(ctx),
+            ColorLegend::gradient(ctx, &score_scale(), vec!["0", "50", "100"]),
+        ]))
+        .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
+        .max_size(Percent::int(35), Percent::int(85))
+        .build(ctx);
+
+        let mut state = WalkabilityHeatmap {
+            reachable,
+            bldgs: index_buildings(app, residences.iter().cloned()),
+            residences,
+            scores: HashMap::new(),
+            draw: Drawable::empty(ctx),
+            hovering: None,
+        };
+        state.recalculate(ctx, app);
+        <dyn SimpleState<_>>::new_state(panel, Box::new(state))
+    }
+
+    fn recalculate(&mut self, ctx: &mut EventCtx, app: &App) {
+        let scale = score_scale();
+        let mut batch = GeomBatch::new();
+        self.scores.clear();
+        for b in &self.residences {
+            let score = score_building(&app.session.weights, &self.reachable, *b);
+            batch.push(
+                scale.eval(score as f64 / 100.0),
+                app.map.get_b(*b).polygon.clone(),
+            );
+            self.scores.insert(*b, score);
+        }
+        self.draw = ctx.upload(batch);
+    }
+}
+
+impl SimpleState<App> for WalkabilityHeatmap {
+    fn on_click(&mut self, _: &mut EventCtx, _: &mut App, x: &str, _: &Panel) -> Transition<App> {
+        match x {
+            "close" => Viewer::pop_and_rescore(),
+            _ => unreachable!(),
+        }
+    }
+
+    fn panel_changed(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        panel: &mut Panel,
+    ) -> Option<Transition<App>> {
+        app.session.weights = Weights::from_controls(ctx, panel);
+        self.recalculate(ctx, app);
+        None
+    }
+
+    fn on_mouseover(&mut self, ctx: &mut EventCtx, app: &mut App) {
+        self.hovering = mouseover_building(ctx, app, &self.bldgs).and_then(|b| {
+            self.scores.get(&b).map(|score| {
+                (
+                    b,
+                    Text::from(format!("Walkability score: {}", describe_score(*score))),
+                )
+            })
+        });
+        app.current_selection = self.hovering.as_ref().map(|(b, _)| ID::Building(*b));
+    }
+
+    fn other_event(&mut self, ctx: &mut EventCtx, _: &mut App) -> Transition<App> {
+        ctx.canvas_movement();
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        g.redraw(&self.draw);
+        if let Some((_, ref txt)) = self.hovering {
+            g.draw_mouse_tooltip(txt.clone());
+        }
+    }
+}
+
+/// A building gets credit for every category whose search reached it.
+fn score_building(
+    weights: &Weights,
+    reachable: &HashMap<AmenityType, HashSet<BuildingID>>,
+    b: BuildingID,
+) -> usize {
+    weights.score(|at| {
+        reachable
+            .get(&at)
+            .map(|bldgs| bldgs.contains(&b))
+            .unwrap_or(false)
+    })
+}
+
+/// Rather than calculating an isochrone from every building, search outwards from all amenities of
+/// each category at once. A building can reach the category if the search touches it.
+fn reachable_per_category(
+    app: &App,
+    options: Options,
+    time_limit: Duration,
+    timer: &mut Timer,
+) -> HashMap<AmenityType, HashSet<BuildingID>> {
+    let map = &app.map;
+    timer
+        .parallelize(
+            "find buildings close to each amenity",
+            AmenityType::all(),
+            |category| {
+                let mut stores = Vec::new();
+                for b in map.all_buildings() {
+                    if b.has_amenity(category) {
+                        stores.push(Spot::Building(b.id));
+                    }
+                }
+                let times: HashMap<BuildingID, Duration> =
+                    options.clone().times_from(map, stores, time_limit);
+                (category, times.into_keys().collect())
+            },
+        )
+        .into_iter()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_score_building() {
+        let (b1, b2, b3) = (BuildingID(1), BuildingID(2), BuildingID(3));
+        let mut reachable = HashMap::new();
+        reachable.insert(AmenityType::Supermarket, vec![b1, b2].into_iter().collect());
+        reachable.insert(AmenityType::Bank, vec![b1].into_iter().collect());
+        // No search reached anything for this category
+        reachable.insert(AmenityType::Library, HashSet::new());
+
+        // Every category counts equally by default, including ones that're missing entirely
+        let weights = Weights::default();
+        let num_categories = AmenityType::all().len() as f64;
+        assert_eq!(
+            score_building(&weights, &reachable, b1),
+            (200.0 / num_categories).round() as usize
+        );
+        assert_eq!(
+            score_building(&weights, &reachable, b2),
+            (100.0 / num_categories).round() as usize
+        );
+        assert_eq!(score_building(&weights, &reachable, b3), 0);
+
+        let weights = weights.only(&[AmenityType::Supermarket, AmenityType::Bank]);
+        assert_eq!(score_building(&weights, &reachable, b1), 100);
+        assert_eq!(score_building(&weights, &reachable, b2), 50);
+        assert_eq!(score_building(&weights, &reachable, b3), 0);
+
+        // Nothing matters
+        let weights = weights.only(&[]);
+        assert_eq!(score_building(&weights, &reachable, b1), 0);
+    }
+}
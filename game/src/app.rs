@@ -15,17 +15,18 @@ use map_gui::render::{unzoomed_agent_radius, AgentCache, DrawMap, DrawOptions, R
 use map_gui::tools::CameraState;
 use map_gui::ID;
 use map_model::AreaType;
-use map_model::{BufferType, IntersectionID, LaneType, Map, Traversable};
-use sim::{AgentID, Analytics, Scenario, Sim, SimCallback, SimFlags, VehicleType};
+use map_model::{BufferType, BuildingID, IntersectionID, LaneType, Map, Traversable};
+use sim::{
+    AgentID, Analytics, DayResults, PersonID, Scenario, Sim, SimCallback, SimFlags, VehicleType,
+};
 use widgetry::mapspace::ToggleZoomed;
 use widgetry::{Cached, Canvas, EventCtx, GfxCtx, Prerender, SharedAppState, State};
 
-use crate::challenges::HighScore;
 use crate::common::Warping;
 use crate::edit::apply_map_edits;
 use crate::layer::Layer;
 use crate::sandbox::dashboards::DashTab;
-use crate::sandbox::{GameplayMode, TutorialState};
+use crate::sandbox::TutorialState;
 
 // Convenient typedef
 pub type Transition = widgetry::Transition<App>;
@@ -84,6 +85,10 @@ impl App {
                     "- bus_arrivals : {} bytes",
                     prettyprint_usize(serialized_size_bytes(&a.bus_arrivals))
                 );
+                println!(
+                    "- bus_schedule_adherence : {} bytes",
+                    prettyprint_usize(serialized_size_bytes(&a.detailed.bus_schedule_adherence))
+                );
                 println!(
                     "- passengers_boarding: {} bytes",
                     prettyprint_usize(serialized_size_bytes(&a.passengers_boarding))
@@ -748,11 +753,17 @@ impl PerMap {
 // TODO Serialize these, but in a very careful, future-compatible way
 pub struct SessionState {
     pub tutorial: Option<TutorialState>,
-    pub high_scores: BTreeMap<GameplayMode, Vec<HighScore>>,
     pub info_panel_tab: BTreeMap<&'static str, &'static str>,
     pub last_gmns_timing_csv: Option<String>,
     pub dash_tab: DashTab,
     pub buffer_lane_type: LaneType,
+    pub ab_test: Option<crate::sandbox::dashboards::ABTestResults>,
+    /// Filled out when the current scenario learned from previous days
+    pub day_to_day: Option<Vec<DayResults>>,
+    pub show_live_stats: bool,
+    /// The people spending the night in each building. Keyed by the map, the simulation's run
+    /// name, and the total number of trips, since it only changes when people or trips are added.
+    pub households: Cached<(MapName, String, usize), BTreeMap<BuildingID, Vec<PersonID>>>,
 
     // Specific to the ungap tool
     pub elevation_contours: Cached<MapName, (FindClosest<Distance>, ToggleZoomed)>,
@@ -769,7 +780,6 @@ impl SessionState {
     pub fn empty() -> SessionState {
         SessionState {
             tutorial: None,
-            high_scores: BTreeMap::new(),
             info_panel_tab: btreemap! {
                 "lane" => "info",
                 "intersection" => "info",
@@ -780,6 +790,10 @@ impl SessionState {
             last_gmns_timing_csv: None,
             dash_tab: DashTab::TripTable,
             buffer_lane_type: LaneType::Buffer(BufferType::Stripes),
+            ab_test: None,
+            day_to_day: None,
+            show_live_stats: true,
+            households: Cached::new(),
 
             elevation_contours: Cached::new(),
             routing_preferences: crate::ungap::RoutingPreferences::default(),
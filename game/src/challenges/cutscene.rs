@@ -1,4 +1,8 @@
-use map_gui::tools::grey_out_map;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use abstutil::Timer;
+use map_gui::tools::{grey_out_map, PopupMsg};
 use widgetry::{
     hotkeys, ButtonStyle, Color, ControlState, EventCtx, GeomBatch, GfxCtx, Image, Key, Line,
     Outcome, Panel, State, Text, Widget,
@@ -6,16 +10,48 @@ use widgetry::{
 
 use crate::app::App;
 use crate::app::Transition;
+use crate::challenges::translations::Translations;
 
 pub struct CutsceneBuilder {
     name: String,
     scenes: Vec<Scene>,
+    /// Lines describing the task, shown before anything from the challenge
+    task: Vec<String>,
+}
+
+/// A cutscene stored in `data/system/assets/cutscenes/`, so it can be written or edited without
+/// recompiling.
+#[derive(Serialize, Deserialize)]
+pub struct CutsceneScript {
+    pub name: String,
+    pub scenes: Vec<SceneScript>,
+    /// Describes what the player has to do after the cutscene. Challenges with a task that
+    /// depends on the map or goal can leave this empty and describe the rest in code.
+    #[serde(default)]
+    pub task: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SceneScript {
+    pub speaker: Speaker,
+    pub text: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub enum Speaker {
+    Player,
+    Boss,
+    /// Another character, using an SVG from `system/assets/characters/`
+    Extra {
+        character: String,
+        scale: f64,
+    },
 }
 
 enum Layout {
     PlayerSpeaking,
     BossSpeaking,
-    Extra(&'static str, f64),
+    Extra(String, f64),
 }
 
 struct Scene {
@@ -28,9 +64,54 @@ impl CutsceneBuilder {
         CutsceneBuilder {
             name: name.to_string(),
             scenes: Vec::new(),
+            task: Vec::new(),
         }
     }
 
+    /// Loads `data/system/assets/cutscenes/{filename}.json`. If the script's task is empty,
+    /// `make_task` alone describes it. A translated script in a subdirectory named after the
+    /// player's language takes priority.
+    pub fn from_file(
+        ctx: &mut EventCtx,
+        app: &App,
+        filename: &str,
+        make_task: Box<dyn Fn(&mut EventCtx) -> Widget>,
+    ) -> Box<dyn State<App>> {
+        let translations = Translations::load(app);
+        match CutsceneBuilder::load(translations.language(), filename) {
+            Ok(builder) => builder.build(ctx, translations, make_task),
+            Err(err) => PopupMsg::new_state(
+                ctx,
+                "Error",
+                vec![format!("Couldn't load cutscene {}: {}", filename, err)],
+            ),
+        }
+    }
+
+    fn load(language: Option<&str>, filename: &str) -> Result<CutsceneBuilder> {
+        let mut path = abstio::path(format!("system/assets/cutscenes/{}.json", filename));
+        if let Some(lang) = language {
+            let translated = abstio::path(format!(
+                "system/assets/cutscenes/{}/{}.json",
+                lang, filename
+            ));
+            if abstio::file_exists(&translated) {
+                path = translated;
+            }
+        }
+        let script: CutsceneScript = abstio::maybe_read_json(path, &mut Timer::throwaway())?;
+        let mut builder = CutsceneBuilder::new(&script.name);
+        for scene in script.scenes {
+            builder = match scene.speaker {
+                Speaker::Player => builder.player(scene.text),
+                Speaker::Boss => builder.boss(scene.text),
+                Speaker::Extra { character, scale } => builder.extra(&character, scale, scene.text),
+            };
+        }
+        builder.task = script.task;
+        Ok(builder)
+    }
+
     fn fg_color() -> Color {
         ButtonStyle::outline_dark_fg().fg
     }
@@ -53,12 +134,12 @@ impl CutsceneBuilder {
 
     pub fn extra<I: Into<String>>(
         mut self,
-        character: &'static str,
+        character: &str,
         scale: f64,
         msg: I,
     ) -> CutsceneBuilder {
         self.scenes.push(Scene {
-            layout: Layout::Extra(character, scale),
+            layout: Layout::Extra(character.to_string(), scale),
             msg: Text::from(Line(msg).fg(Self::fg_color())),
         });
         self
@@ -67,14 +148,33 @@ impl CutsceneBuilder {
     pub fn build(
         self,
         ctx: &mut EventCtx,
+        translations: Translations,
         make_task: Box<dyn Fn(&mut EventCtx) -> Widget>,
     ) -> Box<dyn State<App>> {
+        let make_task: Box<dyn Fn(&mut EventCtx) -> Widget> = if self.task.is_empty() {
+            make_task
+        } else {
+            let task = self.task.clone();
+            Box::new(move |ctx| {
+                Widget::custom_col(vec![
+                    Text::from_multiline(
+                        task.iter()
+                            .map(|line| Line(line).fg(Color::BLACK))
+                            .collect(),
+                    )
+                    .into_widget(ctx)
+                    .margin_below(30),
+                    (make_task)(ctx),
+                ])
+            })
+        };
         Box::new(CutscenePlayer {
-            panel: make_panel(ctx, &self.name, &self.scenes, &make_task, 0),
+            panel: make_panel(ctx, &translations, &self.name, &self.scenes, &make_task, 0),
             name: self.name,
             scenes: self.scenes,
             idx: 0,
             make_task,
+            translations,
         })
     }
 }
@@ -85,6 +185,7 @@ struct CutscenePlayer {
     idx: usize,
     panel: Panel,
     make_task: Box<dyn Fn(&mut EventCtx) -> Widget>,
+    translations: Translations,
 }
 
 impl State<App> for CutscenePlayer {
@@ -99,18 +200,36 @@ impl State<App> for CutscenePlayer {
                 }
                 "back" => {
                     self.idx -= 1;
-                    self.panel =
-                        make_panel(ctx, &self.name, &self.scenes, &self.make_task, self.idx);
+                    self.panel = make_panel(
+                        ctx,
+                        &self.translations,
+                        &self.name,
+                        &self.scenes,
+                        &self.make_task,
+                        self.idx,
+                    );
                 }
                 "next" => {
                     self.idx += 1;
-                    self.panel =
-                        make_panel(ctx, &self.name, &self.scenes, &self.make_task, self.idx);
+                    self.panel = make_panel(
+                        ctx,
+                        &self.translations,
+                        &self.name,
+                        &self.scenes,
+                        &self.make_task,
+                        self.idx,
+                    );
                 }
                 "Skip cutscene" => {
                     self.idx = self.scenes.len();
-                    self.panel =
-                        make_panel(ctx, &self.name, &self.scenes, &self.make_task, self.idx);
+                    self.panel = make_panel(
+                        ctx,
+                        &self.translations,
+                        &self.name,
+                        &self.scenes,
+                        &self.make_task,
+                        self.idx,
+                    );
                 }
                 "Start" => {
                     return Transition::Pop;
@@ -120,7 +239,14 @@ impl State<App> for CutscenePlayer {
         }
         // TODO Should the Panel for text widgets with wrapping do this instead?
         if ctx.input.is_window_resized() {
-            self.panel = make_panel(ctx, &self.name, &self.scenes, &self.make_task, self.idx);
+            self.panel = make_panel(
+                ctx,
+                &self.translations,
+                &self.name,
+                &self.scenes,
+                &self.make_task,
+                self.idx,
+            );
         }
 
         Transition::Keep
@@ -133,6 +259,7 @@ impl State<App> for CutscenePlayer {
 
 fn make_panel(
     ctx: &mut EventCtx,
+    translations: &Translations,
     name: &str,
     scenes: &[Scene],
     make_task: &dyn Fn(&mut EventCtx) -> Widget,
@@ -157,15 +284,15 @@ fn make_panel(
             (make_task)(ctx),
             ctx.style()
                 .btn_solid_primary
-                .text("Start")
+                .text(translations.get("cutscene.start", "Start"))
                 .hotkey(Key::Enter)
-                .build_def(ctx)
+                .build_widget(ctx, "Start")
                 .centered_horiz()
                 .align_bottom(),
         ])
     } else {
         Widget::custom_col(vec![
-            match scenes[idx].layout {
+            match &scenes[idx].layout {
                 Layout::PlayerSpeaking => Widget::custom_row(vec![
                     GeomBatch::load_svg(ctx, "system/assets/characters/boss.svg.gz")
                         .scale(0.75)
@@ -208,7 +335,7 @@ fn make_panel(
                             ctx.prerender,
                             format!("system/assets/characters/{}", filename),
                         )
-                        .scale(scale)
+                        .scale(*scale)
                         .autocrop()
                         .into_widget(ctx),
                         scenes[idx]
@@ -227,8 +354,8 @@ fn make_panel(
             Widget::col(vec![
                 Widget::row(vec![prev.margin_right(40), next]).centered_horiz(),
                 ButtonStyle::outline_dark_fg()
-                    .text("Skip cutscene")
-                    .build_def(ctx)
+                    .text(translations.get("cutscene.skip", "Skip cutscene"))
+                    .build_widget(ctx, "Skip cutscene")
                     .centered_horiz(),
             ])
             .align_bottom(),
@@ -242,7 +369,7 @@ fn make_panel(
             // confusing here, since some people might press it to mean "skip cutscene." But maybe
             // some other sub-menus should use btn_back as well.
             ctx.style()
-                .btn_back("Home")
+                .btn_back(&translations.get("cutscene.home", "Home"))
                 .build_widget(ctx, "quit")
                 .align_right(),
         ])
@@ -262,16 +389,22 @@ pub struct ShowMessage {
 }
 
 impl ShowMessage {
-    pub fn new_state(ctx: &mut EventCtx, contents: Widget, bg: Color) -> Box<dyn State<App>> {
+    pub fn new_state(
+        ctx: &mut EventCtx,
+        app: &App,
+        contents: Widget,
+        bg: Color,
+    ) -> Box<dyn State<App>> {
+        let translations = Translations::load(app);
         Box::new(ShowMessage {
             panel: Panel::new_builder(
                 Widget::custom_col(vec![
                     contents,
                     ctx.style()
                         .btn_solid_primary
-                        .text("OK")
+                        .text(translations.get("message.ok", "OK"))
                         .hotkey(hotkeys(vec![Key::Escape, Key::Space, Key::Enter]))
-                        .build_def(ctx)
+                        .build_widget(ctx, "OK")
                         .centered_horiz()
                         .align_bottom(),
                 ])
@@ -0,0 +1,87 @@
+use widgetry::{EventCtx, Line, Panel, SimpleState, State, Text, Widget};
+
+use crate::app::{App, Transition};
+use crate::challenges::{Challenge, HighScore};
+use crate::edit::{apply_map_edits, EditMode};
+use crate::sandbox::{GameplayMode, SandboxMode};
+
+/// Lists every past attempt at one challenge, best first, and lets the player restart the
+/// challenge with the edits from any of them.
+pub struct ChallengeHistory {
+    mode: GameplayMode,
+    /// Matches the rows in the panel
+    attempts: Vec<HighScore>,
+}
+
+impl ChallengeHistory {
+    pub fn new_state(ctx: &mut EventCtx, challenge: &Challenge) -> Box<dyn State<App>> {
+        let attempts = HighScore::load_all(&challenge.alias);
+
+        let mut col = vec![Widget::row(vec![
+            Line(format!("Past attempts: {}", challenge.title))
+                .small_heading()
+                .into_widget(ctx),
+            ctx.style().btn_close_widget(ctx),
+        ])];
+        if let Some(best) = attempts.first() {
+            col.push(Line(format!("Goal: {}", best.goal)).into_widget(ctx));
+        }
+        for (idx, attempt) in attempts.iter().enumerate() {
+            let mut txt = Text::from(Line(format!("{}) {}", idx + 1, attempt.score)));
+            txt.add_line(
+                Line(format!(
+                    "{} edits in proposal \"{}\", finished {}",
+                    attempt.num_edits, attempt.edits_name, attempt.timestamp
+                ))
+                .secondary(),
+            );
+            col.push(Widget::row(vec![
+                txt.into_widget(ctx),
+                ctx.style()
+                    .btn_outline
+                    .text("Reload these edits")
+                    .disabled(attempt.num_edits == 0)
+                    .build_widget(ctx, format!("reload {}", idx))
+                    .align_right(),
+            ]));
+        }
+
+        let panel = Panel::new_builder(Widget::col(col)).build(ctx);
+        <dyn SimpleState<_>>::new_state(
+            panel,
+            Box::new(ChallengeHistory {
+                mode: challenge.gameplay.clone(),
+                attempts,
+            }),
+        )
+    }
+}
+
+impl SimpleState<App> for ChallengeHistory {
+    fn on_click(&mut self, _: &mut EventCtx, app: &mut App, x: &str, _: &Panel) -> Transition {
+        if x == "close" {
+            return Transition::Pop;
+        }
+        let idx = x.strip_prefix("reload ").unwrap().parse::<usize>().unwrap();
+        let perma = self.attempts[idx].edits.clone();
+        let mode = self.mode.clone();
+        // Start the challenge fresh, then apply the old edits once the map is loaded and let the
+        // player review them before running the simulation.
+        Transition::Multi(vec![
+            Transition::Pop,
+            Transition::Replace(SandboxMode::async_new(
+                app,
+                self.mode.clone(),
+                Box::new(move |ctx, app| {
+                    let edits = perma.into_edits_permissive(&app.primary.map);
+                    if mode.allows(&edits) {
+                        apply_map_edits(ctx, app, edits);
+                    } else {
+                        warn!("The edits from this attempt aren't allowed in this challenge");
+                    }
+                    vec![Transition::Push(EditMode::new_state(ctx, app, mode))]
+                }),
+            )),
+        ])
+    }
+}
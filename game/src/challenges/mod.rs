@@ -1,16 +1,24 @@
 use std::collections::BTreeMap;
 
+use serde::{Deserialize, Serialize};
+
+use abstutil::Timer;
 use geom::{Duration, Percent};
+use map_model::PermanentMapEdits;
 use sim::OrigPersonID;
 use widgetry::{EventCtx, Key, Line, Panel, SimpleState, State, Text, TextExt, Widget};
 
 use crate::app::App;
 use crate::app::Transition;
+use crate::challenges::history::ChallengeHistory;
+use crate::challenges::translations::Translations;
 use crate::sandbox::gameplay::Tutorial;
 use crate::sandbox::{GameplayMode, SandboxMode};
 
 pub mod cutscene;
+mod history;
 pub mod prebake;
+pub mod translations;
 
 // TODO Also have some kind of screenshot to display for each challenge
 pub struct Challenge {
@@ -21,23 +29,53 @@ pub struct Challenge {
     pub cutscene: Option<fn(&mut EventCtx, &App, &GameplayMode) -> Box<dyn State<App>>>,
 }
 
+/// One finished attempt at a challenge, saved as player data so the history survives restarts.
+#[derive(Serialize, Deserialize)]
 pub struct HighScore {
     // TODO This should be tied to the GameplayMode
     pub goal: String,
     // TODO Assuming we always want to maximize the score
     pub score: Duration,
     pub edits_name: String,
+    pub num_edits: usize,
+    /// The exact edits used, so the attempt can be reloaded even if the named proposal later
+    /// changes
+    pub edits: PermanentMapEdits,
+    /// When the attempt finished, in local time
+    pub timestamp: String,
 }
 
 impl HighScore {
-    pub fn record(self, app: &mut App, mode: GameplayMode) {
-        // TODO dedupe
-        // TODO mention placement
-        // TODO show all of em
-        let scores = app.session.high_scores.entry(mode).or_insert_with(Vec::new);
+    /// Describe an attempt that just finished, using the current map edits.
+    pub fn new(app: &App, goal: String, score: Duration) -> HighScore {
+        let edits = app.primary.map.get_edits();
+        HighScore {
+            goal,
+            score,
+            edits_name: edits.edits_name.clone(),
+            num_edits: edits.commands.len(),
+            edits: edits.to_permanent(&app.primary.map),
+            timestamp: chrono::Local::now().format("%Y-%m-%d %H:%M").to_string(),
+        }
+    }
+
+    pub fn record(self, mode: GameplayMode) {
+        let alias = Challenge::find(&mode).0.alias;
+        let mut scores = HighScore::load_all(&alias);
         scores.push(self);
         scores.sort_by_key(|s| s.score);
         scores.reverse();
+        abstio::write_json(HighScore::path(&alias), &scores);
+    }
+
+    /// All past attempts at one challenge, best first.
+    pub fn load_all(alias: &str) -> Vec<HighScore> {
+        abstio::maybe_read_json::<Vec<HighScore>>(HighScore::path(alias), &mut Timer::throwaway())
+            .unwrap_or_else(|_| Vec::new())
+    }
+
+    fn path(alias: &str) -> String {
+        abstio::path_player(format!("challenges/{}.json", alias))
     }
 }
 
@@ -126,34 +164,39 @@ impl ChallengesPicker {
         app: &App,
         challenge_and_stage: Option<(String, usize)>,
     ) -> Box<dyn State<App>> {
+        let tr = Translations::load(app);
         let mut links = BTreeMap::new();
         let mut master_col = vec![
             Widget::row(vec![
-                Line("Challenges").small_heading().into_widget(ctx),
+                Line(tr.get("challenges.heading", "Challenges"))
+                    .small_heading()
+                    .into_widget(ctx),
                 ctx.style().btn_close_widget(ctx),
             ]),
             ctx.style()
                 .btn_outline
-                .text("Introduction and tutorial")
-                .build_def(ctx)
+                .text(tr.get("challenges.tutorial", "Introduction and tutorial"))
+                .build_widget(ctx, "Introduction and tutorial")
                 .container()
                 .section(ctx),
         ];
 
         // First list challenges
         let mut flex_row = Vec::new();
-        for (idx, (name, _)) in Challenge::all().into_iter().enumerate() {
+        for (idx, (name, stages)) in Challenge::all().into_iter().enumerate() {
             let is_current_stage = challenge_and_stage
                 .as_ref()
                 .map(|(n, _)| n == &name)
                 .unwrap_or(false);
+            // Stages share a prefix, like commute/pt1
+            let group = stages[0].alias.split('/').next().unwrap();
             flex_row.push(
                 ctx.style()
                     .btn_outline
-                    .text(&name)
+                    .text(tr.get(&format!("challenges.{}", group), &name))
                     .disabled(is_current_stage)
                     .hotkey(Key::NUM_KEYS[idx])
-                    .build_def(ctx),
+                    .build_widget(ctx, &name),
             );
             links.insert(name.clone(), (name, 0));
         }
@@ -177,9 +220,9 @@ impl ChallengesPicker {
                 col.push(
                     ctx.style()
                         .btn_outline
-                        .text(&stage.title)
+                        .text(tr.get(&format!("challenges.{}.title", stage.alias), &stage.title))
                         .disabled(current == idx)
-                        .build_def(ctx),
+                        .build_widget(ctx, &stage.title),
                 );
                 links.insert(stage.title, (name.to_string(), idx));
             }
@@ -191,33 +234,37 @@ impl ChallengesPicker {
         if let Some((ref name, current)) = challenge_and_stage {
             let challenge = Challenge::all().remove(name).unwrap().remove(current);
             let mut txt = Text::new();
-            for l in &challenge.description {
-                txt.add_line(l);
+            for (idx, l) in challenge.description.iter().enumerate() {
+                txt.add_line(tr.get(
+                    &format!("challenges.{}.description.{}", challenge.alias, idx),
+                    l,
+                ));
             }
 
             let mut inner_col = vec![
                 txt.into_widget(ctx),
                 ctx.style()
                     .btn_outline
-                    .text("Start!")
+                    .text(tr.get("challenges.start", "Start!"))
                     .hotkey(Key::Enter)
-                    .build_def(ctx),
+                    .build_widget(ctx, "Start!"),
             ];
 
-            if let Some(scores) = app.session.high_scores.get(&challenge.gameplay) {
-                let mut txt = Text::from(format!("{} high scores:", scores.len()));
+            let scores = HighScore::load_all(&challenge.alias);
+            if scores.is_empty() {
+                inner_col.push(
+                    tr.get("challenges.no_attempts", "No attempts yet")
+                        .text_widget(ctx),
+                );
+            } else {
+                let mut txt = Text::from(format!("{} attempts", scores.len()));
                 txt.add_line(format!("Goal: {}", scores[0].goal));
-                let mut idx = 1;
-                for score in scores {
-                    txt.add_line(format!(
-                        "{}) {}, using proposal: {}",
-                        idx, score.score, score.edits_name
-                    ));
-                    idx += 1;
-                }
+                txt.add_line(format!(
+                    "Best: {}, using proposal: {}",
+                    scores[0].score, scores[0].edits_name
+                ));
                 inner_col.push(txt.into_widget(ctx));
-            } else {
-                inner_col.push("No attempts yet".text_widget(ctx));
+                inner_col.push(ctx.style().btn_outline.text("Past attempts").build_def(ctx));
             }
 
             main_row.push(Widget::col(inner_col).section(ctx));
@@ -242,6 +289,10 @@ impl SimpleState<App> for ChallengesPicker {
         match x {
             "close" => Transition::Pop,
             "Introduction and tutorial" => Transition::Replace(Tutorial::start(ctx, app)),
+            "Past attempts" => Transition::Push(ChallengeHistory::new_state(
+                ctx,
+                self.challenge.as_ref().unwrap(),
+            )),
             "Start!" => {
                 #[cfg(not(target_arch = "wasm32"))]
                 {
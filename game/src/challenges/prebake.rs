@@ -4,7 +4,7 @@ use abstio::MapName;
 use abstutil::{prettyprint_usize, Timer};
 use geom::{Duration, Time};
 use map_model::Map;
-use sim::{AlertHandler, Scenario, ScenarioGenerator, Sim, SimFlags, SimOptions};
+use sim::{AlertHandler, DemandProfile, Scenario, ScenarioGenerator, Sim, SimFlags, SimOptions};
 
 use crate::sandbox::TutorialState;
 
@@ -61,6 +61,7 @@ pub fn prebake_all() {
         );
         let scenario = ScenarioGenerator::proletariat_robot(
             &tehran_map,
+            &DemandProfile::default(),
             &mut SimFlags::for_test("prebaked").make_rng(),
             &mut timer,
         );
@@ -0,0 +1,105 @@
+use std::collections::BTreeMap;
+
+use abstutil::Timer;
+
+use crate::app::App;
+
+/// Text for challenges and cutscenes in the language chosen in the options. Translators add
+/// `data/system/assets/translations/{language}.json`, mapping keys to text. Anything missing falls
+/// back to the English text passed in by the caller.
+#[derive(Clone)]
+pub struct Translations {
+    language: Option<String>,
+    strings: BTreeMap<String, String>,
+}
+
+impl Translations {
+    pub fn load(app: &App) -> Translations {
+        let language = app.opts.ui_language.clone();
+        let strings = if let Some(ref lang) = language {
+            match abstio::maybe_read_json::<BTreeMap<String, String>>(
+                abstio::path(format!("system/assets/translations/{}.json", lang)),
+                &mut Timer::throwaway(),
+            ) {
+                Ok(strings) => strings,
+                Err(err) => {
+                    warn!("Couldn't load {} translations: {}", lang, err);
+                    BTreeMap::new()
+                }
+            }
+        } else {
+            BTreeMap::new()
+        };
+        Translations { language, strings }
+    }
+
+    /// None means English
+    pub fn language(&self) -> Option<&str> {
+        self.language.as_deref()
+    }
+
+    pub fn get(&self, key: &str, english: &str) -> String {
+        self.strings
+            .get(key)
+            .cloned()
+            .unwrap_or_else(|| english.to_string())
+    }
+
+    /// Like `get`, but fills in `{}` with a value
+    pub fn format<T: std::fmt::Display>(&self, key: &str, english: &str, value: T) -> String {
+        self.get(key, english).replace("{}", &value.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spanish() -> Translations {
+        Translations {
+            language: Some("es".to_string()),
+            strings: serde_json::from_str(include_str!(
+                "../../../data/system/assets/translations/es.json"
+            ))
+            .unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_format() {
+        let english = Translations {
+            language: None,
+            strings: BTreeMap::new(),
+        };
+        assert_eq!(
+            english.format("trafficsig.task.goal_2", "under {}", "5m"),
+            "under 5m"
+        );
+
+        let tr = spanish();
+        assert_eq!(
+            tr.format("trafficsig.task.goal_2", "under {}", "5m"),
+            "por debajo de 5m"
+        );
+        // Missing keys fall back to English
+        assert_eq!(tr.format("missing", "{} and {}", 1), "1 and 1");
+        assert_eq!(tr.get("missing", "English"), "English");
+    }
+
+    #[test]
+    fn test_placeholders_kept() {
+        let tr = spanish();
+        for key in [
+            "commute.task.goal_2",
+            "commute.task.summary",
+            "trafficsig.task.goal_2",
+            "trafficsig.task.summary",
+        ] {
+            assert!(
+                tr.strings[key].contains("{}"),
+                "The translation of {} is missing a {{}}",
+                key
+            );
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use map_gui::tools::grey_out_map;
+use widgetry::{
+    match_quality, Choice, EventCtx, GfxCtx, Line, Menu, MultiKey, Outcome, Panel, State, TextBox,
+    TextExt, Widget,
+};
+
+use crate::app::{App, Transition};
+
+/// How many matching commands to list at once
+const MAX_RESULTS: usize = 12;
+
+/// Something that can be run from the command palette. The transition is applied to the state
+/// that opened the palette.
+pub struct Command {
+    name: String,
+    key: Option<MultiKey>,
+    run: Box<dyn FnOnce(&mut EventCtx, &mut App) -> Transition>,
+}
+
+/// Each mode fills this out with every action that makes sense right now.
+#[derive(Default)]
+pub struct CommandRegistry {
+    commands: Vec<Command>,
+}
+
+impl CommandRegistry {
+    /// If the action also has a hotkey, pass it in so the palette can teach it.
+    pub fn add<F: 'static + FnOnce(&mut EventCtx, &mut App) -> Transition>(
+        &mut self,
+        name: impl Into<String>,
+        key: Option<MultiKey>,
+        run: F,
+    ) {
+        self.commands.push(Command {
+            name: name.into(),
+            key,
+            run: Box::new(run),
+        });
+    }
+}
+
+/// Type part of the name of any action, then run it.
+pub struct CommandPalette {
+    panel: Panel,
+    // Commands are taken out when they're run
+    commands: Vec<Option<Command>>,
+}
+
+impl CommandPalette {
+    pub fn new_state(ctx: &mut EventCtx, registry: CommandRegistry) -> Box<dyn State<App>> {
+        let commands: Vec<Option<Command>> = registry.commands.into_iter().map(Some).collect();
+        let menu = make_menu(ctx, &commands, "");
+        Box::new(CommandPalette {
+            panel: Panel::new_builder(Widget::col(vec![
+                Widget::row(vec![
+                    Line("Run a command").small_heading().into_widget(ctx),
+                    ctx.style().btn_close_widget(ctx),
+                ]),
+                TextBox::default_widget(ctx, "query", String::new()),
+                menu,
+            ]))
+            .build(ctx),
+            commands,
+        })
+    }
+}
+
+impl State<App> for CommandPalette {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => {
+                if x == "close" {
+                    return Transition::Pop;
+                }
+                let idx = self.panel.take_menu_choice::<usize>("menu");
+                let cmd = self.commands[idx].take().unwrap();
+                return Transition::Multi(vec![Transition::Pop, (cmd.run)(ctx, app)]);
+            }
+            Outcome::Changed(_) => {
+                let menu = make_menu(ctx, &self.commands, &self.panel.text_box("query"));
+                self.panel.replace(ctx, "menu", menu);
+            }
+            _ => {}
+        }
+
+        if self.panel.clicked_outside(ctx) {
+            return Transition::Pop;
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        grey_out_map(g, app);
+        self.panel.draw(g);
+    }
+}
+
+fn make_menu(ctx: &mut EventCtx, commands: &[Option<Command>], query: &str) -> Widget {
+    let mut matches = Vec::new();
+    for (idx, cmd) in commands.iter().enumerate() {
+        if let Some(cmd) = cmd {
+            if let Some(quality) = match_quality(&cmd.name, query) {
+                matches.push((quality, idx));
+            }
+        }
+    }
+    // Among equally good matches, prefer shorter names
+    matches.sort_by_key(|(quality, idx)| {
+        let name = &commands[*idx].as_ref().unwrap().name;
+        (*quality, name.len(), name.clone())
+    });
+    matches.truncate(MAX_RESULTS);
+
+    if matches.is_empty() {
+        return "No matching commands".text_widget(ctx).named("menu");
+    }
+    let choices = matches
+        .into_iter()
+        .map(|(_, idx)| {
+            let cmd = commands[idx].as_ref().unwrap();
+            // Don't bind the hotkey to the menu entry; typing in the query would trigger it
+            match cmd.key {
+                Some(ref key) => Choice::new(format!("{} ({})", cmd.name, key.describe()), idx),
+                None => Choice::new(&cmd.name, idx),
+            }
+        })
+        .collect();
+    Menu::widget(ctx, choices).named("menu")
+}
@@ -10,15 +10,19 @@ use widgetry::{
     ScreenPt, ScreenRectangle, Text, TextSpan, Toggle, VerticalAlignment, Widget,
 };
 
+pub use self::command_palette::{CommandPalette, CommandRegistry};
+pub use self::lasso::Lasso;
 pub use self::route_sketcher::RouteSketcher;
 pub use self::select::RoadSelector;
 pub use self::warp::{warp_to_id, Warping};
-pub use self::waypoints::{InputWaypoints, WaypointID};
+pub use self::waypoints::{get_waypoint_text, InputWaypoints, WaypointID};
 use crate::app::App;
 use crate::app::Transition;
 use crate::info::{ContextualActions, InfoPanel, Tab};
 use crate::sandbox::TimeWarpScreen;
 
+mod command_palette;
+mod lasso;
 mod route_sketcher;
 mod select;
 pub mod share;
@@ -301,6 +305,48 @@ impl CommonState {
         self.info_panel.as_ref().and_then(|i| i.active_id(app))
     }
 
+    /// Contribute the actions available from anywhere CommonState is used: the hotkeys in
+    /// `debug_actions`, and the actions for whatever's selected or open in the info panel.
+    pub fn register_commands<A: 'static + ContextualActions + Clone>(
+        &self,
+        app: &App,
+        registry: &mut CommandRegistry,
+        ctx_actions: &A,
+    ) {
+        registry.add(
+            if app.opts.dev {
+                "Turn off developer mode"
+            } else {
+                "Turn on developer mode"
+            },
+            Some(lctrl(Key::S)),
+            |_, app| {
+                app.opts.dev = !app.opts.dev;
+                Transition::Keep
+            },
+        );
+        registry.add("Warp to an object by ID", Some(lctrl(Key::J)), |ctx, _| {
+            Transition::Push(warp::DebugWarp::new_state(ctx))
+        });
+
+        let id = self
+            .info_panel_open(app)
+            .or_else(|| app.primary.current_selection.clone());
+        if let Some(id) = id {
+            for (key, action) in ctx_actions.actions(app, id.clone()) {
+                let mut ctx_actions = ctx_actions.clone();
+                let id = id.clone();
+                let mut name = action.clone();
+                if let Some(first) = name.get_mut(0..1) {
+                    first.make_ascii_uppercase();
+                }
+                registry.add(name, Some(key.into()), move |ctx, app| {
+                    ctx_actions.execute(ctx, app, id, action, &mut false)
+                });
+            }
+        }
+    }
+
     /// Allow toggling of dev mode and warping to an object by ID.
     pub fn debug_actions(ctx: &mut EventCtx, app: &mut App) -> Option<Transition> {
         if ctx.input.pressed(lctrl(Key::S)) {
@@ -357,12 +403,12 @@ pub fn cmp_duration_shorter(app: &App, after: Duration, before: Duration) -> Vec
         vec![Line("same")]
     } else if after < before {
         vec![
-            Line((before - after).to_string(&app.opts.units)).fg(Color::GREEN),
+            Line((before - after).to_string(&app.opts.units)).fg(app.cs.better),
             Line(" faster"),
         ]
     } else if after > before {
         vec![
-            Line((after - before).to_string(&app.opts.units)).fg(Color::RED),
+            Line((after - before).to_string(&app.opts.units)).fg(app.cs.worse),
             Line(" slower"),
         ]
     } else {
@@ -380,12 +426,12 @@ pub fn cmp_dist(txt: &mut Text, app: &App, dist: Distance, shorter: &str, longer
                     (-dist).to_string(&app.opts.units),
                     shorter
                 ))
-                .fg(Color::GREEN),
+                .fg(app.cs.better),
             );
         }
         Ordering::Greater => {
             txt.add_line(
-                Line(format!("{} {}", dist.to_string(&app.opts.units), longer)).fg(Color::RED),
+                Line(format!("{} {}", dist.to_string(&app.opts.units), longer)).fg(app.cs.worse),
             );
         }
         Ordering::Equal => {}
@@ -402,7 +448,7 @@ pub fn cmp_duration(txt: &mut Text, app: &App, duration: Duration, shorter: &str
                     (-duration).to_string(&app.opts.units),
                     shorter
                 ))
-                .fg(Color::GREEN),
+                .fg(app.cs.better),
             );
         }
         Ordering::Greater => {
@@ -412,7 +458,7 @@ pub fn cmp_duration(txt: &mut Text, app: &App, duration: Duration, shorter: &str
                     duration.to_string(&app.opts.units),
                     longer
                 ))
-                .fg(Color::RED),
+                .fg(app.cs.worse),
             );
         }
         Ordering::Equal => {}
@@ -447,7 +493,7 @@ pub fn color_for_trip_phase(app: &App, tpt: TripPhaseType) -> Color {
         TripPhaseType::WaitingForBus(_, _) => app.cs.bus_layer,
         TripPhaseType::RidingBus(_, _, _) => app.cs.bus_trip,
         TripPhaseType::Cancelled | TripPhaseType::Finished => unreachable!(),
-        TripPhaseType::DelayedStart => Color::YELLOW,
+        TripPhaseType::DelayedStart => app.cs.trip_delayed,
     }
 }
 
@@ -256,6 +256,6 @@ impl Waypoint {
     }
 }
 
-fn get_waypoint_text(idx: usize) -> char {
+pub fn get_waypoint_text(idx: usize) -> char {
     char::from_u32('A' as u32 + idx as u32).unwrap()
 }
@@ -1,6 +1,6 @@
 use std::collections::{BTreeMap, BTreeSet};
 
-use abstutil::Timer;
+use abstutil::{prettyprint_usize, Timer};
 use geom::Distance;
 use map_model::osm::RoadRank;
 use map_model::{Block, Perimeter};
@@ -267,12 +267,36 @@ impl OneBlock {
                 app.primary.map.get_r(*r).get_thick_polygon(),
             );
         }
+        let stats = block.calculate_stats(&app.primary.map);
+        for b in &stats.buildings {
+            batch.push(
+                Color::YELLOW.alpha(0.5),
+                app.primary.map.get_b(*b).polygon.clone(),
+            );
+        }
 
         let panel = Panel::new_builder(Widget::col(vec![
             Widget::row(vec![
                 Line("Blockfinder").small_heading().into_widget(ctx),
                 ctx.style().btn_close_widget(ctx),
             ]),
+            Text::from_multiline(vec![
+                Line(format!(
+                    "Area: {} square meters",
+                    prettyprint_usize(stats.area as usize)
+                )),
+                Line(format!(
+                    "{} buildings, {} residents",
+                    prettyprint_usize(stats.buildings.len()),
+                    prettyprint_usize(stats.population)
+                )),
+                Line(format!(
+                    "{} on-street and {} off-street parking spots",
+                    prettyprint_usize(stats.onstreet_parking),
+                    prettyprint_usize(stats.offstreet_parking)
+                )),
+            ])
+            .into_widget(ctx),
             ctx.style()
                 .btn_outline
                 .text("Show perimeter in order")
@@ -757,25 +757,27 @@ impl ContextualActions for Actions {
                 Transition::ModifyState(Box::new(move |state, ctx, app| {
                     if let Some(pt) = ctx.canvas.get_cursor_in_map_space() {
                         let map = &app.primary.map;
-                        let pl = &map.get_l(l).lane_center_pts;
-                        if let Some((dist, _)) = pl.dist_along_of_point(pl.project_pt(pt)) {
-                            let base_pos = Position::new(l, dist);
-                            let mut batch = GeomBatch::new();
-                            for l in &map.get_parent(l).lanes {
-                                let pt = base_pos.equiv_pos(l.id, map).pt(map);
-                                batch.push(
-                                    Color::RED,
-                                    Circle::new(pt, Distance::meters(1.0)).to_polygon(),
-                                );
-                            }
-                            let mut mode = state.downcast_mut::<DebugMode>().unwrap();
-                            // Just abuse this to display the results
-                            mode.search_results = Some(SearchResults {
-                                query: format!("equiv_pos {}", base_pos),
-                                num_matches: 0,
-                                draw: ctx.upload(batch),
-                            });
+                        let dist = map
+                            .get_l(l)
+                            .lane_center_pts
+                            .project_pt_detailed(pt)
+                            .dist_along;
+                        let base_pos = Position::new(l, dist);
+                        let mut batch = GeomBatch::new();
+                        for l in &map.get_parent(l).lanes {
+                            let pt = base_pos.equiv_pos(l.id, map).pt(map);
+                            batch.push(
+                                Color::RED,
+                                Circle::new(pt, Distance::meters(1.0)).to_polygon(),
+                            );
                         }
+                        let mut mode = state.downcast_mut::<DebugMode>().unwrap();
+                        // Just abuse this to display the results
+                        mode.search_results = Some(SearchResults {
+                            query: format!("equiv_pos {}", base_pos),
+                            num_matches: 0,
+                            draw: ctx.upload(batch),
+                        });
                     }
                 }))
             }
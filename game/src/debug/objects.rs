@@ -22,10 +22,8 @@ impl ObjectDebugger {
                 ));
                 if let Some(ID::Lane(l)) = app.primary.current_selection {
                     let lane = app.primary.map.get_l(l);
-                    let pl = &lane.lane_center_pts;
-                    if let Some((dist, _)) = pl.dist_along_of_point(pl.project_pt(pt)) {
-                        txt.add_line(Position::new(l, dist).to_string());
-                    }
+                    let dist = lane.lane_center_pts.project_pt_detailed(pt).dist_along;
+                    txt.add_line(Position::new(l, dist).to_string());
                     txt.add_line(format!(
                         "{:?}",
                         lane.get_nearest_side_of_road(&app.primary.map)
@@ -6,6 +6,7 @@ use geom::{LonLat, Percent};
 use map_gui::colors::ColorSchemeChoice;
 use map_gui::tools::{ChooseSomething, CityPicker};
 use map_gui::AppLike;
+use sim::Scenario;
 use widgetry::{Choice, EventCtx, Key, Line, Panel, SimpleState, State, Widget};
 
 use crate::app::{App, Transition};
@@ -15,6 +16,7 @@ mod destinations;
 pub mod kml;
 mod polygon;
 mod scenario;
+mod scenario_editor;
 mod story;
 
 pub struct DevToolsMode;
@@ -45,6 +47,7 @@ impl DevToolsMode {
                     .text("load scenario")
                     .hotkey(Key::W)
                     .build_def(ctx),
+                ctx.style().btn_outline.text("new scenario").build_def(ctx),
                 ctx.style()
                     .btn_outline
                     .text("view KML")
@@ -141,6 +144,11 @@ impl SimpleState<App> for DevToolsMode {
                     Transition::Replace(scenario::ScenarioManager::new_state(scenario, ctx, app))
                 }),
             )),
+            "new scenario" => Transition::Push(scenario_editor::ScenarioEditor::new_state(
+                ctx,
+                app,
+                Scenario::empty(&app.primary.map, "new scenario"),
+            )),
             "view KML" => Transition::Push(kml::ViewKML::new_state(ctx, app, None)),
             "story maps" => Transition::Push(story::StoryMapEditor::new_state(ctx, app)),
             "collisions" => Transition::Push(collisions::CollisionsViewer::new_state(ctx, app)),
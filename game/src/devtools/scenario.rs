@@ -10,6 +10,7 @@ use widgetry::{
 use crate::app::{App, Transition};
 use crate::common::CommonState;
 use crate::devtools::destinations::PopularDestinations;
+use crate::devtools::scenario_editor::ScenarioEditor;
 
 pub struct ScenarioManager {
     panel: Panel,
@@ -59,6 +60,11 @@ impl ScenarioManager {
                     .text("popular destinations")
                     .hotkey(Key::D)
                     .build_def(ctx),
+                ctx.style()
+                    .btn_outline
+                    .text("edit")
+                    .hotkey(Key::E)
+                    .build_def(ctx),
                 Text::from_multiline(vec![
                     Line(format!(
                         "{} people",
@@ -100,6 +106,13 @@ impl State<App> for ScenarioManager {
                         &self.scenario,
                     ));
                 }
+                "edit" => {
+                    return Transition::Push(ScenarioEditor::new_state(
+                        ctx,
+                        app,
+                        self.scenario.clone(),
+                    ));
+                }
                 _ => unreachable!(),
             }
         }
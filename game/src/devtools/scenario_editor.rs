@@ -0,0 +1,404 @@
+use geom::{ArrowCap, Distance, Duration, PolyLine, Time};
+use map_gui::tools::PopupMsg;
+use map_gui::ID;
+use sim::{IndividTrip, PersonSpec, Scenario, TripEndpoint, TripMode, TripPurpose};
+use widgetry::{
+    Choice, Color, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key, Line, Outcome,
+    Panel, Spinner, State, Text, TextBox, TextExt, VerticalAlignment, Widget,
+};
+
+use crate::app::{App, Transition};
+use crate::common::CommonState;
+
+/// Hand-craft people and their trips, then save the result as a new scenario. This is useful for
+/// building small, reproducible test cases.
+pub struct ScenarioEditor {
+    panel: Panel,
+    scenario: Scenario,
+    /// Index into the scenario's people
+    current: Option<usize>,
+    picking: Picking,
+    // Settings for the next trip, remembered when the panel is rebuilt
+    mode: TripMode,
+    depart: Duration,
+    draw: Drawable,
+}
+
+/// Adding a trip means clicking its origin, then its destination. A person's next trip has to
+/// start where the previous one ended, so only the destination is picked then.
+enum Picking {
+    Nothing,
+    Origin,
+    Destination(TripEndpoint),
+}
+
+impl ScenarioEditor {
+    pub fn new_state(ctx: &mut EventCtx, app: &App, scenario: Scenario) -> Box<dyn State<App>> {
+        let mut state = ScenarioEditor {
+            panel: Panel::empty(ctx),
+            current: if scenario.people.is_empty() {
+                None
+            } else {
+                Some(0)
+            },
+            scenario,
+            picking: Picking::Nothing,
+            mode: TripMode::Drive,
+            depart: Duration::hours(7),
+            draw: Drawable::empty(ctx),
+        };
+        state.recalculate(ctx, app);
+        Box::new(state)
+    }
+
+    fn recalculate(&mut self, ctx: &mut EventCtx, app: &App) {
+        let map = &app.primary.map;
+        // Don't lose a name that's been typed but not saved yet
+        if self.panel.has_widget("name") {
+            self.scenario.scenario_name = self.panel.text_box("name");
+        }
+
+        let mut col = vec![
+            Widget::row(vec![
+                Line("Scenario editor").small_heading().into_widget(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            Widget::row(vec![
+                "Name:".text_widget(ctx).centered_vert(),
+                TextBox::default_widget(ctx, "name", self.scenario.scenario_name.clone()),
+            ]),
+            format!("{} people", self.scenario.people.len()).text_widget(ctx),
+            Widget::row(vec![
+                ctx.style()
+                    .btn_prev()
+                    .disabled(self.current.unwrap_or(0) == 0)
+                    .hotkey(Key::LeftArrow)
+                    .build_widget(ctx, "previous person"),
+                match self.current {
+                    Some(idx) => format!("Person {}/{}", idx + 1, self.scenario.people.len()),
+                    None => "No person selected".to_string(),
+                }
+                .text_widget(ctx)
+                .centered_vert(),
+                ctx.style()
+                    .btn_next()
+                    .disabled(
+                        self.current.map(|idx| idx + 1).unwrap_or(0) >= self.scenario.people.len(),
+                    )
+                    .hotkey(Key::RightArrow)
+                    .build_widget(ctx, "next person"),
+            ])
+            .evenly_spaced(),
+            Widget::row(vec![
+                ctx.style()
+                    .btn_outline
+                    .text("new person")
+                    .hotkey(Key::N)
+                    .build_def(ctx),
+                ctx.style()
+                    .btn_solid_destructive
+                    .text("delete person")
+                    .disabled(self.current.is_none())
+                    .build_def(ctx),
+            ]),
+        ];
+
+        let mut batch = GeomBatch::new();
+        if let Some(idx) = self.current {
+            let trips = &self.scenario.people[idx].trips;
+            let mut txt = Text::new();
+            if trips.is_empty() {
+                txt.add_line("No trips yet");
+            }
+            for (num, trip) in trips.iter().enumerate() {
+                txt.add_line(format!(
+                    "Trip {}: {} at {}",
+                    num + 1,
+                    trip.mode.ongoing_verb(),
+                    trip.depart.ampm_tostring()
+                ));
+                txt.add_line(
+                    Line(format!(
+                        "  from {} to {}",
+                        describe(app, trip.origin),
+                        describe(app, trip.destination)
+                    ))
+                    .secondary(),
+                );
+
+                let from = trip.origin.pt(map);
+                let to = trip.destination.pt(map);
+                if let Ok(pl) = PolyLine::new(vec![from, to]) {
+                    batch.push(
+                        Color::PURPLE.alpha(0.8),
+                        pl.make_arrow(Distance::meters(5.0), ArrowCap::Triangle),
+                    );
+                }
+                batch.append(map_gui::tools::start_marker(ctx, from, 2.0));
+                batch.append(map_gui::tools::goal_marker(ctx, to, 2.0));
+            }
+            col.push(
+                Widget::col(vec![
+                    txt.into_widget(ctx),
+                    ctx.style()
+                        .btn_solid_destructive
+                        .text("delete last trip")
+                        .disabled(trips.is_empty())
+                        .build_def(ctx),
+                ])
+                .section(ctx),
+            );
+        }
+        if let Picking::Destination(from) = self.picking {
+            batch.append(map_gui::tools::start_marker(ctx, from.pt(map), 2.0));
+        }
+        self.draw = ctx.upload(batch);
+
+        col.push(
+            Widget::col(vec![
+                Line("New trip").small_heading().into_widget(ctx),
+                Widget::row(vec![
+                    "Mode:".text_widget(ctx).centered_vert(),
+                    Widget::dropdown(
+                        ctx,
+                        "mode",
+                        self.mode,
+                        TripMode::all()
+                            .into_iter()
+                            .map(|m| Choice::new(m.ongoing_verb(), m))
+                            .collect(),
+                    ),
+                ]),
+                Widget::row(vec![
+                    "Departure:".text_widget(ctx).centered_vert(),
+                    Spinner::widget_with_custom_rendering(
+                        ctx,
+                        "departure",
+                        (Duration::ZERO, Duration::hours(24)),
+                        self.depart,
+                        Duration::minutes(15),
+                        Box::new(|d| (Time::START_OF_DAY + d).ampm_tostring()),
+                    ),
+                ]),
+                match self.picking {
+                    Picking::Nothing => ctx
+                        .style()
+                        .btn_outline
+                        .text("add trip")
+                        .hotkey(Key::T)
+                        .disabled(self.current.is_none())
+                        .build_def(ctx),
+                    Picking::Origin => {
+                        "Click a building or border to start the trip".text_widget(ctx)
+                    }
+                    Picking::Destination(_) => {
+                        "Click a building or border to end the trip".text_widget(ctx)
+                    }
+                },
+            ])
+            .section(ctx),
+        );
+
+        col.push(
+            ctx.style()
+                .btn_solid_primary
+                .text("save")
+                .disabled(self.scenario.people.is_empty())
+                .build_def(ctx),
+        );
+
+        self.panel = Panel::new_builder(Widget::col(col))
+            .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
+            .build(ctx);
+    }
+
+    /// Returns an error message if the trip can't be added.
+    fn add_trip(&mut self, app: &App, from: TripEndpoint, to: TripEndpoint) -> Option<String> {
+        let map = &app.primary.map;
+        if from == to {
+            return Some("A trip has to go somewhere different".to_string());
+        }
+        let depart = Time::START_OF_DAY + self.depart;
+        let trips = &mut self.scenario.people[self.current.unwrap()].trips;
+        if let Some(prev) = trips.last() {
+            if depart <= prev.depart {
+                return Some(format!(
+                    "This trip has to depart after the previous one, at {}",
+                    prev.depart.ampm_tostring()
+                ));
+            }
+        }
+        if TripEndpoint::path_req(from, to, self.mode, map)
+            .and_then(|req| map.pathfind(req).ok())
+            .is_none()
+        {
+            return Some(format!(
+                "There's no path {} from {} to {}",
+                self.mode.ongoing_verb(),
+                describe(app, from),
+                describe(app, to)
+            ));
+        }
+        trips.push(IndividTrip::new(
+            depart,
+            TripPurpose::Shopping,
+            from,
+            to,
+            self.mode,
+        ));
+        None
+    }
+
+    fn save(&mut self, app: &App) -> Result<String, String> {
+        let name = self.panel.text_box("name");
+        if name.is_empty() {
+            return Err("The scenario needs a name".to_string());
+        }
+        for (idx, person) in self.scenario.people.iter().enumerate() {
+            if let Err(err) = person.check_schedule() {
+                return Err(format!("Person {}: {}", idx + 1, err));
+            }
+        }
+        self.scenario.scenario_name = name;
+        self.scenario.save();
+        Ok(abstio::path_scenario(
+            app.primary.map.get_name(),
+            &self.scenario.scenario_name,
+        ))
+    }
+}
+
+impl State<App> for ScenarioEditor {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => {
+                match x.as_ref() {
+                    "close" => {
+                        return Transition::Pop;
+                    }
+                    "previous person" => {
+                        self.current = self.current.map(|idx| idx - 1);
+                    }
+                    "next person" => {
+                        self.current = self.current.map(|idx| idx + 1);
+                    }
+                    "new person" => {
+                        self.scenario.people.push(PersonSpec {
+                            orig_id: None,
+                            trips: Vec::new(),
+                        });
+                        self.current = Some(self.scenario.people.len() - 1);
+                    }
+                    "delete person" => {
+                        let idx = self.current.unwrap();
+                        self.scenario.people.remove(idx);
+                        self.current = if self.scenario.people.is_empty() {
+                            None
+                        } else {
+                            Some(idx.min(self.scenario.people.len() - 1))
+                        };
+                    }
+                    "delete last trip" => {
+                        self.scenario.people[self.current.unwrap()].trips.pop();
+                    }
+                    "add trip" => {
+                        self.picking =
+                            match self.scenario.people[self.current.unwrap()].trips.last() {
+                                Some(prev) => Picking::Destination(prev.destination),
+                                None => Picking::Origin,
+                            };
+                    }
+                    "save" => {
+                        return Transition::Push(match self.save(app) {
+                            Ok(path) => PopupMsg::new_state(
+                                ctx,
+                                "Scenario saved",
+                                vec![format!("Saved to {}", path)],
+                            ),
+                            Err(err) => PopupMsg::new_state(ctx, "Can't save", vec![err]),
+                        });
+                    }
+                    _ => unreachable!(),
+                }
+                // The person changed, so stop adding a trip for the previous one
+                if x != "add trip" {
+                    self.picking = Picking::Nothing;
+                }
+                self.recalculate(ctx, app);
+            }
+            Outcome::Changed(_) => {
+                self.mode = self.panel.dropdown_value("mode");
+                self.depart = self.panel.spinner("departure");
+            }
+            _ => {}
+        }
+
+        ctx.canvas_movement();
+
+        if matches!(self.picking, Picking::Nothing) {
+            return Transition::Keep;
+        }
+        if ctx.input.pressed(Key::Escape) {
+            self.picking = Picking::Nothing;
+            self.recalculate(ctx, app);
+            return Transition::Keep;
+        }
+
+        if ctx.redo_mouseover() {
+            app.primary.current_selection = app.mouseover_unzoomed_everything(ctx);
+            if match app.primary.current_selection {
+                Some(ID::Intersection(i)) => !app.primary.map.get_i(i).is_border(),
+                Some(ID::Building(_)) => false,
+                _ => true,
+            } {
+                app.primary.current_selection = None;
+            }
+        }
+        let hovering = match app.primary.current_selection {
+            Some(ID::Building(b)) => TripEndpoint::Bldg(b),
+            Some(ID::Intersection(i)) => TripEndpoint::Border(i),
+            _ => {
+                return Transition::Keep;
+            }
+        };
+        match self.picking {
+            Picking::Nothing => unreachable!(),
+            Picking::Origin => {
+                if app.per_obj.left_click(ctx, "start the trip here") {
+                    self.picking = Picking::Destination(hovering);
+                    self.recalculate(ctx, app);
+                }
+            }
+            Picking::Destination(from) => {
+                if app.per_obj.left_click(ctx, "end the trip here") {
+                    self.picking = Picking::Nothing;
+                    let err = self.add_trip(app, from, hovering);
+                    self.recalculate(ctx, app);
+                    if let Some(err) = err {
+                        return Transition::Push(PopupMsg::new_state(
+                            ctx,
+                            "Can't add trip",
+                            vec![err],
+                        ));
+                    }
+                }
+            }
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        g.redraw(&self.draw);
+        self.panel.draw(g);
+        CommonState::draw_osd(g, app);
+    }
+}
+
+fn describe(app: &App, endpt: TripEndpoint) -> String {
+    match endpt {
+        TripEndpoint::Bldg(b) => app.primary.map.get_b(b).address.clone(),
+        TripEndpoint::Border(i) => format!("border {}", i.0),
+        TripEndpoint::SuddenlyAppear(pos) => format!("{}", pos.lane()),
+    }
+}
@@ -15,6 +15,7 @@ use widgetry::{
 
 pub use self::roads::RoadEditor;
 pub use self::routes::RouteEditor;
+use self::speed_limits::BulkSpeedLimits;
 pub use self::stop_signs::StopSignEditor;
 pub use self::traffic_signals::TrafficSignalEditor;
 pub use self::validate::{check_blackholes, check_sidewalk_connectivity};
@@ -27,6 +28,7 @@ mod heuristics;
 mod multiple_roads;
 mod roads;
 mod routes;
+mod speed_limits;
 mod stop_signs;
 mod traffic_signals;
 mod validate;
@@ -56,7 +58,7 @@ impl EditMode {
         Box::new(EditMode {
             tool_panel: tool_panel(ctx),
             top_center: make_topcenter(ctx, app),
-            changelist: make_changelist(ctx, app),
+            changelist: make_changelist(ctx, app, &mode),
             orig_edits: app.primary.map.get_edits().clone(),
             orig_dirty,
             mode,
@@ -149,7 +151,7 @@ impl State<App> for EditMode {
             let key = app.primary.map.get_edits_change_key();
             if self.map_edit_key != key {
                 self.map_edit_key = key;
-                self.changelist = make_changelist(ctx, app);
+                self.changelist = make_changelist(ctx, app, &self.mode);
                 let layer = crate::layer::map::Static::edits(ctx, app);
                 self.draw = layer.draw;
             }
@@ -293,6 +295,9 @@ impl State<App> for EditMode {
                     ));
                 }
                 "load proposal" => {}
+                "bulk speed limits" => {
+                    return Transition::Push(BulkSpeedLimits::new_state(ctx, app));
+                }
                 "undo" => {
                     let mut edits = app.primary.map.get_edits().clone();
                     let maybe_id = cmd_to_id(&edits.commands.pop().unwrap());
@@ -782,10 +787,17 @@ pub fn can_edit_lane(app: &App, l: LaneID) -> bool {
 
 pub fn speed_limit_choices(app: &App, preset: Option<Speed>) -> Vec<Choice<Speed>> {
     // Don't need anything higher than 70mph. Though now I kind of miss 3am drives on TX-71...
-    let mut speeds = (10..=70)
-        .step_by(5)
-        .map(|mph| Speed::miles_per_hour(mph as f64))
-        .collect::<Vec<_>>();
+    let mut speeds = if app.opts.units.metric {
+        (10..=110)
+            .step_by(10)
+            .map(|kmph| Speed::km_per_hour(kmph as f64))
+            .collect::<Vec<_>>()
+    } else {
+        (10..=70)
+            .step_by(5)
+            .map(|mph| Speed::miles_per_hour(mph as f64))
+            .collect::<Vec<_>>()
+    };
     if let Some(preset) = preset {
         if !speeds.contains(&preset) {
             speeds.push(preset);
@@ -839,7 +851,7 @@ pub fn maybe_edit_intersection(
     None
 }
 
-fn make_changelist(ctx: &mut EventCtx, app: &App) -> Panel {
+fn make_changelist(ctx: &mut EventCtx, app: &App, mode: &GameplayMode) -> Panel {
     // TODO Support redo. Bit harder here to reset the redo_stack when the edits
     // change, because nested other places modify it too.
     let edits = app.primary.map.get_edits();
@@ -856,6 +868,11 @@ fn make_changelist(ctx: &mut EventCtx, app: &App) -> Panel {
                 .padding(10)
                 .bg(Color::hex("#5D9630")),
         ]),
+        ctx.style()
+            .btn_outline
+            .text("Change speed limits in bulk")
+            .disabled(!mode.can_edit_roads())
+            .build_widget(ctx, "bulk speed limits"),
         ColorLegend::row(
             ctx,
             app.cs.edits_layer,
@@ -332,6 +332,32 @@ impl State<App> for RoadEditor {
                     self.redo_stack.clear();
 
                     self.selected_lane = Some(self.lane_for_idx(app, idx));
+                    self.recalc_hovering(ctx, app);
+                    panels_need_recalc = true;
+                } else if x == "reverse direction" || x == "make one-way" {
+                    let mut edits = app.primary.map.get_edits().clone();
+                    let old = app.primary.map.get_r_edit(self.r);
+                    let mut new = old.clone();
+                    let num_lanes = new.lanes_ltr.len();
+                    if x == "reverse direction" {
+                        new.reverse_direction();
+                        // The lanes are mirrored, so keep the same one selected
+                        self.selected_lane = self
+                            .selected_lane
+                            .map(|l| self.lane_for_idx(app, num_lanes - 1 - l.offset));
+                    } else {
+                        // Keep traffic flowing the way the road's drawn. The player can reverse it
+                        // afterwards.
+                        new.convert_to_oneway(Direction::Fwd);
+                    }
+                    edits.commands.push(EditCmd::ChangeRoad {
+                        r: self.r,
+                        old,
+                        new,
+                    });
+                    apply_map_edits(ctx, app, edits);
+                    self.redo_stack.clear();
+
                     self.recalc_hovering(ctx, app);
                     panels_need_recalc = true;
                 } else if x == "Access restrictions" {
@@ -897,6 +923,18 @@ fn make_main_panel(
             .text("Access restrictions")
             .build_def(ctx)
             .centered_vert(),
+        ctx.style()
+            .btn_outline
+            .text("reverse direction")
+            .build_def(ctx)
+            .centered_vert(),
+        ctx.style()
+            .btn_outline
+            .text("make one-way")
+            .disabled(map.get_r_edit(road.id).oneway_for_driving().is_some())
+            .disabled_tooltip("This road is already one-way for driving")
+            .build_def(ctx)
+            .centered_vert(),
     ]);
 
     Panel::new_builder(
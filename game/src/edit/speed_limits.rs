@@ -0,0 +1,225 @@
+//! Change the speed limit of every road with one OSM highway classification at once, to test
+//! city-wide policies like lowering all residential streets to 30 km/h.
+
+use abstutil::{prettyprint_usize, Counter};
+use geom::{Distance, Polygon, Speed};
+use map_model::{osm, EditCmd};
+use widgetry::{
+    Choice, Color, DrawBaselayer, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key,
+    Line, Outcome, Panel, State, Text, TextExt, VerticalAlignment, Widget,
+};
+
+use crate::app::{App, Transition};
+use crate::common::Lasso;
+use crate::edit::{apply_map_edits, speed_limit_choices};
+
+pub struct BulkSpeedLimits {
+    panel: Panel,
+    /// Only change roads inside this area. None means the whole map.
+    boundary: Option<Polygon>,
+    cmds: Vec<EditCmd>,
+    draw: Drawable,
+}
+
+impl BulkSpeedLimits {
+    pub fn new_state(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        let mut classes = Counter::new();
+        for r in app.primary.map.all_roads() {
+            if let Some(hwy) = r.osm_tags.get(osm::HIGHWAY) {
+                classes.inc(hwy.clone());
+            }
+        }
+        let mut classes = classes.consume().into_iter().collect::<Vec<_>>();
+        classes.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+        let default_class = if classes.iter().any(|(hwy, _)| hwy == "residential") {
+            "residential".to_string()
+        } else {
+            classes[0].0.clone()
+        };
+
+        let default_speed = if app.opts.units.metric {
+            Speed::km_per_hour(30.0)
+        } else {
+            Speed::miles_per_hour(20.0)
+        };
+
+        let panel = Panel::new_builder(Widget::col(vec![
+            Widget::row(vec![
+                Line("Change speed limits in bulk")
+                    .small_heading()
+                    .into_widget(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            Widget::row(vec![
+                "Set every".text_widget(ctx).centered_vert(),
+                Widget::dropdown(
+                    ctx,
+                    "highway",
+                    default_class,
+                    classes
+                        .into_iter()
+                        .map(|(hwy, count)| {
+                            Choice::new(format!("{} ({})", hwy, prettyprint_usize(count)), hwy)
+                        })
+                        .collect(),
+                ),
+                "road to".text_widget(ctx).centered_vert(),
+                Widget::dropdown(
+                    ctx,
+                    "speed limit",
+                    default_speed,
+                    speed_limit_choices(app, Some(default_speed)),
+                ),
+            ]),
+            Widget::row(vec![
+                "".text_widget(ctx).named("boundary").centered_vert(),
+                ctx.style().btn_outline.text("Draw an area").build_def(ctx),
+                ctx.style()
+                    .btn_outline
+                    .text("Use the whole map")
+                    .build_def(ctx),
+            ]),
+            "".text_widget(ctx).named("summary"),
+            Widget::custom_row(vec![
+                ctx.style()
+                    .btn_solid_primary
+                    .text("Apply")
+                    .hotkey(Key::Enter)
+                    .build_def(ctx),
+                ctx.style()
+                    .btn_solid_destructive
+                    .text("Cancel")
+                    .hotkey(Key::Escape)
+                    .build_def(ctx),
+            ])
+            .evenly_spaced(),
+        ]))
+        .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
+        .build(ctx);
+
+        let mut state = BulkSpeedLimits {
+            panel,
+            boundary: None,
+            cmds: Vec::new(),
+            draw: Drawable::empty(ctx),
+        };
+        state.recalculate(ctx, app);
+        Box::new(state)
+    }
+
+    fn recalculate(&mut self, ctx: &mut EventCtx, app: &App) {
+        let highway: String = self.panel.dropdown_value("highway");
+        let speed_limit: Speed = self.panel.dropdown_value("speed limit");
+        self.cmds =
+            app.primary
+                .map
+                .bulk_edit_speed_limits(&highway, self.boundary.as_ref(), speed_limit);
+
+        let mut batch = GeomBatch::new();
+        for cmd in &self.cmds {
+            if let EditCmd::ChangeRoad { r, .. } = cmd {
+                batch.push(
+                    Color::CYAN.alpha(0.5),
+                    app.primary.map.get_r(*r).get_thick_polygon(),
+                );
+            }
+        }
+        if let Some(ref boundary) = self.boundary {
+            if let Ok(outline) = boundary.to_outline(Distance::meters(3.0)) {
+                batch.push(Color::RED.alpha(0.8), outline);
+            }
+        }
+        self.draw = ctx.upload(batch);
+
+        let boundary = if self.boundary.is_some() {
+            "Only inside the area you drew"
+        } else {
+            "Everywhere on the map"
+        };
+        self.panel
+            .replace(ctx, "boundary", boundary.text_widget(ctx).centered_vert());
+        let summary = if self.cmds.is_empty() {
+            Text::from(Line("No roads will change").secondary())
+        } else {
+            Text::from(format!(
+                "{} roads will change",
+                prettyprint_usize(self.cmds.len())
+            ))
+        };
+        self.panel.replace(ctx, "summary", summary.into_widget(ctx));
+    }
+}
+
+impl State<App> for BulkSpeedLimits {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" | "Cancel" => {
+                    return Transition::Pop;
+                }
+                "Apply" => {
+                    if !self.cmds.is_empty() {
+                        let mut edits = app.primary.map.get_edits().clone();
+                        edits.commands.extend(self.cmds.drain(..));
+                        apply_map_edits(ctx, app, edits);
+                    }
+                    return Transition::Pop;
+                }
+                "Draw an area" => {
+                    return Transition::Push(Box::new(DrawBoundary {
+                        lasso: Lasso::new(),
+                    }));
+                }
+                "Use the whole map" => {
+                    self.boundary = None;
+                    self.recalculate(ctx, app);
+                }
+                _ => unreachable!(),
+            },
+            Outcome::Changed(_) => {
+                self.recalculate(ctx, app);
+            }
+            _ => {}
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        g.redraw(&self.draw);
+        self.panel.draw(g);
+    }
+}
+
+struct DrawBoundary {
+    lasso: Lasso,
+}
+
+impl State<App> for DrawBoundary {
+    fn event(&mut self, ctx: &mut EventCtx, _: &mut App) -> Transition {
+        if ctx.input.pressed(Key::Escape) {
+            return Transition::Pop;
+        }
+        if let Some(ring) = self.lasso.event(ctx) {
+            return Transition::Multi(vec![
+                Transition::Pop,
+                Transition::ModifyState(Box::new(move |state, ctx, app| {
+                    let state = state.downcast_mut::<BulkSpeedLimits>().unwrap();
+                    state.boundary = Some(ring.into_polygon());
+                    state.recalculate(ctx, app);
+                })),
+            ]);
+        }
+        Transition::Keep
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::PreviousState
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.lasso.draw(g);
+    }
+}
@@ -48,6 +48,20 @@ impl StopSignEditor {
             })
             .collect();
 
+        // Pedestrians only cross the road here, so the crosswalk can be marked or not
+        let crosswalk_btn = if app.primary.map.get_i(id).is_degenerate() {
+            ctx.style()
+                .btn_outline
+                .text(if crosswalks_marked(app, id) {
+                    "remove crosswalk markings"
+                } else {
+                    "mark crosswalk"
+                })
+                .build_widget(ctx, "toggle crosswalk")
+        } else {
+            Widget::nothing()
+        };
+
         let panel = Panel::new_builder(Widget::col(vec![
             Line("Stop sign editor").small_heading().into_widget(ctx),
             ctx.style()
@@ -59,6 +73,7 @@ impl StopSignEditor {
                         == app.primary.map.get_stop_sign(id),
                 )
                 .build_def(ctx),
+            crosswalk_btn,
             ctx.style()
                 .btn_outline
                 .text("close intersection for construction")
@@ -111,6 +126,28 @@ impl SimpleState<App> for StopSignEditor {
                     self.mode.clone(),
                 ))
             }
+            "toggle crosswalk" => {
+                let marked = crosswalks_marked(app, self.id);
+                let map = &app.primary.map;
+                let mut edits = map.get_edits().clone();
+                for r in &map.get_i(self.id).roads {
+                    let fwd = map.get_r(*r).dst_i == self.id;
+                    edits.commands.push(map.edit_road_cmd(*r, |new| {
+                        if fwd {
+                            new.crosswalk_forward = !marked;
+                        } else {
+                            new.crosswalk_backward = !marked;
+                        }
+                    }));
+                }
+                apply_map_edits(ctx, app, edits);
+                Transition::Replace(StopSignEditor::new_state(
+                    ctx,
+                    app,
+                    self.id,
+                    self.mode.clone(),
+                ))
+            }
             "close intersection for construction" => {
                 let cmd = EditCmd::ChangeIntersection {
                     i: self.id,
@@ -235,3 +272,16 @@ impl SimpleState<App> for StopSignEditor {
         }
     }
 }
+
+/// Does every road have a marked crosswalk at this end?
+fn crosswalks_marked(app: &App, id: IntersectionID) -> bool {
+    let map = &app.primary.map;
+    map.get_i(id).roads.iter().all(|r| {
+        let road = map.get_r(*r);
+        if road.dst_i == id {
+            road.crosswalk_forward
+        } else {
+            road.crosswalk_backward
+        }
+    })
+}
@@ -1,7 +1,10 @@
+use anyhow::Result;
+
 use geom::Duration;
 use map_gui::tools::{ChooseSomething, FilePicker, PopupMsg};
 use map_model::{
-    ControlStopSign, ControlTrafficSignal, EditCmd, EditIntersection, IntersectionID, StageType,
+    ControlStopSign, ControlTrafficSignal, EditCmd, EditIntersection, IntersectionID, Map,
+    StageType,
 };
 use widgetry::{
     Choice, DrawBaselayer, EventCtx, Key, Line, Panel, SimpleState, Spinner, State, Text, TextExt,
@@ -186,6 +189,8 @@ pub fn edit_entire_signal(
 
     let use_template = "use template";
     let all_walk = "add an all-walk stage at the end";
+    let add_lpi = "give pedestrians a head start before vehicles get a green";
+    let remove_lpi = "remove the head start for pedestrians";
     let major_minor_timing = "use timing pattern for a major/minor intersection";
     let stop_sign = "convert to stop signs";
     let close = "close intersection for construction";
@@ -197,10 +202,21 @@ pub fn edit_entire_signal(
         .as_ref()
         .map(|x| format!("import from GMNS {}", x));
     let gmns_all = "import all traffic signals from a new GMNS timing.csv";
+    let json_picker = "import from a traffic_signal_data JSON file";
 
     let mut choices = vec![use_template.to_string()];
     if has_sidewalks {
         choices.push(all_walk.to_string());
+        if app
+            .primary
+            .map
+            .get_traffic_signal(i)
+            .has_leading_pedestrian_intervals()
+        {
+            choices.push(remove_lpi.to_string());
+        } else {
+            choices.push(add_lpi.to_string());
+        }
     }
     choices.push(major_minor_timing.to_string());
     // TODO Conflating stop signs and construction here
@@ -214,6 +230,7 @@ pub fn edit_entire_signal(
         choices.push(x);
     }
     choices.push(gmns_all.to_string());
+    choices.push(json_picker.to_string());
 
     ChooseSomething::new_state(
         ctx,
@@ -251,6 +268,26 @@ pub fn edit_entire_signal(
                     }
                 })),
             ]),
+            x if x == add_lpi || x == remove_lpi => {
+                let add = x == add_lpi;
+                Transition::Multi(vec![
+                    Transition::Pop,
+                    Transition::ModifyState(Box::new(move |state, ctx, app| {
+                        let mut new_signal = app.primary.map.get_traffic_signal(i).clone();
+                        let changed = if add {
+                            new_signal.add_leading_pedestrian_intervals()
+                        } else {
+                            new_signal.remove_leading_pedestrian_intervals()
+                        };
+                        if changed {
+                            let editor = state.downcast_mut::<TrafficSignalEditor>().unwrap();
+                            editor.add_new_edit(ctx, app, 0, |ts| {
+                                *ts = new_signal.clone();
+                            });
+                        }
+                    })),
+                ])
+            }
             x if x == major_minor_timing => Transition::Replace(ChooseSomething::new_state(
                 ctx,
                 "Use what timing split?",
@@ -382,6 +419,33 @@ pub fn edit_entire_signal(
                     )),
                 }
             }
+            x if x == json_picker => Transition::Replace(FilePicker::new_state(
+                ctx,
+                None,
+                Box::new(move |ctx, app, maybe_path| {
+                    if let Ok(Some(path)) = maybe_path {
+                        match import_json(&app.primary.map, i, &path) {
+                            Ok(new_signal) => Transition::Multi(vec![
+                                Transition::Pop,
+                                Transition::ModifyState(Box::new(move |state, ctx, app| {
+                                    let editor =
+                                        state.downcast_mut::<TrafficSignalEditor>().unwrap();
+                                    editor.add_new_edit(ctx, app, 0, |ts| {
+                                        *ts = new_signal.clone();
+                                    });
+                                })),
+                            ]),
+                            Err(err) => Transition::Replace(PopupMsg::new_state(
+                                ctx,
+                                "Error",
+                                vec![err.to_string()],
+                            )),
+                        }
+                    } else {
+                        Transition::Pop
+                    }
+                }),
+            )),
             x if x == gmns_all => Transition::Replace(FilePicker::new_state(
                 ctx,
                 None,
@@ -406,3 +470,15 @@ pub fn edit_entire_signal(
         }),
     )
 }
+
+fn import_json(map: &Map, i: IntersectionID, path: &str) -> Result<ControlTrafficSignal> {
+    let signal = ControlTrafficSignal::import_json(&abstio::slurp_file(path)?, map)?;
+    if signal.id != i {
+        bail!(
+            "{} describes {}, not the signal being edited",
+            path,
+            signal.id
+        );
+    }
+    Ok(signal)
+}
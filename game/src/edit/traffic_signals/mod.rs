@@ -6,7 +6,7 @@ use abstutil::Timer;
 use geom::{Distance, Line, Polygon, Pt2D};
 use map_gui::options::TrafficSignalStyle;
 use map_gui::render::{traffic_signal, DrawMovement, DrawOptions};
-use map_gui::tools::PopupMsg;
+use map_gui::tools::{save_file, PopupMsg};
 use map_model::{
     ControlTrafficSignal, EditCmd, EditIntersection, IntersectionID, MovementID, Stage, StageType,
     TurnPriority,
@@ -27,12 +27,15 @@ mod gmns;
 mod offsets;
 mod picker;
 mod preview;
+mod timing;
 
 // Welcome to one of the most overwhelmingly complicated parts of the UI...
 
 pub struct TrafficSignalEditor {
     side_panel: Panel,
     top_panel: Panel,
+    /// Only shown on request
+    timing_panel: Option<Panel>,
 
     mode: GameplayMode,
     members: BTreeSet<IntersectionID>,
@@ -76,6 +79,7 @@ impl TrafficSignalEditor {
         let mut editor = TrafficSignalEditor {
             side_panel: make_side_panel(ctx, app, &members, 0),
             top_panel: make_top_panel(ctx, app, false, false),
+            timing_panel: None,
             mode,
             current_stage: 0,
             movements: Vec::new(),
@@ -102,6 +106,11 @@ impl TrafficSignalEditor {
             self.current_stage = idx;
             self.side_panel = make_side_panel(ctx, app, &self.members, self.current_stage);
         }
+        if let Some(ref old) = self.timing_panel {
+            let mut new = timing::make_timing_panel(ctx, app, &self.members, self.current_stage);
+            new.restore(ctx, old);
+            self.timing_panel = Some(new);
+        }
 
         self.recalc_draw_current(ctx, app);
     }
@@ -228,6 +237,19 @@ impl State<App> for TrafficSignalEditor {
                         self.members.clone(),
                     ));
                 }
+                "Timing diagram" => {
+                    if self.timing_panel.is_some() {
+                        self.timing_panel = None;
+                    } else {
+                        self.timing_panel = Some(timing::make_timing_panel(
+                            ctx,
+                            app,
+                            &self.members,
+                            self.current_stage,
+                        ));
+                    }
+                    return Transition::Keep;
+                }
                 "Add a new stage" => {
                     self.add_new_edit(ctx, app, num_stages, |ts| {
                         ts.stages.push(Stage::new());
@@ -275,6 +297,18 @@ impl State<App> for TrafficSignalEditor {
             _ => {}
         }
 
+        if let Some(ref mut panel) = self.timing_panel {
+            if let Outcome::Clicked(x) = panel.event(ctx) {
+                match x.as_ref() {
+                    "close" => {
+                        self.timing_panel = None;
+                        return Transition::Keep;
+                    }
+                    _ => unreachable!(),
+                }
+            }
+        }
+
         if let Outcome::Clicked(x) = self.top_panel.event(ctx) {
             match x.as_ref() {
                 "Finish" => {
@@ -347,13 +381,19 @@ impl State<App> for TrafficSignalEditor {
                     ));
                 }
                 "Export" => {
+                    let map = &app.primary.map;
+                    let mut results = Vec::new();
                     for signal in BundleEdits::get_current(app, &self.members).signals {
-                        let ts = signal.export(&app.primary.map);
-                        abstio::write_json(
-                            format!("traffic_signal_data/{}.json", ts.intersection_osm_node_id),
-                            &ts,
-                        );
+                        let filename =
+                            format!("traffic_signal_{}.json", map.get_i(signal.id).orig_id.0);
+                        match save_file(&filename, signal.export_json(map)) {
+                            Ok(path) => results.push(format!("Wrote {}", path)),
+                            Err(err) => {
+                                results.push(format!("Couldn't write {}: {}", filename, err))
+                            }
+                        }
                     }
+                    return Transition::Push(PopupMsg::new_state(ctx, "Export complete", results));
                 }
                 "Preview" => {
                     // Might have to do this first!
@@ -500,6 +540,9 @@ impl State<App> for TrafficSignalEditor {
 
         self.top_panel.draw(g);
         self.side_panel.draw(g);
+        if let Some(ref panel) = self.timing_panel {
+            panel.draw(g);
+        }
 
         if let Some((id, _)) = self.movement_selected {
             let osd = if id.crosswalk {
@@ -762,12 +805,20 @@ fn make_side_panel(
                 .hotkey(Key::O)
                 .build_def(ctx)
         },
+        ctx.style()
+            .btn_outline
+            .text("Timing diagram")
+            .hotkey(Key::T)
+            .build_def(ctx),
     ]));
 
     Panel::new_builder(Widget::col(col))
         .aligned(HorizontalAlignment::Left, VerticalAlignment::Center)
         // Hovering on a stage card after dropping it produces Outcome::Changed
         .ignore_initial_events()
+        .hotkey_group("Stages")
+        .describe_hotkey(Key::LeftArrow, "previous stage")
+        .describe_hotkey(Key::RightArrow, "next stage")
         .build(ctx)
 }
 
@@ -3,7 +3,8 @@ use std::collections::BTreeSet;
 use maplit::btreeset;
 
 use geom::{Distance, Duration};
-use map_model::IntersectionID;
+use map_gui::tools::PopupMsg;
+use map_model::{ControlTrafficSignal, IntersectionID};
 use widgetry::{
     Color, Drawable, EventCtx, GfxCtx, HorizontalAlignment, Key, Line, Panel, RewriteColor,
     SimpleState, Spinner, State, Text, TextExt, VerticalAlignment, Widget,
@@ -265,6 +266,10 @@ impl TuneRelative {
                 .text("Update offset")
                 .hotkey(Key::Enter)
                 .build_def(ctx),
+            ctx.style()
+                .btn_outline
+                .text("Make a green wave")
+                .build_def(ctx),
         ]))
         .build(ctx);
         <dyn SimpleState<_>>::new_state(
@@ -305,6 +310,39 @@ impl SimpleState<App> for TuneRelative {
                     )),
                 ])
             }
+            "Make a green wave" => {
+                // Coordinate every signal being edited along the way, not just the two ends
+                let corridor: Vec<IntersectionID> = app
+                    .primary
+                    .map
+                    .simple_path_btwn(self.i1, self.i2)
+                    .map(|pair| pair.1)
+                    .unwrap_or_else(Vec::new)
+                    .into_iter()
+                    .filter(|i| self.members.contains(i))
+                    .collect();
+                match ControlTrafficSignal::green_wave(&app.primary.map, &corridor) {
+                    Ok(signals) => {
+                        for ts in signals {
+                            app.primary.map.incremental_edit_traffic_signal(ts);
+                        }
+                        Transition::Multi(vec![
+                            Transition::Pop,
+                            Transition::Replace(ShowRelative::new_state(
+                                ctx,
+                                app,
+                                self.i1,
+                                self.members.clone(),
+                            )),
+                        ])
+                    }
+                    Err(err) => Transition::Push(PopupMsg::new_state(
+                        ctx,
+                        "Can't make a green wave",
+                        vec![err.to_string()],
+                    )),
+                }
+            }
             _ => unreachable!(),
         }
     }
@@ -0,0 +1,188 @@
+//! A timing diagram for the signals being edited. Time runs left to right through one full cycle,
+//! and every movement gets a row showing when it's green, yellow, or red. This is the view traffic
+//! engineers are used to, and it's rebuilt after every edit, so changing a stage's duration or
+//! movements shows up immediately.
+
+use std::collections::BTreeSet;
+
+use geom::{Duration, Percent, Polygon, Pt2D};
+use map_gui::tools::ColorLegend;
+use map_model::{IntersectionID, StageType, TurnPriority, TurnType};
+use widgetry::{
+    Color, EventCtx, GeomBatch, HorizontalAlignment, Line, Panel, Text, VerticalAlignment, Widget,
+};
+
+use crate::app::App;
+
+/// Width of one full cycle, in pixels
+const CYCLE_WIDTH: f64 = 600.0;
+const ROW_HEIGHT: f64 = 20.0;
+const PADDING: f64 = 10.0;
+/// The simulation doesn't model yellow lights, but the last few seconds of a green are drawn as
+/// one everywhere else in the UI.
+const YELLOW_DURATION: Duration = Duration::const_seconds(5.0);
+
+pub fn make_timing_panel(
+    ctx: &mut EventCtx,
+    app: &App,
+    members: &BTreeSet<IntersectionID>,
+    selected: usize,
+) -> Panel {
+    let mut col = vec![Widget::row(vec![
+        Line("Timing diagram").small_heading().into_widget(ctx),
+        ctx.style().btn_close_widget(ctx),
+    ])];
+    for i in members {
+        if members.len() > 1 {
+            col.push(Line(i.to_string()).into_widget(ctx));
+        }
+        col.push(draw_diagram(ctx, app, *i, selected).into_widget(ctx));
+    }
+    col.push(Widget::row(vec![
+        ColorLegend::row(ctx, app.cs.signal_protected_turn, "protected"),
+        ColorLegend::row(ctx, app.cs.signal_permitted_turn, "permitted"),
+        ColorLegend::row(ctx, Color::YELLOW, "yellow"),
+        ColorLegend::row(ctx, Color::ORANGE, "yellow, may extend"),
+        ColorLegend::row(ctx, app.cs.signal_banned_turn, "red"),
+    ]));
+
+    Panel::new_builder(Widget::col(col))
+        .aligned(HorizontalAlignment::Center, VerticalAlignment::Bottom)
+        .max_size(Percent::int(70), Percent::int(40))
+        .build(ctx)
+}
+
+fn draw_diagram(ctx: &EventCtx, app: &App, i: IntersectionID, selected: usize) -> GeomBatch {
+    let map = &app.primary.map;
+    let lang = app.opts.language.as_ref();
+    let signal = map.get_traffic_signal(i);
+    let cycle = signal.simple_cycle_duration();
+    let num_stages = signal.stages.len();
+
+    let mut labels = Vec::new();
+    for (id, movement) in &map.get_i(i).movements {
+        let from = map.get_r(id.from.road).get_name(lang);
+        let label = if id.crosswalk {
+            format!("Crosswalk across {}", from)
+        } else {
+            let to = map.get_r(id.to.road).get_name(lang);
+            match movement.turn_type {
+                TurnType::Left => format!("{} → {} (left)", from, to),
+                TurnType::Right => format!("{} → {} (right)", from, to),
+                TurnType::UTurn => format!("{} (U-turn)", from),
+                _ => format!("{} → {}", from, to),
+            }
+        };
+        labels.push((*id, Text::from(Line(label).small()).render_autocropped(ctx)));
+    }
+    let label_width = labels
+        .iter()
+        .map(|(_, batch)| batch.get_dims().width)
+        .fold(0.0, f64::max);
+    let x0 = label_width + PADDING;
+    // One row for the stage numbers, then one per movement, then one for the time axis
+    let height = ROW_HEIGHT * (labels.len() + 2) as f64;
+
+    let mut batch = GeomBatch::new();
+    let x_of = |t: Duration| {
+        if cycle == Duration::ZERO {
+            x0
+        } else {
+            x0 + CYCLE_WIDTH * (t / cycle)
+        }
+    };
+
+    // Highlight the stage being edited, behind everything else
+    let mut start = Duration::ZERO;
+    for (idx, stage) in signal.stages.iter().enumerate() {
+        let end = start + stage.stage_type.simple_duration();
+        if idx == selected {
+            batch.push(
+                ctx.style().btn_solid_primary.bg.alpha(0.5),
+                Polygon::rectangle((x_of(end) - x_of(start)).max(1.0), height)
+                    .translate(x_of(start), 0.0),
+            );
+        }
+        batch.append(
+            Text::from(Line(format!("{}", idx + 1)).small())
+                .render_autocropped(ctx)
+                .centered_on(Pt2D::new((x_of(start) + x_of(end)) / 2.0, ROW_HEIGHT / 2.0)),
+        );
+        start = end;
+    }
+
+    for (row, (id, label)) in labels.into_iter().enumerate() {
+        let y = ROW_HEIGHT * (row + 1) as f64;
+        let label_height = label.get_dims().height;
+        batch.append(label.translate(0.0, y + (ROW_HEIGHT - label_height) / 2.0));
+
+        let mut start = Duration::ZERO;
+        for (idx, stage) in signal.stages.iter().enumerate() {
+            let duration = stage.stage_type.simple_duration();
+            let end = start + duration;
+            let priority = stage.get_priority_of_movement(id);
+            let green = match priority {
+                TurnPriority::Protected => app.cs.signal_protected_turn,
+                TurnPriority::Yield => app.cs.signal_permitted_turn,
+                TurnPriority::Banned => app.cs.signal_banned_turn,
+            };
+            // The movement has to clear the intersection before the next stage starts, unless it
+            // stays allowed
+            let next = &signal.stages[(idx + 1) % num_stages];
+            let yellow = if priority != TurnPriority::Banned
+                && next.get_priority_of_movement(id) == TurnPriority::Banned
+            {
+                YELLOW_DURATION.min(duration)
+            } else {
+                Duration::ZERO
+            };
+
+            push_band(&mut batch, green, x_of(start), x_of(end - yellow), y);
+            if yellow > Duration::ZERO {
+                let color = if let StageType::Fixed(_) = stage.stage_type {
+                    Color::YELLOW
+                } else {
+                    // Variable stages might extend, like how the intersection itself is drawn
+                    Color::ORANGE
+                };
+                push_band(&mut batch, color, x_of(end - yellow), x_of(end), y);
+            }
+            start = end;
+        }
+    }
+
+    // Label the time of every stage boundary, skipping ones that'd overlap
+    let y = height - ROW_HEIGHT;
+    let mut boundaries = vec![Duration::ZERO];
+    for stage in &signal.stages {
+        boundaries.push(*boundaries.last().unwrap() + stage.stage_type.simple_duration());
+    }
+    let mut last_x = None;
+    for t in boundaries {
+        let x = x_of(t);
+        if last_x.map(|last| x - last < 50.0).unwrap_or(false) {
+            continue;
+        }
+        batch.push(
+            ctx.style().text_primary_color,
+            Polygon::rectangle(1.0, ROW_HEIGHT / 4.0).translate(x, y),
+        );
+        batch.append(
+            Text::from(Line(t.to_string()).small())
+                .render_autocropped(ctx)
+                .centered_on(Pt2D::new(x, y + ROW_HEIGHT * 0.6)),
+        );
+        last_x = Some(x);
+    }
+
+    batch
+}
+
+fn push_band(batch: &mut GeomBatch, color: Color, x1: f64, x2: f64, y: f64) {
+    if x2 > x1 {
+        batch.push(
+            color,
+            Polygon::rectangle(x2 - x1, ROW_HEIGHT * 0.6).translate(x1, y + ROW_HEIGHT * 0.2),
+        );
+    }
+}
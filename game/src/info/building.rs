@@ -161,10 +161,10 @@ fn people_body(ctx: &mut EventCtx, app: &App, details: &mut Details, id: Buildin
         let mut has_car = false;
         let mut has_bike = false;
         for vehicle in &person.vehicles {
-            if vehicle.vehicle_type == VehicleType::Car {
-                has_car = true;
-            } else if vehicle.vehicle_type == VehicleType::Bike {
+            if vehicle.vehicle_type == VehicleType::Bike {
                 has_bike = true;
+            } else {
+                has_car = true;
             }
         }
         if has_car {
@@ -1,10 +1,15 @@
+use std::collections::BTreeMap;
+
 use abstutil::{prettyprint_usize, Counter};
-use geom::{Circle, Distance, Time};
+use geom::{Circle, Distance, Duration, Polygon, Pt2D, Time};
 use map_gui::tools::ColorNetwork;
 use map_gui::ID;
 use map_model::{BusRoute, BusRouteID, BusStopID, PathStep};
 use sim::{AgentID, CarID};
-use widgetry::{Color, ControlState, EventCtx, Key, Line, RewriteColor, Text, TextExt, Widget};
+use widgetry::{
+    Color, ControlState, EventCtx, GeomBatch, Key, Line, LinePlot, PlotOptions, RewriteColor,
+    Series, Text, TextExt, Widget,
+};
 
 use crate::app::App;
 use crate::info::{header_btns, make_tabs, Details, Tab};
@@ -173,21 +178,41 @@ fn bus_header(ctx: &mut EventCtx, app: &App, details: &mut Details, id: CarID, t
 }
 
 pub fn route(ctx: &mut EventCtx, app: &App, details: &mut Details, id: BusRouteID) -> Widget {
-    let header = {
-        let map = &app.primary.map;
-        let route = map.get_br(id);
+    Widget::custom_col(vec![
+        route_header(ctx, app, details, id, Tab::BusRoute(id)),
+        route_body(ctx, app, details, id).tab_body(ctx),
+    ])
+}
 
+pub fn route_live(ctx: &mut EventCtx, app: &App, details: &mut Details, id: BusRouteID) -> Widget {
+    Widget::custom_col(vec![
+        route_header(ctx, app, details, id, Tab::BusRouteLive(id)),
+        route_live_body(ctx, app, details, id).tab_body(ctx),
+    ])
+}
+
+fn route_header(
+    ctx: &mut EventCtx,
+    app: &App,
+    details: &mut Details,
+    id: BusRouteID,
+    tab: Tab,
+) -> Widget {
+    let route = app.primary.map.get_br(id);
+
+    Widget::custom_col(vec![
         Widget::row(vec![
             Line(format!("Route {}", route.short_name))
                 .small_heading()
                 .into_widget(ctx),
             header_btns(ctx),
-        ])
-    };
-
-    Widget::custom_col(vec![
-        header,
-        route_body(ctx, app, details, id).tab_body(ctx),
+        ]),
+        make_tabs(
+            ctx,
+            &mut details.hyperlinks,
+            tab,
+            vec![("Info", Tab::BusRoute(id)), ("Live", Tab::BusRouteLive(id))],
+        ),
     ])
 }
 
@@ -315,7 +340,6 @@ fn route_body(ctx: &mut EventCtx, app: &App, details: &mut Details, id: BusRoute
         details.warpers.insert(name, ID::Intersection(i.id));
     }
 
-    // TODO Soon it'll be time to split into tabs
     {
         rows.push(
             ctx.style()
@@ -327,49 +351,265 @@ fn route_body(ctx: &mut EventCtx, app: &App, details: &mut Details, id: BusRoute
         rows.push(describe_schedule(route).into_widget(ctx));
     }
 
-    // Draw the route, label stops, and show location of buses
-    {
-        let mut colorer = ColorNetwork::new(app);
-        for req in route.all_steps(map) {
-            for step in map.pathfind(req).unwrap().get_steps() {
-                if let PathStep::Lane(l) = step {
-                    colorer.add_l(*l, app.cs.unzoomed_bus);
-                }
+    draw_route(ctx, app, details, route, bus_locations);
+
+    Widget::col(rows)
+}
+
+fn route_live_body(ctx: &mut EventCtx, app: &App, details: &mut Details, id: BusRouteID) -> Widget {
+    let mut rows = vec![];
+
+    let map = &app.primary.map;
+    let sim = &app.primary.sim;
+    let route = map.get_br(id);
+
+    let buses = sim.status_of_buses(id, map);
+    let mut bus_locations = Vec::new();
+    if buses.is_empty() {
+        rows.push(format!("No {} running", route.plural_noun()).text_widget(ctx));
+    } else {
+        rows.push(route_strip(ctx, app, route, &buses));
+
+        let arrivals: Vec<(Time, CarID, BusStopID)> = sim
+            .get_analytics()
+            .bus_arrivals
+            .iter()
+            .filter(|(_, _, r, _)| *r == id)
+            .map(|(t, car, _, stop)| (*t, *car, *stop))
+            .collect();
+        for (bus, stop_idx, pct, pt) in buses {
+            let position = match stop_idx {
+                None => "Heading to the first stop".to_string(),
+                Some(idx) if idx + 1 < route.stops.len() => format!(
+                    "Between stops {} and {} ({}%)",
+                    idx + 1,
+                    idx + 2,
+                    (pct * 100.0) as usize
+                ),
+                Some(_) => "Past the last stop".to_string(),
+            };
+            let headway = match headway(&arrivals, bus) {
+                Some(dt) => format!("{} behind the previous {}", dt, route.plural_noun()),
+                None => "Nobody ahead".to_string(),
+            };
+
+            rows.push(Widget::row(vec![
+                ctx.style().btn_outline.text(bus.to_string()).build_def(ctx),
+                Text::from_multiline(vec![
+                    Line(position),
+                    Line(format!(
+                        "{} passengers, {}",
+                        prettyprint_usize(sim.num_transit_passengers(bus)),
+                        headway
+                    ))
+                    .secondary(),
+                ])
+                .into_widget(ctx),
+            ]));
+            details
+                .hyperlinks
+                .insert(bus.to_string(), Tab::BusStatus(bus));
+            bus_locations.push(pt);
+        }
+    }
+
+    rows.push(
+        Line("Passengers departing each stop")
+            .small_heading()
+            .into_widget(ctx),
+    );
+    let loads = passenger_loads(app, id);
+    for (idx, bs) in route.stops.iter().enumerate() {
+        let name = format!("Stop {}: {}", idx + 1, map.get_bs(*bs).name);
+        let summary = match loads.get(bs) {
+            Some(list) => format!(
+                ": {:.1} on average, {} at most",
+                list.iter().sum::<usize>() as f64 / list.len() as f64,
+                prettyprint_usize(list.iter().max().cloned().unwrap())
+            ),
+            None => ": no arrivals yet".to_string(),
+        };
+        rows.push(Widget::row(vec![
+            ctx.style()
+                .btn_plain
+                .icon("system/assets/tools/pin.svg")
+                .build_widget(ctx, &name),
+            Text::from_all(vec![Line(&name), Line(summary).secondary()]).into_widget(ctx),
+        ]));
+        details.warpers.insert(name, ID::BusStop(*bs));
+    }
+
+    let mut series = vec![Series {
+        label: format!("After \"{}\"", map.get_edits().edits_name),
+        color: app.cs.after_changes,
+        pts: sim.get_analytics().transit_boardings(sim.time(), id),
+    }];
+    if app.has_prebaked().is_some() {
+        series.push(Series {
+            label: format!("Before \"{}\"", map.get_edits().edits_name),
+            color: app.cs.before_changes.alpha(0.5),
+            pts: app.prebaked().transit_boardings(sim.get_end_of_day(), id),
+        });
+    }
+    rows.push(
+        Widget::col(vec![
+            Line("Total boardings").small_heading().into_widget(ctx),
+            LinePlot::new_widget(
+                ctx,
+                "boardings",
+                series,
+                PlotOptions::fixed(),
+                app.opts.units,
+            ),
+        ])
+        .padding(10)
+        .bg(app.cs.inner_panel_bg)
+        .outline(ctx.style().section_outline),
+    );
+
+    draw_route(ctx, app, details, route, bus_locations);
+
+    Widget::col(rows)
+}
+
+/// Draws the stops of a route evenly spaced along a line, and where each bus is between them.
+fn route_strip(
+    ctx: &EventCtx,
+    app: &App,
+    route: &BusRoute,
+    buses: &[(CarID, Option<usize>, f64, Pt2D)],
+) -> Widget {
+    let width = 0.2 * ctx.canvas.window_width;
+    let height = 30.0;
+    let spacing = if route.stops.len() > 1 {
+        width / (route.stops.len() - 1) as f64
+    } else {
+        width
+    };
+
+    let mut batch = GeomBatch::new();
+    batch.push(
+        app.cs.unzoomed_bus,
+        Polygon::rectangle(width, 4.0).translate(0.0, height / 2.0 - 2.0),
+    );
+    for idx in 0..route.stops.len() {
+        batch.push(
+            Color::WHITE,
+            Circle::new(
+                Pt2D::new(idx as f64 * spacing, height / 2.0),
+                Distance::meters(5.0),
+            )
+            .to_polygon(),
+        );
+    }
+    for (_, stop_idx, pct, _) in buses {
+        let x = match stop_idx {
+            None => 0.0,
+            Some(idx) if idx + 1 < route.stops.len() => (*idx as f64 + pct) * spacing,
+            Some(_) => width,
+        };
+        batch.push(
+            app.cs.bus_body,
+            Circle::new(Pt2D::new(x, height / 2.0), Distance::meters(8.0)).to_polygon(),
+        );
+    }
+    batch.into_widget(ctx)
+}
+
+/// How long after the previous vehicle did this one reach its most recent stop?
+fn headway(arrivals: &[(Time, CarID, BusStopID)], bus: CarID) -> Option<Duration> {
+    let idx = arrivals.iter().rposition(|(_, car, _)| *car == bus)?;
+    let (t, _, stop) = arrivals[idx];
+    arrivals[..idx]
+        .iter()
+        .rev()
+        .find(|(_, car, s)| *s == stop && *car != bus)
+        .map(|(prev, _, _)| t - *prev)
+}
+
+/// For every stop, how many passengers were on board each time a vehicle left it. Passengers
+/// only board and alight the moment a vehicle arrives, so match those events up with arrivals.
+fn passenger_loads(app: &App, id: BusRouteID) -> BTreeMap<BusStopID, Vec<usize>> {
+    let analytics = app.primary.sim.get_analytics();
+    let mut boardings: BTreeMap<(BusStopID, Time), usize> = BTreeMap::new();
+    for (stop, list) in &analytics.passengers_boarding {
+        for (t, r, _) in list {
+            if *r == id {
+                *boardings.entry((*stop, *t)).or_insert(0) += 1;
             }
         }
-        details.draw_extra.unzoomed.append(colorer.unzoomed);
-        details.draw_extra.zoomed.append(colorer.zoomed);
-
-        for pt in bus_locations {
-            details.draw_extra.unzoomed.push(
-                Color::BLUE,
-                Circle::new(pt, Distance::meters(20.0)).to_polygon(),
-            );
-            details.draw_extra.zoomed.push(
-                Color::BLUE.alpha(0.5),
-                Circle::new(pt, Distance::meters(5.0)).to_polygon(),
-            );
+    }
+    let mut alightings: BTreeMap<(BusStopID, Time), usize> = BTreeMap::new();
+    for (stop, list) in &analytics.passengers_alighting {
+        for (t, r) in list {
+            if *r == id {
+                *alightings.entry((*stop, *t)).or_insert(0) += 1;
+            }
         }
+    }
 
-        for (idx, bs) in route.stops.iter().enumerate() {
-            let bs = map.get_bs(*bs);
-            details.draw_extra.unzoomed.append(
-                Text::from(format!("{}) {}", idx + 1, bs.name))
-                    .bg(app.cs.bus_layer)
-                    .render_autocropped(ctx)
-                    .centered_on(bs.sidewalk_pos.pt(map)),
-            );
-            details.draw_extra.zoomed.append(
-                Text::from(format!("{}) {}", idx + 1, bs.name))
-                    .bg(app.cs.bus_layer)
-                    .render_autocropped(ctx)
-                    .scale(0.1)
-                    .centered_on(bs.sidewalk_pos.pt(map)),
-            );
+    let mut per_bus: BTreeMap<CarID, usize> = BTreeMap::new();
+    let mut loads: BTreeMap<BusStopID, Vec<usize>> = BTreeMap::new();
+    for (t, bus, r, stop) in &analytics.bus_arrivals {
+        if *r != id {
+            continue;
         }
+        let load = per_bus.entry(*bus).or_insert(0);
+        // If two vehicles arrive at the same stop at once, attribute everything to the first
+        *load += boardings.remove(&(*stop, *t)).unwrap_or(0);
+        *load = load.saturating_sub(alightings.remove(&(*stop, *t)).unwrap_or(0));
+        loads.entry(*stop).or_insert_with(Vec::new).push(*load);
     }
+    loads
+}
 
-    Widget::col(rows)
+fn draw_route(
+    ctx: &mut EventCtx,
+    app: &App,
+    details: &mut Details,
+    route: &BusRoute,
+    bus_locations: Vec<Pt2D>,
+) {
+    let map = &app.primary.map;
+    // Draw the route, label stops, and show location of buses
+    let mut colorer = ColorNetwork::new(app);
+    for req in route.all_steps(map) {
+        for step in map.pathfind(req).unwrap().get_steps() {
+            if let PathStep::Lane(l) = step {
+                colorer.add_l(*l, app.cs.unzoomed_bus);
+            }
+        }
+    }
+    details.draw_extra.unzoomed.append(colorer.unzoomed);
+    details.draw_extra.zoomed.append(colorer.zoomed);
+
+    for pt in bus_locations {
+        details.draw_extra.unzoomed.push(
+            Color::BLUE,
+            Circle::new(pt, Distance::meters(20.0)).to_polygon(),
+        );
+        details.draw_extra.zoomed.push(
+            Color::BLUE.alpha(0.5),
+            Circle::new(pt, Distance::meters(5.0)).to_polygon(),
+        );
+    }
+
+    for (idx, bs) in route.stops.iter().enumerate() {
+        let bs = map.get_bs(*bs);
+        details.draw_extra.unzoomed.append(
+            Text::from(format!("{}) {}", idx + 1, bs.name))
+                .bg(app.cs.bus_layer)
+                .render_autocropped(ctx)
+                .centered_on(bs.sidewalk_pos.pt(map)),
+        );
+        details.draw_extra.zoomed.append(
+            Text::from(format!("{}) {}", idx + 1, bs.name))
+                .bg(app.cs.bus_layer)
+                .render_autocropped(ctx)
+                .scale(0.1)
+                .centered_on(bs.sidewalk_pos.pt(map)),
+        );
+    }
 }
 
 // TODO Unit test
@@ -0,0 +1,86 @@
+use instant::Instant;
+
+use geom::Pt2D;
+use map_gui::ID;
+use widgetry::EventCtx;
+
+use crate::app::App;
+
+/// How long it takes the camera to close most of the distance to the agent being followed
+const SMOOTHING_SECONDS: f64 = 0.3;
+/// The zoom level to watch an agent moving around. Matches warping to an object.
+const MOVING_CAM_ZOOM: f64 = 10.0;
+
+/// After the player chooses to follow an agent, keep the camera centered on them while the
+/// simulation runs.
+pub struct FollowCamera {
+    last_update: Instant,
+    inside_building: bool,
+    /// Gradually zoom to this level. Once it's reached, the player is free to zoom themselves.
+    target_cam_zoom: Option<f64>,
+}
+
+impl FollowCamera {
+    pub fn new(ctx: &mut EventCtx, app: &App, id: &ID) -> Option<FollowCamera> {
+        // Don't jump there; the first event starts gliding towards them
+        app.primary.canonical_point(id.clone())?;
+        let inside_building = matches!(id, ID::Building(_));
+        Some(FollowCamera {
+            last_update: Instant::now(),
+            inside_building,
+            target_cam_zoom: Some(cam_zoom(ctx, inside_building)),
+        })
+    }
+
+    /// Move the camera towards the agent. Returns false if they can't be found anymore.
+    pub fn event(&mut self, ctx: &mut EventCtx, app: &App, id: &ID) -> bool {
+        let pt = match app.primary.canonical_point(id.clone()) {
+            Some(pt) => pt,
+            None => {
+                return false;
+            }
+        };
+
+        // Entering or leaving a building changes what's worth looking at
+        let inside_building = matches!(id, ID::Building(_));
+        if inside_building != self.inside_building {
+            self.inside_building = inside_building;
+            self.target_cam_zoom = Some(cam_zoom(ctx, inside_building));
+        }
+
+        // Exponential smoothing, independent of the frame rate
+        let dt = abstutil::elapsed_seconds(self.last_update);
+        self.last_update = Instant::now();
+        let pct = 1.0 - (-dt / SMOOTHING_SECONDS).exp();
+
+        // center_on_map_pt depends on the zoom, so change that first
+        let center = ctx.canvas.center_to_map_pt();
+        if let Some(target) = self.target_cam_zoom {
+            let zoom = ctx.canvas.cam_zoom + pct * (target - ctx.canvas.cam_zoom);
+            if (zoom - target).abs() < 0.01 {
+                ctx.canvas.cam_zoom = target;
+                self.target_cam_zoom = None;
+            } else {
+                ctx.canvas.cam_zoom = zoom;
+            }
+        }
+        ctx.canvas.center_on_map_pt(lerp(center, pt, pct));
+        true
+    }
+}
+
+fn lerp(from: Pt2D, to: Pt2D, pct: f64) -> Pt2D {
+    match geom::Line::new(from, to) {
+        Some(line) => line.percent_along(pct.min(1.0)).unwrap_or(to),
+        None => to,
+    }
+}
+
+/// Inside a building, zoom out to see the surroundings.
+fn cam_zoom(ctx: &EventCtx, inside_building: bool) -> f64 {
+    if inside_building {
+        ctx.canvas.settings.min_zoom_for_detail
+    } else {
+        MOVING_CAM_ZOOM
+    }
+}
@@ -143,8 +143,9 @@ fn delay_body(
     let mut rows = vec![];
     let i = app.primary.map.get_i(id);
 
-    assert!(i.is_traffic_signal());
+    assert!(i.is_traffic_signal() || i.is_stop_sign());
     rows.push(opts.to_controls(ctx, app));
+    rows.push(delay_breakdown(ctx, app, id, opts));
     rows.push(Toggle::choice(
         ctx,
         "fan chart / scatter plot",
@@ -372,6 +373,97 @@ fn traffic_signal_body(ctx: &mut EventCtx, app: &App, id: IntersectionID) -> Wid
     Widget::col(rows)
 }
 
+/// Break down the average vehicle delay by signal stage or, for stop signs, by the road the
+/// vehicles approach from.
+fn delay_breakdown(ctx: &EventCtx, app: &App, id: IntersectionID, opts: &DataOptions) -> Widget {
+    let data = if opts.show_before {
+        app.prebaked()
+    } else {
+        app.primary.sim.get_analytics()
+    };
+    let limit = if opts.show_end_of_day {
+        app.primary.sim.get_end_of_day()
+    } else {
+        app.primary.sim.time()
+    };
+    let map = &app.primary.map;
+    let i = map.get_i(id);
+
+    // Assign each movement, in the order of CompressedMovementID, to a group
+    let mut labels = Vec::new();
+    let mut group_per_movement: Vec<Option<usize>> = Vec::new();
+    if let Some(ts) = map.maybe_get_traffic_signal(id) {
+        for idx in 0..ts.stages.len() {
+            labels.push(format!("Stage {}", idx + 1));
+        }
+        for m in i.movements.keys() {
+            // Blame the stage where the movement is protected, if there is one
+            group_per_movement.push(
+                ts.stages
+                    .iter()
+                    .position(|stage| stage.protected_movements.contains(m))
+                    .or_else(|| {
+                        ts.stages
+                            .iter()
+                            .position(|stage| stage.yield_movements.contains(m))
+                    }),
+            );
+        }
+    } else {
+        let mut approaches = Vec::new();
+        for m in i.movements.keys() {
+            if m.crosswalk {
+                group_per_movement.push(None);
+                continue;
+            }
+            let idx = match approaches.iter().position(|r| *r == m.from.road) {
+                Some(idx) => idx,
+                None => {
+                    approaches.push(m.from.road);
+                    labels.push(format!(
+                        "From {}",
+                        map.get_r(m.from.road).get_name(app.opts.language.as_ref())
+                    ));
+                    approaches.len() - 1
+                }
+            };
+            group_per_movement.push(Some(idx));
+        }
+    }
+
+    let mut per_group = vec![(Duration::ZERO, 0); labels.len()];
+    if let Some(list) = data.intersection_delays.get(&id) {
+        for (idx, t, dt, agent_type) in list {
+            if *t > limit {
+                break;
+            }
+            if *agent_type == AgentType::Pedestrian {
+                continue;
+            }
+            // The movements may have changed since the data was recorded
+            if let Some(Some(group)) = group_per_movement.get(*idx as usize) {
+                per_group[*group].0 += *dt;
+                per_group[*group].1 += 1;
+            }
+        }
+    }
+
+    let mut txt = Text::from(Line("Average vehicle delay").small_heading());
+    for (label, (total, count)) in labels.into_iter().zip(per_group) {
+        if count == 0 {
+            txt.add_line(Line(format!("{}: no vehicles", label)).secondary());
+        } else {
+            txt.add_line(format!(
+                "{}: {} ({} vehicles)",
+                label,
+                (total / (count as f64)).to_string(&app.opts.units),
+                prettyprint_usize(count)
+            ));
+        }
+    }
+    txt.into_widget(ctx)
+}
+
 fn delay_plot(
     ctx: &EventCtx,
     app: &App,
@@ -415,6 +507,7 @@ fn delay_plot(
         max_y: None,
         disabled: opts.disabled_series(),
         dims: None,
+        fill_area: false,
     };
     Widget::col(vec![
         Line("Delay through intersection")
@@ -458,11 +551,13 @@ fn header(
             ("Info", Tab::IntersectionInfo(id)),
             ("Traffic", Tab::IntersectionTraffic(id, DataOptions::new())),
         ];
-        if i.is_traffic_signal() {
+        if i.is_traffic_signal() || i.is_stop_sign() {
             tabs.push((
                 "Delay",
                 Tab::IntersectionDelay(id, DataOptions::new(), false),
             ));
+        }
+        if i.is_traffic_signal() {
             tabs.push(("Current demand", Tab::IntersectionDemand(id)));
             tabs.push(("Signal", Tab::IntersectionTrafficSignal(id)));
         }
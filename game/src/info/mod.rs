@@ -22,10 +22,12 @@ use crate::debug::path_counter::PathCounter;
 use crate::edit::{EditMode, RouteEditor};
 use crate::layer::PANEL_PLACEMENT;
 use crate::sandbox::{dashboards, GameplayMode, SandboxMode, TimeWarpScreen};
+use follow::FollowCamera;
 
 mod building;
 mod bus;
 mod debug;
+mod follow;
 mod intersection;
 mod lane;
 mod parking_lot;
@@ -47,6 +49,10 @@ pub struct InfoPanel {
 
     // For drawing the OSD only
     cached_actions: Vec<Key>,
+
+    /// Did the player ask to follow this agent?
+    following: bool,
+    follow: Option<FollowCamera>,
 }
 
 #[derive(Clone)]
@@ -56,10 +62,13 @@ pub enum Tab {
     PersonTrips(PersonID, BTreeMap<TripID, OpenTrip>),
     PersonBio(PersonID),
     PersonSchedule(PersonID),
+    PersonHousehold(PersonID),
+    PersonExposures(PersonID),
 
     BusStatus(CarID),
     BusStop(BusStopID),
     BusRoute(BusRouteID),
+    BusRouteLive(BusRouteID),
 
     ParkedCar(CarID),
 
@@ -100,7 +109,8 @@ impl Tab {
                 "info" => Tab::IntersectionInfo(i),
                 "traffic" => Tab::IntersectionTraffic(i, DataOptions::new()),
                 "delay" => {
-                    if app.primary.map.get_i(i).is_traffic_signal() {
+                    let intersection = app.primary.map.get_i(i);
+                    if intersection.is_traffic_signal() || intersection.is_stop_sign() {
                         Tab::IntersectionDelay(i, DataOptions::new(), false)
                     } else {
                         Tab::IntersectionInfo(i)
@@ -146,6 +156,8 @@ impl Tab {
                         ),
                         "bio" => Tab::PersonBio(p),
                         "schedule" => Tab::PersonSchedule(p),
+                        "household" => Tab::PersonHousehold(p),
+                        "exposures" => Tab::PersonExposures(p),
                         _ => unreachable!(),
                     }
                 } else if c.vehicle_type == VehicleType::Bus || c.vehicle_type == VehicleType::Train
@@ -176,6 +188,8 @@ impl Tab {
                     ),
                     "bio" => Tab::PersonBio(person),
                     "schedule" => Tab::PersonSchedule(person),
+                    "household" => Tab::PersonHousehold(person),
+                    "exposures" => Tab::PersonExposures(person),
                     _ => unreachable!(),
                 }
             }
@@ -187,18 +201,18 @@ impl Tab {
 
     fn to_id(&self, app: &App) -> Option<ID> {
         match self {
-            Tab::PersonTrips(p, _) | Tab::PersonBio(p) | Tab::PersonSchedule(p) => {
-                match app.primary.sim.get_person(*p).state {
-                    PersonState::Inside(b) => Some(ID::Building(b)),
-                    PersonState::Trip(t) => {
-                        app.primary.sim.trip_to_agent(t).ok().map(ID::from_agent)
-                    }
-                    _ => None,
-                }
-            }
+            Tab::PersonTrips(p, _)
+            | Tab::PersonBio(p)
+            | Tab::PersonSchedule(p)
+            | Tab::PersonHousehold(p)
+            | Tab::PersonExposures(p) => match app.primary.sim.get_person(*p).state {
+                PersonState::Inside(b) => Some(ID::Building(b)),
+                PersonState::Trip(t) => app.primary.sim.trip_to_agent(t).ok().map(ID::from_agent),
+                _ => None,
+            },
             Tab::BusStatus(c) => Some(ID::Car(*c)),
             Tab::BusStop(bs) => Some(ID::BusStop(*bs)),
-            Tab::BusRoute(_) => None,
+            Tab::BusRoute(_) | Tab::BusRouteLive(_) => None,
             // TODO If a parked car becomes in use while the panel is open, should update the
             // panel better.
             Tab::ParkedCar(c) => match app.primary.sim.lookup_parked_car(*c)?.spot {
@@ -223,6 +237,17 @@ impl Tab {
     fn changed_settings(&self, c: &Panel) -> Option<Tab> {
         // Avoid an occasionally expensive clone.
         match self {
+            Tab::PersonTrips(_, open_trips) => {
+                if open_trips
+                    .iter()
+                    .all(|(id, trip)| match replay_pct(c, *id) {
+                        Some(pct) => Some(pct) == trip.replay_pct,
+                        None => true,
+                    })
+                {
+                    return None;
+                }
+            }
             Tab::IntersectionTraffic(_, _)
             | Tab::IntersectionDelay(_, _, _)
             | Tab::IntersectionArrivals(_, _)
@@ -234,6 +259,13 @@ impl Tab {
 
         let mut new_tab = self.clone();
         match new_tab {
+            Tab::PersonTrips(_, ref mut open_trips) => {
+                for (id, trip) in open_trips.iter_mut() {
+                    if let Some(pct) = replay_pct(c, *id) {
+                        trip.replay_pct = Some(pct);
+                    }
+                }
+            }
             Tab::IntersectionTraffic(_, ref mut opts)
             | Tab::IntersectionArrivals(_, ref mut opts)
             | Tab::LaneTraffic(_, ref mut opts) => {
@@ -262,9 +294,12 @@ impl Tab {
             Tab::PersonTrips(_, _) => ("person", "trips"),
             Tab::PersonBio(_) => ("person", "bio"),
             Tab::PersonSchedule(_) => ("person", "schedule"),
+            Tab::PersonHousehold(_) => ("person", "household"),
+            Tab::PersonExposures(_) => ("person", "exposures"),
             Tab::BusStatus(_) => ("bus", "status"),
             Tab::BusStop(_) => ("bus stop", "info"),
             Tab::BusRoute(_) => ("bus route", "info"),
+            Tab::BusRouteLive(_) => ("bus route", "live"),
             Tab::ParkedCar(_) => ("parked car", "info"),
             Tab::BldgInfo(_) => ("bldg", "info"),
             Tab::BldgPeople(_) => ("bldg", "people"),
@@ -332,9 +367,18 @@ impl InfoPanel {
                 person::schedule(ctx, app, &mut details, p, ctx_actions.is_paused()),
                 false,
             ),
+            Tab::PersonHousehold(p) => (
+                person::household(ctx, app, &mut details, p, ctx_actions.is_paused()),
+                false,
+            ),
+            Tab::PersonExposures(p) => (
+                person::exposures(ctx, app, &mut details, p, ctx_actions.is_paused()),
+                false,
+            ),
             Tab::BusStatus(c) => (bus::bus_status(ctx, app, &mut details, c), true),
             Tab::BusStop(bs) => (bus::stop(ctx, app, &mut details, bs), true),
             Tab::BusRoute(br) => (bus::route(ctx, app, &mut details, br), true),
+            Tab::BusRouteLive(br) => (bus::route_live(ctx, app, &mut details, br), false),
             Tab::ParkedCar(c) => (
                 person::parked_car(ctx, app, &mut details, c, ctx_actions.is_paused()),
                 true,
@@ -464,6 +508,8 @@ impl InfoPanel {
             warpers: details.warpers,
             time_warpers: details.time_warpers,
             cached_actions,
+            following: false,
+            follow: None,
         }
     }
 
@@ -482,10 +528,14 @@ impl InfoPanel {
             return (true, None);
         }
 
+        self.update_follow(ctx, app, ctx_actions);
+
         // Live update?
         if app.primary.sim.time() != self.time || ctx_actions.is_paused() != self.is_paused {
             let mut new = InfoPanel::new(ctx, app, self.tab.clone(), ctx_actions);
             new.panel.restore(ctx, &self.panel);
+            new.following = self.following;
+            new.follow = self.follow.take();
             *self = new;
             return (false, None);
         }
@@ -604,6 +654,11 @@ impl InfoPanel {
                         ))),
                     )
                 } else if let Some(id) = maybe_id {
+                    if action == "follow (run the simulation)" {
+                        self.following = true;
+                    } else if action == "unfollow (pause the simulation)" {
+                        self.following = false;
+                    }
                     let mut close_panel = true;
                     let t = ctx_actions.execute(ctx, app, id, action, &mut close_panel);
                     (close_panel, Some(t))
@@ -632,6 +687,48 @@ impl InfoPanel {
         }
     }
 
+    /// After the player clicks the follow button, the camera tracks the agent until the
+    /// simulation is paused.
+    fn update_follow(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &App,
+        ctx_actions: &mut dyn ContextualActions,
+    ) {
+        if ctx_actions.is_paused() {
+            self.following = false;
+        }
+        let follow_id = if !self.following {
+            None
+        } else {
+            match self.tab {
+                Tab::PersonTrips(_, _)
+                | Tab::PersonBio(_)
+                | Tab::PersonSchedule(_)
+                | Tab::PersonHousehold(_)
+                | Tab::PersonExposures(_)
+                | Tab::BusStatus(_) => self.tab.to_id(app),
+                // Parked cars don't go anywhere
+                _ => None,
+            }
+        };
+        let id = match follow_id {
+            Some(id) => id,
+            None => {
+                self.follow = None;
+                return;
+            }
+        };
+
+        if let Some(ref mut follow) = self.follow {
+            if !follow.event(ctx, app, &id) {
+                self.follow = None;
+            }
+        } else {
+            self.follow = FollowCamera::new(ctx, app, &id);
+        }
+    }
+
     pub fn draw(&self, g: &mut GfxCtx, _: &App) {
         self.panel.draw(g);
         self.draw_extra.draw(g);
@@ -732,6 +829,16 @@ fn make_tabs(
     Widget::row(row).margin_above(16)
 }
 
+/// If the trip's replay slider is showing, how far along is it?
+fn replay_pct(c: &Panel, trip: TripID) -> Option<f64> {
+    let name = format!("replay {}", trip);
+    if c.has_widget(&name) {
+        Some(c.slider(&name).get_percent())
+    } else {
+        None
+    }
+}
+
 fn header_btns(ctx: &EventCtx) -> Widget {
     Widget::row(vec![
         ctx.style()
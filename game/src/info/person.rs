@@ -5,10 +5,10 @@ use rand::{Rng, SeedableRng};
 use rand_xorshift::XorShiftRng;
 
 use geom::{Angle, Duration, Time};
-use map_model::Map;
+use map_model::{BuildingID, Map};
 use sim::{
-    AgentID, CarID, ParkingSpot, PedestrianID, Person, PersonID, PersonState, TripEndpoint, TripID,
-    TripMode, TripResult, VehicleType,
+    AgentID, CarID, ExposureLocation, ParkingSpot, PedestrianID, Person, PersonID, PersonState,
+    Sim, TripEndpoint, TripID, TripMode, TripResult, VehicleType,
 };
 use widgetry::{
     include_labeled_bytes, Color, ControlState, CornerRounding, EdgeInsets, EventCtx, GeomBatch,
@@ -74,7 +74,7 @@ fn trips_body(
                 if sim.time() > sim.trip_info(*t).departure {
                     (
                         "delayed start",
-                        Color::YELLOW,
+                        app.cs.trip_delayed,
                         open_trips
                             .get_mut(t)
                             .map(|open_trip| trip::future(ctx, app, *t, open_trip, details)),
@@ -82,7 +82,7 @@ fn trips_body(
                 } else {
                     (
                         "future",
-                        Color::hex("#4CA7E9"),
+                        app.cs.trip_future,
                         open_trips
                             .get_mut(t)
                             .map(|open_trip| trip::future(ctx, app, *t, open_trip, details)),
@@ -94,7 +94,7 @@ fn trips_body(
                 wheres_waldo = false;
                 (
                     "ongoing",
-                    Color::hex("#7FFA4D"),
+                    app.cs.trip_ongoing,
                     open_trips
                         .get_mut(t)
                         .map(|open_trip| trip::ongoing(ctx, app, *t, a, open_trip, details)),
@@ -106,7 +106,7 @@ fn trips_body(
                 wheres_waldo = false;
                 (
                     "ongoing",
-                    Color::hex("#7FFA4D"),
+                    app.cs.trip_ongoing,
                     open_trips.get(t).map(|_| Widget::nothing()),
                 )
             }
@@ -114,7 +114,7 @@ fn trips_body(
                 assert!(wheres_waldo);
                 (
                     "finished",
-                    Color::hex("#A3A3A3"),
+                    app.cs.trip_finished,
                     if open_trips.contains_key(t) {
                         Some(trip::finished(ctx, app, id, open_trips, *t, details))
                     } else {
@@ -126,7 +126,7 @@ fn trips_body(
                 // Cancelled trips can happen anywhere in the schedule right now
                 (
                     "cancelled",
-                    app.cs.signal_banned_turn,
+                    app.cs.trip_cancelled,
                     open_trips
                         .get_mut(t)
                         .map(|open_trip| trip::cancelled(ctx, app, *t, open_trip, details)),
@@ -188,7 +188,7 @@ fn trips_body(
                     .and_then(|_| app.prebaked().finished_trip_time(*t))
                 {
                     let (after, _, _) = app.primary.sim.finished_trip_details(*t).unwrap();
-                    Text::from(cmp_duration_shorter(after, before))
+                    Text::from(cmp_duration_shorter(app, after, before))
                         .batch(ctx)
                         .centered_vert()
                 } else {
@@ -460,6 +460,203 @@ fn schedule_body(ctx: &mut EventCtx, app: &App, id: PersonID) -> Widget {
     Widget::col(rows)
 }
 
+pub fn exposures(
+    ctx: &mut EventCtx,
+    app: &App,
+    details: &mut Details,
+    id: PersonID,
+    is_paused: bool,
+) -> Widget {
+    Widget::custom_col(vec![
+        header(ctx, app, details, id, Tab::PersonExposures(id), is_paused),
+        exposures_body(ctx, app, details, id).tab_body(ctx),
+    ])
+}
+
+fn exposures_body(ctx: &mut EventCtx, app: &App, details: &mut Details, id: PersonID) -> Widget {
+    let mut rows = vec![];
+    for (time, location, exposed, source) in &app.primary.sim.get_analytics().detailed.exposures {
+        let (other, verb) = if *exposed == id {
+            (*source, "Exposed by")
+        } else if *source == id {
+            (*exposed, "Exposed")
+        } else {
+            continue;
+        };
+        let where_ = match location {
+            ExposureLocation::Building(b) => app.primary.map.get_b(*b).address.clone(),
+            ExposureLocation::BusStop(bs) => app.primary.map.get_bs(*bs).name.clone(),
+            ExposureLocation::Bus(bus) => format!("riding {}", bus),
+        };
+        let label = other.to_string();
+        // Only link to each person once; buttons need unique names
+        let person = if details.hyperlinks.contains_key(&label) {
+            label.text_widget(ctx).centered_vert()
+        } else {
+            let btn = ctx.style().btn_outline.text(&label).build_def(ctx);
+            details
+                .hyperlinks
+                .insert(label, Tab::PersonTrips(other, BTreeMap::new()));
+            btn
+        };
+        rows.push(Widget::row(vec![
+            verb.text_widget(ctx).centered_vert(),
+            person,
+            format!("at {}, {}", time.ampm_tostring(), where_)
+                .text_widget(ctx)
+                .centered_vert(),
+        ]));
+    }
+    if rows.is_empty() {
+        rows.push("No exposures so far".text_widget(ctx));
+    }
+    Widget::col(rows)
+}
+
+pub fn household(
+    ctx: &mut EventCtx,
+    app: &mut App,
+    details: &mut Details,
+    id: PersonID,
+    is_paused: bool,
+) -> Widget {
+    // The tab is rebuilt constantly while the simulation runs, so don't look through everybody
+    // every time
+    let (finished, unfinished) = app.primary.sim.num_trips();
+    let key = (
+        app.primary.map.get_name().clone(),
+        app.primary.sim.get_run_name().clone(),
+        finished + unfinished,
+    );
+    let sim = &app.primary.sim;
+    app.session
+        .households
+        .update(Some(key), |_| index_households(sim));
+
+    let app = &*app;
+    Widget::custom_col(vec![
+        header(ctx, app, details, id, Tab::PersonHousehold(id), is_paused),
+        household_body(ctx, app, details, id).tab_body(ctx),
+    ])
+}
+
+fn household_body(ctx: &mut EventCtx, app: &App, details: &mut Details, id: PersonID) -> Widget {
+    let mut rows = vec![];
+    let home = match overnight_bldg(&app.primary.sim, id) {
+        Some(b) => b,
+        None => {
+            return "Doesn't spend the night in a building on this map".text_widget(ctx);
+        }
+    };
+
+    let address = app.primary.map.get_b(home).address.clone();
+    rows.push(Widget::row(vec![
+        "Spends the night at".text_widget(ctx).centered_vert(),
+        ctx.style().btn_outline.text(&address).build_def(ctx),
+    ]));
+    details.hyperlinks.insert(address, Tab::BldgInfo(home));
+
+    let household: Vec<&Person> = app
+        .session
+        .households
+        .value()
+        .and_then(|households| households.get(&home))
+        .map(|people| {
+            people
+                .iter()
+                .map(|p| app.primary.sim.get_person(*p))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut cars = Vec::new();
+    let mut bikes = 0;
+    for person in &household {
+        for v in &person.vehicles {
+            if v.vehicle_type == VehicleType::Bike {
+                bikes += 1;
+            } else {
+                cars.push((v.id, person.id));
+            }
+        }
+    }
+    rows.push(
+        format!(
+            "{} people share {} cars and {} bikes",
+            household.len(),
+            cars.len(),
+            bikes
+        )
+        .text_widget(ctx)
+        .margin_vert(16),
+    );
+    for (car, owner) in cars {
+        if app.primary.sim.lookup_parked_car(car).is_some() {
+            let label = format!("{} (parked)", car);
+            rows.push(Widget::row(vec![
+                ctx.style().btn_outline.text(&label).build_def(ctx),
+                format!("owned by {}", owner)
+                    .text_widget(ctx)
+                    .centered_vert(),
+            ]));
+            details.hyperlinks.insert(label, Tab::ParkedCar(car));
+        } else {
+            rows.push(format!("{} (not parked), owned by {}", car, owner).text_widget(ctx));
+        }
+    }
+
+    if household.len() == 1 {
+        rows.push("Lives alone".text_widget(ctx));
+    } else {
+        rows.push(Line("Other members").small_heading().into_widget(ctx));
+    }
+    for person in household {
+        if person.id == id {
+            continue;
+        }
+        details.hyperlinks.insert(
+            person.id.to_string(),
+            Tab::PersonTrips(person.id, BTreeMap::new()),
+        );
+        let status = match person.state {
+            PersonState::Inside(b) if b == home => "home".to_string(),
+            PersonState::Inside(b) => format!("at {}", app.primary.map.get_b(b).address),
+            PersonState::Trip(t) => app.primary.sim.trip_info(t).mode.ongoing_verb().to_string(),
+            PersonState::OffMap => "off map".to_string(),
+        };
+        rows.push(Widget::row(vec![
+            ctx.style()
+                .btn_outline
+                .text(person.id.to_string())
+                .build_def(ctx),
+            format!("Currently {}", status)
+                .text_widget(ctx)
+                .centered_vert(),
+        ]));
+    }
+
+    Widget::col(rows)
+}
+
+/// The building where someone's last trip of the day ends
+fn overnight_bldg(sim: &Sim, id: PersonID) -> Option<BuildingID> {
+    let last_trip = sim.get_person(id).trips.last()?;
+    match sim.trip_info(*last_trip).end {
+        TripEndpoint::Bldg(b) => Some(b),
+        _ => None,
+    }
+}
+
+fn index_households(sim: &Sim) -> BTreeMap<BuildingID, Vec<PersonID>> {
+    let mut households = BTreeMap::new();
+    for person in sim.get_all_people() {
+        if let Some(b) = overnight_bldg(sim, person.id) {
+            households.entry(b).or_insert_with(Vec::new).push(person.id);
+        }
+    }
+    households
+}
+
 pub fn crowd(ctx: &EventCtx, app: &App, details: &mut Details, members: &[PedestrianID]) -> Widget {
     let header = Widget::custom_col(vec![
         Line("Pedestrian crowd").small_heading().into_widget(ctx),
@@ -534,7 +731,7 @@ pub fn parked_car(
                 ctx.style()
                     .btn_plain
                     .icon("system/assets/tools/location.svg")
-                    .image_color(Color::hex("#7FFA4D"), ControlState::Default)
+                    .image_color(app.cs.trip_ongoing, ControlState::Default)
                     .hotkey(Key::F)
                     .build_widget(ctx, "unfollow (pause the simulation)")
             },
@@ -627,6 +824,13 @@ fn header(
                     }
                     AgentID::Car(c) => match c.vehicle_type {
                         VehicleType::Car => ("driving", Some("system/assets/meters/car.svg")),
+                        VehicleType::Truck => {
+                            ("delivering freight", Some("system/assets/meters/car.svg"))
+                        }
+                        VehicleType::Emergency => (
+                            "responding to an emergency",
+                            Some("system/assets/meters/car.svg"),
+                        ),
                         VehicleType::Bike => ("biking", Some("system/assets/meters/bike.svg")),
                         VehicleType::Bus | VehicleType::Train => unreachable!(),
                     },
@@ -673,7 +877,7 @@ fn header(
                 ctx.style()
                     .btn_plain
                     .icon("system/assets/tools/location.svg")
-                    .image_color(Color::hex("#7FFA4D"), ControlState::Default)
+                    .image_color(app.cs.trip_ongoing, ControlState::Default)
                     .hotkey(Key::F)
                     .build_widget(ctx, "unfollow (pause the simulation)")
             },
@@ -690,7 +894,11 @@ fn header(
     let mut tabs = vec![
         ("Trips", Tab::PersonTrips(id, open_trips)),
         ("Bio", Tab::PersonBio(id)),
+        ("Household", Tab::PersonHousehold(id)),
     ];
+    if app.primary.sim.get_pandemic_model().is_some() {
+        tabs.push(("Exposures", Tab::PersonExposures(id)));
+    }
     if app.opts.dev {
         tabs.push(("Schedule", Tab::PersonSchedule(id)));
     }
@@ -712,17 +920,17 @@ fn current_status(ctx: &EventCtx, person: &Person, map: &Map) -> Widget {
 }
 
 // TODO Dedupe with the version in helpers
-fn cmp_duration_shorter(after: Duration, before: Duration) -> TextSpan {
+fn cmp_duration_shorter(app: &App, after: Duration, before: Duration) -> TextSpan {
     if after.epsilon_eq(before) {
         Line("no change").small()
     } else if after < before {
         Line(format!("{} faster", before - after))
             .small()
-            .fg(Color::GREEN)
+            .fg(app.cs.better)
     } else if after > before {
         Line(format!("{} slower", after - before))
             .small()
-            .fg(Color::RED)
+            .fg(app.cs.worse)
     } else {
         unreachable!()
     }
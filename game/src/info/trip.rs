@@ -1,17 +1,17 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 use maplit::btreemap;
 
-use geom::{Distance, Duration, Percent, Polygon, Pt2D, UnitFmt};
+use geom::{Circle, Distance, Duration, Percent, Polygon, Pt2D, Time, UnitFmt};
 use map_gui::ID;
-use map_model::{Map, Path, PathStep, Traversable};
+use map_model::{BusRouteID, BusStopID, Map, Path, PathStep, Traversable};
 use sim::{
     AgentID, Analytics, PersonID, Problem, TripEndpoint, TripID, TripInfo, TripMode, TripPhase,
-    TripPhaseType,
+    TripPhaseType, TripPurpose,
 };
 use widgetry::{
     Color, ControlState, DrawWithTooltips, EventCtx, GeomBatch, Line, LinePlot, PlotOptions,
-    RewriteColor, Series, Text, TextExt, Widget,
+    RewriteColor, Series, Slider, Text, TextExt, Widget,
 };
 
 use crate::app::App;
@@ -21,6 +21,9 @@ use crate::info::{make_table, Details, Tab};
 #[derive(Clone)]
 pub struct OpenTrip {
     pub show_after: bool,
+    /// How far through the trip to show the agent's recorded position, if the player is scrubbing
+    /// through the replay.
+    pub replay_pct: Option<f64>,
     // (unzoomed, zoomed). Indexed by order of TripPhase.
     cached_routes: Vec<Option<(Polygon, Vec<Polygon>)>>,
 }
@@ -28,7 +31,7 @@ pub struct OpenTrip {
 // Ignore cached_routes
 impl std::cmp::PartialEq for OpenTrip {
     fn eq(&self, other: &OpenTrip) -> bool {
-        self.show_after == other.show_after
+        self.show_after == other.show_after && self.replay_pct == other.replay_pct
     }
 }
 
@@ -40,6 +43,7 @@ impl OpenTrip {
     pub fn new() -> OpenTrip {
         OpenTrip {
             show_after: true,
+            replay_pct: None,
             cached_routes: Vec::new(),
         }
     }
@@ -125,6 +129,9 @@ pub fn ongoing(
         &trip,
         col_width,
     ));
+    col.extend(describe_emergency_response(
+        ctx, app, id, &trip, details, col_width,
+    ));
     {
         col.push(Widget::custom_row(vec![
             Widget::custom_row(vec![Line("Purpose").secondary().into_widget(ctx)])
@@ -254,6 +261,7 @@ pub fn finished(
             id,
             OpenTrip {
                 show_after: false,
+                replay_pct: None,
                 cached_routes: Vec::new(),
             },
         );
@@ -329,6 +337,27 @@ pub fn finished(
             waiting.to_string(&app.opts.units).text_widget(ctx),
         ]));
 
+        if let Some(cruising) = app
+            .primary
+            .sim
+            .get_analytics()
+            .detailed
+            .parking_cruising_time
+            .get(&id)
+        {
+            col.push(Widget::custom_row(vec![
+                Widget::custom_row(vec![Line("Cruising for parking")
+                    .secondary()
+                    .into_widget(ctx)])
+                .force_width_window_pct(ctx, col_width),
+                cruising.to_string(&app.opts.units).text_widget(ctx),
+            ]));
+        }
+
+        col.extend(describe_emergency_response(
+            ctx, app, id, &trip, details, col_width,
+        ));
+
         col.push(Widget::custom_row(vec![
             Widget::custom_row(vec![Line("Purpose").secondary().into_widget(ctx)])
                 .force_width_window_pct(ctx, col_width),
@@ -392,6 +421,69 @@ pub fn cancelled(
     Widget::col(col)
 }
 
+/// Emergency vehicles show how long they took to arrive. The traffic signals that changed for them
+/// are outlined on the map.
+fn describe_emergency_response(
+    ctx: &mut EventCtx,
+    app: &App,
+    id: TripID,
+    trip: &TripInfo,
+    details: &mut Details,
+    col_width: Percent,
+) -> Option<Widget> {
+    if !matches!(trip.purpose, TripPurpose::Emergency) {
+        return None;
+    }
+    let analytics = app.primary.sim.get_analytics();
+
+    let preempted: BTreeSet<_> = analytics
+        .detailed
+        .signal_preemptions
+        .get(&id)
+        .into_iter()
+        .flatten()
+        .map(|(_, i)| *i)
+        .collect();
+    for i in &preempted {
+        let polygon = app.primary.map.get_i(*i).polygon.clone();
+        details
+            .draw_extra
+            .unzoomed
+            .push(app.cs.emergency_vehicle_body.alpha(0.7), polygon.clone());
+        details
+            .draw_extra
+            .zoomed
+            .push(app.cs.emergency_vehicle_body.alpha(0.5), polygon);
+    }
+
+    let mut txt = Text::from(
+        match analytics
+            .detailed
+            .emergency_responses
+            .iter()
+            .find(|(_, trip, _)| *trip == id)
+        {
+            Some((_, _, response_time)) => Line(format!(
+                "Arrived after {}",
+                response_time.to_string(&app.opts.units)
+            )),
+            None => Line("Still on the way"),
+        },
+    );
+    txt.add_line(
+        Line(format!(
+            "{} traffic signals changed for it",
+            preempted.len()
+        ))
+        .secondary(),
+    );
+    Some(Widget::custom_row(vec![
+        Widget::custom_row(vec![Line("Response").secondary().into_widget(ctx)])
+            .force_width_window_pct(ctx, col_width),
+        txt.into_widget(ctx),
+    ]))
+}
+
 fn describe_problems(
     ctx: &mut EventCtx,
     analytics: &Analytics,
@@ -765,6 +857,11 @@ fn make_trip_details(
     };
 
     let timeline = make_timeline(ctx, app, trip_id, &phases, progress_along_path);
+    let transit_legs = if trip.mode == TripMode::Transit {
+        make_transit_legs(ctx, app, details, &phases, map_for_pathfinding)
+    } else {
+        Widget::nothing()
+    };
     let mut elevation = Vec::new();
     let mut path_impossible = false;
     for (idx, p) in phases.into_iter().enumerate() {
@@ -877,6 +974,7 @@ fn make_trip_details(
     if path_impossible {
         col.push("Map edits have disconnected the path taken before".text_widget(ctx));
     }
+    col.push(transit_legs);
     col.extend(elevation);
 
     let analytics = if app.has_prebaked().is_none() || open_trip.show_after {
@@ -884,10 +982,234 @@ fn make_trip_details(
     } else {
         app.prebaked()
     };
+    if let Some(traversals) = analytics.detailed.trip_traversals.get(&trip_id) {
+        col.push(make_replay(
+            ctx,
+            app,
+            trip_id,
+            open_trip.replay_pct,
+            details,
+            traversals,
+            trip.departure,
+            end_time.unwrap_or_else(|| sim.time()),
+        ));
+    }
     draw_problems(ctx, app, analytics, details, trip_id);
     Widget::col(col)
 }
 
+/// Breaks down a transit trip into walking to the stop, waiting, riding each vehicle, and waiting
+/// to transfer, linking to every stop and route along the way.
+fn make_transit_legs(
+    ctx: &mut EventCtx,
+    app: &App,
+    details: &mut Details,
+    phases: &[TripPhase],
+    map_for_pathfinding: &Map,
+) -> Widget {
+    let map = &app.primary.map;
+    let now = app.primary.sim.time();
+
+    let mut rows = vec![Line("Transit journey").small_heading().into_widget(ctx)];
+    let mut walking = Duration::ZERO;
+    let mut waiting = Duration::ZERO;
+    let mut riding = Duration::ZERO;
+    let mut transfers = 0;
+    let mut already_rode = false;
+    for (idx, p) in phases.iter().enumerate() {
+        let duration = p.end_time.unwrap_or(now) - p.start_time;
+        let mut row = match p.phase_type {
+            TripPhaseType::Walking => {
+                walking += duration;
+                match phases.get(idx + 1).map(|next| next.phase_type) {
+                    Some(TripPhaseType::WaitingForBus(_, stop)) => vec![
+                        if already_rode {
+                            "Walk to transfer at"
+                        } else {
+                            "Walk to"
+                        }
+                        .text_widget(ctx)
+                        .centered_vert(),
+                        stop_link(ctx, app, details, stop),
+                    ],
+                    _ => vec!["Walk to the destination".text_widget(ctx)],
+                }
+            }
+            TripPhaseType::WaitingForBus(route, stop) => {
+                waiting += duration;
+                if already_rode {
+                    transfers += 1;
+                }
+                vec![
+                    if already_rode {
+                        "Transfer wait at"
+                    } else {
+                        "Wait at"
+                    }
+                    .text_widget(ctx)
+                    .centered_vert(),
+                    stop_link(ctx, app, details, stop),
+                    "for".text_widget(ctx).centered_vert(),
+                    route_link(ctx, app, details, route),
+                ]
+            }
+            TripPhaseType::RidingBus(route, stop1, _) => {
+                riding += duration;
+                already_rode = true;
+                // The rider's path ends where they get off
+                let end_pos = p.path.as_ref().map(|path| path.get_req().end);
+                let stop2 = map
+                    .get_br(route)
+                    .stops
+                    .iter()
+                    .find(|bs| Some(map_for_pathfinding.get_bs(**bs).driving_pos) == end_pos);
+                let mut row = vec![
+                    "Ride".text_widget(ctx).centered_vert(),
+                    route_link(ctx, app, details, route),
+                    "from".text_widget(ctx).centered_vert(),
+                    stop_link(ctx, app, details, stop1),
+                ];
+                if let Some(stop2) = stop2 {
+                    row.push("to".text_widget(ctx).centered_vert());
+                    row.push(stop_link(ctx, app, details, *stop2));
+                } else if end_pos.is_some() {
+                    row.push("off the map".text_widget(ctx).centered_vert());
+                }
+                row
+            }
+            _ => {
+                continue;
+            }
+        };
+        row.insert(
+            0,
+            if p.end_time.is_some() {
+                Line(duration.to_string(&app.opts.units))
+            } else {
+                Line(format!("{} so far", duration.to_string(&app.opts.units)))
+            }
+            .secondary()
+            .into_widget(ctx)
+            .container()
+            .force_width_window_pct(ctx, Percent::int(7))
+            .centered_vert(),
+        );
+        rows.push(Widget::custom_row(row));
+    }
+
+    rows.push(
+        Text::from(
+            Line(format!(
+                "{} walking, {} waiting, {} riding, {} transfer{}",
+                walking.to_string(&app.opts.units),
+                waiting.to_string(&app.opts.units),
+                riding.to_string(&app.opts.units),
+                transfers,
+                if transfers == 1 { "" } else { "s" }
+            ))
+            .secondary(),
+        )
+        .wrap_to_pct(ctx, 20)
+        .into_widget(ctx),
+    );
+    Widget::col(rows)
+}
+
+fn stop_link(ctx: &mut EventCtx, app: &App, details: &mut Details, stop: BusStopID) -> Widget {
+    let bs = app.primary.map.get_bs(stop);
+    // Stop names can repeat, so include the ID
+    let action = format!("{} ({})", bs.name, bs.id);
+    details
+        .hyperlinks
+        .insert(action.clone(), Tab::BusStop(stop));
+    ctx.style()
+        .btn_outline
+        .text(&bs.name)
+        .build_widget(ctx, action)
+}
+
+fn route_link(ctx: &mut EventCtx, app: &App, details: &mut Details, route: BusRouteID) -> Widget {
+    let r = app.primary.map.get_br(route);
+    let action = format!("Route {} ({})", r.short_name, r.id);
+    details
+        .hyperlinks
+        .insert(action.clone(), Tab::BusRoute(route));
+    ctx.style()
+        .btn_outline
+        .text(format!("Route {}", r.short_name))
+        .build_widget(ctx, action)
+}
+
+/// Lets the player drag through the trip and see where the agent was, based on when they entered
+/// each lane and turn.
+fn make_replay(
+    ctx: &mut EventCtx,
+    app: &App,
+    trip_id: TripID,
+    replay_pct: Option<f64>,
+    details: &mut Details,
+    traversals: &[(Time, Traversable)],
+    start_time: Time,
+    end_time: Time,
+) -> Widget {
+    // The panel is rebuilt while dragging, to move the agent
+    let slider = Slider::restorable_area(
+        ctx,
+        0.15 * ctx.canvas.window_width,
+        replay_pct.unwrap_or(0.0),
+        &format!("replay {}", trip_id),
+    );
+    let pct = match replay_pct {
+        Some(pct) => pct,
+        None => {
+            return Widget::row(vec!["Replay".text_widget(ctx).centered_vert(), slider]);
+        }
+    };
+    let time = start_time + pct * (end_time - start_time);
+
+    // Before the agent first moves, show them at the start of the first lane
+    let idx = traversals
+        .iter()
+        .rposition(|(t, _)| *t <= time)
+        .unwrap_or(0);
+    let (entered, on) = traversals[idx];
+    // TODO Riding transit isn't recorded for the passenger, so this time includes the ride
+    let left = traversals.get(idx + 1).map(|(t, _)| *t).unwrap_or(end_time);
+    let pct_along = if left > entered {
+        ((time - entered) / (left - entered)).max(0.0).min(1.0)
+    } else {
+        1.0
+    };
+    let pl = on.get_polyline(&app.primary.map);
+    if let Ok((pt, _)) = pl.dist_along(pct_along * pl.length()) {
+        details.draw_extra.unzoomed.push(
+            Color::YELLOW,
+            Circle::new(pt, Distance::meters(10.0)).to_polygon(),
+        );
+        details.draw_extra.zoomed.push(
+            Color::YELLOW.alpha(0.8),
+            Circle::new(pt, Distance::meters(3.0)).to_polygon(),
+        );
+    }
+
+    Widget::col(vec![
+        Widget::row(vec![
+            "Replay".text_widget(ctx).centered_vert(),
+            slider,
+            time.ampm_tostring().text_widget(ctx).centered_vert(),
+        ]),
+        Text::from(
+            Line(format!(
+                "Spent {} on {}",
+                (left - entered).to_string(&app.opts.units),
+                on
+            ))
+            .secondary(),
+        )
+        .into_widget(ctx),
+    ])
+}
+
 fn make_elevation(
     ctx: &EventCtx,
     color: Color,
@@ -0,0 +1,100 @@
+use abstutil::prettyprint_usize;
+use geom::{Circle, Distance, Duration, Pt2D};
+use map_gui::tools::ColorLegend;
+use widgetry::mapspace::ToggleZoomed;
+use widgetry::{EventCtx, GfxCtx, Panel, TextExt, Widget};
+
+use crate::app::App;
+use crate::layer::{header, Layer, LayerOutcome, PANEL_PLACEMENT};
+use crate::sandbox::dashboards::ABTestResults;
+
+/// Trips whose duration changes by less than this are considered unaffected.
+const THRESHOLD: Duration = Duration::const_seconds(60.0);
+
+/// Shows where the trips that got faster or slower in an A/B test start.
+pub struct ABTestLayer {
+    draw: ToggleZoomed,
+    panel: Panel,
+}
+
+impl Layer for ABTestLayer {
+    fn name(&self) -> Option<&'static str> {
+        Some("A/B test")
+    }
+    fn event(&mut self, ctx: &mut EventCtx, _: &mut App) -> Option<LayerOutcome> {
+        <dyn Layer>::simple_event(ctx, &mut self.panel)
+    }
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.panel.draw(g);
+        self.draw.draw(g);
+    }
+    fn draw_minimap(&self, g: &mut GfxCtx) {
+        g.redraw(&self.draw.unzoomed);
+    }
+}
+
+impl ABTestLayer {
+    pub fn new(ctx: &mut EventCtx, app: &App) -> ABTestLayer {
+        let faster_color = app.cs.better.alpha(0.8);
+        let slower_color = app.cs.worse.alpha(0.8);
+
+        let mut draw = ToggleZoomed::builder();
+        let mut faster = 0;
+        let mut slower = 0;
+        let mut same = 0;
+        let mut col = vec![header(ctx, "A/B test")];
+        if let Some(results) = ABTestResults::get(app) {
+            let unzoomed_circle =
+                Circle::new(Pt2D::new(0.0, 0.0), Distance::meters(10.0)).to_polygon();
+            let zoomed_circle =
+                Circle::new(Pt2D::new(0.0, 0.0), Distance::meters(3.0)).to_polygon();
+            for trip in &results.trips {
+                let color = if trip.duration_b + THRESHOLD < trip.duration_a {
+                    faster += 1;
+                    faster_color
+                } else if trip.duration_b > trip.duration_a + THRESHOLD {
+                    slower += 1;
+                    slower_color
+                } else {
+                    same += 1;
+                    continue;
+                };
+                let pt = trip.start.pt(&app.primary.map);
+                draw.unzoomed
+                    .push(color, unzoomed_circle.translate(pt.x(), pt.y()));
+                draw.zoomed
+                    .push(color, zoomed_circle.translate(pt.x(), pt.y()));
+            }
+
+            col.push("Where trips start, comparing B to A".text_widget(ctx));
+            col.push(ColorLegend::row(
+                ctx,
+                faster_color,
+                format!(
+                    "{} trips at least {} faster",
+                    prettyprint_usize(faster),
+                    THRESHOLD.to_string(&app.opts.units)
+                ),
+            ));
+            col.push(ColorLegend::row(
+                ctx,
+                slower_color,
+                format!(
+                    "{} trips at least {} slower",
+                    prettyprint_usize(slower),
+                    THRESHOLD.to_string(&app.opts.units)
+                ),
+            ));
+            col.push(format!("{} trips about the same", prettyprint_usize(same)).text_widget(ctx));
+        } else {
+            col.push("Run an A/B test from the data dashboards first".text_widget(ctx));
+        }
+
+        ABTestLayer {
+            draw: draw.build(ctx),
+            panel: Panel::new_builder(Widget::col(col))
+                .aligned_pair(PANEL_PLACEMENT)
+                .build(ctx),
+        }
+    }
+}
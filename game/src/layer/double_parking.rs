@@ -0,0 +1,88 @@
+use std::collections::BTreeMap;
+
+use abstutil::prettyprint_usize;
+use geom::Duration;
+use map_gui::tools::{ColorLegend, ColorNetwork};
+use map_gui::ID;
+use map_model::RoadID;
+use widgetry::mapspace::ToggleZoomed;
+use widgetry::{EventCtx, Line, Panel, Text, Widget};
+
+use crate::app::App;
+use crate::layer::hourly::{hour_dropdown, time_range, HourlyStats};
+use crate::layer::{header, PANEL_PLACEMENT};
+
+/// Shows roads where delivery and ride-hail vehicles double-parked because there was nowhere to
+/// pull over, colored by how long they blocked a lane.
+pub struct DoubleParking {
+    per_road: BTreeMap<RoadID, Duration>,
+}
+
+impl HourlyStats for DoubleParking {
+    type Options = ();
+
+    const NAME: &'static str = "double parking";
+
+    fn new(
+        ctx: &mut EventCtx,
+        app: &App,
+        hour: Option<usize>,
+        _: &(),
+    ) -> (DoubleParking, ToggleZoomed, Panel) {
+        let (t1, t2) = time_range(app, hour);
+        let per_road: BTreeMap<RoadID, Duration> = app
+            .primary
+            .sim
+            .get_analytics()
+            .double_parking_per_road(t1, t2)
+            .into_iter()
+            .collect();
+
+        let max = per_road.values().max().cloned().unwrap_or(Duration::ZERO);
+        let mut colorer = ColorNetwork::new(app);
+        for (r, total) in &per_road {
+            colorer.add_r(*r, app.cs.good_to_bad_red.eval(*total / max));
+        }
+
+        let panel = Panel::new_builder(Widget::col(vec![
+            header(ctx, "Double parking"),
+            Text::from_multiline(vec![
+                Line(
+                    "Roads where delivery and ride-hail vehicles stopped in a travel lane, \
+                     because there was no free spot to pull into",
+                )
+                .secondary(),
+                Line(format!(
+                    "{} roads affected, blocked for {} in total",
+                    prettyprint_usize(per_road.len()),
+                    per_road.values().cloned().sum::<Duration>()
+                )),
+            ])
+            .wrap_to_pct(ctx, 15)
+            .into_widget(ctx),
+            hour_dropdown(ctx, app, hour),
+            ColorLegend::gradient(
+                ctx,
+                &app.cs.good_to_bad_red,
+                vec!["0".to_string(), max.to_string()],
+            ),
+        ]))
+        .aligned_pair(PANEL_PLACEMENT)
+        .build(ctx);
+
+        (DoubleParking { per_road }, colorer.build(ctx), panel)
+    }
+
+    fn options(_: &Panel) {}
+
+    fn tooltip(&self, _: &App, id: ID) -> Option<Text> {
+        if let ID::Road(r) = id {
+            let total = self.per_road.get(&r)?;
+            return Some(Text::from(Line(format!(
+                "A lane was blocked by double-parking for {}",
+                total
+            ))));
+        }
+        None
+    }
+}
@@ -0,0 +1,129 @@
+use std::collections::BTreeMap;
+
+use geom::Distance;
+use map_gui::tools::{ColorLegend, ColorNetwork};
+use map_gui::ID;
+use map_model::RoadID;
+use sim::Emissions;
+use widgetry::mapspace::ToggleZoomed;
+use widgetry::{EventCtx, Line, Panel, Text, TextExt, Toggle, Widget};
+
+use crate::app::App;
+use crate::layer::hourly::{hour_dropdown, HourlyStats};
+use crate::layer::{header, PANEL_PLACEMENT};
+
+/// Colors roads by the estimated emissions of vehicles driving on them, per kilometer of road.
+pub struct EmissionsLayer {
+    per_road: BTreeMap<RoadID, Emissions>,
+}
+
+#[derive(Clone, PartialEq)]
+pub struct Options {
+    /// CO2 if true, NOx if false
+    pub co2: bool,
+}
+
+impl Options {
+    fn grams(&self, emissions: Emissions) -> f64 {
+        if self.co2 {
+            emissions.co2
+        } else {
+            emissions.nox
+        }
+    }
+}
+
+impl HourlyStats for EmissionsLayer {
+    type Options = Options;
+
+    const NAME: &'static str = "emissions";
+
+    fn new(
+        ctx: &mut EventCtx,
+        app: &App,
+        hour: Option<usize>,
+        opts: &Options,
+    ) -> (EmissionsLayer, ToggleZoomed, Panel) {
+        let analytics = app.primary.sim.get_analytics();
+        let per_road = analytics.detailed.emissions.per_road(hour);
+
+        // Longer roads naturally accumulate more, so compare the intensity per kilometer
+        let intensity = |r: RoadID, emissions: Emissions| {
+            let km = app.primary.map.get_r(r).length() / Distance::meters(1000.0);
+            opts.grams(emissions) / km.max(0.001)
+        };
+        let max = per_road
+            .iter()
+            .map(|(r, emissions)| intensity(*r, *emissions))
+            .fold(0.0, f64::max);
+        let mut colorer = ColorNetwork::new(app);
+        if max > 0.0 {
+            for (r, emissions) in &per_road {
+                colorer.add_r(
+                    *r,
+                    app.cs.good_to_bad_red.eval(intensity(*r, *emissions) / max),
+                );
+            }
+        }
+
+        let total = per_road
+            .values()
+            .fold(Emissions::ZERO, |sum, emissions| sum + *emissions);
+        let pollutant = if opts.co2 { "CO2" } else { "NOx" };
+
+        let panel = Panel::new_builder(Widget::col(vec![
+            header(ctx, "Emissions"),
+            Text::from(
+                Line("Estimated tailpipe emissions from simulated driving, based on speed")
+                    .secondary(),
+            )
+            .wrap_to_pct(ctx, 15)
+            .into_widget(ctx),
+            Toggle::choice(ctx, "pollutant", "CO2", "NOx", None, opts.co2),
+            hour_dropdown(ctx, app, hour),
+            format!(
+                "{} of {} total",
+                describe_grams(opts.grams(total)),
+                pollutant
+            )
+            .text_widget(ctx),
+            ColorLegend::gradient(
+                ctx,
+                &app.cs.good_to_bad_red,
+                vec!["0".to_string(), format!("{} per km", describe_grams(max))],
+            ),
+        ]))
+        .aligned_pair(PANEL_PLACEMENT)
+        .build(ctx);
+
+        (EmissionsLayer { per_road }, colorer.build(ctx), panel)
+    }
+
+    fn options(panel: &Panel) -> Options {
+        Options {
+            co2: panel.is_checked("pollutant"),
+        }
+    }
+
+    fn tooltip(&self, _: &App, id: ID) -> Option<Text> {
+        if let ID::Road(r) = id {
+            let emissions = self.per_road.get(&r)?;
+            return Some(Text::from_multiline(vec![
+                Line(format!("CO2: {}", describe_grams(emissions.co2))),
+                Line(format!("NOx: {}", describe_grams(emissions.nox))),
+            ]));
+        }
+        None
+    }
+}
+
+/// Rounds to grams, kilograms, or metric tons.
+pub fn describe_grams(grams: f64) -> String {
+    if grams < 1000.0 {
+        format!("{:.0} g", grams)
+    } else if grams < 1_000_000.0 {
+        format!("{:.1} kg", grams / 1000.0)
+    } else {
+        format!("{:.1} t", grams / 1_000_000.0)
+    }
+}
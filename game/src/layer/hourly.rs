@@ -0,0 +1,149 @@
+use geom::{Duration, Time};
+use map_gui::ID;
+use widgetry::mapspace::ToggleZoomed;
+use widgetry::{Choice, EventCtx, GfxCtx, Outcome, Panel, Text, Widget};
+
+use crate::app::App;
+use crate::layer::{Layer, LayerOutcome};
+
+/// A layer summarizing some analytics per road or intersection, either for one hour of the day or
+/// for the whole day so far. The layer is rebuilt as the simulation advances, and shows a tooltip
+/// when hovering on something unzoomed.
+pub struct HourlyLayer<T: HourlyStats> {
+    time: Time,
+    /// Only show one hour of the day, or the whole day so far
+    hour: Option<usize>,
+    opts: T::Options,
+    stats: T,
+    tooltip: Option<Text>,
+    draw: ToggleZoomed,
+    panel: Panel,
+}
+
+/// The part of an `HourlyLayer` specific to one kind of data.
+pub trait HourlyStats: Sized + 'static {
+    /// Any settings in the panel besides the hour
+    type Options: Clone;
+
+    const NAME: &'static str;
+
+    /// Calculate everything for the hour (or the whole day so far), then build the colored map
+    /// and the panel. The panel should include `hour_dropdown`.
+    fn new(
+        ctx: &mut EventCtx,
+        app: &App,
+        hour: Option<usize>,
+        opts: &Self::Options,
+    ) -> (Self, ToggleZoomed, Panel);
+
+    /// Read the other settings from the panel after something changes.
+    fn options(panel: &Panel) -> Self::Options;
+
+    fn tooltip(&self, app: &App, id: ID) -> Option<Text>;
+
+    /// Handle any button besides "close".
+    fn on_click(_: &mut EventCtx, _: &mut App, _: &str) -> Option<LayerOutcome> {
+        unreachable!()
+    }
+}
+
+impl<T: HourlyStats> Layer for HourlyLayer<T> {
+    fn name(&self) -> Option<&'static str> {
+        Some(T::NAME)
+    }
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Option<LayerOutcome> {
+        let mut recalc_tooltip = false;
+        if app.primary.sim.time() != self.time {
+            *self = HourlyLayer::new(ctx, app, self.hour, self.opts.clone());
+            recalc_tooltip = true;
+        }
+
+        if ctx.canvas.is_unzoomed() {
+            if ctx.redo_mouseover() || recalc_tooltip {
+                self.tooltip = app
+                    .mouseover_unzoomed_roads_and_intersections(ctx)
+                    .and_then(|id| self.stats.tooltip(app, id));
+            }
+        } else {
+            self.tooltip = None;
+        }
+
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => {
+                    return Some(LayerOutcome::Close);
+                }
+                x => {
+                    return T::on_click(ctx, app, x);
+                }
+            },
+            Outcome::Changed(_) => {
+                return Some(LayerOutcome::Replace(Box::new(HourlyLayer::<T>::new(
+                    ctx,
+                    app,
+                    self.panel.dropdown_value("hour"),
+                    T::options(&self.panel),
+                ))));
+            }
+            _ => {}
+        }
+        None
+    }
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.panel.draw(g);
+        self.draw.draw(g);
+        if let Some(ref txt) = self.tooltip {
+            g.draw_mouse_tooltip(txt.clone());
+        }
+    }
+    fn draw_minimap(&self, g: &mut GfxCtx) {
+        g.redraw(&self.draw.unzoomed);
+    }
+}
+
+impl<T: HourlyStats> HourlyLayer<T> {
+    pub fn new(
+        ctx: &mut EventCtx,
+        app: &App,
+        hour: Option<usize>,
+        opts: T::Options,
+    ) -> HourlyLayer<T> {
+        let (stats, draw, panel) = T::new(ctx, app, hour, &opts);
+        HourlyLayer {
+            time: app.primary.sim.time(),
+            hour,
+            opts,
+            stats,
+            tooltip: None,
+            draw,
+            panel,
+        }
+    }
+}
+
+/// The start and end of the hour, or the whole day so far.
+pub fn time_range(app: &App, hour: Option<usize>) -> (Time, Time) {
+    match hour {
+        Some(hour) => (
+            Time::START_OF_DAY + Duration::hours(hour),
+            Time::START_OF_DAY + Duration::hours(hour + 1),
+        ),
+        None => (Time::START_OF_DAY, app.primary.sim.time()),
+    }
+}
+
+/// Picks any hour that's started so far, or the whole day.
+pub fn hour_dropdown(ctx: &EventCtx, app: &App, hour: Option<usize>) -> Widget {
+    let mut hours = vec![Choice::new("whole day so far", None)];
+    for hour in 0..=app.primary.sim.time().get_hours() {
+        hours.push(Choice::new(
+            format!(
+                "{} - {}",
+                (Time::START_OF_DAY + Duration::hours(hour)).ampm_tostring(),
+                (Time::START_OF_DAY + Duration::hours(hour + 1)).ampm_tostring()
+            ),
+            Some(hour),
+        ));
+    }
+    Widget::dropdown(ctx, "hour", hour, hours)
+}
@@ -0,0 +1,103 @@
+use std::collections::BTreeMap;
+
+use abstutil::prettyprint_usize;
+use geom::Duration;
+use map_gui::tools::{ColorLegend, ColorNetwork};
+use map_gui::ID;
+use map_model::IntersectionID;
+use sim::AgentType;
+use widgetry::mapspace::ToggleZoomed;
+use widgetry::{EventCtx, Line, Panel, Text, Widget};
+
+use crate::app::App;
+use crate::layer::hourly::{hour_dropdown, time_range, HourlyStats};
+use crate::layer::{header, PANEL_PLACEMENT};
+
+/// Colors traffic signals and stop signs by the average delay of vehicles passing through them.
+/// Clicking one opens its info panel, which breaks down the delay per stage or approach.
+pub struct IntersectionDelay {
+    /// (average delay, number of vehicles)
+    per_intersection: BTreeMap<IntersectionID, (Duration, usize)>,
+}
+
+impl HourlyStats for IntersectionDelay {
+    type Options = ();
+
+    const NAME: &'static str = "intersection delay";
+
+    fn new(
+        ctx: &mut EventCtx,
+        app: &App,
+        hour: Option<usize>,
+        _: &(),
+    ) -> (IntersectionDelay, ToggleZoomed, Panel) {
+        let (t1, t2) = time_range(app, hour);
+        let mut per_intersection = BTreeMap::new();
+        for (i, list) in &app.primary.sim.get_analytics().intersection_delays {
+            let mut total = Duration::ZERO;
+            let mut count = 0;
+            for (_, t, dt, agent_type) in list {
+                if *t < t1 || *agent_type == AgentType::Pedestrian {
+                    continue;
+                }
+                if *t > t2 {
+                    break;
+                }
+                total += *dt;
+                count += 1;
+            }
+            if count > 0 {
+                per_intersection.insert(*i, (total / (count as f64), count));
+            }
+        }
+
+        let max = per_intersection
+            .values()
+            .map(|(avg, _)| *avg)
+            .max()
+            .unwrap_or(Duration::ZERO);
+        let mut colorer = ColorNetwork::new(app);
+        if max > Duration::ZERO {
+            for (i, (avg, _)) in &per_intersection {
+                colorer.add_i(*i, app.cs.good_to_bad_red.eval(*avg / max));
+            }
+        }
+
+        let panel = Panel::new_builder(Widget::col(vec![
+            header(ctx, "Intersection delay"),
+            Text::from_multiline(vec![
+                Line("Average delay of vehicles at traffic signals and stop signs").secondary(),
+                Line("Click one to see which stages or approaches are responsible").secondary(),
+            ])
+            .wrap_to_pct(ctx, 15)
+            .into_widget(ctx),
+            hour_dropdown(ctx, app, hour),
+            ColorLegend::gradient(
+                ctx,
+                &app.cs.good_to_bad_red,
+                vec!["0".to_string(), max.to_string(&app.opts.units)],
+            ),
+        ]))
+        .aligned_pair(PANEL_PLACEMENT)
+        .build(ctx);
+
+        (
+            IntersectionDelay { per_intersection },
+            colorer.build(ctx),
+            panel,
+        )
+    }
+
+    fn options(_: &Panel) {}
+
+    fn tooltip(&self, app: &App, id: ID) -> Option<Text> {
+        if let ID::Intersection(i) = id {
+            let (avg, count) = self.per_intersection.get(&i)?;
+            return Some(Text::from_multiline(vec![
+                Line(format!("Average delay: {}", avg.to_string(&app.opts.units))),
+                Line(format!("{} vehicles", prettyprint_usize(*count))),
+            ]));
+        }
+        None
+    }
+}
@@ -6,15 +6,22 @@ use widgetry::{
 };
 
 use crate::app::{App, Transition};
+use crate::layer::hourly::HourlyLayer;
 use crate::sandbox::dashboards;
 
+pub mod ab_test;
+mod double_parking;
 pub mod elevation;
+pub mod emissions;
 pub mod favorites;
+pub mod hourly;
+mod intersection_delay;
 pub mod map;
 mod pandemic;
 mod parking;
 mod population;
 mod problems;
+mod spillback;
 pub mod traffic;
 pub mod transit;
 
@@ -79,6 +86,59 @@ impl PickLayer {
         None
     }
 
+    /// Every layer that can be chosen right now, grouped by category, with the hotkey to pick it.
+    pub fn all(app: &App) -> Vec<(&'static str, Vec<(&'static str, Key)>)> {
+        let mut experimental = vec![
+            ("amenities", Key::A),
+            ("backpressure", Key::Z),
+            ("steep streets", Key::V),
+            ("elevation", Key::G),
+            ("parking efficiency", Key::O),
+            ("blackholes", Key::L),
+            ("problem map", Key::K),
+            ("high stress", Key::H),
+        ];
+        if app.primary.sim.get_pandemic_model().is_some() {
+            experimental.push(("pandemic model", Key::Y));
+        }
+        let mut data = vec![
+            ("traffic signal demand", Key::M),
+            ("commuter patterns", Key::R),
+        ];
+        if dashboards::ABTestResults::get(app).is_some() {
+            data.push(("A/B test", Key::I));
+        }
+
+        vec![
+            (
+                "Traffic",
+                vec![
+                    ("delay", Key::D),
+                    ("intersection delay", Key::W),
+                    ("throughput", Key::T),
+                    ("traffic jams", Key::J),
+                    ("queue spillback", Key::Q),
+                    ("double parking", Key::Num1),
+                    ("cycling activity", Key::B),
+                    ("emissions", Key::C),
+                ],
+            ),
+            (
+                "Map",
+                vec![
+                    ("map edits", Key::E),
+                    ("parking occupancy", Key::P),
+                    ("transit network", Key::U),
+                    ("population map", Key::X),
+                    ("no sidewalks", Key::S),
+                    ("favorite buildings", Key::F),
+                ],
+            ),
+            ("Experimental", experimental),
+            ("Data", data),
+        ]
+    }
+
     pub fn pick(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
         let mut col = vec![Widget::custom_row(vec![
             Line("Layers").small_heading().into_widget(ctx),
@@ -100,54 +160,21 @@ impl PickLayer {
 
         col.push(btn("None", Key::N));
 
-        col.push(
-            Widget::custom_row(vec![
-                Widget::col(vec![
-                    "Traffic".text_widget(ctx),
-                    btn("delay", Key::D),
-                    btn("throughput", Key::T),
-                    btn("traffic jams", Key::J),
-                    btn("cycling activity", Key::B),
-                ]),
-                Widget::col(vec![
-                    "Map".text_widget(ctx),
-                    btn("map edits", Key::E),
-                    btn("parking occupancy", Key::P),
-                    btn("transit network", Key::U),
-                    btn("population map", Key::X),
-                    btn("no sidewalks", Key::S),
-                    btn("favorite buildings", Key::F),
-                ]),
-            ])
-            .evenly_spaced(),
-        );
-
-        col.push(
-            Widget::custom_row(vec![
-                Widget::col(vec![
-                    "Experimental".text_widget(ctx),
-                    btn("amenities", Key::A),
-                    btn("backpressure", Key::Z),
-                    btn("steep streets", Key::V),
-                    btn("elevation", Key::G),
-                    btn("parking efficiency", Key::O),
-                    btn("blackholes", Key::L),
-                    btn("problem map", Key::K),
-                    btn("high stress", Key::H),
-                    if app.primary.sim.get_pandemic_model().is_some() {
-                        btn("pandemic model", Key::Y)
-                    } else {
-                        Widget::nothing()
-                    },
-                ]),
-                Widget::col(vec![
-                    "Data".text_widget(ctx),
-                    btn("traffic signal demand", Key::M),
-                    btn("commuter patterns", Key::R),
-                ]),
-            ])
-            .evenly_spaced(),
-        );
+        // Two categories per row
+        let mut categories: Vec<Widget> = PickLayer::all(app)
+            .into_iter()
+            .map(|(category, layers)| {
+                let mut layer_col = vec![category.text_widget(ctx)];
+                for (name, key) in layers {
+                    layer_col.push(btn(name, key));
+                }
+                Widget::col(layer_col)
+            })
+            .collect();
+        while !categories.is_empty() {
+            let row: Vec<Widget> = categories.drain(..2.min(categories.len())).collect();
+            col.push(Widget::custom_row(row).evenly_spaced());
+        }
 
         Box::new(PickLayer {
             panel: Panel::new_builder(Widget::col(col))
@@ -155,108 +182,149 @@ impl PickLayer {
                 .build(ctx),
         })
     }
+
+    /// Activate a layer by name. Some choices open a dashboard instead, which is returned.
+    pub fn launch(ctx: &mut EventCtx, app: &mut App, name: &str) -> Option<Box<dyn State<App>>> {
+        match name {
+            "None" => {
+                app.primary.layer = None;
+            }
+            "amenities" => {
+                app.primary.layer = Some(Box::new(map::Static::amenities(ctx, app)));
+            }
+            "backpressure" => {
+                app.primary.layer = Some(Box::new(traffic::Backpressure::new(ctx, app)));
+            }
+            "cycling activity" => {
+                app.primary.layer = Some(Box::new(map::BikeActivity::new(ctx, app)));
+            }
+            "emissions" => {
+                app.primary.layer = Some(Box::new(HourlyLayer::<emissions::EmissionsLayer>::new(
+                    ctx,
+                    app,
+                    None,
+                    emissions::Options { co2: true },
+                )));
+            }
+            "delay" => {
+                app.primary.layer = Some(Box::new(traffic::Delay::new(ctx, app)));
+            }
+            "intersection delay" => {
+                // Clicking an intersection should show the breakdown of its delay
+                app.session.info_panel_tab.insert("intersection", "delay");
+                app.primary.layer = Some(Box::new(HourlyLayer::<
+                    intersection_delay::IntersectionDelay,
+                >::new(ctx, app, None, ())));
+            }
+            "steep streets" => {
+                app.primary.layer = Some(Box::new(elevation::SteepStreets::new(ctx, app)));
+            }
+            "elevation" => {
+                app.primary.layer = Some(Box::new(elevation::ElevationContours::new(ctx, app)));
+            }
+            "map edits" => {
+                app.primary.layer = Some(Box::new(map::Static::edits(ctx, app)));
+            }
+            "no sidewalks" => {
+                app.primary.layer = Some(Box::new(map::Static::no_sidewalks(ctx, app)));
+            }
+            "high stress" => {
+                app.primary.layer = Some(Box::new(map::Static::high_stress(ctx, app)));
+            }
+            "favorite buildings" => {
+                app.primary.layer = Some(Box::new(favorites::ShowFavorites::new(ctx, app)));
+            }
+            "pandemic model" => {
+                app.primary.layer = Some(Box::new(pandemic::Pandemic::new(
+                    ctx,
+                    app,
+                    pandemic::Options {
+                        heatmap: Some(HeatmapOptions::new()),
+                        state: pandemic::Seir::Infected,
+                        exposures: false,
+                    },
+                )));
+            }
+            "blackholes" => {
+                app.primary.layer = Some(Box::new(map::Static::blackholes(ctx, app)));
+            }
+            "parking occupancy" => {
+                app.primary.layer = Some(Box::new(parking::Occupancy::new(
+                    ctx, app, true, true, true, false, true,
+                )));
+            }
+            "parking efficiency" => {
+                app.primary.layer = Some(Box::new(parking::Efficiency::new(ctx, app)));
+            }
+            "population map" => {
+                app.primary.layer = Some(Box::new(population::PopulationMap::new(
+                    ctx,
+                    app,
+                    population::Options {
+                        heatmap: Some(HeatmapOptions::new()),
+                    },
+                )));
+            }
+            "problem map" => {
+                app.primary.layer = Some(Box::new(problems::ProblemMap::new(
+                    ctx,
+                    app,
+                    problems::Options::new(app),
+                )));
+            }
+            "throughput" => {
+                app.primary.layer = Some(Box::new(traffic::Throughput::new(
+                    ctx,
+                    app,
+                    AgentType::all().into_iter().collect(),
+                )));
+            }
+            "queue spillback" => {
+                app.primary.layer = Some(Box::new(HourlyLayer::<spillback::QueueSpillback>::new(
+                    ctx,
+                    app,
+                    None,
+                    (),
+                )));
+            }
+            "double parking" => {
+                app.primary.layer = Some(Box::new(
+                    HourlyLayer::<double_parking::DoubleParking>::new(ctx, app, None, ()),
+                ));
+            }
+            "traffic jams" => {
+                app.primary.layer = Some(Box::new(traffic::TrafficJams::new(ctx, app)));
+            }
+            "transit network" => {
+                app.primary.layer = Some(Box::new(transit::TransitNetwork::new(
+                    ctx, app, false, true, true,
+                )));
+            }
+            "traffic signal demand" => {
+                return Some(dashboards::TrafficSignalDemand::new_state(ctx, app));
+            }
+            "commuter patterns" => {
+                return Some(dashboards::CommuterPatterns::new_state(ctx, app));
+            }
+            "A/B test" => {
+                app.primary.layer = Some(Box::new(ab_test::ABTestLayer::new(ctx, app)));
+            }
+            _ => unreachable!(),
+        }
+        None
+    }
 }
 
 impl State<App> for PickLayer {
     fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
         match self.panel.event(ctx) {
-            Outcome::Clicked(x) => match x.as_ref() {
-                "close" => {}
-                "None" => {
-                    app.primary.layer = None;
-                }
-                "amenities" => {
-                    app.primary.layer = Some(Box::new(map::Static::amenities(ctx, app)));
-                }
-                "backpressure" => {
-                    app.primary.layer = Some(Box::new(traffic::Backpressure::new(ctx, app)));
-                }
-                "cycling activity" => {
-                    app.primary.layer = Some(Box::new(map::BikeActivity::new(ctx, app)));
-                }
-                "delay" => {
-                    app.primary.layer = Some(Box::new(traffic::Delay::new(ctx, app)));
-                }
-                "steep streets" => {
-                    app.primary.layer = Some(Box::new(elevation::SteepStreets::new(ctx, app)));
-                }
-                "elevation" => {
-                    app.primary.layer = Some(Box::new(elevation::ElevationContours::new(ctx, app)));
-                }
-                "map edits" => {
-                    app.primary.layer = Some(Box::new(map::Static::edits(ctx, app)));
+            Outcome::Clicked(x) => {
+                if x != "close" {
+                    if let Some(state) = PickLayer::launch(ctx, app, &x) {
+                        return Transition::Replace(state);
+                    }
                 }
-                "no sidewalks" => {
-                    app.primary.layer = Some(Box::new(map::Static::no_sidewalks(ctx, app)));
-                }
-                "high stress" => {
-                    app.primary.layer = Some(Box::new(map::Static::high_stress(ctx, app)));
-                }
-                "favorite buildings" => {
-                    app.primary.layer = Some(Box::new(favorites::ShowFavorites::new(ctx, app)));
-                }
-                "pandemic model" => {
-                    app.primary.layer = Some(Box::new(pandemic::Pandemic::new(
-                        ctx,
-                        app,
-                        pandemic::Options {
-                            heatmap: Some(HeatmapOptions::new()),
-                            state: pandemic::Seir::Infected,
-                        },
-                    )));
-                }
-                "blackholes" => {
-                    app.primary.layer = Some(Box::new(map::Static::blackholes(ctx, app)));
-                }
-                "parking occupancy" => {
-                    app.primary.layer = Some(Box::new(parking::Occupancy::new(
-                        ctx, app, true, true, true, false, true,
-                    )));
-                }
-                "parking efficiency" => {
-                    app.primary.layer = Some(Box::new(parking::Efficiency::new(ctx, app)));
-                }
-                "population map" => {
-                    app.primary.layer = Some(Box::new(population::PopulationMap::new(
-                        ctx,
-                        app,
-                        population::Options {
-                            heatmap: Some(HeatmapOptions::new()),
-                        },
-                    )));
-                }
-                "problem map" => {
-                    app.primary.layer = Some(Box::new(problems::ProblemMap::new(
-                        ctx,
-                        app,
-                        problems::Options::new(app),
-                    )));
-                }
-                "throughput" => {
-                    app.primary.layer = Some(Box::new(traffic::Throughput::new(
-                        ctx,
-                        app,
-                        AgentType::all().into_iter().collect(),
-                    )));
-                }
-                "traffic jams" => {
-                    app.primary.layer = Some(Box::new(traffic::TrafficJams::new(ctx, app)));
-                }
-                "transit network" => {
-                    app.primary.layer = Some(Box::new(transit::TransitNetwork::new(
-                        ctx, app, false, true, true,
-                    )));
-                }
-                "traffic signal demand" => {
-                    return Transition::Replace(dashboards::TrafficSignalDemand::new_state(
-                        ctx, app,
-                    ));
-                }
-                "commuter patterns" => {
-                    return Transition::Replace(dashboards::CommuterPatterns::new_state(ctx, app));
-                }
-                _ => unreachable!(),
-            },
+            }
             _ => {
                 if self.panel.clicked_outside(ctx) {
                     return Transition::Pop;
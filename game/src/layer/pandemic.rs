@@ -3,7 +3,7 @@ use std::collections::HashSet;
 use abstutil::prettyprint_usize;
 use geom::{Circle, Distance, Pt2D, Time};
 use map_gui::tools::{make_heatmap, HeatmapOptions};
-use sim::PersonState;
+use sim::{AgentID, ExposureLocation, PersonState};
 use widgetry::mapspace::ToggleZoomed;
 use widgetry::{
     Choice, Color, EventCtx, GfxCtx, Line, Outcome, Panel, Text, TextExt, Toggle, Widget,
@@ -107,6 +107,33 @@ impl Pandemic {
         }
 
         let mut draw = ToggleZoomed::builder();
+        if opts.exposures {
+            // Draw these first, so people show up on top
+            let marker = Circle::new(Pt2D::new(0.0, 0.0), Distance::meters(15.0)).to_polygon();
+            for (_, location, _, _) in &app.primary.sim.get_analytics().detailed.exposures {
+                let pt = match location {
+                    ExposureLocation::Building(b) => {
+                        Some(app.primary.map.get_b(*b).polygon.center())
+                    }
+                    ExposureLocation::BusStop(bs) => Some(
+                        app.primary
+                            .map
+                            .get_bs(*bs)
+                            .sidewalk_pos
+                            .pt(&app.primary.map),
+                    ),
+                    // Buses move, so the best we can do is show where the bus is now
+                    ExposureLocation::Bus(bus) => app
+                        .primary
+                        .sim
+                        .canonical_pt_for_agent(AgentID::Car(*bus), &app.primary.map),
+                };
+                if let Some(pt) = pt {
+                    draw.unzoomed
+                        .push(Color::PURPLE.alpha(0.5), marker.translate(pt.x(), pt.y()));
+                }
+            }
+        }
         let legend = if let Some(ref o) = opts.heatmap {
             pts.extend(repeat_pts);
             Some(make_heatmap(
@@ -143,6 +170,7 @@ impl Pandemic {
         Options {
             heatmap,
             state: self.panel.dropdown_value("seir"),
+            exposures: self.panel.is_checked("Show where exposures happened"),
         }
     }
 }
@@ -162,6 +190,7 @@ pub struct Options {
     // If None, just a dot map
     pub heatmap: Option<HeatmapOptions>,
     pub state: Seir,
+    pub exposures: bool,
 }
 
 fn make_controls(ctx: &mut EventCtx, app: &App, opts: &Options, legend: Option<Widget>) -> Panel {
@@ -196,6 +225,10 @@ fn make_controls(ctx: &mut EventCtx, app: &App, opts: &Options, legend: Option<W
                 prettyprint_usize(model.count_dead()),
                 (model.count_dead() as f64) * pct
             )),
+            Line(format!(
+                "{} exposures so far",
+                prettyprint_usize(app.primary.sim.get_analytics().detailed.exposures.len())
+            )),
         ])
         .into_widget(ctx),
         Widget::row(vec![
@@ -215,6 +248,12 @@ fn make_controls(ctx: &mut EventCtx, app: &App, opts: &Options, legend: Option<W
         ]),
     ];
 
+    col.push(Toggle::switch(
+        ctx,
+        "Show where exposures happened",
+        None,
+        opts.exposures,
+    ));
     col.push(Toggle::switch(
         ctx,
         "Show heatmap",
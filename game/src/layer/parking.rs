@@ -87,7 +87,7 @@ impl Occupancy {
             total_ppl += 1;
             if p.vehicles
                 .iter()
-                .any(|v| v.vehicle_type == VehicleType::Car)
+                .any(|v| v.vehicle_type != VehicleType::Bike)
             {
                 has_car += 1;
             }
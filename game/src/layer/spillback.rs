@@ -0,0 +1,110 @@
+use std::collections::BTreeMap;
+
+use abstutil::prettyprint_usize;
+use map_gui::tools::{ColorLegend, ColorNetwork};
+use map_gui::ID;
+use map_model::IntersectionID;
+use widgetry::mapspace::ToggleZoomed;
+use widgetry::{EventCtx, Line, Panel, Text, Widget};
+
+use crate::app::{App, Transition};
+use crate::layer::hourly::{hour_dropdown, time_range, HourlyStats};
+use crate::layer::{header, LayerOutcome, PANEL_PLACEMENT};
+use crate::sandbox::dashboards::DashTab;
+
+/// Warns about intersections where queues spilled back and vehicles blocked the box, colored by
+/// how often it happened.
+pub struct QueueSpillback {
+    per_intersection: BTreeMap<IntersectionID, usize>,
+}
+
+impl HourlyStats for QueueSpillback {
+    type Options = ();
+
+    const NAME: &'static str = "queue spillback";
+
+    fn new(
+        ctx: &mut EventCtx,
+        app: &App,
+        hour: Option<usize>,
+        _: &(),
+    ) -> (QueueSpillback, ToggleZoomed, Panel) {
+        let (t1, t2) = time_range(app, hour);
+        let per_intersection: BTreeMap<IntersectionID, usize> = app
+            .primary
+            .sim
+            .get_analytics()
+            .worst_queue_spillbacks(t1, t2)
+            .into_iter()
+            .collect();
+
+        let max = per_intersection.values().max().cloned().unwrap_or(0);
+        let mut colorer = ColorNetwork::new(app);
+        for (i, count) in &per_intersection {
+            colorer.add_i(
+                *i,
+                app.cs.good_to_bad_red.eval((*count as f64) / (max as f64)),
+            );
+        }
+
+        let panel = Panel::new_builder(Widget::col(vec![
+            header(ctx, "Queue spillback"),
+            Text::from_multiline(vec![
+                Line(
+                    "Intersections blocked by vehicles that entered before there was room on the \
+                     next lane",
+                )
+                .secondary(),
+                Line(format!(
+                    "{} intersections affected",
+                    prettyprint_usize(per_intersection.len())
+                )),
+            ])
+            .wrap_to_pct(ctx, 15)
+            .into_widget(ctx),
+            hour_dropdown(ctx, app, hour),
+            ColorLegend::gradient(
+                ctx,
+                &app.cs.good_to_bad_red,
+                vec!["1".to_string(), prettyprint_usize(max.max(1))],
+            ),
+            ctx.style()
+                .btn_outline
+                .text("Worst intersections")
+                .build_def(ctx),
+        ]))
+        .aligned_pair(PANEL_PLACEMENT)
+        .build(ctx);
+
+        (
+            QueueSpillback { per_intersection },
+            colorer.build(ctx),
+            panel,
+        )
+    }
+
+    fn options(_: &Panel) {}
+
+    fn tooltip(&self, _: &App, id: ID) -> Option<Text> {
+        if let ID::Intersection(i) = id {
+            let count = self.per_intersection.get(&i)?;
+            return Some(Text::from(Line(format!(
+                "Blocked by spillback {} times",
+                prettyprint_usize(*count)
+            ))));
+        }
+        None
+    }
+
+    fn on_click(ctx: &mut EventCtx, app: &mut App, action: &str) -> Option<LayerOutcome> {
+        match action {
+            "Worst intersections" => {
+                app.session.dash_tab = DashTab::QueueSpillback;
+                Some(LayerOutcome::Transition(Transition::Push(
+                    DashTab::QueueSpillback.launch(ctx, app),
+                )))
+            }
+            _ => unreachable!(),
+        }
+    }
+}
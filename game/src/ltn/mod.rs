@@ -14,6 +14,7 @@ mod draw_cells;
 mod rat_run_viewer;
 mod rat_runs;
 mod route;
+mod through_traffic;
 mod viewer;
 
 pub struct Neighborhood {
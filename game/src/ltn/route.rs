@@ -129,7 +129,7 @@ impl RoutePlanner {
 
                 world
                     .add(ID::RouteAfterFilters)
-                    .hitbox(Polygon::union_all(hitbox_pieces))
+                    .hitbox(Polygon::concat_all(hitbox_pieces))
                     .zorder(0)
                     .draw(draw_route)
                     .hover_outline(Color::BLACK, Distance::meters(2.0))
@@ -212,7 +212,7 @@ impl RoutePlanner {
 
                 world
                     .add(ID::RouteBeforeFilters)
-                    .hitbox(Polygon::union_all(hitbox_pieces))
+                    .hitbox(Polygon::concat_all(hitbox_pieces))
                     // If the two routes partly overlap, put the "before" on top, since it has
                     // the comparison stats.
                     .zorder(1)
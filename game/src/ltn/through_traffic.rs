@@ -0,0 +1,236 @@
+use std::collections::BTreeSet;
+
+use abstutil::{prettyprint_usize, Counter, Timer};
+use geom::Distance;
+use map_model::osm::RoadRank;
+use map_model::{
+    DirectedRoadID, Direction, Map, PathConstraints, PathRequest, PathStep, RoadID,
+    NORMAL_LANE_THICKNESS,
+};
+use widgetry::mapspace::{ObjectID, ToggleZoomed, World};
+use widgetry::{
+    Color, EventCtx, GfxCtx, HorizontalAlignment, Key, Line, Outcome, Panel, State, Text,
+    VerticalAlignment, Widget,
+};
+
+use super::{ModalFilters, Neighborhood};
+use crate::app::{App, Transition};
+
+/// Compares how much traffic cuts through the residential streets of a neighborhood with and
+/// without the modal filters. Traffic is estimated by routing between every pair of roads along
+/// the perimeter, so the numbers are relative: they count routes, not vehicles.
+pub struct ThroughTraffic {
+    panel: Panel,
+    world: World<Obj>,
+    draw_volumes: ToggleZoomed,
+
+    neighborhood: Neighborhood,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct Obj(RoadID);
+impl ObjectID for Obj {}
+
+impl ThroughTraffic {
+    pub fn new_state(
+        ctx: &mut EventCtx,
+        app: &App,
+        neighborhood: Neighborhood,
+    ) -> Box<dyn State<App>> {
+        let map = &app.primary.map;
+        let (before, after) = ctx.loading_screen("calculate through-traffic", |_, timer| {
+            count_through_traffic(map, &neighborhood, &app.session.modal_filters, timer)
+        });
+
+        let residential: BTreeSet<RoadID> = neighborhood
+            .orig_perimeter
+            .interior
+            .iter()
+            .filter(|r| map.get_r(**r).get_rank() == RoadRank::Local)
+            .cloned()
+            .collect();
+        let max = residential
+            .iter()
+            .map(|r| before.get(*r).max(after.get(*r)))
+            .max()
+            .unwrap_or(0);
+
+        let mut world = World::bounded(map.get_bounds());
+        let mut draw_volumes = ToggleZoomed::builder();
+        for r in &residential {
+            let (cnt_before, cnt_after) = (before.get(*r), after.get(*r));
+            let road = map.get_r(*r);
+            if cnt_after > 0 {
+                // Thicker lines for more traffic
+                let pct = (cnt_after as f64) / (max as f64);
+                let shape = road
+                    .center_pts
+                    .make_polygons((1.0 + 4.0 * pct) * NORMAL_LANE_THICKNESS);
+                let color = app.cs.good_to_bad_red.eval(pct);
+                draw_volumes.unzoomed.push(color.alpha(0.8), shape.clone());
+                draw_volumes.zoomed.push(color.alpha(0.5), shape);
+            }
+
+            let mut tooltip = Text::from(Line(road.get_name(app.opts.language.as_ref())));
+            tooltip.add_line(format!(
+                "{} through-routes before the filters",
+                prettyprint_usize(cnt_before)
+            ));
+            tooltip.add_line(format!(
+                "{} through-routes after the filters",
+                prettyprint_usize(cnt_after)
+            ));
+            world
+                .add(Obj(*r))
+                .hitbox(road.get_thick_polygon())
+                .drawn_in_master_batch()
+                .hover_outline(Color::BLACK, Distance::meters(5.0))
+                .tooltip(tooltip)
+                .build(ctx);
+        }
+        world.initialize_hover(ctx);
+
+        let total_before: usize = residential.iter().map(|r| before.get(*r)).sum();
+        let total_after: usize = residential.iter().map(|r| after.get(*r)).sum();
+        let streets_before = residential.iter().filter(|r| before.get(**r) > 0).count();
+        let streets_after = residential.iter().filter(|r| after.get(**r) > 0).count();
+
+        let panel = Panel::new_builder(Widget::col(vec![
+            ctx.style()
+                .btn_outline
+                .text("Back to editing modal filters")
+                .hotkey(Key::Escape)
+                .build_def(ctx),
+            Line("Through-traffic on residential streets")
+                .small_heading()
+                .into_widget(ctx),
+            Text::from_multiline(vec![
+                Line(format!(
+                    "Before filters: {} through-routes use {} of {} streets",
+                    prettyprint_usize(total_before),
+                    streets_before,
+                    residential.len()
+                )),
+                Line(format!(
+                    "After filters: {} through-routes use {} of {} streets",
+                    prettyprint_usize(total_after),
+                    streets_after,
+                    residential.len()
+                )),
+                Line("Routes between every pair of roads around the neighborhood are counted")
+                    .secondary(),
+            ])
+            .into_widget(ctx),
+        ]))
+        .aligned(HorizontalAlignment::Left, VerticalAlignment::Top)
+        .build(ctx);
+
+        Box::new(ThroughTraffic {
+            panel,
+            world,
+            draw_volumes: draw_volumes.build(ctx),
+            neighborhood,
+        })
+    }
+}
+
+impl State<App> for ThroughTraffic {
+    fn event(&mut self, ctx: &mut EventCtx, _: &mut App) -> Transition {
+        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+            match x.as_ref() {
+                "Back to editing modal filters" => {
+                    return Transition::ConsumeState(Box::new(|state, ctx, app| {
+                        let state = state.downcast::<ThroughTraffic>().ok().unwrap();
+                        vec![super::viewer::Viewer::new_state(
+                            ctx,
+                            app,
+                            state.neighborhood,
+                        )]
+                    }));
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        // The World handles canvas movement
+        self.world.event(ctx);
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        self.panel.draw(g);
+        g.redraw(&self.neighborhood.fade_irrelevant);
+        self.draw_volumes.draw(g);
+        self.world.draw(g);
+        g.redraw(&self.neighborhood.draw_filters);
+        if g.canvas.is_unzoomed() {
+            self.neighborhood.labels.draw(g, app);
+        }
+    }
+}
+
+/// Route between every pair of roads on the perimeter, first ignoring the filters and then
+/// respecting them. For each road inside the neighborhood, count how many routes use it.
+fn count_through_traffic(
+    map: &Map,
+    neighborhood: &Neighborhood,
+    modal_filters: &ModalFilters,
+    timer: &mut Timer,
+) -> (Counter<RoadID>, Counter<RoadID>) {
+    let mut endpoints = Vec::new();
+    for r in &neighborhood.perimeter {
+        for dir in [Direction::Fwd, Direction::Back] {
+            endpoints.push(DirectedRoadID { road: *r, dir });
+        }
+    }
+    let mut requests = Vec::new();
+    for from in &endpoints {
+        for to in &endpoints {
+            if from.road != to.road {
+                if let Some(req) =
+                    PathRequest::between_directed_roads(map, *from, *to, PathConstraints::Car)
+                {
+                    requests.push(req);
+                }
+            }
+        }
+    }
+
+    let mut params = map.routing_params().clone();
+    params
+        .avoid_roads
+        .extend(modal_filters.roads.keys().cloned());
+    for filter in modal_filters.intersections.values() {
+        params
+            .avoid_movements_between
+            .extend(filter.avoid_movements_between_roads());
+    }
+
+    let interior = &neighborhood.orig_perimeter.interior;
+    let mut before = Counter::new();
+    let mut after = Counter::new();
+    timer.start_iter("route around the neighborhood", requests.len());
+    for req in requests {
+        timer.next();
+        for (counter, result) in [
+            (&mut before, map.pathfind(req.clone())),
+            (&mut after, map.pathfind_with_params(req, &params, true)),
+        ] {
+            if let Ok(path) = result {
+                let mut roads = BTreeSet::new();
+                for step in path.get_steps() {
+                    if let PathStep::Lane(l) = step {
+                        if interior.contains(&l.road) {
+                            roads.insert(l.road);
+                        }
+                    }
+                }
+                for r in roads {
+                    counter.inc(r);
+                }
+            }
+        }
+    }
+    (before, after)
+}
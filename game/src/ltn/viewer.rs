@@ -1,8 +1,8 @@
 use std::collections::HashSet;
 
 use geom::Distance;
-use map_gui::tools::CityPicker;
-use map_model::{IntersectionID, RoadID};
+use map_gui::tools::{CityPicker, PopupMsg};
+use map_model::{IntersectionID, PathConstraints, RoadID};
 use widgetry::mapspace::{ObjectID, World, WorldOutcome};
 use widgetry::{
     Color, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key, Outcome, Panel, State, TextExt,
@@ -50,6 +50,17 @@ impl Viewer {
                 .text("Pathfind")
                 .hotkey(Key::P)
                 .build_def(ctx),
+            ctx.style()
+                .btn_outline
+                .text("Through-traffic")
+                .hotkey(Key::T)
+                .build_def(ctx),
+            ctx.style()
+                .btn_outline
+                .text("Save filters as map edits")
+                .disabled(app.session.modal_filters.roads.is_empty())
+                .disabled_tooltip("Add a filter to a road first")
+                .build_def(ctx),
             Widget::row(vec![
                 "Draw traffic cells as".text_widget(ctx).centered_vert(),
                 Toggle::choice(ctx, "draw cells", "areas", "streets", Key::C, true),
@@ -110,6 +121,19 @@ impl State<App> for Viewer {
                         )]
                     }));
                 }
+                "Through-traffic" => {
+                    return Transition::ConsumeState(Box::new(|state, ctx, app| {
+                        let state = state.downcast::<Viewer>().ok().unwrap();
+                        vec![super::through_traffic::ThroughTraffic::new_state(
+                            ctx,
+                            app,
+                            state.neighborhood,
+                        )]
+                    }));
+                }
+                "Save filters as map edits" => {
+                    return save_filters_as_edits(ctx, app);
+                }
                 _ => unreachable!(),
             },
             Outcome::Changed(_) => {
@@ -267,3 +291,44 @@ fn make_world(
 
     world
 }
+
+/// Filters along roads become access restrictions, so cars can still reach the road from either
+/// side, but can't pass through. There's no map edit for a diagonal filter yet.
+fn save_filters_as_edits(ctx: &mut EventCtx, app: &mut App) -> Transition {
+    let map = &app.primary.map;
+    let mut edits = map.get_edits().clone();
+    let mut num_roads = 0;
+    for r in app.session.modal_filters.roads.keys() {
+        if map
+            .get_r(*r)
+            .access_restrictions
+            .allow_through_traffic
+            .contains(PathConstraints::Car)
+        {
+            edits.commands.push(map.edit_road_cmd(*r, |new| {
+                new.access_restrictions
+                    .allow_through_traffic
+                    .remove(PathConstraints::Car);
+            }));
+            num_roads += 1;
+        }
+    }
+    let num_diagonal = app.session.modal_filters.intersections.len();
+    crate::edit::apply_map_edits(ctx, app, edits);
+
+    let mut lines = vec![format!(
+        "{} roads no longer allow through-traffic by car",
+        num_roads
+    )];
+    if num_diagonal > 0 {
+        lines.push(format!(
+            "{} diagonal filters at intersections can't be saved as map edits yet",
+            num_diagonal
+        ));
+    }
+    Transition::Push(PopupMsg::new_state(
+        ctx,
+        "Filters saved as map edits",
+        lines,
+    ))
+}
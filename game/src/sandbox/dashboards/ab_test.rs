@@ -0,0 +1,437 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::Result;
+
+use abstio::MapName;
+use abstutil::{prettyprint_usize, Timer};
+use geom::{Duration, Time};
+use map_gui::tools::{save_file, PopupMsg};
+use map_model::MapEdits;
+use sim::{AlertHandler, Emissions, Scenario, Sim, TripEndpoint, TripID, TripMode};
+use widgetry::table::{Col, Filter, Table, EXPORT_CSV};
+use widgetry::{
+    Choice, EventCtx, Filler, GeomBatch, GfxCtx, Line, Outcome, Panel, State, Text, TextExt, Widget,
+};
+
+use crate::app::{App, Transition};
+use crate::common::{checkbox_per_mode, cmp_duration_shorter, color_for_mode};
+use crate::layer::ab_test::ABTestLayer;
+use crate::layer::emissions::describe_grams;
+use crate::sandbox::dashboards::generic_trip_table::{open_trip_transition, preview_trip};
+use crate::sandbox::dashboards::DashTab;
+
+const NO_EDITS: &str = "no edits";
+
+/// The result of running the current scenario twice, on two different sets of map edits, up to
+/// the same time.
+pub struct ABTestResults {
+    map: MapName,
+    scenario: String,
+    pub edits_a: String,
+    pub edits_b: String,
+    pub time: Time,
+    /// Trips that finished in both runs
+    pub trips: Vec<ABTrip>,
+    pub finished_only_in_a: usize,
+    pub finished_only_in_b: usize,
+    /// Estimated tailpipe emissions over each whole run
+    pub emissions_a: Emissions,
+    pub emissions_b: Emissions,
+}
+
+impl ABTestResults {
+    /// Only returns results for the map and scenario currently loaded.
+    pub fn get(app: &App) -> Option<&ABTestResults> {
+        let results = app.session.ab_test.as_ref()?;
+        let scenario = app.primary.scenario.as_ref()?;
+        if &results.map == app.primary.map.get_name() && results.scenario == scenario.scenario_name
+        {
+            Some(results)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ABTrip {
+    pub id: TripID,
+    pub mode: TripMode,
+    pub departure: Time,
+    pub start: TripEndpoint,
+    pub duration_a: Duration,
+    pub duration_b: Duration,
+}
+
+pub struct ABTest {
+    tab: DashTab,
+    panel: Panel,
+    table: Option<Table<App, ABTrip, Filters>>,
+}
+
+impl ABTest {
+    pub fn new_state(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        let mut col = vec![DashTab::ABTest.picker(ctx, app)];
+        let mut table = None;
+        if let Some(results) = ABTestResults::get(app) {
+            let t = make_table(results);
+            col.push(
+                Widget::col(vec![
+                    Text::from_multiline(vec![
+                        Line(format!(
+                            "A: {}, B: {}, both simulated until {}",
+                            results.edits_a,
+                            results.edits_b,
+                            results.time.ampm_tostring()
+                        )),
+                        Line(format!(
+                            "{} trips finished in both runs. {} only finished in A, {} only in B.",
+                            prettyprint_usize(results.trips.len()),
+                            prettyprint_usize(results.finished_only_in_a),
+                            prettyprint_usize(results.finished_only_in_b)
+                        ))
+                        .secondary(),
+                        Line(format!(
+                            "Estimated CO2: {} in A, {} in B. NOx: {} in A, {} in B.",
+                            describe_grams(results.emissions_a.co2),
+                            describe_grams(results.emissions_b.co2),
+                            describe_grams(results.emissions_a.nox),
+                            describe_grams(results.emissions_b.nox)
+                        ))
+                        .secondary(),
+                    ])
+                    .into_widget(ctx),
+                    Widget::row(vec![
+                        ctx.style().btn_outline.text("Show on map").build_def(ctx),
+                        ctx.style()
+                            .btn_outline
+                            .text("Compare different edits")
+                            .build_def(ctx),
+                    ]),
+                    t.render(ctx, app),
+                    Filler::square_width(ctx, 0.15).named("preview"),
+                ])
+                .section(ctx),
+            );
+            table = Some(t);
+        } else {
+            col.push(setup_controls(ctx, app));
+        }
+
+        let panel = Panel::new_builder(Widget::col(col))
+            .exact_size_percent(90, 90)
+            .build(ctx);
+        Box::new(ABTest {
+            tab: DashTab::ABTest,
+            panel,
+            table,
+        })
+    }
+}
+
+impl State<App> for ABTest {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => {
+                if self
+                    .table
+                    .as_mut()
+                    .map(|table| table.clicked(&x))
+                    .unwrap_or(false)
+                {
+                    self.table
+                        .as_mut()
+                        .unwrap()
+                        .replace_render(ctx, app, &mut self.panel);
+                } else if x == EXPORT_CSV {
+                    let table = self.table.as_ref().unwrap();
+                    let result = save_file(&table.csv_filename(), table.to_csv(app));
+                    return Transition::Push(match result {
+                        Ok(path) => PopupMsg::new_state(
+                            ctx,
+                            "Data exported",
+                            vec![format!("Data exported to {}", path)],
+                        ),
+                        Err(err) => {
+                            PopupMsg::new_state(ctx, "Export failed", vec![err.to_string()])
+                        }
+                    });
+                } else if let Ok(idx) = x.parse::<usize>() {
+                    return open_trip_transition(app, idx);
+                } else if x == "close" {
+                    return Transition::Pop;
+                } else if x == "Run both simulations" {
+                    let edits_a: String = self.panel.dropdown_value("edits A");
+                    let edits_b: String = self.panel.dropdown_value("edits B");
+                    match run_ab_test(ctx, app, edits_a, edits_b) {
+                        Ok(results) => {
+                            app.session.ab_test = Some(results);
+                            return Transition::Replace(ABTest::new_state(ctx, app));
+                        }
+                        Err(err) => {
+                            return Transition::Push(PopupMsg::new_state(
+                                ctx,
+                                "A/B test failed",
+                                vec![err.to_string()],
+                            ));
+                        }
+                    }
+                } else if x == "Compare different edits" {
+                    app.session.ab_test = None;
+                    return Transition::Replace(ABTest::new_state(ctx, app));
+                } else if x == "Show on map" {
+                    app.primary.layer = Some(Box::new(ABTestLayer::new(ctx, app)));
+                    return Transition::Pop;
+                } else {
+                    unreachable!("unhandled action: {}", x)
+                }
+            }
+            Outcome::Changed(_) => {
+                if let Some(t) = self.tab.transition(ctx, app, &self.panel) {
+                    return t;
+                }
+
+                if let Some(ref mut table) = self.table {
+                    table.panel_changed(&self.panel);
+                    table.replace_render(ctx, app, &mut self.panel);
+                }
+            }
+            _ => {}
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        self.panel.draw(g);
+        if self.table.is_some() {
+            preview_trip(g, app, &self.panel, GeomBatch::new(), None);
+        }
+    }
+}
+
+fn setup_controls(ctx: &mut EventCtx, app: &App) -> Widget {
+    if app.primary.scenario.is_none() {
+        return "A/B tests compare two runs of a scenario. Start one first."
+            .text_widget(ctx)
+            .section(ctx);
+    }
+
+    let saved_edits = abstio::list_all_objects(abstio::path_all_edits(app.primary.map.get_name()));
+    let choices = || {
+        let mut choices = vec![Choice::string(NO_EDITS)];
+        choices.extend(Choice::strings(saved_edits.clone()));
+        choices
+    };
+    // Default to comparing the current proposal against the unedited map
+    let current = &app.primary.map.get_edits().edits_name;
+    let default_b = if saved_edits.contains(current) {
+        current.clone()
+    } else {
+        NO_EDITS.to_string()
+    };
+
+    Widget::col(vec![
+        Text::from_multiline(vec![
+            Line(
+                "Run the current scenario twice, once with each set of map edits, and compare how \
+                 long every trip takes.",
+            ),
+            Line(format!(
+                "Both runs simulate until {}, the current time. Scenario modifiers aren't applied.",
+                app.primary.sim.time().ampm_tostring()
+            ))
+            .secondary(),
+        ])
+        .wrap_to_pct(ctx, 50)
+        .into_widget(ctx),
+        Widget::row(vec![
+            "A:".text_widget(ctx).centered_vert(),
+            Widget::dropdown(ctx, "edits A", NO_EDITS.to_string(), choices()),
+        ]),
+        Widget::row(vec![
+            "B:".text_widget(ctx).centered_vert(),
+            Widget::dropdown(ctx, "edits B", default_b, choices()),
+        ]),
+        ctx.style()
+            .btn_solid_primary
+            .text("Run both simulations")
+            .build_def(ctx),
+    ])
+    .section(ctx)
+}
+
+fn run_ab_test(
+    ctx: &mut EventCtx,
+    app: &App,
+    edits_a: String,
+    edits_b: String,
+) -> Result<ABTestResults> {
+    let scenario = app.primary.scenario.clone().unwrap();
+    let time = app.primary.sim.time();
+    if time == Time::START_OF_DAY {
+        bail!("Run the simulation for a while first. Both runs simulate until the current time.");
+    }
+
+    ctx.loading_screen("run A/B test", |_, timer| {
+        let sim_a = run_scenario(app, &scenario, &edits_a, time, timer)?;
+        let sim_b = run_scenario(app, &scenario, &edits_b, time, timer)?;
+
+        let mut times_b: BTreeMap<TripID, Duration> = BTreeMap::new();
+        for (_, id, _, maybe_dt) in &sim_b.get_analytics().finished_trips {
+            if let Some(dt) = maybe_dt {
+                times_b.insert(*id, *dt);
+            }
+        }
+
+        let mut trips = Vec::new();
+        let mut finished_only_in_a = 0;
+        for (_, id, mode, maybe_dt) in &sim_a.get_analytics().finished_trips {
+            if let Some(duration_a) = maybe_dt {
+                if let Some(duration_b) = times_b.remove(id) {
+                    let info = sim_a.trip_info(*id);
+                    trips.push(ABTrip {
+                        id: *id,
+                        mode: *mode,
+                        departure: info.departure,
+                        start: info.start,
+                        duration_a: *duration_a,
+                        duration_b,
+                    });
+                } else {
+                    finished_only_in_a += 1;
+                }
+            }
+        }
+
+        Ok(ABTestResults {
+            map: app.primary.map.get_name().clone(),
+            scenario: scenario.scenario_name.clone(),
+            edits_a,
+            edits_b,
+            time,
+            trips,
+            finished_only_in_a,
+            finished_only_in_b: times_b.len(),
+            emissions_a: sim_a.get_analytics().detailed.emissions.total(),
+            emissions_b: sim_b.get_analytics().detailed.emissions.total(),
+        })
+    })
+}
+
+/// Runs the scenario from scratch on a copy of the map with different edits.
+fn run_scenario(
+    app: &App,
+    scenario: &Scenario,
+    edits_name: &str,
+    until: Time,
+    timer: &mut Timer,
+) -> Result<Sim> {
+    timer.start(format!("simulate with {}", edits_name));
+    let mut map = app.primary.map.clone();
+    let edits = if edits_name == NO_EDITS {
+        map.new_edits()
+    } else {
+        MapEdits::load_from_file(&map, abstio::path_edits(map.get_name(), edits_name), timer)?
+    };
+    map.must_apply_edits(edits, timer);
+    map.recalculate_pathfinding_after_edits(timer);
+
+    let mut opts = app.primary.current_flags.sim_flags.opts.clone();
+    opts.alerts = AlertHandler::Silence;
+    let mut sim = Sim::new(&map, opts);
+    scenario.instantiate(
+        &mut sim,
+        &map,
+        &mut app.primary.current_flags.sim_flags.make_rng(),
+        timer,
+    );
+    sim.timed_step(&map, until - Time::START_OF_DAY, &mut None, timer);
+    timer.stop(format!("simulate with {}", edits_name));
+    Ok(sim)
+}
+
+pub struct Filters {
+    modes: BTreeSet<TripMode>,
+}
+
+fn make_table(results: &ABTestResults) -> Table<App, ABTrip, Filters> {
+    let filter: Filter<App, ABTrip, Filters> = Filter {
+        state: Filters {
+            modes: TripMode::all().into_iter().collect(),
+        },
+        to_controls: Box::new(|ctx, app, state| checkbox_per_mode(ctx, app, &state.modes)),
+        from_controls: Box::new(|panel| {
+            let mut modes = BTreeSet::new();
+            for m in TripMode::all() {
+                if panel.is_checked(m.ongoing_verb()) {
+                    modes.insert(m);
+                }
+            }
+            Filters { modes }
+        }),
+        apply: Box::new(|state, x, _| state.modes.contains(&x.mode)),
+    };
+
+    let mut table = Table::new(
+        "ab_test",
+        results.trips.clone(),
+        Box::new(|x| x.id.0.to_string()),
+        "Difference",
+        filter,
+    );
+    table.static_col("Trip ID", Box::new(|x| x.id.0.to_string()));
+    table.column(
+        "Type",
+        Box::new(|ctx, app, x| {
+            Text::from(Line(x.mode.ongoing_verb()).fg(color_for_mode(app, x.mode))).render(ctx)
+        }),
+        Col::Static,
+    );
+    table.text_col(
+        "Departure",
+        Box::new(|_, x| x.departure.ampm_tostring()),
+        Col::Sortable(Box::new(|rows| rows.sort_by_key(|x| x.departure))),
+    );
+    table.text_col(
+        "Duration in A",
+        Box::new(|app, x| x.duration_a.to_string(&app.opts.units)),
+        Col::Sortable(Box::new(|rows| rows.sort_by_key(|x| x.duration_a))),
+    );
+    table.text_col(
+        "Duration in B",
+        Box::new(|app, x| x.duration_b.to_string(&app.opts.units)),
+        Col::Sortable(Box::new(|rows| rows.sort_by_key(|x| x.duration_b))),
+    );
+    table.column(
+        "Difference",
+        Box::new(|ctx, app, x| {
+            Text::from_all(cmp_duration_shorter(app, x.duration_b, x.duration_a)).render(ctx)
+        }),
+        Col::Sortable(Box::new(|rows| {
+            rows.sort_by_key(|x| x.duration_b - x.duration_a)
+        })),
+    );
+    table.column(
+        "Normalized",
+        Box::new(|ctx, _, x| {
+            Text::from(match x.duration_b.cmp(&x.duration_a) {
+                std::cmp::Ordering::Equal => "same".to_string(),
+                std::cmp::Ordering::Less => format!(
+                    "{}% faster",
+                    (100.0 * (1.0 - (x.duration_b / x.duration_a))) as usize
+                ),
+                std::cmp::Ordering::Greater => format!(
+                    "{}% slower",
+                    (100.0 * ((x.duration_b / x.duration_a) - 1.0)) as usize
+                ),
+            })
+            .render(ctx)
+        }),
+        Col::Sortable(Box::new(|rows| {
+            rows.sort_by_key(|x| (100.0 * (x.duration_b / x.duration_a)) as isize)
+        })),
+    );
+
+    table
+}
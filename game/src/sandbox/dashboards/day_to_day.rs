@@ -0,0 +1,133 @@
+use abstutil::prettyprint_usize;
+use geom::Duration;
+use widgetry::{
+    EventCtx, GfxCtx, Line, LinePlot, Outcome, Panel, PlotOptions, Series, State, Text, Widget,
+};
+
+use crate::app::{App, Transition};
+use crate::sandbox::dashboards::DashTab;
+
+/// How people's trips changed over the days simulated before this one, as they learned from each
+/// day. When the lines flatten out, people have settled into an equilibrium.
+pub struct DayToDaySummary {
+    panel: Panel,
+}
+
+impl DayToDaySummary {
+    pub fn new_state(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        let days = app.session.day_to_day.as_ref().unwrap();
+
+        let mut trip_time_pts = Vec::new();
+        let mut surprise_pts = Vec::new();
+        let mut rescheduled_pts = Vec::new();
+        for (idx, day) in days.iter().enumerate() {
+            let day_number = idx + 1;
+            if day.finished_trips > 0 {
+                trip_time_pts.push((
+                    day_number,
+                    day.total_trip_time / (day.finished_trips as f64),
+                ));
+            }
+            if let Some(dt) = day.mean_surprise {
+                surprise_pts.push((day_number, dt));
+            }
+            // Nobody has learned anything yet on the first day
+            if idx > 0 {
+                rescheduled_pts.push((day_number, day.rescheduled_trips));
+            }
+        }
+
+        let last = days.last().unwrap();
+        let panel = Panel::new_builder(Widget::col(vec![
+            DashTab::DayToDayLearning.picker(ctx, app),
+            Text::from_multiline(vec![
+                Line(format!(
+                    "{} days were simulated before this one. On the last, {} trips finished, \
+                     taking {} in total.",
+                    days.len(),
+                    prettyprint_usize(last.finished_trips),
+                    last.total_trip_time
+                )),
+                Line(
+                    "Each day, some people leave earlier or later to arrive when they did on the \
+                     first day, and drivers route around the congestion they've seen so far.",
+                )
+                .secondary(),
+            ])
+            .wrap_to_pct(ctx, 50)
+            .into_widget(ctx)
+            .section(ctx),
+            Widget::col(vec![
+                Line("Average trip time").small_heading().into_widget(ctx),
+                LinePlot::new_widget(
+                    ctx,
+                    "trip time",
+                    vec![Series {
+                        label: "trip time".to_string(),
+                        color: app.cs.after_changes,
+                        pts: trip_time_pts,
+                    }],
+                    PlotOptions::fixed(),
+                    app.opts.units,
+                ),
+            ])
+            .section(ctx),
+            Widget::col(vec![
+                Line("Average change in trip time from the day before")
+                    .small_heading()
+                    .into_widget(ctx),
+                LinePlot::new_widget(
+                    ctx,
+                    "change in trip time",
+                    vec![Series {
+                        label: "change in trip time".to_string(),
+                        color: app.cs.after_changes,
+                        pts: surprise_pts,
+                    }],
+                    PlotOptions::fixed(),
+                    app.opts.units,
+                ),
+            ])
+            .section(ctx),
+            Widget::col(vec![
+                Line("Trips leaving at a different time than the day before")
+                    .small_heading()
+                    .into_widget(ctx),
+                LinePlot::new_widget(
+                    ctx,
+                    "rescheduled trips",
+                    vec![Series {
+                        label: "rescheduled trips".to_string(),
+                        color: app.cs.after_changes,
+                        pts: rescheduled_pts,
+                    }],
+                    PlotOptions::fixed(),
+                    app.opts.units,
+                ),
+            ])
+            .section(ctx),
+        ]))
+        .exact_size_percent(90, 90)
+        .build(ctx);
+        Box::new(DayToDaySummary { panel })
+    }
+}
+
+impl State<App> for DayToDaySummary {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => Transition::Pop,
+                _ => unreachable!(),
+            },
+            Outcome::Changed(_) => DashTab::DayToDayLearning
+                .transition(ctx, app, &self.panel)
+                .unwrap(),
+            _ => Transition::Keep,
+        }
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _app: &App) {
+        self.panel.draw(g);
+    }
+}
@@ -0,0 +1,112 @@
+use geom::{Duration, Time};
+use widgetry::{
+    EventCtx, GfxCtx, Line, LinePlot, Outcome, Panel, PlotOptions, Series, State, Text, Widget,
+};
+
+use crate::app::{App, Transition};
+use crate::layer::emissions::{describe_grams, EmissionsLayer, Options};
+use crate::layer::hourly::HourlyLayer;
+use crate::sandbox::dashboards::DashTab;
+
+/// Totals of the estimated tailpipe emissions so far, and how they vary over the day.
+pub struct EmissionsSummary {
+    panel: Panel,
+}
+
+impl EmissionsSummary {
+    pub fn new_state(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        let emissions = &app.primary.sim.get_analytics().detailed.emissions;
+        let total = emissions.total();
+
+        let mut co2_pts = Vec::new();
+        let mut nox_pts = Vec::new();
+        for (hour, per_hour) in emissions.total_per_hour().into_iter().enumerate() {
+            let time = Time::START_OF_DAY + Duration::hours(hour);
+            co2_pts.push((time, (per_hour.co2 / 1000.0).round() as usize));
+            nox_pts.push((time, per_hour.nox.round() as usize));
+        }
+
+        let panel = Panel::new_builder(Widget::col(vec![
+            DashTab::Emissions.picker(ctx, app),
+            Widget::col(vec![
+                Text::from_multiline(vec![
+                    Line(format!(
+                        "{} of CO2 and {} of NOx so far",
+                        describe_grams(total.co2),
+                        describe_grams(total.nox)
+                    )),
+                    Line(
+                        "Estimated from the speed of every car, truck, and bus over each lane. \
+                         Use these to compare different edits, not as exact measurements.",
+                    )
+                    .secondary(),
+                ])
+                .wrap_to_pct(ctx, 50)
+                .into_widget(ctx),
+                ctx.style().btn_outline.text("Show on map").build_def(ctx),
+            ])
+            .section(ctx),
+            Widget::col(vec![
+                Line("CO2 per hour (kg)").small_heading().into_widget(ctx),
+                LinePlot::new_widget(
+                    ctx,
+                    "CO2",
+                    vec![Series {
+                        label: "CO2".to_string(),
+                        color: app.cs.after_changes,
+                        pts: co2_pts,
+                    }],
+                    PlotOptions::fixed(),
+                    app.opts.units,
+                ),
+            ])
+            .section(ctx),
+            Widget::col(vec![
+                Line("NOx per hour (g)").small_heading().into_widget(ctx),
+                LinePlot::new_widget(
+                    ctx,
+                    "NOx",
+                    vec![Series {
+                        label: "NOx".to_string(),
+                        color: app.cs.after_changes,
+                        pts: nox_pts,
+                    }],
+                    PlotOptions::fixed(),
+                    app.opts.units,
+                ),
+            ])
+            .section(ctx),
+        ]))
+        .exact_size_percent(90, 90)
+        .build(ctx);
+        Box::new(EmissionsSummary { panel })
+    }
+}
+
+impl State<App> for EmissionsSummary {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => Transition::Pop,
+                "Show on map" => {
+                    app.primary.layer = Some(Box::new(HourlyLayer::<EmissionsLayer>::new(
+                        ctx,
+                        app,
+                        None,
+                        Options { co2: true },
+                    )));
+                    Transition::Pop
+                }
+                _ => unreachable!(),
+            },
+            Outcome::Changed(_) => DashTab::Emissions
+                .transition(ctx, app, &self.panel)
+                .unwrap(),
+            _ => Transition::Keep,
+        }
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _app: &App) {
+        self.panel.draw(g);
+    }
+}
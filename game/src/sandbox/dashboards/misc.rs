@@ -1,5 +1,6 @@
+use std::collections::HashSet;
+
 use abstutil::{prettyprint_usize, Counter};
-use geom::Time;
 use map_model::BusRouteID;
 use widgetry::{
     Autocomplete, EventCtx, GfxCtx, Image, Line, LinePlot, Outcome, Panel, PlotOptions, Series,
@@ -13,10 +14,15 @@ use crate::sandbox::SandboxMode;
 
 pub struct ActiveTraffic {
     panel: Panel,
+    labels: Vec<String>,
 }
 
 impl ActiveTraffic {
     pub fn new_state(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        Box::new(ActiveTraffic::new(ctx, app, HashSet::new()))
+    }
+
+    fn new(ctx: &mut EventCtx, app: &App, disabled: HashSet<String>) -> ActiveTraffic {
         // TODO Downsampling in the middle of the day and comparing to the downsampled entire day
         // doesn't work. For the same simulation, by end of day, the plots will be identical, but
         // until then, they'll differ. See https://github.com/a-b-street/abstreet/issues/85 for
@@ -24,39 +30,42 @@ impl ActiveTraffic {
         let mut active_agents = vec![Series {
             label: format!("After \"{}\"", app.primary.map.get_edits().edits_name),
             color: app.cs.after_changes,
-            pts: downsample(
-                app.primary
-                    .sim
-                    .get_analytics()
-                    .active_agents(app.primary.sim.time()),
-            ),
+            pts: app
+                .primary
+                .sim
+                .get_analytics()
+                .active_agents(app.primary.sim.time()),
         }];
         if app.has_prebaked().is_some() {
             active_agents.push(Series {
                 label: format!("Before \"{}\"", app.primary.map.get_edits().edits_name),
                 color: app.cs.before_changes.alpha(0.5),
-                pts: downsample(
-                    app.prebaked()
-                        .active_agents(app.primary.sim.get_end_of_day()),
-                ),
+                pts: app
+                    .prebaked()
+                    .active_agents(app.primary.sim.get_end_of_day()),
             });
         }
+        let labels = active_agents.iter().map(|s| s.label.clone()).collect();
 
-        Box::new(ActiveTraffic {
+        let mut plot_opts = PlotOptions::filterable();
+        plot_opts.disabled = disabled;
+        plot_opts.fill_area = true;
+        ActiveTraffic {
             panel: Panel::new_builder(Widget::col(vec![
                 DashTab::ActiveTraffic.picker(ctx, app),
                 LinePlot::new_widget(
                     ctx,
                     "active traffic",
                     active_agents,
-                    PlotOptions::fixed(),
+                    plot_opts,
                     app.opts.units,
                 )
                 .section(ctx),
             ]))
             .exact_size_percent(90, 90)
             .build(ctx),
-        })
+            labels,
+        }
     }
 }
 
@@ -67,9 +76,20 @@ impl State<App> for ActiveTraffic {
                 "close" => Transition::Pop,
                 _ => unreachable!(),
             },
-            Outcome::Changed(_) => DashTab::ActiveTraffic
-                .transition(ctx, app, &self.panel)
-                .unwrap(),
+            Outcome::Changed(_) => {
+                if let Some(t) = DashTab::ActiveTraffic.transition(ctx, app, &self.panel) {
+                    return t;
+                }
+                // A series was toggled in the legend
+                let disabled = self
+                    .labels
+                    .iter()
+                    .filter(|label| !self.panel.is_checked(label))
+                    .cloned()
+                    .collect();
+                *self = ActiveTraffic::new(ctx, app, disabled);
+                Transition::Keep
+            }
             _ => Transition::Keep,
         }
     }
@@ -79,33 +99,6 @@ impl State<App> for ActiveTraffic {
     }
 }
 
-fn downsample(raw: Vec<(Time, usize)>) -> Vec<(Time, usize)> {
-    if raw.is_empty() {
-        return raw;
-    }
-
-    let min_x = Time::START_OF_DAY;
-    let min_y = 0;
-    let max_x = raw.last().unwrap().0;
-    let max_y = raw.iter().max_by_key(|(_, cnt)| *cnt).unwrap().1;
-
-    let mut pts = Vec::new();
-    for (t, cnt) in raw {
-        pts.push(lttb::DataPoint::new(
-            (t - min_x) / (max_x - min_x),
-            ((cnt - min_y) as f64) / ((max_y - min_y) as f64),
-        ));
-    }
-    let mut downsampled = Vec::new();
-    for pt in lttb::lttb(pts, 100) {
-        downsampled.push((
-            max_x.percent_of(pt.x),
-            min_y + (pt.y * (max_y - min_y) as f64) as usize,
-        ));
-    }
-    downsampled
-}
-
 pub struct TransitRoutes {
     panel: Panel,
 }
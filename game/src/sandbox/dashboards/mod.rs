@@ -1,3 +1,4 @@
+pub use ab_test::ABTestResults;
 pub use commuter::CommuterPatterns;
 pub use traffic_signals::TrafficSignalDemand;
 
@@ -6,14 +7,21 @@ use widgetry::{Choice, EventCtx, Image, Line, Panel, State, TextExt, Widget};
 use crate::app::App;
 use crate::app::Transition;
 
+mod ab_test;
 mod commuter;
+mod day_to_day;
+mod emissions;
 mod generic_trip_table;
 mod misc;
 mod mode_shift;
+mod parking_changes;
 mod parking_overhead;
+mod report;
 mod risks;
 mod selector;
+mod spillback;
 mod traffic_signals;
+mod transit_schedule;
 mod travel_times;
 mod trip_problems;
 mod trip_table;
@@ -25,30 +33,59 @@ pub enum DashTab {
     TravelTimes,
     RiskSummaries,
     ParkingOverhead,
+    ParkingChanges,
     ActiveTraffic,
+    Emissions,
     TransitRoutes,
+    TransitOnTime,
     CommuterPatterns,
     TrafficSignals,
+    QueueSpillback,
     ModeShift,
+    ABTest,
+    DayToDayLearning,
 }
 
 impl DashTab {
-    pub fn picker(self, ctx: &EventCtx, app: &App) -> Widget {
-        let mut choices = vec![
-            Choice::new("Trip Table", DashTab::TripTable),
-            Choice::new("Travel Times", DashTab::TravelTimes),
-            Choice::new("Risk Exposure", DashTab::RiskSummaries),
-            Choice::new("Parking Overhead", DashTab::ParkingOverhead),
-            Choice::new("Active Traffic", DashTab::ActiveTraffic),
-            Choice::new("Transit Routes", DashTab::TransitRoutes),
-            Choice::new("Commuter Patterns", DashTab::CommuterPatterns),
-            Choice::new("Traffic Signal Demand", DashTab::TrafficSignals),
-            Choice::new("Mode shift (experimental)", DashTab::ModeShift),
+    /// Every dashboard that can be opened right now, with its name
+    pub fn all(app: &App) -> Vec<(&'static str, DashTab)> {
+        let mut tabs = vec![
+            ("Trip Table", DashTab::TripTable),
+            ("Travel Times", DashTab::TravelTimes),
+            ("Risk Exposure", DashTab::RiskSummaries),
+            ("Parking Overhead", DashTab::ParkingOverhead),
+            ("Parking Before/After", DashTab::ParkingChanges),
+            ("Active Traffic", DashTab::ActiveTraffic),
+            ("Emissions", DashTab::Emissions),
+            ("Transit Routes", DashTab::TransitRoutes),
+            ("Transit On-Time Performance", DashTab::TransitOnTime),
+            ("Commuter Patterns", DashTab::CommuterPatterns),
+            ("Traffic Signal Demand", DashTab::TrafficSignals),
+            ("Queue Spillback", DashTab::QueueSpillback),
+            ("Mode shift (experimental)", DashTab::ModeShift),
+            ("A/B Test", DashTab::ABTest),
+            ("Day-to-day Learning", DashTab::DayToDayLearning),
         ];
         if app.has_prebaked().is_none() {
-            choices.remove(1);
-            choices.remove(1);
+            // These compare against the baseline
+            tabs.retain(|(_, tab)| {
+                !matches!(
+                    tab,
+                    DashTab::TravelTimes | DashTab::RiskSummaries | DashTab::ParkingChanges
+                )
+            });
+        }
+        if app.session.day_to_day.is_none() {
+            tabs.retain(|(_, tab)| *tab != DashTab::DayToDayLearning);
         }
+        tabs
+    }
+
+    pub fn picker(self, ctx: &EventCtx, app: &App) -> Widget {
+        let choices = DashTab::all(app)
+            .into_iter()
+            .map(|(name, tab)| Choice::new(name, tab))
+            .collect();
         Widget::row(vec![
             Image::from_path("system/assets/meters/trip_histogram.svg").into_widget(ctx),
             Line("Data").big_heading_plain().into_widget(ctx),
@@ -68,11 +105,26 @@ impl DashTab {
             }
             DashTab::RiskSummaries => risks::RiskSummaries::new_state(ctx, app, false),
             DashTab::ParkingOverhead => parking_overhead::ParkingOverhead::new_state(ctx, app),
+            DashTab::ParkingChanges => parking_changes::ParkingChanges::new_state(ctx, app),
             DashTab::ActiveTraffic => misc::ActiveTraffic::new_state(ctx, app),
+            DashTab::Emissions => emissions::EmissionsSummary::new_state(ctx, app),
             DashTab::TransitRoutes => misc::TransitRoutes::new_state(ctx, app),
+            DashTab::TransitOnTime => transit_schedule::TransitOnTime::new_state(ctx, app),
             DashTab::CommuterPatterns => CommuterPatterns::new_state(ctx, app),
             DashTab::TrafficSignals => TrafficSignalDemand::new_state(ctx, app),
+            DashTab::QueueSpillback => spillback::QueueSpillback::new_state(ctx, app),
             DashTab::ModeShift => mode_shift::ModeShift::new_state(ctx, app),
+            DashTab::ABTest => ab_test::ABTest::new_state(ctx, app),
+            DashTab::DayToDayLearning => {
+                // The last dashboard opened might be from a scenario that learned, but this one
+                // didn't
+                if app.session.day_to_day.is_some() {
+                    day_to_day::DayToDaySummary::new_state(ctx, app)
+                } else {
+                    app.session.dash_tab = DashTab::TripTable;
+                    DashTab::TripTable.launch(ctx, app)
+                }
+            }
         }
     }
 
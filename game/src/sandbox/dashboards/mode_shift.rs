@@ -2,10 +2,10 @@ use std::collections::HashSet;
 
 use abstutil::Counter;
 use geom::{Distance, Duration};
-use map_gui::tools::ColorNetwork;
+use map_gui::tools::{save_file, ColorNetwork, PopupMsg};
 use map_model::PathStepV2;
 use sim::{TripEndpoint, TripID, TripMode};
-use widgetry::table::{Col, Filter, Table};
+use widgetry::table::{Col, Filter, Table, EXPORT_CSV};
 use widgetry::{
     Drawable, EventCtx, Filler, GeomBatch, GfxCtx, Line, Outcome, Panel, Spinner, State, Text,
     TextExt, Widget,
@@ -62,6 +62,18 @@ impl State<App> for ModeShift {
             Outcome::Clicked(x) => {
                 if self.table.clicked(&x) {
                     self.table.replace_render(ctx, app, &mut self.panel);
+                } else if x == EXPORT_CSV {
+                    let result = save_file(&self.table.csv_filename(), self.table.to_csv(app));
+                    return Transition::Push(match result {
+                        Ok(path) => PopupMsg::new_state(
+                            ctx,
+                            "Data exported",
+                            vec![format!("Data exported to {}", path)],
+                        ),
+                        Err(err) => {
+                            PopupMsg::new_state(ctx, "Export failed", vec![err.to_string()])
+                        }
+                    });
                 } else if let Ok(idx) = x.parse::<usize>() {
                     return open_trip_transition(app, idx);
                 } else if x == "close" {
@@ -247,27 +259,23 @@ fn make_table(ctx: &mut EventCtx, app: &App) -> Table<App, Entry, Filters> {
         filter,
     );
     table.static_col("Trip ID", Box::new(|x| x.trip.0.to_string()));
-    table.column(
+    table.text_col(
         "Estimated driving time",
-        Box::new(|ctx, app, x| {
-            Text::from(x.estimated_driving_time.to_string(&app.opts.units)).render(ctx)
-        }),
+        Box::new(|app, x| x.estimated_driving_time.to_string(&app.opts.units)),
         Col::Sortable(Box::new(|rows| {
             rows.sort_by_key(|x| x.estimated_driving_time)
         })),
     );
-    table.column(
+    table.text_col(
         "Estimated biking time",
-        Box::new(|ctx, app, x| {
-            Text::from(x.estimated_biking_time.to_string(&app.opts.units)).render(ctx)
-        }),
+        Box::new(|app, x| x.estimated_biking_time.to_string(&app.opts.units)),
         Col::Sortable(Box::new(|rows| {
             rows.sort_by_key(|x| x.estimated_biking_time)
         })),
     );
-    table.column(
+    table.text_col(
         "Distance",
-        Box::new(|ctx, app, x| Text::from(x.distance.to_string(&app.opts.units)).render(ctx)),
+        Box::new(|app, x| x.distance.to_string(&app.opts.units)),
         Col::Sortable(Box::new(|rows| rows.sort_by_key(|x| x.distance))),
     );
     table.column(
@@ -0,0 +1,243 @@
+use abstutil::prettyprint_usize;
+use map_gui::tools::{save_file, PopupMsg};
+use map_model::RoadID;
+use widgetry::table::{Col, Filter, Table, EXPORT_CSV};
+use widgetry::{
+    EventCtx, GfxCtx, Line, LinePlot, Outcome, Panel, PlotOptions, Series, State, Text, Toggle,
+    Widget,
+};
+
+use crate::app::{App, Transition};
+use crate::info::Tab;
+use crate::sandbox::dashboards::DashTab;
+use crate::sandbox::SandboxMode;
+
+/// Compares on-street parking occupancy before and after map edits, to find the roads where
+/// removing parking leaves more cars than spots.
+pub struct ParkingChanges {
+    tab: DashTab,
+    table: Table<App, Entry, Filters>,
+    panel: Panel,
+}
+
+impl ParkingChanges {
+    pub fn new_state(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        let edits_name = &app.primary.map.get_edits().edits_name;
+        let series = vec![
+            Series {
+                label: format!("After \"{}\"", edits_name),
+                color: app.cs.after_changes,
+                pts: app
+                    .primary
+                    .sim
+                    .get_analytics()
+                    .onstreet_parking_occupancy(app.primary.sim.time()),
+            },
+            Series {
+                label: format!("Before \"{}\"", edits_name),
+                color: app.cs.before_changes.alpha(0.5),
+                pts: app
+                    .prebaked()
+                    .onstreet_parking_occupancy(app.primary.sim.get_end_of_day()),
+            },
+        ];
+
+        let table = make_table(app);
+        let col = Widget::col(vec![
+            DashTab::ParkingChanges.picker(ctx, app),
+            Widget::col(vec![
+                Line("Occupied on-street parking spots")
+                    .small_heading()
+                    .into_widget(ctx),
+                LinePlot::new_widget(
+                    ctx,
+                    "parking occupancy",
+                    series,
+                    PlotOptions::fixed(),
+                    app.opts.units,
+                ),
+                Text::from_multiline(vec![
+                    Line("Per road, the most cars parked at once so far in the day."),
+                    Line(
+                        "A road overflows when more cars parked there before the edits than it \
+                         has spots now. Those drivers have to search elsewhere.",
+                    ),
+                ])
+                .into_widget(ctx),
+                table.render(ctx, app),
+            ])
+            .section(ctx),
+        ]);
+
+        let panel = Panel::new_builder(col)
+            .exact_size_percent(90, 90)
+            .build(ctx);
+
+        Box::new(Self {
+            tab: DashTab::ParkingChanges,
+            table,
+            panel,
+        })
+    }
+}
+
+impl State<App> for ParkingChanges {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => {
+                if self.table.clicked(&x) {
+                    self.table.replace_render(ctx, app, &mut self.panel);
+                } else if x == EXPORT_CSV {
+                    let result = save_file(&self.table.csv_filename(), self.table.to_csv(app));
+                    return Transition::Push(match result {
+                        Ok(path) => PopupMsg::new_state(
+                            ctx,
+                            "Data exported",
+                            vec![format!("Data exported to {}", path)],
+                        ),
+                        Err(err) => {
+                            PopupMsg::new_state(ctx, "Export failed", vec![err.to_string()])
+                        }
+                    });
+                } else if let Ok(idx) = x.parse::<usize>() {
+                    return open_road_transition(app, RoadID(idx));
+                } else if x == "close" {
+                    return Transition::Pop;
+                } else {
+                    unreachable!()
+                }
+            }
+            Outcome::Changed(_) => {
+                if let Some(t) = self.tab.transition(ctx, app, &self.panel) {
+                    return t;
+                }
+
+                self.table.panel_changed(&self.panel);
+                self.table.replace_render(ctx, app, &mut self.panel);
+            }
+            _ => {}
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.panel.draw(g);
+    }
+}
+
+/// Show the road's parking lane if it still has one, so the before/after availability is visible.
+fn open_road_transition(app: &App, r: RoadID) -> Transition {
+    let road = app.primary.map.get_r(r);
+    let l = road
+        .lanes
+        .iter()
+        .find(|l| l.is_parking())
+        .unwrap_or(&road.lanes[0])
+        .id;
+
+    Transition::Multi(vec![
+        Transition::Pop,
+        Transition::ModifyState(Box::new(move |state, ctx, app| {
+            let sandbox = state.downcast_mut::<SandboxMode>().unwrap();
+            let mut actions = sandbox.contextual_actions();
+            sandbox.controls.common.as_mut().unwrap().launch_info_panel(
+                ctx,
+                app,
+                Tab::LaneInfo(l),
+                &mut actions,
+            );
+        })),
+    ])
+}
+
+struct Entry {
+    road: RoadID,
+    name: String,
+    /// How many spots the road has now
+    capacity: usize,
+    peak_before: usize,
+    peak_after: usize,
+    /// How many more cars parked here before than fit now
+    overflow: usize,
+}
+
+struct Filters {
+    only_overflowing: bool,
+}
+
+fn produce_raw_data(app: &App) -> Vec<Entry> {
+    let now = app.primary.sim.time();
+    let map = &app.primary.map;
+    let before = app.prebaked().peak_onstreet_parking_per_road(now);
+    let after = app
+        .primary
+        .sim
+        .get_analytics()
+        .peak_onstreet_parking_per_road(now);
+
+    let mut data = Vec::new();
+    for r in map.all_roads() {
+        let capacity = r.number_parking_spots(map.get_config());
+        let peak_before = before.get(&r.id).cloned().unwrap_or(0);
+        let peak_after = after.get(&r.id).cloned().unwrap_or(0);
+        if capacity == 0 && peak_before == 0 && peak_after == 0 {
+            continue;
+        }
+        data.push(Entry {
+            road: r.id,
+            name: r.get_name(app.opts.language.as_ref()),
+            capacity,
+            peak_before,
+            peak_after,
+            overflow: peak_before.saturating_sub(capacity),
+        });
+    }
+    data
+}
+
+fn make_table(app: &App) -> Table<App, Entry, Filters> {
+    let filter: Filter<App, Entry, Filters> = Filter {
+        state: Filters {
+            only_overflowing: false,
+        },
+        to_controls: Box::new(|ctx, _, state| {
+            Toggle::switch(ctx, "only overflowing roads", None, state.only_overflowing)
+        }),
+        from_controls: Box::new(|panel| Filters {
+            only_overflowing: panel.is_checked("only overflowing roads"),
+        }),
+        apply: Box::new(|state, x, _| !state.only_overflowing || x.overflow > 0),
+    };
+
+    let mut table = Table::new(
+        "parking_changes",
+        produce_raw_data(app),
+        Box::new(|x| x.road.0.to_string()),
+        "Overflow",
+        filter,
+    );
+    table.static_col("Road", Box::new(|x| x.name.clone()));
+    table.text_col(
+        "Spots now",
+        Box::new(|_, x| prettyprint_usize(x.capacity)),
+        Col::Sortable(Box::new(|rows| rows.sort_by_key(|x| x.capacity))),
+    );
+    table.text_col(
+        "Most parked before",
+        Box::new(|_, x| prettyprint_usize(x.peak_before)),
+        Col::Sortable(Box::new(|rows| rows.sort_by_key(|x| x.peak_before))),
+    );
+    table.text_col(
+        "Most parked after",
+        Box::new(|_, x| prettyprint_usize(x.peak_after)),
+        Col::Sortable(Box::new(|rows| rows.sort_by_key(|x| x.peak_after))),
+    );
+    table.text_col(
+        "Overflow",
+        Box::new(|_, x| prettyprint_usize(x.overflow)),
+        Col::Sortable(Box::new(|rows| rows.sort_by_key(|x| x.overflow))),
+    );
+
+    table
+}
@@ -1,6 +1,7 @@
 use geom::Duration;
+use map_gui::tools::{save_file, PopupMsg};
 use sim::{TripEndpoint, TripID, TripPhaseType};
-use widgetry::table::{Col, Filter, Table};
+use widgetry::table::{Col, Filter, Table, EXPORT_CSV};
 use widgetry::{
     EventCtx, Filler, GeomBatch, GfxCtx, Line, Outcome, Panel, State, Text, Toggle, Widget,
 };
@@ -76,6 +77,18 @@ impl State<App> for ParkingOverhead {
             Outcome::Clicked(x) => {
                 if self.table.clicked(&x) {
                     self.table.replace_render(ctx, app, &mut self.panel);
+                } else if x == EXPORT_CSV {
+                    let result = save_file(&self.table.csv_filename(), self.table.to_csv(app));
+                    return Transition::Push(match result {
+                        Ok(path) => PopupMsg::new_state(
+                            ctx,
+                            "Data exported",
+                            vec![format!("Data exported to {}", path)],
+                        ),
+                        Err(err) => {
+                            PopupMsg::new_state(ctx, "Export failed", vec![err.to_string()])
+                        }
+                    });
                 } else if let Ok(idx) = x.parse::<usize>() {
                     return open_trip_transition(app, idx);
                 } else if x == "close" {
@@ -207,35 +220,29 @@ fn make_table(app: &App) -> Table<App, Entry, Filters> {
         filter,
     );
     table.static_col("Trip ID", Box::new(|x| x.trip.0.to_string()));
-    table.column(
+    table.text_col(
         "Total duration",
-        Box::new(|ctx, app, x| Text::from(x.total_duration.to_string(&app.opts.units)).render(ctx)),
+        Box::new(|app, x| x.total_duration.to_string(&app.opts.units)),
         Col::Sortable(Box::new(|rows| rows.sort_by_key(|x| x.total_duration))),
     );
-    table.column(
+    table.text_col(
         "Driving duration",
-        Box::new(|ctx, app, x| {
-            Text::from(x.driving_duration.to_string(&app.opts.units)).render(ctx)
-        }),
+        Box::new(|app, x| x.driving_duration.to_string(&app.opts.units)),
         Col::Sortable(Box::new(|rows| rows.sort_by_key(|x| x.driving_duration))),
     );
-    table.column(
+    table.text_col(
         "Parking duration",
-        Box::new(|ctx, app, x| {
-            Text::from(x.parking_duration.to_string(&app.opts.units)).render(ctx)
-        }),
+        Box::new(|app, x| x.parking_duration.to_string(&app.opts.units)),
         Col::Sortable(Box::new(|rows| rows.sort_by_key(|x| x.parking_duration))),
     );
-    table.column(
+    table.text_col(
         "Walking duration",
-        Box::new(|ctx, app, x| {
-            Text::from(x.walking_duration.to_string(&app.opts.units)).render(ctx)
-        }),
+        Box::new(|app, x| x.walking_duration.to_string(&app.opts.units)),
         Col::Sortable(Box::new(|rows| rows.sort_by_key(|x| x.walking_duration))),
     );
-    table.column(
+    table.text_col(
         "Percent overhead",
-        Box::new(|ctx, _, x| Text::from(format!("{}%", x.percent_overhead)).render(ctx)),
+        Box::new(|_, x| format!("{}%", x.percent_overhead)),
         Col::Sortable(Box::new(|rows| rows.sort_by_key(|x| x.percent_overhead))),
     );
 
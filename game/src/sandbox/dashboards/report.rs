@@ -0,0 +1,313 @@
+//! Summarize a proposal in one self-contained HTML file, for sharing with people who don't run
+//! A/B Street themselves. The report includes a map of the edits, the edit list, and how trip
+//! times and mode share compare to the baseline simulation.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Write;
+
+use anyhow::Result;
+
+use abstutil::prettyprint_usize;
+use geom::{Duration, Histogram, Statistic, Time};
+use map_model::EditCmd;
+use sim::{Analytics, TripMode};
+
+use crate::app::App;
+
+/// Width of the map in the report, in pixels. The height follows the map's aspect ratio.
+const MAP_WIDTH: f64 = 800.0;
+
+/// Writes the report to the current directory and returns the path.
+pub fn export_report(app: &App) -> Result<String> {
+    let map = &app.primary.map;
+    let now = app.primary.sim.time();
+    let edits = map.get_edits();
+    let path = format!(
+        "report_{}_{}.html",
+        map.get_name().as_filename(),
+        now.as_filename()
+    );
+
+    let mut f = File::create(&path)?;
+    writeln!(f, "<!DOCTYPE html>")?;
+    writeln!(f, "<html><head><meta charset=\"utf-8\">")?;
+    writeln!(f, "<title>{}</title>", escape(edits.get_title()))?;
+    writeln!(f, "<style>{}</style>", STYLE)?;
+    writeln!(f, "</head><body>")?;
+    writeln!(f, "<h1>{}</h1>", escape(edits.get_title()))?;
+    writeln!(
+        f,
+        "<p class=\"secondary\">{}, simulated until {}</p>",
+        escape(&map.get_name().describe()),
+        now.ampm_tostring()
+    )?;
+    for line in edits.proposal_description.iter().skip(1) {
+        writeln!(f, "<p>{}</p>", escape(line))?;
+    }
+    if let Some(ref link) = edits.proposal_link {
+        writeln!(f, "<p><a href=\"{0}\">{0}</a></p>", escape(link))?;
+    }
+
+    writeln!(f, "<h2>Map of the changes</h2>")?;
+    write_map(&mut f, app)?;
+
+    writeln!(f, "<h2>Changes</h2>")?;
+    write_edit_list(&mut f, app)?;
+
+    if app.has_prebaked().is_some() {
+        writeln!(f, "<h2>Trip times</h2>")?;
+        write_trip_times(&mut f, app)?;
+        writeln!(f, "<h2>Mode share</h2>")?;
+        write_mode_share(&mut f, app)?;
+    } else {
+        writeln!(
+            f,
+            "<p>There's no baseline simulation for this map, so trip times and mode share can't \
+             be compared.</p>"
+        )?;
+    }
+
+    writeln!(f, "</body></html>")?;
+    Ok(path)
+}
+
+const STYLE: &str = "body { font-family: sans-serif; max-width: 900px; margin: auto; } \
+                     table { border-collapse: collapse; } \
+                     td, th { border: 1px solid #ccc; padding: 4px 8px; text-align: right; } \
+                     .secondary { color: #666; } .faster { color: #2E7D32; } \
+                     .slower { color: #C62828; }";
+
+/// Draws every road as a thin line, and the edited roads and intersections in red.
+fn write_map(f: &mut File, app: &App) -> Result<()> {
+    let map = &app.primary.map;
+    let edits = map.get_edits();
+    let bounds = map.get_bounds();
+    let scale = MAP_WIDTH / bounds.width();
+
+    writeln!(
+        f,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" \
+         viewBox=\"0 0 {} {}\" style=\"background: #f5f5f5\">",
+        MAP_WIDTH,
+        (bounds.height() * scale).round(),
+        bounds.width(),
+        bounds.height()
+    )?;
+    for r in map.all_roads() {
+        let changed = edits.changed_roads.contains(&r.id);
+        let points = r
+            .center_pts
+            .points()
+            .iter()
+            .map(|pt| format!("{:.1},{:.1}", pt.x(), pt.y()))
+            .collect::<Vec<_>>()
+            .join(" ");
+        writeln!(
+            f,
+            "<polyline points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{:.1}\" />",
+            points,
+            if changed { "#D32F2F" } else { "#9E9E9E" },
+            if changed {
+                r.get_width().inner_meters().max(3.0 / scale)
+            } else {
+                1.0 / scale
+            }
+        )?;
+    }
+    for i in edits.original_intersections.keys() {
+        let center = map.get_i(*i).polygon.center();
+        writeln!(
+            f,
+            "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"{:.1}\" fill=\"#D32F2F\" />",
+            center.x(),
+            center.y(),
+            6.0 / scale
+        )?;
+    }
+    writeln!(f, "</svg>")?;
+    Ok(())
+}
+
+fn write_edit_list(f: &mut File, app: &App) -> Result<()> {
+    let map = &app.primary.map;
+    let lang = app.opts.language.as_ref();
+    let edits = map.get_edits();
+    if edits.commands.is_empty() {
+        writeln!(f, "<p>No changes were made to the map.</p>")?;
+        return Ok(());
+    }
+
+    writeln!(f, "<ol>")?;
+    for cmd in &edits.commands {
+        let (summary, details) = cmd.describe(map);
+        // The summary only has an ID, which means nothing outside the game
+        let name = match cmd {
+            EditCmd::ChangeRoad { r, .. } => Some(map.get_r(*r).get_name(lang)),
+            EditCmd::ChangeIntersection { i, .. } => Some(map.get_i(*i).name(lang, map)),
+            EditCmd::ChangeRouteSchedule { .. } => None,
+        };
+        write!(f, "<li>{}", escape(&summary))?;
+        if let Some(name) = name {
+            write!(f, " ({})", escape(&name))?;
+        }
+        if !details.is_empty() {
+            write!(f, "<ul>")?;
+            for line in details {
+                write!(f, "<li>{}</li>", escape(&line))?;
+            }
+            write!(f, "</ul>")?;
+        }
+        writeln!(f, "</li>")?;
+    }
+    writeln!(f, "</ol>")?;
+    Ok(())
+}
+
+/// Compares the distribution of trip times for trips that finished in both simulations, overall
+/// and per mode.
+fn write_trip_times(f: &mut File, app: &App) -> Result<()> {
+    let trips = app
+        .primary
+        .sim
+        .get_analytics()
+        .both_finished_trips(app.primary.sim.time(), app.prebaked());
+    if trips.is_empty() {
+        writeln!(f, "<p>No trips have finished yet.</p>")?;
+        return Ok(());
+    }
+
+    let mut faster = 0;
+    let mut slower = 0;
+    let mut saved = Duration::ZERO;
+    let mut lost = Duration::ZERO;
+    for (_, before, after, _) in &trips {
+        if after < before {
+            faster += 1;
+            saved += *before - *after;
+        } else if after > before {
+            slower += 1;
+            lost += *after - *before;
+        }
+    }
+    writeln!(
+        f,
+        "<p>Of {} trips that finished in both simulations, <span class=\"faster\">{} got faster, \
+         saving {} in total</span>, and <span class=\"slower\">{} got slower, losing {} in \
+         total</span>.</p>",
+        prettyprint_usize(trips.len()),
+        prettyprint_usize(faster),
+        saved,
+        prettyprint_usize(slower),
+        lost
+    )?;
+
+    let mut modes: Vec<(String, Vec<(Duration, Duration)>)> = vec![(
+        "All trips".to_string(),
+        trips.iter().map(|(_, b, a, _)| (*b, *a)).collect(),
+    )];
+    for mode in TripMode::all() {
+        let times: Vec<(Duration, Duration)> = trips
+            .iter()
+            .filter(|(_, _, _, m)| *m == mode)
+            .map(|(_, b, a, _)| (*b, *a))
+            .collect();
+        if !times.is_empty() {
+            modes.push((format!("{} trips", mode.noun()), times));
+        }
+    }
+
+    for (label, times) in modes {
+        let mut before = Histogram::new();
+        let mut after = Histogram::new();
+        for (b, a) in &times {
+            before.add(*b);
+            after.add(*a);
+        }
+        writeln!(
+            f,
+            "<h3>{} ({})</h3>",
+            escape(&label),
+            prettyprint_usize(times.len())
+        )?;
+        writeln!(
+            f,
+            "<table><tr><th></th><th>Before</th><th>After</th><th>Change</th></tr>"
+        )?;
+        for stat in Statistic::all() {
+            let b = before.select(stat).unwrap();
+            let a = after.select(stat).unwrap();
+            let change = if a < b {
+                format!("<span class=\"faster\">-{}</span>", b - a)
+            } else if a > b {
+                format!("<span class=\"slower\">+{}</span>", a - b)
+            } else {
+                "same".to_string()
+            };
+            writeln!(
+                f,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                stat, b, a, change
+            )?;
+        }
+        writeln!(f, "</table>")?;
+    }
+    Ok(())
+}
+
+/// Compares how many trips finished by each mode in both simulations, up to the current time.
+fn write_mode_share(f: &mut File, app: &App) -> Result<()> {
+    let now = app.primary.sim.time();
+    let before = count_modes(app.prebaked(), now);
+    let after = count_modes(app.primary.sim.get_analytics(), now);
+    let total_before: usize = before.values().sum();
+    let total_after: usize = after.values().sum();
+    if total_before == 0 || total_after == 0 {
+        writeln!(f, "<p>No trips have finished yet.</p>")?;
+        return Ok(());
+    }
+
+    writeln!(
+        f,
+        "<table><tr><th>Mode</th><th>Before</th><th>After</th><th>Change</th></tr>"
+    )?;
+    for mode in TripMode::all() {
+        let b = before.get(&mode).cloned().unwrap_or(0);
+        let a = after.get(&mode).cloned().unwrap_or(0);
+        let pct_before = 100.0 * (b as f64) / (total_before as f64);
+        let pct_after = 100.0 * (a as f64) / (total_after as f64);
+        writeln!(
+            f,
+            "<tr><td>{}</td><td>{:.1}% ({})</td><td>{:.1}% ({})</td><td>{:+.1} points</td></tr>",
+            mode.noun(),
+            pct_before,
+            prettyprint_usize(b),
+            pct_after,
+            prettyprint_usize(a),
+            pct_after - pct_before
+        )?;
+    }
+    writeln!(f, "</table>")?;
+    Ok(())
+}
+
+fn count_modes(analytics: &Analytics, now: Time) -> BTreeMap<TripMode, usize> {
+    let mut counts = BTreeMap::new();
+    for (t, _, mode, maybe_dt) in &analytics.finished_trips {
+        if *t > now {
+            break;
+        }
+        // Cancelled trips didn't happen by any mode
+        if maybe_dt.is_some() {
+            *counts.entry(*mode).or_insert(0) += 1;
+        }
+    }
+    counts
+}
+
+fn escape(raw: &str) -> String {
+    raw.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
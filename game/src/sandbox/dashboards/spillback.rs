@@ -0,0 +1,231 @@
+use std::collections::BTreeMap;
+
+use abstutil::prettyprint_usize;
+use geom::{Duration, Time};
+use map_gui::tools::{save_file, PopupMsg};
+use map_gui::ID;
+use map_model::{IntersectionID, LaneID};
+use widgetry::table::{Col, Filter, Table, EXPORT_CSV};
+use widgetry::{EventCtx, GfxCtx, Line, Outcome, Panel, State, Text, Toggle, Widget};
+
+use crate::app::{App, Transition};
+use crate::common::Warping;
+use crate::sandbox::dashboards::DashTab;
+
+/// Ranks intersections by how often queues spilled back into them over the day, so the worst
+/// places where vehicles block the box can be found and fixed.
+pub struct QueueSpillback {
+    tab: DashTab,
+    table: Table<App, Entry, Filters>,
+    panel: Panel,
+}
+
+impl QueueSpillback {
+    pub fn new_state(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        let data = produce_raw_data(app);
+        let total: usize = data.iter().map(|x| x.count).sum();
+        let summary = if total == 0 {
+            Text::from(Line("No queues have spilled back into an intersection yet"))
+        } else {
+            Text::from_multiline(vec![
+                Line(format!(
+                    "Queues spilled back into {} intersections {} times",
+                    prettyprint_usize(data.len()),
+                    prettyprint_usize(total)
+                )),
+                Line(
+                    "This happens when a vehicle starts a turn without room for it on the next \
+                     lane, either because blocking the box is allowed there or to break gridlock.",
+                )
+                .secondary(),
+            ])
+        };
+
+        let table = make_table(data);
+        let col = Widget::col(vec![
+            DashTab::QueueSpillback.picker(ctx, app),
+            Widget::col(vec![
+                Line("Queue spillback").small_heading().into_widget(ctx),
+                summary.into_widget(ctx),
+                table.render(ctx, app),
+            ])
+            .section(ctx),
+        ]);
+
+        let panel = Panel::new_builder(col)
+            .exact_size_percent(90, 90)
+            .build(ctx);
+
+        Box::new(Self {
+            tab: DashTab::QueueSpillback,
+            table,
+            panel,
+        })
+    }
+}
+
+impl State<App> for QueueSpillback {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => {
+                if self.table.clicked(&x) {
+                    self.table.replace_render(ctx, app, &mut self.panel);
+                } else if x == EXPORT_CSV {
+                    let result = save_file(&self.table.csv_filename(), self.table.to_csv(app));
+                    return Transition::Push(match result {
+                        Ok(path) => PopupMsg::new_state(
+                            ctx,
+                            "Data exported",
+                            vec![format!("Data exported to {}", path)],
+                        ),
+                        Err(err) => {
+                            PopupMsg::new_state(ctx, "Export failed", vec![err.to_string()])
+                        }
+                    });
+                } else if let Ok(idx) = x.parse::<usize>() {
+                    let i = IntersectionID(idx);
+                    return Transition::Push(Warping::new_state(
+                        ctx,
+                        app.primary.canonical_point(ID::Intersection(i)).unwrap(),
+                        Some(10.0),
+                        Some(ID::Intersection(i)),
+                        &mut app.primary,
+                    ));
+                } else if x == "close" {
+                    return Transition::Pop;
+                } else {
+                    unreachable!()
+                }
+            }
+            Outcome::Changed(_) => {
+                if let Some(t) = self.tab.transition(ctx, app, &self.panel) {
+                    return t;
+                }
+
+                self.table.panel_changed(&self.panel);
+                self.table.replace_render(ctx, app, &mut self.panel);
+            }
+            _ => {}
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.panel.draw(g);
+    }
+}
+
+struct Entry {
+    intersection: IntersectionID,
+    name: String,
+    count: usize,
+    /// The hour of the day with the most spillbacks
+    worst_hour: usize,
+    /// The incoming lane that was most often full
+    worst_lane: LaneID,
+    first: Time,
+    last: Time,
+}
+
+struct Filters {
+    only_signals: bool,
+}
+
+fn produce_raw_data(app: &App) -> Vec<Entry> {
+    let map = &app.primary.map;
+    let mut results = Vec::new();
+    for (i, list) in &app.primary.sim.get_analytics().detailed.queue_spillbacks {
+        if list.is_empty() {
+            continue;
+        }
+        let mut per_hour: BTreeMap<usize, usize> = BTreeMap::new();
+        let mut per_lane: BTreeMap<LaneID, usize> = BTreeMap::new();
+        for (t, l) in list {
+            *per_hour.entry(t.get_hours()).or_insert(0) += 1;
+            *per_lane.entry(*l).or_insert(0) += 1;
+        }
+        results.push(Entry {
+            intersection: *i,
+            name: map.get_i(*i).name(app.opts.language.as_ref(), map),
+            count: list.len(),
+            worst_hour: per_hour
+                .into_iter()
+                .max_by_key(|(_, count)| *count)
+                .unwrap()
+                .0,
+            worst_lane: per_lane
+                .into_iter()
+                .max_by_key(|(_, count)| *count)
+                .unwrap()
+                .0,
+            first: list[0].0,
+            last: list.last().unwrap().0,
+        });
+    }
+    results
+}
+
+fn make_table(data: Vec<Entry>) -> Table<App, Entry, Filters> {
+    let filter: Filter<App, Entry, Filters> = Filter {
+        state: Filters {
+            only_signals: false,
+        },
+        to_controls: Box::new(|ctx, _, state| {
+            Toggle::switch(ctx, "only traffic signals", None, state.only_signals)
+        }),
+        from_controls: Box::new(|panel| Filters {
+            only_signals: panel.is_checked("only traffic signals"),
+        }),
+        apply: Box::new(|state, x, app| {
+            !state.only_signals || app.primary.map.get_i(x.intersection).is_traffic_signal()
+        }),
+    };
+
+    let mut table = Table::new(
+        "queue_spillback",
+        data,
+        Box::new(|x| x.intersection.0.to_string()),
+        "Spillbacks",
+        filter,
+    );
+    table.static_col("Intersection", Box::new(|x| x.name.clone()));
+    table.text_col(
+        "Spillbacks",
+        Box::new(|_, x| prettyprint_usize(x.count)),
+        Col::Sortable(Box::new(|rows| rows.sort_by_key(|x| x.count))),
+    );
+    table.text_col(
+        "Worst hour",
+        Box::new(|_, x| {
+            format!(
+                "{} - {}",
+                (Time::START_OF_DAY + Duration::hours(x.worst_hour)).ampm_tostring(),
+                (Time::START_OF_DAY + Duration::hours(x.worst_hour + 1)).ampm_tostring()
+            )
+        }),
+        Col::Sortable(Box::new(|rows| rows.sort_by_key(|x| x.worst_hour))),
+    );
+    table.text_col(
+        "Most often full",
+        Box::new(|app, x| {
+            app.primary
+                .map
+                .get_parent(x.worst_lane)
+                .get_name(app.opts.language.as_ref())
+        }),
+        Col::Static,
+    );
+    table.text_col(
+        "First",
+        Box::new(|_, x| x.first.ampm_tostring()),
+        Col::Sortable(Box::new(|rows| rows.sort_by_key(|x| x.first))),
+    );
+    table.text_col(
+        "Last",
+        Box::new(|_, x| x.last.ampm_tostring()),
+        Col::Sortable(Box::new(|rows| rows.sort_by_key(|x| x.last))),
+    );
+
+    table
+}
@@ -0,0 +1,289 @@
+use std::collections::BTreeMap;
+
+use abstutil::prettyprint_usize;
+use geom::{Duration, Time};
+use map_gui::tools::{save_file, PopupMsg};
+use map_model::{BusRouteID, BusStopID};
+use widgetry::table::{Col, Filter, Table, EXPORT_CSV};
+use widgetry::{EventCtx, GfxCtx, Line, Outcome, Panel, State, Text, Toggle, Widget};
+
+use crate::app::{App, Transition};
+use crate::info::Tab;
+use crate::sandbox::dashboards::DashTab;
+use crate::sandbox::SandboxMode;
+
+/// Arriving this much earlier than scheduled or less counts as on-time
+const MAX_EARLY: Duration = Duration::const_seconds(60.0);
+/// Arriving this much later than scheduled or less counts as on-time
+const MAX_LATE: Duration = Duration::const_seconds(300.0);
+
+/// Compares when buses and trains reach each stop against their schedule, to find routes that run
+/// late or bunch up.
+pub struct TransitOnTime {
+    tab: DashTab,
+    table: Table<App, Entry, Filters>,
+    panel: Panel,
+}
+
+impl TransitOnTime {
+    pub fn new_state(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        let data = produce_raw_data(app);
+        let total_arrivals: usize = data.iter().map(|x| x.arrivals).sum();
+        let total_on_time: usize = data.iter().map(|x| x.on_time).sum();
+        let summary = if total_arrivals == 0 {
+            Text::from(Line("No buses or trains have reached a scheduled stop yet"))
+        } else {
+            Text::from_multiline(vec![
+                Line(format!(
+                    "{} of {} arrivals at scheduled stops were on-time ({}%)",
+                    prettyprint_usize(total_on_time),
+                    prettyprint_usize(total_arrivals),
+                    100 * total_on_time / total_arrivals
+                )),
+                Line(format!(
+                    "On-time means no more than {} early or {} late. Vehicles wait at the first \
+                     stop if they're early.",
+                    MAX_EARLY.to_string(&app.opts.units),
+                    MAX_LATE.to_string(&app.opts.units)
+                ))
+                .secondary(),
+                Line(
+                    "A vehicle is bunched when it reaches a stop less than a quarter of the \
+                     scheduled headway after the previous one on the same route.",
+                )
+                .secondary(),
+            ])
+        };
+
+        let table = make_table(data);
+        let col = Widget::col(vec![
+            DashTab::TransitOnTime.picker(ctx, app),
+            Widget::col(vec![
+                Line("Transit on-time performance")
+                    .small_heading()
+                    .into_widget(ctx),
+                summary.into_widget(ctx),
+                table.render(ctx, app),
+            ])
+            .section(ctx),
+        ]);
+
+        let panel = Panel::new_builder(col)
+            .exact_size_percent(90, 90)
+            .build(ctx);
+
+        Box::new(Self {
+            tab: DashTab::TransitOnTime,
+            table,
+            panel,
+        })
+    }
+}
+
+impl State<App> for TransitOnTime {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => {
+                if self.table.clicked(&x) {
+                    self.table.replace_render(ctx, app, &mut self.panel);
+                } else if x == EXPORT_CSV {
+                    let result = save_file(&self.table.csv_filename(), self.table.to_csv(app));
+                    return Transition::Push(match result {
+                        Ok(path) => PopupMsg::new_state(
+                            ctx,
+                            "Data exported",
+                            vec![format!("Data exported to {}", path)],
+                        ),
+                        Err(err) => {
+                            PopupMsg::new_state(ctx, "Export failed", vec![err.to_string()])
+                        }
+                    });
+                } else if let Ok(idx) = x.parse::<usize>() {
+                    return open_route_transition(BusRouteID(idx));
+                } else if x == "close" {
+                    return Transition::Pop;
+                } else {
+                    unreachable!()
+                }
+            }
+            Outcome::Changed(_) => {
+                if let Some(t) = self.tab.transition(ctx, app, &self.panel) {
+                    return t;
+                }
+
+                self.table.panel_changed(&self.panel);
+                self.table.replace_render(ctx, app, &mut self.panel);
+            }
+            _ => {}
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.panel.draw(g);
+    }
+}
+
+fn open_route_transition(route: BusRouteID) -> Transition {
+    Transition::Multi(vec![
+        Transition::Pop,
+        Transition::ModifyState(Box::new(move |state, ctx, app| {
+            let sandbox = state.downcast_mut::<SandboxMode>().unwrap();
+            let mut actions = sandbox.contextual_actions();
+            sandbox.controls.common.as_mut().unwrap().launch_info_panel(
+                ctx,
+                app,
+                Tab::BusRoute(route),
+                &mut actions,
+            );
+        })),
+    ])
+}
+
+struct Entry {
+    route: BusRouteID,
+    name: String,
+    /// Arrivals at stops with a scheduled time
+    arrivals: usize,
+    on_time: usize,
+    late: usize,
+    /// Averaged over arrivals that were late
+    avg_lateness: Duration,
+    worst_lateness: Duration,
+    bunched: usize,
+}
+
+struct Filters {
+    only_bunched: bool,
+}
+
+fn produce_raw_data(app: &App) -> Vec<Entry> {
+    let map = &app.primary.map;
+
+    // For every route and stop, (actual, scheduled) arrivals
+    let mut per_stop: BTreeMap<(BusRouteID, BusStopID), Vec<(Time, Time)>> = BTreeMap::new();
+    for (actual, _, route, stop, scheduled) in
+        &app.primary.sim.get_analytics().detailed.bus_schedule_adherence
+    {
+        per_stop
+            .entry((*route, *stop))
+            .or_insert_with(Vec::new)
+            .push((*actual, *scheduled));
+    }
+
+    let mut per_route: BTreeMap<BusRouteID, Entry> = BTreeMap::new();
+    for ((route, _), mut arrivals) in per_stop {
+        let entry = per_route.entry(route).or_insert_with(|| {
+            let r = map.get_br(route);
+            Entry {
+                route,
+                name: format!("{} ({})", r.short_name, r.full_name),
+                arrivals: 0,
+                on_time: 0,
+                late: 0,
+                avg_lateness: Duration::ZERO,
+                worst_lateness: Duration::ZERO,
+                bunched: 0,
+            }
+        });
+
+        for (actual, scheduled) in &arrivals {
+            entry.arrivals += 1;
+            if *actual < *scheduled {
+                if *scheduled - *actual <= MAX_EARLY {
+                    entry.on_time += 1;
+                }
+            } else {
+                let late = *actual - *scheduled;
+                if late <= MAX_LATE {
+                    entry.on_time += 1;
+                }
+                // Accumulate the total for now
+                entry.late += 1;
+                entry.avg_lateness += late;
+                entry.worst_lateness = entry.worst_lateness.max(late);
+            }
+        }
+
+        // Compare consecutive vehicles at this stop
+        arrivals.sort();
+        for pair in arrivals.windows(2) {
+            let actual_gap = pair[1].0 - pair[0].0;
+            let scheduled_gap = pair[1].1 - pair[0].1;
+            if scheduled_gap > Duration::ZERO && actual_gap < scheduled_gap / 4.0 {
+                entry.bunched += 1;
+            }
+        }
+    }
+
+    // Turn the totals into averages
+    per_route
+        .into_values()
+        .map(|mut entry| {
+            if entry.late > 0 {
+                entry.avg_lateness = entry.avg_lateness / (entry.late as f64);
+            }
+            entry
+        })
+        .collect()
+}
+
+fn make_table(data: Vec<Entry>) -> Table<App, Entry, Filters> {
+    let filter: Filter<App, Entry, Filters> = Filter {
+        state: Filters {
+            only_bunched: false,
+        },
+        to_controls: Box::new(|ctx, _, state| {
+            Toggle::switch(ctx, "only routes with bunching", None, state.only_bunched)
+        }),
+        from_controls: Box::new(|panel| Filters {
+            only_bunched: panel.is_checked("only routes with bunching"),
+        }),
+        apply: Box::new(|state, x, _| !state.only_bunched || x.bunched > 0),
+    };
+
+    let mut table = Table::new(
+        "transit_on_time",
+        data,
+        Box::new(|x| x.route.0.to_string()),
+        "Arrivals",
+        filter,
+    );
+    table.static_col("Route", Box::new(|x| x.name.clone()));
+    table.text_col(
+        "Arrivals",
+        Box::new(|_, x| prettyprint_usize(x.arrivals)),
+        Col::Sortable(Box::new(|rows| rows.sort_by_key(|x| x.arrivals))),
+    );
+    table.text_col(
+        "On-time",
+        Box::new(|_, x| format!("{}%", pct_on_time(x))),
+        Col::Sortable(Box::new(|rows| rows.sort_by_key(|x| pct_on_time(x)))),
+    );
+    table.text_col(
+        "Average lateness",
+        Box::new(|app, x| x.avg_lateness.to_string(&app.opts.units)),
+        Col::Sortable(Box::new(|rows| rows.sort_by_key(|x| x.avg_lateness))),
+    );
+    table.text_col(
+        "Worst lateness",
+        Box::new(|app, x| x.worst_lateness.to_string(&app.opts.units)),
+        Col::Sortable(Box::new(|rows| rows.sort_by_key(|x| x.worst_lateness))),
+    );
+    table.text_col(
+        "Bunched arrivals",
+        Box::new(|_, x| prettyprint_usize(x.bunched)),
+        Col::Sortable(Box::new(|rows| rows.sort_by_key(|x| x.bunched))),
+    );
+
+    table
+}
+
+fn pct_on_time(x: &Entry) -> usize {
+    if x.arrivals == 0 {
+        100
+    } else {
+        100 * x.on_time / x.arrivals
+    }
+}
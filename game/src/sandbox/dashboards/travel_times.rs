@@ -13,6 +13,7 @@ use widgetry::{
     Panel, State, Text, TextExt, Toggle, Widget,
 };
 
+use super::report::export_report;
 use super::trip_problems::{problem_matrix, ProblemType, TripProblemFilter};
 use crate::app::{App, Transition};
 use crate::common::color_for_mode;
@@ -50,6 +51,13 @@ impl TravelTimes {
                     .build_def(ctx)
                     .align_bottom(),
             );
+            filters.push(
+                ctx.style()
+                    .btn_plain
+                    .text("Export report")
+                    .tooltip("Summarize the proposal and its effects in a web page to share")
+                    .build_def(ctx),
+            );
         }
 
         Panel::new_builder(Widget::col(vec![
@@ -128,6 +136,18 @@ impl State<App> for TravelTimes {
                         }
                     });
                 }
+                "Export report" => {
+                    return Transition::Push(match export_report(app) {
+                        Ok(path) => PopupMsg::new_state(
+                            ctx,
+                            "Report exported",
+                            vec![format!("Report exported to {}", path)],
+                        ),
+                        Err(err) => {
+                            PopupMsg::new_state(ctx, "Export failed", vec![err.to_string()])
+                        }
+                    });
+                }
                 "close" => Transition::Pop,
                 _ => unreachable!(),
             },
@@ -2,8 +2,9 @@ use std::collections::{BTreeSet, HashMap};
 
 use abstutil::prettyprint_usize;
 use geom::{Duration, Polygon, Time};
+use map_gui::tools::{save_file, PopupMsg};
 use sim::{TripEndpoint, TripID, TripMode};
-use widgetry::table::{Col, Filter, Table};
+use widgetry::table::{Col, Filter, Table, EXPORT_CSV};
 use widgetry::{
     Color, EventCtx, Filler, GeomBatch, GfxCtx, Line, Outcome, Panel, Stash, State, TabController,
     Text, Toggle, Widget,
@@ -134,6 +135,29 @@ impl State<App> for TripTable {
                 {
                     self.unfinished_trips_table
                         .replace_render(ctx, app, &mut self.panel);
+                } else if x == EXPORT_CSV {
+                    // Finished trips get a richer export than the columns in the table
+                    let result = match self.table_tabs.active_tab_idx() {
+                        0 => save_file(&finished_trips_filename(app), export_trips(app)),
+                        1 => save_file(
+                            &self.cancelled_trips_table.csv_filename(),
+                            self.cancelled_trips_table.to_csv(app),
+                        ),
+                        _ => save_file(
+                            &self.unfinished_trips_table.csv_filename(),
+                            self.unfinished_trips_table.to_csv(app),
+                        ),
+                    };
+                    return Transition::Push(match result {
+                        Ok(path) => PopupMsg::new_state(
+                            ctx,
+                            "Data exported",
+                            vec![format!("Data exported to {}", path)],
+                        ),
+                        Err(err) => {
+                            PopupMsg::new_state(ctx, "Export failed", vec![err.to_string()])
+                        }
+                    });
                 } else if let Ok(idx) = x.parse::<usize>() {
                     return open_trip_transition(app, idx);
                 } else if x == "close" {
@@ -311,6 +335,55 @@ fn produce_raw_data(app: &App) -> (Vec<FinishedTrip>, Vec<CancelledTrip>) {
     (finished, cancelled)
 }
 
+fn finished_trips_filename(app: &App) -> String {
+    format!(
+        "finished_trips_{}_{}.csv",
+        app.primary.map.get_name().as_filename(),
+        app.primary.sim.time().as_filename()
+    )
+}
+
+/// Describe every finished trip as CSV, for analysis elsewhere. If there's no prebaked data to
+/// compare against, the columns about the baseline are left blank.
+fn export_trips(app: &App) -> String {
+    // All times are in seconds
+    let mut out =
+        String::from("trip,person,mode,departure,duration,baseline_duration,delay,waiting\n");
+
+    let has_baseline = app.has_prebaked().is_some();
+    let (finished, _) = produce_raw_data(app);
+    for trip in finished {
+        let person = app
+            .primary
+            .sim
+            .trip_to_person(trip.id)
+            .map(|p| p.0.to_string())
+            .unwrap_or_default();
+        let (baseline, delay) = if has_baseline {
+            (
+                trip.duration_before.inner_seconds().to_string(),
+                (trip.duration_after - trip.duration_before)
+                    .inner_seconds()
+                    .to_string(),
+            )
+        } else {
+            (String::new(), String::new())
+        };
+        out.push_str(&format!(
+            "{},{},{:?},{},{},{},{},{}\n",
+            trip.id.0,
+            person,
+            trip.mode,
+            trip.departure.inner_seconds(),
+            trip.duration_after.inner_seconds(),
+            baseline,
+            delay,
+            trip.waiting.inner_seconds()
+        ));
+    }
+    out
+}
+
 fn make_table_finished_trips(app: &App) -> Table<App, FinishedTrip, Filters> {
     let (finished, _) = produce_raw_data(app);
     let filter: Filter<App, FinishedTrip, Filters> = Filter {
@@ -434,14 +507,14 @@ fn make_table_finished_trips(app: &App) -> Table<App, FinishedTrip, Filters> {
         }),
         Col::Static,
     );
-    table.column(
+    table.text_col(
         "Departure",
-        Box::new(|ctx, _, x| Text::from(x.departure.ampm_tostring()).render(ctx)),
+        Box::new(|_, x| x.departure.ampm_tostring()),
         Col::Sortable(Box::new(|rows| rows.sort_by_key(|x| x.departure))),
     );
-    table.column(
+    table.text_col(
         "Duration",
-        Box::new(|ctx, app, x| Text::from(x.duration_after.to_string(&app.opts.units)).render(ctx)),
+        Box::new(|app, x| x.duration_after.to_string(&app.opts.units)),
         Col::Sortable(Box::new(|rows| rows.sort_by_key(|x| x.duration_after))),
     );
 
@@ -486,14 +559,14 @@ fn make_table_finished_trips(app: &App) -> Table<App, FinishedTrip, Filters> {
         );
     }
 
-    table.column(
+    table.text_col(
         "Time spent waiting",
-        Box::new(|ctx, app, x| Text::from(x.waiting.to_string(&app.opts.units)).render(ctx)),
+        Box::new(|app, x| x.waiting.to_string(&app.opts.units)),
         Col::Sortable(Box::new(|rows| rows.sort_by_key(|x| x.waiting))),
     );
-    table.column(
+    table.text_col(
         "Percent waiting",
-        Box::new(|ctx, _, x| Text::from(x.percent_waiting.to_string()).render(ctx)),
+        Box::new(|_, x| x.percent_waiting.to_string()),
         Col::Sortable(Box::new(|rows| rows.sort_by_key(|x| x.percent_waiting))),
     );
 
@@ -578,17 +651,15 @@ fn make_table_cancelled_trips(app: &App) -> Table<App, CancelledTrip, Filters> {
         }),
         Col::Static,
     );
-    table.column(
+    table.text_col(
         "Departure",
-        Box::new(|ctx, _, x| Text::from(x.departure.ampm_tostring()).render(ctx)),
+        Box::new(|_, x| x.departure.ampm_tostring()),
         Col::Sortable(Box::new(|rows| rows.sort_by_key(|x| x.departure))),
     );
     if app.has_prebaked().is_some() {
-        table.column(
+        table.text_col(
             "Estimated duration",
-            Box::new(|ctx, app, x| {
-                Text::from(x.duration_before.to_string(&app.opts.units)).render(ctx)
-            }),
+            Box::new(|app, x| x.duration_before.to_string(&app.opts.units)),
             Col::Sortable(Box::new(|rows| rows.sort_by_key(|x| x.duration_before))),
         );
     }
@@ -679,17 +750,15 @@ fn make_table_unfinished_trips(app: &App) -> Table<App, UnfinishedTrip, Filters>
         }),
         Col::Static,
     );
-    table.column(
+    table.text_col(
         "Departure",
-        Box::new(|ctx, _, x| Text::from(x.departure.ampm_tostring()).render(ctx)),
+        Box::new(|_, x| x.departure.ampm_tostring()),
         Col::Sortable(Box::new(|rows| rows.sort_by_key(|x| x.departure))),
     );
     if app.has_prebaked().is_some() {
-        table.column(
+        table.text_col(
             "Estimated duration",
-            Box::new(|ctx, app, x| {
-                Text::from(x.duration_before.to_string(&app.opts.units)).render(ctx)
-            }),
+            Box::new(|app, x| x.duration_before.to_string(&app.opts.units)),
             Col::Sortable(Box::new(|rows| rows.sort_by_key(|x| x.duration_before))),
         );
     }
@@ -10,6 +10,7 @@ use widgetry::{
 use crate::app::App;
 use crate::app::Transition;
 use crate::challenges::cutscene::{CutsceneBuilder, ShowMessage};
+use crate::challenges::translations::Translations;
 use crate::challenges::{Challenge, HighScore};
 use crate::common::cmp_duration_shorter;
 use crate::edit::EditMode;
@@ -54,47 +55,13 @@ impl OptimizeCommute {
         })
     }
 
-    pub fn cutscene_pt1(ctx: &mut EventCtx, _: &App, mode: &GameplayMode) -> Box<dyn State<App>> {
-        CutsceneBuilder::new("Optimize one commute: part 1")
-            .boss("Listen up, I've got a special job for you today.")
-            .player("What is it? The scooter coalition back with demands for more valet parking?")
-            .boss("No, all the tax-funded valets are still busy with the kayakers.")
-            .boss(
-                "I've got a... friend who's tired of getting stuck in traffic. You've got to make \
-                 their commute as fast as possible.",
-            )
-            .player("Uh, what's so special about them?")
-            .boss(
-                "That's none of your concern! I've anonymized their name, so don't even bother \
-                 digging into what happened in Ballard --",
-            )
-            .boss("JUST GET TO WORK, KID!")
-            .player(
-                "(Somebody's blackmailing the boss. Guess it's time to help this Very Impatient \
-                 Person.)",
-            )
-            .build(ctx, cutscene_task(mode))
+    pub fn cutscene_pt1(ctx: &mut EventCtx, app: &App, mode: &GameplayMode) -> Box<dyn State<App>> {
+        CutsceneBuilder::from_file(ctx, app, "optimize_commute_pt1", cutscene_task(app, mode))
     }
 
-    pub fn cutscene_pt2(ctx: &mut EventCtx, _: &App, mode: &GameplayMode) -> Box<dyn State<App>> {
+    pub fn cutscene_pt2(ctx: &mut EventCtx, app: &App, mode: &GameplayMode) -> Box<dyn State<App>> {
         // TODO The person chosen for this currently has more of an issue needing PBLs, actually.
-        CutsceneBuilder::new("Optimize one commute: part 2")
-            .boss("I've got another, er, friend who's sick of this parking situation.")
-            .player(
-                "Yeah, why do we dedicate so much valuable land to storing unused cars? It's \
-                 ridiculous!",
-            )
-            .boss(
-                "No, I mean, they're tired of having to hunt for parking. You need to make it \
-                 easier.",
-            )
-            .player(
-                "What? We're trying to encourage people to be less car-dependent. Why's this \
-                 \"friend\" more important than the city's carbon-neutral goals?",
-            )
-            .boss("Everyone's calling in favors these days. Just make it happen!")
-            .player("(Too many people have dirt on the boss. Guess we have another VIP to help.)")
-            .build(ctx, cutscene_task(mode))
+        CutsceneBuilder::from_file(ctx, app, "optimize_commute_pt2", cutscene_task(app, mode))
     }
 }
 
@@ -144,23 +111,35 @@ impl GameplayState for OptimizeCommute {
                     )));
                 }
                 "instructions" => {
-                    let contents = (cutscene_task(&self.mode))(ctx);
+                    let contents = (cutscene_task(app, &self.mode))(ctx);
                     return Some(Transition::Push(ShowMessage::new_state(
                         ctx,
+                        app,
                         contents,
                         Color::WHITE,
                     )));
                 }
                 "hint" => {
                     // TODO Multiple hints. Point to follow button.
-                    let mut txt = Text::from("Hints");
+                    let tr = Translations::load(app);
+                    let mut txt = Text::from(tr.get("commute.hints", "Hints"));
                     txt.add_line("");
-                    txt.add_line("Use the locator at the top right to find the VIP.");
-                    txt.add_line("You can wait for one of their trips to begin or end.");
-                    txt.add_line("Focus on trips spent mostly waiting");
+                    txt.add_line(tr.get(
+                        "commute.hints.locator",
+                        "Use the locator at the top right to find the VIP.",
+                    ));
+                    txt.add_line(tr.get(
+                        "commute.hints.wait",
+                        "You can wait for one of their trips to begin or end.",
+                    ));
+                    txt.add_line(tr.get(
+                        "commute.hints.waiting",
+                        "Focus on trips spent mostly waiting",
+                    ));
                     let contents = txt.into_widget(ctx);
                     return Some(Transition::Push(ShowMessage::new_state(
                         ctx,
+                        app,
                         contents,
                         app.cs.panel_bg,
                     )));
@@ -266,12 +245,12 @@ fn final_score(
             goal
         )
     } else {
-        HighScore {
-            goal: format!("make VIP's commute at least {} faster", goal),
-            score: before - after,
-            edits_name: app.primary.map.get_edits().edits_name.clone(),
-        }
-        .record(app, mode.clone());
+        HighScore::new(
+            app,
+            format!("make VIP's commute at least {} faster", goal),
+            before - after,
+        )
+        .record(mode.clone());
 
         next_mode = Challenge::find(&mode).1.map(|c| c.gameplay);
 
@@ -286,54 +265,72 @@ fn final_score(
     FinalScore::new_state(ctx, msg, mode, next_mode)
 }
 
-fn cutscene_task(mode: &GameplayMode) -> Box<dyn Fn(&mut EventCtx) -> Widget> {
+fn cutscene_task(app: &App, mode: &GameplayMode) -> Box<dyn Fn(&mut EventCtx) -> Widget> {
     let goal = match mode {
         GameplayMode::OptimizeCommute(_, d) => *d,
         _ => unreachable!(),
     };
+    let tr = Translations::load(app);
 
     Box::new(move |ctx| {
         let icon_builder = Image::empty().color(Color::BLACK).dims(50.0);
         Widget::custom_col(vec![
             Text::from_multiline(vec![
-                Line(format!("Speed up the VIP's trips by a total of {}", goal)).fg(Color::BLACK),
-                Line("Ignore the damage done to everyone else.").fg(Color::BLACK),
+                Line(tr.format(
+                    "commute.task.summary",
+                    "Speed up the VIP's trips by a total of {}",
+                    goal,
+                ))
+                .fg(Color::BLACK),
+                Line(tr.get(
+                    "commute.task.ignore_damage",
+                    "Ignore the damage done to everyone else.",
+                ))
+                .fg(Color::BLACK),
             ])
             .into_widget(ctx)
             .margin_below(30),
             Widget::row(vec![
                 Widget::col(vec![
-                    Line("Time").fg(Color::BLACK).into_widget(ctx),
+                    Line(tr.get("commute.task.time", "Time"))
+                        .fg(Color::BLACK)
+                        .into_widget(ctx),
                     icon_builder
                         .clone()
                         .source_path("system/assets/tools/time.svg")
                         .into_widget(ctx),
                     Text::from_multiline(vec![
-                        Line("Until the VIP's").fg(Color::BLACK),
-                        Line("last trip is done").fg(Color::BLACK),
+                        Line(tr.get("commute.task.time_1", "Until the VIP's")).fg(Color::BLACK),
+                        Line(tr.get("commute.task.time_2", "last trip is done")).fg(Color::BLACK),
                     ])
                     .into_widget(ctx),
                 ]),
                 Widget::col(vec![
-                    Line("Goal").fg(Color::BLACK).into_widget(ctx),
+                    Line(tr.get("commute.task.goal", "Goal"))
+                        .fg(Color::BLACK)
+                        .into_widget(ctx),
                     icon_builder
                         .clone()
                         .source_path("system/assets/tools/location.svg")
                         .into_widget(ctx),
                     Text::from_multiline(vec![
-                        Line("Speed up the VIP's trips").fg(Color::BLACK),
-                        Line(format!("by at least {}", goal)).fg(Color::BLACK),
+                        Line(tr.get("commute.task.goal_1", "Speed up the VIP's trips"))
+                            .fg(Color::BLACK),
+                        Line(tr.format("commute.task.goal_2", "by at least {}", goal))
+                            .fg(Color::BLACK),
                     ])
                     .into_widget(ctx),
                 ]),
                 Widget::col(vec![
-                    Line("Score").fg(Color::BLACK).into_widget(ctx),
+                    Line(tr.get("commute.task.score", "Score"))
+                        .fg(Color::BLACK)
+                        .into_widget(ctx),
                     icon_builder
                         .source_path("system/assets/tools/star.svg")
                         .into_widget(ctx),
                     Text::from_multiline(vec![
-                        Line("How much time").fg(Color::BLACK),
-                        Line("the VIP saves").fg(Color::BLACK),
+                        Line(tr.get("commute.task.score_1", "How much time")).fg(Color::BLACK),
+                        Line(tr.get("commute.task.score_2", "the VIP saves")).fg(Color::BLACK),
                     ])
                     .into_widget(ctx),
                 ]),
@@ -9,6 +9,7 @@ use widgetry::{
 use crate::app::Transition;
 use crate::app::{App, FindDelayedIntersections};
 use crate::challenges::cutscene::{CutsceneBuilder, ShowMessage};
+use crate::challenges::translations::Translations;
 use crate::challenges::HighScore;
 use crate::common::Warping;
 use crate::edit::EditMode;
@@ -36,48 +37,8 @@ impl FixTrafficSignals {
         })
     }
 
-    pub fn cutscene_pt1(ctx: &mut EventCtx, _: &App, _: &GameplayMode) -> Box<dyn State<App>> {
-        CutsceneBuilder::new("Traffic signal survivor")
-            .boss("I hope you've had your coffee. There's a huge mess downtown.")
-            .player("Did two buses get tangled together again?")
-            .boss("Worse. SCOOT along Mercer is going haywire.")
-            .player("SCOOT?")
-            .boss(
-                "You know, Split Cycle Offset Optimization Technique, the traffic signal \
-                 coordination system? Did you sleep through college or what?",
-            )
-            .boss(
-                "It's offline. All the traffic signals look like they've been reset to industry \
-                 defaults.",
-            )
-            .player("Uh oh. Too much scooter traffic overwhelm it? Eh? EHH?")
-            .boss("...")
-            .boss("You know, not every problem you will face in life is caused by a pun.")
-            .boss(
-                "Most, in fact, will be caused by me ruining your life because you won't take \
-                 your job seriously.",
-            )
-            .player("Sorry, boss.")
-            .extra(
-                "parents.svg.gz",
-                0.6,
-                "Hi, er, we're calling from Lower Queen Anne. What's going on?!",
-            )
-            .extra(
-                "parents.svg.gz",
-                0.6,
-                "We just missed a VERY important appointment. Nobody's moving an inch!",
-            )
-            .boss(
-                "Oh no... reports are coming in, ALL of the traffic signals downtown are screwed \
-                 up!",
-            )
-            .boss(
-                "You need to go fix all of them. But listen, you haven't got much time. Focus on \
-                 the worst problems first.",
-            )
-            .player("Sigh... it's going to be a long day.")
-            .build(ctx, Box::new(cutscene_pt1_task))
+    pub fn cutscene_pt1(ctx: &mut EventCtx, app: &App, _: &GameplayMode) -> Box<dyn State<App>> {
+        CutsceneBuilder::from_file(ctx, app, "traffic_signal_survivor", cutscene_pt1_task(app))
     }
 }
 
@@ -164,29 +125,41 @@ impl GameplayState for FixTrafficSignals {
                     )));
                 }
                 "instructions" => {
-                    let contents = cutscene_pt1_task(ctx);
+                    let contents = (cutscene_pt1_task(app))(ctx);
                     return Some(Transition::Push(ShowMessage::new_state(
                         ctx,
+                        app,
                         contents,
                         Color::WHITE,
                     )));
                 }
                 "hint" => {
                     // TODO Multiple hints. Point to layers.
-                    let mut txt = Text::from("Hint");
+                    let tr = Translations::load(app);
+                    let mut txt = Text::from(tr.get("trafficsig.hint", "Hint"));
                     txt.add_line("");
-                    txt.add_appended(vec![
-                        Line("Press "),
-                        Key::L.txt(ctx),
-                        Line(" to open layers. Try "),
-                        Key::D.txt(ctx),
-                        Line("elay or worst traffic "),
-                        Key::J.txt(ctx),
-                        Line("ams"),
-                    ]);
+                    if tr.language().is_some() {
+                        // The hotkeys are embedded in English words, so translations describe
+                        // them separately
+                        txt.add_line(tr.get(
+                            "trafficsig.hint.layers",
+                            "Press L to open layers. Try Delay or worst traffic Jams",
+                        ));
+                    } else {
+                        txt.add_appended(vec![
+                            Line("Press "),
+                            Key::L.txt(ctx),
+                            Line(" to open layers. Try "),
+                            Key::D.txt(ctx),
+                            Line("elay or worst traffic "),
+                            Key::J.txt(ctx),
+                            Line("ams"),
+                        ]);
+                    }
                     let contents = txt.into_widget(ctx);
                     return Some(Transition::Push(ShowMessage::new_state(
                         ctx,
+                        app,
                         contents,
                         app.cs.panel_bg,
                     )));
@@ -217,15 +190,24 @@ impl GameplayState for FixTrafficSignals {
                 }
                 "explain score" => {
                     // TODO Adjust wording
+                    let tr = Translations::load(app);
                     return Some(Transition::Push(ShowMessage::new_state(
                         ctx,
+                        app,
                         Text::from_multiline(vec![
-                            Line("You changed some traffic signals in the middle of the day."),
-                            Line(
+                            Line(tr.get(
+                                "trafficsig.explain_score.1",
+                                "You changed some traffic signals in the middle of the day.",
+                            )),
+                            Line(tr.get(
+                                "trafficsig.explain_score.2",
                                 "First see if you can survive for a full day, making changes \
                                 along the way.",
-                            ),
-                            Line("Then you should check if your changes work from midnight."),
+                            )),
+                            Line(tr.get(
+                                "trafficsig.explain_score.3",
+                                "Then you should check if your changes work from midnight.",
+                            )),
                         ])
                         .into_widget(ctx),
                         app.cs.panel_bg,
@@ -325,16 +307,16 @@ fn final_score(
     failed: bool,
 ) -> Box<dyn State<App>> {
     let score = app.primary.sim.time() - Time::START_OF_DAY;
-    HighScore {
-        goal: format!(
+    HighScore::new(
+        app,
+        format!(
             "make it {} without delay exceeding {}",
             app.primary.sim.get_end_of_day() - Time::START_OF_DAY,
             THRESHOLD
         ),
         score,
-        edits_name: app.primary.map.get_edits().edits_name.clone(),
-    }
-    .record(app, mode.clone());
+    )
+    .record(mode.clone());
 
     let msg = if failed {
         format!(
@@ -348,51 +330,68 @@ fn final_score(
 }
 
 // TODO Can we automatically transform text and SVG colors?
-fn cutscene_pt1_task(ctx: &mut EventCtx) -> Widget {
-    let icon_builder = Image::empty().color(Color::BLACK).dims(50.0);
-    Widget::custom_col(vec![
-        Text::from_multiline(vec![
-            Line(format!(
-                "Don't let anyone be delayed by one traffic signal more than {}!",
-                THRESHOLD
-            ))
-            .fg(Color::BLACK),
-            Line("Survive as long as possible through 24 hours of a busy weekday.")
+fn cutscene_pt1_task(app: &App) -> Box<dyn Fn(&mut EventCtx) -> Widget> {
+    let tr = Translations::load(app);
+    Box::new(move |ctx| {
+        let icon_builder = Image::empty().color(Color::BLACK).dims(50.0);
+        Widget::custom_col(vec![
+            Text::from_multiline(vec![
+                Line(tr.format(
+                    "trafficsig.task.summary",
+                    "Don't let anyone be delayed by one traffic signal more than {}!",
+                    THRESHOLD,
+                ))
                 .fg(Color::BLACK),
-        ])
-        .into_widget(ctx)
-        .margin_below(30),
-        Widget::custom_row(vec![
-            Widget::col(vec![
-                Line("Time").fg(Color::BLACK).into_widget(ctx),
-                icon_builder
-                    .clone()
-                    .source_path("system/assets/tools/time.svg")
-                    .into_widget(ctx),
-                Line("24 hours").fg(Color::BLACK).into_widget(ctx),
-            ]),
-            Widget::col(vec![
-                Line("Goal").fg(Color::BLACK).into_widget(ctx),
-                icon_builder
-                    .clone()
-                    .source_path("system/assets/tools/location.svg")
-                    .into_widget(ctx),
-                Text::from_multiline(vec![
-                    Line("Keep delay at all intersections").fg(Color::BLACK),
-                    Line(format!("under {}", THRESHOLD)).fg(Color::BLACK),
-                ])
-                .into_widget(ctx),
-            ]),
-            Widget::col(vec![
-                Line("Score").fg(Color::BLACK).into_widget(ctx),
-                icon_builder
-                    .source_path("system/assets/tools/star.svg")
-                    .into_widget(ctx),
-                Line("How long you survive")
-                    .fg(Color::BLACK)
+                Line(tr.get(
+                    "trafficsig.task.survive",
+                    "Survive as long as possible through 24 hours of a busy weekday.",
+                ))
+                .fg(Color::BLACK),
+            ])
+            .into_widget(ctx)
+            .margin_below(30),
+            Widget::custom_row(vec![
+                Widget::col(vec![
+                    Line(tr.get("trafficsig.task.time", "Time"))
+                        .fg(Color::BLACK)
+                        .into_widget(ctx),
+                    icon_builder
+                        .clone()
+                        .source_path("system/assets/tools/time.svg")
+                        .into_widget(ctx),
+                    Line(tr.get("trafficsig.task.time_1", "24 hours"))
+                        .fg(Color::BLACK)
+                        .into_widget(ctx),
+                ]),
+                Widget::col(vec![
+                    Line(tr.get("trafficsig.task.goal", "Goal"))
+                        .fg(Color::BLACK)
+                        .into_widget(ctx),
+                    icon_builder
+                        .clone()
+                        .source_path("system/assets/tools/location.svg")
+                        .into_widget(ctx),
+                    Text::from_multiline(vec![
+                        Line(tr.get("trafficsig.task.goal_1", "Keep delay at all intersections"))
+                            .fg(Color::BLACK),
+                        Line(tr.format("trafficsig.task.goal_2", "under {}", THRESHOLD))
+                            .fg(Color::BLACK),
+                    ])
                     .into_widget(ctx),
-            ]),
+                ]),
+                Widget::col(vec![
+                    Line(tr.get("trafficsig.task.score", "Score"))
+                        .fg(Color::BLACK)
+                        .into_widget(ctx),
+                    icon_builder
+                        .source_path("system/assets/tools/star.svg")
+                        .into_widget(ctx),
+                    Line(tr.get("trafficsig.task.score_1", "How long you survive"))
+                        .fg(Color::BLACK)
+                        .into_widget(ctx),
+                ]),
+            ])
+            .evenly_spaced(),
         ])
-        .evenly_spaced(),
-    ])
+    })
 }
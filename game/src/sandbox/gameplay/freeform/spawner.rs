@@ -6,7 +6,7 @@ use map_model::{BuildingID, NORMAL_LANE_THICKNESS};
 use sim::{IndividTrip, PersonSpec, Scenario, TripEndpoint, TripMode, TripPurpose};
 use widgetry::{
     Choice, Color, EventCtx, GfxCtx, HorizontalAlignment, Key, Line, Outcome, Panel, Spinner,
-    State, TextExt, VerticalAlignment, Widget,
+    State, TextExt, Toggle, VerticalAlignment, Widget,
 };
 
 use crate::app::{App, Transition};
@@ -56,6 +56,7 @@ impl AgentSpawner {
                     "Number of trips:".text_widget(ctx).centered_vert(),
                     Spinner::widget(ctx, "number", (1, 1000), 1, 1),
                 ]),
+                Toggle::checkbox(ctx, "emergency vehicle (only for driving)", None, false),
                 if app.opts.dev {
                     ctx.style()
                         .btn_plain_destructive
@@ -99,15 +100,25 @@ impl State<App> for AgentSpawner {
                     let mut scenario = Scenario::empty(map, "one-shot");
                     let from = self.start.take().unwrap().0;
                     let to = self.goal.take().unwrap().0;
+                    let mode = self.panel.dropdown_value("mode");
+                    let purpose = if mode == TripMode::Drive
+                        && self
+                            .panel
+                            .is_checked("emergency vehicle (only for driving)")
+                    {
+                        TripPurpose::Emergency
+                    } else {
+                        TripPurpose::Shopping
+                    };
                     for _ in 0..self.panel.spinner("number") {
                         scenario.people.push(PersonSpec {
                             orig_id: None,
                             trips: vec![IndividTrip::new(
                                 app.primary.sim.time(),
-                                TripPurpose::Shopping,
+                                purpose,
                                 from,
                                 to,
-                                self.panel.dropdown_value("mode"),
+                                mode,
                             )],
                         });
                     }
@@ -8,7 +8,7 @@ use abstio::MapName;
 use abstutil::Timer;
 use geom::Duration;
 use map_model::{EditCmd, EditIntersection, MapEdits};
-use sim::{OrigPersonID, Scenario, ScenarioGenerator, ScenarioModifier};
+use sim::{DemandProfile, OrigPersonID, Scenario, ScenarioGenerator, ScenarioModifier};
 use widgetry::{
     lctrl, EventCtx, GeomBatch, GfxCtx, Key, Line, Outcome, Panel, State, TextExt, Widget,
 };
@@ -133,7 +133,12 @@ impl GameplayMode {
         if name == "random" {
             LoadScenario::Scenario(ScenarioGenerator::small_run(map).generate(map, &mut rng, timer))
         } else if name == "home_to_work" {
-            LoadScenario::Scenario(ScenarioGenerator::proletariat_robot(map, &mut rng, timer))
+            LoadScenario::Scenario(ScenarioGenerator::proletariat_robot(
+                map,
+                &DemandProfile::default(),
+                &mut rng,
+                timer,
+            ))
         } else if name == "census" {
             let map_area = map.get_boundary_polygon().clone();
             let map_bounds = map.get_gps_bounds().clone();
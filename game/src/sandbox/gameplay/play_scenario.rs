@@ -5,10 +5,13 @@ use maplit::btreeset;
 use abstutil::prettyprint_usize;
 use geom::{Duration, Time};
 use map_gui::tools::{grey_out_map, ChooseSomething, CityPicker, PopupMsg, URLManager};
-use sim::{ScenarioModifier, SlidingWindow, TripMode};
+use sim::{
+    DayToDayLearning, DriverBehaviorRange, ScenarioModifier, SlidingWindow, TripMode, Weather,
+    DEFAULT_CURBSIDE_DWELL,
+};
 use widgetry::{
     lctrl, Choice, Color, EventCtx, GfxCtx, HorizontalAlignment, Key, Line, LinePlot, Outcome,
-    Panel, PlotOptions, Series, SimpleState, Slider, Spinner, State, Text, TextExt,
+    Panel, PlotOptions, Series, SimpleState, Slider, Spinner, State, Text, TextExt, Toggle,
     VerticalAlignment, Widget,
 };
 
@@ -107,6 +110,7 @@ impl GameplayState for PlayScenario {
                 "edit traffic patterns" => {
                     Some(Transition::Push(EditScenarioModifiers::new_state(
                         ctx,
+                        app,
                         self.scenario_name.clone(),
                         self.modifiers.clone(),
                     )))
@@ -216,6 +220,7 @@ struct EditScenarioModifiers {
 impl EditScenarioModifiers {
     pub fn new_state(
         ctx: &mut EventCtx,
+        app: &App,
         scenario_name: String,
         modifiers: Vec<ScenarioModifier>,
     ) -> Box<dyn State<App>> {
@@ -264,6 +269,106 @@ impl EditScenarioModifiers {
                 .text("Repeat schedule multiple days")
                 .build_def(ctx),
         ]));
+        rows.push(Widget::row(vec![
+            Spinner::widget(ctx, "pct_freight", (1, 100), 10_usize, 1),
+            ctx.style()
+                .btn_outline
+                .text("Percent of drivers making freight deliveries")
+                .build_def(ctx),
+        ]));
+        rows.push(Widget::row(vec![
+            Spinner::widget(ctx, "pct_bike_shift", (1, 100), 10_usize, 1),
+            "% of drivers bike if a low-stress route takes at most"
+                .text_widget(ctx)
+                .centered_vert(),
+            Spinner::widget(
+                ctx,
+                "max_biking_time",
+                (Duration::minutes(1), Duration::hours(2)),
+                Duration::minutes(20),
+                Duration::minutes(1),
+            ),
+            ctx.style()
+                .btn_outline
+                .text("Shift drivers to low-stress biking")
+                .build_def(ctx),
+        ]));
+        rows.push(Widget::row(vec![
+            "Drivers go".text_widget(ctx).centered_vert(),
+            Spinner::widget(ctx, "min_speed_pct", (50, 150), 90_usize, 5),
+            "% to".text_widget(ctx).centered_vert(),
+            Spinner::widget(ctx, "max_speed_pct", (50, 150), 110_usize, 5),
+            "% of their usual speed, and react in up to"
+                .text_widget(ctx)
+                .centered_vert(),
+            Spinner::widget(
+                ctx,
+                "max_reaction_time",
+                (Duration::ZERO, Duration::seconds(5.0)),
+                Duration::seconds(1.5),
+                Duration::seconds(0.5),
+            ),
+            ctx.style()
+                .btn_outline
+                .text("Vary driver behavior")
+                .build_def(ctx),
+        ]));
+        rows.push(Widget::row(vec![
+            Widget::dropdown(
+                ctx,
+                "weather",
+                Weather::Rain,
+                Weather::all()
+                    .into_iter()
+                    .map(|w| Choice::new(w.to_string(), w))
+                    .collect(),
+            ),
+            ctx.style()
+                .btn_outline
+                .text("Change the weather")
+                .build_def(ctx),
+        ]));
+        rows.push(Widget::row(vec![
+            Spinner::widget(ctx, "pct_deliveries", (0, 100), 5_usize, 1),
+            "% of driving trips deliver packages and"
+                .text_widget(ctx)
+                .centered_vert(),
+            Spinner::widget(ctx, "pct_ride_hail", (0, 100), 5_usize, 1),
+            "% drop off ride-hail passengers, stopping at the curb for"
+                .text_widget(ctx)
+                .centered_vert(),
+            Spinner::widget(
+                ctx,
+                "curbside_dwell",
+                (Duration::seconds(30.0), Duration::minutes(15)),
+                DEFAULT_CURBSIDE_DWELL,
+                Duration::seconds(30.0),
+            ),
+            ctx.style()
+                .btn_outline
+                .text("Add curbside stops")
+                .build_def(ctx),
+        ]));
+        rows.push(Widget::row(vec![
+            "Simulate".text_widget(ctx).centered_vert(),
+            Spinner::widget(ctx, "learning_days", (1, 30), 5_usize, 1),
+            "previous days first, with".text_widget(ctx).centered_vert(),
+            Spinner::widget(ctx, "learning_rate_pct", (1, 100), 20_usize, 1),
+            "% of people adjusting their departure after each day"
+                .text_widget(ctx)
+                .centered_vert(),
+            ctx.style()
+                .btn_outline
+                .text("Learn from previous days")
+                .build_def(ctx),
+        ]));
+        rows.push(Widget::horiz_separator(ctx, 1.0));
+        rows.push(Toggle::checkbox(
+            ctx,
+            "Drivers cruise around the block looking for parking",
+            None,
+            app.primary.current_flags.sim_flags.opts.cruise_for_parking,
+        ));
         rows.push(Widget::horiz_separator(ctx, 1.0));
         rows.push(
             Widget::row(vec![
@@ -293,8 +398,8 @@ impl EditScenarioModifiers {
 
 impl State<App> for EditScenarioModifiers {
     fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
-        if let Outcome::Clicked(x) = self.panel.event(ctx) {
-            match x.as_ref() {
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
                 "Discard changes" => {
                     return Transition::Pop;
                 }
@@ -336,12 +441,13 @@ impl State<App> for EditScenarioModifiers {
                         Box::new(|name, _, _| {
                             Transition::Multi(vec![
                                 Transition::Pop,
-                                Transition::ConsumeState(Box::new(|state, ctx, _| {
+                                Transition::ConsumeState(Box::new(|state, ctx, app| {
                                     let mut state =
                                         state.downcast::<EditScenarioModifiers>().ok().unwrap();
                                     state.modifiers.push(ScenarioModifier::AddExtraTrips(name));
                                     vec![EditScenarioModifiers::new_state(
                                         ctx,
+                                        app,
                                         state.scenario_name,
                                         state.modifiers,
                                     )]
@@ -356,6 +462,93 @@ impl State<App> for EditScenarioModifiers {
                     ));
                     return Transition::Replace(EditScenarioModifiers::new_state(
                         ctx,
+                        app,
+                        self.scenario_name.clone(),
+                        self.modifiers.clone(),
+                    ));
+                }
+                "Percent of drivers making freight deliveries" => {
+                    self.modifiers.push(ScenarioModifier::AddFreight(
+                        self.panel.spinner("pct_freight"),
+                    ));
+                    return Transition::Replace(EditScenarioModifiers::new_state(
+                        ctx,
+                        app,
+                        self.scenario_name.clone(),
+                        self.modifiers.clone(),
+                    ));
+                }
+                "Shift drivers to low-stress biking" => {
+                    self.modifiers
+                        .push(ScenarioModifier::ShiftToLowStressBiking {
+                            pct_drivers: self.panel.spinner("pct_bike_shift"),
+                            max_biking_time: self.panel.spinner("max_biking_time"),
+                        });
+                    return Transition::Replace(EditScenarioModifiers::new_state(
+                        ctx,
+                        app,
+                        self.scenario_name.clone(),
+                        self.modifiers.clone(),
+                    ));
+                }
+                "Change the weather" => {
+                    self.modifiers
+                        .retain(|m| !matches!(m, ScenarioModifier::Weather(_)));
+                    self.modifiers.push(ScenarioModifier::Weather(
+                        self.panel.dropdown_value("weather"),
+                    ));
+                    return Transition::Replace(EditScenarioModifiers::new_state(
+                        ctx,
+                        app,
+                        self.scenario_name.clone(),
+                        self.modifiers.clone(),
+                    ));
+                }
+                "Add curbside stops" => {
+                    self.modifiers.push(ScenarioModifier::CurbsideStops {
+                        pct_deliveries: self.panel.spinner("pct_deliveries"),
+                        pct_ride_hail: self.panel.spinner("pct_ride_hail"),
+                        dwell_time: self.panel.spinner("curbside_dwell"),
+                    });
+                    return Transition::Replace(EditScenarioModifiers::new_state(
+                        ctx,
+                        app,
+                        self.scenario_name.clone(),
+                        self.modifiers.clone(),
+                    ));
+                }
+                "Learn from previous days" => {
+                    self.modifiers
+                        .retain(|m| !matches!(m, ScenarioModifier::DayToDayLearning(_)));
+                    self.modifiers
+                        .push(ScenarioModifier::DayToDayLearning(DayToDayLearning {
+                            days: self.panel.spinner("learning_days"),
+                            learning_rate_pct: self.panel.spinner("learning_rate_pct"),
+                        }));
+                    return Transition::Replace(EditScenarioModifiers::new_state(
+                        ctx,
+                        app,
+                        self.scenario_name.clone(),
+                        self.modifiers.clone(),
+                    ));
+                }
+                "Vary driver behavior" => {
+                    let min_speed_pct: usize = self.panel.spinner("min_speed_pct");
+                    let max_speed_pct: usize = self.panel.spinner("max_speed_pct");
+                    self.modifiers
+                        .push(ScenarioModifier::DriverBehavior(DriverBehaviorRange {
+                            speed_pct: (
+                                min_speed_pct.min(max_speed_pct),
+                                min_speed_pct.max(max_speed_pct),
+                            ),
+                            reaction_time: (
+                                Duration::ZERO,
+                                self.panel.spinner("max_reaction_time"),
+                            ),
+                        }));
+                    return Transition::Replace(EditScenarioModifiers::new_state(
+                        ctx,
+                        app,
                         self.scenario_name.clone(),
                         self.modifiers.clone(),
                     ));
@@ -365,6 +558,7 @@ impl State<App> for EditScenarioModifiers {
                         self.modifiers.remove(x.parse::<usize>().unwrap() - 1);
                         return Transition::Replace(EditScenarioModifiers::new_state(
                             ctx,
+                            app,
                             self.scenario_name.clone(),
                             self.modifiers.clone(),
                         ));
@@ -372,7 +566,14 @@ impl State<App> for EditScenarioModifiers {
                         unreachable!()
                     }
                 }
+            },
+            Outcome::Changed(_) => {
+                // This takes effect the next time the scenario starts
+                app.primary.current_flags.sim_flags.opts.cruise_for_parking = self
+                    .panel
+                    .is_checked("Drivers cruise around the block looking for parking");
             }
+            _ => {}
         }
 
         Transition::Keep
@@ -530,6 +731,7 @@ impl State<App> for ChangeMode {
                         Transition::Pop,
                         Transition::Replace(EditScenarioModifiers::new_state(
                             ctx,
+                            app,
                             self.scenario_name.clone(),
                             mods,
                         )),
@@ -76,7 +76,7 @@ impl Tutorial {
                                 .unwrap_or_else(|| TutorialPointer::new(0, 0)),
                         ),
                     )),
-                    Transition::Push(intro_story(ctx)),
+                    Transition::Push(intro_story(ctx, app)),
                 ])
             }),
         )
@@ -1382,44 +1382,8 @@ pub fn execute(ctx: &mut EventCtx, app: &mut App, id: ID, action: &str) -> Trans
     Transition::Push(response)
 }
 
-fn intro_story(ctx: &mut EventCtx) -> Box<dyn State<App>> {
-    CutsceneBuilder::new("Introduction")
-        .boss(
-            "Argh, the mayor's on my case again about the West Seattle bridge. This day couldn't \
-             get any worse.",
-        )
-        .player("Er, hello? Boss? I'm --")
-        .boss("Yet somehow it did.. You're the new recruit. Yeah, yeah. Come in.")
-        .boss(
-            "Due to budget cuts, we couldn't hire a real traffic engineer, so we just called some \
-             know-it-all from Reddit who seems to think they can fix Seattle traffic.",
-        )
-        .player("Yes, hi, my name is --")
-        .boss("We can't afford name-tags, didn't you hear, budget cuts? Your name doesn't matter.")
-        .player("What about my Insta handle?")
-        .boss("-glare-")
-        .boss(
-            "Look, you think fixing traffic is easy? Hah! You can't fix one intersection without \
-             breaking ten more.",
-        )
-        .boss(
-            "And everybody wants something different! Bike lanes here! More parking! Faster \
-             buses! Cheaper housing! Less rain! Free this, subsidized that!",
-        )
-        .boss("Light rail and robot cars aren't here to save the day! Know what you'll be using?")
-        .extra("drone.svg", 1.0, "The traffic drone")
-        .player("Is that... duct tape?")
-        .boss(
-            "Can't spit anymore cause of COVID and don't get me started on prayers. Well, off to \
-             training for you!",
-        )
-        .build(
-            ctx,
-            Box::new(|ctx| {
-                Text::from(Line("Use the tutorial to learn the basic controls.").fg(Color::BLACK))
-                    .into_widget(ctx)
-            }),
-        )
+fn intro_story(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+    CutsceneBuilder::from_file(ctx, app, "tutorial_intro", Box::new(|_| Widget::nothing()))
 }
 
 // Assumes ways
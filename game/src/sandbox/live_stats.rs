@@ -0,0 +1,176 @@
+use abstutil::prettyprint_usize;
+use geom::{Duration, Percent, Time};
+use sim::AgentType;
+use widgetry::{
+    EventCtx, GfxCtx, HorizontalAlignment, Line, Outcome, Panel, Text, VerticalAlignment, Widget,
+};
+
+use crate::app::{App, Transition};
+use crate::common::color_for_agent_type;
+
+/// A small panel summarizing the simulation as it runs: how many agents of each type are active,
+/// how many trips have finished, and how much delay those trips hit. It refreshes once per
+/// simulated minute and can be collapsed.
+pub struct LiveStats {
+    panel: Panel,
+    top: f64,
+    shown: bool,
+    last_refresh: Option<Time>,
+
+    // Memoized over the finished trips in Analytics, so each refresh only looks at new ones
+    num_processed: usize,
+    num_successful: usize,
+    total_delay: Duration,
+}
+
+impl LiveStats {
+    /// The panel goes on the left, starting at `top`.
+    pub fn new(ctx: &mut EventCtx, app: &App, top: f64) -> LiveStats {
+        let mut stats = LiveStats {
+            panel: Panel::empty(ctx),
+            top,
+            shown: app.session.show_live_stats,
+            last_refresh: None,
+            num_processed: 0,
+            num_successful: 0,
+            total_delay: Duration::ZERO,
+        };
+        stats.refresh(ctx, app);
+        stats
+    }
+
+    pub fn recreate_panel(&mut self, ctx: &mut EventCtx, app: &App) {
+        self.shown = app.session.show_live_stats;
+        if !self.shown {
+            self.panel = Panel::new_builder(
+                ctx.style()
+                    .btn_plain
+                    .icon_text("system/assets/tools/maximize.svg", "Live stats")
+                    .build_widget(ctx, "show live stats"),
+            )
+            .aligned(
+                HorizontalAlignment::Left,
+                VerticalAlignment::Below(self.top),
+            )
+            .build(ctx);
+            return;
+        }
+
+        let counts = app.primary.sim.num_agents();
+        let total_agents = counts.sum();
+        let mut agents = Text::new();
+        for agent_type in AgentType::all() {
+            let cnt = counts.get(agent_type);
+            agents.add_line(
+                Line(format!(
+                    "{} {}",
+                    prettyprint_usize(cnt),
+                    agent_type.plural_noun()
+                ))
+                .fg(color_for_agent_type(app, agent_type)),
+            );
+            if total_agents > 0 {
+                agents.append(Line(format!(" ({})", Percent::of(cnt, total_agents))).secondary());
+            }
+        }
+
+        let (finished, unfinished) = app.primary.sim.num_trips();
+        let mut trips = Text::new();
+        trips.add_line(Line(format!(
+            "{} of {} trips finished",
+            prettyprint_usize(finished),
+            prettyprint_usize(finished + unfinished)
+        )));
+        if self.num_successful > 0 {
+            trips.add_line(Line(format!(
+                "Average delay so far: {}",
+                self.total_delay / (self.num_successful as f64)
+            )));
+        } else {
+            trips.add_line(Line("Average delay so far: none yet").secondary());
+        }
+
+        self.panel = Panel::new_builder(Widget::col(vec![
+            Widget::row(vec![
+                Line(format!("{} active agents", prettyprint_usize(total_agents)))
+                    .small_heading()
+                    .into_widget(ctx),
+                ctx.style()
+                    .btn_plain
+                    .icon("system/assets/tools/minimize.svg")
+                    .build_widget(ctx, "hide live stats")
+                    .align_right(),
+            ]),
+            agents.into_widget(ctx),
+            trips.into_widget(ctx),
+            ctx.style()
+                .btn_outline
+                .text("More data")
+                .build_widget(ctx, "open dashboards"),
+        ]))
+        .aligned(
+            HorizontalAlignment::Left,
+            VerticalAlignment::Below(self.top),
+        )
+        .build(ctx);
+    }
+
+    /// Catch up on the trips that finished since the last refresh, then rebuild the panel.
+    fn refresh(&mut self, ctx: &mut EventCtx, app: &App) {
+        let sim = &app.primary.sim;
+        let finished_trips = &sim.get_analytics().finished_trips;
+        // The simulation was reset or time went backwards
+        if self.last_refresh.map(|t| t > sim.time()).unwrap_or(false)
+            || finished_trips.len() < self.num_processed
+        {
+            self.num_processed = 0;
+            self.num_successful = 0;
+            self.total_delay = Duration::ZERO;
+        }
+        for (_, id, _, maybe_duration) in &finished_trips[self.num_processed..] {
+            if maybe_duration.is_some() {
+                if let Some((_, waiting, _)) = sim.finished_trip_details(*id) {
+                    self.num_successful += 1;
+                    self.total_delay += waiting;
+                }
+            }
+        }
+        self.num_processed = finished_trips.len();
+        self.last_refresh = Some(sim.time());
+        self.recreate_panel(ctx, app);
+    }
+
+    pub fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Option<Transition> {
+        let now = app.primary.sim.time();
+        let stale = match self.last_refresh {
+            Some(t) => now < t || now - t >= Duration::minutes(1),
+            None => true,
+        };
+        // Also notice if somebody else toggled the panel
+        if stale || self.shown != app.session.show_live_stats {
+            self.refresh(ctx, app);
+        }
+
+        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+            match x.as_ref() {
+                "hide live stats" => {
+                    app.session.show_live_stats = false;
+                    self.recreate_panel(ctx, app);
+                }
+                "show live stats" => {
+                    app.session.show_live_stats = true;
+                    self.recreate_panel(ctx, app);
+                }
+                "open dashboards" => {
+                    return Some(Transition::Push(app.session.dash_tab.launch(ctx, app)));
+                }
+                _ => unreachable!(),
+            }
+        }
+        None
+    }
+
+    pub fn draw(&self, g: &mut GfxCtx) {
+        self.panel.draw(g);
+    }
+}
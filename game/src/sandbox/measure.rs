@@ -0,0 +1,330 @@
+//! Measure distances along the street network and the contents of an area, for quick sanity
+//! checks while evaluating a proposal.
+
+use abstutil::prettyprint_usize;
+use geom::{Distance, Duration, Polygon, UnitFmt};
+use map_model::{BuildingType, MAX_WALKING_SPEED, NORMAL_LANE_THICKNESS};
+use sim::{TripEndpoint, TripMode};
+use widgetry::mapspace::{ObjectID, ToggleZoomed, ToggleZoomedBuilder, World};
+use widgetry::{
+    Color, EventCtx, GfxCtx, HorizontalAlignment, Key, Line, Outcome, Panel, State, Text, Toggle,
+    VerticalAlignment, Widget,
+};
+
+use crate::app::{App, Transition};
+use crate::common::{InputWaypoints, Lasso, WaypointID};
+
+pub struct MeasureTool {
+    panel: Panel,
+    world: World<ID>,
+    draw: ToggleZoomed,
+    waypoints: InputWaypoints,
+    /// Measuring an area, instead of the distance between waypoints
+    measure_area: bool,
+    /// Only while the user is outlining an area
+    lasso: Option<Lasso>,
+    area: Option<Polygon>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum ID {
+    Waypoint(WaypointID),
+}
+impl ObjectID for ID {}
+
+impl MeasureTool {
+    pub fn new_state(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        let mut tool = MeasureTool {
+            panel: Panel::empty(ctx),
+            world: World::bounded(app.primary.map.get_bounds()),
+            draw: ToggleZoomed::empty(ctx),
+            waypoints: InputWaypoints::new(app),
+            measure_area: false,
+            lasso: None,
+            area: None,
+        };
+        tool.update(ctx, app);
+        Box::new(tool)
+    }
+
+    fn update(&mut self, ctx: &mut EventCtx, app: &App) {
+        let mut col = vec![
+            Widget::row(vec![
+                Line("Measure").small_heading().into_widget(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            Toggle::choice(
+                ctx,
+                "measure",
+                "distance",
+                "area",
+                Key::Tab,
+                !self.measure_area,
+            ),
+        ];
+        let mut draw = ToggleZoomed::builder();
+        if self.measure_area {
+            col.push(
+                ctx.style()
+                    .btn_outline
+                    .text("Draw an area")
+                    .hotkey(Key::D)
+                    .disabled(self.lasso.is_some())
+                    .build_def(ctx),
+            );
+            if self.lasso.is_some() {
+                col.push(
+                    Line("Drag on the map to outline an area")
+                        .secondary()
+                        .into_widget(ctx),
+                );
+            } else if let Some(ref polygon) = self.area {
+                col.push(describe_area(ctx, app, polygon));
+                draw.unzoomed.push(Color::CYAN.alpha(0.3), polygon.clone());
+                draw.zoomed.push(Color::CYAN.alpha(0.2), polygon.clone());
+                if let Ok(outline) = polygon.to_outline(Distance::meters(3.0)) {
+                    draw.unzoomed.push(Color::CYAN, outline.clone());
+                    draw.zoomed.push(Color::CYAN.alpha(0.5), outline);
+                }
+            }
+        } else {
+            col.push(self.waypoints.get_panel_widget(ctx));
+            col.push(self.measure_routes(ctx, app, &mut draw));
+        }
+
+        let mut panel = Panel::new_builder(Widget::col(col))
+            .aligned(HorizontalAlignment::Left, VerticalAlignment::Top)
+            // Hovering on waypoint cards
+            .ignore_initial_events()
+            .build(ctx);
+        panel.restore(ctx, &self.panel);
+        self.panel = panel;
+        self.draw = draw.build(ctx);
+
+        let mut world = World::bounded(app.primary.map.get_bounds());
+        if !self.measure_area {
+            self.waypoints
+                .rebuild_world(ctx, &mut world, ID::Waypoint, 0);
+        }
+        world.initialize_hover(ctx);
+        world.rebuilt_during_drag(&self.world);
+        self.world = world;
+    }
+
+    /// Draws the walking and driving routes between the waypoints, and describes them.
+    fn measure_routes(
+        &self,
+        ctx: &mut EventCtx,
+        app: &App,
+        draw: &mut ToggleZoomedBuilder,
+    ) -> Widget {
+        let map = &app.primary.map;
+        let units = &app.opts.units;
+        let waypoints = self.waypoints.get_waypoints();
+        if waypoints.len() < 2 {
+            return Line("Add at least two waypoints to measure between them")
+                .secondary()
+                .into_widget(ctx);
+        }
+
+        let straight_line: Distance = waypoints
+            .windows(2)
+            .map(|pair| pair[0].pt(map).dist_to(pair[1].pt(map)))
+            .sum();
+        let mut txt = Text::new();
+        txt.add_line(Line(format!(
+            "Straight line: {}",
+            straight_line.to_string(units)
+        )));
+
+        // Draw the driving route first, since sidewalks often run right alongside
+        for (mode, label, color, width, speed) in [
+            (
+                TripMode::Drive,
+                "Driving",
+                Color::RED,
+                5.0 * NORMAL_LANE_THICKNESS,
+                None,
+            ),
+            (
+                TripMode::Walk,
+                "Walking",
+                Color::BLUE,
+                3.0 * NORMAL_LANE_THICKNESS,
+                Some(MAX_WALKING_SPEED),
+            ),
+        ] {
+            let mut total_dist = Distance::ZERO;
+            let mut total_time = Duration::ZERO;
+            let mut reachable = true;
+            for pair in waypoints.windows(2) {
+                match TripEndpoint::path_req(pair[0], pair[1], mode, map)
+                    .and_then(|req| map.pathfind(req).ok())
+                {
+                    Some(path) => {
+                        total_dist += path.total_length();
+                        total_time += path.estimate_duration(map, speed);
+                        if let Some(pl) = path.trace(map) {
+                            let shape = pl.make_polygons(width);
+                            draw.unzoomed.push(color.alpha(0.8), shape.clone());
+                            draw.zoomed.push(color.alpha(0.5), shape);
+                        }
+                    }
+                    None => {
+                        reachable = false;
+                    }
+                }
+            }
+
+            txt.add_line(Line(format!("{}: ", label)).fg(color));
+            if reachable {
+                txt.append(Line(format!(
+                    "{}, about {}",
+                    total_dist.to_string(units),
+                    total_time.to_string(units)
+                )));
+            } else {
+                txt.append(Line("no route").secondary());
+            }
+        }
+        txt.add_line(
+            Line("Times assume free-flowing traffic and the speed limit")
+                .secondary()
+                .small(),
+        );
+        txt.into_widget(ctx)
+    }
+}
+
+impl State<App> for MeasureTool {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        if let Some(ref mut lasso) = self.lasso {
+            if ctx.input.pressed(Key::Escape) {
+                self.lasso = None;
+                self.update(ctx, app);
+            } else if let Some(ring) = lasso.event(ctx) {
+                self.lasso = None;
+                self.area = Some(ring.into_polygon());
+                self.update(ctx, app);
+            }
+            return Transition::Keep;
+        }
+
+        let world_outcome = self.world.event(ctx).map_id(|ID::Waypoint(id)| id);
+        let panel_outcome = self.panel.event(ctx);
+        match panel_outcome {
+            Outcome::Clicked(ref x) if x == "close" => {
+                return Transition::Pop;
+            }
+            Outcome::Clicked(ref x) if x == "Draw an area" => {
+                self.lasso = Some(Lasso::new());
+                self.update(ctx, app);
+                return Transition::Keep;
+            }
+            Outcome::Changed(ref x) if x == "measure" => {
+                self.measure_area = !self.panel.is_checked("measure");
+                self.update(ctx, app);
+                return Transition::Keep;
+            }
+            _ => {}
+        }
+
+        if !self.measure_area && self.waypoints.event(app, panel_outcome, world_outcome) {
+            self.update(ctx, app);
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.draw.draw(g);
+        self.world.draw(g);
+        self.panel.draw(g);
+        if let Some(ref lasso) = self.lasso {
+            lasso.draw(g);
+        }
+    }
+}
+
+/// Summarizes what's inside an area: its size, who lives there, and how much parking it has.
+fn describe_area(ctx: &mut EventCtx, app: &App, polygon: &Polygon) -> Widget {
+    let map = &app.primary.map;
+    let bounds = polygon.get_bounds();
+    let inside = |pt| bounds.contains(pt) && polygon.contains_pt(pt);
+
+    let mut buildings = 0;
+    let mut residents = 0;
+    let mut people_inside = 0;
+    let mut offstreet_parking = 0;
+    for b in map.all_buildings() {
+        if !inside(b.label_center) {
+            continue;
+        }
+        buildings += 1;
+        residents += match b.bldg_type {
+            BuildingType::Residential { num_residents, .. } => num_residents,
+            BuildingType::ResidentialCommercial(residents, _) => residents,
+            BuildingType::Commercial(_) | BuildingType::Empty => 0,
+        };
+        people_inside += app.primary.sim.bldg_to_people(b.id).len();
+        offstreet_parking += b.num_parking_spots();
+    }
+    for lot in map.all_parking_lots() {
+        if inside(lot.polygon.center()) {
+            offstreet_parking += lot.capacity();
+        }
+    }
+    let cfg = map.get_config();
+    let mut onstreet_parking = 0;
+    for r in map.all_roads() {
+        for lane in &r.lanes {
+            if lane.is_parking() && inside(lane.lane_center_pts.middle()) {
+                onstreet_parking += lane.number_parking_spots(cfg);
+            }
+        }
+    }
+
+    Text::from_multiline(vec![
+        Line(format!(
+            "Area: {}",
+            describe_square_meters(polygon.geodesic_area(map.get_gps_bounds()), &app.opts.units)
+        )),
+        Line(format!(
+            "{} buildings, {} residents",
+            prettyprint_usize(buildings),
+            prettyprint_usize(residents)
+        )),
+        Line(format!(
+            "{} people inside buildings right now",
+            prettyprint_usize(people_inside)
+        )),
+        Line(format!(
+            "{} on-street and {} off-street parking spots",
+            prettyprint_usize(onstreet_parking),
+            prettyprint_usize(offstreet_parking)
+        )),
+    ])
+    .into_widget(ctx)
+}
+
+fn describe_square_meters(area: f64, units: &UnitFmt) -> String {
+    if units.metric {
+        if area < 10_000.0 {
+            format!("{} m²", prettyprint_usize(area.round() as usize))
+        } else if area < 1_000_000.0 {
+            format!("{:.1} hectares", area / 10_000.0)
+        } else {
+            format!("{:.2} km²", area / 1_000_000.0)
+        }
+    } else {
+        let square_feet = area * 10.7639;
+        let acres = square_feet / 43_560.0;
+        if acres < 1.0 {
+            format!("{} sq ft", prettyprint_usize(square_feet.round() as usize))
+        } else if acres < 640.0 {
+            format!("{:.1} acres", acres)
+        } else {
+            format!("{:.2} sq mi", acres / 640.0)
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use abstutil::prettyprint_usize;
-use map_gui::tools::{MinimapControls, Navigator};
+use map_gui::tools::MinimapControls;
 use widgetry::{
     ControlState, EventCtx, GfxCtx, HorizontalAlignment, Image, Key, Line, Panel, ScreenDims, Text,
     VerticalAlignment, Widget,
@@ -9,6 +9,7 @@ use crate::app::App;
 use crate::app::Transition;
 use crate::common::Warping;
 use crate::layer::PickLayer;
+use crate::sandbox::search::SearchAgents;
 
 pub struct MinimapController;
 
@@ -70,7 +71,7 @@ impl MinimapControls<App> for MinimapController {
     fn panel_clicked(&self, ctx: &mut EventCtx, app: &mut App, action: &str) -> Option<Transition> {
         match action {
             "search" => {
-                return Some(Transition::Push(Navigator::new_state(ctx, app)));
+                return Some(Transition::Push(SearchAgents::new_state(ctx, app)));
             }
             "zoom out fully" => {
                 return Some(Transition::Push(Warping::new_state(
@@ -93,6 +94,7 @@ impl MinimapControls<App> for MinimapController {
             "change layers" => {
                 return Some(Transition::Push(PickLayer::pick(ctx, app)));
             }
+            "measure" => Some(Transition::Push(MeasureTool::new_state(ctx, app))),
             "more data" => Some(Transition::Push(app.session.dash_tab.launch(ctx, app))),
             _ => unreachable!(),
         }
@@ -329,6 +331,11 @@ fn make_tool_panel(ctx: &mut EventCtx, app: &App) -> Widget {
             .image_path("system/assets/tools/search.svg")
             .hotkey(Key::K)
             .build_widget(ctx, "search"),
+        buttons
+            .clone()
+            .image_path("system/assets/tools/ruler.svg")
+            .hotkey(Key::U)
+            .build_widget(ctx, "measure"),
         buttons
             .image_path("system/assets/meters/trip_histogram.svg")
             .hotkey(Key::Q)
@@ -6,18 +6,24 @@ use map_gui::colors::ColorSchemeChoice;
 use map_gui::load::{FileLoader, FutureLoader, MapLoader};
 use map_gui::options::OptionsPanel;
 use map_gui::render::{unzoomed_agent_radius, UnzoomedAgents};
-use map_gui::tools::{ChooseSomething, Minimap, TurnExplorer, URLManager};
+use map_gui::tools::{ChooseSomething, Minimap, Navigator, TurnExplorer, URLManager};
 use map_gui::{AppLike, ID};
-use sim::{Analytics, Scenario};
+use sim::{Analytics, Scenario, Sim, Weather};
 use widgetry::{lctrl, Choice, EventCtx, GfxCtx, Key, Outcome, Panel, State, UpdateType};
 
+use self::dashboards::DashTab;
 pub use self::gameplay::{spawn_agents_around, GameplayMode, TutorialPointer, TutorialState};
+use self::live_stats::LiveStats;
+use self::measure::MeasureTool;
 pub use self::minimap::MinimapController;
 use self::misc_tools::{RoutePreview, TrafficRecorder};
+use self::search::SearchAgents;
+use self::sky::{is_daytime, Precipitation};
 pub use self::speed::{SpeedSetting, TimePanel};
+use self::time_warp::JumpToTime;
 pub use self::time_warp::TimeWarpScreen;
 use crate::app::{App, Transition};
-use crate::common::{tool_panel, CommonState};
+use crate::common::{tool_panel, CommandPalette, CommandRegistry, CommonState};
 use crate::debug::DebugMode;
 use crate::edit::{
     can_edit_lane, EditMode, RoadEditor, SaveEdits, StopSignEditor, TrafficSignalEditor,
@@ -29,8 +35,12 @@ use crate::pregame::TitleScreen;
 
 pub mod dashboards;
 pub mod gameplay;
+mod live_stats;
+mod measure;
 mod minimap;
 mod misc_tools;
+mod search;
+mod sky;
 mod speed;
 mod time_warp;
 
@@ -42,6 +52,7 @@ pub struct SandboxMode {
 
     recalc_unzoomed_agent: Option<Time>,
     last_cs: ColorSchemeChoice,
+    precipitation: Option<Precipitation>,
 }
 
 pub struct SandboxControls {
@@ -50,6 +61,7 @@ pub struct SandboxControls {
     tool_panel: Option<Panel>,
     pub time_panel: Option<TimePanel>,
     minimap: Option<Minimap<App, MinimapController>>,
+    live_stats: Option<LiveStats>,
 }
 
 impl SandboxMode {
@@ -103,16 +115,103 @@ impl SandboxMode {
             gameplay: self.gameplay_mode.clone(),
         }
     }
+
+    /// Everything that can be done from the command palette right now. Most of these are also
+    /// reachable through the panels.
+    fn commands(&self, app: &App) -> CommandRegistry {
+        let mut registry = CommandRegistry::default();
+        if let Some(ref common) = self.controls.common {
+            common.register_commands(app, &mut registry, &self.contextual_actions());
+        }
+
+        if self.controls.minimap.is_some() {
+            registry.add("Change layers", None, |ctx, app| {
+                Transition::Push(PickLayer::pick(ctx, app))
+            });
+            if app.primary.layer.is_some() {
+                registry.add("Hide the current layer", None, |_, app| {
+                    app.primary.layer = None;
+                    Transition::Keep
+                });
+            }
+            for (_, layers) in PickLayer::all(app) {
+                for (name, _) in layers {
+                    registry.add(format!("Show layer: {}", name), None, move |ctx, app| {
+                        match PickLayer::launch(ctx, app, name) {
+                            Some(state) => Transition::Push(state),
+                            None => Transition::Keep,
+                        }
+                    });
+                }
+            }
+            for (name, tab) in DashTab::all(app) {
+                registry.add(
+                    format!("Open dashboard: {}", name),
+                    None,
+                    move |ctx, app| {
+                        app.session.dash_tab = tab;
+                        Transition::Push(tab.launch(ctx, app))
+                    },
+                );
+            }
+            registry.add(
+                "Search for a person, vehicle, or address",
+                None,
+                |ctx, app| Transition::Push(SearchAgents::new_state(ctx, app)),
+            );
+            registry.add("Search by street name", None, |ctx, app| {
+                Transition::Push(Navigator::new_state(ctx, app))
+            });
+            registry.add("Measure distance or area", None, |ctx, app| {
+                Transition::Push(MeasureTool::new_state(ctx, app))
+            });
+        }
+        if self.controls.live_stats.is_some() {
+            registry.add("Toggle live stats", None, |_, app| {
+                app.session.show_live_stats = !app.session.show_live_stats;
+                Transition::Keep
+            });
+        }
+
+        if self.controls.time_panel.is_some() {
+            let mode = self.gameplay_mode.clone();
+            registry.add("Jump to a later time", None, move |ctx, app| {
+                Transition::Push(JumpToTime::new_state(ctx, app, Some(mode)))
+            });
+        }
+        registry.add("Settings", None, |ctx, app| {
+            Transition::Push(OptionsPanel::new_state(ctx, app))
+        });
+        if app.opts.dev {
+            registry.add("Debug mode", Some(lctrl(Key::D)), |ctx, app| {
+                Transition::Push(DebugMode::new_state(ctx, app))
+            });
+        }
+        registry
+    }
 }
 
 impl State<App> for SandboxMode {
     fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
         if app.opts.toggle_day_night_colors {
-            if is_daytime(app) {
-                app.change_color_scheme(ctx, ColorSchemeChoice::DayMode)
+            let scheme = if is_daytime(app) {
+                app.opts.color_scheme.day()
             } else {
-                app.change_color_scheme(ctx, ColorSchemeChoice::NightMode)
+                app.opts.color_scheme.night()
             };
+            app.change_color_scheme(ctx, scheme);
+        }
+
+        // The sim might be replaced with a different scenario
+        let weather = app.primary.sim.get_weather();
+        if self
+            .precipitation
+            .as_ref()
+            .map(|p| p.weather)
+            .unwrap_or(Weather::Clear)
+            != weather
+        {
+            self.precipitation = Precipitation::new(ctx, weather);
         }
 
         if app.opts.color_scheme != self.last_cs {
@@ -142,6 +241,9 @@ impl State<App> for SandboxMode {
         if app.opts.dev && ctx.input.pressed(lctrl(Key::D)) {
             return Transition::Push(DebugMode::new_state(ctx, app));
         }
+        if ctx.input.pressed(lctrl(Key::P)) {
+            return Transition::Push(CommandPalette::new_state(ctx, self.commands(app)));
+        }
 
         if let Some(ref mut m) = self.controls.minimap {
             if let Some(t) = m.event(ctx, app) {
@@ -157,6 +259,13 @@ impl State<App> for SandboxMode {
                 return t;
             }
         }
+        if !app.opts.minimal_controls {
+            if let Some(ref mut stats) = self.controls.live_stats {
+                if let Some(t) = stats.event(ctx, app) {
+                    return t;
+                }
+            }
+        }
 
         // We need to recalculate unzoomed agent mouseover when the mouse is still and time passes
         // (since something could move beneath the cursor), or when the mouse moves.
@@ -214,6 +323,9 @@ impl State<App> for SandboxMode {
     }
 
     fn draw(&self, g: &mut GfxCtx, app: &App) {
+        if let Some(ref p) = self.precipitation {
+            p.draw(g, app);
+        }
         if let Some(ref l) = app.primary.layer {
             l.draw(g, app);
         }
@@ -231,6 +343,11 @@ impl State<App> for SandboxMode {
         if let Some(ref tp) = self.controls.time_panel {
             tp.draw(g);
         }
+        if !app.opts.minimal_controls {
+            if let Some(ref stats) = self.controls.live_stats {
+                stats.draw(g);
+            }
+        }
         if let Some(ref m) = self.controls.minimap {
             m.draw(g, app);
         }
@@ -285,7 +402,7 @@ struct BackToTitleScreen;
 
 impl State<App> for BackToTitleScreen {
     fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
-        app.change_color_scheme(ctx, ColorSchemeChoice::DayMode);
+        app.change_color_scheme(ctx, app.opts.color_scheme.day());
         app.clear_everything(ctx);
         Transition::Clear(vec![TitleScreen::new_state(ctx, app)])
     }
@@ -294,6 +411,7 @@ impl State<App> for BackToTitleScreen {
 }
 
 // pub for Warping
+#[derive(Clone)]
 pub struct Actions {
     is_paused: bool,
     can_interact: bool,
@@ -550,6 +668,22 @@ impl State<App> for SandboxLoader {
                             }
                         }
 
+                        app.session.day_to_day = None;
+                        if let Some(learning) = scenario.day_to_day {
+                            let mut opts = app.primary.current_flags.sim_flags.opts.clone();
+                            let learned = learning.simulate(
+                                &app.primary.map,
+                                scenario,
+                                &opts,
+                                &app.primary.current_flags.sim_flags.make_rng(),
+                                &mut timer,
+                            );
+                            opts.route_with_congestion = Some(learned.congestion);
+                            app.primary.sim = Sim::new(&app.primary.map, opts);
+                            scenario = learned.scenario;
+                            app.session.day_to_day = Some(learned.days);
+                        }
+
                         scenario.instantiate(
                             &mut app.primary.sim,
                             &app.primary.map,
@@ -623,6 +757,7 @@ impl State<App> for SandboxLoader {
                         gameplay_mode: self.mode.clone(),
                         recalc_unzoomed_agent: None,
                         last_cs: app.opts.color_scheme,
+                        precipitation: Precipitation::new(ctx, app.primary.sim.get_weather()),
                     });
 
                     let mut transitions = vec![Transition::Replace(sandbox)];
@@ -666,17 +801,17 @@ fn mouseover_unzoomed_agent_circle(ctx: &mut EventCtx, app: &mut App) {
     }
 }
 
-fn is_daytime(app: &App) -> bool {
-    let hours = app.primary.sim.time().get_hours() % 24;
-    (6..18).contains(&hours)
-}
-
 impl SandboxControls {
     pub fn new(
         ctx: &mut EventCtx,
         app: &App,
         gameplay: &dyn gameplay::GameplayState,
     ) -> SandboxControls {
+        let time_panel = if gameplay.has_time_panel() {
+            Some(TimePanel::new(ctx, app))
+        } else {
+            None
+        };
         SandboxControls {
             common: if gameplay.has_common() {
                 Some(CommonState::new())
@@ -693,11 +828,14 @@ impl SandboxControls {
             } else {
                 None
             },
-            time_panel: if gameplay.has_time_panel() {
-                Some(TimePanel::new(ctx, app))
-            } else {
-                None
-            },
+            live_stats: time_panel.as_ref().and_then(|tp| {
+                if gameplay.has_minimap() {
+                    Some(LiveStats::new(ctx, app, tp.panel.panel_dims().height))
+                } else {
+                    None
+                }
+            }),
+            time_panel,
             minimap: if gameplay.has_minimap() {
                 Some(Minimap::new(ctx, app, MinimapController))
             } else {
@@ -716,5 +854,8 @@ impl SandboxControls {
         if let Some(ref mut minimap) = self.minimap {
             minimap.recreate_panel(ctx, app);
         }
+        if let Some(ref mut stats) = self.live_stats {
+            stats.recreate_panel(ctx, app);
+        }
     }
 }
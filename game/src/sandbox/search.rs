@@ -0,0 +1,176 @@
+use std::collections::BTreeMap;
+
+use map_gui::tools::{grey_out_map, Navigator, PopupMsg};
+use map_gui::ID;
+use map_model::BuildingID;
+use sim::{AgentID, CarID, PersonID, PersonState, TripResult};
+use widgetry::{Autocomplete, EventCtx, GfxCtx, Key, Line, Outcome, Panel, State, Widget};
+
+use crate::app::{App, Transition};
+use crate::common::Warping;
+use crate::info::Tab;
+use crate::sandbox::SandboxMode;
+
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Target {
+    Person(PersonID),
+    Car(CarID),
+    Building(BuildingID),
+}
+
+/// Type a person or vehicle ID or a building address, then jump to its info panel. Only people and
+/// vehicles currently on the map are listed; there can be millions of people in a scenario, and
+/// most of them are inside some building, which can be searched by address instead.
+pub struct SearchAgents {
+    panel: Panel,
+}
+
+impl SearchAgents {
+    pub fn new_state(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        let sim = &app.primary.sim;
+        let mut entries = Vec::new();
+        for agent in sim.active_agents() {
+            if let AgentID::Car(car) = agent {
+                entries.push((car.to_string(), Target::Car(car)));
+            }
+            if let Some(person) = sim.agent_to_person(agent) {
+                entries.push((format!("Person #{}", person.0), Target::Person(person)));
+            }
+        }
+        for b in app.primary.map.all_buildings() {
+            entries.push((b.address.clone(), Target::Building(b.id)));
+        }
+
+        Box::new(SearchAgents {
+            panel: Panel::new_builder(Widget::col(vec![
+                Widget::row(vec![
+                    Line("Search for someone on the map, or an address")
+                        .small_heading()
+                        .into_widget(ctx),
+                    ctx.style().btn_close_widget(ctx),
+                ]),
+                Autocomplete::new_widget(ctx, entries, 10).named("search"),
+                ctx.style()
+                    .btn_outline
+                    .text("Search by street name")
+                    .hotkey(Key::Tab)
+                    .build_def(ctx),
+            ]))
+            .build(ctx),
+        })
+    }
+}
+
+impl State<App> for SearchAgents {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+            match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "Search by street name" => {
+                    return Transition::Replace(Navigator::new_state(ctx, app));
+                }
+                _ => unreachable!(),
+            }
+        }
+        if let Some(targets) = self.panel.autocomplete_done::<Target>("search") {
+            return match targets.into_iter().next() {
+                Some(target) => jump_to(ctx, app, target),
+                None => Transition::Pop,
+            };
+        }
+
+        if self.panel.clicked_outside(ctx) {
+            return Transition::Pop;
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        grey_out_map(g, app);
+        self.panel.draw(g);
+    }
+}
+
+fn jump_to(ctx: &mut EventCtx, app: &mut App, target: Target) -> Transition {
+    match target {
+        Target::Person(p) => {
+            // If they're in the middle of a trip, warping to their current body opens the right tab
+            let pt = match app.primary.sim.get_person(p).state.clone() {
+                PersonState::Trip(t) => {
+                    if let TripResult::Ok(agent) = app.primary.sim.trip_to_agent(t) {
+                        let id = ID::from_agent(agent);
+                        if let Some(pt) = app.primary.canonical_point(id.clone()) {
+                            return Transition::Replace(Warping::new_state(
+                                ctx,
+                                pt,
+                                Some(10.0),
+                                Some(id),
+                                &mut app.primary,
+                            ));
+                        }
+                    }
+                    None
+                }
+                PersonState::Inside(b) => Some(app.primary.map.get_b(b).polygon.center()),
+                PersonState::OffMap => None,
+            };
+            let mut transitions = vec![
+                Transition::Pop,
+                launch_info_panel(Tab::PersonTrips(p, BTreeMap::new())),
+            ];
+            if let Some(pt) = pt {
+                transitions.push(Transition::Push(Warping::new_state(
+                    ctx,
+                    pt,
+                    Some(10.0),
+                    None,
+                    &mut app.primary,
+                )));
+            }
+            Transition::Multi(transitions)
+        }
+        Target::Car(c) => {
+            if let Some(pt) = app.primary.canonical_point(ID::Car(c)) {
+                Transition::Replace(Warping::new_state(
+                    ctx,
+                    pt,
+                    Some(10.0),
+                    Some(ID::Car(c)),
+                    &mut app.primary,
+                ))
+            } else if app.primary.sim.lookup_parked_car(c).is_some() {
+                Transition::Multi(vec![Transition::Pop, launch_info_panel(Tab::ParkedCar(c))])
+            } else {
+                Transition::Replace(PopupMsg::new_state(
+                    ctx,
+                    "Vehicle not found",
+                    vec![format!("{} isn't on the map right now", c)],
+                ))
+            }
+        }
+        Target::Building(b) => Transition::Replace(Warping::new_state(
+            ctx,
+            app.primary.map.get_b(b).polygon.center(),
+            Some(10.0),
+            Some(ID::Building(b)),
+            &mut app.primary,
+        )),
+    }
+}
+
+fn launch_info_panel(tab: Tab) -> Transition {
+    Transition::ModifyState(Box::new(move |state, ctx, app| {
+        // Other states pretty much don't use info panels.
+        if let Some(ref mut s) = state.downcast_mut::<SandboxMode>() {
+            let mut actions = s.contextual_actions();
+            s.controls
+                .common
+                .as_mut()
+                .unwrap()
+                .launch_info_panel(ctx, app, tab, &mut actions);
+        }
+    }))
+}
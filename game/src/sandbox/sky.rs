@@ -0,0 +1,110 @@
+use std::f64::consts::PI;
+
+use chrono::Datelike;
+use rand::{Rng, SeedableRng};
+use rand_xorshift::XorShiftRng;
+
+use geom::{Circle, Distance, Line, Polygon, Pt2D, Time};
+use sim::Weather;
+use widgetry::{Color, Drawable, EventCtx, GeomBatch, GfxCtx, ScreenPt};
+
+use crate::app::App;
+
+/// The sun's center is just below the horizon at sunset, because of refraction.
+const SUNSET_ELEVATION: f64 = -0.833;
+/// The precipitation pattern repeats in squares of this many pixels.
+const TILE_SIZE: f64 = 256.0;
+
+/// Is the sun up over the map right now? The simulation has no calendar date, so use today's, and
+/// treat the simulation time as local solar time.
+pub fn is_daytime(app: &App) -> bool {
+    let gps = app.primary.map.get_gps_bounds();
+    let lat = (gps.min_lat + gps.max_lat) / 2.0;
+    let day_of_year = chrono::Local::now().ordinal();
+    sun_elevation(lat, day_of_year, app.primary.sim.time()) > SUNSET_ELEVATION
+}
+
+/// In degrees above the horizon
+fn sun_elevation(lat: f64, day_of_year: u32, time: Time) -> f64 {
+    let declination =
+        -(23.44_f64.to_radians()) * (2.0 * PI / 365.0 * (day_of_year as f64 + 10.0)).cos();
+    let hours = (time.inner_seconds() / 3600.0) % 24.0;
+    let hour_angle = (15.0 * (hours - 12.0)).to_radians();
+    let lat = lat.to_radians();
+    (lat.sin() * declination.sin() + lat.cos() * declination.cos() * hour_angle.cos())
+        .asin()
+        .to_degrees()
+}
+
+/// Rain or snow falling over the map, moving as the simulation runs.
+pub struct Precipitation {
+    pub weather: Weather,
+    tile: Drawable,
+    tint: Color,
+    /// Pixels per second of simulation time
+    fall_speed: f64,
+}
+
+impl Precipitation {
+    /// Nothing falls from a clear sky
+    pub fn new(ctx: &mut EventCtx, weather: Weather) -> Option<Precipitation> {
+        // Always use the same pattern
+        let mut rng = XorShiftRng::seed_from_u64(42);
+        let mut batch = GeomBatch::new();
+        let (tint, fall_speed) = match weather {
+            Weather::Clear => {
+                return None;
+            }
+            Weather::Rain => {
+                for _ in 0..40 {
+                    let pt = random_pt(&mut rng);
+                    batch.push(
+                        Color::hex("#A4C8E8").alpha(0.6),
+                        Line::must_new(pt, pt.offset(-3.0, 15.0))
+                            .make_polygons(Distance::meters(1.0)),
+                    );
+                }
+                (Color::grey(0.3).alpha(0.15), 400.0)
+            }
+            Weather::Snow => {
+                for _ in 0..30 {
+                    batch.push(
+                        Color::WHITE.alpha(0.8),
+                        Circle::new(random_pt(&mut rng), Distance::meters(2.0)).to_polygon(),
+                    );
+                }
+                (Color::WHITE.alpha(0.15), 60.0)
+            }
+        };
+        Some(Precipitation {
+            weather,
+            tile: ctx.upload(batch),
+            tint,
+            fall_speed,
+        })
+    }
+
+    pub fn draw(&self, g: &mut GfxCtx, app: &App) {
+        g.fork_screenspace();
+        g.draw_polygon(
+            self.tint,
+            Polygon::rectangle(g.canvas.window_width, g.canvas.window_height),
+        );
+        g.unfork();
+
+        let offset = (app.primary.sim.time().inner_seconds() * self.fall_speed) % TILE_SIZE;
+        let mut y = offset - TILE_SIZE;
+        while y < g.canvas.window_height {
+            let mut x = 0.0;
+            while x < g.canvas.window_width {
+                g.redraw_at(ScreenPt::new(x, y), &self.tile);
+                x += TILE_SIZE;
+            }
+            y += TILE_SIZE;
+        }
+    }
+}
+
+fn random_pt(rng: &mut XorShiftRng) -> Pt2D {
+    Pt2D::new(rng.gen_range(0.0..TILE_SIZE), rng.gen_range(0.0..TILE_SIZE))
+}
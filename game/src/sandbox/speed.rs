@@ -153,7 +153,10 @@ impl TimePanel {
             self.create_time_panel(ctx, app).named("time"),
             Widget::custom_row(row),
         ]))
-        .aligned(HorizontalAlignment::Left, VerticalAlignment::Top);
+        .aligned(HorizontalAlignment::Left, VerticalAlignment::Top)
+        .hotkey_group("Time")
+        .describe_hotkey(Key::LeftArrow, "slow down")
+        .describe_hotkey(Key::RightArrow, "speed up");
         if let Some(h) = self.override_height {
             panel = panel.exact_height(h);
         }
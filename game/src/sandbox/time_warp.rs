@@ -5,9 +5,10 @@ use geom::{Duration, Polygon, Pt2D, Ring, Time};
 use map_gui::render::DrawOptions;
 use map_gui::tools::{grey_out_map, PopupMsg};
 use map_gui::ID;
+use sim::Sim;
 use widgetry::{
     Choice, DrawBaselayer, EventCtx, GeomBatch, GfxCtx, Key, Line, Outcome, Panel, ScreenDims,
-    Slider, State, TabController, Text, Toggle, UpdateType, Widget,
+    Slider, State, TabController, Text, TextExt, Toggle, UpdateType, Widget,
 };
 
 use crate::app::{App, FindDelayedIntersections, ShowEverything, Transition};
@@ -91,9 +92,57 @@ impl JumpToTime {
             build_jump_to_delay_button(ctx, app.opts.jump_to_delay),
         ]);
 
+        let checkpoint_btn = ctx
+            .style()
+            .btn_tab
+            .text("Checkpoint")
+            .hotkey(Key::C)
+            .tooltip("Save or resume the simulation");
+        let checkpoints = Sim::list_checkpoints(&app.primary.map);
+        let checkpoint_content = Widget::col(vec![
+            Line("Checkpoint").small_heading().into_widget(ctx),
+            Text::from(Line(
+                "Save the simulation now, so you can come back to this time later without running \
+                 from midnight again.",
+            ))
+            .wrap_to_pct(ctx, 40)
+            .into_widget(ctx),
+            ctx.style()
+                .btn_solid_primary
+                .text(format!(
+                    "Save checkpoint at {}",
+                    app.primary.sim.time().ampm_tostring()
+                ))
+                .build_widget(ctx, "save checkpoint")
+                .centered_horiz()
+                .margin_above(16),
+            if checkpoints.is_empty() {
+                "No checkpoints saved yet"
+                    .text_widget(ctx)
+                    .centered_horiz()
+                    .margin_above(16)
+            } else {
+                Widget::row(vec![
+                    Widget::dropdown(
+                        ctx,
+                        "checkpoint",
+                        checkpoints.last().cloned().unwrap(),
+                        Choice::strings(checkpoints),
+                    ),
+                    ctx.style()
+                        .btn_outline
+                        .text("Resume from checkpoint")
+                        .build_widget(ctx, "resume from checkpoint"),
+                ])
+                .centered_horiz()
+                .margin_above(16)
+            },
+        ]);
+
         let mut tabs = TabController::new("jump_to_time_tabs");
         tabs.push_tab(jump_to_time_btn, jump_to_time_content);
         tabs.push_tab(jump_to_delay_btn, jump_to_delay_content);
+        tabs.push_tab(checkpoint_btn, checkpoint_content);
 
         Box::new(JumpToTime {
             target,
@@ -147,6 +196,48 @@ impl State<App> for JumpToTime {
                         None,
                     ));
                 }
+                "save checkpoint" => {
+                    let name = app.primary.sim.time().as_filename();
+                    let path = ctx.loading_screen("save checkpoint", |_, _| {
+                        app.primary.sim.save_checkpoint(&name)
+                    });
+                    return Transition::Replace(PopupMsg::new_state(
+                        ctx,
+                        "Checkpoint saved",
+                        vec![
+                            format!(
+                                "Saved the simulation at {}",
+                                app.primary.sim.time().ampm_tostring()
+                            ),
+                            path,
+                        ],
+                    ));
+                }
+                "resume from checkpoint" => {
+                    let name: String = self.panel.dropdown_value("checkpoint");
+                    let result = ctx.loading_screen("resume from checkpoint", |_, timer| {
+                        Sim::load_checkpoint(&app.primary.map, &name, timer)
+                    });
+                    return match result {
+                        Ok(sim) => {
+                            app.primary.sim = sim;
+                            app.recalculate_current_selection(ctx);
+                            Transition::Multi(vec![
+                                Transition::Pop,
+                                Transition::ModifyState(Box::new(|state, ctx, app| {
+                                    if let Some(sandbox) = state.downcast_mut::<SandboxMode>() {
+                                        sandbox.controls.recreate_panels(ctx, app);
+                                    }
+                                })),
+                            ])
+                        }
+                        Err(err) => Transition::Replace(PopupMsg::new_state(
+                            ctx,
+                            "Error",
+                            vec![format!("Couldn't resume from checkpoint {}: {}", name, err)],
+                        )),
+                    };
+                }
                 "jump to delay" => {
                     let delay = self.panel.dropdown_value("delay");
                     app.opts.jump_to_delay = delay;
@@ -0,0 +1,326 @@
+use geom::{Distance, Duration};
+use map_model::connectivity::{walking_route, WalkingOptions};
+use map_model::{PathConstraints, PathStep, Position};
+use sim::TripEndpoint;
+use widgetry::mapspace::World;
+use widgetry::{
+    Color, Drawable, EventCtx, GeomBatch, GfxCtx, Line, Outcome, Panel, State, Text, TextExt,
+    Toggle, Widget,
+};
+
+use crate::app::{App, Transition};
+use crate::common::{get_waypoint_text, InputWaypoints, WaypointID};
+use crate::ungap::{Layers, Tab, TakeLayers};
+
+/// Plans walking routes that somebody using a wheelchair can follow, avoiding stairs, steep
+/// grades, roads without sidewalks, and crossings without curb ramps.
+pub struct AccessibleRoute {
+    layers: Layers,
+
+    input_panel: Panel,
+    waypoints: InputWaypoints,
+    world: World<WaypointID>,
+    wheelchair: bool,
+    draw_route: Drawable,
+    draw_inaccessible: Drawable,
+}
+
+impl TakeLayers for AccessibleRoute {
+    fn take_layers(self) -> Layers {
+        self.layers
+    }
+}
+
+impl AccessibleRoute {
+    pub fn new_state(ctx: &mut EventCtx, app: &mut App, layers: Layers) -> Box<dyn State<App>> {
+        let mut state = AccessibleRoute {
+            layers,
+
+            input_panel: Panel::empty(ctx),
+            waypoints: InputWaypoints::new(app),
+            world: World::bounded(app.primary.map.get_bounds()),
+            wheelchair: true,
+            draw_route: Drawable::empty(ctx),
+            draw_inaccessible: Drawable::empty(ctx),
+        };
+        state.recalculate(ctx, app);
+        Box::new(state)
+    }
+
+    fn opts(&self) -> WalkingOptions {
+        if self.wheelchair {
+            WalkingOptions::wheelchair()
+        } else {
+            WalkingOptions::default()
+        }
+    }
+
+    fn recalculate(&mut self, ctx: &mut EventCtx, app: &App) {
+        let map = &app.primary.map;
+
+        let mut batch = GeomBatch::new();
+        if self.wheelchair {
+            for r in map.all_roads() {
+                if !r.is_light_rail() && !r.is_wheelchair_accessible() {
+                    batch.push(Color::RED.alpha(0.5), r.get_thick_polygon());
+                }
+            }
+        }
+        self.draw_inaccessible = ctx.upload(batch);
+
+        let waypoints = self.waypoints.get_waypoints();
+        let mut legs = Vec::new();
+        let mut failed_leg = None;
+        for (idx, pair) in waypoints.windows(2).enumerate() {
+            match endpoint_pos(pair[0], app)
+                .zip(endpoint_pos(pair[1], app))
+                .and_then(|(start, end)| {
+                    walking_route(map, start, end, self.opts()).map(|(duration, steps)| Leg {
+                        start,
+                        end,
+                        duration,
+                        steps,
+                    })
+                }) {
+                Some(leg) => legs.push(leg),
+                None => {
+                    failed_leg = Some(idx);
+                    break;
+                }
+            }
+        }
+
+        let mut batch = GeomBatch::new();
+        for leg in &legs {
+            for step in &leg.steps {
+                let pl = match step {
+                    PathStep::Lane(l) | PathStep::ContraflowLane(l) => {
+                        &map.get_l(*l).lane_center_pts
+                    }
+                    PathStep::Turn(t) => &map.get_t(*t).geom,
+                };
+                batch.push(
+                    Color::CYAN.alpha(0.9),
+                    pl.make_polygons(Distance::meters(3.0)),
+                );
+            }
+        }
+        self.draw_route = ctx.upload(batch);
+
+        let mut world = World::bounded(map.get_bounds());
+        self.waypoints.rebuild_world(ctx, &mut world, |id| id, 1);
+        world.initialize_hover(ctx);
+        world.rebuilt_during_drag(&self.world);
+        self.world = world;
+
+        self.update_input_panel(ctx, app, &legs, failed_leg);
+    }
+
+    fn update_input_panel(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &App,
+        legs: &[Leg],
+        failed_leg: Option<usize>,
+    ) {
+        let mut sections = vec![
+            self.waypoints.get_panel_widget(ctx).section(ctx),
+            Toggle::checkbox(
+                ctx,
+                "Wheelchair: avoid stairs, steep grades, and raised curbs",
+                None,
+                self.wheelchair,
+            )
+            .section(ctx),
+        ];
+
+        if let Some(idx) = failed_leg {
+            sections.push(
+                Text::from(Line(format!(
+                    "There's no accessible route from {} to {}",
+                    get_waypoint_text(idx),
+                    get_waypoint_text(idx + 1)
+                )))
+                .wrap_to_pct(ctx, 20)
+                .into_widget(ctx)
+                .section(ctx),
+            );
+        } else if !legs.is_empty() {
+            let total_time: Duration = legs.iter().map(|leg| leg.duration).sum();
+            let mut col = vec![Line(format!(
+                "Total time: {}",
+                total_time.to_string(&app.opts.units)
+            ))
+            .small_heading()
+            .into_widget(ctx)];
+            for (idx, line) in directions(app, legs).into_iter().enumerate() {
+                col.push(format!("{}. {}", idx + 1, line).text_widget(ctx));
+            }
+            sections.push(Widget::col(col).section(ctx));
+        } else {
+            sections.push(
+                "Click the map to choose where to start and end"
+                    .text_widget(ctx)
+                    .section(ctx),
+            );
+        }
+
+        let mut new_panel = Tab::Accessible.make_left_panel(ctx, app, Widget::col(sections));
+        new_panel.restore_scroll(ctx, &self.input_panel);
+        self.input_panel = new_panel;
+    }
+}
+
+impl State<App> for AccessibleRoute {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        let world_outcome = self.world.event(ctx);
+
+        let panel_outcome = self.input_panel.event(ctx);
+        if let Outcome::Clicked(ref x) = panel_outcome {
+            if let Some(t) = Tab::Accessible.handle_action::<AccessibleRoute>(ctx, app, x) {
+                return t;
+            }
+        }
+        if let Outcome::Changed(ref x) = panel_outcome {
+            if x == "Wheelchair: avoid stairs, steep grades, and raised curbs" {
+                self.wheelchair = self.input_panel.is_checked(x);
+                self.recalculate(ctx, app);
+                return Transition::Keep;
+            }
+        }
+
+        if self.waypoints.event(app, panel_outcome, world_outcome) {
+            self.recalculate(ctx, app);
+        }
+
+        if let Some(t) = self.layers.event(ctx, app) {
+            return t;
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        self.layers.draw(g, app);
+        g.redraw(&self.draw_inaccessible);
+        g.redraw(&self.draw_route);
+        self.input_panel.draw(g);
+        self.world.draw(g);
+    }
+}
+
+struct Leg {
+    start: Position,
+    end: Position,
+    duration: Duration,
+    steps: Vec<PathStep>,
+}
+
+impl Leg {
+    fn step_length(&self, idx: usize, app: &App) -> Distance {
+        let map = &app.primary.map;
+        match self.steps[idx] {
+            PathStep::Lane(l) | PathStep::ContraflowLane(l) => {
+                let len = map.get_l(l).length();
+                let forwards = matches!(self.steps[idx], PathStep::Lane(_));
+                // The first and last steps only cover part of the sidewalk
+                let from = if idx == 0 {
+                    self.start.dist_along()
+                } else if forwards {
+                    Distance::ZERO
+                } else {
+                    len
+                };
+                let to = if idx == self.steps.len() - 1 {
+                    self.end.dist_along()
+                } else if forwards {
+                    len
+                } else {
+                    Distance::ZERO
+                };
+                if to >= from {
+                    to - from
+                } else {
+                    from - to
+                }
+            }
+            PathStep::Turn(t) => map.get_t(t).geom.length(),
+        }
+    }
+}
+
+fn endpoint_pos(endpt: TripEndpoint, app: &App) -> Option<Position> {
+    let map = &app.primary.map;
+    match endpt {
+        TripEndpoint::Bldg(b) => Some(map.get_b(b).sidewalk_pos),
+        TripEndpoint::Border(i) => {
+            // Sidewalks are bidirectional, so any sidewalk touching the border works
+            let i = map.get_i(i);
+            if let Some(l) = i
+                .get_outgoing_lanes(map, PathConstraints::Pedestrian)
+                .get(0)
+            {
+                Some(Position::start(*l))
+            } else {
+                i.get_incoming_lanes(map, PathConstraints::Pedestrian)
+                    .get(0)
+                    .map(|l| Position::end(*l, map))
+            }
+        }
+        TripEndpoint::SuddenlyAppear(pos) => Some(pos),
+    }
+}
+
+/// Describe the route step-by-step, grouping consecutive sidewalks along the same street.
+fn directions(app: &App, legs: &[Leg]) -> Vec<String> {
+    let map = &app.primary.map;
+    let lang = app.opts.language.as_ref();
+    let mut lines = Vec::new();
+    for (leg_idx, leg) in legs.iter().enumerate() {
+        // The street we're walking along and how far
+        let mut current: Option<(String, Distance)> = None;
+        for idx in 0..leg.steps.len() {
+            let dist = leg.step_length(idx, app);
+            match leg.steps[idx] {
+                PathStep::Lane(l) | PathStep::ContraflowLane(l) => {
+                    let name = map.get_parent(l).get_name(lang);
+                    match current {
+                        Some((ref current_name, ref mut total)) if *current_name == name => {
+                            *total += dist;
+                        }
+                        _ => {
+                            flush_street(app, &mut lines, current.take());
+                            current = Some((name, dist));
+                        }
+                    }
+                }
+                PathStep::Turn(t) => {
+                    let turn = map.get_t(t);
+                    if turn.turn_type.pedestrian_crossing() {
+                        flush_street(app, &mut lines, current.take());
+                        lines.push(match turn.crosswalk_over_road(map) {
+                            Some(dr) => format!("Cross {}", map.get_r(dr.road).get_name(lang)),
+                            None => "Cross the intersection".to_string(),
+                        });
+                    } else if let Some((_, ref mut total)) = current {
+                        // Going around a corner
+                        *total += dist;
+                    }
+                }
+            }
+        }
+        flush_street(app, &mut lines, current);
+        lines.push(format!("Arrive at {}", get_waypoint_text(leg_idx + 1)));
+    }
+    lines
+}
+
+fn flush_street(app: &App, lines: &mut Vec<String>, street: Option<(String, Distance)>) {
+    if let Some((name, dist)) = street {
+        lines.push(format!(
+            "Continue along {} for {}",
+            name,
+            dist.to_string(&app.opts.units)
+        ));
+    }
+}
@@ -1,3 +1,4 @@
+mod accessible;
 mod bike_network;
 mod explore;
 mod layers;
@@ -26,6 +27,7 @@ pub enum Tab {
     Trip,
     AddLanes,
     PredictImpact,
+    Accessible,
 }
 
 pub trait TakeLayers {
@@ -114,6 +116,12 @@ impl Tab {
                 "Predict impact",
                 Key::Num4,
             )),
+            build_tab((
+                Tab::Accessible,
+                "system/assets/meters/pedestrian.svg",
+                "Accessible route",
+                Key::Num5,
+            )),
         ]);
 
         let mut panel = Panel::new_builder(Widget::col(vec![header, tabs]))
@@ -157,6 +165,9 @@ impl Tab {
                                 Tab::PredictImpact => {
                                     predict::ShowGaps::new_state(ctx, app, layers)
                                 }
+                                Tab::Accessible => {
+                                    accessible::AccessibleRoute::new_state(ctx, app, layers)
+                                }
                             }),
                         ])
                     }),
@@ -186,6 +197,14 @@ impl Tab {
                 let state = state.downcast::<T>().ok().unwrap();
                 vec![predict::ShowGaps::new_state(ctx, app, state.take_layers())]
             }))),
+            "Accessible route" => Some(Transition::ConsumeState(Box::new(|state, ctx, app| {
+                let state = state.downcast::<T>().ok().unwrap();
+                vec![accessible::AccessibleRoute::new_state(
+                    ctx,
+                    app,
+                    state.take_layers(),
+                )]
+            }))),
             _ => None,
         }
     }
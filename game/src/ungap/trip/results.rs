@@ -230,7 +230,7 @@ impl RouteDetails {
                 // Dummy tiny hitbox
                 Polygon::rectangle(0.0001, 0.0001)
             } else {
-                Polygon::union_all(hitbox_pieces)
+                Polygon::concat_all(hitbox_pieces)
             },
             tooltip_for_alt: None,
         }
@@ -0,0 +1,245 @@
+use std::f64::consts::PI;
+use std::fmt;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::{Angle, Distance, PolyLine, Pt2D};
+
+/// Part of a circle's circumference. It starts at `start` and sweeps in the direction of
+/// increasing angles until `end`. If the two angles are the same, the arc is the full circle.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Arc {
+    pub center: Pt2D,
+    pub radius: Distance,
+    pub start: Angle,
+    pub end: Angle,
+}
+
+impl Arc {
+    /// Creates an arc.
+    pub fn new(center: Pt2D, radius: Distance, start: Angle, end: Angle) -> Arc {
+        Arc {
+            center,
+            radius,
+            start,
+            end,
+        }
+    }
+
+    /// How far the arc sweeps around the center, in (0, 2pi] radians.
+    pub fn sweep_radians(&self) -> f64 {
+        let sweep =
+            (self.end.normalized_radians() - self.start.normalized_radians()).rem_euclid(2.0 * PI);
+        if sweep == 0.0 {
+            2.0 * PI
+        } else {
+            sweep
+        }
+    }
+
+    /// The distance along the arc.
+    pub fn length(&self) -> Distance {
+        self.radius * self.sweep_radians()
+    }
+
+    /// The point where the arc begins.
+    pub fn start_pt(&self) -> Pt2D {
+        self.center.project_away(self.radius, self.start)
+    }
+
+    /// The point where the arc ends.
+    pub fn end_pt(&self) -> Pt2D {
+        self.center.project_away(self.radius, self.end)
+    }
+
+    /// Approximates the arc with straight segments, using as few as possible while keeping every
+    /// segment within `tolerance` of the true curve. The first and last points are exactly the
+    /// start and end of the arc.
+    pub fn to_polyline(&self, tolerance: Distance) -> Result<PolyLine> {
+        PolyLine::deduping_new(self.points(tolerance))
+    }
+
+    fn points(&self, tolerance: Distance) -> Vec<Pt2D> {
+        let sweep = self.sweep_radians();
+        // The furthest a chord spanning theta radians gets from the curve is r * (1 - cos(theta /
+        // 2)). Solve for the largest theta that stays within the tolerance.
+        let max_step = if tolerance < self.radius {
+            2.0 * (1.0 - tolerance / self.radius).acos()
+        } else {
+            PI
+        };
+        let num_segments = if max_step > 0.0 {
+            ((sweep / max_step).ceil() as usize).max(1)
+        } else {
+            1
+        };
+
+        let start = self.start.normalized_radians();
+        (0..=num_segments)
+            .map(|i| {
+                let theta = start + sweep * (i as f64) / (num_segments as f64);
+                self.center
+                    .project_away(self.radius, Angle::new_rads(theta))
+            })
+            .collect()
+    }
+
+    /// Finds the arc with the given radius that's tangent to the line leading from `from` to
+    /// `corner` and the line leading from `corner` to `to`, rounding off the corner. Also returns
+    /// true if the arc has to be traversed backwards (in the direction of decreasing angles) to
+    /// get from the first line to the second. Fails if the lines are parallel or too short to
+    /// fit the arc.
+    pub fn fillet(from: Pt2D, corner: Pt2D, to: Pt2D, radius: Distance) -> Result<(Arc, bool)> {
+        if radius <= Distance::ZERO {
+            bail!("a fillet needs a positive radius, not {}", radius);
+        }
+        let (dx1, dy1) = unit_vector(from, corner)?;
+        let (dx2, dy2) = unit_vector(corner, to)?;
+        let cross = dx1 * dy2 - dy1 * dx2;
+        let dot = dx1 * dx2 + dy1 * dy2;
+        // How much the direction changes at the corner
+        let turn = cross.atan2(dot);
+        if turn.abs() < 1e-6 {
+            bail!(
+                "the lines around {} are parallel; there's no corner",
+                corner
+            );
+        }
+        if (PI - turn.abs()).abs() < 1e-6 {
+            bail!("the lines around {} double back on each other", corner);
+        }
+
+        // How far from the corner the arc touches each line
+        let tangent = radius * (turn.abs() / 2.0).tan();
+        if tangent > from.dist_to(corner) || tangent > corner.dist_to(to) {
+            bail!(
+                "a fillet with radius {} doesn't fit the corner at {}",
+                radius,
+                corner
+            );
+        }
+        let t1 = corner.offset(-dx1 * tangent.inner_meters(), -dy1 * tangent.inner_meters());
+        let t2 = corner.offset(dx2 * tangent.inner_meters(), dy2 * tangent.inner_meters());
+
+        // The center is to the side of the first line that the corner turns towards
+        let (nx, ny) = if turn > 0.0 { (-dy1, dx1) } else { (dy1, -dx1) };
+        let center = t1.offset(nx * radius.inner_meters(), ny * radius.inner_meters());
+
+        let start = Angle::new_rads((t1.y() - center.y()).atan2(t1.x() - center.x()));
+        let end = Angle::new_rads((t2.y() - center.y()).atan2(t2.x() - center.x()));
+        if turn > 0.0 {
+            Ok((Arc::new(center, radius, start, end), false))
+        } else {
+            Ok((Arc::new(center, radius, end, start), true))
+        }
+    }
+}
+
+impl fmt::Display for Arc {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Arc(center {}, radius {}, from {} to {})",
+            self.center, self.radius, self.start, self.end
+        )
+    }
+}
+
+fn unit_vector(from: Pt2D, to: Pt2D) -> Result<(f64, f64)> {
+    let len = from.dist_to(to).inner_meters();
+    if len == 0.0 {
+        bail!("{} and {} are the same point", from, to);
+    }
+    Ok(((to.x() - from.x()) / len, (to.y() - from.y()) / len))
+}
+
+impl PolyLine {
+    /// Rounds off the corner where this polyline ends and `other` begins with a circular arc of
+    /// the given radius, approximated within `tolerance`. Fails if the corner is too sharp for the
+    /// adjacent segments to fit the arc, or if there's no corner at all.
+    pub fn fillet(
+        &self,
+        other: &PolyLine,
+        radius: Distance,
+        tolerance: Distance,
+    ) -> Result<PolyLine> {
+        let corner = self.last_pt();
+        if corner != other.first_pt() {
+            bail!(
+                "the first polyline ends at {}, but the second starts at {}",
+                corner,
+                other.first_pt()
+            );
+        }
+        let before = self.last_line().pt1();
+        let after = other.first_line().pt2();
+        let (arc, backwards) = Arc::fillet(before, corner, after, radius)?;
+        let mut arc_pts = arc.points(tolerance);
+        if backwards {
+            arc_pts.reverse();
+        }
+
+        let mut pts = self.points().clone();
+        pts.pop();
+        pts.extend(arc_pts);
+        pts.extend(other.points().iter().skip(1).cloned());
+        PolyLine::deduping_new(pts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn polyline_stays_within_tolerance() {
+        let arc = Arc::new(
+            Pt2D::new(0.0, 0.0),
+            Distance::meters(10.0),
+            Angle::degrees(0.0),
+            Angle::degrees(90.0),
+        );
+        assert!((arc.length().inner_meters() - 5.0 * PI).abs() < 1e-3);
+
+        let tolerance = Distance::meters(0.1);
+        let pl = arc.to_polyline(tolerance).unwrap();
+        assert!(pl.first_pt() == arc.start_pt());
+        assert!(pl.last_pt() == arc.end_pt());
+        for line in pl.lines() {
+            let mid = line.middle().unwrap();
+            assert!(arc.radius - mid.dist_to(arc.center) <= tolerance + Distance::meters(0.01));
+        }
+    }
+
+    #[test]
+    fn fillet_right_angle() {
+        let first = PolyLine::must_new(vec![Pt2D::new(0.0, 0.0), Pt2D::new(20.0, 0.0)]);
+        let second = PolyLine::must_new(vec![Pt2D::new(20.0, 0.0), Pt2D::new(20.0, 20.0)]);
+        let rounded = first
+            .fillet(&second, Distance::meters(5.0), Distance::meters(0.05))
+            .unwrap();
+        assert!(rounded.first_pt() == first.first_pt());
+        assert!(rounded.last_pt() == second.last_pt());
+        // The corner is cut off, and the arc is a quarter circle centered at (15, 5)
+        for pt in rounded.points() {
+            assert!(pt.dist_to(Pt2D::new(20.0, 0.0)) > Distance::meters(1.0));
+        }
+        assert!(rounded.points().iter().any(|pt| {
+            (pt.dist_to(Pt2D::new(15.0, 5.0)) - Distance::meters(5.0)).abs()
+                < Distance::meters(0.01)
+                && pt.x() > 15.1
+        }));
+
+        // Turning the other way works too
+        let third = PolyLine::must_new(vec![Pt2D::new(20.0, 0.0), Pt2D::new(20.0, -20.0)]);
+        assert!(first
+            .fillet(&third, Distance::meters(5.0), Distance::meters(0.05))
+            .is_ok());
+
+        // A radius that doesn't fit
+        assert!(first
+            .fillet(&second, Distance::meters(50.0), Distance::meters(0.05))
+            .is_err());
+    }
+}
@@ -9,6 +9,9 @@ use serde::{Deserialize, Serialize};
 
 use crate::{Distance, GPSBounds, Pt2D};
 
+/// The mean radius of the earth, in meters
+const EARTH_RADIUS_M: f64 = 6_371_000.0;
+
 /// Represents a (longitude, latitude) point.
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Serialize, Deserialize)]
 pub struct LonLat {
@@ -51,7 +54,6 @@ impl LonLat {
 
     /// Returns the Haversine distance to another point.
     pub(crate) fn gps_dist(self, other: LonLat) -> Distance {
-        let earth_radius_m = 6_371_000.0;
         let lon1 = self.x().to_radians();
         let lon2 = other.x().to_radians();
         let lat1 = self.y().to_radians();
@@ -63,7 +65,27 @@ impl LonLat {
         let a = (delta_lat / 2.0).sin().powi(2)
             + (delta_lon / 2.0).sin().powi(2) * lat1.cos() * lat2.cos();
         let c = 2.0 * a.sqrt().atan2((1.0 - a).sqrt());
-        Distance::meters(earth_radius_m * c)
+        Distance::meters(EARTH_RADIUS_M * c)
+    }
+
+    /// The length of a path along the surface of the earth. Unlike measuring in map-space, this
+    /// doesn't get distorted far from the equator or far from the map's southern edge.
+    pub fn geodesic_length(pts: &[LonLat]) -> Distance {
+        pts.windows(2).map(|pair| pair[0].gps_dist(pair[1])).sum()
+    }
+
+    /// The area enclosed by a ring on the surface of the earth, in square meters. The first and
+    /// last point should match. Uses the spherical approximation from "Some Algorithms for
+    /// Polygons on a Sphere" (Chamberlain and Duquette), which is accurate for city-sized areas.
+    pub fn geodesic_area(pts: &[LonLat]) -> f64 {
+        let mut total = 0.0;
+        for pair in pts.windows(2) {
+            let (lon1, lat1) = (pair[0].x().to_radians(), pair[0].y().to_radians());
+            let (lon2, lat2) = (pair[1].x().to_radians(), pair[1].y().to_radians());
+            total += (lon2 - lon1) * (2.0 + lat1.sin() + lat2.sin());
+        }
+        // The sign depends on the orientation of the ring
+        (total * EARTH_RADIUS_M * EARTH_RADIUS_M / 2.0).abs()
     }
 
     /// Pretty meaningless units, for comparing distances very roughly
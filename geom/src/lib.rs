@@ -6,6 +6,7 @@ extern crate anyhow;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 pub use crate::angle::Angle;
+pub use crate::arc::Arc;
 pub use crate::bounds::{Bounds, GPSBounds};
 pub use crate::circle::Circle;
 pub use crate::distance::Distance;
@@ -15,14 +16,17 @@ pub use crate::gps::LonLat;
 pub use crate::line::{InfiniteLine, Line};
 pub use crate::percent::Percent;
 pub use crate::polygon::{Polygon, Triangle};
-pub use crate::polyline::{ArrowCap, PolyLine};
+pub use crate::polyline::{ArrowCap, PolyLine, ProjectedPoint};
 pub use crate::pt::{HashablePt2D, Pt2D};
+pub use crate::quadtree::QuadTree;
 pub use crate::ring::Ring;
 pub use crate::speed::Speed;
 pub use crate::stats::{HgramValue, Histogram, Statistic};
 pub use crate::time::Time;
+pub use crate::triangulation::triangulate;
 
 mod angle;
+mod arc;
 mod bounds;
 mod circle;
 mod distance;
@@ -34,10 +38,12 @@ mod percent;
 mod polygon;
 mod polyline;
 mod pt;
+mod quadtree;
 mod ring;
 mod speed;
 mod stats;
 mod time;
+mod triangulation;
 
 // About 0.4 inches... which is quite tiny on the scale of things. :)
 pub const EPSILON_DIST: Distance = Distance::const_meters(0.01);
@@ -136,4 +142,43 @@ mod tests {
         assert_eq!(json_roundtrip, 1.2346);
         assert_eq!(bincode_roundtrip, 1.2346);
     }
+
+    #[test]
+    fn geodesic_area_shrinks_away_from_equator() {
+        let square = |lat: f64| {
+            let pts = vec![
+                LonLat::new(0.0, lat),
+                LonLat::new(0.01, lat),
+                LonLat::new(0.01, lat + 0.01),
+                LonLat::new(0.0, lat + 0.01),
+                LonLat::new(0.0, lat),
+            ];
+            let gps = GPSBounds::from(pts.clone());
+            let ring = Ring::must_new(gps.convert(&pts));
+            (LonLat::geodesic_area(&pts), ring.geodesic_area(&gps))
+        };
+
+        // About 1.11km on each side at the equator
+        let (equator, equator_ring) = square(0.0);
+        assert!((equator - 1_234_000.0).abs() < 10_000.0, "got {}", equator);
+        assert!((equator - equator_ring).abs() < 1.0);
+
+        // Lines of longitude are half as far apart at 60 degrees
+        let (north, north_ring) = square(60.0);
+        assert!(
+            (north / equator - 0.5).abs() < 0.01,
+            "got {}",
+            north / equator
+        );
+        assert!((north - north_ring).abs() < 1.0);
+    }
+
+    #[test]
+    fn geodesic_length_roundtrip() {
+        let pts = vec![LonLat::new(10.0, 59.9), LonLat::new(10.01, 59.91)];
+        let gps = GPSBounds::from(pts.clone());
+        let pl = PolyLine::must_new(gps.convert(&pts));
+        let expected = LonLat::geodesic_length(&pts);
+        assert!((pl.geodesic_length(&gps) - expected).abs() < Distance::meters(0.1));
+    }
 }
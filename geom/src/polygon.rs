@@ -12,7 +12,8 @@ use serde::{Deserialize, Serialize};
 use abstutil::Tags;
 
 use crate::{
-    Angle, Bounds, CornerRadii, Distance, GPSBounds, HashablePt2D, LonLat, PolyLine, Pt2D, Ring,
+    triangulate, Angle, Bounds, CornerRadii, Distance, GPSBounds, HashablePt2D, LonLat, PolyLine,
+    Pt2D, Ring,
 };
 
 #[derive(PartialEq, Serialize, Deserialize, Clone, Debug)]
@@ -33,12 +34,7 @@ impl Polygon {
     pub fn buggy_new(orig_pts: Vec<Pt2D>) -> Polygon {
         assert!(orig_pts.len() >= 3);
 
-        let mut vertices = Vec::new();
-        for pt in &orig_pts {
-            vertices.push(pt.x());
-            vertices.push(pt.y());
-        }
-        let indices = downsize(earcutr::earcut(&vertices, &Vec::new(), 2));
+        let indices = downsize(triangulate(&orig_pts, &[]).into_iter().flatten().collect());
 
         Polygon {
             points: orig_pts,
@@ -48,23 +44,19 @@ impl Polygon {
     }
 
     pub fn with_holes(outer: Ring, mut inner: Vec<Ring>) -> Polygon {
-        inner.insert(0, outer);
-        let geojson_style: Vec<Vec<Vec<f64>>> = inner
-            .iter()
-            .map(|ring| {
-                ring.points()
-                    .iter()
-                    .map(|pt| vec![pt.x(), pt.y()])
-                    .collect()
-            })
-            .collect();
-        let (vertices, holes, dims) = earcutr::flatten(&geojson_style);
-        let indices = downsize(earcutr::earcut(&vertices, &holes, dims));
+        let holes: Vec<&[Pt2D]> = inner.iter().map(|ring| ring.points().as_slice()).collect();
+        let indices = downsize(
+            triangulate(outer.points(), &holes)
+                .into_iter()
+                .flatten()
+                .collect(),
+        );
 
+        inner.insert(0, outer);
         Polygon {
-            points: vertices
-                .chunks(2)
-                .map(|pair| Pt2D::new(pair[0], pair[1]))
+            points: inner
+                .iter()
+                .flat_map(|ring| ring.points().clone())
                 .collect(),
             indices,
             rings: if inner.len() == 1 { None } else { Some(inner) },
@@ -107,19 +99,17 @@ impl Polygon {
     }
 
     pub fn triangles(&self) -> Vec<Triangle> {
-        let mut triangles: Vec<Triangle> = Vec::new();
-        for slice in self.indices.chunks_exact(3) {
-            triangles.push(Triangle::new(
-                self.points[slice[0] as usize],
-                self.points[slice[1] as usize],
-                self.points[slice[2] as usize],
-            ));
-        }
-        triangles
+        self.indexed_triangles()
+            .map(|[a, b, c]| Triangle::new(self.points[a], self.points[b], self.points[c]))
+            .collect()
     }
 
-    pub fn raw_for_rendering(&self) -> (&Vec<Pt2D>, &Vec<u16>) {
-        (&self.points, &self.indices)
+    /// The triangles covering this polygon, as triples of indices into `points()`. Renderers
+    /// should upload the points once and use these, instead of triangulating again.
+    pub fn indexed_triangles(&self) -> impl Iterator<Item = [usize; 3]> + '_ {
+        self.indices
+            .chunks_exact(3)
+            .map(|tri| [tri[0] as usize, tri[1] as usize, tri[2] as usize])
     }
 
     /// Does this polygon contain the point either in the interior or right on the border? Haven't
@@ -204,6 +194,11 @@ impl Polygon {
         }
     }
 
+    /// Does this polygon have any holes?
+    pub fn has_holes(&self) -> bool {
+        self.rings.is_some()
+    }
+
     pub fn center(&self) -> Pt2D {
         // TODO dedupe just out of fear of the first/last point being repeated
         let mut pts: Vec<HashablePt2D> = self.points.iter().map(|pt| pt.to_hashable()).collect();
@@ -318,8 +313,10 @@ impl Polygon {
         Polygon::maybe_rounded_rectangle(w, h, r).unwrap_or_else(|| Polygon::rectangle(w, h))
     }
 
+    /// Cheaply combines two polygons, without merging anything that overlaps. This is fine for
+    /// drawing and hit-testing; use `union_all` when the result needs proper rings.
     // TODO Result won't be a nice Ring
-    pub fn union(self, other: Polygon) -> Polygon {
+    pub fn concat(self, other: Polygon) -> Polygon {
         let mut points = self.points;
         let mut indices = self.indices;
         let offset = points.len() as u16;
@@ -334,17 +331,39 @@ impl Polygon {
         }
     }
 
-    pub fn union_all(mut list: Vec<Polygon>) -> Polygon {
+    /// Cheaply combines many polygons, like `concat`.
+    pub fn concat_all(mut list: Vec<Polygon>) -> Polygon {
         let mut result = list.pop().unwrap();
         for p in list {
-            result = result.union(p);
+            result = result.concat(p);
         }
         result
     }
 
-    // TODO Result won't be a nice Ring
+    /// Merges all of the polygons together, returning the disjoint pieces. Holes are preserved.
+    /// Overlapping areas are really merged, so this is more expensive than `concat_all`. The input
+    /// polygons must have a valid outer ring; the results of `concat` don't.
+    pub fn union_all(list: Vec<Polygon>) -> Vec<Polygon> {
+        let mut iter = list.into_iter().map(|p| geo::MultiPolygon(vec![p.into()]));
+        let first = match iter.next() {
+            Some(first) => first,
+            None => return Vec::new(),
+        };
+        from_multi(iter.fold(first, |acc, p| acc.union(&p)))
+    }
+
+    /// Returns the areas covered by both polygons. Holes are preserved.
     pub fn intersection(&self, other: &Polygon) -> Vec<Polygon> {
-        from_multi(to_geo(self.points()).intersection(&to_geo(other.points())))
+        let p1: geo::Polygon<f64> = self.clone().into();
+        let p2: geo::Polygon<f64> = other.clone().into();
+        from_multi(p1.intersection(&p2))
+    }
+
+    /// Returns the areas covered by this polygon, but not the other. Holes are preserved.
+    pub fn difference(&self, other: &Polygon) -> Vec<Polygon> {
+        let p1: geo::Polygon<f64> = self.clone().into();
+        let p2: geo::Polygon<f64> = other.clone().into();
+        from_multi(p1.difference(&p2))
     }
 
     pub fn convex_hull(list: Vec<Polygon>) -> Polygon {
@@ -378,7 +397,7 @@ impl Polygon {
     /// PolyLines won't work, for example.
     pub fn to_outline(&self, thickness: Distance) -> Result<Polygon> {
         if let Some(ref rings) = self.rings {
-            Ok(Polygon::union_all(
+            Ok(Polygon::concat_all(
                 rings.iter().map(|r| r.to_outline(thickness)).collect(),
             ))
         } else {
@@ -400,6 +419,22 @@ impl Polygon {
         to_geo(self.points()).unsigned_area()
     }
 
+    /// The real-world area in square meters, measured on the surface of the earth instead of in
+    /// map-space, and excluding any holes. The `GPSBounds` must be the same one used to produce
+    /// the points.
+    pub fn geodesic_area(&self, gps: &GPSBounds) -> f64 {
+        if let Some(ref rings) = self.rings {
+            let holes: f64 = rings[1..].iter().map(|r| r.geodesic_area(gps)).sum();
+            (rings[0].geodesic_area(gps) - holes).max(0.0)
+        } else {
+            let mut pts = gps.convert_back(&self.points);
+            if pts.len() > 1 && pts[0] != *pts.last().unwrap() {
+                pts.push(pts[0]);
+            }
+            LonLat::geodesic_area(&pts)
+        }
+    }
+
     /// Doesn't handle multiple crossings in and out.
     pub fn clip_polyline(&self, input: &PolyLine) -> Option<Vec<Pt2D>> {
         let ring = Ring::must_new(self.points.clone());
@@ -442,7 +477,9 @@ impl Polygon {
                 return Some(input.points().clone());
             }
         } else if hits.len() == 2 {
-            let (pl1, pl2) = input.get_both_slices_btwn(hits[0], hits[1])?;
+            let (pl1, pl2) = input
+                .get_both_slices_between(hits[0], hits[1], Distance::ZERO)
+                .ok()?;
 
             // One of these should be partly outside the polygon. The endpoints won't be in the
             // polygon itself, but they'll be on the ring.
@@ -634,7 +671,7 @@ impl From<geo::Polygon<f64>> for Polygon {
 impl From<Polygon> for geo::Polygon<f64> {
     fn from(poly: Polygon) -> Self {
         if let Some(mut rings) = poly.rings {
-            let exterior = rings.pop().expect("expected poly.rings[0] to be exterior");
+            let exterior = rings.remove(0);
             let interiors: Vec<geo::LineString<f64>> =
                 rings.into_iter().map(geo::LineString::from).collect();
             Self::new(exterior.into(), interiors)
@@ -651,19 +688,28 @@ impl From<Polygon> for geo::Polygon<f64> {
 }
 
 fn from_multi(multi: geo::MultiPolygon<f64>) -> Vec<Polygon> {
-    // TODO This should just call Polygon::from, but while importing maps, it seems like
-    // intersection() is hitting non-Ring cases that crash. So keep using buggy_new for now.
+    // This should just call Polygon::from, but while importing maps, it seems like intersection()
+    // sometimes produces things that aren't valid Rings. In that case, use buggy_new on the outer
+    // points and lose any holes.
     multi
         .into_iter()
         .map(|p| {
-            let pts = p
-                .into_inner()
-                .0
-                .into_points()
+            let (exterior, interiors) = p.into_inner();
+            let to_pts = |ls: geo::LineString<f64>| -> Vec<Pt2D> {
+                ls.into_points()
+                    .into_iter()
+                    .map(|pt| Pt2D::new(pt.x(), pt.y()))
+                    .collect()
+            };
+            let outer_pts = to_pts(exterior);
+            let inner: Result<Vec<Ring>> = interiors
                 .into_iter()
-                .map(|pt| Pt2D::new(pt.x(), pt.y()))
+                .map(|ls| Ring::new(to_pts(ls)))
                 .collect();
-            Polygon::buggy_new(pts)
+            match (Ring::new(outer_pts.clone()), inner) {
+                (Ok(outer), Ok(inner)) => Polygon::with_holes(outer, inner),
+                _ => Polygon::buggy_new(outer_pts),
+            }
         })
         .collect()
 }
@@ -679,3 +725,89 @@ fn downsize(input: Vec<usize>) -> Vec<u16> {
     }
     output
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(x: f64, y: f64, size: f64) -> Polygon {
+        Ring::must_new(vec![
+            Pt2D::new(x, y),
+            Pt2D::new(x + size, y),
+            Pt2D::new(x + size, y + size),
+            Pt2D::new(x, y + size),
+            Pt2D::new(x, y),
+        ])
+        .into_polygon()
+    }
+
+    fn total_area(list: &[Polygon]) -> f64 {
+        list.iter().map(|p| p.area()).sum()
+    }
+
+    #[test]
+    fn test_union_all() {
+        assert!(Polygon::union_all(Vec::new()).is_empty());
+
+        // Overlapping squares merge into one piece
+        let merged = Polygon::union_all(vec![square(0.0, 0.0, 10.0), square(5.0, 0.0, 10.0)]);
+        assert_eq!(merged.len(), 1);
+        assert!((total_area(&merged) - 150.0).abs() < 0.01);
+
+        // Disjoint ones stay separate
+        let separate = Polygon::union_all(vec![square(0.0, 0.0, 10.0), square(20.0, 0.0, 10.0)]);
+        assert_eq!(separate.len(), 2);
+        assert!((total_area(&separate) - 200.0).abs() < 0.01);
+
+        // Holes survive
+        let donut = square(0.0, 0.0, 30.0)
+            .difference(&square(10.0, 10.0, 10.0))
+            .remove(0);
+        let merged = Polygon::union_all(vec![donut, square(25.0, 0.0, 10.0)]);
+        assert_eq!(merged.len(), 1);
+        assert!(merged[0].has_holes());
+
+        let ring_union = square(0.0, 0.0, 10.0)
+            .into_ring()
+            .union(&square(5.0, 0.0, 10.0).into_ring());
+        assert!((total_area(&ring_union) - 150.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_intersection() {
+        let overlap = square(0.0, 0.0, 10.0).intersection(&square(5.0, 0.0, 10.0));
+        assert_eq!(overlap.len(), 1);
+        assert!((total_area(&overlap) - 50.0).abs() < 0.01);
+
+        assert!(square(0.0, 0.0, 10.0)
+            .intersection(&square(20.0, 0.0, 10.0))
+            .is_empty());
+
+        let ring_overlap = square(0.0, 0.0, 10.0)
+            .into_ring()
+            .intersection(&square(5.0, 5.0, 10.0).into_ring());
+        assert!((total_area(&ring_overlap) - 25.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_difference() {
+        let rest = square(0.0, 0.0, 10.0).difference(&square(5.0, 0.0, 10.0));
+        assert_eq!(rest.len(), 1);
+        assert!((total_area(&rest) - 50.0).abs() < 0.01);
+
+        // Cutting out the middle leaves a hole
+        let donut = square(0.0, 0.0, 30.0).difference(&square(10.0, 10.0, 10.0));
+        assert_eq!(donut.len(), 1);
+        assert!(donut[0].has_holes());
+
+        // Nothing's left when the other polygon covers everything
+        assert!(square(5.0, 5.0, 5.0)
+            .difference(&square(0.0, 0.0, 30.0))
+            .is_empty());
+
+        let ring_rest = square(0.0, 0.0, 10.0)
+            .into_ring()
+            .difference(&square(0.0, 0.0, 5.0).into_ring());
+        assert!((total_area(&ring_rest) - 75.0).abs() < 0.01);
+    }
+}
@@ -2,16 +2,17 @@ use std::collections::HashSet;
 use std::fmt;
 
 use anyhow::{Context, Result};
+use geo::algorithm::simplify::Simplify;
 use geo::prelude::ClosestPoint;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    Angle, Bounds, Distance, GPSBounds, HashablePt2D, InfiniteLine, Line, Polygon, Pt2D, Ring,
-    EPSILON_DIST,
+    Angle, Bounds, Distance, GPSBounds, HashablePt2D, InfiniteLine, Line, LonLat, Polygon, Pt2D,
+    Ring, EPSILON_DIST,
 };
 
 // TODO How to tune this?
-const MITER_THRESHOLD: f64 = 500.0;
+pub(crate) const MITER_THRESHOLD: f64 = 500.0;
 
 // TODO There used to be a second style that just has extra little hooks going out
 pub enum ArrowCap {
@@ -30,6 +31,20 @@ pub struct PolyLine {
     length: Distance,
 }
 
+/// Where a point projects onto a `PolyLine`, as returned by `PolyLine::project_pt_detailed`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ProjectedPoint {
+    /// The closest point on the polyline
+    pub pt: Pt2D,
+    /// How far along the polyline `pt` is
+    pub dist_along: Distance,
+    /// The index of the line segment containing `pt`. If the closest point is a shared vertex,
+    /// this is the earlier segment.
+    pub segment: usize,
+    /// The distance from the query to `pt`
+    pub distance: Distance,
+}
+
 impl PolyLine {
     pub fn new(pts: Vec<Pt2D>) -> Result<PolyLine> {
         if pts.len() < 2 {
@@ -437,6 +452,12 @@ impl PolyLine {
         self.shift_with_corrections(width)
     }
 
+    /// Shifts the polyline `width` to the left and to the right, returning both sides in that
+    /// order. Fails if either side can't be shifted.
+    pub fn shift_both_sides(&self, width: Distance) -> Result<(PolyLine, PolyLine)> {
+        Ok((self.shift_left(width)?, self.shift_right(width)?))
+    }
+
     // Things to remember about shifting polylines:
     // - the length before and after probably don't match up
     // - the number of points may not match
@@ -450,7 +471,11 @@ impl PolyLine {
         }
     }
 
-    fn shift_with_sharp_angles(&self, width: Distance, miter_threshold: f64) -> Vec<Pt2D> {
+    pub(crate) fn shift_with_sharp_angles(
+        &self,
+        width: Distance,
+        miter_threshold: f64,
+    ) -> Vec<Pt2D> {
         if self.pts.len() == 2 {
             let l = Line::must_new(self.pts[0], self.pts[1]).shift_either_direction(width);
             return vec![l.pt1(), l.pt2()];
@@ -875,6 +900,12 @@ impl PolyLine {
         }
     }
 
+    /// The real-world length of this polyline, measured along the surface of the earth instead of
+    /// in map-space. The `GPSBounds` must be the same one used to produce the points.
+    pub fn geodesic_length(&self, gps: &GPSBounds) -> Distance {
+        LonLat::geodesic_length(&gps.convert_back(&self.pts))
+    }
+
     /// Produces a GeoJSON linestring, optionally mapping the world-space points back to GPS.
     pub fn to_geojson(&self, gps: Option<&GPSBounds>) -> geojson::Geometry {
         let mut pts = Vec::new();
@@ -903,6 +934,40 @@ impl PolyLine {
         }
     }
 
+    /// Finds the point on the polyline closest to the query, and describes where it is. Unlike
+    /// `project_pt` followed by `dist_along_of_point`, this always succeeds.
+    pub fn project_pt_detailed(&self, query: Pt2D) -> ProjectedPoint {
+        let mut best: Option<ProjectedPoint> = None;
+        let mut dist_so_far = Distance::ZERO;
+        for (segment, l) in self.lines().enumerate() {
+            let dx = l.pt2().x() - l.pt1().x();
+            let dy = l.pt2().y() - l.pt1().y();
+            // Lines always have a non-zero length, so this is safe
+            let percent = (((query.x() - l.pt1().x()) * dx + (query.y() - l.pt1().y()) * dy)
+                / (dx * dx + dy * dy))
+                .max(0.0)
+                .min(1.0);
+            let pt = l.unbounded_percent_along(percent);
+            let distance = pt.dist_to(query);
+            if best.as_ref().map(|b| distance < b.distance).unwrap_or(true) {
+                best = Some(ProjectedPoint {
+                    pt,
+                    dist_along: dist_so_far + percent * l.length(),
+                    segment,
+                    distance,
+                });
+            }
+            dist_so_far += l.length();
+        }
+        // A PolyLine always has at least one line
+        best.unwrap()
+    }
+
+    /// The shortest distance from the query to any point on the polyline.
+    pub fn distance_to_point(&self, query: Pt2D) -> Distance {
+        self.project_pt_detailed(query).distance
+    }
+
     /// Returns the angle from the start to end of this polyline.
     pub fn overall_angle(&self) -> Angle {
         self.first_pt().angle_to(self.last_pt())
@@ -990,6 +1055,49 @@ impl PolyLine {
 
         PolyLine::new(output).unwrap()
     }
+
+    /// Removes points that barely change the shape, using Ramer-Douglas-Peucker. Every removed
+    /// point is within `epsilon` of the result. The first and last points are always kept.
+    ///
+    /// ```
+    /// use geom::{PolyLine, Pt2D, Distance};
+    ///
+    /// let polyline = PolyLine::must_new(vec![
+    ///     Pt2D::new(0.0, 0.0),
+    ///     Pt2D::new(5.0, 0.1),
+    ///     Pt2D::new(10.0, 0.0),
+    ///     Pt2D::new(10.0, 10.0),
+    /// ]);
+    ///
+    /// assert_eq!(
+    ///     polyline.simplify(Distance::meters(1.0)).unwrap().points(),
+    ///     &vec![
+    ///         Pt2D::new(0.0, 0.0),
+    ///         Pt2D::new(10.0, 0.0),
+    ///         Pt2D::new(10.0, 10.0),
+    ///     ]
+    /// );
+    /// ```
+    pub fn simplify(&self, epsilon: Distance) -> Result<PolyLine> {
+        PolyLine::new(
+            self.to_geo()
+                .simplify(&epsilon.inner_meters())
+                .0
+                .into_iter()
+                .map(Pt2D::from)
+                .collect(),
+        )
+    }
+
+    /// Rounds off corners using Chaikin's algorithm, repeated `iterations` times. Each iteration
+    /// roughly doubles the number of points. The first and last points are always kept.
+    pub fn smooth(&self, iterations: usize) -> Result<PolyLine> {
+        let mut pts = self.pts.clone();
+        for _ in 0..iterations {
+            pts = chaikin(&pts);
+        }
+        PolyLine::deduping_new(pts)
+    }
 }
 
 impl fmt::Display for PolyLine {
@@ -1053,3 +1161,59 @@ fn to_set(pts: &[Pt2D]) -> (HashSet<HashablePt2D>, HashSet<HashablePt2D>) {
     }
     (deduped, dupes)
 }
+
+/// One round of Chaikin corner-cutting. Every segment is replaced by points 1/4 and 3/4 along it,
+/// except the first and last point stay put.
+pub(crate) fn chaikin(pts: &[Pt2D]) -> Vec<Pt2D> {
+    if pts.len() <= 2 {
+        return pts.to_vec();
+    }
+    let mut result = vec![pts[0]];
+    for (idx, pair) in pts.windows(2).enumerate() {
+        let (a, b) = (pair[0], pair[1]);
+        if idx != 0 {
+            result.push(lerp(a, b, 0.25));
+        }
+        if idx != pts.len() - 2 {
+            result.push(lerp(a, b, 0.75));
+        }
+    }
+    result.push(*pts.last().unwrap());
+    result
+}
+
+fn lerp(a: Pt2D, b: Pt2D, pct: f64) -> Pt2D {
+    Pt2D::new(a.x() + pct * (b.x() - a.x()), a.y() + pct * (b.y() - a.y()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn project_pt_detailed() {
+        let pl = PolyLine::must_new(vec![
+            Pt2D::new(0.0, 0.0),
+            Pt2D::new(10.0, 0.0),
+            Pt2D::new(10.0, 10.0),
+        ]);
+
+        let projected = pl.project_pt(Pt2D::new(12.0, 4.0));
+        assert_eq!(projected, Pt2D::new(10.0, 4.0));
+        let detailed = pl.project_pt_detailed(Pt2D::new(12.0, 4.0));
+        assert_eq!(detailed.pt, projected);
+        assert_eq!(detailed.dist_along, Distance::meters(14.0));
+        assert_eq!(detailed.segment, 1);
+        assert_eq!(detailed.distance, Distance::meters(2.0));
+
+        // Past the end of the first segment, the closest point is the start
+        let detailed = pl.project_pt_detailed(Pt2D::new(-3.0, -4.0));
+        assert_eq!(detailed.pt, Pt2D::new(0.0, 0.0));
+        assert_eq!(detailed.dist_along, Distance::ZERO);
+        assert_eq!(detailed.segment, 0);
+        assert_eq!(
+            pl.distance_to_point(Pt2D::new(-3.0, -4.0)),
+            Distance::meters(5.0)
+        );
+    }
+}
@@ -0,0 +1,72 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use aabb_quadtree::ItemId;
+
+use crate::{Bounds, Pt2D};
+
+/// A spatial index to quickly find objects whose bounding boxes overlap some area, instead of
+/// scanning through everything. Only bounding boxes are stored; callers check the real geometry
+/// of the candidates returned.
+pub struct QuadTree<K> {
+    quadtree: aabb_quadtree::QuadTree<K>,
+    ids: HashMap<K, ItemId>,
+}
+
+impl<K> QuadTree<K>
+where
+    K: Clone + Eq + Hash,
+{
+    /// Creates an empty index, limited to objects contained in the boundary.
+    pub fn new(bounds: &Bounds) -> QuadTree<K> {
+        QuadTree {
+            quadtree: aabb_quadtree::QuadTree::default(bounds.as_bbox()),
+            ids: HashMap::new(),
+        }
+    }
+
+    /// Creates an empty index that accepts any non-negative coordinates. Useful when the bounds
+    /// change over time, like while editing a map.
+    pub fn unbounded() -> QuadTree<K> {
+        QuadTree::new(&Bounds::from(&[
+            Pt2D::new(0.0, 0.0),
+            Pt2D::new(std::f64::MAX, std::f64::MAX),
+        ]))
+    }
+
+    /// Adds an object to the index. If the key is already present, its bounds are replaced.
+    pub fn insert(&mut self, key: K, bounds: Bounds) {
+        self.remove(&key);
+        let id = self.quadtree.insert_with_box(key.clone(), bounds.as_bbox());
+        self.ids.insert(key, id);
+    }
+
+    /// Removes an object from the index, returning false if it wasn't there.
+    pub fn remove(&mut self, key: &K) -> bool {
+        match self.ids.remove(key) {
+            Some(id) => self.quadtree.remove(id).is_some(),
+            None => false,
+        }
+    }
+
+    /// Returns every object whose bounding box overlaps the query, in no particular order.
+    pub fn query(&self, bounds: Bounds) -> Vec<&K> {
+        self.quadtree
+            .query(bounds.as_bbox())
+            .into_iter()
+            .map(|(key, _, _)| key)
+            .collect()
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        self.ids.contains_key(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+}
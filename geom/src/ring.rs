@@ -2,9 +2,11 @@ use std::collections::HashSet;
 use std::fmt;
 
 use anyhow::Result;
+use geo::algorithm::simplify::Simplify;
 use serde::{Deserialize, Serialize};
 
-use crate::{Distance, GPSBounds, Line, PolyLine, Polygon, Pt2D};
+use crate::polyline::{chaikin, MITER_THRESHOLD};
+use crate::{Distance, GPSBounds, Line, LonLat, PolyLine, Polygon, Pt2D, EPSILON_DIST};
 
 /// Maybe a misnomer, but like a PolyLine, but closed.
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -82,69 +84,86 @@ impl Ring {
         hits
     }
 
-    pub(crate) fn get_both_slices_btwn(
+    /// Splits the ring at two points, returning both ways of tracing along the ring from `pt1` to
+    /// `pt2`. The points don't have to be exactly on the ring; each one is snapped to the closest
+    /// point on the ring, as long as that's within `snap_tolerance`.
+    pub fn get_both_slices_between(
         &self,
         pt1: Pt2D,
         pt2: Pt2D,
-    ) -> Option<(PolyLine, PolyLine)> {
-        assert!(pt1 != pt2);
+        snap_tolerance: Distance,
+    ) -> Result<(PolyLine, PolyLine)> {
         let pl = PolyLine::unchecked_new(self.pts.clone());
+        let dist_along = |pt: Pt2D| -> Result<Distance> {
+            let projected = pl.project_pt_detailed(pt);
+            if projected.distance > snap_tolerance {
+                bail!(
+                    "{} is {} away from the ring, more than the tolerance of {}",
+                    pt,
+                    projected.distance,
+                    snap_tolerance
+                );
+            }
+            Ok(projected.dist_along)
+        };
 
-        let mut dist1 = pl.dist_along_of_point(pt1)?.0;
-        let mut dist2 = pl.dist_along_of_point(pt2)?.0;
-        if dist1 > dist2 {
-            std::mem::swap(&mut dist1, &mut dist2);
-        }
+        let dist1 = dist_along(pt1)?;
+        let dist2 = dist_along(pt2)?;
         if dist1 == dist2 {
-            return None;
+            bail!("{} and {} are at the same place along the ring", pt1, pt2);
         }
+        let (low, high) = if dist1 < dist2 {
+            (dist1, dist2)
+        } else {
+            (dist2, dist1)
+        };
 
-        // TODO If we reversed the points, we need to reverse these results! Argh
-        let candidate1 = pl.maybe_exact_slice(dist1, dist2).ok()?;
-        let candidate2 = pl
-            .maybe_exact_slice(dist2, pl.length())
-            .ok()?
-            .must_extend(pl.maybe_exact_slice(Distance::ZERO, dist1).ok()?);
-        Some((candidate1, candidate2))
+        let inside = pl.maybe_exact_slice(low, high)?;
+        let wraparound = match (low == Distance::ZERO, high == pl.length()) {
+            (true, true) => bail!("{} and {} are both at the start of the ring", pt1, pt2),
+            (true, false) => pl.maybe_exact_slice(high, pl.length())?,
+            (false, true) => pl.maybe_exact_slice(Distance::ZERO, low)?,
+            (false, false) => pl
+                .maybe_exact_slice(high, pl.length())?
+                .extend(pl.maybe_exact_slice(Distance::ZERO, low)?)?,
+        };
+        // Orient both from pt1 to pt2
+        if dist1 < dist2 {
+            Ok((inside, wraparound.reversed()))
+        } else {
+            Ok((inside.reversed(), wraparound))
+        }
     }
 
     /// Assuming both points are somewhere along the ring, return the points in between the two, by
-    /// tracing along the ring in the longer or shorter direction (depending on `longer`). If both
-    /// points are the same, returns `None`.  The result is oriented from `pt1` to `pt2`.
-    pub fn get_slice_between(&self, pt1: Pt2D, pt2: Pt2D, longer: bool) -> Option<PolyLine> {
-        if pt1 == pt2 {
-            return None;
-        }
-        let (candidate1, candidate2) = self.get_both_slices_btwn(pt1, pt2)?;
-        let slice = if longer == (candidate1.length() > candidate2.length()) {
-            candidate1
-        } else {
-            candidate2
-        };
-        if slice.first_pt() == pt1 {
-            Some(slice)
+    /// tracing along the ring in the longer or shorter direction (depending on `longer`). Points
+    /// within `snap_tolerance` of the ring are snapped onto it first. Fails if both points are the
+    /// same. The result is oriented from `pt1` to `pt2`.
+    pub fn get_slice_between(
+        &self,
+        pt1: Pt2D,
+        pt2: Pt2D,
+        longer: bool,
+        snap_tolerance: Distance,
+    ) -> Result<PolyLine> {
+        let (candidate1, candidate2) = self.get_both_slices_between(pt1, pt2, snap_tolerance)?;
+        if longer == (candidate1.length() > candidate2.length()) {
+            Ok(candidate1)
         } else {
-            // TODO Do we want to be paranoid here? Or just do the fix in get_both_slices_btwn
-            // directly?
-            Some(slice.reversed())
+            Ok(candidate2)
         }
     }
 
-    /// Assuming both points are somewhere along the ring, return the points in between the two, by
+    /// Assuming both points are exactly along the ring, return the points in between the two, by
     /// tracing along the ring in the shorter direction. If both points are the same, returns
     /// `None`.  The result is oriented from `pt1` to `pt2`.
     pub fn get_shorter_slice_between(&self, pt1: Pt2D, pt2: Pt2D) -> Option<PolyLine> {
-        self.get_slice_between(pt1, pt2, false)
+        self.get_slice_between(pt1, pt2, false, Distance::ZERO).ok()
     }
 
     // TODO Rmove this one, fix all callers
     pub fn get_shorter_slice_btwn(&self, pt1: Pt2D, pt2: Pt2D) -> Option<PolyLine> {
-        let (candidate1, candidate2) = self.get_both_slices_btwn(pt1, pt2)?;
-        if candidate1.length() < candidate2.length() {
-            Some(candidate1)
-        } else {
-            Some(candidate2)
-        }
+        self.get_shorter_slice_between(pt1, pt2)
     }
 
     /// Extract all PolyLines and Rings. Doesn't handle crazy double loops and stuff.
@@ -179,10 +198,16 @@ impl Ring {
         Ok((polylines, rings))
     }
 
+    /// True if the point is on the ring itself (not just inside it), within `EPSILON_DIST`.
     pub fn contains_pt(&self, pt: Pt2D) -> bool {
-        PolyLine::unchecked_new(self.pts.clone())
-            .dist_along_of_point(pt)
-            .is_some()
+        PolyLine::unchecked_new(self.pts.clone()).distance_to_point(pt) < EPSILON_DIST
+    }
+
+    /// The real-world area inside this ring in square meters, measured on the surface of the
+    /// earth instead of in map-space. The `GPSBounds` must be the same one used to produce the
+    /// points.
+    pub fn geodesic_area(&self, gps: &GPSBounds) -> f64 {
+        LonLat::geodesic_area(&gps.convert_back(&self.pts))
     }
 
     /// Produces a GeoJSON polygon, optionally mapping the world-space points back to GPS.
@@ -200,6 +225,100 @@ impl Ring {
         geojson::Geometry::new(geojson::Value::Polygon(vec![pts]))
     }
 
+    /// Returns the areas inside both rings.
+    pub fn intersection(&self, other: &Ring) -> Vec<Polygon> {
+        self.clone()
+            .into_polygon()
+            .intersection(&other.clone().into_polygon())
+    }
+
+    /// Returns the areas inside this ring, but not the other.
+    pub fn difference(&self, other: &Ring) -> Vec<Polygon> {
+        self.clone()
+            .into_polygon()
+            .difference(&other.clone().into_polygon())
+    }
+
+    /// Returns the areas inside either ring, merging them if they overlap.
+    pub fn union(&self, other: &Ring) -> Vec<Polygon> {
+        Polygon::union_all(vec![
+            self.clone().into_polygon(),
+            other.clone().into_polygon(),
+        ])
+    }
+
+    /// Removes points that barely change the shape, using Ramer-Douglas-Peucker. Every removed
+    /// point is within `epsilon` of the result. The first point is always kept. Fails if the
+    /// result collapses to a line.
+    pub fn simplify(&self, epsilon: Distance) -> Result<Ring> {
+        let pts: Vec<Pt2D> = geo::LineString::from(self.clone())
+            .simplify(&epsilon.inner_meters())
+            .0
+            .into_iter()
+            .map(Pt2D::from)
+            .collect();
+        if pts.len() < 4 {
+            bail!("Simplifying a ring with epsilon {} collapses it", epsilon);
+        }
+        Ring::new(pts)
+    }
+
+    /// Rounds off corners using Chaikin's algorithm, repeated `iterations` times. The first point
+    /// is always kept, so the corner there stays sharp.
+    pub fn smooth(&self, iterations: usize) -> Result<Ring> {
+        let mut pts = self.pts.clone();
+        for _ in 0..iterations {
+            pts = chaikin(&pts);
+        }
+        pts.dedup();
+        Ring::new(pts)
+    }
+
+    /// Grows the ring outwards by `distance`, or shrinks it if negative. Where shifted edges cross
+    /// over each other, like concave corners or narrow parts when shrinking, the loops are cut
+    /// off. Fails instead of returning a twisted ring if the result collapses.
+    pub fn offset(&self, distance: Distance) -> Result<Ring> {
+        if distance == Distance::ZERO {
+            return Ok(self.clone());
+        }
+        let orientation = signed_area(&self.pts);
+        if orientation == 0.0 {
+            bail!("Can't offset a ring with no area");
+        }
+
+        // Wrap around, so the corner at the first point gets shifted like all the others
+        let n = self.pts.len() - 1;
+        let mut extended = vec![self.pts[n - 1]];
+        extended.extend(self.pts.iter().cloned());
+        extended.push(self.pts[1]);
+        // Counter-clockwise rings have their interior to the right
+        let width = if orientation > 0.0 {
+            -distance
+        } else {
+            distance
+        };
+        let shifted =
+            PolyLine::unchecked_new(extended).shift_with_sharp_angles(width, MITER_THRESHOLD);
+        let mut pts = shifted[1..=n].to_vec();
+        pts.push(pts[0]);
+        pts.dedup();
+
+        let pts = remove_loops(pts, orientation)?;
+        if pts.len() < 4 || signed_area(&pts) * orientation <= 0.0 {
+            bail!("Offsetting a ring by {} collapses it", distance);
+        }
+        // Shrinking past the middle can flip the ring around without changing its orientation, so
+        // also check that every point really is `distance` away from the original.
+        let original = PolyLine::unchecked_new(self.pts.clone());
+        if pts
+            .iter()
+            .any(|pt| original.project_pt_detailed(*pt).distance < distance.abs() - EPSILON_DIST)
+        {
+            bail!("Offsetting a ring by {} collapses it", distance);
+        }
+        Ring::new(pts)
+    }
+
     /// Translates the ring by a fixed offset.
     pub fn translate(mut self, dx: f64, dy: f64) -> Ring {
         for pt in &mut self.pts {
@@ -238,3 +357,186 @@ impl From<geo::LineString<f64>> for Ring {
         Self::must_new(pts)
     }
 }
+
+// Positive for counter-clockwise rings
+fn signed_area(pts: &[Pt2D]) -> f64 {
+    pts.windows(2)
+        .map(|pair| pair[0].x() * pair[1].y() - pair[1].x() * pair[0].y())
+        .sum::<f64>()
+        / 2.0
+}
+
+// Repeatedly splits a closed list of points where two edges cross, keeping the biggest piece that
+// still has the original orientation.
+fn remove_loops(mut pts: Vec<Pt2D>, orientation: f64) -> Result<Vec<Pt2D>> {
+    'search: loop {
+        let num_edges = pts.len() - 1;
+        for idx1 in 0..num_edges {
+            for idx2 in (idx1 + 2)..num_edges {
+                // The first and last edge are adjacent
+                if idx1 == 0 && idx2 == num_edges - 1 {
+                    continue;
+                }
+                let hit = match (
+                    Line::new(pts[idx1], pts[idx1 + 1]),
+                    Line::new(pts[idx2], pts[idx2 + 1]),
+                ) {
+                    (Some(l1), Some(l2)) => l1.intersection(&l2),
+                    _ => None,
+                };
+                if let Some(hit) = hit {
+                    let mut inner = vec![hit];
+                    inner.extend(&pts[idx1 + 1..=idx2]);
+                    inner.push(hit);
+                    let mut outer = pts[..=idx1].to_vec();
+                    outer.push(hit);
+                    outer.extend(&pts[idx2 + 1..]);
+
+                    let mut best = None;
+                    for mut piece in [inner, outer] {
+                        piece.dedup();
+                        let area = signed_area(&piece);
+                        if area * orientation > 0.0
+                            && best
+                                .as_ref()
+                                .map(|(best_area, _)| area.abs() > *best_area)
+                                .unwrap_or(true)
+                        {
+                            best = Some((area.abs(), piece));
+                        }
+                    }
+                    match best {
+                        Some((_, piece)) => {
+                            pts = piece;
+                            continue 'search;
+                        }
+                        None => bail!("Offset ring turned inside out"),
+                    }
+                }
+            }
+        }
+        return Ok(pts);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ring(pts: Vec<(f64, f64)>) -> Ring {
+        Ring::must_new(pts.into_iter().map(|(x, y)| Pt2D::new(x, y)).collect())
+    }
+
+    fn area(ring: &Ring) -> f64 {
+        signed_area(ring.points()).abs()
+    }
+
+    #[test]
+    fn test_get_both_slices_between() {
+        let square = ring(vec![
+            (0.0, 0.0),
+            (10.0, 0.0),
+            (10.0, 10.0),
+            (0.0, 10.0),
+            (0.0, 0.0),
+        ]);
+        let close = |pt: Pt2D, x: f64, y: f64| pt.dist_to(Pt2D::new(x, y)) < Distance::meters(0.01);
+        let tolerance = Distance::meters(0.5);
+
+        // Both points are slightly off the ring, and get snapped to (5, 0) and (10, 5)
+        let (pt1, pt2) = (Pt2D::new(5.0, 0.3), Pt2D::new(10.2, 5.0));
+        for (from, to) in [(pt1, pt2), (pt2, pt1)] {
+            let (slice1, slice2) = square.get_both_slices_between(from, to, tolerance).unwrap();
+            let mut lengths = vec![slice1.length(), slice2.length()];
+            lengths.sort();
+            assert!((lengths[0] - Distance::meters(10.0)).abs() < Distance::meters(0.01));
+            assert!((lengths[1] - Distance::meters(30.0)).abs() < Distance::meters(0.01));
+            for slice in [slice1, slice2] {
+                let (start, end) = if from == pt1 {
+                    ((5.0, 0.0), (10.0, 5.0))
+                } else {
+                    ((10.0, 5.0), (5.0, 0.0))
+                };
+                assert!(close(slice.first_pt(), start.0, start.1), "{}", slice);
+                assert!(close(slice.last_pt(), end.0, end.1), "{}", slice);
+            }
+        }
+
+        // One point is exactly on the first corner
+        let (slice1, slice2) = square
+            .get_both_slices_between(Pt2D::new(0.0, 0.0), Pt2D::new(10.0, 5.0), tolerance)
+            .unwrap();
+        assert!(
+            (slice1.length() + slice2.length() - Distance::meters(40.0)).abs()
+                < Distance::meters(0.01)
+        );
+
+        // Too far from the ring
+        assert!(square
+            .get_both_slices_between(Pt2D::new(5.0, 2.0), pt2, tolerance)
+            .is_err());
+        assert!(square
+            .get_both_slices_between(pt1, pt2, Distance::ZERO)
+            .is_err());
+        // Both snap to the same place
+        assert!(square
+            .get_both_slices_between(pt1, Pt2D::new(5.0, -0.3), tolerance)
+            .is_err());
+    }
+
+    #[test]
+    fn test_offset_square() {
+        let square = ring(vec![
+            (0.0, 0.0),
+            (10.0, 0.0),
+            (10.0, 10.0),
+            (0.0, 10.0),
+            (0.0, 0.0),
+        ]);
+        let mut reversed = square.points().clone();
+        reversed.reverse();
+        // The orientation of the ring shouldn't matter
+        for input in [square, Ring::must_new(reversed)] {
+            let grown = input.offset(Distance::meters(1.0)).unwrap();
+            assert!((area(&grown) - 144.0).abs() < 0.01, "grew to {}", grown);
+            let shrunk = input.offset(Distance::meters(-2.0)).unwrap();
+            assert!((area(&shrunk) - 36.0).abs() < 0.01, "shrunk to {}", shrunk);
+            assert!(input.offset(Distance::meters(-6.0)).is_err());
+        }
+    }
+
+    #[test]
+    fn test_offset_concave() {
+        let l_shape = ring(vec![
+            (0.0, 0.0),
+            (10.0, 0.0),
+            (10.0, 4.0),
+            (4.0, 4.0),
+            (4.0, 10.0),
+            (0.0, 10.0),
+            (0.0, 0.0),
+        ]);
+        let shrunk = l_shape.offset(Distance::meters(-1.0)).unwrap();
+        assert!((area(&shrunk) - 28.0).abs() < 0.01, "shrunk to {}", shrunk);
+    }
+
+    #[test]
+    fn test_remove_loops() {
+        // A bowtie with a bigger lobe on the left. The edges from (0, 0) to (10, 8) and from
+        // (10, 0) to (0, 10) cross.
+        let bowtie = ring(vec![
+            (0.0, 0.0),
+            (10.0, 8.0),
+            (10.0, 0.0),
+            (0.0, 10.0),
+            (0.0, 0.0),
+        ]);
+        let orientation = signed_area(bowtie.points());
+        assert!(orientation > 0.0);
+        let pts = remove_loops(bowtie.into_points(), orientation).unwrap();
+        // Only the left lobe remains
+        assert_eq!(pts.len(), 4);
+        let expected = 0.5 * 10.0 * (10.0 / 1.8);
+        assert!((signed_area(&pts) - expected).abs() < 0.01);
+    }
+}
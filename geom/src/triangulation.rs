@@ -0,0 +1,112 @@
+use crate::Pt2D;
+
+/// Breaks a polygon into triangles using ear-clipping. `outer` and each of the `holes` are rings
+/// of points, which may or may not repeat their first point at the end.
+///
+/// Returns triples of indices into the points of `outer` followed by the points of every hole, in
+/// order. This lets callers upload the points once (to a GPU buffer, for instance) and refer to
+/// them by index.
+///
+/// Messy input is tolerated: repeated consecutive points are skipped, and so are holes with fewer
+/// than 3 distinct points. If the outer ring itself is degenerate, there are no triangles.
+pub fn triangulate(outer: &[Pt2D], holes: &[&[Pt2D]]) -> Vec<[usize; 3]> {
+    let mut vertices: Vec<f64> = Vec::new();
+    // For every vertex passed to earcut, its index in the concatenated input points
+    let mut orig_idx: Vec<usize> = Vec::new();
+    let mut hole_starts: Vec<usize> = Vec::new();
+    let mut offset = 0;
+    for (ring_idx, ring) in std::iter::once(outer)
+        .chain(holes.iter().cloned())
+        .enumerate()
+    {
+        let keep = distinct_points(ring);
+        if keep.len() >= 3 {
+            if ring_idx > 0 {
+                hole_starts.push(orig_idx.len());
+            }
+            for idx in keep {
+                vertices.push(ring[idx].x());
+                vertices.push(ring[idx].y());
+                orig_idx.push(offset + idx);
+            }
+        } else if ring_idx == 0 {
+            return Vec::new();
+        }
+        offset += ring.len();
+    }
+
+    earcutr::earcut(&vertices, &hole_starts, 2)
+        .chunks_exact(3)
+        .map(|tri| [orig_idx[tri[0]], orig_idx[tri[1]], orig_idx[tri[2]]])
+        .collect()
+}
+
+/// The indices of points in a ring, skipping any equal to the previous point and the last point if
+/// it just closes the ring.
+fn distinct_points(ring: &[Pt2D]) -> Vec<usize> {
+    let mut keep: Vec<usize> = Vec::new();
+    for (idx, pt) in ring.iter().enumerate() {
+        if keep.last().map(|last| ring[*last] != *pt).unwrap_or(true) {
+            keep.push(idx);
+        }
+    }
+    if keep.len() > 1 && ring[keep[0]] == ring[*keep.last().unwrap()] {
+        keep.pop();
+    }
+    keep
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(x1: f64, y1: f64, x2: f64, y2: f64) -> Vec<Pt2D> {
+        vec![
+            Pt2D::new(x1, y1),
+            Pt2D::new(x2, y1),
+            Pt2D::new(x2, y2),
+            Pt2D::new(x1, y2),
+            Pt2D::new(x1, y1),
+        ]
+    }
+
+    fn total_area(pts: &[Pt2D], triangles: &[[usize; 3]]) -> f64 {
+        triangles
+            .iter()
+            .map(|[a, b, c]| {
+                let (a, b, c) = (pts[*a], pts[*b], pts[*c]);
+                ((b.x() - a.x()) * (c.y() - a.y()) - (c.x() - a.x()) * (b.y() - a.y())).abs() / 2.0
+            })
+            .sum()
+    }
+
+    #[test]
+    fn square_with_hole() {
+        let outer = square(0.0, 0.0, 10.0, 10.0);
+        let hole = square(2.0, 2.0, 4.0, 4.0);
+        let triangles = triangulate(&outer, &[&hole]);
+
+        let mut all_pts = outer.clone();
+        all_pts.extend(hole.clone());
+        assert!((total_area(&all_pts, &triangles) - 96.0).abs() < 1e-6);
+        // The closing points are never used
+        for tri in &triangles {
+            assert!(!tri.contains(&4));
+            assert!(!tri.contains(&9));
+        }
+    }
+
+    #[test]
+    fn messy_input() {
+        let mut outer = square(0.0, 0.0, 10.0, 10.0);
+        outer.insert(1, Pt2D::new(0.0, 0.0));
+        // A hole that's just a point is ignored
+        let hole = vec![Pt2D::new(5.0, 5.0), Pt2D::new(5.0, 5.0)];
+        let triangles = triangulate(&outer, &[&hole]);
+        assert_eq!(triangles.len(), 2);
+        assert!((total_area(&outer, &triangles) - 100.0).abs() < 1e-6);
+
+        let line = vec![Pt2D::new(0.0, 0.0), Pt2D::new(1.0, 1.0)];
+        assert!(triangulate(&line, &[]).is_empty());
+    }
+}
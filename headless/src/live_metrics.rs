@@ -0,0 +1,187 @@
+//! Streams `LiveMetrics` over WebSockets while the simulation runs, so external dashboards and
+//! notebooks can monitor a long run. Connect to
+//! ws://localhost:1234/sim/live-metrics?intersections=12,34 and every simulated minute, whenever
+//! something like /sim/goto-time advances the simulation, a JSON message arrives.
+//!
+//! The WebSocket protocol itself, including answering pings and close frames, is handled by
+//! tokio-tungstenite.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use anyhow::Result;
+use futures::{SinkExt, StreamExt};
+use hyper::header::{CONNECTION, SEC_WEBSOCKET_ACCEPT, SEC_WEBSOCKET_KEY, UPGRADE};
+use hyper::{Body, Request, Response, StatusCode};
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::handshake::derive_accept_key;
+use tokio_tungstenite::tungstenite::protocol::{Message, Role};
+use tokio_tungstenite::WebSocketStream;
+
+use abstutil::Timer;
+use geom::{Duration, Time};
+use map_model::{IntersectionID, Map};
+use sim::{LiveMetrics, Sim};
+
+const REPORT_EVERY: Duration = Duration::const_seconds(60.0);
+
+lazy_static::lazy_static! {
+    static ref SUBSCRIBERS: Mutex<Vec<Subscriber>> = Mutex::new(Vec::new());
+}
+
+struct Subscriber {
+    intersections: Vec<IntersectionID>,
+    /// JSON-encoded metrics
+    tx: mpsc::UnboundedSender<String>,
+}
+
+/// Advance the simulation. If anybody is listening, publish metrics every simulated minute.
+pub fn step(sim: &mut Sim, map: &Map, dt: Duration, timer: &mut Timer) {
+    if SUBSCRIBERS.lock().unwrap().is_empty() {
+        sim.timed_step(map, dt, &mut None, timer);
+        return;
+    }
+
+    let end = sim.time() + dt;
+    while sim.time() < end {
+        let since = sim.time();
+        let remaining = end - since;
+        let step = if remaining < REPORT_EVERY {
+            remaining
+        } else {
+            REPORT_EVERY
+        };
+        sim.timed_step(map, step, &mut None, timer);
+        publish(sim, map, since);
+    }
+}
+
+fn publish(sim: &Sim, map: &Map, since: Time) {
+    // Skip IDs that don't exist in the current map
+    let num_intersections = map.all_intersections().len();
+    // If the receiving end is gone, the client disconnected
+    SUBSCRIBERS.lock().unwrap().retain(|sub| {
+        let intersections: Vec<IntersectionID> = sub
+            .intersections
+            .iter()
+            .filter(|i| i.0 < num_intersections)
+            .cloned()
+            .collect();
+        let metrics = LiveMetrics::new(sim, since, &intersections);
+        sub.tx.send(abstutil::to_json_terse(&metrics)).is_ok()
+    });
+}
+
+/// Handles a request to /sim/live-metrics by upgrading the connection to a WebSocket.
+pub fn subscribe(req: Request<Body>, params: &HashMap<String, String>) -> Result<Response<Body>> {
+    let key = match req.headers().get(SEC_WEBSOCKET_KEY) {
+        Some(key) => key.to_str()?.to_string(),
+        None => bail!("this endpoint needs a WebSocket connection"),
+    };
+    let mut intersections = Vec::new();
+    if let Some(list) = params.get("intersections") {
+        for x in list.split(',') {
+            intersections.push(IntersectionID(x.trim().parse::<usize>()?));
+        }
+    }
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    SUBSCRIBERS
+        .lock()
+        .unwrap()
+        .push(Subscriber { intersections, tx });
+
+    tokio::spawn(async move {
+        let upgraded = match hyper::upgrade::on(req).await {
+            Ok(upgraded) => upgraded,
+            Err(err) => {
+                error!("Couldn't upgrade to a WebSocket: {}", err);
+                return;
+            }
+        };
+        let (mut sink, mut stream) = WebSocketStream::from_raw_socket(upgraded, Role::Server, None)
+            .await
+            .split();
+
+        loop {
+            tokio::select! {
+                json = rx.recv() => {
+                    let json = match json {
+                        Some(json) => json,
+                        None => break,
+                    };
+                    if let Err(err) = sink.send(Message::Text(json)).await {
+                        info!("Live metrics client disconnected: {}", err);
+                        break;
+                    }
+                }
+                // Keep reading, so pings and close frames get answered. Nothing else the client
+                // sends matters.
+                incoming = stream.next() => match incoming {
+                    Some(Ok(Message::Close(_))) | None => {
+                        info!("Live metrics client closed the connection");
+                        break;
+                    }
+                    Some(Ok(_)) => {}
+                    Some(Err(err)) => {
+                        info!("Stopped reading from a live metrics client: {}", err);
+                        break;
+                    }
+                },
+            }
+        }
+        // Dropping rx makes the next publish forget about this subscriber
+    });
+
+    Ok(Response::builder()
+        .status(StatusCode::SWITCHING_PROTOCOLS)
+        .header(UPGRADE, "websocket")
+        .header(CONNECTION, "Upgrade")
+        .header(SEC_WEBSOCKET_ACCEPT, derive_accept_key(key.as_bytes()))
+        .body(Body::empty())?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::Server;
+    use sim::SimOptions;
+
+    #[tokio::test]
+    async fn test_subscribe() {
+        let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(
+            hyper::service::make_service_fn(|_| async {
+                Ok::<_, hyper::Error>(hyper::service::service_fn(|req| async move {
+                    subscribe(req, &HashMap::new())
+                }))
+            }),
+        );
+        let addr = server.local_addr();
+        tokio::spawn(server);
+
+        let (mut client, resp) =
+            tokio_tungstenite::connect_async(format!("ws://{}/sim/live-metrics", addr))
+                .await
+                .unwrap();
+        assert_eq!(resp.status(), StatusCode::SWITCHING_PROTOCOLS);
+        // The server registers the subscriber before finishing the handshake
+        assert_eq!(SUBSCRIBERS.lock().unwrap().len(), 1);
+
+        let map = Map::blank();
+        let sim = Sim::new(&map, SimOptions::new("test_subscribe"));
+        publish(&sim, &map, Time::START_OF_DAY);
+
+        let msg = client.next().await.unwrap().unwrap();
+        let metrics: serde_json::Value = serde_json::from_str(msg.to_text().unwrap()).unwrap();
+        assert_eq!(metrics["trips_finished"], 0);
+        assert!(metrics["active_agents"].is_object());
+        assert_eq!(metrics["queue_lengths"], serde_json::json!([]));
+
+        // After the client leaves, the next report forgets about it
+        client.close(None).await.unwrap();
+        while let Some(Ok(_)) = client.next().await {}
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        publish(&sim, &map, Time::START_OF_DAY);
+        assert!(SUBSCRIBERS.lock().unwrap().is_empty());
+    }
+}
@@ -8,12 +8,17 @@
 //! it's now 01:01:00.0
 //! > curl http://localhost:1234/data/get-road-thruput
 //! ... huge JSON blob
+//!
+//! Connect a WebSocket to ws://localhost:1234/sim/live-metrics to receive a summary of the
+//! simulation every simulated minute while it runs.
 
 #[macro_use]
 extern crate anyhow;
 #[macro_use]
 extern crate log;
 
+mod live_metrics;
+
 use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::sync::RwLock;
 
@@ -102,6 +107,18 @@ async fn serve_req(req: Request<Body>) -> Result<Response<Body>, hyper::Error> {
             .query_pairs()
             .map(|(k, v)| (k.to_string(), v.to_string()))
             .collect();
+    if path == "/sim/live-metrics" {
+        return Ok(match live_metrics::subscribe(req, &params) {
+            Ok(resp) => resp,
+            Err(err) => {
+                error!("{}: {}", path, err);
+                Response::builder()
+                    .status(StatusCode::BAD_REQUEST)
+                    .body(Body::from(format!("Bad command {}: {}", path, err)))
+                    .unwrap()
+            }
+        });
+    }
     let body = hyper::body::to_bytes(req).await?.to_vec();
     info!("Handling {}", path);
     Ok(
@@ -168,7 +185,7 @@ fn handle_command(
                 bail!("{} is in the past. call /sim/reset first?", t)
             } else {
                 let dt = t - sim.time();
-                sim.timed_step(map, dt, &mut None, &mut Timer::new("goto-time"));
+                live_metrics::step(sim, map, dt, &mut Timer::new("goto-time"));
                 Ok(format!("it's now {}", t))
             }
         }
@@ -205,6 +222,10 @@ fn handle_command(
         "/traffic-signals/set" => {
             let ts: ControlTrafficSignal = abstutil::from_json(body)?;
             let id = ts.id;
+            if id.0 >= map.all_intersections().len() {
+                bail!("{} doesn't exist", id);
+            }
+            ts.validate(map.get_i(id))?;
 
             // incremental_edit_traffic_signal is the cheap option, but since we may need to call
             // get-edits later, go through the proper flow.
@@ -216,6 +237,9 @@ fn handle_command(
             });
             map.must_apply_edits(edits, &mut Timer::throwaway());
             map.recalculate_pathfinding_after_edits(&mut Timer::throwaway());
+            // Let the running simulation pick up the new stages and timing. A stage already in
+            // progress keeps its old duration; the changes apply starting with the next stage.
+            sim.handle_live_edited_traffic_signals(map);
 
             Ok(format!("{} has been updated", id))
         }
@@ -336,6 +360,17 @@ fn handle_command(
                 .map(|((r, a, hr), cnt)| (*r, *a, *hr, *cnt))
                 .collect(),
         })),
+        "/data/save-congestion-profile" => {
+            // Pass this file to --route_with_congestion for the next run
+            let path = get("path")?;
+            let profile = sim
+                .get_analytics()
+                .detailed
+                .road_travel_times
+                .congestion_profile();
+            abstio::write_binary(path.to_string(), &profile);
+            Ok(format!("congestion profile saved to {}", path))
+        }
         "/data/get-blocked-by-graph" => Ok(abstutil::to_json(&BlockedByGraph {
             blocked_by: sim
                 .get_blocked_by_graph(map)
@@ -93,6 +93,7 @@ pub fn oneshot(
     clip: Option<String>,
     drive_on_right: bool,
     filter_crosswalks: bool,
+    include_driveways: bool,
     opts: RawToMapOptions,
 ) {
     let mut timer = abstutil::Timer::new("oneshot");
@@ -122,6 +123,7 @@ pub fn oneshot(
             extra_buildings: None,
             skip_local_roads: false,
             filter_crosswalks,
+            include_driveways,
             gtfs_url: None,
         },
         &mut timer,
@@ -5,7 +5,9 @@ use geom::{LonLat, PolyLine};
 use map_model::{
     osm, BuildingID, IntersectionID, Map, Path, PathConstraints, PathRequest, PathStep,
 };
-use sim::{IndividTrip, MapBorders, OrigPersonID, PersonSpec, Scenario, TripEndpoint, TripMode};
+use sim::{
+    IndividTrip, MapBorders, OrigPersonID, PersonSpec, Scenario, TripEndpoint, TripMode, Weather,
+};
 
 use crate::soundcast::popdat::{Endpoint, OrigTrip, PopDat};
 
@@ -296,6 +298,10 @@ pub fn make_scenario(
         map_name: map.get_name().clone(),
         people,
         only_seed_buses: None,
+        driver_behavior: None,
+        weather: Weather::Clear,
+        curbside_dwell: None,
+        day_to_day: None,
     }
     .remove_weird_schedules()
 }
@@ -1,20 +1,34 @@
-use geom::{Distance, Line, Polygon, Pt2D};
-use map_gui::tools::{CameraState, URLManager};
+use anyhow::bail;
+
+use geom::{Bounds, Circle, Distance, Line, Polygon, Pt2D};
+use map_gui::tools::{CameraState, FilePicker, PopupMsg, URLManager};
 use map_gui::AppLike;
 use map_model::osm;
 use map_model::raw::OriginalRoad;
 use widgetry::{
-    lctrl, Canvas, Color, EventCtx, GfxCtx, HorizontalAlignment, Key, Line, Outcome, Panel,
-    SharedAppState, State, Text, Toggle, Transition, VerticalAlignment, Widget,
+    lctrl, Canvas, Color, DrawBaselayer, Drawable, EventCtx, GeomBatch, GfxCtx,
+    HorizontalAlignment, Key, Line, Outcome, Panel, RoundedF64, SharedAppState, Spinner, State,
+    Text, TextBox, TextExt, Toggle, Transition, VerticalAlignment, Widget,
 };
 
+use crate::autosave::Autosave;
+use crate::background::Background;
 use crate::model::{Model, ID};
+use crate::snap::Snapping;
 
 pub struct App {
     pub model: Model,
+    pub snapping: Snapping,
+    /// Imagery drawn under the map
+    pub background: Option<Background>,
+    pub autosave: Autosave,
 }
 
 impl SharedAppState for App {
+    fn before_event(&mut self) {
+        self.autosave.maybe_save(&self.model);
+    }
+
     fn draw_default(&self, g: &mut GfxCtx) {
         g.clear(Color::BLACK);
     }
@@ -23,6 +37,7 @@ impl SharedAppState for App {
         if !self.model.map.name.map.is_empty() {
             CameraState::save(canvas, &self.model.map.name);
         }
+        self.autosave.save_before_abort(&self.model);
     }
 
     fn before_quit(&self, canvas: &Canvas) {
@@ -89,6 +104,8 @@ impl AppLike for App {
 pub struct MainState {
     mode: Mode,
     panel: Panel,
+    // Results from searching for an OSM ID
+    highlight: Drawable,
 
     last_id: Option<ID>,
 }
@@ -138,6 +155,11 @@ impl MainState {
             Key::Backspace.txt(ctx),
             Line(" to delete something"),
         ]);
+        instructions.add_appended(vec![
+            Line("Press "),
+            Line(lctrl(Key::F).describe()).fg(ctx.style().text_hotkey_color),
+            Line(" to search for an OSM ID"),
+        ]);
 
         Box::new(MainState {
             mode: Mode::Viewing,
@@ -180,6 +202,10 @@ impl MainState {
                             .btn_outline
                             .text("adjust boundary")
                             .build_def(ctx),
+                        ctx.style()
+                            .btn_outline
+                            .text("clip to GeoJSON boundary")
+                            .build_def(ctx),
                         ctx.style()
                             .btn_outline
                             .text("auto mark junctions")
@@ -188,6 +214,53 @@ impl MainState {
                             .btn_outline
                             .text("simplify RawMap")
                             .build_def(ctx),
+                        ctx.style()
+                            .btn_outline
+                            .text("compare with original")
+                            .build_def(ctx),
+                        ctx.style()
+                            .btn_outline
+                            .text("preview conversion")
+                            .build_def(ctx),
+                        ctx.style().btn_outline.text("validate").build_def(ctx),
+                        ctx.style()
+                            .btn_outline
+                            .text("find duplicate roads")
+                            .build_def(ctx),
+                        ctx.style()
+                            .btn_outline
+                            .text("background imagery")
+                            .build_def(ctx),
+                    ])
+                    .section(ctx),
+                    Widget::col(vec![
+                        Widget::row(vec![
+                            Toggle::switch(ctx, "snap to grid", None, app.snapping.grid.is_some()),
+                            Spinner::widget(
+                                ctx,
+                                "grid size (m)",
+                                (1, 100),
+                                app.snapping
+                                    .grid
+                                    .map(|grid| grid.inner_meters() as usize)
+                                    .unwrap_or(10),
+                                1,
+                            ),
+                        ]),
+                        Toggle::switch(ctx, "snap angles to 45°", None, app.snapping.angles),
+                        Toggle::switch(
+                            ctx,
+                            "snap to existing points",
+                            None,
+                            app.snapping.to_points,
+                        ),
+                        Spinner::widget(
+                            ctx,
+                            "nudge distance (m)",
+                            (RoundedF64(0.1), RoundedF64(100.0)),
+                            RoundedF64(1.0),
+                            RoundedF64(0.1),
+                        ),
                     ])
                     .section(ctx),
                 ]),
@@ -195,10 +268,15 @@ impl MainState {
             ]))
             .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
             .build(ctx),
+            highlight: Drawable::empty(ctx),
 
             last_id: None,
         })
     }
+
+    fn nudge_distance(&self) -> Distance {
+        Distance::meters(self.panel.spinner::<RoundedF64>("nudge distance (m)").0)
+    }
 }
 
 impl State<App> for MainState {
@@ -237,6 +315,13 @@ impl State<App> for MainState {
 
         match self.mode {
             Mode::Viewing => {
+                if ctx.input.pressed(lctrl(Key::F)) {
+                    return Transition::Push(SearchOsmID::new_state(ctx));
+                }
+                if ctx.input.pressed(Key::Escape) {
+                    self.highlight = Drawable::empty(ctx);
+                }
+
                 {
                     let before = match self.last_id {
                         Some(ID::Road(r)) | Some(ID::RoadPoint(r, _)) => Some(r),
@@ -270,6 +355,32 @@ impl State<App> for MainState {
                             app.model.toggle_i(ctx, i);
                         } else if ctx.input.pressed(Key::P) {
                             app.model.debug_intersection_geometry(ctx, i);
+                        } else if ctx.input.pressed(Key::E) {
+                            return Transition::Push(
+                                crate::turn_restrictions::EditTurnRestrictions::new_state(
+                                    ctx, app, i,
+                                ),
+                            );
+                        } else if ctx.input.pressed(Key::O) {
+                            return Transition::Push(crate::reimport::ReimportFromOSM::new_state(
+                                ctx,
+                                crate::reimport::Target::Intersection(i),
+                            ));
+                        } else if ctx.input.pressed(Key::C) {
+                            return Transition::Push(
+                                crate::coordinates::SetCoordinates::new_state(
+                                    ctx,
+                                    app,
+                                    ID::Intersection(i),
+                                ),
+                            );
+                        } else {
+                            crate::coordinates::nudge(
+                                ctx,
+                                app,
+                                ID::Intersection(i),
+                                self.nudge_distance(),
+                            );
                         }
 
                         let mut txt = Text::new();
@@ -290,13 +401,26 @@ impl State<App> for MainState {
                         txt.add_appended(vec![
                             Line("- Press "),
                             Key::T.txt(ctx),
-                            Line(" to toggle stop sign / traffic signal"),
+                            Line(" to change the control type (currently "),
+                            Line(app.model.describe_i_control(i)),
+                            Line(")"),
                         ]);
                         txt.add_appended(vec![
                             Line("- Press "),
                             Key::P.txt(ctx),
                             Line(" to debug intersection geometry"),
                         ]);
+                        txt.add_appended(vec![
+                            Line("- Press "),
+                            Key::E.txt(ctx),
+                            Line(" to edit turn restrictions"),
+                        ]);
+                        txt.add_appended(vec![
+                            Line("- Press "),
+                            Key::O.txt(ctx),
+                            Line(" to re-import from OSM"),
+                        ]);
+                        add_precise_movement_instructions(ctx, &mut txt);
                         let instructions = txt.into_widget(ctx);
                         self.panel.replace(ctx, "instructions", instructions);
                     }
@@ -306,6 +430,10 @@ impl State<App> for MainState {
                         } else if ctx.input.pressed(Key::Backspace) {
                             app.model.delete_b(b);
                             app.model.world.handle_mouseover(ctx);
+                        } else if ctx.input.pressed(Key::E) {
+                            return Transition::Push(crate::building::EditBuilding::new_state(
+                                ctx, app, b,
+                            ));
                         }
 
                         let mut txt = Text::new();
@@ -314,6 +442,11 @@ impl State<App> for MainState {
                             Key::Backspace.txt(ctx),
                             Line(" to delete"),
                         ]);
+                        txt.add_appended(vec![
+                            Line("- Press "),
+                            Key::E.txt(ctx),
+                            Line(" to edit its shape and amenities"),
+                        ]);
                         txt.add_appended(vec![
                             Line("- Click and drag").fg(ctx.style().text_hotkey_color),
                             Line(" to move"),
@@ -336,6 +469,24 @@ impl State<App> for MainState {
                             app.model.world.handle_mouseover(ctx);
                         } else if ctx.input.pressed(Key::J) {
                             app.model.toggle_junction(ctx, r);
+                        } else if cursor.is_some() && ctx.input.pressed(Key::K) {
+                            match app.model.add_crossing(ctx, r, cursor.unwrap()) {
+                                Ok(i) => {
+                                    app.model.world.force_set_selection(ID::Intersection(i));
+                                }
+                                Err(err) => {
+                                    return Transition::Push(PopupMsg::new_state(
+                                        ctx,
+                                        "Can't add a crossing",
+                                        vec![err.to_string()],
+                                    ));
+                                }
+                            }
+                        } else if ctx.input.pressed(Key::O) {
+                            return Transition::Push(crate::reimport::ReimportFromOSM::new_state(
+                                ctx,
+                                crate::reimport::Target::Road(r),
+                            ));
                         } else if ctx.normal_left_click() {
                             return Transition::Push(crate::edit::EditRoad::new_state(ctx, app, r));
                         }
@@ -370,6 +521,16 @@ impl State<App> for MainState {
                             Key::J.txt(ctx),
                             Line(" to mark/unmark as a junction"),
                         ]);
+                        txt.add_appended(vec![
+                            Line("- Press "),
+                            Key::K.txt(ctx),
+                            Line(" to add a pedestrian crossing here"),
+                        ]);
+                        txt.add_appended(vec![
+                            Line("- Press "),
+                            Key::O.txt(ctx),
+                            Line(" to re-import from OSM"),
+                        ]);
                         let instructions = txt.into_widget(ctx);
                         self.panel.replace(ctx, "instructions", instructions);
                     }
@@ -379,6 +540,21 @@ impl State<App> for MainState {
                         } else if ctx.input.pressed(Key::Backspace) {
                             app.model.delete_r_pt(ctx, r, idx);
                             app.model.world.handle_mouseover(ctx);
+                        } else if ctx.input.pressed(Key::C) {
+                            return Transition::Push(
+                                crate::coordinates::SetCoordinates::new_state(
+                                    ctx,
+                                    app,
+                                    ID::RoadPoint(r, idx),
+                                ),
+                            );
+                        } else {
+                            crate::coordinates::nudge(
+                                ctx,
+                                app,
+                                ID::RoadPoint(r, idx),
+                                self.nudge_distance(),
+                            );
                         }
 
                         let mut txt = Text::new();
@@ -391,6 +567,7 @@ impl State<App> for MainState {
                             Line("- Click and drag").fg(ctx.style().text_hotkey_color),
                             Line(" to move"),
                         ]);
+                        add_precise_movement_instructions(ctx, &mut txt);
                         let instructions = txt.into_widget(ctx);
                         self.panel.replace(ctx, "instructions", instructions);
                     }
@@ -400,6 +577,9 @@ impl State<App> for MainState {
                                 "adjust boundary" => {
                                     self.mode = Mode::SetBoundaryPt1;
                                 }
+                                "clip to GeoJSON boundary" => {
+                                    return Transition::Push(clip_to_geojson(ctx));
+                                }
                                 "auto mark junctions" => {
                                     for r in app.model.map.auto_mark_junctions() {
                                         app.model.road_deleted(r);
@@ -412,11 +592,37 @@ impl State<App> for MainState {
                                         app.model.recreate_world(ctx, timer);
                                     });
                                 }
+                                "compare with original" => {
+                                    return Transition::Push(crate::diff::DiffViewer::load(
+                                        ctx, app,
+                                    ));
+                                }
+                                "preview conversion" => {
+                                    return Transition::Push(
+                                        crate::preview::PreviewConversion::new_state(ctx, app),
+                                    );
+                                }
+                                "validate" => {
+                                    return Transition::Push(
+                                        crate::validate::ValidateMap::new_state(ctx, app),
+                                    );
+                                }
+                                "find duplicate roads" => {
+                                    return Transition::Push(
+                                        crate::duplicates::FindDuplicates::new_state(ctx, app),
+                                    );
+                                }
+                                "background imagery" => {
+                                    return Transition::Push(
+                                        crate::background::BackgroundSettings::new_state(ctx, app),
+                                    );
+                                }
                                 "export to OSM" => {
                                     app.model.export_to_osm();
                                 }
                                 "overwrite RawMap" => {
                                     app.model.map.save();
+                                    app.autosave.map_saved(&app.model);
                                 }
                                 "reload" => {
                                     CameraState::save(ctx.canvas, &app.model.map.name);
@@ -433,15 +639,30 @@ impl State<App> for MainState {
                                 }
                                 _ => unreachable!(),
                             },
-                            Outcome::Changed(_) => {
-                                app.model.show_intersection_geometry(
-                                    ctx,
-                                    self.panel.is_checked("intersection geometry"),
-                                );
+                            Outcome::Changed(x) => {
+                                if x == "intersection geometry" {
+                                    app.model.show_intersection_geometry(
+                                        ctx,
+                                        self.panel.is_checked("intersection geometry"),
+                                    );
+                                } else {
+                                    app.snapping = Snapping {
+                                        grid: if self.panel.is_checked("snap to grid") {
+                                            Some(Distance::meters(
+                                                self.panel.spinner::<usize>("grid size (m)") as f64,
+                                            ))
+                                        } else {
+                                            None
+                                        },
+                                        angles: self.panel.is_checked("snap angles to 45°"),
+                                        to_points: self.panel.is_checked("snap to existing points"),
+                                    };
+                                }
                             }
                             _ => {
                                 if ctx.input.pressed(Key::I) {
                                     if let Some(pt) = cursor {
+                                        let pt = app.snapping.snap(&app.model.map, pt, None, None);
                                         app.model.create_i(ctx, pt);
                                         app.model.world.handle_mouseover(ctx);
                                     }
@@ -453,6 +674,16 @@ impl State<App> for MainState {
                                         let id = app.model.create_b(ctx, pt);
                                         app.model.world.force_set_selection(id);
                                     }
+                                } else if ctx.input.pressed(Key::N) {
+                                    return Transition::Push(
+                                        crate::building::DrawBuilding::new_state(ctx),
+                                    );
+                                } else if ctx.input.pressed(Key::D) {
+                                    return Transition::Push(crate::edit::DrawRoad::new_state(ctx));
+                                } else if ctx.input.pressed(Key::S) {
+                                    return Transition::Push(
+                                        crate::edit::BulkEditRoads::new_state(ctx),
+                                    );
                                 }
 
                                 let mut txt = Text::new();
@@ -466,6 +697,21 @@ impl State<App> for MainState {
                                     Key::B.txt(ctx),
                                     Line(" to create a building"),
                                 ]);
+                                txt.add_appended(vec![
+                                    Line("- Press "),
+                                    Key::N.txt(ctx),
+                                    Line(" to draw a new building"),
+                                ]);
+                                txt.add_appended(vec![
+                                    Line("- Press "),
+                                    Key::D.txt(ctx),
+                                    Line(" to draw a new road"),
+                                ]);
+                                txt.add_appended(vec![
+                                    Line("- Press "),
+                                    Key::S.txt(ctx),
+                                    Line(" to select and edit many roads"),
+                                ]);
                                 let instructions = txt.into_widget(ctx);
                                 self.panel.replace(ctx, "instructions", instructions);
                             }
@@ -475,6 +721,19 @@ impl State<App> for MainState {
             }
             Mode::MovingIntersection(id) => {
                 if let Some(pt) = cursor {
+                    // Keep a dead-end road straight
+                    let roads = app.model.map.roads_per_intersection(id);
+                    let anchor = if roads.len() == 1 {
+                        let pts = &app.model.map.roads[&roads[0]].center_points;
+                        Some(if roads[0].i1 == id {
+                            pts[1]
+                        } else {
+                            pts[pts.len() - 2]
+                        })
+                    } else {
+                        None
+                    };
+                    let pt = app.snapping.snap(&app.model.map, pt, anchor, Some(id));
                     app.model.move_i(ctx, id, pt);
                     if ctx.input.left_mouse_button_released() {
                         self.mode = Mode::Viewing;
@@ -556,8 +815,12 @@ impl State<App> for MainState {
             Color::rgb(242, 239, 233),
             app.model.map.boundary_polygon.clone(),
         );
+        if let Some(ref bg) = app.background {
+            bg.draw(g);
+        }
         app.model.world.draw(g, |_| true);
         g.redraw(&app.model.draw_extra);
+        g.redraw(&self.highlight);
 
         match self.mode {
             Mode::CreatingRoad(i1) => {
@@ -584,3 +847,246 @@ impl State<App> for MainState {
         self.panel.draw(g);
     }
 }
+
+fn add_precise_movement_instructions(ctx: &EventCtx, txt: &mut Text) {
+    txt.add_appended(vec![
+        Line("- Press the "),
+        Line("arrow keys").fg(ctx.style().text_hotkey_color),
+        Line(" to nudge it"),
+    ]);
+    txt.add_appended(vec![
+        Line("- Press "),
+        Key::C.txt(ctx),
+        Line(" to type exact coordinates"),
+    ]);
+}
+
+/// Pick a GeoJSON file with a polygon, then clip the map to it.
+fn clip_to_geojson(ctx: &mut EventCtx) -> Box<dyn State<App>> {
+    FilePicker::new_state(
+        ctx,
+        None,
+        Box::new(|ctx, app, maybe_path| {
+            let path = match maybe_path {
+                Ok(Some(path)) => path,
+                // The user didn't pick a file
+                _ => {
+                    return Transition::Pop;
+                }
+            };
+            let result = abstio::slurp_file(&path).and_then(|bytes| {
+                let mut polygons =
+                    Polygon::from_geojson_bytes(&bytes, &app.model.map.gps_bounds, false)?;
+                if polygons.is_empty() {
+                    bail!("{} doesn't contain any polygons", path);
+                }
+                let boundary = polygons.remove(0).0;
+                ctx.loading_screen("clip map", |ctx, timer| {
+                    app.model.clip_to_boundary(ctx, boundary, timer)
+                })
+            });
+            match result {
+                Ok(()) => Transition::Pop,
+                Err(err) => Transition::Replace(PopupMsg::new_state(
+                    ctx,
+                    "Error",
+                    vec![format!("Couldn't clip the map: {}", err)],
+                )),
+            }
+        }),
+    )
+}
+
+/// Warp to whatever matches an OSM way, node, or relation ID. Debugging the importer usually
+/// starts from an ID in a log message.
+struct SearchOsmID {
+    panel: Panel,
+}
+
+impl SearchOsmID {
+    fn new_state(ctx: &mut EventCtx) -> Box<dyn State<App>> {
+        Box::new(SearchOsmID {
+            panel: Panel::new_builder(Widget::col(vec![
+                Widget::row(vec![
+                    Line("Search for an OSM ID")
+                        .small_heading()
+                        .into_widget(ctx),
+                    ctx.style().btn_close_widget(ctx),
+                ]),
+                "Like \"way 123\", \"n123\", or an openstreetmap.org URL".text_widget(ctx),
+                TextBox::default_widget(ctx, "input", String::new()),
+                ctx.style()
+                    .btn_outline
+                    .text("Go!")
+                    .hotkey(Key::Enter)
+                    .build_def(ctx),
+            ]))
+            .build(ctx),
+        })
+    }
+}
+
+impl State<App> for SearchOsmID {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition<App> {
+        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+            match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "Go!" => {
+                    let input = self.panel.text_box("input");
+                    let ids = parse_osm_id(&input);
+                    let matches = find_osm_ids(app, &ids);
+                    if matches.is_empty() {
+                        return Transition::Replace(PopupMsg::new_state(
+                            ctx,
+                            "Not found",
+                            vec![format!("Nothing in this map matches {}", input)],
+                        ));
+                    }
+
+                    let mut bounds = Bounds::new();
+                    let mut batch = GeomBatch::new();
+                    for poly in matches {
+                        for pt in poly.points() {
+                            bounds.update(*pt);
+                        }
+                        batch.push(Color::YELLOW.alpha(0.8), poly);
+                    }
+                    ctx.canvas.center_on_map_pt(bounds.center());
+                    let highlight = ctx.upload(batch);
+
+                    return Transition::Multi(vec![
+                        Transition::Pop,
+                        Transition::ModifyState(Box::new(move |state, _, _| {
+                            if let Some(state) = state.downcast_mut::<MainState>() {
+                                state.highlight = highlight;
+                            }
+                        })),
+                    ]);
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        Transition::Keep
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::PreviousState
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.panel.draw(g);
+    }
+}
+
+/// Understands "way 123", "w123", "way/123", and openstreetmap.org URLs. A plain number could be
+/// any type of ID.
+fn parse_osm_id(input: &str) -> Vec<osm::OsmID> {
+    let input = input.trim().trim_end_matches('/');
+    let input = input.rsplit("openstreetmap.org/").next().unwrap();
+    let split = input
+        .find(|c: char| c.is_ascii_digit() || c == '-')
+        .unwrap_or(input.len());
+    let (kind, num) = input.split_at(split);
+    let id = match num.parse::<i64>() {
+        Ok(id) => id,
+        Err(_) => {
+            return Vec::new();
+        }
+    };
+    match kind.trim().trim_end_matches('/').to_lowercase().as_ref() {
+        "w" | "way" => vec![osm::OsmID::Way(osm::WayID(id))],
+        "n" | "node" => vec![osm::OsmID::Node(osm::NodeID(id))],
+        "r" | "relation" => vec![osm::OsmID::Relation(osm::RelationID(id))],
+        "" => vec![
+            osm::OsmID::Way(osm::WayID(id)),
+            osm::OsmID::Node(osm::NodeID(id)),
+            osm::OsmID::Relation(osm::RelationID(id)),
+        ],
+        _ => Vec::new(),
+    }
+}
+
+/// The shape of everything in the map matching any of the IDs
+fn find_osm_ids(app: &App, ids: &[osm::OsmID]) -> Vec<Polygon> {
+    let map = &app.model.map;
+    let mut results = Vec::new();
+    for id in ids {
+        match id {
+            osm::OsmID::Way(w) => {
+                for (r, road) in &map.roads {
+                    if r.osm_way_id == *w {
+                        if let Ok((pl, width)) = road.get_geometry(*r, &map.config) {
+                            results.push(pl.make_polygons(width));
+                        }
+                    }
+                }
+            }
+            osm::OsmID::Node(n) => {
+                if let Some(i) = map.intersections.get(n) {
+                    results.push(Circle::new(i.point, Distance::meters(10.0)).to_polygon());
+                }
+            }
+            // Only areas and buildings come from relations
+            osm::OsmID::Relation(_) => {}
+        }
+
+        if let Some(b) = map.buildings.get(id) {
+            results.push(b.polygon.clone());
+        }
+        for area in &map.areas {
+            if area.osm_id == *id {
+                results.push(area.polygon.clone());
+            }
+        }
+        for lot in &map.parking_lots {
+            if lot.osm_id == *id {
+                results.push(lot.polygon.clone());
+            }
+        }
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_osm_id() {
+        let way = vec![osm::OsmID::Way(osm::WayID(123))];
+        for input in [
+            "way 123",
+            "w123",
+            "way/123",
+            " Way/123 ",
+            "https://www.openstreetmap.org/way/123",
+            "https://www.openstreetmap.org/way/123/",
+        ] {
+            assert_eq!(parse_osm_id(input), way, "parsing {:?}", input);
+        }
+        assert_eq!(
+            parse_osm_id("node/-5"),
+            vec![osm::OsmID::Node(osm::NodeID(-5))]
+        );
+        assert_eq!(
+            parse_osm_id("r 7"),
+            vec![osm::OsmID::Relation(osm::RelationID(7))]
+        );
+        // A plain number could be anything
+        assert_eq!(
+            parse_osm_id("42"),
+            vec![
+                osm::OsmID::Way(osm::WayID(42)),
+                osm::OsmID::Node(osm::NodeID(42)),
+                osm::OsmID::Relation(osm::RelationID(42)),
+            ]
+        );
+
+        for input in ["", "way", "way 12x", "changeset 5", "123 way"] {
+            assert!(parse_osm_id(input).is_empty(), "parsing {:?}", input);
+        }
+    }
+}
@@ -0,0 +1,192 @@
+//! The editor can crash, usually on some weird geometry, so the map being edited is periodically
+//! saved to a scratch file. On the next launch, the editor offers to restore it. There's no undo
+//! history in this editor, so the RawMap itself is the whole session.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use instant::Instant;
+
+use abstutil::Timer;
+use map_gui::tools::CameraState;
+use map_model::raw::RawMap;
+use widgetry::{EventCtx, GfxCtx, Key, Line, Outcome, Panel, State, Text, Transition, Widget};
+
+use crate::app::App;
+use crate::model::Model;
+
+/// How often to check for changes, in seconds
+const AUTOSAVE_INTERVAL: f64 = 30.0;
+
+pub struct Autosave {
+    last_check: Instant,
+    /// A hash of the map as it was last saved, either to the scratch file or its real path
+    saved_hash: u64,
+}
+
+impl Autosave {
+    pub fn new(model: &Model) -> Autosave {
+        Autosave {
+            last_check: Instant::now(),
+            saved_hash: hash(model),
+        }
+    }
+
+    /// Call this often. Every so often, if the map changed since the last save, write it.
+    pub fn maybe_save(&mut self, model: &Model) {
+        if cfg!(target_arch = "wasm32")
+            || abstutil::elapsed_seconds(self.last_check) < AUTOSAVE_INTERVAL
+        {
+            return;
+        }
+        self.last_check = Instant::now();
+        let hash = hash(model);
+        if hash != self.saved_hash {
+            save(model);
+            self.saved_hash = hash;
+        }
+    }
+
+    /// Used when the editor is about to crash. Skip saving if nothing changed since the last
+    /// save.
+    pub fn save_before_abort(&self, model: &Model) {
+        if !cfg!(target_arch = "wasm32") && hash(model) != self.saved_hash {
+            save(model);
+        }
+    }
+
+    /// A new map has been loaded, or the current one was saved. Either way, there's nothing to
+    /// recover yet.
+    pub fn reset(&mut self, model: &Model) {
+        self.last_check = Instant::now();
+        self.saved_hash = hash(model);
+    }
+
+    /// The map was written to its real path, so the scratch file is stale.
+    pub fn map_saved(&mut self, model: &Model) {
+        abstio::delete_file(path());
+        self.reset(model);
+    }
+}
+
+fn path() -> String {
+    abstio::path_player("map_editor_autosave.bin")
+}
+
+fn hash(model: &Model) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    abstutil::to_binary(&model.map).hash(&mut hasher);
+    hasher.finish()
+}
+
+fn save(model: &Model) {
+    abstio::write_binary(path(), &model.map);
+}
+
+pub struct RestoreSession {
+    panel: Panel,
+    map: Option<RawMap>,
+    /// If the user doesn't restore, then load this map instead
+    then_load: Option<(String, Option<String>)>,
+}
+
+impl RestoreSession {
+    /// If the editor left behind a session last time, ask to restore it. `then_load` is the path
+    /// to a map and the initial camera to use otherwise.
+    pub fn maybe_new_state(
+        ctx: &mut EventCtx,
+        then_load: Option<(String, Option<String>)>,
+    ) -> Option<Box<dyn State<App>>> {
+        if cfg!(target_arch = "wasm32") || !abstio::file_exists(path()) {
+            return None;
+        }
+        let map: RawMap = match abstio::maybe_read_binary(path(), &mut Timer::throwaway()) {
+            Ok(map) => map,
+            Err(err) => {
+                warn!(
+                    "Couldn't read the autosaved session, discarding it: {}",
+                    err
+                );
+                abstio::delete_file(path());
+                return None;
+            }
+        };
+
+        let name = if map.name.map.is_empty() {
+            "a new map".to_string()
+        } else {
+            map.name.describe()
+        };
+        let panel = Panel::new_builder(Widget::col(vec![
+            Line("Restore unsaved work?")
+                .small_heading()
+                .into_widget(ctx),
+            Text::from_multiline(vec![
+                Line(format!("Changes to {} weren't saved last time.", name)),
+                Line("Discarding them can't be undone.").secondary(),
+            ])
+            .into_widget(ctx),
+            Widget::row(vec![
+                ctx.style()
+                    .btn_solid_primary
+                    .text("Restore")
+                    .hotkey(Key::Enter)
+                    .build_def(ctx),
+                ctx.style()
+                    .btn_solid_destructive
+                    .text("Discard")
+                    .build_def(ctx),
+            ]),
+        ]))
+        .build(ctx);
+
+        Some(Box::new(RestoreSession {
+            panel,
+            map: Some(map),
+            then_load,
+        }))
+    }
+}
+
+impl State<App> for RestoreSession {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition<App> {
+        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+            match x.as_ref() {
+                "Restore" => {
+                    app.model = Model::from_map(
+                        ctx,
+                        self.map.take().unwrap(),
+                        app.model.include_bldgs,
+                        &mut Timer::throwaway(),
+                    );
+                    app.background = None;
+                    // Keep the scratch file until the user saves or changes something
+                    app.autosave.reset(&app.model);
+                    if !app.model.map.name.map.is_empty() {
+                        CameraState::load(ctx, &app.model.map.name);
+                    }
+                    return Transition::Clear(vec![crate::app::MainState::new_state(ctx, app)]);
+                }
+                "Discard" => {
+                    abstio::delete_file(path());
+                    return if let Some((map_path, cam)) = self.then_load.take() {
+                        Transition::Replace(crate::load::load_map(
+                            ctx,
+                            map_path,
+                            app.model.include_bldgs,
+                            cam,
+                        ))
+                    } else {
+                        Transition::Pop
+                    };
+                }
+                _ => unreachable!(),
+            }
+        }
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.panel.draw(g);
+    }
+}
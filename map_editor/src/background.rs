@@ -0,0 +1,486 @@
+//! Raster imagery drawn underneath the RawMap. When fixing geometry, it's hard to know where a road
+//! truly is without seeing it. Imagery comes from an XYZ tile server, with tiles cached locally, or
+//! from a local PNG with known bounds.
+
+use std::f64::consts::PI;
+
+use anyhow::{bail, Result};
+use image::RgbaImage;
+
+use geom::{GPSBounds, LonLat, Polygon, Pt2D};
+use map_gui::load::FutureLoader;
+use map_gui::tools::{FilePicker, PopupMsg};
+use widgetry::{
+    Color, DrawBaselayer, Drawable, EventCtx, GeomBatch, GfxCtx, Line, Outcome, Panel,
+    RewriteColor, Spinner, State, TextBox, TextExt, Transition, Widget,
+};
+
+use crate::app::App;
+
+/// Every cell of an image becomes a rectangle, so drawing each pixel would be far too many
+/// polygons. Downsample all of the imagery to about this many cells per side.
+const TOTAL_CELLS_PER_SIDE: f64 = 1024.0;
+/// Don't hammer a tile server. Lower the zoom level to cover a big map.
+const MAX_TILES: usize = 256;
+/// Pick a default zoom level with about this many tiles.
+const DEFAULT_NUM_TILES: usize = 64;
+// TODO Aerial imagery needs a provider-specific URL, and usually an API key
+const DEFAULT_TILE_URL: &str = "https://tile.openstreetmap.org/{z}/{x}/{y}.png";
+
+/// Imagery that's been converted to map-space
+pub struct Background {
+    /// Where the imagery came from
+    source: String,
+    /// Fully opaque; the transparency is applied when uploading
+    batch: GeomBatch,
+    /// A percentage
+    opacity: usize,
+    draw: Drawable,
+}
+
+impl Background {
+    fn new(ctx: &EventCtx, source: String, batch: GeomBatch, opacity: usize) -> Background {
+        let mut bg = Background {
+            source,
+            batch,
+            opacity,
+            draw: Drawable::empty(ctx),
+        };
+        bg.set_opacity(ctx, opacity);
+        bg
+    }
+
+    fn set_opacity(&mut self, ctx: &EventCtx, opacity: usize) {
+        self.opacity = opacity;
+        self.draw = ctx.upload(
+            self.batch
+                .clone()
+                .color(RewriteColor::ChangeAlpha(opacity as f32 / 100.0)),
+        );
+    }
+
+    pub fn draw(&self, g: &mut GfxCtx) {
+        g.redraw(&self.draw);
+    }
+}
+
+/// Fetch, load, remove, or adjust the background imagery
+pub struct BackgroundSettings {
+    panel: Panel,
+}
+
+impl BackgroundSettings {
+    pub fn new_state(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        let gps_bounds = &app.model.map.gps_bounds;
+
+        let current = if let Some(ref bg) = app.background {
+            Widget::col(vec![
+                format!("Showing {}", bg.source).text_widget(ctx),
+                Widget::row(vec![
+                    Spinner::widget(ctx, "opacity (%)", (0, 100), bg.opacity, 10),
+                    ctx.style()
+                        .btn_solid_destructive
+                        .text("remove")
+                        .build_def(ctx),
+                ]),
+            ])
+        } else {
+            "No background imagery".text_widget(ctx)
+        };
+
+        Box::new(BackgroundSettings {
+            panel: Panel::new_builder(Widget::col(vec![
+                Widget::row(vec![
+                    Line("Background imagery").small_heading().into_widget(ctx),
+                    ctx.style().btn_close_widget(ctx),
+                ]),
+                current.section(ctx),
+                Widget::col(vec![
+                    Line("Fetch XYZ tiles").small_heading().into_widget(ctx),
+                    "Tiles are cached in data/input/shared/imagery".text_widget(ctx),
+                    TextBox::default_widget(ctx, "tile URL", DEFAULT_TILE_URL.to_string()),
+                    Spinner::widget(ctx, "zoom", (1, 20), default_zoom(gps_bounds), 1),
+                    ctx.style().btn_outline.text("fetch tiles").build_def(ctx),
+                ])
+                .section(ctx),
+                Widget::col(vec![
+                    Line("Load a local PNG").small_heading().into_widget(ctx),
+                    "Bounds of the image: min lon, min lat, max lon, max lat".text_widget(ctx),
+                    TextBox::default_widget(
+                        ctx,
+                        "image bounds",
+                        format!(
+                            "{}, {}, {}, {}",
+                            gps_bounds.min_lon,
+                            gps_bounds.min_lat,
+                            gps_bounds.max_lon,
+                            gps_bounds.max_lat
+                        ),
+                    ),
+                    ctx.style().btn_outline.text("load PNG").build_def(ctx),
+                ])
+                .section(ctx),
+            ]))
+            .build(ctx),
+        })
+    }
+}
+
+impl State<App> for BackgroundSettings {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition<App> {
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "remove" => {
+                    app.background = None;
+                    return Transition::Replace(BackgroundSettings::new_state(ctx, app));
+                }
+                "fetch tiles" => {
+                    let template = self.panel.text_box("tile URL");
+                    let zoom = self.panel.spinner::<usize>("zoom") as u32;
+                    let tiles = tiles_covering(&app.model.map.gps_bounds, zoom);
+                    if tiles.len() > MAX_TILES {
+                        return Transition::Push(PopupMsg::new_state(
+                            ctx,
+                            "Too many tiles",
+                            vec![format!(
+                                "Covering this map at zoom {} needs {} tiles. Try a lower zoom.",
+                                zoom,
+                                tiles.len()
+                            )],
+                        ));
+                    }
+                    return Transition::Replace(fetch_tiles(ctx, template, zoom, tiles));
+                }
+                "load PNG" => match parse_bounds(&self.panel.text_box("image bounds")) {
+                    Ok(bounds) => {
+                        return Transition::Replace(load_png(ctx, bounds));
+                    }
+                    Err(err) => {
+                        return Transition::Push(PopupMsg::new_state(
+                            ctx,
+                            "Error",
+                            vec![err.to_string()],
+                        ));
+                    }
+                },
+                _ => unreachable!(),
+            },
+            Outcome::Changed(x) => {
+                if x == "opacity (%)" {
+                    if let Some(ref mut bg) = app.background {
+                        bg.set_opacity(ctx, self.panel.spinner("opacity (%)"));
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        Transition::Keep
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::PreviousState
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.panel.draw(g);
+    }
+}
+
+fn fetch_tiles(
+    ctx: &mut EventCtx,
+    template: String,
+    zoom: u32,
+    tiles: Vec<(u32, u32)>,
+) -> Box<dyn State<App>> {
+    let (mut outer_progress_tx, outer_progress_rx) = futures_channel::mpsc::channel(1000);
+    let (_, inner_progress_rx) = futures_channel::mpsc::channel(1);
+    let source = template.clone();
+    FutureLoader::<App, GeomBatch>::new_state(
+        ctx,
+        Box::pin(async move {
+            let result = download_tiles(template, zoom, tiles, &mut outer_progress_tx).await;
+            result.map(|images| {
+                let wrap: Box<dyn Send + FnOnce(&App) -> GeomBatch> =
+                    Box::new(move |app: &App| tiles_to_batch(app, zoom, images));
+                wrap
+            })
+        }),
+        outer_progress_rx,
+        inner_progress_rx,
+        "Fetching background imagery",
+        Box::new(move |ctx, app, result| match result {
+            Ok(batch) => {
+                app.background = Some(Background::new(ctx, source, batch, 50));
+                Transition::Pop
+            }
+            Err(err) => Transition::Replace(PopupMsg::new_state(
+                ctx,
+                "Error",
+                vec![format!("Couldn't fetch tiles: {}", err)],
+            )),
+        }),
+    )
+}
+
+async fn download_tiles(
+    template: String,
+    zoom: u32,
+    tiles: Vec<(u32, u32)>,
+    progress: &mut futures_channel::mpsc::Sender<String>,
+) -> Result<Vec<(u32, u32, RgbaImage)>> {
+    let mut images = Vec::new();
+    let num_tiles = tiles.len();
+    for (idx, (x, y)) in tiles.into_iter().enumerate() {
+        if let Err(err) = progress.try_send(format!(
+            "Fetching tile {}/{} at zoom {}",
+            idx + 1,
+            num_tiles,
+            zoom
+        )) {
+            warn!("Couldn't send progress: {}", err);
+        }
+        let bytes = fetch_tile(&template, zoom, x, y).await?;
+        images.push((x, y, image::load_from_memory(&bytes)?.to_rgba8()));
+    }
+    Ok(images)
+}
+
+fn tiles_to_batch(app: &App, zoom: u32, images: Vec<(u32, u32, RgbaImage)>) -> GeomBatch {
+    let gps_bounds = &app.model.map.gps_bounds;
+    // The tiles are usually arranged in a square
+    let cells_per_side = (TOTAL_CELLS_PER_SIDE / (images.len() as f64).sqrt()) as u32;
+    let mut batch = GeomBatch::new();
+    for (x, y, img) in images {
+        let (width, height) = (img.width() as f64, img.height() as f64);
+        image_to_batch(
+            &img,
+            cells_per_side,
+            |px, py| {
+                LonLat::new(
+                    tile_x_to_lon(x as f64 + px / width, zoom),
+                    tile_y_to_lat(y as f64 + py / height, zoom),
+                )
+                .to_pt(gps_bounds)
+            },
+            &mut batch,
+        );
+    }
+    batch
+}
+
+/// Use the cached copy of a tile if it exists. Otherwise download and cache it.
+async fn fetch_tile(template: &str, zoom: u32, x: u32, y: u32) -> Result<Vec<u8>> {
+    let cache_path = abstio::path(format!(
+        "input/shared/imagery/{}/{}/{}/{}.png",
+        cache_name(template),
+        zoom,
+        x,
+        y
+    ));
+    if abstio::file_exists(&cache_path) {
+        return abstio::slurp_file(&cache_path);
+    }
+
+    let url = template
+        .replace("{z}", &zoom.to_string())
+        .replace("{x}", &x.to_string())
+        .replace("{y}", &y.to_string());
+    let bytes = abstio::http_get(&url).await?;
+    // Caching is just an optimization
+    if let Err(err) = std::fs::create_dir_all(std::path::Path::new(&cache_path).parent().unwrap())
+        .and_then(|_| std::fs::write(&cache_path, &bytes))
+    {
+        warn!("Couldn't cache {}: {}", cache_path, err);
+    }
+    Ok(bytes)
+}
+
+/// Tiles from different servers shouldn't mix, so cache them by the server's host.
+fn cache_name(template: &str) -> String {
+    let host = template
+        .split("//")
+        .nth(1)
+        .and_then(|rest| rest.split('/').next())
+        .unwrap_or(template);
+    host.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+fn load_png(ctx: &mut EventCtx, bounds: GPSBounds) -> Box<dyn State<App>> {
+    FilePicker::new_state(
+        ctx,
+        None,
+        Box::new(move |ctx, app: &mut App, maybe_path| {
+            let path = match maybe_path {
+                Ok(Some(path)) => path,
+                // The user didn't pick a file
+                _ => {
+                    return Transition::Pop;
+                }
+            };
+            let result = abstio::slurp_file(&path).and_then(|bytes| {
+                let img = image::load_from_memory(&bytes)?.to_rgba8();
+                let (width, height) = (img.width() as f64, img.height() as f64);
+                let gps_bounds = &app.model.map.gps_bounds;
+                let mut batch = GeomBatch::new();
+                // Assume the image isn't projected. Over the area of one map, this is close enough
+                // to line things up by eye.
+                image_to_batch(
+                    &img,
+                    TOTAL_CELLS_PER_SIDE as u32,
+                    |px, py| {
+                        LonLat::new(
+                            bounds.min_lon + px / width * (bounds.max_lon - bounds.min_lon),
+                            bounds.max_lat - py / height * (bounds.max_lat - bounds.min_lat),
+                        )
+                        .to_pt(gps_bounds)
+                    },
+                    &mut batch,
+                );
+                Ok(batch)
+            });
+            match result {
+                Ok(batch) => {
+                    app.background = Some(Background::new(ctx, path, batch, 50));
+                    Transition::Pop
+                }
+                Err(err) => Transition::Replace(PopupMsg::new_state(
+                    ctx,
+                    "Error",
+                    vec![format!("Couldn't load {}: {}", path, err)],
+                )),
+            }
+        }),
+    )
+}
+
+/// Like "-122.3, 47.6, -122.2, 47.7"
+fn parse_bounds(input: &str) -> Result<GPSBounds> {
+    let nums = input
+        .split(',')
+        .map(|x| x.trim().parse::<f64>())
+        .collect::<Result<Vec<_>, _>>()?;
+    if nums.len() != 4 {
+        bail!("Bounds need 4 numbers, not {}", nums.len());
+    }
+    if nums[0] >= nums[2] || nums[1] >= nums[3] {
+        bail!("The minimum longitude and latitude must be less than the maximum");
+    }
+    let mut bounds = GPSBounds::new();
+    bounds.update(LonLat::new(nums[0], nums[1]));
+    bounds.update(LonLat::new(nums[2], nums[3]));
+    Ok(bounds)
+}
+
+/// Turn an image into rectangles of roughly uniform color, with at most `cells_per_side` along
+/// each side. `to_pt` transforms fractional pixel coordinates into map-space, and must preserve
+/// the axes.
+fn image_to_batch<F: Fn(f64, f64) -> Pt2D>(
+    img: &RgbaImage,
+    cells_per_side: u32,
+    to_pt: F,
+    batch: &mut GeomBatch,
+) {
+    let (width, height) = (img.width(), img.height());
+    let cells_per_side = cells_per_side.max(1);
+    let step = ((width.max(height) + cells_per_side - 1) / cells_per_side).max(1);
+
+    let mut push_cell = |x1: u32, x2: u32, y1: u32, y2: u32, color: Option<[u8; 3]>| {
+        if let Some([r, g, b]) = color {
+            if let Some(rect) = Polygon::rectangle_two_corners(
+                to_pt(x1 as f64, y1 as f64),
+                to_pt(x2 as f64, y2 as f64),
+            ) {
+                batch.push(Color::rgb(r as usize, g as usize, b as usize), rect);
+            }
+        }
+    };
+
+    for y1 in (0..height).step_by(step as usize) {
+        let y2 = (y1 + step).min(height);
+        // Merge neighboring cells with the same color into one rectangle
+        let mut run: Option<(u32, Option<[u8; 3]>)> = None;
+        for x1 in (0..width).step_by(step as usize) {
+            let [r, g, b, a] = img
+                .get_pixel(
+                    (x1 + step / 2).min(width - 1),
+                    (y1 + step / 2).min(height - 1),
+                )
+                .0;
+            // Mostly transparent cells aren't drawn. Drop the low bits of each channel, so
+            // similar colors merge.
+            let color = if a < 128 {
+                None
+            } else {
+                Some([r & 0xF8, g & 0xF8, b & 0xF8])
+            };
+            match run {
+                Some((_, run_color)) if run_color == color => {}
+                Some((start, run_color)) => {
+                    push_cell(start, x1, y1, y2, run_color);
+                    run = Some((x1, color));
+                }
+                None => {
+                    run = Some((x1, color));
+                }
+            }
+        }
+        if let Some((start, run_color)) = run {
+            push_cell(start, width, y1, y2, run_color);
+        }
+    }
+}
+
+// Slippy map tile math, from https://wiki.openstreetmap.org/wiki/Slippy_map_tilenames
+
+fn num_tiles(zoom: u32) -> f64 {
+    2.0_f64.powi(zoom as i32)
+}
+
+fn lon_to_tile_x(lon: f64, zoom: u32) -> f64 {
+    (lon + 180.0) / 360.0 * num_tiles(zoom)
+}
+
+fn lat_to_tile_y(lat: f64, zoom: u32) -> f64 {
+    let lat = lat.to_radians();
+    (1.0 - (lat.tan() + 1.0 / lat.cos()).ln() / PI) / 2.0 * num_tiles(zoom)
+}
+
+fn tile_x_to_lon(x: f64, zoom: u32) -> f64 {
+    x / num_tiles(zoom) * 360.0 - 180.0
+}
+
+fn tile_y_to_lat(y: f64, zoom: u32) -> f64 {
+    (PI * (1.0 - 2.0 * y / num_tiles(zoom)))
+        .sinh()
+        .atan()
+        .to_degrees()
+}
+
+fn tiles_covering(bounds: &GPSBounds, zoom: u32) -> Vec<(u32, u32)> {
+    let x1 = lon_to_tile_x(bounds.min_lon, zoom).floor() as u32;
+    let x2 = lon_to_tile_x(bounds.max_lon, zoom).floor() as u32;
+    // Tile y increases going south
+    let y1 = lat_to_tile_y(bounds.max_lat, zoom).floor() as u32;
+    let y2 = lat_to_tile_y(bounds.min_lat, zoom).floor() as u32;
+    let mut tiles = Vec::new();
+    for x in x1..=x2 {
+        for y in y1..=y2 {
+            tiles.push((x, y));
+        }
+    }
+    tiles
+}
+
+/// The most detailed zoom level that covers the map with a reasonable number of tiles
+fn default_zoom(bounds: &GPSBounds) -> usize {
+    (1..=19)
+        .rev()
+        .find(|zoom| tiles_covering(bounds, *zoom).len() <= DEFAULT_NUM_TILES)
+        .unwrap_or(1) as usize
+}
@@ -0,0 +1,447 @@
+use abstutil::Tags;
+use geom::{Circle, Distance, Polygon, Pt2D, Ring};
+use map_model::{osm, Amenity, AmenityType, NamePerLanguage};
+use widgetry::{
+    Color, DrawBaselayer, EventCtx, GfxCtx, HorizontalAlignment, Key, Line, Outcome, Panel, State,
+    Text, TextBox, TextExt, Toggle, Transition, VerticalAlignment, Widget,
+};
+
+use crate::App;
+
+const HANDLE_RADIUS: Distance = Distance::const_meters(1.5);
+
+/// Reshape a building by dragging the corners of its footprint, and edit the amenities inside of
+/// it. Amenities are what the 15-minute tool and scenario generation treat as destinations.
+pub struct EditBuilding {
+    id: osm::OsmID,
+    panel: Panel,
+    // The corners of the outer ring, without repeating the first point
+    pts: Vec<Pt2D>,
+    // Restored if the edits are cancelled
+    orig_polygon: Polygon,
+    amenities: Vec<Amenity>,
+    hovering: Option<usize>,
+    dragging: bool,
+}
+
+impl EditBuilding {
+    pub(crate) fn new_state(ctx: &mut EventCtx, app: &App, id: osm::OsmID) -> Box<dyn State<App>> {
+        let b = &app.model.map.buildings[&id];
+        let pts = match b.polygon.get_outer_ring() {
+            Some(ring) => {
+                let mut pts = ring.into_points();
+                pts.pop();
+                pts
+            }
+            None => {
+                warn!(
+                    "{} doesn't have a simple outer ring, so it can't be reshaped",
+                    id
+                );
+                Vec::new()
+            }
+        };
+        let mut state = EditBuilding {
+            id,
+            panel: Panel::empty(ctx),
+            pts,
+            orig_polygon: b.polygon.clone(),
+            amenities: b.amenities.clone(),
+            hovering: None,
+            dragging: false,
+        };
+        state.panel = state.make_panel(ctx);
+        Box::new(state)
+    }
+
+    fn make_panel(&self, ctx: &mut EventCtx) -> Panel {
+        let mut txt = Text::new();
+        txt.add_appended(vec![
+            Line("- Click and drag").fg(ctx.style().text_hotkey_color),
+            Line(" a corner to reshape"),
+        ]);
+        txt.add_appended(vec![
+            Line("- Press "),
+            Key::Backspace.txt(ctx),
+            Line(" on a corner to remove it"),
+        ]);
+
+        let mut amenities = vec![Line("Amenities").small_heading().into_widget(ctx)];
+        if self.amenities.is_empty() {
+            amenities.push("None yet".text_widget(ctx));
+        }
+        for (idx, amenity) in self.amenities.iter().enumerate() {
+            amenities.push(Widget::row(vec![
+                TextBox::widget(
+                    ctx,
+                    format!("amenity type {}", idx),
+                    amenity.amenity_type.clone(),
+                    false,
+                    20,
+                ),
+                TextBox::widget(
+                    ctx,
+                    format!("amenity name {}", idx),
+                    amenity_name(amenity),
+                    false,
+                    30,
+                ),
+                category(ctx, idx, &amenity.amenity_type),
+                ctx.style()
+                    .btn_solid_destructive
+                    .icon("system/assets/tools/trash.svg")
+                    .build_widget(ctx, format!("delete amenity {}", idx)),
+            ]));
+        }
+        amenities.push(ctx.style().btn_outline.text("add amenity").build_def(ctx));
+
+        Panel::new_builder(Widget::col(vec![
+            Widget::row(vec![
+                Line("Editing building").small_heading().into_widget(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            Line(self.id.to_string()).secondary().into_widget(ctx),
+            txt.into_widget(ctx),
+            Widget::col(amenities).section(ctx),
+            ctx.style()
+                .btn_solid_primary
+                .text("Apply")
+                .hotkey(Key::Enter)
+                .build_def(ctx),
+        ]))
+        .aligned(HorizontalAlignment::Left, VerticalAlignment::Top)
+        .build(ctx)
+    }
+
+    /// Copy whatever's been typed into the panel back to the amenities.
+    fn sync_amenities(&mut self) {
+        for (idx, amenity) in self.amenities.iter_mut().enumerate() {
+            amenity.amenity_type = self.panel.text_box(&format!("amenity type {}", idx));
+            let name = self.panel.text_box(&format!("amenity name {}", idx));
+            if name != amenity_name(amenity) {
+                if name.is_empty() {
+                    amenity.osm_tags.remove(osm::NAME);
+                } else {
+                    amenity.osm_tags.insert(osm::NAME, name);
+                }
+                amenity.names = NamePerLanguage::new(&amenity.osm_tags)
+                    .unwrap_or_else(NamePerLanguage::unnamed);
+            }
+        }
+    }
+
+    fn polygon(&self) -> Option<Polygon> {
+        let mut pts = self.pts.clone();
+        pts.push(*pts.get(0)?);
+        Ring::new(pts).ok().map(|ring| ring.into_polygon())
+    }
+}
+
+impl State<App> for EditBuilding {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition<App> {
+        if self.dragging {
+            if let Some(idx) = self.hovering {
+                if let Some(pt) = ctx.canvas.get_cursor_in_map_space() {
+                    let old_pt = self.pts[idx];
+                    self.pts[idx] = pt;
+                    // Don't let a corner land on top of another one
+                    match self.polygon() {
+                        Some(polygon) => {
+                            app.model.set_b_polygon(ctx, self.id, polygon);
+                        }
+                        None => {
+                            self.pts[idx] = old_pt;
+                        }
+                    }
+                }
+            }
+            if ctx.input.left_mouse_button_released() {
+                self.dragging = false;
+            }
+            if let Some((_, dy)) = ctx.input.get_mouse_scroll() {
+                ctx.canvas.zoom(dy, ctx.canvas.get_cursor());
+            }
+            return Transition::Keep;
+        }
+
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => {
+                    app.model
+                        .set_b_polygon(ctx, self.id, self.orig_polygon.clone());
+                    return Transition::Pop;
+                }
+                "add amenity" => {
+                    self.sync_amenities();
+                    self.amenities.push(Amenity {
+                        names: NamePerLanguage::unnamed(),
+                        amenity_type: String::new(),
+                        osm_tags: Tags::empty(),
+                    });
+                    self.panel = self.make_panel(ctx);
+                }
+                "Apply" => {
+                    self.sync_amenities();
+                    let amenities = std::mem::take(&mut self.amenities)
+                        .into_iter()
+                        .filter(|a| !a.amenity_type.is_empty())
+                        .collect();
+                    app.model.set_b_amenities(ctx, self.id, amenities);
+                    return Transition::Pop;
+                }
+                x => {
+                    if let Some(idx) = x.strip_prefix("delete amenity ") {
+                        self.sync_amenities();
+                        self.amenities.remove(idx.parse::<usize>().unwrap());
+                        self.panel = self.make_panel(ctx);
+                    } else {
+                        unreachable!()
+                    }
+                }
+            },
+            Outcome::Changed(x) => {
+                if let Some(idx) = x.strip_prefix("amenity type ") {
+                    let idx = idx.parse::<usize>().unwrap();
+                    let label = category(ctx, idx, &self.panel.text_box(&x));
+                    self.panel.replace(ctx, &format!("category {}", idx), label);
+                }
+            }
+            _ => {}
+        }
+
+        if ctx.redo_mouseover() {
+            self.hovering = ctx.canvas.get_cursor_in_map_space().and_then(|cursor| {
+                self.pts
+                    .iter()
+                    .position(|pt| pt.dist_to(cursor) <= HANDLE_RADIUS)
+            });
+        }
+
+        if let Some(idx) = self.hovering {
+            if ctx.input.left_mouse_button_pressed() {
+                self.dragging = true;
+            } else if self.pts.len() > 3 && ctx.input.pressed(Key::Backspace) {
+                self.pts.remove(idx);
+                self.hovering = None;
+                if let Some(polygon) = self.polygon() {
+                    app.model.set_b_polygon(ctx, self.id, polygon);
+                }
+            }
+        } else {
+            ctx.canvas_movement();
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        for (idx, pt) in self.pts.iter().enumerate() {
+            let color = if self.hovering == Some(idx) {
+                Color::YELLOW
+            } else {
+                Color::GREEN
+            };
+            g.draw_polygon(color, Circle::new(*pt, HANDLE_RADIUS).to_polygon());
+        }
+        self.panel.draw(g);
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::PreviousState
+    }
+}
+
+/// Blank for unnamed amenities, so the text box doesn't start with a placeholder
+fn amenity_name(amenity: &Amenity) -> String {
+    if amenity.names == NamePerLanguage::unnamed() {
+        String::new()
+    } else {
+        amenity.names.get(None).clone()
+    }
+}
+
+/// Show how the 15-minute tool will categorize an OSM amenity type
+fn category(ctx: &EventCtx, idx: usize, amenity_type: &str) -> Widget {
+    match AmenityType::categorize(amenity_type) {
+        Some(at) => Line(at.to_string()),
+        None => Line("uncategorized").secondary(),
+    }
+    .into_widget(ctx)
+    .centered_vert()
+    .named(format!("category {}", idx))
+}
+
+/// Trace the footprint of a new building, either by dragging out a rectangle or by clicking each
+/// corner of a polygon.
+pub struct DrawBuilding {
+    panel: Panel,
+    pts: Vec<Pt2D>,
+    // While dragging out a rectangle, the first corner
+    rectangle_start: Option<Pt2D>,
+}
+
+impl DrawBuilding {
+    pub(crate) fn new_state(ctx: &mut EventCtx) -> Box<dyn State<App>> {
+        Box::new(DrawBuilding {
+            panel: DrawBuilding::make_panel(ctx, true, false),
+            pts: Vec::new(),
+            rectangle_start: None,
+        })
+    }
+
+    fn make_panel(ctx: &mut EventCtx, rectangle: bool, can_finish: bool) -> Panel {
+        let mut txt = Text::new();
+        if rectangle {
+            txt.add_appended(vec![
+                Line("Click and drag").fg(ctx.style().text_hotkey_color),
+                Line(" to draw a rectangle"),
+            ]);
+        } else {
+            txt.add_appended(vec![
+                Line("Click").fg(ctx.style().text_hotkey_color),
+                Line(" to add a corner"),
+            ]);
+            txt.add_appended(vec![
+                Line("- Press "),
+                Key::Backspace.txt(ctx),
+                Line(" to remove the last corner"),
+            ]);
+        }
+
+        Panel::new_builder(Widget::col(vec![
+            Widget::row(vec![
+                Line("Draw a building").small_heading().into_widget(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            Toggle::choice(ctx, "shape", "rectangle", "polygon", Key::L, rectangle),
+            txt.into_widget(ctx),
+            if rectangle {
+                Widget::nothing()
+            } else {
+                ctx.style()
+                    .btn_solid_primary
+                    .text("Finish")
+                    .hotkey(Key::Enter)
+                    .disabled(!can_finish)
+                    .build_def(ctx)
+            },
+        ]))
+        .aligned(HorizontalAlignment::Left, VerticalAlignment::Top)
+        .build(ctx)
+    }
+
+    fn polygon(&self) -> Option<Polygon> {
+        let mut pts = self.pts.clone();
+        pts.push(*pts.get(0)?);
+        Ring::new(pts).ok().map(|ring| ring.into_polygon())
+    }
+
+    fn finish(&self, ctx: &mut EventCtx, app: &mut App, polygon: Polygon) -> Transition<App> {
+        let id = app.model.create_b_from_polygon(ctx, polygon);
+        app.model.world.force_set_selection(id);
+        Transition::Pop
+    }
+}
+
+impl State<App> for DrawBuilding {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition<App> {
+        let mut cursor = ctx.canvas.get_cursor_in_map_space();
+        // Negative coordinates break the quadtree in World
+        if let Some(pt) = cursor {
+            if pt.x() < 0.0 || pt.y() < 0.0 {
+                cursor = None;
+            }
+        }
+
+        if let Some(start) = self.rectangle_start {
+            if ctx.input.left_mouse_button_released() {
+                self.rectangle_start = None;
+                if let Some(polygon) =
+                    cursor.and_then(|pt| Polygon::rectangle_two_corners(start, pt))
+                {
+                    return self.finish(ctx, app, polygon);
+                }
+            }
+            return Transition::Keep;
+        }
+
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "Finish" => {
+                    let polygon = self.polygon().unwrap();
+                    return self.finish(ctx, app, polygon);
+                }
+                _ => unreachable!(),
+            },
+            Outcome::Changed(_) => {
+                self.pts.clear();
+                self.panel = DrawBuilding::make_panel(ctx, self.panel.is_checked("shape"), false);
+                return Transition::Keep;
+            }
+            _ => {}
+        }
+
+        if self.panel.is_checked("shape") {
+            if let Some((_, dy)) = ctx.input.get_mouse_scroll() {
+                ctx.canvas.zoom(dy, ctx.canvas.get_cursor());
+            }
+            if ctx.input.left_mouse_button_pressed() {
+                self.rectangle_start = cursor;
+            }
+            return Transition::Keep;
+        }
+
+        ctx.canvas_movement();
+        let num_pts = self.pts.len();
+        if ctx.input.pressed(Key::Backspace) {
+            self.pts.pop();
+        } else if ctx.normal_left_click() {
+            if let Some(pt) = cursor {
+                self.pts.push(pt);
+            }
+        }
+        if self.pts.len() != num_pts {
+            let can_finish = self.polygon().is_some();
+            self.panel = DrawBuilding::make_panel(ctx, false, can_finish);
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        let cursor = g.get_cursor_in_map_space();
+        if let (Some(start), Some(cursor)) = (self.rectangle_start, cursor) {
+            if let Some(rect) = Polygon::rectangle_two_corners(start, cursor) {
+                g.draw_polygon(Color::BLUE.alpha(0.5), rect);
+            }
+        }
+
+        for pt in &self.pts {
+            g.draw_polygon(Color::GREEN, Circle::new(*pt, HANDLE_RADIUS).to_polygon());
+        }
+        let mut pts = self.pts.clone();
+        if let Some(cursor) = cursor {
+            pts.push(cursor);
+        }
+        if let Some(first) = self.pts.get(0) {
+            pts.push(*first);
+        }
+        for pair in pts.windows(2) {
+            if let Some(l) = geom::Line::new(pair[0], pair[1]) {
+                g.draw_polygon(
+                    Color::GREEN.alpha(0.8),
+                    l.make_polygons(Distance::meters(0.5)),
+                );
+            }
+        }
+
+        self.panel.draw(g);
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::PreviousState
+    }
+}
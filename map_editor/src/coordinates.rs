@@ -0,0 +1,154 @@
+use anyhow::{bail, Result};
+
+use geom::{Distance, LonLat, Pt2D};
+use map_gui::tools::PopupMsg;
+use widgetry::{
+    DrawBaselayer, EventCtx, GfxCtx, Key, Line, Outcome, Panel, State, TextBox, TextExt,
+    Transition, Widget,
+};
+
+use crate::app::App;
+use crate::model::ID;
+
+/// Where an intersection or road point currently is
+pub fn get_pt(app: &App, id: ID) -> Pt2D {
+    match id {
+        ID::Intersection(i) => app.model.map.intersections[&i].point,
+        ID::RoadPoint(r, idx) => app.model.map.roads[&r].center_points[idx],
+        _ => unreachable!(),
+    }
+}
+
+/// Move an intersection or road point, keeping it selected.
+pub fn move_to(ctx: &EventCtx, app: &mut App, id: ID, pt: Pt2D) {
+    match id {
+        ID::Intersection(i) => app.model.move_i(ctx, i, pt),
+        ID::RoadPoint(r, idx) => app.model.move_r_pt(ctx, r, idx, pt),
+        _ => unreachable!(),
+    }
+    app.model.world.force_set_selection(id);
+}
+
+/// If an arrow key is pressed, shift an intersection or road point by some distance. Returns true
+/// if it moved.
+pub fn nudge(ctx: &mut EventCtx, app: &mut App, id: ID, dist: Distance) -> bool {
+    let (dx, dy) = if ctx.input.pressed(Key::LeftArrow) {
+        (-1.0, 0.0)
+    } else if ctx.input.pressed(Key::RightArrow) {
+        (1.0, 0.0)
+    } else if ctx.input.pressed(Key::UpArrow) {
+        (0.0, -1.0)
+    } else if ctx.input.pressed(Key::DownArrow) {
+        (0.0, 1.0)
+    } else {
+        return false;
+    };
+    let pt = get_pt(app, id).offset(dx * dist.inner_meters(), dy * dist.inner_meters());
+    // Don't push things off the map
+    if pt.x() < 0.0 || pt.y() < 0.0 {
+        return false;
+    }
+    move_to(ctx, app, id, pt);
+    true
+}
+
+/// Type in the exact position of an intersection or road point, either in map-space meters or
+/// longitude and latitude.
+pub struct SetCoordinates {
+    panel: Panel,
+    id: ID,
+}
+
+impl SetCoordinates {
+    pub fn new_state(ctx: &mut EventCtx, app: &App, id: ID) -> Box<dyn State<App>> {
+        let pt = get_pt(app, id);
+        let gps = pt.to_gps(&app.model.map.gps_bounds);
+        Box::new(SetCoordinates {
+            panel: Panel::new_builder(Widget::col(vec![
+                Widget::row(vec![
+                    Line("Set the exact position")
+                        .small_heading()
+                        .into_widget(ctx),
+                    ctx.style().btn_close_widget(ctx),
+                ]),
+                Widget::row(vec![
+                    "x (m)".text_widget(ctx).centered_vert(),
+                    TextBox::default_widget(ctx, "x", pt.x().to_string()),
+                    "y (m)".text_widget(ctx).centered_vert(),
+                    TextBox::default_widget(ctx, "y", pt.y().to_string()),
+                    ctx.style()
+                        .btn_outline
+                        .text("move to x/y")
+                        .hotkey(Key::Enter)
+                        .build_def(ctx),
+                ]),
+                Widget::row(vec![
+                    "longitude".text_widget(ctx).centered_vert(),
+                    TextBox::default_widget(ctx, "longitude", gps.x().to_string()),
+                    "latitude".text_widget(ctx).centered_vert(),
+                    TextBox::default_widget(ctx, "latitude", gps.y().to_string()),
+                    ctx.style()
+                        .btn_outline
+                        .text("move to longitude/latitude")
+                        .build_def(ctx),
+                ]),
+            ]))
+            .build(ctx),
+            id,
+        })
+    }
+
+    fn parse(&self, app: &App, use_gps: bool) -> Result<Pt2D> {
+        let pt = if use_gps {
+            let lon = self.panel.text_box("longitude").trim().parse::<f64>()?;
+            let lat = self.panel.text_box("latitude").trim().parse::<f64>()?;
+            LonLat::new(lon, lat).to_pt(&app.model.map.gps_bounds)
+        } else {
+            Pt2D::new(
+                self.panel.text_box("x").trim().parse::<f64>()?,
+                self.panel.text_box("y").trim().parse::<f64>()?,
+            )
+        };
+        // Negative coordinates break the quadtree in World
+        if pt.x() < 0.0 || pt.y() < 0.0 {
+            bail!("{} is outside the map", pt);
+        }
+        Ok(pt)
+    }
+}
+
+impl State<App> for SetCoordinates {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition<App> {
+        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+            match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "move to x/y" | "move to longitude/latitude" => {
+                    return match self.parse(app, x == "move to longitude/latitude") {
+                        Ok(pt) => {
+                            move_to(ctx, app, self.id, pt);
+                            Transition::Pop
+                        }
+                        Err(err) => Transition::Push(PopupMsg::new_state(
+                            ctx,
+                            "Error",
+                            vec![format!("Couldn't move there: {}", err)],
+                        )),
+                    };
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        Transition::Keep
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::PreviousState
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.panel.draw(g);
+    }
+}
@@ -0,0 +1,439 @@
+//! Compare the RawMap being edited against the version saved on disk, which is usually the fresh
+//! import from OSM. This is useful to see what's been changed while debugging a broken import.
+
+use std::collections::HashMap;
+
+use abstutil::Tags;
+use geom::{Circle, Distance, Percent, Pt2D};
+use map_gui::load::FileLoader;
+use map_gui::tools::PopupMsg;
+use map_model::osm;
+use map_model::raw::{OriginalRoad, RawMap, RawRoad};
+use widgetry::{
+    Color, ControlState, DrawBaselayer, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment,
+    Line, Outcome, Panel, State, Text, TextExt, Transition, VerticalAlignment, Widget,
+};
+
+use crate::app::App;
+
+const ADDED: Color = Color::GREEN;
+const DELETED: Color = Color::RED;
+const MODIFIED: Color = Color::YELLOW;
+
+pub enum Change {
+    Added,
+    Deleted,
+    /// A description of each thing that changed
+    Modified(Vec<String>),
+}
+
+impl Change {
+    fn color(&self) -> Color {
+        match self {
+            Change::Added => ADDED,
+            Change::Deleted => DELETED,
+            Change::Modified(_) => MODIFIED,
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Change::Added => "added".to_string(),
+            Change::Deleted => "deleted".to_string(),
+            Change::Modified(changes) => format!("modified: {}", changes.join(", ")),
+        }
+    }
+}
+
+pub struct RawMapDiff {
+    pub roads: Vec<(OriginalRoad, Change)>,
+    pub intersections: Vec<(osm::NodeID, Change)>,
+}
+
+impl RawMapDiff {
+    /// Find everything that differs between the original and current version of a map.
+    pub fn new(original: &RawMap, current: &RawMap) -> RawMapDiff {
+        let mut roads = Vec::new();
+        for (id, road) in &current.roads {
+            match original.roads.get(id) {
+                Some(orig) => {
+                    if orig != road {
+                        roads.push((*id, Change::Modified(diff_road(orig, road))));
+                    }
+                }
+                None => {
+                    roads.push((*id, Change::Added));
+                }
+            }
+        }
+        for id in original.roads.keys() {
+            if !current.roads.contains_key(id) {
+                roads.push((*id, Change::Deleted));
+            }
+        }
+
+        let mut intersections = Vec::new();
+        for (id, i) in &current.intersections {
+            match original.intersections.get(id) {
+                Some(orig) => {
+                    let mut changes = Vec::new();
+                    if orig.point != i.point {
+                        changes.push(format!("moved {}", orig.point.dist_to(i.point)));
+                    }
+                    if orig.intersection_type != i.intersection_type {
+                        changes.push(format!(
+                            "{:?} -> {:?}",
+                            orig.intersection_type, i.intersection_type
+                        ));
+                    }
+                    if orig.stop_sign_placement != i.stop_sign_placement {
+                        changes.push(format!(
+                            "{} -> {}",
+                            orig.stop_sign_placement.describe(),
+                            i.stop_sign_placement.describe()
+                        ));
+                    }
+                    if orig.elevation != i.elevation {
+                        changes.push(format!("elevation {} -> {}", orig.elevation, i.elevation));
+                    }
+                    if orig.trim_roads_for_merging != i.trim_roads_for_merging {
+                        changes.push("merged roads".to_string());
+                    }
+                    if !changes.is_empty() {
+                        intersections.push((*id, Change::Modified(changes)));
+                    }
+                }
+                None => {
+                    intersections.push((*id, Change::Added));
+                }
+            }
+        }
+        for id in original.intersections.keys() {
+            if !current.intersections.contains_key(id) {
+                intersections.push((*id, Change::Deleted));
+            }
+        }
+
+        RawMapDiff {
+            roads,
+            intersections,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.roads.is_empty() && self.intersections.is_empty()
+    }
+}
+
+fn diff_road(orig: &RawRoad, current: &RawRoad) -> Vec<String> {
+    let mut changes = Vec::new();
+    if orig.center_points != current.center_points {
+        changes.push("center line".to_string());
+    }
+    changes.extend(diff_tags(&orig.osm_tags, &current.osm_tags));
+    if orig.turn_restrictions != current.turn_restrictions
+        || orig.complicated_turn_restrictions != current.complicated_turn_restrictions
+    {
+        changes.push("turn restrictions".to_string());
+    }
+    if orig.percent_incline != current.percent_incline {
+        changes.push("incline".to_string());
+    }
+    if orig.crosswalk_forward != current.crosswalk_forward
+        || orig.crosswalk_backward != current.crosswalk_backward
+    {
+        changes.push("crosswalks".to_string());
+    }
+    changes
+}
+
+fn diff_tags(orig: &Tags, current: &Tags) -> Vec<String> {
+    let mut changes = Vec::new();
+    for (k, v) in current.inner() {
+        match orig.get(k) {
+            Some(old) => {
+                if old != v {
+                    changes.push(format!("{} = {} -> {}", k, old, v));
+                }
+            }
+            None => {
+                changes.push(format!("+ {} = {}", k, v));
+            }
+        }
+    }
+    for (k, v) in orig.inner() {
+        if !current.contains_key(k) {
+            changes.push(format!("- {} = {}", k, v));
+        }
+    }
+    changes
+}
+
+pub struct DiffViewer {
+    panel: Panel,
+    draw: Drawable,
+    // Clicking a change in the list centers the camera here
+    locations: HashMap<String, Pt2D>,
+}
+
+impl DiffViewer {
+    /// Load the version of the current map saved on disk, then show the differences.
+    pub fn load(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        FileLoader::<App, RawMap>::new_state(
+            ctx,
+            abstio::path_raw_map(&app.model.map.name),
+            Box::new(|ctx, app, _, original| match original {
+                Ok(original) => Transition::Replace(DiffViewer::new_state(ctx, app, original)),
+                Err(err) => Transition::Replace(PopupMsg::new_state(
+                    ctx,
+                    "Error",
+                    vec![format!("Couldn't load the original map: {}", err)],
+                )),
+            }),
+        )
+    }
+
+    fn new_state(ctx: &mut EventCtx, app: &App, original: RawMap) -> Box<dyn State<App>> {
+        let current = &app.model.map;
+        let diff = RawMapDiff::new(&original, current);
+
+        let mut batch = GeomBatch::new();
+        let mut locations = HashMap::new();
+        let mut col = vec![
+            Widget::row(vec![
+                Line("Changes from the original map")
+                    .small_heading()
+                    .into_widget(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            Text::from_all(vec![
+                Line("added").fg(ADDED),
+                Line(" / "),
+                Line("deleted").fg(DELETED),
+                Line(" / "),
+                Line("modified").fg(MODIFIED),
+            ])
+            .into_widget(ctx),
+            format!(
+                "{} roads and {} intersections changed",
+                diff.roads.len(),
+                diff.intersections.len()
+            )
+            .text_widget(ctx),
+        ];
+        if diff.is_empty() {
+            col.push("Nothing has changed".text_widget(ctx));
+        }
+
+        for (id, change) in &diff.roads {
+            // Deleted roads only exist in the original
+            let (map, road) = if let Change::Deleted = change {
+                (&original, &original.roads[id])
+            } else {
+                (current, &current.roads[id])
+            };
+            let (pl, width) = match road.get_geometry(*id, &map.config) {
+                Ok(pair) => pair,
+                Err(err) => {
+                    warn!("No geometry for {}: {}", id, err);
+                    continue;
+                }
+            };
+            batch.push(change.color().alpha(0.8), pl.make_polygons(width));
+
+            let label = format!("{}: {}", id, change.describe());
+            col.push(
+                ctx.style()
+                    .btn_plain
+                    .text(&label)
+                    .label_color(change.color(), ControlState::Default)
+                    .build_widget(ctx, &label),
+            );
+            locations.insert(label, pl.middle());
+        }
+
+        for (id, change) in &diff.intersections {
+            let pt = if let Change::Deleted = change {
+                original.intersections[id].point
+            } else {
+                current.intersections[id].point
+            };
+            batch.push(
+                change.color().alpha(0.8),
+                Circle::new(pt, Distance::meters(5.0)).to_polygon(),
+            );
+
+            let label = format!("{}: {}", id, change.describe());
+            col.push(
+                ctx.style()
+                    .btn_plain
+                    .text(&label)
+                    .label_color(change.color(), ControlState::Default)
+                    .build_widget(ctx, &label),
+            );
+            locations.insert(label, pt);
+        }
+
+        Box::new(DiffViewer {
+            panel: Panel::new_builder(Widget::col(col))
+                .aligned(HorizontalAlignment::Left, VerticalAlignment::Top)
+                .max_size(Percent::int(40), Percent::int(80))
+                .build(ctx),
+            draw: ctx.upload(batch),
+            locations,
+        })
+    }
+}
+
+impl State<App> for DiffViewer {
+    fn event(&mut self, ctx: &mut EventCtx, _: &mut App) -> Transition<App> {
+        ctx.canvas_movement();
+
+        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+            if x == "close" {
+                return Transition::Pop;
+            }
+            ctx.canvas.center_on_map_pt(self.locations[&x]);
+        }
+
+        Transition::Keep
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::PreviousState
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        g.redraw(&self.draw);
+        self.panel.draw(g);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use abstio::MapName;
+    use map_model::raw::RawIntersection;
+    use map_model::{IntersectionType, StopSignPlacement};
+
+    use super::*;
+
+    fn intersection(x: f64, y: f64) -> RawIntersection {
+        RawIntersection {
+            point: Pt2D::new(x, y),
+            intersection_type: IntersectionType::StopSign,
+            stop_sign_placement: StopSignPlacement::Automatic,
+            elevation: Distance::ZERO,
+            trim_roads_for_merging: BTreeMap::new(),
+            midblock_crossing: false,
+        }
+    }
+
+    fn road(pts: Vec<Pt2D>) -> RawRoad {
+        let mut osm_tags = Tags::empty();
+        osm_tags.insert(osm::HIGHWAY, "residential");
+        RawRoad {
+            center_points: pts,
+            osm_tags,
+            turn_restrictions: Vec::new(),
+            complicated_turn_restrictions: Vec::new(),
+            percent_incline: 0.0,
+            crosswalk_forward: false,
+            crosswalk_backward: false,
+        }
+    }
+
+    // Three intersections in a row, connected by two roads
+    fn original() -> RawMap {
+        let mut map = RawMap::blank(MapName::new("zz", "test", "diff"));
+        for idx in 0..3 {
+            map.intersections
+                .insert(osm::NodeID(idx), intersection(100.0 * idx as f64, 0.0));
+        }
+        for idx in 0..2 {
+            map.roads.insert(
+                OriginalRoad::new(idx, (idx, idx + 1)),
+                road(vec![
+                    Pt2D::new(100.0 * idx as f64, 0.0),
+                    Pt2D::new(100.0 * (idx + 1) as f64, 0.0),
+                ]),
+            );
+        }
+        map
+    }
+
+    #[test]
+    fn test_no_changes() {
+        assert!(RawMapDiff::new(&original(), &original()).is_empty());
+    }
+
+    #[test]
+    fn test_changes() {
+        let orig = original();
+        let mut current = original();
+
+        // Delete the second road and its far intersection
+        current.roads.remove(&OriginalRoad::new(1, (1, 2)));
+        current.intersections.remove(&osm::NodeID(2));
+        // Add a new road branching off the middle
+        current
+            .intersections
+            .insert(osm::NodeID(3), intersection(100.0, 100.0));
+        current.roads.insert(
+            OriginalRoad::new(2, (1, 3)),
+            road(vec![Pt2D::new(100.0, 0.0), Pt2D::new(100.0, 100.0)]),
+        );
+        // Retag the first road and make the middle an all-way stop
+        let r = current
+            .roads
+            .get_mut(&OriginalRoad::new(0, (0, 1)))
+            .unwrap();
+        r.osm_tags.insert("lanes", "2");
+        r.osm_tags.insert(osm::HIGHWAY, "tertiary");
+        current
+            .intersections
+            .get_mut(&osm::NodeID(1))
+            .unwrap()
+            .stop_sign_placement = StopSignPlacement::AllWay;
+
+        let diff = RawMapDiff::new(&orig, &current);
+        let roads: Vec<(OriginalRoad, String)> = diff
+            .roads
+            .iter()
+            .map(|(id, change)| (*id, change.describe()))
+            .collect();
+        assert_eq!(
+            roads,
+            vec![
+                (
+                    OriginalRoad::new(0, (0, 1)),
+                    "modified: highway = residential -> tertiary, + lanes = 2".to_string()
+                ),
+                (OriginalRoad::new(2, (1, 3)), "added".to_string()),
+                (OriginalRoad::new(1, (1, 2)), "deleted".to_string()),
+            ]
+        );
+
+        let intersections: Vec<(osm::NodeID, String)> = diff
+            .intersections
+            .iter()
+            .map(|(id, change)| (*id, change.describe()))
+            .collect();
+        assert_eq!(
+            intersections,
+            vec![
+                (
+                    osm::NodeID(1),
+                    format!(
+                        "modified: {} -> {}",
+                        StopSignPlacement::Automatic.describe(),
+                        StopSignPlacement::AllWay.describe()
+                    )
+                ),
+                (osm::NodeID(3), "added".to_string()),
+                (osm::NodeID(2), "deleted".to_string()),
+            ]
+        );
+    }
+}
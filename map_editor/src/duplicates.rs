@@ -0,0 +1,283 @@
+//! OSM sometimes has a footway or service road drawn nearly on top of another road. The importer
+//! then produces tiny, broken intersections where the two touch. Find pairs of roads that run
+//! close and parallel to each other for a while, and offer quick fixes.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use geom::{Bounds, Circle, Distance, Percent, PolyLine, Pt2D};
+use map_model::osm;
+use map_model::raw::{OriginalRoad, RawMap, RawRoad};
+use widgetry::{
+    Color, ControlState, DrawBaselayer, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment,
+    Line, Outcome, Panel, State, Text, TextExt, Transition, VerticalAlignment, Widget,
+};
+
+use crate::app::App;
+
+/// Roads further apart than this aren't duplicates, even if they're parallel
+const MAX_SEPARATION: Distance = Distance::const_meters(3.0);
+/// Roads have to run alongside each other at least this long to be flagged
+const MIN_OVERLAP: Distance = Distance::const_meters(10.0);
+const SAMPLE_EVERY: Distance = Distance::const_meters(2.0);
+/// Only compare roads whose bounding boxes share a grid cell of this size
+const GRID_SIZE: f64 = 50.0;
+
+pub struct NearDuplicate {
+    /// The more important of the two roads
+    pub major: OriginalRoad,
+    pub minor: OriginalRoad,
+    /// Somewhere the two roads run alongside each other
+    pub pt: Pt2D,
+    pub overlap: Distance,
+}
+
+/// Returns every pair of roads that run close and parallel to each other, longest overlap first.
+pub fn find_near_duplicates(map: &RawMap) -> Vec<NearDuplicate> {
+    let mut center_lines: BTreeMap<OriginalRoad, PolyLine> = BTreeMap::new();
+    let mut grid: BTreeMap<(i64, i64), Vec<OriginalRoad>> = BTreeMap::new();
+    for (id, road) in &map.roads {
+        let pl = match PolyLine::new(road.center_points.clone()) {
+            Ok(pl) => pl,
+            Err(_) => continue,
+        };
+        let bounds = Bounds::from(pl.points());
+        for x in cell(bounds.min_x)..=cell(bounds.max_x) {
+            for y in cell(bounds.min_y)..=cell(bounds.max_y) {
+                grid.entry((x, y)).or_insert_with(Vec::new).push(*id);
+            }
+        }
+        center_lines.insert(*id, pl);
+    }
+
+    let mut candidates = BTreeSet::new();
+    for roads in grid.values() {
+        for (idx, r1) in roads.iter().enumerate() {
+            for r2 in &roads[idx + 1..] {
+                candidates.insert((*r1.min(r2), *r1.max(r2)));
+            }
+        }
+    }
+
+    let mut results = Vec::new();
+    for (r1, r2) in candidates {
+        // Sample along the shorter road, so the overlap can cover it entirely
+        let (r1, r2) = if center_lines[&r1].length() <= center_lines[&r2].length() {
+            (r1, r2)
+        } else {
+            (r2, r1)
+        };
+        if let Some((pt, overlap)) = overlap(&center_lines[&r1], &center_lines[&r2]) {
+            let (major, minor) = if importance(&map.roads[&r1]) >= importance(&map.roads[&r2]) {
+                (r1, r2)
+            } else {
+                (r2, r1)
+            };
+            results.push(NearDuplicate {
+                major,
+                minor,
+                pt,
+                overlap,
+            });
+        }
+    }
+    results.sort_by_key(|x| std::cmp::Reverse(x.overlap));
+    results
+}
+
+fn cell(x: f64) -> i64 {
+    (x / GRID_SIZE).floor() as i64
+}
+
+/// How long does `pl1` run close and parallel to `pl2`? Also returns the first point where they
+/// do.
+fn overlap(pl1: &PolyLine, pl2: &PolyLine) -> Option<(Pt2D, Distance)> {
+    let mut first_pt = None;
+    let mut total = Distance::ZERO;
+    let mut dist = Distance::ZERO;
+    while dist <= pl1.length() {
+        let (pt, angle) = pl1.must_dist_along(dist);
+        let closest = pl2.project_pt_detailed(pt);
+        if closest.distance <= MAX_SEPARATION {
+            let other_angle =
+                pl2.points()[closest.segment].angle_to(pl2.points()[closest.segment + 1]);
+            if angle.approx_parallel(other_angle, 20.0) {
+                first_pt.get_or_insert(pt);
+                total += SAMPLE_EVERY;
+            }
+        }
+        dist += SAMPLE_EVERY;
+    }
+    if total >= MIN_OVERLAP {
+        first_pt.map(|pt| (pt, total))
+    } else {
+        None
+    }
+}
+
+/// Footways and paths are never more important than a road
+fn importance(road: &RawRoad) -> usize {
+    if road.is_footway() {
+        return 0;
+    }
+    road.osm_tags
+        .get(osm::HIGHWAY)
+        .map(|hwy| osm::RoadRank::detailed_from_highway(hwy.as_str()))
+        .unwrap_or(0)
+}
+
+pub struct FindDuplicates {
+    panel: Panel,
+    duplicates: Vec<NearDuplicate>,
+    highlight: Drawable,
+}
+
+impl FindDuplicates {
+    pub fn new_state(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        let duplicates = find_near_duplicates(&app.model.map);
+
+        let mut col = vec![Widget::row(vec![
+            Line(format!("{} near-duplicate roads", duplicates.len()))
+                .small_heading()
+                .into_widget(ctx),
+            ctx.style().btn_close_widget(ctx),
+        ])];
+        if duplicates.is_empty() {
+            col.push("No roads are drawn on top of each other".text_widget(ctx));
+        }
+        for (idx, dupe) in duplicates.iter().enumerate() {
+            let major = &app.model.map.roads[&dupe.major];
+            let minor = &app.model.map.roads[&dupe.minor];
+            col.push(
+                ctx.style()
+                    .btn_plain
+                    .btn()
+                    .label_styled_text(
+                        Text::from_multiline(vec![
+                            Line(format!("{} and {}", dupe.major, dupe.minor)),
+                            Line(format!(
+                                "{} and {} run within {} of each other for {}",
+                                describe(major),
+                                describe(minor),
+                                MAX_SEPARATION,
+                                dupe.overlap
+                            ))
+                            .secondary(),
+                        ]),
+                        ControlState::Default,
+                    )
+                    .build_widget(ctx, idx.to_string()),
+            );
+            let mut fixes = vec![
+                ctx.style()
+                    .btn_outline
+                    .text("merge")
+                    .tooltip(if minor.is_footway() {
+                        "Delete the footway, and give the road sidewalks instead"
+                    } else {
+                        "Keep only the more important road"
+                    })
+                    .build_widget(ctx, format!("merge {}", idx)),
+                ctx.style()
+                    .btn_outline
+                    .text(format!("delete {}", describe(major)))
+                    .build_widget(ctx, format!("delete major {}", idx)),
+                ctx.style()
+                    .btn_outline
+                    .text(format!("delete {}", describe(minor)))
+                    .build_widget(ctx, format!("delete minor {}", idx)),
+            ];
+            if minor.is_footway() && !major.is_footway() {
+                fixes.push(
+                    ctx.style()
+                        .btn_outline
+                        .text("mark as separate sidewalk")
+                        .build_widget(ctx, format!("separate sidewalk {}", idx)),
+                );
+            }
+            col.push(Widget::row(fixes));
+        }
+        col.push(ctx.style().btn_outline.text("check again").build_def(ctx));
+
+        Box::new(FindDuplicates {
+            panel: Panel::new_builder(Widget::col(col))
+                .aligned(HorizontalAlignment::Left, VerticalAlignment::Top)
+                .max_size(Percent::int(30), Percent::int(80))
+                .build(ctx),
+            duplicates,
+            highlight: Drawable::empty(ctx),
+        })
+    }
+}
+
+fn describe(road: &RawRoad) -> String {
+    road.osm_tags
+        .get(osm::HIGHWAY)
+        .cloned()
+        .unwrap_or_else(|| "road".to_string())
+}
+
+impl State<App> for FindDuplicates {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition<App> {
+        ctx.canvas_movement();
+
+        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+            match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "check again" => {
+                    return Transition::Replace(FindDuplicates::new_state(ctx, app));
+                }
+                x => {
+                    if let Some(idx) = x.strip_prefix("merge ") {
+                        let dupe = &self.duplicates[idx.parse::<usize>().unwrap()];
+                        if app.model.map.roads[&dupe.minor].is_footway() {
+                            app.model.edit_r_tags(ctx, &[dupe.major], |tags| {
+                                if !tags.is_any(osm::SIDEWALK, vec!["both", "left", "right"]) {
+                                    tags.insert(osm::SIDEWALK, "both");
+                                }
+                            });
+                        }
+                        app.model.delete_r(ctx, dupe.minor);
+                    } else if let Some(idx) = x.strip_prefix("delete major ") {
+                        let r = self.duplicates[idx.parse::<usize>().unwrap()].major;
+                        app.model.delete_r(ctx, r);
+                    } else if let Some(idx) = x.strip_prefix("delete minor ") {
+                        let r = self.duplicates[idx.parse::<usize>().unwrap()].minor;
+                        app.model.delete_r(ctx, r);
+                    } else if let Some(idx) = x.strip_prefix("separate sidewalk ") {
+                        let dupe = &self.duplicates[idx.parse::<usize>().unwrap()];
+                        app.model.edit_r_tags(ctx, &[dupe.major], |tags| {
+                            tags.insert(osm::SIDEWALK, "separate");
+                        });
+                        app.model.edit_r_tags(ctx, &[dupe.minor], |tags| {
+                            tags.insert("footway", "sidewalk");
+                        });
+                    } else {
+                        let pt = self.duplicates[x.parse::<usize>().unwrap()].pt;
+                        ctx.canvas.cam_zoom = ctx.canvas.cam_zoom.max(2.0);
+                        ctx.canvas.center_on_map_pt(pt);
+                        self.highlight = ctx.upload(GeomBatch::from(vec![(
+                            Color::RED.alpha(0.5),
+                            Circle::new(pt, Distance::meters(15.0)).to_polygon(),
+                        )]));
+                        return Transition::Keep;
+                    }
+                    // Every fix changes the roads, so look for problems again
+                    return Transition::Replace(FindDuplicates::new_state(ctx, app));
+                }
+            }
+        }
+
+        Transition::Keep
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::PreviousState
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        g.redraw(&self.highlight);
+        self.panel.draw(g);
+    }
+}
@@ -1,10 +1,13 @@
-use geom::{ArrowCap, Distance};
+use geom::{ArrowCap, Circle, Distance, PolyLine, Polygon, Pt2D, Ring};
+use map_model::osm;
 use map_model::raw::OriginalRoad;
 use widgetry::{
     Choice, Color, DrawBaselayer, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key,
-    Line, Panel, SimpleState, Spinner, State, Text, TextExt, Transition, VerticalAlignment, Widget,
+    Line, Outcome, Panel, SimpleState, Spinner, State, Text, TextExt, Toggle, Transition,
+    VerticalAlignment, Widget,
 };
 
+use crate::model::ID;
 use crate::App;
 
 pub struct EditRoad {
@@ -118,11 +121,18 @@ impl EditRoad {
                 ctx.style().btn_close_widget(ctx),
             ]),
             Widget::row(vec![info, controls]),
-            ctx.style()
-                .btn_solid_primary
-                .text("Apply")
-                .hotkey(Key::Enter)
-                .build_def(ctx),
+            Widget::row(vec![
+                ctx.style()
+                    .btn_solid_primary
+                    .text("Apply")
+                    .hotkey(Key::Enter)
+                    .build_def(ctx),
+                ctx.style()
+                    .btn_outline
+                    .text("edit raw tags")
+                    .hotkey(Key::T)
+                    .build_def(ctx),
+            ]),
         ];
         let panel = Panel::new_builder(Widget::col(col))
             .aligned(HorizontalAlignment::Left, VerticalAlignment::Top)
@@ -147,6 +157,7 @@ impl SimpleState<App> for EditRoad {
     ) -> Transition<App> {
         match x {
             "close" => Transition::Pop,
+            "edit raw tags" => Transition::Push(crate::tags::EditTags::new_state(ctx, app, self.r)),
             "Apply" => {
                 app.model.road_deleted(self.r);
 
@@ -209,3 +220,327 @@ impl SimpleState<App> for EditRoad {
         DrawBaselayer::PreviousState
     }
 }
+
+/// Click a series of points to trace the center line of a new road. Intersections are created at
+/// the endpoints and anywhere the line crosses an existing road.
+pub struct DrawRoad {
+    panel: Panel,
+    pts: Vec<Pt2D>,
+    preview: Drawable,
+}
+
+impl DrawRoad {
+    pub(crate) fn new_state(ctx: &mut EventCtx) -> Box<dyn State<App>> {
+        let mut txt = Text::new();
+        txt.add_appended(vec![
+            Line("Click").fg(ctx.style().text_hotkey_color),
+            Line(" to add a point"),
+        ]);
+        txt.add_appended(vec![
+            Line("- Press "),
+            Key::Backspace.txt(ctx),
+            Line(" to remove the last point"),
+        ]);
+
+        let panel = Panel::new_builder(Widget::col(vec![
+            Widget::row(vec![
+                Line("Draw a road").small_heading().into_widget(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            txt.into_widget(ctx),
+            ctx.style()
+                .btn_solid_primary
+                .text("Finish")
+                .hotkey(Key::Enter)
+                .disabled(true)
+                .build_def(ctx),
+        ]))
+        .aligned(HorizontalAlignment::Left, VerticalAlignment::Top)
+        .build(ctx);
+
+        Box::new(DrawRoad {
+            panel,
+            pts: Vec::new(),
+            preview: Drawable::empty(ctx),
+        })
+    }
+
+    fn points_changed(&mut self, ctx: &mut EventCtx) {
+        let mut batch = GeomBatch::new();
+        for pt in &self.pts {
+            batch.push(
+                Color::GREEN,
+                Circle::new(*pt, Distance::meters(1.5)).to_polygon(),
+            );
+        }
+        if let Ok(pl) = PolyLine::deduping_new(self.pts.clone()) {
+            batch.push(
+                Color::GREEN.alpha(0.8),
+                pl.make_polygons(Distance::meters(5.0)),
+            );
+        }
+        self.preview = ctx.upload(batch);
+
+        let finish = ctx
+            .style()
+            .btn_solid_primary
+            .text("Finish")
+            .hotkey(Key::Enter)
+            .disabled(self.pts.len() < 2)
+            .build_def(ctx);
+        self.panel.replace(ctx, "Finish", finish);
+    }
+}
+
+impl State<App> for DrawRoad {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition<App> {
+        ctx.canvas_movement();
+
+        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+            match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "Finish" => {
+                    app.model
+                        .create_traced_r(ctx, std::mem::take(&mut self.pts));
+                    app.model.world.handle_mouseover(ctx);
+                    return Transition::Pop;
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        if ctx.input.pressed(Key::Backspace) {
+            if self.pts.pop().is_some() {
+                self.points_changed(ctx);
+            }
+        } else if ctx.normal_left_click() {
+            if let Some(pt) = ctx.canvas.get_cursor_in_map_space() {
+                let pt = app
+                    .snapping
+                    .snap(&app.model.map, pt, self.pts.last().cloned(), None);
+                // Negative coordinates break the quadtree in World
+                if pt.x() >= 0.0 && pt.y() >= 0.0 {
+                    self.pts.push(pt);
+                    self.points_changed(ctx);
+                }
+            }
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        g.redraw(&self.preview);
+        if let Some(cursor) = g.get_cursor_in_map_space() {
+            let cursor = app
+                .snapping
+                .snap(&app.model.map, cursor, self.pts.last().cloned(), None);
+            if app.snapping.is_enabled() {
+                g.draw_polygon(
+                    Color::YELLOW,
+                    Circle::new(cursor, Distance::meters(1.5)).to_polygon(),
+                );
+            }
+            if let Some(l) = self
+                .pts
+                .last()
+                .and_then(|last| geom::Line::new(*last, cursor))
+            {
+                g.draw_polygon(
+                    Color::GREEN.alpha(0.5),
+                    l.make_polygons(Distance::meters(5.0)),
+                );
+            }
+        }
+        self.panel.draw(g);
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::PreviousState
+    }
+}
+
+/// Select many roads at once by dragging a rectangle or lasso, then apply the same change to all
+/// of them.
+pub struct BulkEditRoads {
+    panel: Panel,
+    selected: Vec<OriginalRoad>,
+    draw_selected: Drawable,
+    // The points dragged out so far, while the mouse is held down
+    dragging: Option<Vec<Pt2D>>,
+}
+
+impl BulkEditRoads {
+    pub(crate) fn new_state(ctx: &mut EventCtx) -> Box<dyn State<App>> {
+        let mut state = BulkEditRoads {
+            panel: Panel::empty(ctx),
+            selected: Vec::new(),
+            draw_selected: Drawable::empty(ctx),
+            dragging: None,
+        };
+        state.panel = state.make_panel(ctx, true);
+        Box::new(state)
+    }
+
+    fn make_panel(&self, ctx: &mut EventCtx, rectangle: bool) -> Panel {
+        Panel::new_builder(Widget::col(vec![
+            Widget::row(vec![
+                Line("Edit many roads").small_heading().into_widget(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            Toggle::choice(
+                ctx,
+                "selection shape",
+                "rectangle",
+                "lasso",
+                Key::L,
+                rectangle,
+            ),
+            Text::from_all(vec![
+                Line("Click and drag").fg(ctx.style().text_hotkey_color),
+                Line(" to select roads"),
+            ])
+            .into_widget(ctx),
+            format!("{} roads selected", self.selected.len()).text_widget(ctx),
+            Widget::col(vec![
+                Widget::row(vec![
+                    Toggle::checkbox(ctx, "set speed limit", None, false),
+                    Spinner::widget(ctx, "speed limit (km/h)", (5, 130), 30, 5),
+                ]),
+                Toggle::checkbox(ctx, "add a bike lane", None, false),
+                Toggle::checkbox(ctx, "mark as private", None, false),
+            ])
+            .section(ctx),
+            ctx.style()
+                .btn_solid_primary
+                .text("Apply")
+                .hotkey(Key::Enter)
+                .disabled(self.selected.is_empty())
+                .build_def(ctx),
+        ]))
+        .aligned(HorizontalAlignment::Left, VerticalAlignment::Top)
+        .build(ctx)
+    }
+
+    fn select(&mut self, ctx: &mut EventCtx, app: &App, area: Polygon) {
+        self.selected.clear();
+        let mut batch = GeomBatch::new();
+        for id in app.model.world.query_polygon(&area) {
+            if let ID::Road(r) = id {
+                self.selected.push(r);
+                if let Some(poly) = app.model.world.get_unioned_polygon(id) {
+                    batch.push(Color::CYAN.alpha(0.5), poly.clone());
+                }
+            }
+        }
+        self.draw_selected = ctx.upload(batch);
+
+        let rectangle = self.panel.is_checked("selection shape");
+        self.panel = self.make_panel(ctx, rectangle);
+    }
+
+    /// The shape of the current selection, if it's valid
+    fn selection_area(&self, pts: &[Pt2D]) -> Option<Polygon> {
+        if self.panel.is_checked("selection shape") {
+            Polygon::rectangle_two_corners(pts[0], *pts.last().unwrap())
+        } else {
+            let mut pts = Pt2D::approx_dedupe(pts.to_vec(), Distance::meters(1.0));
+            pts.push(pts[0]);
+            Ring::new(pts).ok().map(|ring| ring.into_polygon())
+        }
+    }
+}
+
+impl State<App> for BulkEditRoads {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition<App> {
+        if let Some((_, dy)) = ctx.input.get_mouse_scroll() {
+            ctx.canvas.zoom(dy, ctx.canvas.get_cursor());
+        }
+
+        if let Some(ref mut pts) = self.dragging {
+            if let Some(pt) = ctx.canvas.get_cursor_in_map_space() {
+                if pts.last().map(|last| *last != pt).unwrap_or(true) {
+                    pts.push(pt);
+                }
+            }
+            if ctx.input.left_mouse_button_released() {
+                let pts = self.dragging.take().unwrap();
+                match self.selection_area(&pts) {
+                    Some(area) => {
+                        self.select(ctx, app, area);
+                    }
+                    None => {
+                        warn!("Couldn't make a selection out of the dragged points");
+                    }
+                }
+            }
+            return Transition::Keep;
+        }
+
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "Apply" => {
+                    let speed = if self.panel.is_checked("set speed limit") {
+                        Some(self.panel.spinner::<usize>("speed limit (km/h)"))
+                    } else {
+                        None
+                    };
+                    let bike_lane = self.panel.is_checked("add a bike lane");
+                    let private = self.panel.is_checked("mark as private");
+
+                    app.model.edit_r_tags(ctx, &self.selected, |tags| {
+                        if let Some(speed) = speed {
+                            // OSM assumes km/h when no units are specified
+                            tags.insert(osm::MAXSPEED, speed.to_string());
+                        }
+                        if bike_lane {
+                            tags.remove("cycleway");
+                            tags.remove("cycleway:left");
+                            tags.remove("cycleway:right");
+                            tags.insert("cycleway:both", "lane");
+                        }
+                        if private {
+                            tags.insert("access", "private");
+                        }
+                    });
+                    app.model.world.handle_mouseover(ctx);
+                    return Transition::Pop;
+                }
+                _ => unreachable!(),
+            },
+            Outcome::Changed(_) => {}
+            _ => {
+                if ctx.input.left_mouse_button_pressed() {
+                    if let Some(pt) = ctx.canvas.get_cursor_in_map_space() {
+                        self.dragging = Some(vec![pt]);
+                    }
+                }
+            }
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        g.redraw(&self.draw_selected);
+        if let Some(ref pts) = self.dragging {
+            if pts.len() >= 2 {
+                if let Some(area) = self.selection_area(pts) {
+                    g.draw_polygon(Color::YELLOW.alpha(0.5), area);
+                } else if let Ok(pl) = PolyLine::deduping_new(pts.clone()) {
+                    g.draw_polygon(Color::YELLOW, pl.make_polygons(Distance::meters(1.0)));
+                }
+            }
+        }
+        self.panel.draw(g);
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::PreviousState
+    }
+}
@@ -12,9 +12,21 @@ use widgetry::Settings;
 use crate::app::App;
 
 mod app;
+mod autosave;
+mod background;
+mod building;
+mod coordinates;
+mod diff;
+mod duplicates;
 mod edit;
 mod load;
 mod model;
+mod preview;
+mod reimport;
+mod snap;
+mod tags;
+mod turn_restrictions;
+mod validate;
 mod world;
 
 pub fn main() {
@@ -42,20 +54,24 @@ fn run(mut settings: Settings) {
     settings = settings.read_svg(Box::new(abstio::slurp_bytes));
     widgetry::run(settings, |ctx| {
         let args = Args::from_iter(abstutil::cli_args());
+        let model = model::Model::blank(ctx);
         let mut app = App {
-            model: model::Model::blank(ctx),
+            autosave: autosave::Autosave::new(&model),
+            model,
+            snapping: snap::Snapping::default(),
+            background: None,
         };
         app.model.include_bldgs = args.include_buildings;
 
-        let states = if let Some(path) = args.load {
-            // In case the initial load fails, stick a blank state at the bottom
-            vec![
-                app::MainState::new_state(ctx, &app),
-                load::load_map(ctx, path, args.include_buildings, args.cam),
-            ]
-        } else {
-            vec![app::MainState::new_state(ctx, &app)]
-        };
+        let then_load = args.load.map(|path| (path, args.cam));
+        // In case the initial load fails, stick a blank state at the bottom
+        let mut states = vec![app::MainState::new_state(ctx, &app)];
+        if let Some(state) = autosave::RestoreSession::maybe_new_state(ctx, then_load.clone()) {
+            // Only load the map if the user doesn't restore the previous session
+            states.push(state);
+        } else if let Some((path, cam)) = then_load {
+            states.push(load::load_map(ctx, path, args.include_buildings, cam));
+        }
         (app, states)
     });
 }
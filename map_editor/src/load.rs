@@ -77,6 +77,9 @@ pub fn load_map(
         Box::new(move |ctx, app, timer, map| match map {
             Ok(map) => {
                 app.model = crate::model::Model::from_map(ctx, map, include_bldgs, timer);
+                app.autosave.reset(&app.model);
+                // The imagery was placed relative to the old map
+                app.background = None;
 
                 if !URLManager::change_camera(
                     ctx,
@@ -1,11 +1,16 @@
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::io::Write;
 
+use anyhow::{bail, Result};
+
 use abstio::{CityName, MapName};
 use abstutil::{Tags, Timer};
-use geom::{Bounds, Circle, Distance, FindClosest, GPSBounds, HashablePt2D, LonLat, Polygon, Pt2D};
+use geom::{
+    Bounds, Circle, Distance, FindClosest, GPSBounds, HashablePt2D, Line, LonLat, PolyLine,
+    Polygon, Pt2D,
+};
 use map_model::raw::{OriginalRoad, RawBuilding, RawIntersection, RawMap, RawRoad};
-use map_model::{osm, IntersectionType};
+use map_model::{osm, Amenity, IntersectionType, StopSignPlacement};
 use widgetry::{Color, Drawable, EventCtx, GeomBatch, Line, Text};
 
 use crate::world::{Object, ObjectID, World};
@@ -124,6 +129,42 @@ impl Model {
         }
     }
 
+    /// Clip the map to a new boundary, trimming roads that cross it and turning their endpoints
+    /// into border intersections.
+    pub fn clip_to_boundary(
+        &mut self,
+        ctx: &EventCtx,
+        boundary: Polygon,
+        timer: &mut Timer,
+    ) -> Result<()> {
+        if !self.map.roads.values().any(|r| {
+            boundary.contains_pt(r.center_points[0])
+                || boundary.contains_pt(*r.center_points.last().unwrap())
+        }) {
+            bail!("No roads are inside the new boundary");
+        }
+
+        // Clipping might fail partway through, so only keep the result if it succeeds
+        let mut clipped = self.map.clone();
+        clipped.boundary_polygon = boundary;
+        convert_osm::clip_map(&mut clipped, timer)?;
+        self.map = clipped;
+
+        // Clipping leaves behind intersections that're totally out of bounds
+        let mut used = BTreeSet::new();
+        for r in self.map.roads.keys() {
+            used.insert(r.i1);
+            used.insert(r.i2);
+        }
+        let boundary = &self.map.boundary_polygon;
+        self.map
+            .intersections
+            .retain(|id, i| used.contains(id) || boundary.contains_pt(i.point));
+
+        self.recreate_world(ctx, timer);
+        Ok(())
+    }
+
     fn compute_bounds(&self) -> Bounds {
         let mut bounds = Bounds::new();
         for b in self.map.buildings.values() {
@@ -149,7 +190,12 @@ impl Model {
         let i = &self.map.intersections[&id];
         let color = match i.intersection_type {
             IntersectionType::TrafficSignal => Color::GREEN,
-            IntersectionType::StopSign => Color::RED,
+            IntersectionType::StopSign => match i.stop_sign_placement {
+                StopSignPlacement::Automatic => Color::RED,
+                StopSignPlacement::AllWay => Color::PURPLE,
+                StopSignPlacement::TwoWay => Color::PINK,
+                StopSignPlacement::Uncontrolled => Color::WHITE,
+            },
             IntersectionType::Border => Color::BLUE,
             IntersectionType::Construction => Color::ORANGE,
         };
@@ -170,18 +216,31 @@ impl Model {
             .add(ctx, Object::new(ID::Intersection(id), color, poly));
     }
 
-    pub fn create_i(&mut self, ctx: &EventCtx, point: Pt2D) {
+    pub fn create_i(&mut self, ctx: &EventCtx, point: Pt2D) -> osm::NodeID {
         let id = self.map.new_osm_node_id(time_to_id());
         self.map.intersections.insert(
             id,
             RawIntersection {
                 point,
                 intersection_type: IntersectionType::StopSign,
+                stop_sign_placement: StopSignPlacement::Automatic,
                 elevation: Distance::ZERO,
                 trim_roads_for_merging: BTreeMap::new(),
+                midblock_crossing: false,
             },
         );
         self.intersection_added(ctx, id);
+        id
+    }
+
+    /// Find an existing intersection close to the point.
+    fn find_i_near(&self, pt: Pt2D) -> Option<osm::NodeID> {
+        self.map
+            .intersections
+            .iter()
+            .filter(|(_, i)| i.point.dist_to(pt) <= 2.0 * INTERSECTION_RADIUS)
+            .min_by_key(|(_, i)| i.point.dist_to(pt))
+            .map(|(id, _)| *id)
     }
 
     pub fn move_i(&mut self, ctx: &EventCtx, id: osm::NodeID, point: Pt2D) {
@@ -202,19 +261,62 @@ impl Model {
         self.world.delete(ID::Intersection(id));
     }
 
+    /// Cycle through traffic signals and the different ways of placing stop signs.
     pub fn toggle_i(&mut self, ctx: &EventCtx, id: osm::NodeID) {
         self.world.delete(ID::Intersection(id));
 
         let i = self.map.intersections.get_mut(&id).unwrap();
-        if i.intersection_type == IntersectionType::TrafficSignal {
-            i.intersection_type = IntersectionType::StopSign;
-        } else if i.intersection_type == IntersectionType::StopSign {
-            i.intersection_type = IntersectionType::TrafficSignal;
+        match (i.intersection_type, i.stop_sign_placement) {
+            (IntersectionType::TrafficSignal, _) => {
+                i.intersection_type = IntersectionType::StopSign;
+                i.stop_sign_placement = StopSignPlacement::Automatic;
+            }
+            (IntersectionType::StopSign, StopSignPlacement::Automatic) => {
+                i.stop_sign_placement = StopSignPlacement::AllWay;
+            }
+            (IntersectionType::StopSign, StopSignPlacement::AllWay) => {
+                i.stop_sign_placement = StopSignPlacement::TwoWay;
+            }
+            (IntersectionType::StopSign, StopSignPlacement::TwoWay) => {
+                i.stop_sign_placement = StopSignPlacement::Uncontrolled;
+            }
+            (IntersectionType::StopSign, StopSignPlacement::Uncontrolled) => {
+                i.intersection_type = IntersectionType::TrafficSignal;
+                i.stop_sign_placement = StopSignPlacement::Automatic;
+            }
+            (IntersectionType::Border, _) | (IntersectionType::Construction, _) => {}
         }
 
         self.intersection_added(ctx, id);
     }
 
+    /// Change how an intersection is controlled, like after re-importing it from OSM.
+    pub fn set_i_control(
+        &mut self,
+        ctx: &EventCtx,
+        id: osm::NodeID,
+        intersection_type: IntersectionType,
+        stop_sign_placement: StopSignPlacement,
+    ) {
+        self.world.delete(ID::Intersection(id));
+
+        let i = self.map.intersections.get_mut(&id).unwrap();
+        i.intersection_type = intersection_type;
+        i.stop_sign_placement = stop_sign_placement;
+
+        self.intersection_added(ctx, id);
+    }
+
+    pub fn describe_i_control(&self, id: osm::NodeID) -> String {
+        let i = &self.map.intersections[&id];
+        match i.intersection_type {
+            IntersectionType::StopSign => i.stop_sign_placement.describe().to_string(),
+            IntersectionType::TrafficSignal => "traffic signal".to_string(),
+            IntersectionType::Border => "border".to_string(),
+            IntersectionType::Construction => "construction".to_string(),
+        }
+    }
+
     pub fn show_intersection_geometry(&mut self, ctx: &mut EventCtx, show: bool) {
         self.intersection_geom = show;
 
@@ -263,6 +365,20 @@ impl Model {
     }
 
     pub fn create_r(&mut self, ctx: &EventCtx, i1: osm::NodeID, i2: osm::NodeID) {
+        let center_points = vec![
+            self.map.intersections[&i1].point,
+            self.map.intersections[&i2].point,
+        ];
+        self.insert_r(ctx, i1, i2, center_points);
+    }
+
+    fn insert_r(
+        &mut self,
+        ctx: &EventCtx,
+        i1: osm::NodeID,
+        i2: osm::NodeID,
+        center_points: Vec<Pt2D>,
+    ) {
         // Ban cul-de-sacs, since they get stripped out later anyway.
         if self
             .map
@@ -297,10 +413,7 @@ impl Model {
         self.map.roads.insert(
             id,
             RawRoad {
-                center_points: vec![
-                    self.map.intersections[&i1].point,
-                    self.map.intersections[&i2].point,
-                ],
+                center_points,
                 osm_tags,
                 turn_restrictions: Vec::new(),
                 complicated_turn_restrictions: Vec::new(),
@@ -315,6 +428,166 @@ impl Model {
         self.intersection_added(ctx, i2);
     }
 
+    /// Create roads following a freehand line. The endpoints snap to nearby intersections, and
+    /// anywhere the line crosses an existing road, that road is split and a new intersection is
+    /// created.
+    pub fn create_traced_r(&mut self, ctx: &EventCtx, pts: Vec<Pt2D>) {
+        let pts = Pt2D::approx_dedupe(pts, INTERSECTION_RADIUS);
+        if pts.len() < 2 {
+            error!("Need at least two points to trace a road");
+            return;
+        }
+        let first_pt = pts[0];
+        let last_pt = *pts.last().unwrap();
+
+        // Find the crossings before anything changes, so the new roads don't cross themselves.
+        // (index of the traced segment, distance along that segment, the crossing)
+        let mut crossings: Vec<(usize, Distance, Pt2D)> = Vec::new();
+        for (idx, pair) in pts.windows(2).enumerate() {
+            let traced = Line::must_new(pair[0], pair[1]);
+            for road in self.map.roads.values() {
+                for existing in road.center_points.windows(2) {
+                    if let Some(pt) = Line::new(existing[0], existing[1])
+                        .and_then(|existing| traced.intersection(&existing))
+                    {
+                        // The endpoints get handled separately
+                        if pt.dist_to(first_pt) > 2.0 * INTERSECTION_RADIUS
+                            && pt.dist_to(last_pt) > 2.0 * INTERSECTION_RADIUS
+                        {
+                            crossings.push((idx, pair[0].dist_to(pt), pt));
+                        }
+                    }
+                }
+            }
+        }
+        crossings.sort_by_key(|(idx, dist, _)| (*idx, *dist));
+
+        let mut current_i = self.snap_or_create_i(ctx, first_pt);
+        let mut current_pts = vec![self.map.intersections[&current_i].point];
+        let mut crossings = crossings.into_iter().peekable();
+        for (idx, pt) in pts.iter().enumerate().skip(1) {
+            while let Some((_, _, crossing)) = crossings.next_if(|(i, _, _)| *i == idx - 1) {
+                let i = self.snap_or_create_i(ctx, crossing);
+                if i != current_i {
+                    current_pts.push(self.map.intersections[&i].point);
+                    self.insert_r(ctx, current_i, i, current_pts);
+                    current_i = i;
+                    current_pts = vec![self.map.intersections[&i].point];
+                }
+            }
+            if idx != pts.len() - 1 {
+                current_pts.push(*pt);
+            }
+        }
+
+        let last_i = self.snap_or_create_i(ctx, last_pt);
+        if last_i != current_i {
+            current_pts.push(self.map.intersections[&last_i].point);
+            self.insert_r(ctx, current_i, last_i, current_pts);
+        }
+    }
+
+    /// Use an existing intersection near the point, split a road passing through the point, or
+    /// otherwise just create a new intersection.
+    fn snap_or_create_i(&mut self, ctx: &EventCtx, pt: Pt2D) -> osm::NodeID {
+        if let Some(i) = self.find_i_near(pt) {
+            return i;
+        }
+        if let Some(i) = self.split_r_at(ctx, pt) {
+            return i;
+        }
+        self.create_i(ctx, pt)
+    }
+
+    /// Split whatever road passes through the point, creating a new intersection there. Returns
+    /// None if no road is close enough.
+    fn split_r_at(&mut self, ctx: &EventCtx, pt: Pt2D) -> Option<osm::NodeID> {
+        // (road, index of the segment along the road, distance to the point)
+        let mut best: Option<(OriginalRoad, usize, Distance)> = None;
+        for (id, road) in &self.map.roads {
+            if let Ok(pl) = PolyLine::new(road.center_points.clone()) {
+                let projected = pl.project_pt_detailed(pt);
+                if best.map(|(_, _, d)| projected.distance < d).unwrap_or(true) {
+                    best = Some((*id, projected.segment, projected.distance));
+                }
+            }
+        }
+        let (id, idx, dist) = best?;
+        if dist > INTERSECTION_RADIUS {
+            return None;
+        }
+
+        self.stop_showing_pts(id);
+        self.road_deleted(id);
+        self.world.delete(ID::Intersection(id.i1));
+        self.world.delete(ID::Intersection(id.i2));
+
+        let new_i = self.create_i(ctx, pt);
+        let road = self.map.roads.remove(&id).unwrap();
+
+        // The new intersection splits the OSM way, so only the outer pieces are endpoints of it.
+        let mut first = road.clone();
+        first.center_points.truncate(idx + 1);
+        first.center_points.push(pt);
+        first.center_points = Pt2D::approx_dedupe(first.center_points, Distance::meters(0.1));
+        first.osm_tags.remove(osm::ENDPT_FWD);
+
+        let mut second = road;
+        second.center_points = std::iter::once(pt)
+            .chain(second.center_points.into_iter().skip(idx + 1))
+            .collect();
+        second.center_points = Pt2D::approx_dedupe(second.center_points, Distance::meters(0.1));
+        second.osm_tags.remove(osm::ENDPT_BACK);
+
+        let r1 = OriginalRoad {
+            osm_way_id: id.osm_way_id,
+            i1: id.i1,
+            i2: new_i,
+        };
+        let r2 = OriginalRoad {
+            osm_way_id: id.osm_way_id,
+            i1: new_i,
+            i2: id.i2,
+        };
+        self.map.roads.insert(r1, first);
+        self.map.roads.insert(r2, second);
+        self.road_added(ctx, r1);
+        self.road_added(ctx, r2);
+
+        self.world.delete(ID::Intersection(new_i));
+        self.intersection_added(ctx, id.i1);
+        self.intersection_added(ctx, id.i2);
+        self.intersection_added(ctx, new_i);
+
+        Some(new_i)
+    }
+
+    /// Place a pedestrian crossing on the road where the point projects onto it.
+    pub fn add_crossing(
+        &mut self,
+        ctx: &EventCtx,
+        id: OriginalRoad,
+        pt: Pt2D,
+    ) -> Result<osm::NodeID> {
+        let dist = PolyLine::new(self.map.roads[&id].center_points.clone())?
+            .project_pt_detailed(pt)
+            .dist_along;
+        let new_i = self.map.add_crossing(id, dist)?;
+
+        self.stop_showing_pts(id);
+        self.road_deleted(id);
+        self.world.delete(ID::Intersection(id.i1));
+        self.world.delete(ID::Intersection(id.i2));
+        for r in self.map.roads_per_intersection(new_i) {
+            self.road_added(ctx, r);
+        }
+        for i in [id.i1, id.i2, new_i] {
+            self.intersection_added(ctx, i);
+        }
+
+        Ok(new_i)
+    }
+
     pub fn delete_r(&mut self, ctx: &EventCtx, id: OriginalRoad) {
         self.stop_showing_pts(id);
         self.road_deleted(id);
@@ -487,6 +760,42 @@ impl Model {
         info!("Merged {}", id.as_string_code());
     }
 
+    /// Apply the same change to the tags of many roads at once.
+    pub fn edit_r_tags<F: Fn(&mut Tags)>(&mut self, ctx: &EventCtx, ids: &[OriginalRoad], edit: F) {
+        let mut intersections = BTreeSet::new();
+        for id in ids {
+            self.stop_showing_pts(*id);
+            self.road_deleted(*id);
+            edit(&mut self.map.roads.get_mut(id).unwrap().osm_tags);
+            self.road_added(ctx, *id);
+            intersections.insert(id.i1);
+            intersections.insert(id.i2);
+        }
+        // Road widths may have changed
+        for i in intersections {
+            self.world.delete(ID::Intersection(i));
+            self.intersection_added(ctx, i);
+        }
+    }
+
+    /// Replace a road's geometry and tags entirely, like after re-importing it from OSM. The
+    /// endpoints of the new center-line should match the road's intersections.
+    pub fn replace_r(&mut self, ctx: &EventCtx, id: OriginalRoad, pts: Vec<Pt2D>, tags: Tags) {
+        self.stop_showing_pts(id);
+        self.road_deleted(id);
+
+        let road = self.map.roads.get_mut(&id).unwrap();
+        road.center_points = pts;
+        road.osm_tags = tags;
+
+        self.road_added(ctx, id);
+        // Road widths may have changed
+        for i in [id.i1, id.i2] {
+            self.world.delete(ID::Intersection(i));
+            self.intersection_added(ctx, i);
+        }
+    }
+
     pub fn toggle_junction(&mut self, ctx: &EventCtx, id: OriginalRoad) {
         self.road_deleted(id);
 
@@ -505,18 +814,29 @@ impl Model {
 impl Model {
     fn bldg_added(&mut self, ctx: &EventCtx, id: osm::OsmID) {
         let b = &self.map.buildings[&id];
-        self.world.add(
-            ctx,
-            Object::new(ID::Building(id), Color::BLUE, b.polygon.clone()),
-        );
+        // Make destinations stand out
+        let color = if b.amenities.is_empty() {
+            Color::BLUE
+        } else {
+            Color::PURPLE
+        };
+        self.world
+            .add(ctx, Object::new(ID::Building(id), color, b.polygon.clone()));
     }
 
     pub fn create_b(&mut self, ctx: &EventCtx, center: Pt2D) -> ID {
+        self.create_b_from_polygon(
+            ctx,
+            Polygon::rectangle_centered(center, BUILDING_LENGTH, BUILDING_LENGTH),
+        )
+    }
+
+    pub fn create_b_from_polygon(&mut self, ctx: &EventCtx, polygon: Polygon) -> ID {
         let id = osm::OsmID::Way(self.map.new_osm_way_id(time_to_id()));
         self.map.buildings.insert(
             id,
             RawBuilding {
-                polygon: Polygon::rectangle_centered(center, BUILDING_LENGTH, BUILDING_LENGTH),
+                polygon,
                 osm_tags: Tags::empty(),
                 public_garage_name: None,
                 num_parking_spots: 0,
@@ -544,6 +864,18 @@ impl Model {
         self.world.delete(ID::Building(id));
         self.map.buildings.remove(&id).unwrap();
     }
+
+    pub fn set_b_polygon(&mut self, ctx: &EventCtx, id: osm::OsmID, polygon: Polygon) {
+        self.world.delete(ID::Building(id));
+        self.map.buildings.get_mut(&id).unwrap().polygon = polygon;
+        self.bldg_added(ctx, id);
+    }
+
+    pub fn set_b_amenities(&mut self, ctx: &EventCtx, id: osm::OsmID, amenities: Vec<Amenity>) {
+        self.world.delete(ID::Building(id));
+        self.map.buildings.get_mut(&id).unwrap().amenities = amenities;
+        self.bldg_added(ctx, id);
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
@@ -0,0 +1,201 @@
+//! Run the RawMap -> Map conversion on just the part of the map on screen, and draw the resulting
+//! lanes and intersections on top of the RawMap. Geometry problems usually only show up after the
+//! full conversion, so this is a quick way to check a fix.
+
+use anyhow::{anyhow, Result};
+use futures_channel::mpsc;
+
+use abstutil::{Timer, TimerSink};
+use geom::{Bounds, Distance};
+use map_gui::load::FutureLoader;
+use map_gui::tools::PopupMsg;
+use map_model::raw::RawMap;
+use map_model::{LaneType, Map, RawToMapOptions};
+use widgetry::{
+    Color, DrawBaselayer, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key, Line,
+    Outcome, Panel, State, TextExt, Transition, VerticalAlignment, Widget,
+};
+
+use crate::app::App;
+
+pub struct PreviewConversion {
+    panel: Panel,
+    draw: Drawable,
+}
+
+impl PreviewConversion {
+    /// Converts the visible area in the background, then shows the result.
+    pub fn new_state(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        let raw = clip_to_bounds(&app.model.map, &ctx.canvas.get_screen_bounds());
+        if raw.roads.is_empty() {
+            return PopupMsg::new_state(
+                ctx,
+                "Conversion failed",
+                vec!["No roads are visible".to_string()],
+            );
+        }
+
+        let (outer_progress_tx, outer_progress_rx) = mpsc::channel(1000);
+        let (_, inner_progress_rx) = mpsc::channel(1);
+        FutureLoader::<App, Map>::new_state(
+            ctx,
+            Box::pin(async move {
+                let map = convert(raw, outer_progress_tx)?;
+                let wrap: Box<dyn Send + FnOnce(&App) -> Map> = Box::new(move |_: &App| map);
+                Ok(wrap)
+            }),
+            outer_progress_rx,
+            inner_progress_rx,
+            "Converting the visible area",
+            Box::new(|ctx, _, result| match result {
+                Ok(map) => Transition::Replace(PreviewConversion::show(ctx, map)),
+                Err(err) => Transition::Replace(PopupMsg::new_state(
+                    ctx,
+                    "Conversion failed",
+                    vec![format!("Converting the visible area failed: {}", err)],
+                )),
+            }),
+        )
+    }
+
+    fn show(ctx: &mut EventCtx, map: Map) -> Box<dyn State<App>> {
+        let mut batch = GeomBatch::new();
+        for i in map.all_intersections() {
+            batch.push(Color::grey(0.3).alpha(0.8), i.polygon.clone());
+            if let Ok(outline) = i.polygon.to_outline(Distance::meters(0.5)) {
+                batch.push(Color::WHITE, outline);
+            }
+        }
+        for l in map.all_lanes() {
+            batch.push(lane_color(l.lane_type).alpha(0.8), l.get_thick_polygon());
+        }
+
+        let panel = Panel::new_builder(Widget::col(vec![
+            Widget::row(vec![
+                Line("Preview of the converted map")
+                    .small_heading()
+                    .into_widget(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            format!(
+                "{} roads, {} intersections",
+                map.all_roads().len(),
+                map.all_intersections().len()
+            )
+            .text_widget(ctx),
+            ctx.style()
+                .btn_outline
+                .text("recalculate")
+                .hotkey(Key::R)
+                .build_def(ctx),
+        ]))
+        .aligned(HorizontalAlignment::Left, VerticalAlignment::Top)
+        .build(ctx);
+
+        Box::new(PreviewConversion {
+            panel,
+            draw: ctx.upload(batch),
+        })
+    }
+}
+
+impl State<App> for PreviewConversion {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition<App> {
+        ctx.canvas_movement();
+
+        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+            match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "recalculate" => {
+                    return Transition::Replace(PreviewConversion::new_state(ctx, app));
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        Transition::Keep
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::PreviousState
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        g.redraw(&self.draw);
+        self.panel.draw(g);
+    }
+}
+
+fn convert(raw: RawMap, progress: mpsc::Sender<String>) -> Result<Map> {
+    let mut timer = Timer::new_with_sink("preview conversion", Box::new(ProgressSink(progress)));
+    // The conversion is full of asserts. Don't crash the whole editor if it fails.
+    std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        Map::create_from_raw(
+            raw,
+            RawToMapOptions {
+                skip_ch: true,
+                ..Default::default()
+            },
+            &mut timer,
+        )
+    }))
+    .map_err(|err| {
+        anyhow!(err
+            .downcast_ref::<String>()
+            .cloned()
+            .or_else(|| err.downcast_ref::<&str>().map(|x| x.to_string()))
+            .unwrap_or_else(|| "unknown error".to_string()))
+    })
+}
+
+/// Passes along the conversion's progress to the loading screen
+struct ProgressSink(mpsc::Sender<String>);
+
+impl TimerSink for ProgressSink {
+    fn println(&mut self, line: String) {
+        // If the loading screen falls behind, it only needs the latest line anyway
+        let _ = self.0.try_send(line);
+    }
+
+    fn reprintln(&mut self, line: String) {
+        self.println(line);
+    }
+}
+
+/// Copy every road with an endpoint inside the bounds, and the intersections they connect.
+/// Buildings and areas aren't needed to check road geometry.
+fn clip_to_bounds(map: &RawMap, bounds: &Bounds) -> RawMap {
+    let mut raw = RawMap::blank(map.name.clone());
+    raw.gps_bounds = map.gps_bounds.clone();
+    raw.boundary_polygon = bounds.get_rectangle();
+    raw.config = map.config.clone();
+
+    for (id, road) in &map.roads {
+        if bounds.contains(map.intersections[&id.i1].point)
+            || bounds.contains(map.intersections[&id.i2].point)
+        {
+            raw.roads.insert(*id, road.clone());
+            for i in [id.i1, id.i2] {
+                raw.intersections
+                    .entry(i)
+                    .or_insert_with(|| map.intersections[&i].clone());
+            }
+        }
+    }
+    raw
+}
+
+fn lane_color(lt: LaneType) -> Color {
+    match lt {
+        LaneType::Driving | LaneType::SharedLeftTurn => Color::BLACK,
+        LaneType::Parking => Color::grey(0.5),
+        LaneType::Sidewalk | LaneType::Shoulder => Color::grey(0.8),
+        LaneType::Biking => Color::GREEN,
+        LaneType::Bus => Color::RED,
+        LaneType::Construction => Color::ORANGE,
+        LaneType::LightRail => Color::hex("#844204"),
+        LaneType::Buffer(_) => Color::hex("#555555"),
+    }
+}
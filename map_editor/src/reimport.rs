@@ -0,0 +1,282 @@
+//! Re-import one road or intersection from fresh OSM data, without regenerating the whole RawMap.
+//! This is useful after fixing something upstream in OSM, to check the fix quickly.
+
+use anyhow::{bail, Result};
+
+use abstutil::{Tags, Timer};
+use convert_osm::reader::Document;
+use map_gui::load::FutureLoader;
+use map_gui::tools::{FilePicker, PopupMsg};
+use map_model::raw::OriginalRoad;
+use map_model::{osm, IntersectionType, StopSignPlacement};
+use widgetry::{
+    DrawBaselayer, EventCtx, GfxCtx, Line, Outcome, Panel, State, TextExt, Transition, Widget,
+};
+
+use crate::app::App;
+
+const OVERPASS_URL: &str = "https://overpass-api.de/api/interpreter";
+
+#[derive(Clone, Copy)]
+pub enum Target {
+    Road(OriginalRoad),
+    Intersection(osm::NodeID),
+}
+
+impl Target {
+    fn describe(self) -> String {
+        match self {
+            Target::Road(r) => format!("{}", r.osm_way_id),
+            Target::Intersection(i) => format!("{}", i),
+        }
+    }
+
+    /// An Overpass query returning just the OSM XML needed for this object. Ways need their nodes
+    /// too.
+    fn overpass_url(self) -> String {
+        // Percent-encoded versions of "[out:xml];way(123);(._;>;);out;" and
+        // "[out:xml];node(123);out;"
+        let query = match self {
+            Target::Road(r) => format!(
+                "%5Bout%3Axml%5D%3Bway%28{}%29%3B%28._%3B%3E%3B%29%3Bout%3B",
+                r.osm_way_id.0
+            ),
+            Target::Intersection(i) => {
+                format!("%5Bout%3Axml%5D%3Bnode%28{}%29%3Bout%3B", i.0)
+            }
+        };
+        format!("{}?data={}", OVERPASS_URL, query)
+    }
+}
+
+/// Choose where to get the fresh OSM data from
+pub struct ReimportFromOSM {
+    target: Target,
+    panel: Panel,
+}
+
+impl ReimportFromOSM {
+    pub fn new_state(ctx: &mut EventCtx, target: Target) -> Box<dyn State<App>> {
+        Box::new(ReimportFromOSM {
+            target,
+            panel: Panel::new_builder(Widget::col(vec![
+                Widget::row(vec![
+                    Line("Re-import from OSM").small_heading().into_widget(ctx),
+                    ctx.style().btn_close_widget(ctx),
+                ]),
+                Line(target.describe()).secondary().into_widget(ctx),
+                "Tags and geometry will be replaced. Manual edits to this object will be lost."
+                    .text_widget(ctx),
+                ctx.style()
+                    .btn_outline
+                    .text("download from Overpass")
+                    .build_def(ctx),
+                ctx.style()
+                    .btn_outline
+                    .text("load a local .osm file")
+                    .build_def(ctx),
+            ]))
+            .build(ctx),
+        })
+    }
+}
+
+impl State<App> for ReimportFromOSM {
+    fn event(&mut self, ctx: &mut EventCtx, _: &mut App) -> Transition<App> {
+        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+            match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "download from Overpass" => {
+                    return Transition::Replace(download(ctx, self.target));
+                }
+                "load a local .osm file" => {
+                    return Transition::Replace(load_file(ctx, self.target));
+                }
+                _ => unreachable!(),
+            }
+        }
+        Transition::Keep
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::PreviousState
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.panel.draw(g);
+    }
+}
+
+fn download(ctx: &mut EventCtx, target: Target) -> Box<dyn State<App>> {
+    let url = target.overpass_url();
+    let (_, outer_progress_rx) = futures_channel::mpsc::channel(1);
+    let (_, inner_progress_rx) = futures_channel::mpsc::channel(1);
+    FutureLoader::<App, Vec<u8>>::new_state(
+        ctx,
+        Box::pin(async move {
+            let bytes = abstio::http_get(url).await?;
+            let wrap: Box<dyn Send + FnOnce(&App) -> Vec<u8>> = Box::new(move |_: &App| bytes);
+            Ok(wrap)
+        }),
+        outer_progress_rx,
+        inner_progress_rx,
+        "Downloading from Overpass",
+        Box::new(move |ctx, app, result| {
+            let result = result.and_then(|bytes| {
+                convert_osm::reader::parse(
+                    &bytes,
+                    &app.model.map.gps_bounds,
+                    &mut Timer::throwaway(),
+                )
+            });
+            finish(ctx, app, target, result)
+        }),
+    )
+}
+
+fn load_file(ctx: &mut EventCtx, target: Target) -> Box<dyn State<App>> {
+    FilePicker::new_state(
+        ctx,
+        None,
+        Box::new(move |ctx, app: &mut App, maybe_path| {
+            let path = match maybe_path {
+                Ok(Some(path)) => path,
+                // The user didn't pick a file
+                _ => {
+                    return Transition::Pop;
+                }
+            };
+            // TODO Only OSM XML, not .pbf
+            let result = convert_osm::reader::read(
+                &path,
+                &app.model.map.gps_bounds,
+                &mut Timer::throwaway(),
+            );
+            finish(ctx, app, target, result)
+        }),
+    )
+}
+
+fn finish(
+    ctx: &mut EventCtx,
+    app: &mut App,
+    target: Target,
+    doc: Result<Document>,
+) -> Transition<App> {
+    let result = doc.and_then(|doc| match target {
+        Target::Road(r) => reimport_road(ctx, app, r, doc),
+        Target::Intersection(i) => reimport_intersection(ctx, app, i, doc),
+    });
+    match result {
+        Ok(()) => {
+            app.model.world.handle_mouseover(ctx);
+            Transition::Pop
+        }
+        Err(err) => Transition::Replace(PopupMsg::new_state(
+            ctx,
+            "Error",
+            vec![format!("Couldn't re-import {}: {}", target.describe(), err)],
+        )),
+    }
+}
+
+fn reimport_road(ctx: &EventCtx, app: &mut App, id: OriginalRoad, doc: Document) -> Result<()> {
+    let way = match doc.ways.get(&id.osm_way_id) {
+        Some(way) => way,
+        None => bail!("{} isn't in the OSM data", id.osm_way_id),
+    };
+    // The way was split at intersections, so just find this road's piece of it.
+    let start = way.nodes.iter().position(|n| *n == id.i1);
+    let end = way.nodes.iter().rposition(|n| *n == id.i2);
+    let (start, end) = match (start, end) {
+        (Some(start), Some(end)) if start < end => (start, end),
+        _ => bail!(
+            "{} doesn't go from {} to {} anymore. Regenerate the whole map instead.",
+            id.osm_way_id,
+            id.i1,
+            id.i2
+        ),
+    };
+
+    let old = &app.model.map.roads[&id];
+    let mut pts = way.pts[start..=end].to_vec();
+    // Keep the road attached to the intersections, even if they were moved
+    pts[0] = app.model.map.intersections[&id.i1].point;
+    *pts.last_mut().unwrap() = app.model.map.intersections[&id.i2].point;
+
+    let tags = merge_tags(&old.osm_tags, way.tags.clone());
+    app.model.replace_r(ctx, id, pts, tags);
+    Ok(())
+}
+
+/// Use the fresh tags, but keep anything the importer added. If sidewalks or parking were inferred
+/// originally and still aren't in OSM, keep the inferred values.
+fn merge_tags(old: &Tags, mut tags: Tags) -> Tags {
+    for (k, v) in old.inner() {
+        if k.starts_with("abst:") {
+            tags.insert(k, v);
+        }
+    }
+
+    if old.contains_key(osm::INFERRED_SIDEWALKS) {
+        if tags.contains_key(osm::SIDEWALK) {
+            tags.remove(osm::INFERRED_SIDEWALKS);
+        } else if let Some(v) = old.get(osm::SIDEWALK) {
+            tags.insert(osm::SIDEWALK, v);
+        }
+    }
+
+    if old.contains_key(osm::INFERRED_PARKING) {
+        let parking_keys = [osm::PARKING_LEFT, osm::PARKING_RIGHT, osm::PARKING_BOTH];
+        if parking_keys.iter().any(|k| tags.contains_key(k)) {
+            tags.remove(osm::INFERRED_PARKING);
+        } else {
+            for k in parking_keys {
+                if let Some(v) = old.get(k) {
+                    tags.insert(k, v);
+                }
+            }
+        }
+    }
+
+    tags
+}
+
+fn reimport_intersection(
+    ctx: &EventCtx,
+    app: &mut App,
+    id: osm::NodeID,
+    doc: Document,
+) -> Result<()> {
+    let node = match doc.nodes.get(&id) {
+        Some(node) => node,
+        None => bail!("{} isn't in the OSM data", id),
+    };
+    app.model.move_i(ctx, id, node.pt);
+
+    let current = app.model.map.intersections[&id].intersection_type;
+    let intersection_type = match current {
+        // These aren't from the node's tags
+        IntersectionType::Border | IntersectionType::Construction => current,
+        // Signals can also be tagged on the incoming ways, which weren't downloaded, so don't
+        // remove an existing signal.
+        IntersectionType::TrafficSignal => IntersectionType::TrafficSignal,
+        IntersectionType::StopSign => {
+            if node.tags.is(osm::HIGHWAY, "traffic_signals") {
+                IntersectionType::TrafficSignal
+            } else {
+                IntersectionType::StopSign
+            }
+        }
+    };
+    let stop_sign_placement = if node.tags.is(osm::HIGHWAY, "stop") && node.tags.is("stop", "all") {
+        StopSignPlacement::AllWay
+    } else {
+        StopSignPlacement::Automatic
+    };
+    app.model
+        .set_i_control(ctx, id, intersection_type, stop_sign_placement);
+    Ok(())
+}
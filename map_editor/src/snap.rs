@@ -0,0 +1,81 @@
+//! Optional drawing aids for synthetic maps. Points can be rounded to a grid, kept at multiples of
+//! 45 degrees from the previous point, and pulled onto existing points nearby.
+
+use geom::{Angle, Distance, Pt2D};
+use map_model::osm;
+use map_model::raw::RawMap;
+
+/// How close the cursor has to be to an existing point to snap onto it
+const SNAP_TO_POINT_DISTANCE: Distance = Distance::const_meters(5.0);
+
+#[derive(Default)]
+pub struct Snapping {
+    /// Round coordinates to multiples of this
+    pub grid: Option<Distance>,
+    /// Keep segments from the previous point at multiples of 45 degrees
+    pub angles: bool,
+    /// Snap onto nearby intersections and road points
+    pub to_points: bool,
+}
+
+impl Snapping {
+    pub fn is_enabled(&self) -> bool {
+        self.grid.is_some() || self.angles || self.to_points
+    }
+
+    /// Adjust a point the user is placing. `anchor` is the point the new segment starts from, if
+    /// there is one. Anything belonging to the `moving` intersection is ignored when looking for
+    /// existing points.
+    pub fn snap(
+        &self,
+        map: &RawMap,
+        pt: Pt2D,
+        anchor: Option<Pt2D>,
+        moving: Option<osm::NodeID>,
+    ) -> Pt2D {
+        if self.to_points {
+            if let Some(existing) = closest_existing_pt(map, pt, moving) {
+                return existing;
+            }
+        }
+
+        if let (true, Some(anchor)) = (self.angles, anchor) {
+            let angle = anchor.angle_to(pt).normalized_degrees();
+            let angle = Angle::degrees((angle / 45.0).round() * 45.0);
+            let mut dist = anchor.dist_to(pt);
+            if let Some(grid) = self.grid {
+                // Keep the angle exact and round the length instead
+                dist = grid * (dist / grid).round().max(1.0);
+            }
+            return anchor.project_away(dist, angle);
+        }
+
+        if let Some(grid) = self.grid {
+            let grid = grid.inner_meters();
+            return Pt2D::new(
+                (pt.x() / grid).round() * grid,
+                (pt.y() / grid).round() * grid,
+            );
+        }
+
+        pt
+    }
+}
+
+fn closest_existing_pt(map: &RawMap, pt: Pt2D, moving: Option<osm::NodeID>) -> Option<Pt2D> {
+    let mut candidates = Vec::new();
+    for (id, i) in &map.intersections {
+        if Some(*id) != moving {
+            candidates.push(i.point);
+        }
+    }
+    for (id, r) in &map.roads {
+        if Some(id.i1) != moving && Some(id.i2) != moving {
+            candidates.extend(r.center_points.iter().cloned());
+        }
+    }
+    candidates
+        .into_iter()
+        .filter(|candidate| candidate.dist_to(pt) <= SNAP_TO_POINT_DISTANCE)
+        .min_by_key(|candidate| candidate.dist_to(pt))
+}
@@ -0,0 +1,345 @@
+use abstutil::Tags;
+use geom::Percent;
+use map_gui::tools::PopupMsg;
+use map_model::osm;
+use map_model::raw::OriginalRoad;
+use widgetry::{
+    Color, DrawBaselayer, EventCtx, GfxCtx, HorizontalAlignment, Key, Line, Outcome, Panel, State,
+    Text, TextBox, TextExt, Transition, VerticalAlignment, Widget,
+};
+
+use crate::app::App;
+
+/// Keys that are often worth adding to a road, with some typical values for each
+const COMMON_TAGS: &[(&str, &[&str])] = &[
+    (
+        osm::HIGHWAY,
+        &[
+            "motorway",
+            "trunk",
+            "primary",
+            "secondary",
+            "tertiary",
+            "unclassified",
+            "residential",
+            "service",
+            "living_street",
+            "pedestrian",
+            "footway",
+            "cycleway",
+        ],
+    ),
+    ("lanes", &["1", "2", "3", "4"]),
+    ("lanes:forward", &["1", "2", "3"]),
+    ("lanes:backward", &["0", "1", "2", "3"]),
+    ("lanes:both_ways", &["1"]),
+    (osm::MAXSPEED, &["20 mph", "25 mph", "30 mph", "30", "50"]),
+    ("oneway", &["yes", "no", "-1"]),
+    (
+        osm::SIDEWALK,
+        &["both", "left", "right", "none", "separate"],
+    ),
+    ("cycleway:both", &["lane", "track", "shared_lane", "no"]),
+    (
+        osm::PARKING_BOTH,
+        &[
+            "parallel",
+            "diagonal",
+            "perpendicular",
+            "no_parking",
+            "no_stopping",
+        ],
+    ),
+    ("access", &["yes", "private", "destination", "no"]),
+    (osm::NAME, &[]),
+];
+
+/// How many autocomplete suggestions to show at once
+const MAX_SUGGESTIONS: usize = 6;
+
+/// Directly edit the raw OSM tags of one road. Values are checked as they're typed.
+pub struct EditTags {
+    panel: Panel,
+    r: OriginalRoad,
+    // Not applied to the road until the user confirms
+    tags: Tags,
+}
+
+impl EditTags {
+    pub fn new_state(ctx: &mut EventCtx, app: &App, r: OriginalRoad) -> Box<dyn State<App>> {
+        let mut state = EditTags {
+            panel: Panel::empty(ctx),
+            r,
+            tags: app.model.map.roads[&r].osm_tags.clone(),
+        };
+        state.rebuild_panel(ctx, String::new(), String::new());
+        Box::new(state)
+    }
+
+    fn rebuild_panel(&mut self, ctx: &mut EventCtx, new_key: String, new_value: String) {
+        let mut rows = Vec::new();
+        for (k, v) in self.tags.inner() {
+            rows.push(Widget::row(vec![
+                k.as_str().text_widget(ctx).centered_vert(),
+                TextBox::default_widget(ctx, format!("value {}", k), v.clone()),
+                ctx.style()
+                    .btn_plain_destructive
+                    .text("remove")
+                    .build_widget(ctx, format!("remove {}", k)),
+                problem_widget(ctx, check_tag(k, v)).named(format!("problem {}", k)),
+            ]));
+        }
+
+        self.panel = Panel::new_builder(Widget::col(vec![
+            Widget::row(vec![
+                Line("Raw OSM tags").small_heading().into_widget(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            Widget::col(rows),
+            Widget::col(vec![
+                Line("Add a tag").small_heading().into_widget(ctx),
+                Widget::row(vec![
+                    "key".text_widget(ctx).centered_vert(),
+                    TextBox::default_widget(ctx, "new key", new_key.clone()),
+                    "value".text_widget(ctx).centered_vert(),
+                    TextBox::default_widget(ctx, "new value", new_value.clone()),
+                    ctx.style().btn_outline.text("add").build_def(ctx),
+                ]),
+                key_suggestions(ctx, &self.tags, &new_key).named("key suggestions"),
+                value_suggestions(ctx, &new_key, &new_value).named("value suggestions"),
+                problem_widget(ctx, check_new_tag(&new_key, &new_value)).named("new problem"),
+            ])
+            .section(ctx),
+            ctx.style()
+                .btn_solid_primary
+                .text("Apply")
+                .hotkey(Key::Enter)
+                .build_def(ctx),
+        ]))
+        .aligned(HorizontalAlignment::Left, VerticalAlignment::Top)
+        .max_size(Percent::int(50), Percent::int(90))
+        .build(ctx);
+    }
+
+    /// Refresh the suggestions and validation for the tag being added
+    fn new_tag_changed(&mut self, ctx: &mut EventCtx) {
+        let key = self.panel.text_box("new key");
+        let value = self.panel.text_box("new value");
+        let keys = key_suggestions(ctx, &self.tags, &key);
+        self.panel.replace(ctx, "key suggestions", keys);
+        let values = value_suggestions(ctx, &key, &value);
+        self.panel.replace(ctx, "value suggestions", values);
+        let problem = problem_widget(ctx, check_new_tag(&key, &value));
+        self.panel.replace(ctx, "new problem", problem);
+    }
+}
+
+impl State<App> for EditTags {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition<App> {
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => {
+                if x == "close" {
+                    return Transition::Pop;
+                } else if x == "Apply" {
+                    let problems: Vec<String> = self
+                        .tags
+                        .inner()
+                        .iter()
+                        .filter_map(|(k, v)| check_tag(k, v).map(|p| format!("{}: {}", k, p)))
+                        .collect();
+                    if !problems.is_empty() {
+                        return Transition::Push(PopupMsg::new_state(
+                            ctx,
+                            "Fix these tags first",
+                            problems,
+                        ));
+                    }
+                    let tags = self.tags.clone();
+                    app.model
+                        .edit_r_tags(ctx, &[self.r], |orig| *orig = tags.clone());
+                    app.model.world.handle_mouseover(ctx);
+                    // The road editor underneath shows the old tags
+                    return Transition::Multi(vec![
+                        Transition::Pop,
+                        Transition::Replace(crate::edit::EditRoad::new_state(ctx, app, self.r)),
+                    ]);
+                } else if x == "add" {
+                    let key = self.panel.text_box("new key").trim().to_string();
+                    let value = self.panel.text_box("new value").trim().to_string();
+                    let problem = if key.is_empty() {
+                        Some("the key can't be blank".to_string())
+                    } else {
+                        check_new_tag(&key, &value)
+                    };
+                    if let Some(problem) = problem {
+                        return Transition::Push(PopupMsg::new_state(
+                            ctx,
+                            "Can't add this tag",
+                            vec![problem],
+                        ));
+                    }
+                    self.tags.insert(key, value);
+                    self.rebuild_panel(ctx, String::new(), String::new());
+                } else if let Some(k) = x.strip_prefix("remove ") {
+                    self.tags.remove(k);
+                    let key = self.panel.text_box("new key");
+                    let value = self.panel.text_box("new value");
+                    self.rebuild_panel(ctx, key, value);
+                } else if let Some(k) = x.strip_prefix("suggest key ") {
+                    let key = TextBox::default_widget(ctx, "new key", k.to_string());
+                    self.panel.replace(ctx, "new key", key);
+                    self.new_tag_changed(ctx);
+                } else if let Some(v) = x.strip_prefix("suggest value ") {
+                    let value = TextBox::default_widget(ctx, "new value", v.to_string());
+                    self.panel.replace(ctx, "new value", value);
+                    self.new_tag_changed(ctx);
+                } else {
+                    unreachable!()
+                }
+            }
+            Outcome::Changed(x) => {
+                if x == "new key" || x == "new value" {
+                    self.new_tag_changed(ctx);
+                } else if let Some(k) = x.strip_prefix("value ") {
+                    let k = k.to_string();
+                    let v = self.panel.text_box(&x);
+                    let problem = problem_widget(ctx, check_tag(&k, &v));
+                    self.panel.replace(ctx, &format!("problem {}", k), problem);
+                    self.tags.insert(k, v);
+                }
+            }
+            _ => {
+                ctx.canvas_movement();
+            }
+        }
+
+        Transition::Keep
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::PreviousState
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.panel.draw(g);
+    }
+}
+
+fn problem_widget(ctx: &EventCtx, problem: Option<String>) -> Widget {
+    match problem {
+        Some(problem) => Text::from(Line(problem).fg(Color::RED)).into_widget(ctx),
+        None => Text::new().into_widget(ctx),
+    }
+}
+
+fn key_suggestions(ctx: &EventCtx, tags: &Tags, typed: &str) -> Widget {
+    let typed = typed.trim();
+    Widget::row(
+        COMMON_TAGS
+            .iter()
+            .map(|(k, _)| *k)
+            .filter(|k| *k != typed && k.starts_with(typed) && !tags.contains_key(k))
+            .take(MAX_SUGGESTIONS)
+            .map(|k| {
+                ctx.style()
+                    .btn_plain
+                    .text(k)
+                    .build_widget(ctx, format!("suggest key {}", k))
+            })
+            .collect(),
+    )
+}
+
+fn value_suggestions(ctx: &EventCtx, key: &str, typed: &str) -> Widget {
+    let typed = typed.trim();
+    let values = COMMON_TAGS
+        .iter()
+        .find(|(k, _)| *k == key.trim())
+        .map(|(_, values)| *values)
+        .unwrap_or(&[]);
+    Widget::row(
+        values
+            .iter()
+            .filter(|v| **v != typed && v.starts_with(typed))
+            .take(MAX_SUGGESTIONS)
+            .map(|v| {
+                ctx.style()
+                    .btn_plain
+                    .text(*v)
+                    .build_widget(ctx, format!("suggest value {}", v))
+            })
+            .collect(),
+    )
+}
+
+/// Before anything's been typed, don't complain about blank fields
+fn check_new_tag(key: &str, value: &str) -> Option<String> {
+    let key = key.trim();
+    let value = value.trim();
+    if key.is_empty() && value.is_empty() {
+        return None;
+    }
+    if key.is_empty() {
+        return Some("the key can't be blank".to_string());
+    }
+    if key.contains(' ') {
+        return Some("keys can't contain spaces".to_string());
+    }
+    check_tag(key, value)
+}
+
+/// Describes what's wrong with a tag's value, if the importer wouldn't understand it.
+fn check_tag(key: &str, value: &str) -> Option<String> {
+    if value.trim().is_empty() {
+        return Some("the value can't be blank".to_string());
+    }
+    if value != value.trim() {
+        return Some("remove the spaces at the beginning or end".to_string());
+    }
+
+    match key {
+        "lanes" | "lanes:forward" | "lanes:both_ways" => match value.parse::<usize>() {
+            Ok(0) => Some("there must be at least one lane".to_string()),
+            Ok(_) => None,
+            Err(_) => Some("must be a whole number".to_string()),
+        },
+        "lanes:backward" => {
+            if value.parse::<usize>().is_err() {
+                Some("must be a whole number".to_string())
+            } else {
+                None
+            }
+        }
+        "oneway" => {
+            if ["yes", "no", "-1", "reversible", "alternating"].contains(&value) {
+                None
+            } else {
+                Some("must be yes, no, or -1".to_string())
+            }
+        }
+        _ if key == osm::MAXSPEED || key.starts_with("maxspeed:") => check_maxspeed(value),
+        _ => None,
+    }
+}
+
+fn check_maxspeed(value: &str) -> Option<String> {
+    if ["none", "walk", "signals"].contains(&value) {
+        return None;
+    }
+    // OSM assumes km/h when no units are specified
+    let number = if let Some(mph) = value.strip_suffix(" mph") {
+        mph
+    } else if ["km/h", "kmh", "kph"]
+        .iter()
+        .any(|unit| value.ends_with(*unit))
+    {
+        return Some("km/h is the default; just write the number, like 50".to_string());
+    } else {
+        value
+    };
+    match number.parse::<f64>() {
+        Ok(x) if x > 0.0 => None,
+        Ok(_) => Some("the speed limit must be positive".to_string()),
+        Err(_) => Some("write a number in km/h like 50, or in mph like 30 mph".to_string()),
+    }
+}
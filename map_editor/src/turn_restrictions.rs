@@ -0,0 +1,223 @@
+use geom::{ArrowCap, Distance, PolyLine, Pt2D};
+use map_model::raw::{OriginalRoad, RawMap, RestrictionType, TurnRestriction};
+use map_model::{osm, TurnType};
+use widgetry::{
+    Choice, Color, DrawBaselayer, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Line,
+    Outcome, Panel, State, Text, TextExt, Transition, VerticalAlignment, Widget,
+};
+
+use crate::app::App;
+
+/// List, add, and remove the simple turn restrictions through one intersection. Any way the
+/// restrictions disconnect roads is shown immediately, and the validation tool checks the same
+/// thing for the whole map.
+pub struct EditTurnRestrictions {
+    i: osm::NodeID,
+    panel: Panel,
+    draw: Drawable,
+}
+
+impl EditTurnRestrictions {
+    pub(crate) fn new_state(ctx: &mut EventCtx, app: &App, i: osm::NodeID) -> Box<dyn State<App>> {
+        let mut state = EditTurnRestrictions {
+            i,
+            panel: Panel::empty(ctx),
+            draw: Drawable::empty(ctx),
+        };
+        state.recalculate(ctx, app);
+        Box::new(state)
+    }
+
+    fn recalculate(&mut self, ctx: &mut EventCtx, app: &App) {
+        let map = &app.model.map;
+        let i = self.i;
+        let restrictions = map.turn_restrictions_at(i);
+
+        let mut col = vec![
+            Widget::row(vec![
+                Line("Turn restrictions").small_heading().into_widget(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            Line(i.to_string()).secondary().into_widget(ctx),
+        ];
+
+        let mut current = Vec::new();
+        if restrictions.is_empty() {
+            current.push("None yet".text_widget(ctx));
+        }
+        for (idx, restriction) in restrictions.iter().enumerate() {
+            current.push(Widget::row(vec![
+                describe(map, i, restriction)
+                    .text_widget(ctx)
+                    .centered_vert(),
+                ctx.style()
+                    .btn_solid_destructive
+                    .icon("system/assets/tools/trash.svg")
+                    .build_widget(ctx, format!("delete restriction {}", idx)),
+            ]));
+        }
+        col.push(Widget::col(current).section(ctx));
+
+        let (incoming, outgoing) = map.vehicle_roads_at(i);
+        if incoming.is_empty() || outgoing.is_empty() {
+            col.push("Vehicles can't pass through here".text_widget(ctx));
+        } else {
+            let road_choices = |roads: &Vec<OriginalRoad>| {
+                roads
+                    .iter()
+                    .map(|r| Choice::new(road_name(map, *r), *r))
+                    .collect::<Vec<_>>()
+            };
+            col.push(
+                Widget::col(vec![
+                    Line("New restriction").small_heading().into_widget(ctx),
+                    Widget::row(vec![
+                        "From".text_widget(ctx).centered_vert(),
+                        Widget::dropdown(ctx, "from", incoming[0], road_choices(&incoming)),
+                    ]),
+                    Widget::row(vec![
+                        "To".text_widget(ctx).centered_vert(),
+                        Widget::dropdown(ctx, "to", outgoing[0], road_choices(&outgoing)),
+                    ]),
+                    Widget::dropdown(
+                        ctx,
+                        "restriction",
+                        RestrictionType::BanTurns,
+                        vec![
+                            Choice::new("ban this movement", RestrictionType::BanTurns),
+                            Choice::new(
+                                "only allow this movement",
+                                RestrictionType::OnlyAllowTurns,
+                            ),
+                        ],
+                    ),
+                    ctx.style().btn_outline.text("add").build_def(ctx),
+                ])
+                .section(ctx),
+            );
+        }
+
+        let problems = map.turn_restriction_problems(i);
+        if !problems.is_empty() {
+            let mut txt = Text::new();
+            for problem in problems {
+                txt.add_line(Line(problem).fg(Color::RED));
+            }
+            col.push(txt.wrap_to_pct(ctx, 25).into_widget(ctx));
+        }
+
+        self.panel = Panel::new_builder(Widget::col(col))
+            .aligned(HorizontalAlignment::Left, VerticalAlignment::Top)
+            .build(ctx);
+
+        // Banned movements are red, and the only allowed ones green
+        let mut batch = GeomBatch::new();
+        for TurnRestriction(from, rt, to) in &restrictions {
+            let color = match rt {
+                RestrictionType::BanTurns => Color::RED,
+                RestrictionType::OnlyAllowTurns => Color::GREEN,
+            };
+            let pts = match (pt_near(map, *from, i), pt_near(map, *to, i)) {
+                (Some(pt1), Some(pt2)) => vec![pt1, map.intersections[&i].point, pt2],
+                _ => continue,
+            };
+            if let Ok(pl) = PolyLine::new(pts) {
+                batch.push(
+                    color.alpha(0.8),
+                    pl.make_arrow(Distance::meters(1.0), ArrowCap::Triangle),
+                );
+            }
+        }
+        self.draw = ctx.upload(batch);
+    }
+}
+
+impl State<App> for EditTurnRestrictions {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition<App> {
+        ctx.canvas_movement();
+
+        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+            match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "add" => {
+                    let from: OriginalRoad = self.panel.dropdown_value("from");
+                    let to: OriginalRoad = self.panel.dropdown_value("to");
+                    let rt: RestrictionType = self.panel.dropdown_value("restriction");
+                    app.model
+                        .map
+                        .add_turn_restriction(TurnRestriction(from, rt, to));
+                    self.recalculate(ctx, app);
+                }
+                x => {
+                    if let Some(idx) = x.strip_prefix("delete restriction ") {
+                        let restriction = app.model.map.turn_restrictions_at(self.i)
+                            [idx.parse::<usize>().unwrap()];
+                        app.model.map.remove_turn_restriction(restriction);
+                        self.recalculate(ctx, app);
+                    } else {
+                        unreachable!()
+                    }
+                }
+            }
+        }
+
+        Transition::Keep
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::PreviousState
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        g.redraw(&self.draw);
+        self.panel.draw(g);
+    }
+}
+
+/// Like "No left turn from Main St onto 2nd Ave"
+fn describe(map: &RawMap, i: osm::NodeID, restriction: &TurnRestriction) -> String {
+    let TurnRestriction(from, rt, to) = restriction;
+    let movement = match map.movement_type(i, *from, *to) {
+        Some(TurnType::Left) => "left turn",
+        Some(TurnType::Right) => "right turn",
+        Some(TurnType::UTurn) => "U-turn",
+        Some(TurnType::Straight) => "going straight",
+        _ => "turn",
+    };
+    let prefix = match rt {
+        RestrictionType::BanTurns => "No",
+        RestrictionType::OnlyAllowTurns => "Only",
+    };
+    if from == to {
+        format!("{} {} on {}", prefix, movement, road_name(map, *from))
+    } else {
+        format!(
+            "{} {} from {} onto {}",
+            prefix,
+            movement,
+            road_name(map, *from),
+            road_name(map, *to)
+        )
+    }
+}
+
+fn road_name(map: &RawMap, r: OriginalRoad) -> String {
+    match map.roads[&r].osm_tags.get(osm::NAME) {
+        Some(name) => format!("{} ({})", name, r.osm_way_id),
+        None => r.osm_way_id.to_string(),
+    }
+}
+
+/// A point along a road, a short distance away from one of its intersections
+fn pt_near(map: &RawMap, r: OriginalRoad, i: osm::NodeID) -> Option<Pt2D> {
+    let pl = PolyLine::new(map.roads[&r].center_points.clone()).ok()?;
+    let pl = if r.i1 == i { pl } else { pl.reversed() };
+    let dist = if pl.length() > Distance::meters(20.0) {
+        Distance::meters(10.0)
+    } else {
+        pl.length() / 2.0
+    };
+    pl.dist_along(dist).ok().map(|(pt, _)| pt)
+}
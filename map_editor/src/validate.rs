@@ -0,0 +1,344 @@
+//! Find problems in a RawMap that would make the conversion to a full Map skip things or crash,
+//! and list them so each one can be fixed. The checks mirror assumptions the importer makes.
+
+use std::collections::BTreeMap;
+
+use abstutil::Tags;
+use geom::{Circle, Distance, Percent, PolyLine, Pt2D};
+use map_model::raw::RawMap;
+use map_model::{osm, IntersectionType};
+use widgetry::{
+    Color, DrawBaselayer, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Line,
+    Outcome, Panel, State, TextExt, Transition, VerticalAlignment, Widget,
+};
+
+use crate::app::App;
+
+pub struct Problem {
+    pub pt: Pt2D,
+    pub description: String,
+}
+
+/// Returns every problem found, roads first.
+pub fn find_problems(map: &RawMap) -> Vec<Problem> {
+    let mut problems = Vec::new();
+
+    for (id, road) in &map.roads {
+        let mut problem = |description: String| {
+            let pt = road
+                .center_points
+                .get(0)
+                .cloned()
+                .or_else(|| map.intersections.get(&id.i1).map(|i| i.point))
+                .unwrap_or_else(|| Pt2D::new(0.0, 0.0));
+            problems.push(Problem {
+                pt,
+                description: format!("{}: {}", id, description),
+            });
+        };
+
+        for i in [id.i1, id.i2] {
+            if !map.intersections.contains_key(&i) {
+                problem(format!("references {}, which doesn't exist", i));
+            }
+        }
+        if id.i1 == id.i2 {
+            problem("is a loop, and the importer skips it".to_string());
+        }
+
+        if road.center_points.len() < 2 {
+            problem("has fewer than 2 points".to_string());
+        } else if road.center_points.windows(2).any(|pair| pair[0] == pair[1]) {
+            problem("has duplicate adjacent points".to_string());
+        } else if road.length() == Distance::ZERO {
+            problem("has zero length".to_string());
+        } else if let Err(err) = PolyLine::new(road.center_points.clone()) {
+            problem(format!("has broken geometry: {}", err));
+        }
+
+        if let Some(err) = check_lane_tags(&road.osm_tags) {
+            problem(err);
+        } else if let Ok((_, width)) = road.get_geometry(*id, &map.config) {
+            if width == Distance::ZERO {
+                problem("doesn't have any lanes".to_string());
+            }
+        }
+    }
+
+    let mut roads_per_intersection: BTreeMap<osm::NodeID, usize> = BTreeMap::new();
+    for id in map.roads.keys() {
+        *roads_per_intersection.entry(id.i1).or_insert(0) += 1;
+        *roads_per_intersection.entry(id.i2).or_insert(0) += 1;
+    }
+    for (id, i) in &map.intersections {
+        let num_roads = roads_per_intersection.get(id).cloned().unwrap_or(0);
+        let description = if num_roads == 0 {
+            "isn't connected to any roads"
+        } else if i.intersection_type == IntersectionType::Border && num_roads != 1 {
+            "is a border, but is connected to more than one road"
+        } else {
+            continue;
+        };
+        problems.push(Problem {
+            pt: i.point,
+            description: format!("{} {}", id, description),
+        });
+    }
+    for (id, i) in &map.intersections {
+        for description in map.turn_restriction_problems(*id) {
+            problems.push(Problem {
+                pt: i.point,
+                description,
+            });
+        }
+    }
+
+    problems
+}
+
+/// The lane tags the editor writes must be counts, and the directions must add up.
+fn check_lane_tags(tags: &Tags) -> Option<String> {
+    let mut counts = BTreeMap::new();
+    for key in ["lanes", "lanes:forward", "lanes:backward"] {
+        if let Some(value) = tags.get(key) {
+            match value.parse::<usize>() {
+                Ok(n) => {
+                    counts.insert(key, n);
+                }
+                Err(_) => {
+                    return Some(format!("has {}={}, which isn't a number", key, value));
+                }
+            }
+        }
+    }
+    if let (Some(total), Some(fwd), Some(back)) = (
+        counts.get("lanes"),
+        counts.get("lanes:forward"),
+        counts.get("lanes:backward"),
+    ) {
+        if fwd + back != *total {
+            return Some(format!(
+                "has lanes={}, but lanes:forward={} and lanes:backward={}",
+                total, fwd, back
+            ));
+        }
+    }
+    None
+}
+
+pub struct ValidateMap {
+    panel: Panel,
+    problems: Vec<Problem>,
+    highlight: Drawable,
+}
+
+impl ValidateMap {
+    pub fn new_state(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        let problems = find_problems(&app.model.map);
+
+        let mut col = vec![Widget::row(vec![
+            Line(format!("{} problems", problems.len()))
+                .small_heading()
+                .into_widget(ctx),
+            ctx.style().btn_close_widget(ctx),
+        ])];
+        if problems.is_empty() {
+            col.push("The importer shouldn't have any trouble with this map".text_widget(ctx));
+        }
+        for (idx, problem) in problems.iter().enumerate() {
+            col.push(
+                ctx.style()
+                    .btn_plain
+                    .text(&problem.description)
+                    .build_widget(ctx, idx.to_string()),
+            );
+        }
+        col.push(ctx.style().btn_outline.text("check again").build_def(ctx));
+
+        Box::new(ValidateMap {
+            panel: Panel::new_builder(Widget::col(col))
+                .aligned(HorizontalAlignment::Left, VerticalAlignment::Top)
+                .max_size(Percent::int(30), Percent::int(80))
+                .build(ctx),
+            problems,
+            highlight: Drawable::empty(ctx),
+        })
+    }
+}
+
+impl State<App> for ValidateMap {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition<App> {
+        ctx.canvas_movement();
+
+        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+            match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "check again" => {
+                    return Transition::Replace(ValidateMap::new_state(ctx, app));
+                }
+                x => {
+                    let pt = self.problems[x.parse::<usize>().unwrap()].pt;
+                    ctx.canvas.cam_zoom = ctx.canvas.cam_zoom.max(2.0);
+                    ctx.canvas.center_on_map_pt(pt);
+                    self.highlight = ctx.upload(GeomBatch::from(vec![(
+                        Color::RED.alpha(0.5),
+                        Circle::new(pt, Distance::meters(15.0)).to_polygon(),
+                    )]));
+                }
+            }
+        }
+
+        Transition::Keep
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::PreviousState
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        g.redraw(&self.highlight);
+        self.panel.draw(g);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use abstio::MapName;
+    use map_model::raw::{OriginalRoad, RawIntersection, RawRoad};
+    use map_model::StopSignPlacement;
+
+    use super::*;
+
+    fn intersection(x: f64, y: f64, intersection_type: IntersectionType) -> RawIntersection {
+        RawIntersection {
+            point: Pt2D::new(x, y),
+            intersection_type,
+            stop_sign_placement: StopSignPlacement::Automatic,
+            elevation: Distance::ZERO,
+            trim_roads_for_merging: BTreeMap::new(),
+            midblock_crossing: false,
+        }
+    }
+
+    fn road(pts: Vec<(f64, f64)>, kv: Vec<&str>) -> RawRoad {
+        let mut osm_tags = Tags::empty();
+        osm_tags.insert(osm::HIGHWAY, "residential");
+        for pair in kv {
+            let parts = pair.split('=').collect::<Vec<_>>();
+            osm_tags.insert(parts[0], parts[1]);
+        }
+        RawRoad {
+            center_points: pts.into_iter().map(|(x, y)| Pt2D::new(x, y)).collect(),
+            osm_tags,
+            turn_restrictions: Vec::new(),
+            complicated_turn_restrictions: Vec::new(),
+            percent_incline: 0.0,
+            crosswalk_forward: false,
+            crosswalk_backward: false,
+        }
+    }
+
+    #[test]
+    fn test_find_problems() {
+        let mut map = RawMap::blank(MapName::new("zz", "test", "validate"));
+        for (id, x, y, intersection_type) in [
+            (1, 0.0, 0.0, IntersectionType::StopSign),
+            (2, 100.0, 0.0, IntersectionType::StopSign),
+            (3, 200.0, 0.0, IntersectionType::StopSign),
+            (4, 0.0, 100.0, IntersectionType::Border),
+        ] {
+            map.intersections
+                .insert(osm::NodeID(id), intersection(x, y, intersection_type));
+        }
+        for (way, i1, i2, pts, kv) in [
+            // Fine
+            (1, 1, 2, vec![(0.0, 0.0), (100.0, 0.0)], vec![]),
+            (2, 1, 4, vec![(0.0, 0.0), (0.0, 100.0)], vec![]),
+            // Another road at the border
+            (3, 2, 4, vec![(100.0, 0.0), (0.0, 100.0)], vec![]),
+            (
+                4,
+                2,
+                2,
+                vec![(100.0, 0.0), (150.0, 50.0), (120.0, 80.0)],
+                vec![],
+            ),
+            (5, 1, 9, vec![(0.0, 0.0), (50.0, 50.0)], vec![]),
+            (
+                6,
+                2,
+                1,
+                vec![(100.0, 0.0), (100.0, 0.0), (0.0, 0.0)],
+                vec![],
+            ),
+            (7, 1, 2, vec![(0.0, 0.0), (100.0, 0.0)], vec!["lanes=two"]),
+            (
+                8,
+                2,
+                1,
+                vec![(100.0, 0.0), (0.0, 0.0)],
+                vec!["lanes=3", "lanes:forward=1", "lanes:backward=1"],
+            ),
+        ] {
+            map.roads
+                .insert(OriginalRoad::new(way, (i1, i2)), road(pts, kv));
+        }
+
+        let problems: Vec<String> = find_problems(&map)
+            .into_iter()
+            .map(|p| p.description)
+            .collect();
+        let expected = vec![
+            "is a loop",
+            "doesn't exist",
+            "duplicate adjacent points",
+            "lanes=two, which isn't a number",
+            "but lanes:forward=1 and lanes:backward=1",
+            "isn't connected to any roads",
+            "is a border, but is connected to more than one road",
+        ];
+        assert_eq!(problems.len(), expected.len(), "{:?}", problems);
+        for (problem, expected) in problems.iter().zip(expected) {
+            assert!(
+                problem.contains(expected),
+                "{} should mention {}",
+                problem,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_check_lane_tags() {
+        let tags = |kv: Vec<(&str, &str)>| {
+            let mut tags = Tags::empty();
+            for (k, v) in kv {
+                tags.insert(k, v);
+            }
+            tags
+        };
+        assert_eq!(check_lane_tags(&tags(vec![])), None);
+        assert_eq!(
+            check_lane_tags(&tags(vec![
+                ("lanes", "3"),
+                ("lanes:forward", "2"),
+                ("lanes:backward", "1")
+            ])),
+            None
+        );
+        // Without all three, there's nothing to add up
+        assert_eq!(
+            check_lane_tags(&tags(vec![("lanes", "3"), ("lanes:forward", "5")])),
+            None
+        );
+        assert!(check_lane_tags(&tags(vec![("lanes:backward", "-1")])).is_some());
+        assert!(check_lane_tags(&tags(vec![
+            ("lanes", "2"),
+            ("lanes:forward", "2"),
+            ("lanes:backward", "1")
+        ]))
+        .is_some());
+    }
+}
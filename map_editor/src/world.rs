@@ -2,9 +2,7 @@ use std::collections::HashMap;
 use std::fmt::Debug;
 use std::hash::Hash;
 
-use aabb_quadtree::{ItemId, QuadTree};
-
-use geom::{Bounds, Circle, Distance, Polygon, Pt2D};
+use geom::{Circle, Distance, Polygon, QuadTree};
 use widgetry::{Color, Drawable, EventCtx, GeomBatch, GfxCtx};
 
 pub trait ObjectID: Clone + Copy + Debug + Eq + Hash {
@@ -29,7 +27,6 @@ impl<ID: ObjectID> Object<ID> {
 struct WorldObject {
     unioned_polygon: Polygon,
     draw: Drawable,
-    quadtree_id: ItemId,
 }
 
 pub struct World<ID: ObjectID> {
@@ -45,17 +42,14 @@ impl<ID: ObjectID> World<ID> {
             // Force the quadtree to support any possible positions. Especially when creating
             // synthetic maps, the bounds change, but updating the quadtree is nontrivial. But they
             // have to be non-negative.
-            quadtree: QuadTree::default(
-                Bounds::from(&[Pt2D::new(0.0, 0.0), Pt2D::new(std::f64::MAX, std::f64::MAX)])
-                    .as_bbox(),
-            ),
+            quadtree: QuadTree::unbounded(),
             current_selection: None,
         }
     }
 
     pub fn draw<F: Fn(ID) -> bool>(&self, g: &mut GfxCtx, show: F) {
         let mut objects: Vec<ID> = Vec::new();
-        for &(id, _, _) in &self.quadtree.query(g.get_screen_bounds().as_bbox()) {
+        for id in self.quadtree.query(g.get_screen_bounds()) {
             if show(*id) {
                 objects.push(*id);
             }
@@ -83,11 +77,10 @@ impl<ID: ObjectID> World<ID> {
         };
 
         let mut objects: Vec<ID> = Vec::new();
-        for &(id, _, _) in &self.quadtree.query(
-            Circle::new(cursor, Distance::meters(3.0))
-                .get_bounds()
-                .as_bbox(),
-        ) {
+        for id in self
+            .quadtree
+            .query(Circle::new(cursor, Distance::meters(3.0)).get_bounds())
+        {
             objects.push(*id);
         }
         objects.sort_by_key(|id| id.zorder());
@@ -101,6 +94,21 @@ impl<ID: ObjectID> World<ID> {
         }
     }
 
+    /// Returns every object overlapping the area, such as a rectangle or lasso drawn by the user.
+    pub fn query_polygon(&self, area: &Polygon) -> Vec<ID> {
+        let mut objects: Vec<ID> = Vec::new();
+        for id in self.quadtree.query(area.get_bounds()) {
+            if area.intersects(&self.objects[id].unioned_polygon) {
+                objects.push(*id);
+            }
+        }
+        objects
+    }
+
+    pub fn get_unioned_polygon(&self, id: ID) -> Option<&Polygon> {
+        self.objects.get(&id).map(|obj| &obj.unioned_polygon)
+    }
+
     pub fn force_set_selection(&mut self, id: ID) {
         self.current_selection = Some(id);
     }
@@ -112,28 +120,27 @@ impl<ID: ObjectID> World<ID> {
     // TODO This and delete assume the original bounds passed to the quadtree are still valid.
     pub fn add(&mut self, ctx: &EventCtx, obj: Object<ID>) {
         let unioned_polygon =
-            Polygon::union_all(obj.geometry.iter().map(|(_, p)| p.clone()).collect());
+            Polygon::concat_all(obj.geometry.iter().map(|(_, p)| p.clone()).collect());
 
         let bounds = unioned_polygon.get_bounds();
         // This might break, it might not; the quadtree impl is a little unclear.
         if bounds.min_x < 0.0 || bounds.min_y < 0.0 {
             warn!("{:?} has negative coordinates {:?}", obj.id, bounds);
         }
-        let quadtree_id = self.quadtree.insert_with_box(obj.id, bounds.as_bbox());
+        self.quadtree.insert(obj.id, bounds);
         let draw = ctx.upload(GeomBatch::from(obj.geometry));
         self.objects.insert(
             obj.id,
             WorldObject {
                 unioned_polygon,
                 draw,
-                quadtree_id,
             },
         );
     }
 
     pub fn delete(&mut self, id: ID) {
-        let obj = self.objects.remove(&id).unwrap();
-        if self.quadtree.remove(obj.quadtree_id).is_none() {
+        self.objects.remove(&id).unwrap();
+        if !self.quadtree.remove(&id) {
             // TODO This happens for nodes that're totally off-map. They're retained because we
             // defer running the remove_disconnected code, so map_editor can actually debug more
             // stuff.
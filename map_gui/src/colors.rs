@@ -30,6 +30,8 @@ pub enum ColorSchemeChoice {
     NightMode,
     Textured,
     ClassicDayMode,
+    ColorBlind,
+    HighContrast,
 }
 
 impl ColorSchemeChoice {
@@ -39,9 +41,30 @@ impl ColorSchemeChoice {
             Choice::new("night mode", ColorSchemeChoice::NightMode),
             Choice::new("textured", ColorSchemeChoice::Textured),
             Choice::new("classic", ColorSchemeChoice::ClassicDayMode),
+            Choice::new("color-blind friendly", ColorSchemeChoice::ColorBlind),
+            Choice::new("high contrast (dark)", ColorSchemeChoice::HighContrast),
         ]
     }
 
+    /// The scheme to switch to during the day, when the colors follow the time of day.
+    pub fn day(self) -> ColorSchemeChoice {
+        match self {
+            ColorSchemeChoice::NightMode => ColorSchemeChoice::DayMode,
+            x => x,
+        }
+    }
+
+    /// The scheme to switch to at night, when the colors follow the time of day. The accessible
+    /// schemes stay accessible.
+    pub fn night(self) -> ColorSchemeChoice {
+        match self {
+            ColorSchemeChoice::ColorBlind | ColorSchemeChoice::HighContrast => {
+                ColorSchemeChoice::HighContrast
+            }
+            _ => ColorSchemeChoice::NightMode,
+        }
+    }
+
     pub fn parse(x: &str) -> Result<ColorSchemeChoice> {
         let mut options = Vec::new();
         for c in ColorSchemeChoice::choices() {
@@ -139,6 +162,8 @@ pub struct ColorScheme {
     pub bus_body: Color,
     pub bus_label: Color,
     pub train_body: Color,
+    pub truck_body: Color,
+    pub emergency_vehicle_body: Color,
     pub ped_head: Color,
     pub ped_foot: Color,
     pub ped_preparing_bike_body: Color,
@@ -158,6 +183,15 @@ pub struct ColorScheme {
     pub bus_trip: Color,
     pub before_changes: Color,
     pub after_changes: Color,
+
+    // Comparisons and statuses shown in info panels and layers
+    pub better: Color,
+    pub worse: Color,
+    pub trip_future: Color,
+    pub trip_delayed: Color,
+    pub trip_ongoing: Color,
+    pub trip_finished: Color,
+    pub trip_cancelled: Color,
 }
 
 impl ColorScheme {
@@ -167,6 +201,8 @@ impl ColorScheme {
             ColorSchemeChoice::NightMode => ColorScheme::night_mode(),
             ColorSchemeChoice::Textured => ColorScheme::textured(),
             ColorSchemeChoice::ClassicDayMode => ColorScheme::classic(),
+            ColorSchemeChoice::ColorBlind => ColorScheme::color_blind(),
+            ColorSchemeChoice::HighContrast => ColorScheme::high_contrast(),
         };
         cs.scheme = scheme;
         ctx.set_style(cs.gui_style.clone());
@@ -265,6 +301,8 @@ impl ColorScheme {
             bus_body: Color::rgb(50, 133, 117),
             bus_label: Color::rgb(249, 206, 24),
             train_body: hex("#42B6E9"),
+            truck_body: hex("#8C5E3C"),
+            emergency_vehicle_body: hex("#E8221B"),
             ped_head: Color::rgb(139, 69, 19),
             ped_foot: Color::BLACK,
             ped_preparing_bike_body: Color::rgb(255, 0, 144),
@@ -284,6 +322,15 @@ impl ColorScheme {
             bus_trip: Color::rgb(190, 74, 76),
             before_changes: Color::BLUE,
             after_changes: Color::RED,
+
+            // Comparisons and statuses
+            better: Color::GREEN,
+            worse: Color::RED,
+            trip_future: hex("#4CA7E9"),
+            trip_delayed: Color::YELLOW,
+            trip_ongoing: hex("#7FFA4D"),
+            trip_finished: hex("#A3A3A3"),
+            trip_cancelled: hex("#EB3223"),
         }
     }
 
@@ -334,6 +381,101 @@ impl ColorScheme {
         cs
     }
 
+    /// Day mode, but never relying on red versus green to tell things apart, since that's hard
+    /// with the most common forms of color blindness. Colors come from the Okabe-Ito palette.
+    fn color_blind() -> ColorScheme {
+        let mut cs = ColorScheme::day_mode();
+        cs.scheme = ColorSchemeChoice::ColorBlind;
+        cs.use_color_blind_palette();
+        cs
+    }
+
+    /// A dark background with bright map elements and strong outlines. Also color-blind friendly.
+    fn high_contrast() -> ColorScheme {
+        let mut cs = ColorScheme::night_mode();
+        cs.scheme = ColorSchemeChoice::HighContrast;
+        cs.experiment = false;
+        cs.use_color_blind_palette();
+
+        cs.void_background = Color::BLACK;
+        cs.map_background = Color::BLACK.into();
+        cs.grass = hex("#0B2A1C").into();
+        cs.water = hex("#003A63").into();
+        cs.residential_building = Color::grey(0.25);
+        cs.commercial_building = Color::grey(0.4);
+        cs.building_outline = Color::WHITE;
+        cs.parking_lot = Color::grey(0.3);
+
+        cs.driving_lane = Color::grey(0.15);
+        cs.parking_lane = Color::grey(0.25);
+        cs.sidewalk = Color::grey(0.65);
+        cs.sidewalk_lines = Color::grey(0.45);
+        cs.general_road_marking = Color::WHITE;
+        cs.road_center_line = hex("#F0E442");
+        cs.normal_intersection = cs.driving_lane;
+        cs.unzoomed_highway = hex("#E69F00");
+        cs.unzoomed_arterial = hex("#F0E442");
+        cs.unzoomed_residential = Color::grey(0.6);
+        cs.unzoomed_interesting_intersection = Color::WHITE;
+
+        cs.selected = hex("#F0E442").alpha(0.8);
+        cs.current_object = Color::WHITE;
+        cs.perma_selected_object = hex("#56B4E9");
+        cs.route = hex("#F0E442").alpha(0.7);
+        cs
+    }
+
+    /// Replace every color that carries meaning with the Okabe-Ito palette. Good and bad are
+    /// blue and orange instead of green and red.
+    fn use_color_blind_palette(&mut self) {
+        let orange = hex("#E69F00");
+        let sky_blue = hex("#56B4E9");
+        let bluish_green = hex("#009E73");
+        let yellow = hex("#F0E442");
+        let blue = hex("#0072B2");
+        let vermillion = hex("#D55E00");
+        let reddish_purple = hex("#CC79A7");
+
+        self.bus_lane = vermillion;
+        self.bike_lane = blue;
+        self.stop_sign = vermillion;
+        self.signal_protected_turn = blue;
+        self.signal_permitted_turn = sky_blue;
+        self.signal_banned_turn = vermillion;
+        self.signal_spinner = orange;
+
+        self.slowest_intersection = vermillion;
+        self.slower_intersection = orange;
+        self.slow_intersection = yellow;
+
+        self.unzoomed_car = vermillion;
+        self.unzoomed_bike = blue;
+        self.unzoomed_bus = yellow;
+        self.unzoomed_pedestrian = reddish_purple;
+        self.agent_colors = vec![blue, orange, sky_blue, vermillion, bluish_green];
+        self.bus_body = bluish_green;
+        self.bus_label = yellow;
+        self.train_body = sky_blue;
+
+        self.good_to_bad_red = ColorScale(vec![hex("#FCE0B2"), vermillion]);
+        self.good_to_bad_green = ColorScale(vec![hex("#C6E2F5"), blue]);
+        self.bus_layer = sky_blue;
+        self.edits_layer = blue;
+
+        self.bike_trip = blue;
+        self.bus_trip = vermillion;
+        self.before_changes = blue;
+        self.after_changes = orange;
+
+        self.better = sky_blue;
+        self.worse = orange;
+        self.trip_future = sky_blue;
+        self.trip_delayed = yellow;
+        self.trip_ongoing = bluish_green;
+        self.trip_finished = Color::grey(0.64);
+        self.trip_cancelled = vermillion;
+    }
+
     fn day_mode() -> ColorScheme {
         let mut cs = Self::light_background(Style::light_bg());
         cs.scheme = ColorSchemeChoice::DayMode;
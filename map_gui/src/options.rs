@@ -50,6 +50,9 @@ pub struct Options {
     /// Display roads and buildings in an alternate language, if possible. None means to use the
     /// OSM native name.
     pub language: Option<String>,
+    /// Show challenges and cutscenes in this language, if there's a translation. None means
+    /// English.
+    pub ui_language: Option<String>,
     /// How to render geometric units
     pub units: UnitFmt,
 }
@@ -87,6 +90,7 @@ impl Options {
             minimal_controls: false,
             canvas_settings: CanvasSettings::new(),
             language: None,
+            ui_language: None,
             units: UnitFmt {
                 round_durations: true,
                 // TODO Should default be based on the map?
@@ -252,6 +256,18 @@ impl OptionsPanel {
                         }
                         Widget::dropdown(ctx, "language", default, choices)
                     }]),
+                    Widget::row(vec!["Challenge and cutscene language".text_widget(ctx), {
+                        let mut default = None;
+                        let mut choices = vec![Choice::new("English", None)];
+                        for path in abstio::list_dir(abstio::path("system/assets/translations")) {
+                            let lang = abstutil::basename(path);
+                            if app.opts().ui_language == Some(lang.clone()) {
+                                default = Some(lang.clone());
+                            }
+                            choices.push(Choice::new(lang.clone(), Some(lang)));
+                        }
+                        Widget::dropdown(ctx, "ui language", default, choices)
+                    }]),
                     Toggle::choice(
                         ctx,
                         "metric / imperial units",
@@ -368,6 +384,8 @@ impl<A: AppLike> State<A> for OptionsPanel {
 
                     opts.units.metric = self.panel.is_checked("metric / imperial units");
 
+                    opts.ui_language = self.panel.dropdown_value("ui language");
+
                     let language = self.panel.dropdown_value("language");
                     if language != opts.language {
                         opts.language = language;
@@ -76,7 +76,7 @@ impl DrawCar {
                 tip_pt.project_away(CAR_WIDTH / 4.0, tip_angle.rotate_degs(90.0)),
                 corner_pt.project_away(CAR_WIDTH / 2.0, corner_angle.rotate_degs(90.0)),
             ]) {
-                Ok(front) => front.into_polygon().union(thick_line),
+                Ok(front) => front.into_polygon().concat(thick_line),
                 Err(_) => thick_line,
             }
         };
@@ -249,6 +249,10 @@ fn zoomed_color_car(input: &DrawCarInput, sim: &Sim, cs: &ColorScheme) -> Color
         cs.train_body
     } else {
         let color = match input.status {
+            CarStatus::Moving if input.id.vehicle_type == VehicleType::Truck => cs.truck_body,
+            CarStatus::Moving if input.id.vehicle_type == VehicleType::Emergency => {
+                cs.emergency_vehicle_body
+            }
             CarStatus::Moving => cs.rotating_color_agents(input.id.id),
             CarStatus::Parked => cs.parked_car,
         };
@@ -3,7 +3,7 @@ use structopt::StructOpt;
 use abstio::MapName;
 use abstutil::Timer;
 use geom::{Circle, Distance, Duration, Pt2D, Time};
-use map_model::{IntersectionID, Map};
+use map_model::{IntersectionID, Map, MapEdits};
 use sim::Sim;
 use widgetry::{Canvas, EventCtx, GfxCtx, SharedAppState, State, Transition, Warper};
 
@@ -50,6 +50,10 @@ struct Args {
     /// When making a screen recording, enable this option to hide some UI elements
     #[structopt(long)]
     minimal_controls: bool,
+    /// Apply these map edits after loading the map. This can be the name of edits saved for the
+    /// current map, or a proposal.
+    #[structopt(long)]
+    edits: Option<String>,
 }
 
 impl<T: 'static> SimpleApp<T> {
@@ -107,6 +111,9 @@ impl<T: 'static> SimpleApp<T> {
             &app,
             map_name,
             Box::new(move |ctx, app| {
+                if let Some(ref edits_name) = args.edits {
+                    app.apply_edits_by_name(ctx, edits_name);
+                }
                 URLManager::change_camera(ctx, args.cam.as_ref(), app.map().get_gps_bounds());
                 Transition::Clear(init_states(ctx, app))
             }),
@@ -114,6 +121,30 @@ impl<T: 'static> SimpleApp<T> {
         (app, states)
     }
 
+    fn apply_edits_by_name(&mut self, ctx: &mut EventCtx, edits_name: &str) {
+        let mut timer = Timer::new(format!("apply edits {}", edits_name));
+        for path in [
+            abstio::path_edits(self.map.get_name(), edits_name),
+            abstio::path(format!("system/proposals/{}.json", edits_name)),
+        ] {
+            if !abstio::file_exists(&path) {
+                continue;
+            }
+            match MapEdits::load_from_file(&self.map, path, &mut timer) {
+                Ok(edits) => {
+                    self.map.must_apply_edits(edits, &mut timer);
+                    self.map.recalculate_pathfinding_after_edits(&mut timer);
+                    self.draw_map = DrawMap::new(ctx, &self.map, &self.opts, &self.cs, &mut timer);
+                }
+                Err(err) => {
+                    error!("Couldn't load edits {}: {}", edits_name, err);
+                }
+            }
+            return;
+        }
+        error!("Can't find edits {}", edits_name);
+    }
+
     pub fn draw_unzoomed(&self, g: &mut GfxCtx) {
         g.clear(self.cs.void_background);
         g.redraw(&self.draw_map.boundary_polygon);
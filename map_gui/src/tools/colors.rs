@@ -138,7 +138,7 @@ impl ColorLegend {
                     .map(|(idx, color)| ((idx as f64) / ((n - 1) as f64), *color))
                     .collect(),
             }),
-            Polygon::union_all(
+            Polygon::concat_all(
                 (0..n - 1)
                     .map(|i| {
                         Polygon::rectangle(width_each, 32.0).translate((i as f64) * width_each, 0.0)
@@ -291,6 +291,46 @@ pub fn open_browser<I: AsRef<str>>(url: I) {
     let _ = webbrowser::open(url.as_ref());
 }
 
+/// On native, write a file to the current directory. On web, make the browser download it. Returns
+/// the path written, or just the filename on web.
+pub fn save_file(filename: &str, contents: String) -> anyhow::Result<String> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        std::fs::write(filename, contents)?;
+        Ok(filename.to_string())
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    {
+        use wasm_bindgen::JsCast;
+
+        let window = web_sys::window().ok_or(anyhow!("no window?"))?;
+        let document = window.document().ok_or(anyhow!("no document?"))?;
+        let link = document
+            .create_element("a")
+            .map_err(|err| {
+                anyhow!(err
+                    .as_string()
+                    .unwrap_or("create_element failed".to_string()))
+            })?
+            .dyn_into::<web_sys::HtmlElement>()
+            .map_err(|_| anyhow!("<a> isn't an HtmlElement?"))?;
+        let href = format!(
+            "data:text/plain;charset=utf-8,{}",
+            js_sys::encode_uri_component(&contents)
+        );
+        link.set_attribute("href", &href)
+            .and_then(|_| link.set_attribute("download", filename))
+            .map_err(|err| {
+                anyhow!(err
+                    .as_string()
+                    .unwrap_or("set_attribute failed".to_string()))
+            })?;
+        link.click();
+        Ok(filename.to_string())
+    }
+}
+
 /// Returns the path to an executable. Native-only.
 pub fn find_exe(cmd: &str) -> String {
     for dir in [
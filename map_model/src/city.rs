@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 
 use abstio::{CityName, MapName};
@@ -91,8 +93,8 @@ impl City {
         // later in the UI picker.
         districts.sort_by_key(|(_, poly)| poly.get_bounds().width() as usize);
 
-        // Add areas from every map. It's fine if they partly overlap.
-        let mut areas = Vec::new();
+        // Add areas from every map. Neighboring maps overlap, so merge areas of the same type.
+        let mut polygons_per_type: BTreeMap<AreaType, Vec<Polygon>> = BTreeMap::new();
         for path in abstio::list_dir(abstio::path(format!(
             "system/{}/{}/maps",
             city_name.country, city_name.city
@@ -102,13 +104,19 @@ impl City {
                 let pts = map.gps_bounds.convert_back(area.polygon.points());
                 // TODO Holes in the polygons get lost
                 if let Ok(ring) = Ring::new(gps_bounds.convert(&pts)) {
-                    areas.push((
-                        area.area_type,
-                        ring.into_polygon().simplify(POLYGON_EPSILON),
-                    ));
+                    polygons_per_type
+                        .entry(area.area_type)
+                        .or_insert_with(Vec::new)
+                        .push(ring.into_polygon());
                 }
             }
         }
+        let mut areas = Vec::new();
+        for (area_type, polygons) in polygons_per_type {
+            for polygon in Polygon::union_all(polygons) {
+                areas.push((area_type, polygon.simplify(POLYGON_EPSILON)));
+            }
+        }
 
         City {
             name: city_name.clone(),
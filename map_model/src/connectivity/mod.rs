@@ -8,10 +8,14 @@ use petgraph::graphmap::DiGraphMap;
 
 use geom::Duration;
 
-pub use self::walking::{all_walking_costs_from, WalkingOptions};
+pub use self::regions::MapRegions;
+pub use self::walking::{
+    all_walking_costs_from, all_walking_costs_to, walking_route, WalkingOptions,
+};
 pub use crate::pathfind::{vehicle_cost, WalkingNode};
-use crate::{BuildingID, DirectedRoadID, IntersectionID, LaneID, Map, PathConstraints};
+use crate::{BuildingID, DirectedRoadID, IntersectionID, LaneID, Map, MovementID, PathConstraints};
 
+mod regions;
 mod walking;
 
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
@@ -65,6 +69,29 @@ pub fn all_vehicle_costs_from(
     starts: Vec<Spot>,
     time_limit: Duration,
     constraints: PathConstraints,
+) -> HashMap<BuildingID, Duration> {
+    vehicle_costs(map, starts, time_limit, constraints, false)
+}
+
+/// The reverse of `all_vehicle_costs_from`: calculate the cost from all buildings to reach any of
+/// the destinations. One-way roads and turn restrictions make this differ from the forward
+/// direction.
+pub fn all_vehicle_costs_to(
+    map: &Map,
+    ends: Vec<Spot>,
+    time_limit: Duration,
+    constraints: PathConstraints,
+) -> HashMap<BuildingID, Duration> {
+    vehicle_costs(map, ends, time_limit, constraints, true)
+}
+
+/// If `reverse`, search backwards through movements, from the end of each one to its start.
+fn vehicle_costs(
+    map: &Map,
+    starts: Vec<Spot>,
+    time_limit: Duration,
+    constraints: PathConstraints,
+    reverse: bool,
 ) -> HashMap<BuildingID, Duration> {
     assert!(constraints != PathConstraints::Pedestrian);
     // TODO We have a graph of DirectedRoadIDs, but mapping a building to one isn't
@@ -120,6 +147,19 @@ pub fn all_vehicle_costs_from(
         }
     }
 
+    // The map only knows the movements leaving each road, so flip the graph when searching
+    // backwards
+    let mut movements_into: HashMap<DirectedRoadID, Vec<MovementID>> = HashMap::new();
+    if reverse {
+        for r in map.all_roads() {
+            for dr in r.id.both_directions() {
+                for mvmnt in map.get_movements_for(dr, constraints) {
+                    movements_into.entry(mvmnt.to).or_default().push(mvmnt);
+                }
+            }
+        }
+    }
+
     let mut cost_per_node: HashMap<DirectedRoadID, Duration> = HashMap::new();
     while let Some(current) = queue.pop() {
         if cost_per_node.contains_key(&current.node) {
@@ -130,11 +170,19 @@ pub fn all_vehicle_costs_from(
         }
         cost_per_node.insert(current.node, current.cost);
 
-        for mvmnt in map.get_movements_for(current.node, constraints) {
+        let movements = if reverse {
+            movements_into
+                .get(&current.node)
+                .cloned()
+                .unwrap_or_default()
+        } else {
+            map.get_movements_for(current.node, constraints)
+        };
+        for mvmnt in movements {
             queue.push(Item {
                 cost: current.cost
                     + vehicle_cost(mvmnt.from, mvmnt, constraints, map.routing_params(), map),
-                node: mvmnt.to,
+                node: if reverse { mvmnt.from } else { mvmnt.to },
             });
         }
     }
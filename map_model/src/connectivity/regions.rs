@@ -0,0 +1,101 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use crate::{IntersectionID, LaneID, Map, Road, RoadID, Traversable};
+
+/// A partition of the map into regions that only connect to each other over bridges. Within a
+/// region, every intersection can be reached from every other one (ignoring one-ways and lane
+/// types) without crossing a bridge. Bridges and small islands of road between them don't belong
+/// to any region.
+pub struct MapRegions {
+    intersections: BTreeMap<IntersectionID, usize>,
+    num_regions: usize,
+}
+
+impl MapRegions {
+    /// Find regions with at least `min_intersections` intersections.
+    pub fn new(map: &Map, min_intersections: usize) -> MapRegions {
+        let mut component: BTreeMap<IntersectionID, usize> = BTreeMap::new();
+        let mut sizes: Vec<usize> = Vec::new();
+        // Flood from every intersection in order, so the numbering is deterministic
+        for start in map.all_intersections() {
+            if component.contains_key(&start.id) {
+                continue;
+            }
+            let idx = sizes.len();
+            let mut size = 0;
+            let mut queue = VecDeque::new();
+            queue.push_back(start.id);
+            component.insert(start.id, idx);
+            while let Some(i) = queue.pop_front() {
+                size += 1;
+                for r in &map.get_i(i).roads {
+                    let road = map.get_r(*r);
+                    if is_bridge(road) {
+                        continue;
+                    }
+                    for next in [road.src_i, road.dst_i] {
+                        if !component.contains_key(&next) {
+                            component.insert(next, idx);
+                            queue.push_back(next);
+                        }
+                    }
+                }
+            }
+            sizes.push(size);
+        }
+
+        // Renumber the big components, dropping the small ones
+        let mut renumber: BTreeMap<usize, usize> = BTreeMap::new();
+        for (idx, size) in sizes.into_iter().enumerate() {
+            if size >= min_intersections {
+                renumber.insert(idx, renumber.len());
+            }
+        }
+        MapRegions {
+            intersections: component
+                .into_iter()
+                .filter_map(|(i, idx)| renumber.get(&idx).map(|region| (i, *region)))
+                .collect(),
+            num_regions: renumber.len(),
+        }
+    }
+
+    /// How many regions there are. They're numbered from 0.
+    pub fn len(&self) -> usize {
+        self.num_regions
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.num_regions == 0
+    }
+
+    /// The region containing an intersection, if any.
+    pub fn intersection(&self, i: IntersectionID) -> Option<usize> {
+        self.intersections.get(&i).cloned()
+    }
+
+    /// The region containing a road, if any. Bridges don't belong to a region.
+    pub fn road(&self, map: &Map, r: RoadID) -> Option<usize> {
+        let road = map.get_r(r);
+        if is_bridge(road) {
+            return None;
+        }
+        // Both ends of a road that isn't a bridge are always in the same region
+        self.intersection(road.src_i)
+    }
+
+    pub fn lane(&self, map: &Map, l: LaneID) -> Option<usize> {
+        self.road(map, map.get_parent(l).id)
+    }
+
+    pub fn traversable(&self, map: &Map, on: Traversable) -> Option<usize> {
+        match on {
+            Traversable::Lane(l) => self.lane(map, l),
+            Traversable::Turn(t) => self.intersection(t.parent),
+        }
+    }
+}
+
+fn is_bridge(road: &Road) -> bool {
+    road.osm_tags.contains_key("bridge") && !road.osm_tags.is("bridge", "no")
+}
@@ -2,17 +2,24 @@ use std::cmp::Ordering;
 use std::collections::{BinaryHeap, HashMap, HashSet};
 
 use abstutil::MultiMap;
-use geom::{Duration, Speed};
+use geom::{Distance, Duration, Speed};
 
 use crate::connectivity::Spot;
 use crate::pathfind::{zone_cost, WalkingNode};
-use crate::{BuildingID, Lane, LaneType, Map, PathConstraints, PathStep};
+use crate::{
+    BuildingID, Direction, Lane, LaneType, Map, PathConstraints, PathStep, Position, Turn,
+};
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub struct WalkingOptions {
     /// If true, allow walking on shoulders.
     pub allow_shoulders: bool,
     pub walking_speed: Speed,
+    /// If true, walking up steep roads is slower, using the road's incline. If false, assume every
+    /// road is flat.
+    pub consider_elevation: bool,
+    /// If true, avoid stairs, steep grades, and crossings without curb ramps.
+    pub wheelchair: bool,
 }
 
 impl WalkingOptions {
@@ -20,6 +27,18 @@ impl WalkingOptions {
         WalkingOptions {
             allow_shoulders: true,
             walking_speed: WalkingOptions::default_speed(),
+            consider_elevation: true,
+            wheelchair: false,
+        }
+    }
+
+    /// Somebody using a manual wheelchair, who needs sidewalks, gentle grades, and curb ramps
+    pub fn wheelchair() -> WalkingOptions {
+        WalkingOptions {
+            allow_shoulders: false,
+            walking_speed: WalkingOptions::common_speeds()[1].1,
+            consider_elevation: true,
+            wheelchair: true,
         }
     }
 
@@ -34,6 +53,34 @@ impl WalkingOptions {
     pub fn default_speed() -> Speed {
         WalkingOptions::common_speeds()[0].1
     }
+
+    fn can_walk_along(&self, lane: &Lane, map: &Map) -> bool {
+        (self.allow_shoulders || lane.lane_type != LaneType::Shoulder)
+            && (!self.wheelchair || map.get_r(lane.id.road).is_wheelchair_accessible())
+    }
+
+    fn can_take_turn(&self, turn: &Turn, map: &Map) -> bool {
+        if !self.wheelchair || !turn.turn_type.pedestrian_crossing() {
+            return true;
+        }
+        // Check the curbs on both sides of the crossing
+        [turn.id.src, turn.id.dst].into_iter().all(|l| {
+            let road = map.get_r(l.road);
+            road.has_curb_ramps(if road.dst_i == turn.id.parent {
+                Direction::Fwd
+            } else {
+                Direction::Back
+            })
+        })
+    }
+
+    fn speed_along(&self, step: PathStep, map: &Map) -> Speed {
+        if self.consider_elevation {
+            step.max_speed_along(Some(self.walking_speed), PathConstraints::Pedestrian, map)
+        } else {
+            self.walking_speed
+        }
+    }
 }
 
 #[derive(PartialEq, Eq)]
@@ -69,6 +116,30 @@ pub fn all_walking_costs_from(
     starts: Vec<Spot>,
     time_limit: Duration,
     opts: WalkingOptions,
+) -> HashMap<BuildingID, Duration> {
+    walking_costs(map, starts, time_limit, opts, false)
+}
+
+/// The reverse of `all_walking_costs_from`: calculate the cost from all buildings to reach any of
+/// the destinations. The sidewalk graph is the same in both directions, but walking speed up and
+/// down hills isn't.
+pub fn all_walking_costs_to(
+    map: &Map,
+    ends: Vec<Spot>,
+    time_limit: Duration,
+    opts: WalkingOptions,
+) -> HashMap<BuildingID, Duration> {
+    walking_costs(map, ends, time_limit, opts, true)
+}
+
+/// If `reverse`, the search still expands outwards from `starts`, but each sidewalk is costed as if
+/// it's walked in the opposite direction.
+fn walking_costs(
+    map: &Map,
+    starts: Vec<Spot>,
+    time_limit: Duration,
+    opts: WalkingOptions,
+    reverse: bool,
 ) -> HashMap<BuildingID, Duration> {
     let mut queue: BinaryHeap<Item> = BinaryHeap::new();
 
@@ -152,15 +223,14 @@ pub fn all_walking_costs_from(
         };
         let lane = map.get_l(r.must_get_sidewalk(map));
         // Cross the lane
-        if opts.allow_shoulders || lane.lane_type != LaneType::Shoulder {
+        if opts.can_walk_along(lane, map) {
             let sidewalk_len = lane.length();
-            let step = if is_dst_i {
+            let step = if is_dst_i != reverse {
                 PathStep::ContraflowLane(lane.id)
             } else {
                 PathStep::Lane(lane.id)
             };
-            let speed =
-                step.max_speed_along(Some(opts.walking_speed), PathConstraints::Pedestrian, map);
+            let speed = opts.speed_along(step, map);
             let cross_to_node = WalkingNode::SidewalkEndpoint(r, !is_dst_i);
 
             // We're crossing the sidewalk from one end to the other. If we haven't already found a
@@ -191,17 +261,13 @@ pub fn all_walking_costs_from(
         }
         // All turns from the lane
         for turn in map.get_turns_for(lane.id, PathConstraints::Pedestrian) {
-            if (turn.id.parent == lane.dst_i) != is_dst_i {
+            if (turn.id.parent == lane.dst_i) != is_dst_i || !opts.can_take_turn(turn, map) {
                 continue;
             }
             queue.push(Item {
                 cost: current.cost
-                    + turn.geom.length()
-                        / PathStep::Turn(turn.id).max_speed_along(
-                            Some(opts.walking_speed),
-                            PathConstraints::Pedestrian,
-                            map,
-                        )
+                    + turn.geom.length() / opts.speed_along(PathStep::Turn(turn.id), map)
+                    + turn.unmarked_crossing_delay(map, opts.walking_speed)
                     + zone_cost(turn.id.to_movement(map), PathConstraints::Pedestrian, map),
                 node: WalkingNode::SidewalkEndpoint(
                     map.get_l(turn.id.dst).get_directed_parent(),
@@ -213,3 +279,152 @@ pub fn all_walking_costs_from(
 
     results
 }
+
+#[derive(PartialEq, Eq)]
+struct RouteItem {
+    cost: Duration,
+    node: WalkingNode,
+    /// How this node was reached. No previous node means the route started here.
+    prev: Option<WalkingNode>,
+    step: PathStep,
+}
+impl PartialOrd for RouteItem {
+    fn partial_cmp(&self, other: &RouteItem) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for RouteItem {
+    fn cmp(&self, other: &RouteItem) -> Ordering {
+        // BinaryHeap is a max-heap, so reverse the comparison to get smallest times first.
+        let ord = other.cost.cmp(&self.cost);
+        if ord != Ordering::Equal {
+            return ord;
+        }
+        self.node.cmp(&other.node)
+    }
+}
+
+/// Find the fastest walking route between two positions on sidewalks, respecting `opts`. Unlike
+/// the pathfinder baked into the map, this can avoid stairs, steep grades, and crossings without
+/// curb ramps. Returns the total time and every step along the way, or `None` if there's no route.
+pub fn walking_route(
+    map: &Map,
+    start: Position,
+    end: Position,
+    opts: WalkingOptions,
+) -> Option<(Duration, Vec<PathStep>)> {
+    let start_lane = map.get_l(start.lane());
+    let end_lane = map.get_l(end.lane());
+    if !opts.can_walk_along(start_lane, map) || !opts.can_walk_along(end_lane, map) {
+        return None;
+    }
+
+    // Walking from `from` to `to` along one lane
+    let partial = |lane: &Lane, from: Distance, to: Distance| -> (Duration, PathStep) {
+        let step = if to >= from {
+            PathStep::Lane(lane.id)
+        } else {
+            PathStep::ContraflowLane(lane.id)
+        };
+        let dist = if to >= from { to - from } else { from - to };
+        (dist / opts.speed_along(step, map), step)
+    };
+
+    // The cost, the node the final step leaves from, and the final step
+    let mut best: Option<(Duration, Option<WalkingNode>, PathStep)> = None;
+    if start.lane() == end.lane() {
+        let (cost, step) = partial(start_lane, start.dist_along(), end.dist_along());
+        best = Some((cost, None, step));
+    }
+
+    let mut queue: BinaryHeap<RouteItem> = BinaryHeap::new();
+    let dr = start_lane.get_directed_parent();
+    for (is_dst_i, to) in [(true, start_lane.length()), (false, Distance::ZERO)] {
+        let (cost, step) = partial(start_lane, start.dist_along(), to);
+        queue.push(RouteItem {
+            cost,
+            node: WalkingNode::SidewalkEndpoint(dr, is_dst_i),
+            prev: None,
+            step,
+        });
+    }
+
+    let mut visited: HashMap<WalkingNode, (Option<WalkingNode>, PathStep)> = HashMap::new();
+    while let Some(current) = queue.pop() {
+        if best
+            .map(|(cost, _, _)| current.cost >= cost)
+            .unwrap_or(false)
+        {
+            break;
+        }
+        if visited.contains_key(&current.node) {
+            continue;
+        }
+        visited.insert(current.node, (current.prev, current.step));
+
+        let (r, is_dst_i) = match current.node {
+            WalkingNode::SidewalkEndpoint(r, is_dst_i) => (r, is_dst_i),
+            _ => unreachable!(),
+        };
+        let lane = map.get_l(r.must_get_sidewalk(map));
+        if lane.id == end.lane() {
+            let here = if is_dst_i {
+                lane.length()
+            } else {
+                Distance::ZERO
+            };
+            let (cost, step) = partial(lane, here, end.dist_along());
+            let cost = current.cost + cost;
+            if best
+                .map(|(best_cost, _, _)| cost < best_cost)
+                .unwrap_or(true)
+            {
+                best = Some((cost, Some(current.node), step));
+            }
+        }
+
+        // Cross the lane
+        if opts.can_walk_along(lane, map) {
+            let step = if is_dst_i {
+                PathStep::ContraflowLane(lane.id)
+            } else {
+                PathStep::Lane(lane.id)
+            };
+            queue.push(RouteItem {
+                cost: current.cost + lane.length() / opts.speed_along(step, map),
+                node: WalkingNode::SidewalkEndpoint(r, !is_dst_i),
+                prev: Some(current.node),
+                step,
+            });
+        }
+        // All turns from the lane
+        for turn in map.get_turns_for(lane.id, PathConstraints::Pedestrian) {
+            if (turn.id.parent == lane.dst_i) != is_dst_i || !opts.can_take_turn(turn, map) {
+                continue;
+            }
+            queue.push(RouteItem {
+                cost: current.cost
+                    + turn.geom.length() / opts.speed_along(PathStep::Turn(turn.id), map)
+                    + turn.unmarked_crossing_delay(map, opts.walking_speed)
+                    + zone_cost(turn.id.to_movement(map), PathConstraints::Pedestrian, map),
+                node: WalkingNode::SidewalkEndpoint(
+                    map.get_l(turn.id.dst).get_directed_parent(),
+                    map.get_l(turn.id.dst).dst_i == turn.id.parent,
+                ),
+                prev: Some(current.node),
+                step: PathStep::Turn(turn.id),
+            });
+        }
+    }
+
+    let (cost, mut node, last_step) = best?;
+    let mut steps = vec![last_step];
+    while let Some(n) = node {
+        let (prev, step) = visited[&n];
+        steps.push(step);
+        node = prev;
+    }
+    steps.reverse();
+    Some((cost, steps))
+}
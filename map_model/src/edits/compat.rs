@@ -111,6 +111,13 @@ pub fn upgrade(mut value: Value, map: &Map) -> Result<PermanentMapEdits> {
             .unwrap()
             .insert("version".to_string(), Value::Number(11.into()));
     }
+    if value["version"] == Value::Number(11.into()) {
+        fix_crosswalks(&mut value, map)?;
+        value
+            .as_object_mut()
+            .unwrap()
+            .insert("version".to_string(), Value::Number(12.into()));
+    }
 
     abstutil::from_json(&value.to_string().into_bytes())
 }
@@ -442,6 +449,36 @@ fn remove_vehicle_caps(value: &mut Value) {
     });
 }
 
+// Marking crosswalks added them to EditRoad.
+fn fix_crosswalks(value: &mut Value, map: &Map) -> Result<()> {
+    for orig in value.as_object_mut().unwrap()["commands"]
+        .as_array_mut()
+        .unwrap()
+    {
+        let cmd = orig.as_object_mut().unwrap();
+        if let Some(cmd) = cmd.get_mut("ChangeRoad") {
+            let road_id: OriginalRoad = serde_json::from_value(cmd["r"].clone()).unwrap();
+            let road = map.get_r(map.find_r_by_osm_id(road_id)?);
+            let cmd = cmd.as_object_mut().unwrap();
+
+            for key in ["old", "new"] {
+                let edit = cmd[key].as_object_mut().unwrap();
+                // Before this, crosswalks weren't modifiable, so the original works for both "old"
+                // and "new".
+                edit.insert(
+                    "crosswalk_forward".to_string(),
+                    Value::Bool(road.orig_crosswalk_forward),
+                );
+                edit.insert(
+                    "crosswalk_backward".to_string(),
+                    Value::Bool(road.orig_crosswalk_backward),
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
 // These're old structs used in fix_old_lane_cmds.
 #[derive(Debug, Deserialize)]
 struct OriginalLane {
@@ -8,13 +8,13 @@ use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
 use abstutil::Timer;
-use geom::{Distance, HashablePt2D, Line, Speed, Time};
+use geom::{Distance, HashablePt2D, Line, Polygon, Speed, Time};
 
 pub use self::perma::PermanentMapEdits;
 use crate::make::initial::lane_specs::get_lane_specs_ltr;
 use crate::make::{match_points_to_lanes, snap_driveway, trim_path};
 use crate::{
-    connectivity, AccessRestrictions, BuildingID, BusRouteID, ControlStopSign,
+    connectivity, osm, AccessRestrictions, BuildingID, BusRouteID, ControlStopSign,
     ControlTrafficSignal, Direction, IntersectionID, IntersectionType, LaneID, LaneSpec, LaneType,
     Map, MapConfig, Movement, ParkingLotID, PathConstraints, Pathfinder, Road, RoadID, TurnID,
     Zone,
@@ -61,6 +61,10 @@ pub struct EditRoad {
     pub lanes_ltr: Vec<LaneSpec>,
     pub speed_limit: Speed,
     pub access_restrictions: AccessRestrictions,
+    /// Is there a marked crosswalk near each end of the road? Otherwise people cross there without
+    /// one.
+    pub crosswalk_forward: bool,
+    pub crosswalk_backward: bool,
 }
 
 impl EditRoad {
@@ -69,6 +73,8 @@ impl EditRoad {
             lanes_ltr: get_lane_specs_ltr(&r.osm_tags, cfg),
             speed_limit: r.speed_limit_from_osm(),
             access_restrictions: r.access_restrictions_from_osm(),
+            crosswalk_forward: r.orig_crosswalk_forward,
+            crosswalk_backward: r.orig_crosswalk_backward,
         }
     }
 
@@ -89,7 +95,27 @@ impl EditRoad {
             }
         }
 
+        // Converting parking lanes to something else (or back) changes parking capacity
+        let parking = |edit: &EditRoad| {
+            edit.lanes_ltr
+                .iter()
+                .filter(|spec| spec.lt == LaneType::Parking)
+                .count()
+        };
+        let (parking_before, parking_after) = (parking(other), parking(self));
+
         let mut changes = Vec::new();
+        if parking_after < parking_before {
+            changes.push(format!(
+                "{} parking lane(s) removed",
+                parking_before - parking_after
+            ));
+        } else if parking_after > parking_before {
+            changes.push(format!(
+                "{} parking lane(s) added",
+                parking_after - parking_before
+            ));
+        }
         if lt == 1 {
             changes.push("1 lane type".to_string());
         } else if lt > 1 {
@@ -111,9 +137,51 @@ impl EditRoad {
         if self.access_restrictions != other.access_restrictions {
             changes.push("access restrictions".to_string());
         }
+        if self.crosswalk_forward != other.crosswalk_forward
+            || self.crosswalk_backward != other.crosswalk_backward
+        {
+            changes.push("crosswalks".to_string());
+        }
         changes
     }
 
+    /// Reverses the direction of the whole road. The order of lanes is mirrored too, so everything
+    /// stays on the correct side for the direction it now points.
+    pub fn reverse_direction(&mut self) {
+        self.lanes_ltr.reverse();
+        for spec in &mut self.lanes_ltr {
+            spec.dir = spec.dir.opposite();
+        }
+    }
+
+    /// Points every lane in one direction, except for sidewalks and shoulders. Returns false if
+    /// this doesn't change anything.
+    pub fn convert_to_oneway(&mut self, dir: Direction) -> bool {
+        let mut changed = false;
+        for spec in &mut self.lanes_ltr {
+            if !spec.lt.is_walkable() && spec.dir != dir {
+                spec.dir = dir;
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    /// Is there driving traffic in only one direction? If so, which?
+    pub fn oneway_for_driving(&self) -> Option<Direction> {
+        let mut dirs = self
+            .lanes_ltr
+            .iter()
+            .filter(|spec| spec.lt == LaneType::Driving)
+            .map(|spec| spec.dir);
+        let first = dirs.next()?;
+        if dirs.all(|dir| dir == first) {
+            Some(first)
+        } else {
+            None
+        }
+    }
+
     /// Transforms a string describing lane types and directions, like "spddps" and "vv^^^^^", into
     /// an EditRoad. Useful for unit tests.
     pub fn create_for_test(input_lt: &str, input_dir: &str) -> EditRoad {
@@ -135,6 +203,8 @@ impl EditRoad {
                 .collect(),
             speed_limit: Speed::ZERO,
             access_restrictions: AccessRestrictions::new(),
+            crosswalk_forward: true,
+            crosswalk_backward: true,
         }
     }
 
@@ -339,6 +409,8 @@ impl MapEdits {
             // What exactly changed?
             if r.speed_limit != orig.speed_limit
                 || r.access_restrictions != orig.access_restrictions
+                || r.crosswalk_forward != orig.crosswalk_forward
+                || r.crosswalk_backward != orig.crosswalk_backward
                 // If a lane was added or deleted, figuring out if any were modified is kind of
                 // unclear -- just mark the entire road.
                 || r.lanes.len() != orig.lanes_ltr.len()
@@ -414,6 +486,8 @@ impl EditCmd {
                 let road = &mut map.roads[r.0];
                 road.speed_limit = new.speed_limit;
                 road.access_restrictions = new.access_restrictions.clone();
+                road.crosswalk_forward = new.crosswalk_forward;
+                road.crosswalk_backward = new.crosswalk_backward;
 
                 effects.changed_roads.insert(road.id);
                 for i in [road.src_i, road.dst_i] {
@@ -784,6 +858,8 @@ impl Map {
             lanes_ltr: r.lane_specs(),
             speed_limit: r.speed_limit,
             access_restrictions: r.access_restrictions.clone(),
+            crosswalk_forward: r.crosswalk_forward,
+            crosswalk_backward: r.crosswalk_backward,
         }
     }
 
@@ -794,6 +870,32 @@ impl Map {
         EditCmd::ChangeRoad { r, old, new }
     }
 
+    /// Produces commands to change the speed limit of every road with this OSM highway
+    /// classification (like "residential"). If a boundary is given, only roads with their middle
+    /// inside it change. Roads that already have this speed limit are skipped.
+    pub fn bulk_edit_speed_limits(
+        &self,
+        highway: &str,
+        boundary: Option<&Polygon>,
+        speed_limit: Speed,
+    ) -> Vec<EditCmd> {
+        self.all_roads()
+            .iter()
+            .filter(|r| {
+                r.osm_tags.is(osm::HIGHWAY, highway)
+                    && r.speed_limit != speed_limit
+                    && boundary
+                        .map(|poly| poly.contains_pt(r.center_pts.middle()))
+                        .unwrap_or(true)
+            })
+            .map(|r| {
+                self.edit_road_cmd(r.id, |new| {
+                    new.speed_limit = speed_limit;
+                })
+            })
+            .collect()
+    }
+
     /// Panics on borders
     pub fn get_i_edit(&self, i: IntersectionID) -> EditIntersection {
         match self.get_i(i).intersection_type {
@@ -999,3 +1101,65 @@ impl Map {
         self.edits_generation
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_change_road_direction() {
+        let mut ok = true;
+        for (description, input_lt, input_dir, oneway, expected_lt, expected_dir) in vec![
+            (
+                "Reverse a one-way",
+                "spdds",
+                "v^^^^",
+                None,
+                "sddps",
+                "vvvv^",
+            ),
+            (
+                "Reverse a two-way",
+                "sbddbs",
+                "vvv^^^",
+                None,
+                "sbddbs",
+                "vvv^^^",
+            ),
+            (
+                "Two-way to one-way",
+                "spddps",
+                "vvv^^^",
+                Some(Direction::Fwd),
+                "spddps",
+                "v^^^^^",
+            ),
+            (
+                "Two-way to one-way, the other way",
+                "sdbds",
+                "vv^^^",
+                Some(Direction::Back),
+                "sdbds",
+                "vvvv^",
+            ),
+        ] {
+            let mut edit = EditRoad::create_for_test(input_lt, input_dir);
+            match oneway {
+                Some(dir) => {
+                    edit.convert_to_oneway(dir);
+                    assert_eq!(edit.oneway_for_driving(), Some(dir));
+                }
+                None => edit.reverse_direction(),
+            }
+            edit.check_lanes_ltr(
+                description.to_string(),
+                input_lt,
+                input_dir,
+                expected_lt,
+                expected_dir,
+                &mut ok,
+            );
+        }
+        assert!(ok);
+    }
+}
@@ -43,7 +43,7 @@ pub use crate::edits::{
 pub use crate::make::RawToMapOptions;
 pub use crate::map::{DrivingSide, MapConfig};
 pub use crate::objects::area::{Area, AreaID, AreaType};
-pub use crate::objects::block::{Block, Perimeter};
+pub use crate::objects::block::{Block, BlockStats, Perimeter};
 pub use crate::objects::building::{
     Amenity, AmenityType, Building, BuildingID, BuildingType, NamePerLanguage, OffstreetParking,
 };
@@ -54,16 +54,20 @@ pub use crate::objects::lane::{
     SIDEWALK_THICKNESS,
 };
 pub use crate::objects::movement::{CompressedMovementID, Movement, MovementID};
+pub use crate::objects::opening_hours::{OpeningHours, Weekday};
 pub use crate::objects::parking_lot::{ParkingLot, ParkingLotID};
-pub use crate::objects::road::{DirectedRoadID, Direction, Road, RoadID, RoadSideID, SideOfRoad};
-pub use crate::objects::stop_signs::{ControlStopSign, RoadWithStopSign};
+pub use crate::objects::road::{
+    DirectedRoadID, Direction, LevelOfTrafficStress, Road, RoadID, RoadSideID, SideOfRoad,
+};
+pub use crate::objects::stop_signs::{ControlStopSign, RoadWithStopSign, StopSignPlacement};
 pub use crate::objects::traffic_signals::{ControlTrafficSignal, Stage, StageType};
 pub use crate::objects::turn::{Turn, TurnID, TurnPriority, TurnType};
 pub use crate::objects::zone::{AccessRestrictions, Zone};
 pub use crate::pathfind::uber_turns::{IntersectionCluster, UberTurn};
 use crate::pathfind::Pathfinder;
 pub use crate::pathfind::{
-    Path, PathConstraints, PathRequest, PathStep, PathStepV2, PathV2, RoutingParams,
+    CongestionProfile, Path, PathConstraints, PathRequest, PathStep, PathStepV2, PathV2,
+    RoutingParams,
 };
 pub use crate::traversable::{Position, Traversable, MAX_BIKE_SPEED, MAX_WALKING_SPEED};
 
@@ -1,4 +1,4 @@
-use std::collections::{BTreeMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 use rand::{Rng, SeedableRng};
 use rand_xorshift::XorShiftRng;
@@ -42,6 +42,19 @@ pub fn make_all_buildings(
         Distance::meters(1000.0),
         timer,
     );
+    // Alleys are usually imported, but driveways only when the importer was asked to keep them
+    let access_road_pts = if map.all_roads().iter().any(|r| r.is_access_road()) {
+        match_points_to_lanes(
+            map,
+            center_per_bldg.values().cloned().collect(),
+            |l| l.is_driving() && map.get_r(l.id.road).is_access_road(),
+            Distance::meters(2.0),
+            Distance::meters(30.0),
+            timer,
+        )
+    } else {
+        HashMap::new()
+    };
 
     let mut results = Vec::new();
     timer.start_iter("match buildings to sidewalks", center_per_bldg.len());
@@ -62,6 +75,15 @@ pub fn make_all_buildings(
 
             let id = BuildingID(results.len());
 
+            // Only use the driveway if it's closer than the street out front
+            let access_road = access_road_pts
+                .get(&bldg_center)
+                .filter(|pos| {
+                    pos.pt(map).dist_to(bldg_center.to_pt2d())
+                        < sidewalk_pos.pt(map).dist_to(bldg_center.to_pt2d())
+                })
+                .map(|pos| pos.lane().road);
+
             let mut rng = XorShiftRng::seed_from_u64(orig_id.inner() as u64);
             // TODO is it worth using height or building:height as an alternative if not tagged?
             let levels = b
@@ -85,7 +107,12 @@ pub fn make_all_buildings(
                         .iter()
                         .map(|a| {
                             let mut a = a.clone();
-                            a.osm_tags = Tags::empty();
+                            // Opening hours are small and useful to keep
+                            let mut tags = Tags::empty();
+                            if let Some(hours) = a.osm_tags.get("opening_hours") {
+                                tags.insert("opening_hours", hours);
+                            }
+                            a.osm_tags = tags;
                             a
                         })
                         .collect()
@@ -113,6 +140,7 @@ pub fn make_all_buildings(
 
                 sidewalk_pos: *sidewalk_pos,
                 driveway_geom: sidewalk_line.to_polyline(),
+                access_road,
             });
         }
     }
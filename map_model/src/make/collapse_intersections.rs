@@ -12,6 +12,8 @@ use crate::{osm, IntersectionType, LaneSpec, LaneType};
 /// Collapse degenerate intersections:
 /// - between two cycleways
 /// - when the lane specs match and only "unimportant" OSM tags differ
+///
+/// Midblock crossings are always kept.
 pub fn collapse(raw: &mut RawMap) {
     let mut merge: Vec<NodeID> = Vec::new();
     for id in raw.intersections.keys() {
@@ -19,6 +21,9 @@ pub fn collapse(raw: &mut RawMap) {
         if roads.len() != 2 {
             continue;
         }
+        if raw.intersections[id].midblock_crossing {
+            continue;
+        }
         match should_collapse(roads[0], roads[1], raw) {
             Ok(()) => {
                 merge.push(*id);
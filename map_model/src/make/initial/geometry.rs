@@ -70,8 +70,7 @@ pub fn intersection_polygon(
                 id, intersection_id
             );
         };
-        let pl_normal = pl.shift_right(r.half_width)?;
-        let pl_reverse = pl.shift_left(r.half_width)?;
+        let (pl_reverse, pl_normal) = pl.shift_both_sides(r.half_width)?;
         lines.push((*id, pl.first_pt(), pl_normal, pl_reverse));
         endpoints_for_center.push(pl.last_pt());
     }
@@ -277,12 +276,13 @@ fn generalized_trim_back(
         }
 
         // Shift those final centers out again to find the main endpoints for the polygon.
+        let (left, right) = r.trimmed_center_pts.shift_both_sides(r.half_width)?;
         if r.dst_i == i {
-            endpoints.push(r.trimmed_center_pts.shift_right(r.half_width)?.last_pt());
-            endpoints.push(r.trimmed_center_pts.shift_left(r.half_width)?.last_pt());
+            endpoints.push(right.last_pt());
+            endpoints.push(left.last_pt());
         } else {
-            endpoints.push(r.trimmed_center_pts.shift_left(r.half_width)?.first_pt());
-            endpoints.push(r.trimmed_center_pts.shift_right(r.half_width)?.first_pt());
+            endpoints.push(left.first_pt());
+            endpoints.push(right.first_pt());
         }
 
         if back_pl.length() >= EPSILON_DIST * 3.0 && adj_back_pl.length() >= EPSILON_DIST * 3.0 {
@@ -341,12 +341,13 @@ fn pretrimmed_geometry(
     for (r, _, _, _) in lines {
         let r = &roads[r];
         // Shift those final centers out again to find the main endpoints for the polygon.
+        let (left, right) = r.trimmed_center_pts.shift_both_sides(r.half_width)?;
         if r.dst_i == i {
-            endpoints.push(r.trimmed_center_pts.shift_right(r.half_width)?.last_pt());
-            endpoints.push(r.trimmed_center_pts.shift_left(r.half_width)?.last_pt());
+            endpoints.push(right.last_pt());
+            endpoints.push(left.last_pt());
         } else {
-            endpoints.push(r.trimmed_center_pts.shift_left(r.half_width)?.first_pt());
-            endpoints.push(r.trimmed_center_pts.shift_right(r.half_width)?.first_pt());
+            endpoints.push(left.first_pt());
+            endpoints.push(right.first_pt());
         }
     }
 
@@ -400,12 +401,13 @@ fn deadend(
     // points, so shift the center out again to find the endpoints.
     // TODO Refactor with generalized_trim_back.
     let mut endpts = vec![pl_b.last_pt(), pl_a.last_pt()];
+    let (left, right) = trimmed.shift_both_sides(r.half_width)?;
     if r.dst_i == i {
-        endpts.push(trimmed.shift_right(r.half_width)?.last_pt());
-        endpts.push(trimmed.shift_left(r.half_width)?.last_pt());
+        endpts.push(right.last_pt());
+        endpts.push(left.last_pt());
     } else {
-        endpts.push(trimmed.shift_left(r.half_width)?.first_pt());
-        endpts.push(trimmed.shift_right(r.half_width)?.first_pt());
+        endpts.push(left.first_pt());
+        endpts.push(right.first_pt());
     }
 
     endpts.dedup();
@@ -26,6 +26,7 @@ mod parking_lots;
 pub mod remove_disconnected;
 pub mod snappy;
 pub mod traffic_signals;
+mod transit;
 pub mod turns;
 mod walking_turns;
 
@@ -42,6 +43,10 @@ pub struct RawToMapOptions {
     /// Preserve all OSM tags for buildings, increasing the final file size substantially.
     #[structopt(long)]
     pub keep_bldg_tags: bool,
+    /// Create public transit routes from the GTFS feed imported into the RawMap. Transit routes
+    /// aren't built from OSM route relations yet, so without this, the map has no transit.
+    #[structopt(long)]
+    pub use_gtfs: bool,
 }
 
 impl Map {
@@ -96,6 +101,7 @@ impl Map {
                 elevation: i.elevation,
                 // Might change later
                 intersection_type: i.intersection_type,
+                stop_sign_placement: raw.intersections[&i.id].stop_sign_placement,
                 orig_id: i.id,
                 incoming_lanes: Vec::new(),
                 outgoing_lanes: Vec::new(),
@@ -154,6 +160,8 @@ impl Map {
                 percent_incline: raw_road.percent_incline,
                 crosswalk_forward: raw_road.crosswalk_forward,
                 crosswalk_backward: raw_road.crosswalk_backward,
+                orig_crosswalk_forward: raw_road.crosswalk_forward,
+                orig_crosswalk_backward: raw_road.crosswalk_backward,
             };
             road.speed_limit = road.speed_limit_from_osm();
             road.access_restrictions = road.access_restrictions_from_osm();
@@ -267,6 +275,14 @@ impl Map {
                     if i.movements.is_empty() {
                         error!("Traffic signal at {} downgraded to stop sign, because it has no movements -- probably roads under construction", i.orig_id);
                         stop_signs.insert(i.id, ControlStopSign::new(&map, i.id));
+                    } else if i.is_roundabout(&map) {
+                        // Vehicles yield when entering a roundabout. The signal is probably just
+                        // for a pedestrian crossing, which we don't model separately.
+                        warn!(
+                            "Traffic signal at {} is on a roundabout; using yield signs instead",
+                            i.orig_id
+                        );
+                        stop_signs.insert(i.id, ControlStopSign::new(&map, i.id));
                     } else {
                         traffic_signals
                             .insert(i.id, ControlTrafficSignal::validating_new(&map, i.id));
@@ -284,6 +300,10 @@ impl Map {
 
         traffic_signals::synchronize(&mut map);
 
+        if opts.use_gtfs {
+            transit::make_stops_and_routes(&mut map, &raw, timer);
+        }
+
         timer.start("setup pathfinding");
         let engine = if opts.skip_ch {
             CreateEngine::Dijkstra
@@ -0,0 +1,36 @@
+use super::*;
+
+/// Every stage gets at least this much time once it starts
+const MIN_GREEN: Duration = Duration::const_seconds(5.0);
+/// Keep extending the stage while vehicles keep arriving at least this often
+const PASSAGE_TIME: Duration = Duration::const_seconds(3.0);
+/// Stop extending after this much extra time, so other movements get a chance
+const MAX_EXTENSION: Duration = Duration::const_seconds(30.0);
+
+/// Create a traffic signal that reacts to demand, using the same stages as the simpler 3- and
+/// 4-way heuristics. Every stage is variable. When the simulation runs with `actuated_signals`,
+/// it skips a stage when no vehicle is waiting or approaching for one of its protected movements
+/// (and no pedestrian is waiting to cross), and extends it while vehicles keep showing up.
+pub fn make_traffic_signal(map: &Map, i: &Intersection) -> Option<ControlTrafficSignal> {
+    let mut ts = four_way_four_stage(map, i)
+        .or_else(|| three_way(map, i))
+        .unwrap_or_else(|| stage_per_road(map, i));
+    if ts.stages.len() < 2 {
+        return None;
+    }
+
+    for stage in &mut ts.stages {
+        // Demand is only detected for protected movements, so a stage where every vehicle yields
+        // would never be served. Promote what we can.
+        let yields: Vec<MovementID> = stage.yield_movements.iter().cloned().collect();
+        for m in yields {
+            if stage.could_be_protected(m, i) {
+                stage.yield_movements.remove(&m);
+                stage.protected_movements.insert(m);
+            }
+        }
+        // The minimum crosswalk time is enforced for all policies later
+        stage.stage_type = StageType::Variable(MIN_GREEN, PASSAGE_TIME, MAX_EXTENSION);
+    }
+    Some(ts)
+}
@@ -2,7 +2,8 @@ use super::*;
 
 /// Create a traffic signal which has a stage that is: protected straight, protected right,
 /// unprotected left, unprotected right on red. Followed by a variable stage that has protected
-/// left, unprotected right on red. With a last stage that is all-walk and variable.
+/// left, unprotected right on red, but only if there's a dedicated left turn lane. With a last
+/// stage that is all-walk and variable.
 /// In some degenerate cases, usually with one or more one-way, this can reduce to stage per road.
 /// In some rare cases, usually with an alleyway, oncoming lanes can't both be protected left turns.
 /// In such cases the stage is split into two stages with each having a protected and yeild turn.
@@ -22,12 +23,14 @@ fn make_signal(i: &Intersection, map: &Map) -> Option<ControlTrafficSignal> {
         ts.stages = other.stages;
     }
     ts.convert_to_ped_scramble_without_promotion(i);
+    remove_protected_turns_from_shared_lanes(&mut ts, i, map);
     // We don't always get a valid traffic signal from the default 3-way and 4-way. When we don't
     // we need to try assembling stages with a more complex algorithm.
     if ts.validate(i).is_err() {
         if let Some(other) = multi_way_stages(i) {
             ts.stages = other.stages;
             ts.convert_to_ped_scramble_without_promotion(i);
+            remove_protected_turns_from_shared_lanes(&mut ts, i, map);
         }
     }
     if let Err(err) = ts.validate(i) {
@@ -39,6 +42,47 @@ fn make_signal(i: &Intersection, map: &Map) -> Option<ControlTrafficSignal> {
     Some(ts)
 }
 
+/// Turns across oncoming traffic only get a protected stage when they have a lane to themselves.
+/// If the lane is shared with straight traffic, then the turning vehicles just yield in the stage
+/// with the oncoming traffic, and the stage dedicated to them goes away (in `optimize`) if nothing
+/// else uses it.
+fn remove_protected_turns_from_shared_lanes(
+    ts: &mut ControlTrafficSignal,
+    i: &Intersection,
+    map: &Map,
+) {
+    let hard_turn = if map.config.driving_side == DrivingSide::Right {
+        TurnType::Left
+    } else {
+        TurnType::Right
+    };
+    // Don't remove a movement entirely
+    let yielding: BTreeSet<MovementID> = ts
+        .stages
+        .iter()
+        .flat_map(|s| s.yield_movements.iter().cloned())
+        .collect();
+    for stage in &mut ts.stages {
+        stage.protected_movements.retain(|m| {
+            i.movements[m].turn_type != hard_turn
+                || !yielding.contains(m)
+                || has_exclusive_lane(*m, i)
+        });
+    }
+}
+
+/// Is there some lane where every vehicle turns through this movement? U-turns are allowed to
+/// share the lane too.
+fn has_exclusive_lane(m: MovementID, i: &Intersection) -> bool {
+    let members = &i.movements[&m].members;
+    members.iter().any(|t| {
+        i.turns
+            .iter()
+            .filter(|other| other.id.src == t.src)
+            .all(|other| members.contains(&other.id) || other.turn_type == TurnType::UTurn)
+    })
+}
+
 fn optimize(mut ts: ControlTrafficSignal, i: &Intersection) -> Option<ControlTrafficSignal> {
     // Remove stages which don't contain a protected route.
     ts.stages.retain(|s| !s.protected_movements.is_empty());
@@ -13,6 +13,7 @@ use crate::{
 };
 use geom::Duration;
 
+mod actuated;
 mod lagging_green;
 
 /// Applies a bunch of heuristics to a single intersection, returning the valid results in
@@ -76,6 +77,9 @@ pub fn get_possible_policies(
     if let Some(ts) = lagging_green::make_traffic_signal(map, i) {
         results.push(("lagging green".to_string(), ts));
     }
+    if let Some(ts) = actuated::make_traffic_signal(map, i) {
+        results.push(("actuated by demand".to_string(), ts));
+    }
     results.push(("stage per road".to_string(), stage_per_road(map, i)));
     results.push(("arbitrary assignment".to_string(), greedy_assignment(i)));
     results.push((
@@ -99,6 +103,19 @@ pub fn get_possible_policies(
     }
 
     results.retain(|pair| pair.1.validate(i).is_ok());
+
+    // Offer the best policy with a head start for pedestrians too. This has to happen after
+    // enforcing crosswalk time, since the short walk-only stages would otherwise be lengthened.
+    let with_lpi = results.first().and_then(|(name, ts)| {
+        let mut ts = ts.clone();
+        if ts.add_leading_pedestrian_intervals() && ts.validate(i).is_ok() {
+            Some((format!("{}, with leading pedestrian intervals", name), ts))
+        } else {
+            None
+        }
+    });
+    results.extend(with_lpi);
+
     results
 }
 
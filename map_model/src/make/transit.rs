@@ -0,0 +1,248 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+
+use anyhow::Result;
+
+use abstutil::Timer;
+use geom::{Distance, HashablePt2D};
+
+use crate::make::match_points_to_lanes;
+use crate::pathfind::{CreateEngine, Pathfinder};
+use crate::raw::{RawMap, RawTransitRoute, RawTransitType};
+use crate::{
+    osm, BusRoute, BusRouteID, BusStop, BusStopID, LaneID, Map, PathConstraints, PathRequest,
+    Position,
+};
+
+/// Buses and trains spawn at the start of a lane, so it must be at least this long. A bit more
+/// than the longest transit vehicle in the simulation.
+const MIN_START_LANE_LENGTH: Distance = Distance::const_meters(13.0);
+
+/// Snap the stops from a GTFS feed to the map, then create routes visiting them. Routes that
+/// can't be followed on this map are skipped. This must happen before the final pathfinder is
+/// built, since walking with transit depends on the routes.
+pub fn make_stops_and_routes(map: &mut Map, raw: &RawMap, timer: &mut Timer) {
+    if raw.transit_routes.is_empty() {
+        return;
+    }
+    timer.start("make transit stops and routes");
+
+    let pts: HashSet<HashablePt2D> = raw
+        .transit_stops
+        .values()
+        .map(|s| s.position.to_hashable())
+        .collect();
+    let sidewalk_pts = match_points_to_lanes(
+        map,
+        pts.clone(),
+        |l| l.is_walkable(),
+        Distance::ZERO,
+        Distance::meters(30.0),
+        timer,
+    );
+    // Train stops are often on a platform away from the sidewalk, so snap them separately
+    let rail_pts = match_points_to_lanes(
+        map,
+        pts,
+        |l| l.is_light_rail(),
+        Distance::ZERO,
+        Distance::meters(50.0),
+        timer,
+    );
+
+    // Just enough to check the routes can be followed
+    map.pathfinder = Pathfinder::new_limited(
+        map,
+        map.routing_params().clone(),
+        CreateEngine::Dijkstra,
+        vec![PathConstraints::Bus, PathConstraints::Train],
+        timer,
+    );
+
+    // Routes with different patterns often share stops
+    let mut stop_ids: BTreeMap<(Position, Position), BusStopID> = BTreeMap::new();
+    let mut skipped = 0;
+    timer.start_iter("make transit routes", raw.transit_routes.len());
+    for route in &raw.transit_routes {
+        timer.next();
+        if let Err(err) = make_route(map, raw, route, &sidewalk_pts, &rail_pts, &mut stop_ids) {
+            warn!(
+                "Skipping transit route {} ({}): {}",
+                route.short_name, route.gtfs_id, err
+            );
+            skipped += 1;
+        }
+    }
+    info!(
+        "Made {} transit routes and {} stops; skipped {} routes",
+        map.bus_routes.len(),
+        map.bus_stops.len(),
+        skipped
+    );
+
+    map.pathfinder = Pathfinder::empty();
+    timer.stop("make transit stops and routes");
+}
+
+fn make_route(
+    map: &mut Map,
+    raw: &RawMap,
+    route: &RawTransitRoute,
+    sidewalk_pts: &HashMap<HashablePt2D, Position>,
+    rail_pts: &HashMap<HashablePt2D, Position>,
+    stop_ids: &mut BTreeMap<(Position, Position), BusStopID>,
+) -> Result<()> {
+    let route_type = match route.route_type {
+        RawTransitType::Bus => PathConstraints::Bus,
+        RawTransitType::Train => PathConstraints::Train,
+    };
+    if route.spawn_times.is_empty() {
+        bail!("no trips run on a typical weekday");
+    }
+
+    // (name, driving_pos, sidewalk_pos) for each stop
+    let mut stops: Vec<(String, Position, Position)> = Vec::new();
+    let mut stop_offsets = Vec::new();
+    for (idx, gtfs_id) in route.stops.iter().enumerate() {
+        let stop = &raw.transit_stops[gtfs_id];
+        let pt = stop.position.to_hashable();
+        let sidewalk_pos = *sidewalk_pts
+            .get(&pt)
+            .ok_or_else(|| anyhow!("stop {} isn't near a sidewalk", gtfs_id))?;
+        let driving_pos = if route_type == PathConstraints::Train {
+            *rail_pts
+                .get(&pt)
+                .ok_or_else(|| anyhow!("stop {} isn't near a rail line", gtfs_id))?
+        } else {
+            let lane = map
+                .get_parent(sidewalk_pos.lane())
+                .find_closest_lane(sidewalk_pos.lane(), |l| route_type.can_use(l, map))
+                .ok_or_else(|| anyhow!("stop {} isn't on a road buses can use", gtfs_id))?;
+            sidewalk_pos.equiv_pos(lane, map)
+        };
+        // GTFS sometimes lists two stops that snap to the same place
+        if stops.last().map(|(_, pos, _)| *pos) == Some(driving_pos) {
+            continue;
+        }
+        stops.push((stop.name.clone(), driving_pos, sidewalk_pos));
+        if let Some(dt) = route.stop_offsets.get(idx) {
+            stop_offsets.push(*dt);
+        }
+    }
+    if stops.len() < 2 {
+        bail!("only {} stops inside the map", stops.len());
+    }
+
+    let start = pick_start_lane(stops[0].1, route_type, map)?;
+    let end_border = {
+        let last_lane = map.get_l(stops.last().unwrap().1.lane());
+        if map.get_i(last_lane.dst_i).is_border() {
+            Some(last_lane.id)
+        } else {
+            None
+        }
+    };
+
+    // Make sure the vehicle can actually get between each stop before committing to anything
+    let mut requests = vec![PathRequest::vehicle(
+        Position::start(start),
+        stops[0].1,
+        route_type,
+    )];
+    for pair in stops.windows(2) {
+        requests.push(PathRequest::vehicle(pair[0].1, pair[1].1, route_type));
+    }
+    if let Some(l) = end_border {
+        requests.push(PathRequest::vehicle(
+            stops.last().unwrap().1,
+            Position::end(l, map),
+            route_type,
+        ));
+    }
+    for req in requests {
+        if req.start.lane() == req.end.lane() && req.start.dist_along() > req.end.dist_along() {
+            bail!("two stops on {} are out of order", req.start.lane());
+        }
+        if map.pathfind(req.clone()).is_err() {
+            bail!("no path for {}", req);
+        }
+    }
+
+    let mut stop_list = Vec::new();
+    for (name, driving_pos, sidewalk_pos) in stops {
+        let id = if let Some(id) = stop_ids.get(&(driving_pos, sidewalk_pos)) {
+            *id
+        } else {
+            let id = BusStopID {
+                sidewalk: sidewalk_pos.lane(),
+                idx: map.get_l(sidewalk_pos.lane()).bus_stops.len(),
+            };
+            stop_ids.insert((driving_pos, sidewalk_pos), id);
+            map.mut_lane(sidewalk_pos.lane()).bus_stops.insert(id);
+            map.bus_stops.insert(
+                id,
+                BusStop {
+                    id,
+                    name,
+                    driving_pos,
+                    sidewalk_pos,
+                    is_train_stop: route_type == PathConstraints::Train,
+                },
+            );
+            id
+        };
+        stop_list.push(id);
+    }
+
+    if stop_offsets.len() != stop_list.len() {
+        stop_offsets.clear();
+    }
+
+    let id = BusRouteID(map.bus_routes.len());
+    map.bus_routes.push(BusRoute {
+        id,
+        full_name: route.long_name.clone(),
+        short_name: route.short_name.clone(),
+        gtfs_trip_marker: Some(route.gtfs_id.clone()),
+        // GTFS routes don't come from OSM, but edits refer to routes by this ID, so make up a
+        // stable one
+        osm_rel_id: osm::RelationID(-(id.0 as i64) - 1),
+        stops: stop_list,
+        start,
+        end_border,
+        route_type,
+        spawn_times: route.spawn_times.clone(),
+        orig_spawn_times: route.spawn_times.clone(),
+        stop_offsets,
+    });
+    Ok(())
+}
+
+/// Find a lane long enough for the vehicle to appear on, leading to the first stop.
+fn pick_start_lane(
+    first_stop: Position,
+    constraints: PathConstraints,
+    map: &Map,
+) -> Result<LaneID> {
+    if first_stop.dist_along() >= MIN_START_LANE_LENGTH {
+        return Ok(first_stop.lane());
+    }
+
+    // Flood backwards until we find a long enough lane
+    let mut queue = VecDeque::new();
+    queue.push_back(first_stop.lane());
+    let mut visited = BTreeSet::new();
+    while let Some(current) = queue.pop_front() {
+        if current != first_stop.lane() && map.get_l(current).length() >= MIN_START_LANE_LENGTH {
+            return Ok(current);
+        }
+        for t in map.get_turns_to_lane(current) {
+            if constraints.can_use(map.get_l(t.id.src), map) && visited.insert(t.id.src) {
+                queue.push_back(t.id.src);
+            }
+        }
+    }
+    bail!(
+        "couldn't find any lanes leading to {} that're long enough for a vehicle to start",
+        first_stop.lane()
+    )
+}
@@ -17,11 +17,15 @@ pub fn make_all_turns(map: &Map, i: &Intersection) -> Vec<Turn> {
         i,
     ));
     let unique_turns = ensure_unique(raw_turns);
+    let crossing_layers = crossing_layers(map, i);
     // Never allow turns that go against road-level turn restrictions; that upstream OSM data is
-    // usually not extremely broken.
+    // usually not extremely broken. Nor jump between a bridge and the road underneath it.
     let all_turns: Vec<Turn> = unique_turns
         .into_iter()
         .filter(|t| t.permitted_by_road(i, map))
+        .filter(|t| {
+            !crossing_layers || map.get_parent(t.id.src).zorder == map.get_parent(t.id.dst).zorder
+        })
         .collect();
 
     // Try to use turn lane tags...
@@ -66,6 +70,17 @@ pub fn make_all_turns(map: &Map, i: &Intersection) -> Vec<Turn> {
     }
 }
 
+/// Roads on different layers sometimes meet at an intersection where they cross, even though one
+/// passes over the other. Detect this when roads continue through on more than one layer. Where
+/// only one road arrives on some layer, like at the end of a bridge, the layers really do connect.
+fn crossing_layers(map: &Map, i: &Intersection) -> bool {
+    let mut roads_per_layer: BTreeMap<isize, usize> = BTreeMap::new();
+    for r in &i.roads {
+        *roads_per_layer.entry(map.get_r(*r).zorder).or_insert(0) += 1;
+    }
+    roads_per_layer.values().filter(|cnt| **cnt >= 2).count() >= 2
+}
+
 fn ensure_unique(turns: Vec<Turn>) -> Vec<Turn> {
     let mut ids = HashSet::new();
     let mut keep: Vec<Turn> = Vec::new();
@@ -384,7 +399,7 @@ fn remove_merging_turns(map: &Map, input: Vec<Turn>, turn_type: TurnType) -> Vec
     turns
 }
 
-fn turn_type_from_angles(from: Angle, to: Angle) -> TurnType {
+pub(crate) fn turn_type_from_angles(from: Angle, to: Angle) -> TurnType {
     let diff = from.simple_shortest_rotation_towards(to);
     // This is a pretty arbitrary parameter, but a difference of 30 degrees seems reasonable for
     // some observed cases.
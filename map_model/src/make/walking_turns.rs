@@ -481,6 +481,16 @@ fn make_degenerate_crosswalks(
     )
 }
 
+/// When the intersection polygon between two sidewalks doubles back on itself, shifting that piece
+/// of it loops. Instead shrink the whole polygon to the middle of the sidewalks, which cuts off the
+/// loops, and trace along that from `l2` to `l1`.
+fn trace_inner_corner(i: &Intersection, l1: &Lane, l2: &Lane) -> Option<PolyLine> {
+    let width = l1.width.min(l2.width);
+    let ring = i.polygon.get_outer_ring()?.offset(-width / 2.0).ok()?;
+    ring.get_slice_between(l2.first_pt(), l1.last_pt(), false, width)
+        .ok()
+}
+
 // TODO This doesn't handle sidewalk/shoulder transitions
 fn make_shared_sidewalk_corner(
     driving_side: DrivingSide,
@@ -514,21 +524,19 @@ fn make_shared_sidewalk_corner(
         let mut deduped = pts;
         deduped.dedup();
         if deduped.len() >= 2 {
-            if abstutil::contains_duplicates(
+            let shifted = if abstutil::contains_duplicates(
                 &deduped
                     .iter()
                     .map(|pt| pt.to_hashable())
                     .collect::<Vec<_>>(),
             ) {
-                warn!(
-                    "SharedSidewalkCorner between {} and {} has weird duplicate geometry, so just \
-                     doing straight line",
-                    l1.id, l2.id
-                );
-                return baseline;
-            }
-
-            if let Ok(pl) = PolyLine::must_new(deduped).shift_right(l1.width.min(l2.width) / 2.0) {
+                None
+            } else {
+                PolyLine::must_new(deduped)
+                    .shift_right(l1.width.min(l2.width) / 2.0)
+                    .ok()
+            };
+            if let Some(pl) = shifted.or_else(|| trace_inner_corner(i, l1, l2)) {
                 pts_between.extend(pl.points());
             } else {
                 warn!(
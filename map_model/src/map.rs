@@ -636,6 +636,20 @@ impl Map {
         Some(i.turn_to_movement(t))
     }
 
+    /// Like `get_movement_for_traffic_signal`, but also works at stop signs.
+    pub fn get_movement_for_controlled_intersection(
+        &self,
+        t: TurnID,
+    ) -> Option<(MovementID, CompressedMovementID)> {
+        let i = self.get_i(t.parent);
+        if !(i.is_traffic_signal() || i.is_stop_sign())
+            || self.get_t(t).turn_type == TurnType::SharedSidewalkCorner
+        {
+            return None;
+        }
+        Some(i.turn_to_movement(t))
+    }
+
     pub fn find_r_by_osm_id(&self, id: OriginalRoad) -> Result<RoadID> {
         for r in self.all_roads() {
             if r.orig_id == id {
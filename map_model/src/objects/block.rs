@@ -3,9 +3,13 @@ use std::collections::{BTreeSet, HashMap, HashSet};
 use anyhow::Result;
 
 use abstutil::wraparound_get;
-use geom::{Polygon, Pt2D, Ring};
+use geom::{Distance, Polygon, Pt2D, Ring};
 
-use crate::{Direction, LaneID, Map, RoadID, RoadSideID, SideOfRoad};
+use crate::{BuildingID, BuildingType, Direction, LaneID, Map, RoadID, RoadSideID, SideOfRoad};
+
+/// The ends of lanes don't always land exactly on the intersection polygon, so snap them onto it
+/// when tracing around corners.
+const SNAP_TO_INTERSECTION: Distance = Distance::const_meters(0.5);
 
 /// A block is defined by a perimeter that traces along the sides of roads. Inside the perimeter,
 /// the block may contain buildings and interior roads. In the simple case, a block represents a
@@ -19,6 +23,20 @@ pub struct Block {
     pub polygon: Polygon,
 }
 
+/// A summary of everything inside one block, for tools that compare areas of the city.
+#[derive(Clone, Debug)]
+pub struct BlockStats {
+    /// In square meters, measured on the surface of the earth
+    pub area: f64,
+    pub buildings: Vec<BuildingID>,
+    /// Residents of all buildings inside the block
+    pub population: usize,
+    /// Parking spots along the inner side of the perimeter and on interior roads
+    pub onstreet_parking: usize,
+    /// Parking spots in buildings and parking lots inside the block
+    pub offstreet_parking: usize,
+}
+
 /// A sequence of roads in order, beginning and ending at the same place. No "crossings" -- tracing
 /// along this sequence should geometrically yield a simple polygon.
 // TODO Handle the map boundary. Sometimes this perimeter should be broken up by border
@@ -405,6 +423,88 @@ impl Perimeter {
 }
 
 impl Block {
+    /// Finds every single city block on the map. Perimeters that don't form a valid polygon are
+    /// skipped, so the result doesn't cover roads near the map boundary.
+    pub fn find_all_single_blocks(map: &Map) -> Vec<Block> {
+        Perimeter::find_all_single_blocks(map)
+            .into_iter()
+            .filter_map(|perimeter| match perimeter.to_block(map) {
+                Ok(block) => Some(block),
+                Err(err) => {
+                    warn!("Failed to make a block from a perimeter: {}", err);
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the buildings whose center lies inside this block. Buildings facing the outer side
+    /// of a perimeter road belong to a neighboring block.
+    pub fn find_buildings(&self, map: &Map) -> Vec<BuildingID> {
+        let bounds = self.polygon.get_bounds();
+        map.all_buildings()
+            .iter()
+            .filter(|b| bounds.contains(b.label_center) && self.polygon.contains_pt(b.label_center))
+            .map(|b| b.id)
+            .collect()
+    }
+
+    /// Assigns every building to the first block containing it. Buildings outside of all blocks
+    /// are omitted. The values are indices into `blocks`.
+    pub fn assign_buildings(map: &Map, blocks: &[Block]) -> HashMap<BuildingID, usize> {
+        let mut result = HashMap::new();
+        for (idx, block) in blocks.iter().enumerate() {
+            for b in block.find_buildings(map) {
+                result.entry(b).or_insert(idx);
+            }
+        }
+        result
+    }
+
+    pub fn calculate_stats(&self, map: &Map) -> BlockStats {
+        let buildings = self.find_buildings(map);
+        let mut population = 0;
+        let mut offstreet_parking = 0;
+        for b in &buildings {
+            let bldg = map.get_b(*b);
+            population += match bldg.bldg_type {
+                BuildingType::Residential { num_residents, .. } => num_residents,
+                BuildingType::ResidentialCommercial(residents, _) => residents,
+                BuildingType::Commercial(_) | BuildingType::Empty => 0,
+            };
+            offstreet_parking += bldg.num_parking_spots();
+        }
+        for lot in map.all_parking_lots() {
+            if self.polygon.contains_pt(lot.polygon.center()) {
+                offstreet_parking += lot.capacity();
+            }
+        }
+
+        let cfg = map.get_config();
+        let mut onstreet_parking = 0;
+        // The perimeter repeats the first road at the end
+        let sides: HashSet<RoadSideID> = self.perimeter.roads.iter().cloned().collect();
+        for side in sides {
+            // Parking on the far side of a perimeter road belongs to the neighboring block
+            for lane in &map.get_r(side.road).lanes {
+                if lane.is_parking() && lane.get_nearest_side_of_road(map) == side {
+                    onstreet_parking += lane.number_parking_spots(cfg);
+                }
+            }
+        }
+        for r in &self.perimeter.interior {
+            onstreet_parking += map.get_r(*r).number_parking_spots(cfg);
+        }
+
+        BlockStats {
+            area: self.polygon.geodesic_area(map.get_gps_bounds()),
+            buildings,
+            population,
+            onstreet_parking,
+            offstreet_parking,
+        }
+    }
+
     fn from_perimeter(map: &Map, perimeter: Perimeter) -> Result<Block> {
         // Trace along the perimeter and build the polygon
         let mut pts: Vec<Pt2D> = Vec::new();
@@ -470,7 +570,12 @@ impl Block {
                 if let Some(ring) = prev_i.polygon.get_outer_ring() {
                     // At dead-ends, trace around the intersection on the longer side
                     let longer = prev_i.is_deadend();
-                    if let Some(slice) = ring.get_slice_between(*last_pt, pl.first_pt(), longer) {
+                    if let Ok(slice) = ring.get_slice_between(
+                        *last_pt,
+                        pl.first_pt(),
+                        longer,
+                        SNAP_TO_INTERSECTION,
+                    ) {
                         pts.extend(slice.into_points());
                     }
                 }
@@ -483,7 +588,9 @@ impl Block {
         let first_intersection = map.get_i(first_intersection.unwrap());
         if let Some(ring) = first_intersection.polygon.get_outer_ring() {
             let longer = first_intersection.is_deadend();
-            if let Some(slice) = ring.get_slice_between(*pts.last().unwrap(), pts[0], longer) {
+            if let Ok(slice) =
+                ring.get_slice_between(*pts.last().unwrap(), pts[0], longer, SNAP_TO_INTERSECTION)
+            {
                 pts.extend(slice.into_points());
             }
         }
@@ -8,9 +8,10 @@ use strum_macros::{Display, EnumIter, EnumString};
 use abstutil::{
     deserialize_btreemap, deserialize_usize, serialize_btreemap, serialize_usize, Tags,
 };
-use geom::{Distance, PolyLine, Polygon, Pt2D};
+use geom::{Distance, Line, PolyLine, Polygon, Pt2D, Time};
 
-use crate::{osm, LaneID, Map, PathConstraints, Position};
+use crate::make::trim_path;
+use crate::{osm, LaneID, Map, OpeningHours, PathConstraints, Position, RoadID, Weekday};
 
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct BuildingID(
@@ -51,6 +52,9 @@ pub struct Building {
     pub sidewalk_pos: Position,
     /// Goes from building to sidewalk
     pub driveway_geom: PolyLine,
+    /// If the building is closer to a driveway or alley than to the street its sidewalk is on,
+    /// vehicles start and end trips there instead.
+    pub access_road: Option<RoadID>,
 }
 
 /// A business located inside a building.
@@ -63,6 +67,21 @@ pub struct Amenity {
     pub osm_tags: Tags,
 }
 
+impl Amenity {
+    /// Parses the `opening_hours` tag, if it's present and understood.
+    pub fn opening_hours(&self) -> Option<OpeningHours> {
+        OpeningHours::parse(self.osm_tags.get("opening_hours")?).ok()
+    }
+
+    /// Is the amenity open at some time of day? When the opening hours are missing or can't be
+    /// parsed, assume it's open.
+    pub fn is_open(&self, day: Weekday, time: Time) -> bool {
+        self.opening_hours()
+            .map(|hours| hours.is_open(day, time))
+            .unwrap_or(true)
+    }
+}
+
 /// Represent no parking as Private(0, false).
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub enum OffstreetParking {
@@ -150,6 +169,10 @@ impl Building {
     /// The polyline goes from the building to the driving position
     // TODO Make this handle parking_blackhole
     pub fn driving_connection(&self, map: &Map) -> Option<(Position, PolyLine)> {
+        if let Some(pair) = self.access_road_connection(map) {
+            return Some(pair);
+        }
+
         let lane = map
             .get_parent(self.sidewalk())
             .find_closest_lane(self.sidewalk(), |l| PathConstraints::Car.can_use(l, map))?;
@@ -161,6 +184,27 @@ impl Building {
         Some((pos, self.driveway_geom.clone().optionally_push(pos.pt(map))))
     }
 
+    /// Connect straight to the closest point on a driveway or alley, if there is one and it's
+    /// still usable by cars after map edits.
+    fn access_road_connection(&self, map: &Map) -> Option<(Position, PolyLine)> {
+        let road = map.get_r(self.access_road?);
+        let center = self.polygon.center();
+        let (pos, pt) = road
+            .lanes
+            .iter()
+            .filter(|l| PathConstraints::Car.can_use(l, map))
+            .filter_map(|l| {
+                let pt = l.lane_center_pts.project_pt(center);
+                let dist_along = l.dist_along_of_point(pt)?;
+                Some((Position::new(l.id, dist_along), pt))
+            })
+            .min_by_key(|(_, pt)| pt.dist_to(center))?;
+        // Driveways are short, so use a smaller buffer than the main street
+        let pos = pos.buffer_dist(Distance::meters(2.0), map)?;
+        let geom = trim_path(&self.polygon, Line::new(center, pt)?).to_polyline();
+        Some((pos, geom.optionally_push(pos.pt(map))))
+    }
+
     /// Returns (biking position, sidewalk position). Could fail if the biking graph is
     /// disconnected.
     pub fn biking_connection(&self, map: &Map) -> Option<(Position, Position)> {
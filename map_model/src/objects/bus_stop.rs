@@ -1,13 +1,12 @@
 //! Bus stops and routes.
 // TODO Rename public transit -- these also cover light rail now.
-// TODO No code creates any of these structures right now.
 
 use std::fmt;
 
 use serde::{Deserialize, Serialize};
 
 use abstutil::{deserialize_usize, serialize_usize};
-use geom::Time;
+use geom::{Duration, Time};
 
 use crate::{osm, LaneID, Map, PathConstraints, PathRequest, Position};
 
@@ -63,11 +62,14 @@ pub struct BusRoute {
     pub start: LaneID,
     pub end_border: Option<LaneID>,
     pub route_type: PathConstraints,
-    /// Non-empty, times in order for one day when a vehicle should begin at start.
+    /// Non-empty, times in order for one day when a vehicle should depart from the first stop.
     pub spawn_times: Vec<Time>,
     /// Explicitly store whatever the original was, since this can't be reconstructed without side
     /// input.
     pub orig_spawn_times: Vec<Time>,
+    /// For each stop, how long after departing the first stop a vehicle is scheduled to arrive
+    /// there. Empty if the route doesn't have a detailed schedule.
+    pub stop_offsets: Vec<Duration>,
 }
 
 impl BusRoute {
@@ -94,6 +96,15 @@ impl BusRoute {
         steps
     }
 
+    /// When a vehicle departing the first stop at `departure` should arrive at the stop with this
+    /// index. Only the first stop is known if the route doesn't have a detailed schedule.
+    pub fn scheduled_arrival(&self, departure: Time, stop_idx: usize) -> Option<Time> {
+        if stop_idx == 0 {
+            return Some(departure);
+        }
+        self.stop_offsets.get(stop_idx).map(|dt| departure + *dt)
+    }
+
     pub fn plural_noun(&self) -> &'static str {
         if self.route_type == PathConstraints::Bus {
             "buses"
@@ -9,7 +9,7 @@ use geom::{Distance, Polygon};
 
 use crate::{
     osm, CompressedMovementID, DirectedRoadID, LaneID, Map, Movement, MovementID, PathConstraints,
-    Road, RoadID, RoadSideID, SideOfRoad, Turn, TurnID,
+    Road, RoadID, RoadSideID, SideOfRoad, StopSignPlacement, Turn, TurnID,
 };
 
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
@@ -47,6 +47,8 @@ pub struct Intersection {
     pub elevation: Distance,
 
     pub intersection_type: IntersectionType,
+    /// Used when this intersection has, or gets edited into, a stop sign
+    pub stop_sign_placement: StopSignPlacement,
     pub orig_id: osm::NodeID,
 
     /// Note that a lane may belong to both incoming_lanes and outgoing_lanes.
@@ -106,6 +108,11 @@ impl Intersection {
         self.roads.iter().all(|r| map.get_r(*r).is_cycleway())
     }
 
+    /// Is this somewhere along a roundabout, where vehicles enter or leave it?
+    pub fn is_roundabout(&self, map: &Map) -> bool {
+        self.roads.iter().any(|r| map.get_r(*r).is_roundabout())
+    }
+
     /// Does this intersection only connect two road segments? Then usually, the intersection only
     /// exists to mark the road name or lanes changing.
     pub fn is_degenerate(&self) -> bool {
@@ -130,10 +137,11 @@ impl Intersection {
         constraints.filter_lanes(self.outgoing_lanes.clone(), map)
     }
 
-    /// Higher numbers get drawn on top
+    /// Higher numbers get drawn on top. Where roads on different layers meet, this is the lowest
+    /// one, so a bridge passing over the intersection is drawn above it.
     pub fn get_zorder(&self, map: &Map) -> isize {
-        // TODO Not sure min makes sense -- what about a 1 and a 0? Prefer the nonzeros. If there's
-        // a -1 and a 1... need to see it to know what to do.
+        // TODO Not sure min makes sense at the end of a bridge, where a 1 and a 0 meet. Prefer the
+        // nonzeros?
         self.roads
             .iter()
             .map(|r| map.get_r(*r).zorder)
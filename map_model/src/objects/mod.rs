@@ -5,6 +5,7 @@ pub mod bus_stop;
 pub mod intersection;
 pub mod lane;
 pub mod movement;
+pub mod opening_hours;
 pub mod parking_lot;
 pub mod road;
 pub mod stop_signs;
@@ -0,0 +1,204 @@
+//! Parses the common subset of OSM's `opening_hours` syntax. See
+//! <https://wiki.openstreetmap.org/wiki/Key:opening_hours> for the full specification. Anything
+//! fancier than weekdays and times, like months, week numbers, or sunrise, fails to parse.
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+use strum_macros::{Display, EnumIter};
+
+use geom::{Duration, Time};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, Display, EnumIter)]
+pub enum Weekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl Weekday {
+    /// All days, starting with Monday.
+    pub fn all() -> Vec<Weekday> {
+        Weekday::iter().collect()
+    }
+
+    fn parse(x: &str) -> Result<Weekday> {
+        Ok(match x {
+            "Mo" => Weekday::Monday,
+            "Tu" => Weekday::Tuesday,
+            "We" => Weekday::Wednesday,
+            "Th" => Weekday::Thursday,
+            "Fr" => Weekday::Friday,
+            "Sa" => Weekday::Saturday,
+            "Su" => Weekday::Sunday,
+            _ => bail!("unknown weekday {}", x),
+        })
+    }
+
+    fn idx(self) -> usize {
+        self as usize
+    }
+
+    fn prev(self) -> Weekday {
+        Weekday::iter().nth((self.idx() + 6) % 7).unwrap()
+    }
+}
+
+/// When a business is open during a typical week. Public and school holidays aren't modeled.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OpeningHours {
+    /// Indexed by `Weekday`. Each day has (open, close) times since midnight. The close time might
+    /// be past midnight, when a business is open late into the next day.
+    per_day: Vec<Vec<(Duration, Duration)>>,
+}
+
+impl OpeningHours {
+    pub fn parse(raw: &str) -> Result<OpeningHours> {
+        let raw = raw.trim();
+        if raw == "24/7" {
+            return Ok(OpeningHours {
+                per_day: vec![vec![(Duration::ZERO, Duration::hours(24))]; 7],
+            });
+        }
+
+        let mut per_day = vec![Vec::new(); 7];
+        // Rules separated by ';' override earlier rules for the same days, but rules separated by
+        // ',' add to them.
+        for rule_group in raw.split(';') {
+            let mut overridden = vec![false; 7];
+            for rule in split_additional_rules(rule_group) {
+                let (days, spans) = parse_rule(&rule)?;
+                for day in days {
+                    if !overridden[day.idx()] {
+                        overridden[day.idx()] = true;
+                        per_day[day.idx()].clear();
+                    }
+                    per_day[day.idx()].extend(spans.clone());
+                }
+            }
+        }
+        Ok(OpeningHours { per_day })
+    }
+
+    /// Is the business open at this time of day?
+    pub fn is_open(&self, day: Weekday, time: Time) -> bool {
+        let t = time - Time::START_OF_DAY;
+        if self.per_day[day.idx()]
+            .iter()
+            .any(|(open, close)| *open <= t && t < *close)
+        {
+            return true;
+        }
+        // Still open from late the previous night?
+        let t = t + Duration::hours(24);
+        self.per_day[day.prev().idx()]
+            .iter()
+            .any(|(open, close)| *open <= t && t < *close)
+    }
+}
+
+/// "Mo-Fr 08:00-17:00, Sa 10:00-14:00" has two rules, but "Mo,We 08:00-12:00" has one. A piece
+/// starting with a letter only begins a new rule if the previous rule already has some times.
+fn split_additional_rules(rule_group: &str) -> Vec<String> {
+    let mut rules: Vec<String> = Vec::new();
+    for piece in rule_group.split(',') {
+        let piece = piece.trim();
+        if piece.is_empty() {
+            continue;
+        }
+        let starts_rule = piece.starts_with(|c: char| c.is_ascii_alphabetic());
+        match rules.last_mut() {
+            Some(current) if !(starts_rule && current.contains(|c: char| c.is_ascii_digit())) => {
+                current.push(',');
+                current.push_str(piece);
+            }
+            _ => {
+                rules.push(piece.to_string());
+            }
+        }
+    }
+    rules
+}
+
+/// Returns the days the rule applies to and when it's open on those days. A rule with only
+/// holidays applies to no days.
+fn parse_rule(rule: &str) -> Result<(Vec<Weekday>, Vec<(Duration, Duration)>)> {
+    let (selector, times) = match rule.find(|c: char| c == ' ' || c.is_ascii_digit()) {
+        Some(idx) if !rule.starts_with(|c: char| c.is_ascii_digit()) => {
+            (rule[..idx].trim(), rule[idx..].trim())
+        }
+        // Like "08:00-17:00" or "off", applying to every day
+        _ => ("", rule),
+    };
+
+    let days = if selector.is_empty() {
+        Weekday::iter().collect()
+    } else {
+        parse_days(selector)?
+    };
+
+    let spans = if times == "off" || times == "closed" {
+        Vec::new()
+    } else {
+        times
+            .split(',')
+            .map(|span| parse_span(span.trim()))
+            .collect::<Result<Vec<_>>>()?
+    };
+    Ok((days, spans))
+}
+
+/// Like "Mo-Fr,Su" or "Fr-Mo"
+fn parse_days(selector: &str) -> Result<Vec<Weekday>> {
+    let mut days = Vec::new();
+    for part in selector.split(',') {
+        let part = part.trim();
+        if part == "PH" || part == "SH" {
+            continue;
+        }
+        if let Some((from, to)) = part.split_once('-') {
+            let from = Weekday::parse(from)?.idx();
+            let to = Weekday::parse(to)?.idx();
+            // Ranges like Fr-Mo wrap around the weekend
+            let len = (to + 7 - from) % 7 + 1;
+            for i in 0..len {
+                days.push(Weekday::iter().nth((from + i) % 7).unwrap());
+            }
+        } else {
+            days.push(Weekday::parse(part)?);
+        }
+    }
+    Ok(days)
+}
+
+/// Like "08:00-17:30" or "18:00-02:00"
+fn parse_span(span: &str) -> Result<(Duration, Duration)> {
+    let (open, close) = match span.split_once('-') {
+        Some(pair) => pair,
+        None => bail!("{} isn't a time span", span),
+    };
+    let open = parse_time(open)?;
+    let mut close = parse_time(close)?;
+    if close <= open {
+        close += Duration::hours(24);
+    }
+    Ok((open, close))
+}
+
+/// Like "08:30" or "24:00"
+fn parse_time(x: &str) -> Result<Duration> {
+    let (hours, minutes) = match x.trim().split_once(':') {
+        Some(pair) => pair,
+        None => bail!("{} isn't a time", x),
+    };
+    let hours = hours.parse::<usize>()?;
+    let minutes = minutes.parse::<usize>()?;
+    if hours > 24 || minutes >= 60 {
+        bail!("{} isn't a time", x);
+    }
+    Ok(Duration::hours(hours) + Duration::minutes(minutes))
+}
@@ -10,8 +10,8 @@ use geom::{Distance, PolyLine, Polygon, Speed};
 
 use crate::raw::{OriginalRoad, RestrictionType};
 use crate::{
-    osm, AccessRestrictions, BusStopID, DrivingSide, IntersectionID, Lane, LaneID, LaneSpec,
-    LaneType, Map, PathConstraints, Zone,
+    osm, AccessRestrictions, BufferType, BusStopID, DrivingSide, IntersectionID, Lane, LaneID,
+    LaneSpec, LaneType, Map, MapConfig, PathConstraints, Zone,
 };
 
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
@@ -182,9 +182,13 @@ pub struct Road {
     pub untrimmed_center_pts: PolyLine,
     pub src_i: IntersectionID,
     pub dst_i: IntersectionID,
-    /// Is there a tagged crosswalk near each end of the road?
+    /// Is there a marked crosswalk near each end of the road? Map edits can change this.
     pub crosswalk_forward: bool,
     pub crosswalk_backward: bool,
+    /// Explicitly store whatever the original crosswalks were, since this can't be reconstructed
+    /// from the OSM tags of the road.
+    pub orig_crosswalk_forward: bool,
+    pub orig_crosswalk_backward: bool,
 }
 
 impl Road {
@@ -319,6 +323,15 @@ impl Road {
         self.center_pts.length()
     }
 
+    /// The total number of on-street parking spots along all parking lanes of this road.
+    pub fn number_parking_spots(&self, cfg: &MapConfig) -> usize {
+        self.lanes
+            .iter()
+            .filter(|l| l.lane_type == LaneType::Parking)
+            .map(|l| l.number_parking_spots(cfg))
+            .sum()
+    }
+
     /// Creates the thick polygon representing one half of the road. For roads with multipe
     /// direction changes (like a two-way cycletrack adjacent to a regular two-way road), the
     /// results are probably weird.
@@ -432,6 +445,12 @@ impl Road {
         self.osm_tags.is(osm::HIGHWAY, "service")
     }
 
+    /// Driveways and alleys give buildings access from behind or off the main street. Driveways
+    /// are only imported with `convert_osm::Options::include_driveways`.
+    pub fn is_access_road(&self) -> bool {
+        self.is_service() && self.osm_tags.is_any("service", vec!["driveway", "alley"])
+    }
+
     pub fn is_cycleway(&self) -> bool {
         let mut bike = false;
         for lane in &self.lanes {
@@ -444,6 +463,29 @@ impl Road {
         bike
     }
 
+    /// Can somebody using a wheelchair travel along this road's sidewalks? Stairs, grades steeper
+    /// than an accessible ramp, and anything tagged `wheelchair=no` are off-limits.
+    pub fn is_wheelchair_accessible(&self) -> bool {
+        !self.osm_tags.is(osm::HIGHWAY, "steps")
+            && !self.osm_tags.is("wheelchair", "no")
+            && self.percent_incline.abs() <= MAX_WHEELCHAIR_INCLINE
+    }
+
+    /// Do crossings at one end of this road have curb ramps? `Fwd` is the end at `dst_i`. Unless
+    /// OSM maps a raised curb there, assume they do.
+    pub fn has_curb_ramps(&self, dir: Direction) -> bool {
+        !self.osm_tags.contains_key(if dir == Direction::Fwd {
+            osm::NO_CURB_RAMP_FWD
+        } else {
+            osm::NO_CURB_RAMP_BACK
+        })
+    }
+
+    /// Is this part of the circulating carriageway of a roundabout?
+    pub fn is_roundabout(&self) -> bool {
+        self.osm_tags.is("junction", "roundabout")
+    }
+
     /// Returns the common intersection between two roads, panicking if they're not adjacent
     // TODO Doesn't handle two roads between the same pair of intersections
     pub fn common_endpt(&self, other: &Road) -> IntersectionID {
@@ -482,6 +524,9 @@ impl Road {
                 allow |= PathConstraints::Bus;
             }
             allow
+        } else if self.osm_tags.is("service", "driveway") {
+            // Only drive on a driveway to reach a building along it
+            PathConstraints::Pedestrian | PathConstraints::Bike
         } else {
             EnumSet::all()
         };
@@ -612,24 +657,93 @@ impl Road {
     // TODO Should elevation matter or not? Flat high-speed roads are still terrifying, but there's
     // something about slogging up (or flying down!) a pothole-filled road inches from cars.
     pub fn high_stress_for_bikes(&self, map: &Map, dir: Direction) -> bool {
-        let mut bike_lanes = false;
-        let mut can_use = false;
-        // Can a bike even use it, or is it a highway?
-        for l in &self.lanes {
+        self.bike_stress(map, dir) >= LevelOfTrafficStress::Lts3 && self.bikes_can_use(map)
+    }
+
+    /// Classifies the directed road by how stressful it is to cycle along, based on the lane
+    /// configuration, the road's rank, and the speed limit. Roads that bikes can't use at all are
+    /// `Lts4`.
+    pub fn bike_stress(&self, map: &Map, dir: Direction) -> LevelOfTrafficStress {
+        if !self.bikes_can_use(map) {
+            return LevelOfTrafficStress::Lts4;
+        }
+        if self.is_cycleway() {
+            return LevelOfTrafficStress::Lts1;
+        }
+
+        let mut bike_lanes = Vec::new();
+        let mut driving_lanes = 0;
+        for (idx, l) in self.lanes.iter().enumerate() {
             if l.lane_type == LaneType::Biking && l.dir == dir {
-                bike_lanes = true;
+                bike_lanes.push(idx);
             }
-            if PathConstraints::Bike.can_use(l, map) {
-                can_use = true;
+            if l.lane_type == LaneType::Driving && l.dir == dir {
+                driving_lanes += 1;
             }
         }
-        if !can_use || bike_lanes {
-            return false;
+
+        if !bike_lanes.is_empty() {
+            // Is the bike lane physically separated from moving vehicles? Parking, curbs, and
+            // sturdier buffers count, but paint doesn't.
+            let exposed = |idx: usize| {
+                matches!(
+                    self.lanes.get(idx).map(|l| l.lane_type),
+                    Some(LaneType::Driving)
+                        | Some(LaneType::Bus)
+                        | Some(LaneType::SharedLeftTurn)
+                        | Some(LaneType::Buffer(BufferType::Stripes))
+                )
+            };
+            let protected = bike_lanes
+                .iter()
+                .any(|idx| !exposed(idx + 1) && (*idx == 0 || !exposed(idx - 1)));
+            return if protected {
+                LevelOfTrafficStress::Lts1
+            } else {
+                LevelOfTrafficStress::Lts2
+            };
         }
-        self.get_rank() != osm::RoadRank::Local
+
+        // Mixing with traffic
+        if self.get_rank() == osm::RoadRank::Local {
+            if driving_lanes <= 1 && self.speed_limit <= Speed::miles_per_hour(25.0) {
+                LevelOfTrafficStress::Lts1
+            } else {
+                LevelOfTrafficStress::Lts2
+            }
+        } else if driving_lanes <= 1 && self.speed_limit <= Speed::miles_per_hour(30.0) {
+            LevelOfTrafficStress::Lts3
+        } else {
+            LevelOfTrafficStress::Lts4
+        }
+    }
+
+    /// Can a bike use this road at all, or is it a highway?
+    fn bikes_can_use(&self, map: &Map) -> bool {
+        self.lanes
+            .iter()
+            .any(|l| PathConstraints::Bike.can_use(l, map))
     }
 }
 
+// The steepest running slope allowed for an accessible ramp, 1:12
+const MAX_WHEELCHAIR_INCLINE: f64 = 1.0 / 12.0;
+
+/// How stressful it is to cycle along a road, loosely following the Level of Traffic Stress
+/// classification from <https://peterfurth.sites.northeastern.edu/level-of-traffic-stress/>.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum LevelOfTrafficStress {
+    /// Comfortable for almost anybody, including children: trails, protected bike lanes, and calm
+    /// residential streets
+    Lts1,
+    /// Comfortable for most adults: painted bike lanes and most local streets
+    Lts2,
+    /// Only for confident cyclists: sharing a lane with traffic on a busier road
+    Lts3,
+    /// Only for the strong and fearless: sharing a lane on a fast or multi-lane road
+    Lts4,
+}
+
 // TODO All of this is kind of deprecated? Some callers seem to really need to still handle lanes
 // going outward from the "center" line. Should keep whittling this down, probably. These very much
 // don't handle multiple direction changes.
@@ -48,9 +48,40 @@ pub struct ControlStopSign {
 pub struct RoadWithStopSign {
     pub lane_closest_to_edge: LaneID,
     pub must_stop: bool,
+    /// Vehicles from this road give way to conflicting traffic, but don't stop when the way is
+    /// clear. This is how roundabouts are entered. `must_stop` takes precedence.
+    #[serde(default)]
+    pub must_yield: bool,
+}
+
+/// How stop signs are placed at an intersection. This can be set per intersection in the RawMap.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StopSignPlacement {
+    /// Guess from the types of roads meeting. Same-rank roads get an all-way stop; otherwise the
+    /// biggest roads don't stop.
+    Automatic,
+    /// Every road stops.
+    AllWay,
+    /// The main road continuing through the intersection doesn't stop, but everything crossing it
+    /// does.
+    TwoWay,
+    /// Nobody stops; everybody just yields.
+    Uncontrolled,
+}
+
+impl StopSignPlacement {
+    pub fn describe(self) -> &'static str {
+        match self {
+            StopSignPlacement::Automatic => "automatic",
+            StopSignPlacement::AllWay => "all-way stop",
+            StopSignPlacement::TwoWay => "two-way stop",
+            StopSignPlacement::Uncontrolled => "uncontrolled",
+        }
+    }
 }
 
 impl ControlStopSign {
+    /// Places stop signs according to the intersection's `stop_sign_placement`.
     pub fn new(map: &Map, id: IntersectionID) -> ControlStopSign {
         let mut ss = ControlStopSign {
             id,
@@ -88,20 +119,40 @@ impl ControlStopSign {
                     RoadWithStopSign {
                         lane_closest_to_edge,
                         must_stop: false,
+                        must_yield: false,
                     },
                 );
             }
         }
 
-        // Degenerate roads and deadends don't need any stop signs. But be careful with
-        // roundabouts; we want it to be lower priority to enter a roundabout than continue through
-        // it.
-        if ss.roads.len() <= 2
-            && ss
-                .roads
-                .keys()
-                .all(|r| !map.get_r(*r).osm_tags.is("junction", "roundabout"))
-        {
+        match map.get_i(id).stop_sign_placement {
+            StopSignPlacement::Automatic => {}
+            StopSignPlacement::AllWay => {
+                for cfg in ss.roads.values_mut() {
+                    cfg.must_stop = true;
+                }
+                return ss;
+            }
+            StopSignPlacement::TwoWay => {
+                ss.place_two_way(map);
+                return ss;
+            }
+            StopSignPlacement::Uncontrolled => {
+                return ss;
+            }
+        }
+
+        // Traffic circulating around a roundabout has priority, no matter what type of road is
+        // entering. Everything entering yields, without stopping.
+        if map.get_i(id).is_roundabout(map) {
+            for (r, cfg) in ss.roads.iter_mut() {
+                cfg.must_yield = !map.get_r(*r).is_roundabout();
+            }
+            return ss;
+        }
+
+        // Degenerate roads and deadends don't need any stop signs.
+        if ss.roads.len() <= 2 {
             return ss;
         }
         if map.get_i(id).is_cycleway(map) {
@@ -109,18 +160,43 @@ impl ControlStopSign {
             return ss;
         }
 
-        // Rank each road based on OSM highway type, and additionally:
-        // - Treat cycleways as lower priority than local roads (sad but typical reality)
-        // - Prioritize roundabouts, so they clear out faster than people enter them
-        // - Treat on/off ramps with less priority than the main part of the highway
-        // - Lower the priority of service roads
+        let (rank, ranks) = ss.rank_roads(map);
+
+        // If all roads have the same rank, all-way stop. Otherwise, everything stops except the
+        // highest-priority roads.
+        for (r, cfg) in ss.roads.iter_mut() {
+            if ranks.len() == 1 || rank[r] != ranks[0] {
+                // Don't stop in the middle of something that's likely actually an intersection.
+                if !map.get_r(*r).is_extremely_short() {
+                    cfg.must_stop = true;
+                }
+            }
+        }
+        ss
+    }
+
+    /// Rank each road based on OSM highway type, and additionally:
+    /// - Treat cycleways as lower priority than local roads (sad but typical reality)
+    /// - Prioritize roundabouts, so they clear out faster than people enter them
+    /// - Treat on/off ramps with less priority than the main part of the highway
+    /// - Lower the priority of service roads
+    ///
+    /// Also returns the distinct ranks, highest first.
+    #[allow(clippy::type_complexity)]
+    fn rank_roads(
+        &self,
+        map: &Map,
+    ) -> (
+        HashMap<RoadID, (osm::RoadRank, usize)>,
+        Vec<(osm::RoadRank, usize)>,
+    ) {
         let mut rank: HashMap<RoadID, (osm::RoadRank, usize)> = HashMap::new();
-        for r in ss.roads.keys() {
+        for r in self.roads.keys() {
             let r = map.get_r(*r);
             // Lower number is lower priority
             let priority = if r.is_cycleway() || r.osm_tags.is(osm::HIGHWAY, "service") {
                 0
-            } else if r.osm_tags.is("junction", "roundabout") {
+            } else if r.is_roundabout() {
                 3
             } else if r
                 .osm_tags
@@ -139,18 +215,44 @@ impl ControlStopSign {
         ranks.dedup();
         // Highest rank is first
         ranks.reverse();
+        (rank, ranks)
+    }
 
-        // If all roads have the same rank, all-way stop. Otherwise, everything stops except the
-        // highest-priority roads.
-        for (r, cfg) in ss.roads.iter_mut() {
-            if ranks.len() == 1 || rank[r] != ranks[0] {
-                // Don't stop in the middle of something that's likely actually an intersection.
-                if !map.get_r(*r).is_extremely_short() {
-                    cfg.must_stop = true;
-                }
+    /// The highest-ranked road and whichever road continues most straight through the
+    /// intersection from it don't stop. Everything else does.
+    fn place_two_way(&mut self, map: &Map) {
+        if self.roads.len() <= 2 {
+            return;
+        }
+        let (rank, ranks) = self.rank_roads(map);
+        let main = *self.roads.keys().find(|r| rank[*r] == ranks[0]).unwrap();
+
+        // The angle of each road pointing into the intersection
+        let angle = |r: RoadID| {
+            let road = map.get_r(r);
+            if road.dst_i == self.id {
+                road.center_pts.last_line().angle()
+            } else {
+                road.center_pts.first_line().reversed().angle()
             }
+        };
+        let main_angle = angle(main);
+        let continuation = self
+            .roads
+            .keys()
+            .filter(|r| **r != main)
+            .min_by_key(|r| {
+                let rotation = main_angle
+                    .opposite()
+                    .simple_shortest_rotation_towards(angle(**r))
+                    .abs();
+                (rotation * 100.0) as usize
+            })
+            .cloned();
+
+        for (r, cfg) in self.roads.iter_mut() {
+            cfg.must_stop = *r != main && Some(*r) != continuation;
         }
-        ss
     }
 
     /// Get the priority of a turn according to the stop sign -- either protected or yield, never
@@ -161,7 +263,8 @@ impl ControlStopSign {
             TurnType::Crosswalk => TurnPriority::Protected,
             TurnType::UnmarkedCrossing => TurnPriority::Yield,
             _ => {
-                if self.roads[&turn.src.road].must_stop {
+                let cfg = &self.roads[&turn.src.road];
+                if cfg.must_stop || cfg.must_yield {
                     TurnPriority::Yield
                 } else {
                     TurnPriority::Protected
@@ -170,6 +273,20 @@ impl ControlStopSign {
         }
     }
 
+    /// Can a vehicle making this turn go right away when nobody with priority is in the way, like
+    /// when entering a roundabout? Otherwise yielding means stopping first.
+    pub fn yields_without_stopping(&self, turn: TurnID, map: &Map) -> bool {
+        match map.get_t(turn).turn_type {
+            TurnType::SharedSidewalkCorner | TurnType::Crosswalk | TurnType::UnmarkedCrossing => {
+                false
+            }
+            _ => {
+                let cfg = &self.roads[&turn.src.road];
+                cfg.must_yield && !cfg.must_stop
+            }
+        }
+    }
+
     pub fn flip_sign(&mut self, r: RoadID) {
         let ss = self.roads.get_mut(&r).unwrap();
         ss.must_stop = !ss.must_stop;
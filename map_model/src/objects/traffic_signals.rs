@@ -9,12 +9,14 @@ use crate::make::traffic_signals::get_possible_policies;
 use crate::raw::OriginalRoad;
 use crate::{
     osm, DirectedRoadID, Direction, Intersection, IntersectionID, Map, Movement, MovementID,
-    RoadID, TurnID, TurnPriority,
+    RoadID, TurnID, TurnPriority, TurnType,
 };
 
 // The pace to use for crosswalk pace in m/s
 // https://en.wikipedia.org/wiki/Preferred_walking_speed
 const CROSSWALK_PACE: Speed = Speed::const_meters_per_second(1.4);
+// How long pedestrians can start crossing before vehicles get a green
+const LEADING_PEDESTRIAN_INTERVAL: Duration = Duration::const_seconds(5.0);
 
 /// A traffic signal consists of a sequence of Stages that repeat in a cycle. Most Stages last for a
 /// fixed duration. During a single Stage, some movements are protected (can proceed with the
@@ -33,12 +35,16 @@ pub struct Stage {
     // TODO Not renaming this, because this is going to change radically in
     // https://github.com/a-b-street/abstreet/pull/298 anyway
     pub stage_type: StageType,
+    /// Is this stage just a head start for pedestrians crossing during the next stage? Only set by
+    /// `add_leading_pedestrian_intervals`, so those stages can be removed again.
+    pub leading_pedestrian_interval: bool,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub enum StageType {
     Fixed(Duration),
-    /// Minimum is the minimum duration, 0 allows cycle to be skipped if no demand.
+    /// Minimum is the minimum duration, once the stage starts. The stage is skipped entirely if
+    /// nobody is waiting or approaching for one of its protected movements.
     /// Delay is the elapsed time with no demand that ends a cycle.
     /// Additional is the additional duration for an extended cycle.
     Variable(Duration, Duration, Duration),
@@ -92,7 +98,13 @@ impl ControlTrafficSignal {
         let time = max_distance / CROSSWALK_PACE;
         assert!(time >= Duration::ZERO);
         // Round up because it is converted to a usize elsewhere
-        Duration::seconds(time.inner_seconds().ceil())
+        let time = Duration::seconds(time.inner_seconds().ceil());
+        if self.stages[idx].leading_pedestrian_interval {
+            // Pedestrians can keep crossing during the next stage
+            let next = &self.stages[(idx + 1) % self.stages.len()];
+            return (time - next.stage_type.simple_duration()).max(Duration::ZERO);
+        }
+        time
     }
 
     pub fn validate(&self, i: &Intersection) -> Result<()> {
@@ -117,6 +129,11 @@ impl ControlTrafficSignal {
                     .collect::<Vec<_>>()
             );
         }
+        let turn_to_movement: BTreeMap<TurnID, MovementID> = i
+            .movements
+            .values()
+            .flat_map(|m| m.members.iter().map(move |t| (*t, m.id)))
+            .collect();
         for (stage_index, stage) in self.stages.iter().enumerate() {
             // Do any of the priority movements in one stage conflict?
             for m1 in stage.protected_movements.iter().map(|m| &i.movements[m]) {
@@ -132,6 +149,36 @@ impl ControlTrafficSignal {
                 }
             }
 
+            // A movement can only be protected if everybody sharing a lane with it can go too.
+            // Otherwise, whoever is stopped at the front of the lane blocks the rest. This rules
+            // out protected turns from a lane shared with straight traffic, unless the straight
+            // traffic goes at the same time.
+            for m in stage.protected_movements.iter().map(|m| &i.movements[m]) {
+                if m.id.crosswalk {
+                    continue;
+                }
+                for t in &m.members {
+                    for other in &i.turns {
+                        if other.id.src != t.src || other.turn_type == TurnType::UTurn {
+                            continue;
+                        }
+                        let other_movement = turn_to_movement[&other.id];
+                        if !stage.protected_movements.contains(&other_movement)
+                            && !stage.yield_movements.contains(&other_movement)
+                        {
+                            bail!(
+                                "Traffic signal for {} protects {:?} in stage {}, but {} shares \
+                                 a lane with it and has to stop",
+                                self.id,
+                                m.id,
+                                stage_index,
+                                other.id
+                            );
+                        }
+                    }
+                }
+            }
+
             // Do any of the crosswalks yield?
             for m in stage.yield_movements.iter().map(|m| &i.movements[m]) {
                 // TODO Maybe make UnmarkedCrossing yield
@@ -164,6 +211,59 @@ impl ControlTrafficSignal {
         self.internal_convert_to_ped_scramble(false, i)
     }
 
+    /// Gives pedestrians a head start. Before every stage with both crosswalks and vehicle
+    /// movements, insert a short stage where only those crosswalks can go. True is returned if any
+    /// stages were added.
+    pub fn add_leading_pedestrian_intervals(&mut self) -> bool {
+        let num_stages = self.stages.len();
+        let mut stages = Vec::new();
+        for (idx, stage) in self.stages.iter().enumerate() {
+            let crosswalks: BTreeSet<MovementID> = stage
+                .protected_movements
+                .iter()
+                .filter(|m| m.crosswalk)
+                .cloned()
+                .collect();
+            let has_vehicles = stage
+                .protected_movements
+                .iter()
+                .chain(stage.yield_movements.iter())
+                .any(|m| !m.crosswalk);
+            let prev = &self.stages[(idx + num_stages - 1) % num_stages];
+            if !crosswalks.is_empty()
+                && has_vehicles
+                && !stage.leading_pedestrian_interval
+                && !prev.leading_pedestrian_interval
+            {
+                stages.push(Stage {
+                    protected_movements: crosswalks,
+                    yield_movements: BTreeSet::new(),
+                    stage_type: StageType::Fixed(LEADING_PEDESTRIAN_INTERVAL),
+                    leading_pedestrian_interval: true,
+                });
+            }
+            stages.push(stage.clone());
+        }
+        let changed = stages.len() != num_stages;
+        self.stages = stages;
+        changed
+    }
+
+    /// Removes any stages added by `add_leading_pedestrian_intervals`. True is returned if any
+    /// stages were removed.
+    pub fn remove_leading_pedestrian_intervals(&mut self) -> bool {
+        let num_stages = self.stages.len();
+        self.stages
+            .retain(|stage| !stage.leading_pedestrian_interval);
+        self.stages.len() != num_stages
+    }
+
+    pub fn has_leading_pedestrian_intervals(&self) -> bool {
+        self.stages
+            .iter()
+            .any(|stage| stage.leading_pedestrian_interval)
+    }
+
     fn internal_convert_to_ped_scramble(
         &mut self,
         promote_yield_to_protected: bool,
@@ -292,6 +392,101 @@ impl ControlTrafficSignal {
         }
         total
     }
+
+    /// How long after the start of the cycle does the first stage with a protected movement
+    /// matching `from` and `to` begin? Crosswalks are ignored. Assumes no actuated timings.
+    pub fn stage_start_for(&self, from: Option<RoadID>, to: Option<RoadID>) -> Option<Duration> {
+        let mut start = Duration::ZERO;
+        for stage in &self.stages {
+            if stage.protected_movements.iter().any(|m| {
+                !m.crosswalk
+                    && from.map(|r| m.from.road == r).unwrap_or(true)
+                    && to.map(|r| m.to.road == r).unwrap_or(true)
+            }) {
+                return Some(start);
+            }
+            start += stage.stage_type.simple_duration();
+        }
+        None
+    }
+
+    /// Coordinates signals along a corridor. The `base` signal keeps its offset, and each of the
+    /// others starts its cycle the given duration after `base` does. The map isn't modified; the
+    /// caller should apply the returned signals.
+    pub fn coordinate_offsets(
+        map: &Map,
+        base: IntersectionID,
+        relative_offsets: &[(IntersectionID, Duration)],
+    ) -> Result<Vec<ControlTrafficSignal>> {
+        let base_offset = match map.maybe_get_traffic_signal(base) {
+            Some(ts) => ts.offset,
+            None => bail!("{} isn't a traffic signal", base),
+        };
+        let mut results = Vec::new();
+        for (i, relative) in relative_offsets {
+            let mut ts = match map.maybe_get_traffic_signal(*i) {
+                Some(ts) => ts.clone(),
+                None => bail!("{} isn't a traffic signal", i),
+            };
+            let cycle = ts.simple_cycle_duration();
+            if cycle == Duration::ZERO {
+                bail!("{} has no stages", i);
+            }
+            ts.offset = wrap_offset(base_offset + *relative, cycle);
+            results.push(ts);
+        }
+        Ok(results)
+    }
+
+    /// Picks offsets for consecutive signals along a corridor to create a "green wave". A vehicle
+    /// leaving the first signal as soon as the corridor's stage begins and driving at the speed
+    /// limit should arrive at each following signal just as its stage for the corridor begins.
+    /// The first signal keeps its offset. The map isn't modified; the caller should apply the
+    /// returned signals.
+    pub fn green_wave(map: &Map, corridor: &[IntersectionID]) -> Result<Vec<ControlTrafficSignal>> {
+        if corridor.len() < 2 {
+            bail!("A corridor needs at least two signals");
+        }
+        for i in corridor {
+            if map.maybe_get_traffic_signal(*i).is_none() {
+                bail!("{} isn't a traffic signal", i);
+            }
+        }
+
+        // The roads between each pair of consecutive signals
+        let mut paths = Vec::new();
+        for pair in corridor.windows(2) {
+            match map.simple_path_btwn(pair[0], pair[1]) {
+                Some((roads, _)) if !roads.is_empty() => {
+                    paths.push(roads);
+                }
+                _ => bail!("No path between {} and {}", pair[0], pair[1]),
+            }
+        }
+
+        let base_start = map
+            .get_traffic_signal(corridor[0])
+            .stage_start_for(None, Some(paths[0][0]))
+            .ok_or_else(|| anyhow!("{} never lets vehicles into the corridor", corridor[0]))?;
+        let mut travel_time = Duration::ZERO;
+        let mut relative_offsets = Vec::new();
+        for (idx, i) in corridor.iter().enumerate().skip(1) {
+            for r in &paths[idx - 1] {
+                let r = map.get_r(*r);
+                travel_time += r.length() / r.speed_limit;
+            }
+            let from = *paths[idx - 1].last().unwrap();
+            let to = paths.get(idx).map(|roads| roads[0]);
+            let ts = map.get_traffic_signal(*i);
+            let start = ts
+                .stage_start_for(Some(from), to)
+                .or_else(|| ts.stage_start_for(Some(from), None))
+                .ok_or_else(|| anyhow!("{} never lets vehicles through from the corridor", i))?;
+            relative_offsets.push((*i, start - base_start - travel_time));
+        }
+
+        ControlTrafficSignal::coordinate_offsets(map, corridor[0], &relative_offsets)
+    }
 }
 
 impl Stage {
@@ -301,6 +496,7 @@ impl Stage {
             yield_movements: BTreeSet::new(),
             // TODO Set a default
             stage_type: StageType::Fixed(Duration::seconds(30.0)),
+            leading_pedestrian_interval: false,
         }
     }
 
@@ -419,6 +615,7 @@ impl ControlTrafficSignal {
                                 )
                             }
                         },
+                        leading_pedestrian_interval: s.leading_pedestrian_interval,
                     })
                     .collect(),
                 offset_seconds: self.offset.inner_seconds() as usize,
@@ -426,11 +623,32 @@ impl ControlTrafficSignal {
         }
     }
 
+    /// Writes this signal as JSON, following the schema documented in the `traffic_signal_data`
+    /// crate. Movements are identified by OSM node and way IDs, so the file can be shared and
+    /// imported into other versions of the map.
+    pub fn export_json(&self, map: &Map) -> String {
+        abstutil::to_json(&self.export(map))
+    }
+
+    /// Reads a signal in the `traffic_signal_data` JSON schema -- written by `export_json` or
+    /// transcribed from a city's timing sheet -- and matches it against this map.
+    pub fn import_json(raw: &[u8], map: &Map) -> Result<ControlTrafficSignal> {
+        let raw: traffic_signal_data::TrafficSignal = abstutil::from_json(raw)?;
+        let id = map.find_i_by_osm_id(osm::NodeID(raw.intersection_osm_node_id))?;
+        ControlTrafficSignal::import(raw, id, map)
+    }
+
     pub(crate) fn import(
         mut raw: traffic_signal_data::TrafficSignal,
         id: IntersectionID,
         map: &Map,
     ) -> Result<ControlTrafficSignal> {
+        if raw.plans.is_empty() {
+            bail!(
+                "The signal at {} has no plans",
+                raw.intersection_osm_node_id
+            );
+        }
         // TODO Only import the first plan. Will import all of them later.
         let plan = raw.plans.remove(0);
         let mut stages = Vec::new();
@@ -474,6 +692,7 @@ impl ControlTrafficSignal {
                             )
                         }
                     },
+                    leading_pedestrian_interval: s.leading_pedestrian_interval,
                 });
             } else {
                 bail!("{}", errors.join("; "));
@@ -489,6 +708,11 @@ impl ControlTrafficSignal {
     }
 }
 
+/// Keeps an offset within one cycle, and not negative
+fn wrap_offset(offset: Duration, cycle: Duration) -> Duration {
+    (offset % cycle + cycle) % cycle
+}
+
 fn export_movement(id: &MovementID, map: &Map) -> traffic_signal_data::Turn {
     let from = map.get_r(id.from.road).orig_id;
     let to = map.get_r(id.to.road).orig_id;
@@ -533,3 +757,132 @@ fn find_r(id: traffic_signal_data::DirectedRoad, map: &Map) -> Result<DirectedRo
         },
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn movement(from: usize, to: usize, crosswalk: bool) -> MovementID {
+        MovementID {
+            from: DirectedRoadID {
+                road: RoadID(from),
+                dir: Direction::Fwd,
+            },
+            to: DirectedRoadID {
+                road: RoadID(to),
+                dir: Direction::Fwd,
+            },
+            parent: IntersectionID(0),
+            crosswalk,
+        }
+    }
+
+    fn stage(protected: Vec<MovementID>, seconds: f64) -> Stage {
+        let mut stage = Stage::new();
+        stage.protected_movements = protected.into_iter().collect();
+        stage.stage_type = StageType::Fixed(Duration::seconds(seconds));
+        stage
+    }
+
+    fn two_stage_signal() -> ControlTrafficSignal {
+        ControlTrafficSignal {
+            id: IntersectionID(0),
+            stages: vec![
+                stage(vec![movement(0, 2, false), movement(1, 1, true)], 30.0),
+                stage(vec![movement(1, 3, false), movement(0, 0, true)], 30.0),
+            ],
+            offset: Duration::ZERO,
+        }
+    }
+
+    #[test]
+    fn test_stage_start_for() {
+        let ts = two_stage_signal();
+        assert_eq!(
+            ts.stage_start_for(Some(RoadID(0)), Some(RoadID(2))),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(
+            ts.stage_start_for(Some(RoadID(1)), None),
+            Some(Duration::seconds(30.0))
+        );
+        assert_eq!(
+            ts.stage_start_for(None, Some(RoadID(3))),
+            Some(Duration::seconds(30.0))
+        );
+        // Crosswalks don't count
+        assert_eq!(ts.stage_start_for(None, Some(RoadID(1))), None);
+        assert_eq!(ts.stage_start_for(Some(RoadID(0)), Some(RoadID(3))), None);
+    }
+
+    #[test]
+    fn test_wrap_offset() {
+        let cycle = Duration::seconds(60.0);
+        assert_eq!(wrap_offset(Duration::ZERO, cycle), Duration::ZERO);
+        assert_eq!(
+            wrap_offset(Duration::seconds(15.0), cycle),
+            Duration::seconds(15.0)
+        );
+        assert_eq!(wrap_offset(cycle, cycle), Duration::ZERO);
+        assert_eq!(
+            wrap_offset(Duration::seconds(135.0), cycle),
+            Duration::seconds(15.0)
+        );
+        assert_eq!(
+            wrap_offset(Duration::seconds(-15.0), cycle),
+            Duration::seconds(45.0)
+        );
+        assert_eq!(
+            wrap_offset(Duration::seconds(-125.0), cycle),
+            Duration::seconds(55.0)
+        );
+    }
+
+    #[test]
+    fn test_leading_pedestrian_interval_round_trip() {
+        let orig = two_stage_signal();
+        let mut ts = orig.clone();
+        assert!(!ts.has_leading_pedestrian_intervals());
+
+        assert!(ts.add_leading_pedestrian_intervals());
+        assert_eq!(ts.stages.len(), 4);
+        assert!(ts.has_leading_pedestrian_intervals());
+        for idx in [0, 2] {
+            let lpi = &ts.stages[idx];
+            assert!(lpi.leading_pedestrian_interval);
+            assert!(lpi.protected_movements.iter().all(|m| m.crosswalk));
+            assert!(lpi
+                .protected_movements
+                .is_subset(&ts.stages[idx + 1].protected_movements));
+        }
+
+        // Adding again doesn't stack up more head starts
+        assert!(!ts.add_leading_pedestrian_intervals());
+        assert_eq!(ts.stages.len(), 4);
+
+        assert!(ts.remove_leading_pedestrian_intervals());
+        assert_eq!(ts, orig);
+        assert!(!ts.remove_leading_pedestrian_intervals());
+    }
+
+    #[test]
+    fn test_remove_only_marked_intervals() {
+        // A crosswalk-only stage leading into one that continues the crosswalk looks like a head
+        // start, but somebody deliberately set it up, so it stays.
+        let mut ts = two_stage_signal();
+        ts.stages.insert(0, stage(vec![movement(1, 1, true)], 5.0));
+        let orig = ts.clone();
+        assert!(!ts.has_leading_pedestrian_intervals());
+        assert!(!ts.remove_leading_pedestrian_intervals());
+        assert_eq!(ts, orig);
+
+        // The manual stage doesn't lead into vehicle movements, so it doesn't get a head start
+        assert!(ts.add_leading_pedestrian_intervals());
+        assert_eq!(ts.stages.len(), 5);
+        assert!(!ts.stages[0].leading_pedestrian_interval);
+        assert!(ts.stages[1].leading_pedestrian_interval);
+        assert!(ts.stages[3].leading_pedestrian_interval);
+        assert!(ts.remove_leading_pedestrian_intervals());
+        assert_eq!(ts, orig);
+    }
+}
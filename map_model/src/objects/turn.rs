@@ -3,8 +3,9 @@ use std::fmt;
 
 use serde::{Deserialize, Serialize};
 
-use geom::{Angle, PolyLine};
+use geom::{Angle, Duration, PolyLine, Speed};
 
+use crate::osm::RoadRank;
 use crate::raw::RestrictionType;
 use crate::{
     DirectedRoadID, Direction, Intersection, IntersectionID, LaneID, Map, MovementID,
@@ -119,6 +120,50 @@ impl Turn {
             || self.turn_type == TurnType::UnmarkedCrossing
     }
 
+    /// How long a pedestrian should expect to wait at the curb before taking this turn. Only
+    /// unmarked crossings have a delay; drivers don't have to stop for people there, so somebody
+    /// waits until there's a gap in traffic long enough to cross. This is zero for every other
+    /// type of turn.
+    ///
+    /// There's no traffic count data here, so the volume is estimated from the road's
+    /// classification and number of driving lanes. The gap needed is the time to walk across plus
+    /// a few seconds to react and step off the curb. Treating vehicle arrivals as random, the
+    /// expected wait for that gap is `(e^(q*t) - q*t - 1) / q`, where `q` is the volume and `t`
+    /// the gap. Slower drivers are more likely to yield anyway, which shortens the wait.
+    pub fn unmarked_crossing_delay(&self, map: &Map, walking_speed: Speed) -> Duration {
+        if self.turn_type != TurnType::UnmarkedCrossing {
+            return Duration::ZERO;
+        }
+        let road = map.get_r(self.id.src.road);
+
+        let per_lane_per_hour = match road.get_rank() {
+            RoadRank::Local => 60.0,
+            RoadRank::Arterial => 300.0,
+            RoadRank::Highway => 800.0,
+        };
+        let driving_lanes = road
+            .lanes
+            .iter()
+            .filter(|l| l.is_driving() || l.is_bus())
+            .count();
+        let volume = per_lane_per_hour * (driving_lanes as f64) / 3600.0;
+
+        let gap = self.geom.length() / walking_speed + Duration::seconds(3.0);
+
+        let speed = road.speed_limit;
+        let yield_rate = if speed <= Speed::miles_per_hour(20.0) {
+            0.5
+        } else if speed <= Speed::miles_per_hour(30.0) {
+            0.3
+        } else if speed <= Speed::miles_per_hour(40.0) {
+            0.1
+        } else {
+            0.0
+        };
+
+        expected_wait_for_gap(volume, gap, yield_rate)
+    }
+
     // TODO Maybe precompute this.
     /// Penalties for (lane types, lane-changing, slow lane). The penalty may depend on the vehicle
     /// performing the turn. Lower means preferable.
@@ -290,3 +335,52 @@ impl TurnID {
         }
     }
 }
+
+/// The expected wait for a gap of at least `gap` in vehicles arriving randomly at `volume` per
+/// second, when a fraction `yield_rate` of drivers stop anyway.
+fn expected_wait_for_gap(volume: f64, gap: Duration, yield_rate: f64) -> Duration {
+    if volume == 0.0 {
+        return Duration::ZERO;
+    }
+    let gap = gap.inner_seconds();
+    let wait = ((volume * gap).exp() - volume * gap - 1.0) / volume;
+    // Past a few minutes, people would give up and find somewhere else to cross. Clamp before
+    // making a Duration, since the exponential overflows for long gaps.
+    Duration::seconds(((1.0 - yield_rate) * wait).min(180.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expected_wait_for_gap() {
+        let per_hour = |x: f64| x / 3600.0;
+
+        assert_eq!(
+            expected_wait_for_gap(0.0, Duration::seconds(10.0), 0.0),
+            Duration::ZERO
+        );
+
+        // One local lane, a 10s gap, and half of the drivers yielding
+        let wait = expected_wait_for_gap(per_hour(60.0), Duration::seconds(10.0), 0.5);
+        assert!((wait.inner_seconds() - 0.44).abs() < 0.01, "{}", wait);
+
+        // Busier roads and longer gaps mean longer waits
+        let quiet = expected_wait_for_gap(per_hour(300.0), Duration::seconds(10.0), 0.0);
+        let busy = expected_wait_for_gap(per_hour(1200.0), Duration::seconds(10.0), 0.0);
+        let wide = expected_wait_for_gap(per_hour(300.0), Duration::seconds(20.0), 0.0);
+        assert!(quiet < busy);
+        assert!(quiet < wide);
+
+        // Long gaps on a highway overflow the exponential, but the wait is clamped
+        assert_eq!(
+            expected_wait_for_gap(per_hour(3200.0), Duration::seconds(1000.0), 0.0),
+            Duration::seconds(180.0)
+        );
+        assert_eq!(
+            expected_wait_for_gap(per_hour(3200.0), Duration::seconds(100.0), 0.3),
+            Duration::seconds(180.0)
+        );
+    }
+}
@@ -30,6 +30,9 @@ pub const ENDPT_BACK: &str = "abst:endpt_back";
 // Any roads might have these.
 pub const INFERRED_PARKING: &str = "abst:parking_inferred";
 pub const INFERRED_SIDEWALKS: &str = "abst:sidewalks_inferred";
+// A crossing near one end of the road is tagged with a raised curb, so there's no curb ramp.
+pub const NO_CURB_RAMP_FWD: &str = "abst:no_curb_ramp_fwd";
+pub const NO_CURB_RAMP_BACK: &str = "abst:no_curb_ramp_back";
 
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
 pub enum RoadRank {
@@ -0,0 +1,98 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use geom::Time;
+
+use crate::{RoadID, RoutingParams};
+
+/// Ignore roads that were barely slower than free-flow. This keeps the profile small, and avoids
+/// building custom pathfinders for hours with no real congestion.
+const MIN_SLOWDOWN: f64 = 1.1;
+/// A few vehicles stuck behind something unusual shouldn't make a road look impassable forever.
+const MAX_SLOWDOWN: f64 = 10.0;
+/// Slowdowns are rounded to a multiple of this. Every distinct set of slowdowns needs its own
+/// pathfinder, so this lets hours (and days, when drivers learn gradually) with similar congestion
+/// share one.
+const SLOWDOWN_STEP: f64 = 0.25;
+
+/// How much longer than free-flow it took to drive along roads during each hour of the day,
+/// usually measured from a previous simulation. Routing with this lets vehicles anticipate the
+/// congestion they'll probably hit when they depart, instead of assuming empty roads.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct CongestionProfile {
+    /// Indexed by hour of the day. A road missing from some hour wasn't congested then.
+    per_hour: Vec<BTreeMap<RoadID, f64>>,
+}
+
+impl CongestionProfile {
+    /// Each hour maps a road to the ratio of the observed time to cross it over the free-flow time.
+    /// Small slowdowns are ignored, and the rest are rounded.
+    pub fn new(per_hour: Vec<BTreeMap<RoadID, f64>>) -> CongestionProfile {
+        CongestionProfile {
+            per_hour: per_hour
+                .into_iter()
+                .map(|slowdowns| {
+                    slowdowns
+                        .into_iter()
+                        .filter(|(_, x)| *x >= MIN_SLOWDOWN)
+                        .map(|(r, x)| {
+                            (
+                                r,
+                                (x.min(MAX_SLOWDOWN) / SLOWDOWN_STEP).round() * SLOWDOWN_STEP,
+                            )
+                        })
+                        // Rounding might bring something back to free-flow
+                        .filter(|(_, x)| *x > 1.0)
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+
+    /// How many times slower than free-flow is it to drive along this road at some time? 1.0
+    /// means no congestion.
+    pub fn slowdown(&self, r: RoadID, time: Time) -> f64 {
+        self.per_hour
+            .get(time.get_hours())
+            .and_then(|slowdowns| slowdowns.get(&r))
+            .cloned()
+            .unwrap_or(1.0)
+    }
+
+    /// Adjust some routing parameters to account for congestion at a certain time. None means
+    /// nothing was congested then, so the parameters don't need to change.
+    pub fn routing_params(&self, base: &RoutingParams, time: Time) -> Option<RoutingParams> {
+        let slowdowns = self.per_hour.get(time.get_hours())?;
+        if slowdowns.is_empty() {
+            return None;
+        }
+        let mut params = base.clone();
+        params.road_slowdowns = slowdowns.clone();
+        Some(params)
+    }
+
+    /// Moves `weight` of the way from this profile towards `other`, like drivers gradually
+    /// learning about congestion they keep hitting. A weight of 0 keeps this profile, and 1
+    /// replaces it entirely.
+    pub fn blend(&self, other: &CongestionProfile, weight: f64) -> CongestionProfile {
+        let empty = BTreeMap::new();
+        let mut per_hour = Vec::new();
+        for hour in 0..self.per_hour.len().max(other.per_hour.len()) {
+            let before = self.per_hour.get(hour).unwrap_or(&empty);
+            let after = other.per_hour.get(hour).unwrap_or(&empty);
+            let mut slowdowns = BTreeMap::new();
+            for r in before.keys().chain(after.keys()) {
+                let x = before.get(r).cloned().unwrap_or(1.0);
+                let y = after.get(r).cloned().unwrap_or(1.0);
+                slowdowns.insert(*r, (1.0 - weight) * x + weight * y);
+            }
+            per_hour.push(slowdowns);
+        }
+        CongestionProfile::new(per_hour)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.per_hour.iter().all(|slowdowns| slowdowns.is_empty())
+    }
+}
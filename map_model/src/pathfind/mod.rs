@@ -1,12 +1,13 @@
 //! Everything related to pathfinding through a map for different types of agents.
 
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 
 use enumset::EnumSetType;
 use serde::{Deserialize, Serialize};
 
 use geom::Duration;
 
+pub use self::congestion::CongestionProfile;
 pub use self::engine::CreateEngine;
 pub use self::pathfinder::Pathfinder;
 pub use self::v1::{Path, PathRequest, PathStep};
@@ -15,6 +16,7 @@ pub use self::vehicles::vehicle_cost;
 pub use self::walking::WalkingNode;
 use crate::{osm, Lane, LaneID, LaneType, Map, MovementID, RoadID, TurnType};
 
+mod congestion;
 mod engine;
 mod node_map;
 mod pathfinder;
@@ -175,7 +177,8 @@ pub struct RoutingParams {
     // further "delay" on top of that!)
     // TODO But even steeper roads matter more!
     pub avoid_steep_incline_penalty: f64,
-    // If the road is `high_stress_for_bikes`, multiply by the base cost.
+    // If the road's `bike_stress` is Lts3, multiply by the base cost. For Lts4, multiply by the
+    // square.
     pub avoid_high_stress: f64,
 
     /// When crossing an arterial or highway road, multiply the base cost by this penalty. When
@@ -193,6 +196,11 @@ pub struct RoutingParams {
     /// destination. Only affects vehicle routing, not pedestrian.
     #[serde(skip_serializing, skip_deserializing)]
     pub avoid_movements_between: BTreeSet<(RoadID, RoadID)>,
+
+    /// Multiply the base cost of driving along these roads, to account for expected congestion.
+    /// Only affects cars and buses. See `CongestionProfile`.
+    #[serde(skip_serializing, skip_deserializing)]
+    pub road_slowdowns: BTreeMap<RoadID, f64>,
 }
 
 impl Default for RoutingParams {
@@ -213,6 +221,8 @@ impl Default for RoutingParams {
 
             avoid_roads: BTreeSet::new(),
             avoid_movements_between: BTreeSet::new(),
+
+            road_slowdowns: BTreeMap::new(),
         }
     }
 }
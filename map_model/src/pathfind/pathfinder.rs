@@ -4,7 +4,7 @@ use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 use thread_local::ThreadLocal;
 
-use abstutil::{Timer, VecMap};
+use abstutil::Timer;
 use geom::Duration;
 
 use crate::pathfind::engine::CreateEngine;
@@ -27,12 +27,17 @@ pub struct Pathfinder {
     // These params cover the main graphs
     params: RoutingParams,
 
-    // Callers can opt into caching with pathfind_with_params
-    // TODO VecMap is probably fast enough. RoutingParams is annoying to implement Hash.
+    // Callers can opt into caching with pathfind_with_params. The most recently used are last.
+    // TODO A Vec is probably fast enough. RoutingParams is annoying to implement Hash.
     #[serde(skip_serializing, skip_deserializing)]
-    cached_alternatives: ThreadLocal<RefCell<VecMap<(PathConstraints, RoutingParams), Pathfinder>>>,
+    cached_alternatives: ThreadLocal<RefCell<Vec<((PathConstraints, RoutingParams), Pathfinder)>>>,
 }
 
+/// How many pathfinders with custom params each thread remembers. Each one is a full graph, so on a
+/// large map, caching one for every set of params ever used (like congestion for every hour of the
+/// day, for each routing profile) takes too much memory. The least recently used is forgotten.
+const MAX_CACHED_ALTERNATIVES: usize = 8;
+
 // Implemented manually to deal with the ThreadLocal
 impl Clone for Pathfinder {
     fn clone(&self) -> Self {
@@ -185,13 +190,24 @@ impl Pathfinder {
 
         // If the params differ from the ones baked into the map, the CHs won't match. Do we have a
         // cached alternative?
-        if let Some(alt) = self
-            .cached_alternatives
-            .get_or(|| RefCell::new(VecMap::new()))
-            .borrow()
-            .get(&(constraints, params.clone()))
         {
-            return alt.pathfind_with_params(req, params, false, map);
+            let mut cache = self
+                .cached_alternatives
+                .get_or(|| RefCell::new(Vec::new()))
+                .borrow_mut();
+            if let Some(idx) = cache
+                .iter()
+                .position(|(key, _)| key.0 == constraints && &key.1 == params)
+            {
+                // Mark it as the most recently used
+                let entry = cache.remove(idx);
+                cache.push(entry);
+                return cache
+                    .last()
+                    .unwrap()
+                    .1
+                    .pathfind_with_params(req, params, false, map);
+            }
         }
 
         // If somebody's repeatedly calling this without caching, log very obnoxiously.
@@ -205,17 +221,21 @@ impl Pathfinder {
         );
         let result = tmp_pathfinder.pathfind_with_params(req, params, false, map);
         if cache_custom {
-            self.cached_alternatives
-                .get_or(|| RefCell::new(VecMap::new()))
-                .borrow_mut()
-                .push((constraints, params.clone()), tmp_pathfinder);
+            let mut cache = self
+                .cached_alternatives
+                .get_or(|| RefCell::new(Vec::new()))
+                .borrow_mut();
+            if cache.len() >= MAX_CACHED_ALTERNATIVES {
+                cache.remove(0);
+            }
+            cache.push(((constraints, params.clone()), tmp_pathfinder));
         }
         result
     }
 
     pub fn clear_custom_pathfinder_cache(&self) {
         self.cached_alternatives
-            .get_or(|| RefCell::new(VecMap::new()))
+            .get_or(|| RefCell::new(Vec::new()))
             .borrow_mut()
             .clear();
     }
@@ -14,8 +14,8 @@ use crate::pathfind::uber_turns::{IntersectionCluster, UberTurnV2};
 use crate::pathfind::zone_cost;
 use crate::pathfind::{round, unround};
 use crate::{
-    osm, DirectedRoadID, Direction, LaneType, Map, MovementID, PathConstraints, PathRequest,
-    PathV2, Position, RoutingParams, Traversable,
+    osm, DirectedRoadID, Direction, LaneType, LevelOfTrafficStress, Map, MovementID,
+    PathConstraints, PathRequest, PathV2, Position, RoutingParams, Traversable,
 };
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -330,11 +330,17 @@ pub fn vehicle_cost(
         }
     }
 
-    if constraints == PathConstraints::Bike
-        && (params.avoid_high_stress - 1.0).abs() > f64::EPSILON
-        && road.high_stress_for_bikes(map, dr.dir)
+    if constraints == PathConstraints::Bike && (params.avoid_high_stress - 1.0).abs() > f64::EPSILON
     {
-        multiplier *= params.avoid_high_stress;
+        match road.bike_stress(map, dr.dir) {
+            LevelOfTrafficStress::Lts1 | LevelOfTrafficStress::Lts2 => {}
+            LevelOfTrafficStress::Lts3 => {
+                multiplier *= params.avoid_high_stress;
+            }
+            LevelOfTrafficStress::Lts4 => {
+                multiplier *= params.avoid_high_stress * params.avoid_high_stress;
+            }
+        }
     }
 
     let mut extra = zone_cost(mvmnt, constraints, map);
@@ -349,6 +355,12 @@ pub fn vehicle_cost(
         multiplier *= params.main_road_penalty;
     }
 
+    if constraints == PathConstraints::Car || constraints == PathConstraints::Bus {
+        if let Some(slowdown) = params.road_slowdowns.get(&dr.road) {
+            multiplier *= *slowdown;
+        }
+    }
+
     if params.avoid_roads.contains(&dr.road) {
         // Similar to the zone_cost trick, just add an outrageous penalty here
         extra += Duration::hours(3);
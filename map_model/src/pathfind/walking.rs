@@ -14,7 +14,7 @@ use crate::pathfind::zone_cost;
 use crate::pathfind::{round, unround};
 use crate::{
     BusRoute, BusRouteID, BusStopID, DirectedRoadID, IntersectionID, Map, MovementID,
-    PathConstraints, PathRequest, PathStep, PathStepV2, PathV2, Position, TurnType,
+    PathConstraints, PathRequest, PathStep, PathStepV2, PathV2, Position,
 };
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -304,10 +304,8 @@ fn make_input_graph(
                 WalkingNode::SidewalkEndpoint(dst.get_directed_parent(), dst.dst_i == t.id.parent);
             let mut cost = t.geom.length()
                 / PathStep::Turn(t.id).max_speed_along(max_speed, PathConstraints::Pedestrian, map);
-            if t.turn_type == TurnType::UnmarkedCrossing {
-                // TODO Add to RoutingParams
-                cost = 3.0 * cost;
-            }
+            // Waiting for a gap in traffic at unmarked crossings
+            cost += t.unmarked_crossing_delay(map, crate::MAX_WALKING_SPEED);
 
             input_graph.add_edge(
                 nodes.get(from),
@@ -11,14 +11,15 @@ use serde::{Deserialize, Serialize};
 
 use abstio::{CityName, MapName};
 use abstutil::{deserialize_btreemap, serialize_btreemap, Tags, Timer};
-use geom::{Distance, GPSBounds, PolyLine, Polygon, Pt2D};
+use geom::{Distance, Duration, GPSBounds, PolyLine, Polygon, Pt2D, Time};
 
 use crate::make::initial::lane_specs::get_lane_specs_ltr;
 use crate::{
     osm, Amenity, AreaType, Direction, DrivingSide, IntersectionType, LaneType, MapConfig,
+    StopSignPlacement, TurnType,
 };
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct RawMap {
     pub name: MapName,
     #[serde(
@@ -39,6 +40,9 @@ pub struct RawMap {
     pub areas: Vec<RawArea>,
     pub parking_lots: Vec<RawParkingLot>,
     pub parking_aisles: Vec<(osm::WayID, Vec<Pt2D>)>,
+    /// Public transit routes from a GTFS feed, referring to `transit_stops` by their GTFS ID.
+    pub transit_routes: Vec<RawTransitRoute>,
+    pub transit_stops: BTreeMap<String, RawTransitStop>,
 
     pub boundary_polygon: Polygon,
     pub gps_bounds: GPSBounds,
@@ -114,6 +118,8 @@ impl RawMap {
             areas: Vec::new(),
             parking_lots: Vec::new(),
             parking_aisles: Vec::new(),
+            transit_routes: Vec::new(),
+            transit_stops: BTreeMap::new(),
             // Some nonsense thing
             boundary_polygon: Polygon::rectangle(1.0, 1.0),
             gps_bounds: GPSBounds::new(),
@@ -270,6 +276,110 @@ impl RawMap {
         Some(fixed)
     }
 
+    /// Split a road with a new intersection `dist` along its center line, marking a pedestrian
+    /// crossing there. The crossing starts uncontrolled, so drivers just yield to people crossing,
+    /// but it can be changed to a stop sign or signal like any other intersection. Returns the new
+    /// intersection.
+    pub fn add_crossing(&mut self, id: OriginalRoad, dist: Distance) -> Result<osm::NodeID> {
+        // Closer than this, the crossing would really be part of the intersection
+        let min_dist = Distance::meters(5.0);
+        let pl = PolyLine::new(self.roads[&id].center_points.clone())?;
+        if dist < min_dist || dist > pl.length() - min_dist {
+            bail!(
+                "A crossing must be at least {} from both ends of {}, which is {} long",
+                min_dist,
+                id,
+                pl.length()
+            );
+        }
+        let point = pl.must_dist_along(dist).0;
+        let first_pts = pl.maybe_exact_slice(Distance::ZERO, dist)?.into_points();
+        let second_pts = pl.maybe_exact_slice(dist, pl.length())?.into_points();
+
+        let new_i = self.new_osm_node_id(-1);
+        let pct = dist / pl.length();
+        let elevation = self.intersections[&id.i1].elevation * (1.0 - pct)
+            + self.intersections[&id.i2].elevation * pct;
+        self.intersections.insert(
+            new_i,
+            RawIntersection {
+                point,
+                intersection_type: IntersectionType::StopSign,
+                stop_sign_placement: StopSignPlacement::Uncontrolled,
+                elevation,
+                trim_roads_for_merging: BTreeMap::new(),
+                midblock_crossing: true,
+            },
+        );
+
+        let r1 = OriginalRoad {
+            osm_way_id: id.osm_way_id,
+            i1: id.i1,
+            i2: new_i,
+        };
+        let r2 = OriginalRoad {
+            osm_way_id: id.osm_way_id,
+            i1: new_i,
+            i2: id.i2,
+        };
+        // Which half of the split road does another road touch?
+        let half_touching = |other: &OriginalRoad| {
+            if other.i1 == id.i1 || other.i2 == id.i1 {
+                r1
+            } else {
+                r2
+            }
+        };
+
+        // The new intersection splits the OSM way, so only the outer pieces are endpoints of it.
+        // Everything tagged about the original ends stays with that end.
+        let mut first = self.roads.remove(&id).unwrap();
+        let mut second = first.clone();
+        first.center_points = first_pts;
+        first.osm_tags.remove(osm::ENDPT_FWD);
+        first.osm_tags.remove(osm::NO_CURB_RAMP_FWD);
+        first.crosswalk_forward = true;
+        second.center_points = second_pts;
+        second.osm_tags.remove(osm::ENDPT_BACK);
+        second.osm_tags.remove(osm::NO_CURB_RAMP_BACK);
+        second.crosswalk_backward = true;
+
+        // Restrictions starting from this road go through one of its ends
+        first
+            .turn_restrictions
+            .retain(|(_, to)| half_touching(to) == r1);
+        second
+            .turn_restrictions
+            .retain(|(_, to)| half_touching(to) == r2);
+        first
+            .complicated_turn_restrictions
+            .retain(|(via, _)| half_touching(via) == r1);
+        second
+            .complicated_turn_restrictions
+            .retain(|(via, _)| half_touching(via) == r2);
+
+        // Restrictions ending at this road now end at the half they reach first. A restriction
+        // passing through the whole road can't be expressed anymore.
+        for (other_id, road) in &mut self.roads {
+            for (_, to) in &mut road.turn_restrictions {
+                if *to == id {
+                    *to = half_touching(other_id);
+                }
+            }
+            road.complicated_turn_restrictions
+                .retain(|(via, _)| *via != id);
+            for (via, to) in &mut road.complicated_turn_restrictions {
+                if *to == id {
+                    *to = half_touching(via);
+                }
+            }
+        }
+
+        self.roads.insert(r1, first);
+        self.roads.insert(r2, second);
+        Ok(new_i)
+    }
+
     pub fn closest_intersection(&self, pt: Pt2D) -> osm::NodeID {
         self.intersections
             .iter()
@@ -326,6 +436,9 @@ impl RawMap {
             {
                 bail!("{} touches a border", short);
             }
+            if i1.midblock_crossing || i2.midblock_crossing {
+                bail!("{} touches a midblock crossing", short);
+            }
         }
 
         // TODO Fix up turn restrictions. Many cases:
@@ -548,6 +661,161 @@ impl RawMap {
     }
 }
 
+// Turn restrictions
+impl RawMap {
+    /// Every simple turn restriction through an intersection. Restrictions are stored on the road
+    /// they start from without the intersection they pass through, so a restriction between two
+    /// roads that meet at both ends (or a U-turn restriction) is listed at both ends.
+    pub fn turn_restrictions_at(&self, i: osm::NodeID) -> Vec<TurnRestriction> {
+        let mut results = Vec::new();
+        for from in self.roads_per_intersection(i) {
+            for (rt, to) in &self.roads[&from].turn_restrictions {
+                // The importer drops restrictions pointing at roads that don't exist
+                if (to.i1 == i || to.i2 == i) && self.roads.contains_key(to) {
+                    results.push(TurnRestriction(from, *rt, *to));
+                }
+            }
+        }
+        results
+    }
+
+    /// Adds a simple turn restriction, replacing any existing one between the same two roads.
+    pub fn add_turn_restriction(&mut self, restriction: TurnRestriction) {
+        let TurnRestriction(from, rt, to) = restriction;
+        let road = self.roads.get_mut(&from).unwrap();
+        road.turn_restrictions.retain(|(_, x)| *x != to);
+        road.turn_restrictions.push((rt, to));
+    }
+
+    pub fn remove_turn_restriction(&mut self, restriction: TurnRestriction) {
+        let TurnRestriction(from, rt, to) = restriction;
+        if let Some(road) = self.roads.get_mut(&from) {
+            road.turn_restrictions.retain(|x| *x != (rt, to));
+        }
+    }
+
+    /// Do the simple turn restrictions allow vehicles to go from one road to another through an
+    /// intersection? This matches how turns are filtered when the full map is built.
+    pub fn turn_restrictions_permit(
+        &self,
+        i: osm::NodeID,
+        from: OriginalRoad,
+        to: OriginalRoad,
+    ) -> bool {
+        for (rt, restricted) in &self.roads[&from].turn_restrictions {
+            // The restriction only applies to one end of the road.
+            if restricted.i1 != i && restricted.i2 != i {
+                continue;
+            }
+            match rt {
+                RestrictionType::BanTurns => {
+                    if to == *restricted {
+                        return false;
+                    }
+                }
+                RestrictionType::OnlyAllowTurns => {
+                    if to != *restricted {
+                        return false;
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    /// Classifies the movement from one road to another through an intersection, using the
+    /// angles of the road center-lines where they meet it. None if the geometry is broken.
+    pub fn movement_type(
+        &self,
+        i: osm::NodeID,
+        from: OriginalRoad,
+        to: OriginalRoad,
+    ) -> Option<TurnType> {
+        if from == to {
+            return Some(TurnType::UTurn);
+        }
+        let from_pts = &self.roads[&from].center_points;
+        let to_pts = &self.roads[&to].center_points;
+        if from_pts.len() < 2 || to_pts.len() < 2 {
+            return None;
+        }
+        let approach = if from.i2 == i {
+            from_pts[from_pts.len() - 2].angle_to(from_pts[from_pts.len() - 1])
+        } else {
+            from_pts[1].angle_to(from_pts[0])
+        };
+        let exit = if to.i1 == i {
+            to_pts[0].angle_to(to_pts[1])
+        } else {
+            to_pts[to_pts.len() - 1].angle_to(to_pts[to_pts.len() - 2])
+        };
+        Some(crate::make::turns::turn_type_from_angles(approach, exit))
+    }
+
+    /// Roads that vehicles can use to enter and to leave an intersection, based on the direction
+    /// of driving lanes.
+    pub fn vehicle_roads_at(&self, i: osm::NodeID) -> (Vec<OriginalRoad>, Vec<OriginalRoad>) {
+        let mut incoming = Vec::new();
+        let mut outgoing = Vec::new();
+        for r in self.roads_per_intersection(i) {
+            for spec in get_lane_specs_ltr(&self.roads[&r].osm_tags, &self.config) {
+                if spec.lt != LaneType::Driving {
+                    continue;
+                }
+                // Forward lanes point from i1 to i2
+                let towards_i = (spec.dir == Direction::Fwd) == (r.i2 == i);
+                let list = if towards_i {
+                    &mut incoming
+                } else {
+                    &mut outgoing
+                };
+                if !list.contains(&r) {
+                    list.push(r);
+                }
+            }
+        }
+        (incoming, outgoing)
+    }
+
+    /// Describes every way the turn restrictions at an intersection disconnect the road network:
+    /// roads that can enter the intersection but have no permitted exit, and roads that can be
+    /// entered from the intersection but have no permitted way in.
+    pub fn turn_restriction_problems(&self, i: osm::NodeID) -> Vec<String> {
+        if self.turn_restrictions_at(i).is_empty() {
+            return Vec::new();
+        }
+
+        let mut problems = Vec::new();
+        let (incoming, outgoing) = self.vehicle_roads_at(i);
+        for from in &incoming {
+            if !outgoing.is_empty()
+                && !outgoing
+                    .iter()
+                    .any(|to| self.turn_restrictions_permit(i, *from, *to))
+            {
+                problems.push(format!(
+                    "turn restrictions leave vehicles on {} no way out of {}",
+                    from, i
+                ));
+            }
+        }
+        for to in &outgoing {
+            // A dead-end is only reachable by U-turning, so don't complain about those.
+            if incoming.iter().any(|from| from != to)
+                && !incoming
+                    .iter()
+                    .any(|from| self.turn_restrictions_permit(i, *from, *to))
+            {
+                problems.push(format!(
+                    "turn restrictions leave no way onto {} from {}",
+                    to, i
+                ));
+            }
+        }
+        problems
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct RawRoad {
     /// This is effectively a PolyLine, except there's a case where we need to plumb forward
@@ -638,6 +906,9 @@ impl RawRoad {
         PolyLine::unchecked_new(self.center_points.clone()).length()
     }
 
+    /// Which layer the road is on. Higher numbers are drawn on top, and roads on different layers
+    /// can't be connected by turns where they cross. Bridges and tunnels without an explicit
+    /// `layer` tag are assumed to be one layer above or below the ground.
     pub fn get_zorder(&self) -> isize {
         if let Some(layer) = self.osm_tags.get("layer") {
             match layer.parse::<f64>() {
@@ -652,6 +923,13 @@ impl RawRoad {
                     0
                 }
             }
+        } else if self.osm_tags.contains_key("bridge") && !self.osm_tags.is("bridge", "no") {
+            1
+        } else if self.osm_tags.contains_key("tunnel")
+            // A building passage goes through a building at ground level
+            && !self.osm_tags.is_any("tunnel", vec!["no", "building_passage"])
+        {
+            -1
         } else {
             0
         }
@@ -664,10 +942,16 @@ pub struct RawIntersection {
     /// RawMap; roads and intersections get merged and deleted.
     pub point: Pt2D,
     pub intersection_type: IntersectionType,
+    /// Only meaningful for stop sign intersections
+    pub stop_sign_placement: StopSignPlacement,
     pub elevation: Distance,
 
     // true if src_i matches this intersection (or the deleted/consolidated one, whatever)
     pub trim_roads_for_merging: BTreeMap<(osm::WayID, bool), Pt2D>,
+    /// A pedestrian crossing splitting a road, rather than a place where roads meet. These are
+    /// never collapsed or merged away.
+    #[serde(default)]
+    pub midblock_crossing: bool,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -694,6 +978,34 @@ pub struct RawParkingLot {
     pub osm_tags: Tags,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RawTransitRoute {
+    pub long_name: String,
+    pub short_name: String,
+    /// The GTFS route_id, plus a suffix distinguishing different stop patterns of the same route
+    pub gtfs_id: String,
+    /// The GTFS IDs of stops, in order, clipped to the map boundary
+    pub stops: Vec<String>,
+    pub route_type: RawTransitType,
+    /// When a vehicle should depart from the first stop, in order, for one typical weekday
+    pub spawn_times: Vec<Time>,
+    /// For each stop, how long after departing the first stop a vehicle is scheduled to arrive
+    pub stop_offsets: Vec<Duration>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RawTransitType {
+    Bus,
+    Train,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RawTransitStop {
+    pub gtfs_id: String,
+    pub position: Pt2D,
+    pub name: String,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum RestrictionType {
     BanTurns,
@@ -84,30 +84,17 @@ impl Position {
         let other_lane = map.get_l(other_lane);
         assert_eq!(our_lane.id.road, other_lane.id.road);
 
-        let pl = &other_lane.lane_center_pts;
-        let pt = self.pt(map);
-        if let Some((mut dist, _)) = pl.dist_along_of_point(pl.project_pt(pt)) {
-            if other_lane.dir != our_lane.dir {
-                // Account for the object_length if needed
-                dist += object_length;
-                dist = dist.max(Distance::ZERO).min(other_lane.length());
-            }
-            return Position::new(other_lane.id, dist);
-        }
-        // TODO So far I haven't observed this happening, but fallback if so.
-        warn!("equiv_pos of {} for {} messes up", self, other_lane.id);
-
-        let len = other_lane.length();
-        if other_lane.dir == our_lane.dir {
-            Position::new(other_lane.id, self.dist_along.min(len))
-        } else {
-            Position::new(
-                other_lane.id,
-                (len - self.dist_along + object_length)
-                    .max(Distance::ZERO)
-                    .min(len),
-            )
+        let mut dist = other_lane
+            .lane_center_pts
+            .project_pt_detailed(self.pt(map))
+            .dist_along
+            .min(other_lane.length());
+        if other_lane.dir != our_lane.dir {
+            // Account for the object_length if needed
+            dist += object_length;
+            dist = dist.max(Distance::ZERO).min(other_lane.length());
         }
+        Position::new(other_lane.id, dist)
     }
     pub fn min_dist(mut self, dist_along: Distance, map: &Map) -> Option<Position> {
         if self.dist_along >= dist_along {
@@ -190,7 +177,12 @@ impl Traversable {
     pub fn get_zorder(&self, map: &Map) -> isize {
         match *self {
             Traversable::Lane(id) => map.get_parent(id).zorder,
-            Traversable::Turn(id) => map.get_i(id.parent).get_zorder(map),
+            // Roads on different layers can meet at one intersection, so use the turn's own
+            // roads. When a turn climbs onto a bridge, draw it on top.
+            Traversable::Turn(id) => map
+                .get_parent(id.src)
+                .zorder
+                .max(map.get_parent(id.dst).zorder),
         }
     }
 
@@ -13,9 +13,13 @@ use map_model::{
 };
 
 use crate::{
-    AgentID, AgentType, AlertLocation, CarID, Event, ParkingSpot, TripID, TripMode, TripPhaseType,
+    AgentID, AgentType, AlertLocation, CarID, EmissionsPerRoad, Event, ExposureLocation,
+    ParkingSpot, PersonID, TravelTimesPerRoad, TripID, TripMode, TripPhaseType,
 };
 
+/// Roughly how many lane and turn traversals to remember for replaying trips
+const MAX_TRIP_TRAVERSALS: usize = 2_000_000;
+
 /// As a simulation runs, different pieces emit Events. The Analytics object listens to these,
 /// organizing and storing some information from them. The UI queries Analytics to draw time-series
 /// and display statistics.
@@ -24,6 +28,8 @@ use crate::{
 /// results." These are just serialized Analytics after running the simulation on a map without any
 /// edits for the full day. This is the basis of A/B testing -- the player can edit the map, start
 /// running the simulation, and compare the live Analytics to the prebaked baseline Analytics.
+///
+/// Prebaked results leave out `detailed`, so that older files keep loading.
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Analytics {
     pub road_thruput: TimeSeriesCount<RoadID>,
@@ -52,9 +58,9 @@ pub struct Analytics {
 
     // TODO This subsumes finished_trips
     pub trip_log: Vec<(Time, TripID, Option<PathRequest>, TripPhaseType)>,
-
     // TODO Transit riders aren't represented here yet, just the vehicle they're riding.
-    /// Only for traffic signals. The u8 is the movement index from a CompressedMovementID.
+    /// Only for traffic signals and stop signs. The u8 is the movement index from a
+    /// CompressedMovementID.
     pub intersection_delays: BTreeMap<IntersectionID, Vec<(u8, Time, Duration, AgentType)>>,
 
     /// Per parking lane or lot, when does a spot become filled (true) or free (false)
@@ -63,10 +69,58 @@ pub struct Analytics {
 
     pub(crate) alerts: Vec<(Time, AlertLocation, String)>,
 
+    /// Detailed data, only kept in simulation checkpoints.
+    #[serde(skip)]
+    pub detailed: DetailedAnalytics,
+
     /// For benchmarking, we may want to disable collecting data.
     record_anything: bool,
 }
 
+/// Analytics left out of prebaked results and savestates. Simulation checkpoints save these
+/// separately.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct DetailedAnalytics {
+    /// Actual arrival, vehicle, route, stop, and scheduled arrival, for stops with a schedule
+    pub bus_schedule_adherence: Vec<(Time, CarID, BusRouteID, BusStopID, Time)>,
+    /// When each trip's agent entered every lane and turn, so the UI can replay where they were.
+    /// This grows quickly, so the oldest finished trips are forgotten once there are more than
+    /// `MAX_TRIP_TRAVERSALS` entries.
+    pub trip_traversals: BTreeMap<TripID, Vec<(Time, Traversable)>>,
+    num_trip_traversals: usize,
+    /// The index into finished_trips of the oldest trip that might still have traversals
+    oldest_traversals: usize,
+    /// Never forget traversals, because something needs every trip's full route
+    keep_all_trip_traversals: bool,
+
+    /// When the pandemic model is enabled, every exposure: when and where it happened, who was
+    /// exposed, and who they were exposed to.
+    pub exposures: Vec<(Time, ExposureLocation, PersonID, PersonID)>,
+
+    /// When drivers cruise for parking, the total time each trip spent circling before finding a
+    /// spot.
+    pub parking_cruising_time: BTreeMap<TripID, Duration>,
+    cruising_since: BTreeMap<TripID, Time>,
+
+    /// Every time a queue spilled back into an intersection: when, and which lane was full.
+    pub queue_spillbacks: BTreeMap<IntersectionID, Vec<(Time, LaneID)>>,
+    /// When each emergency vehicle arrived, and how long it took to get there after being
+    /// dispatched.
+    pub emergency_responses: Vec<(Time, TripID, Duration)>,
+    /// The traffic signals that each emergency vehicle's trip preempted, and when.
+    pub signal_preemptions: BTreeMap<TripID, Vec<(Time, IntersectionID)>>,
+    /// Every time a delivery or ride-hail vehicle double-parked on a road: when it pulled away,
+    /// and how long it blocked the lane.
+    pub double_parking: BTreeMap<RoadID, Vec<(Time, Duration)>>,
+
+    /// Estimated tailpipe emissions per road.
+    pub emissions: EmissionsPerRoad,
+    /// How congested each road was over time, to route later simulations with.
+    pub road_travel_times: TravelTimesPerRoad,
+    /// Which lane each vehicle is on, to measure emissions and travel times once it's finished
+    current_lanes: CurrentLanes,
+}
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Problem {
     /// A vehicle waited >30s, or a pedestrian waited >15s.
@@ -97,6 +151,7 @@ impl Analytics {
             parking_lane_changes: BTreeMap::new(),
             parking_lot_changes: BTreeMap::new(),
             alerts: Vec::new(),
+            detailed: DetailedAnalytics::default(),
             record_anything,
         }
     }
@@ -107,7 +162,17 @@ impl Analytics {
         }
 
         // Throughput
-        if let Event::AgentEntersTraversable(a, _, to, passengers) = ev {
+        if let Event::AgentEntersTraversable(a, maybe_trip, to, passengers) = ev {
+            if let Some(trip) = maybe_trip {
+                self.detailed
+                    .trip_traversals
+                    .entry(trip)
+                    .or_insert_with(Vec::new)
+                    .push((time, to));
+                self.detailed.num_trip_traversals += 1;
+                self.prune_trip_traversals();
+            }
+
             match to {
                 Traversable::Lane(l) => {
                     self.road_thruput.record(time, l.road, a.to_type(), 1);
@@ -155,10 +220,37 @@ impl Analytics {
             _ => {}
         }
 
+        // Emissions and travel times, measured whenever a vehicle finishes a lane
+        let finished = match ev {
+            Event::AgentEntersTraversable(AgentID::Car(car), _, Traversable::Lane(l), _) => {
+                self.detailed.current_lanes.enter_lane(car, l, time)
+            }
+            Event::AgentEntersTraversable(AgentID::Car(car), _, Traversable::Turn(_), _)
+            | Event::PersonLeavesMap(_, Some(AgentID::Car(car)), _) => {
+                self.detailed.current_lanes.finish_lane(car, time)
+            }
+            Event::CarReachedParkingSpot(car, _) | Event::BikeStoppedAtSidewalk(car, _) => {
+                // The vehicle stopped partway along the lane, so its speed over the lane isn't
+                // known
+                self.detailed.current_lanes.abandon_lane(car);
+                None
+            }
+            _ => None,
+        };
+        if let Some(lane) = finished {
+            self.detailed.emissions.record(&lane, map);
+            self.detailed.road_travel_times.record(&lane, map);
+        }
+
         // Bus arrivals
         if let Event::BusArrivedAtStop(bus, route, stop) = ev {
             self.bus_arrivals.push((time, bus, route, stop));
         }
+        if let Event::BusArrivalVsSchedule(bus, route, stop, scheduled) = ev {
+            self.detailed
+                .bus_schedule_adherence
+                .push((time, bus, route, stop, scheduled));
+        }
 
         // Passengers boarding/alighting
         if let Event::PassengerBoardsTransit(_, _, route, stop, waiting) = ev {
@@ -194,6 +286,26 @@ impl Analytics {
             self.finished_trips.push((time, id, mode, None));
         }
 
+        // Cruising for parking ends when the driver finds a spot (starting the parking phase) or
+        // the trip ends some other way
+        match ev {
+            Event::CruisingForParking(trip) => {
+                self.detailed.cruising_since.entry(trip).or_insert(time);
+            }
+            Event::TripPhaseStarting(trip, _, _, _)
+            | Event::TripFinished { trip, .. }
+            | Event::TripCancelled(trip, _) => {
+                if let Some(since) = self.detailed.cruising_since.remove(&trip) {
+                    *self
+                        .detailed
+                        .parking_cruising_time
+                        .entry(trip)
+                        .or_insert(Duration::ZERO) += time - since;
+                }
+            }
+            _ => {}
+        }
+
         // Intersection delay
         if let Event::IntersectionDelayMeasured(trip_id, turn_id, agent, delay) = ev {
             let threshold = match agent {
@@ -209,9 +321,9 @@ impl Analytics {
                     .push((time, Problem::IntersectionDelay(turn_id.parent, delay)));
             }
 
-            // Save memory and space by only storing these measurements at traffic signals, for
-            // turns that actually conflict (so no SharedSidewalkCorners).
-            if let Some((_, compressed)) = map.get_movement_for_traffic_signal(turn_id) {
+            // Save memory and space by only storing these measurements at traffic signals and stop
+            // signs, for turns that actually conflict (so no SharedSidewalkCorners).
+            if let Some((_, compressed)) = map.get_movement_for_controlled_intersection(turn_id) {
                 self.intersection_delays
                     .entry(turn_id.parent)
                     .or_insert_with(Vec::new)
@@ -219,6 +331,15 @@ impl Analytics {
             }
         }
 
+        // Blocking the box
+        if let Event::QueueSpillback(i, l, _) = ev {
+            self.detailed
+                .queue_spillbacks
+                .entry(i)
+                .or_insert_with(Vec::new)
+                .push((time, l));
+        }
+
         // Parking spot changes
         if let Event::CarReachedParkingSpot(_, spot) = ev {
             if let ParkingSpot::Onstreet(l, _) = spot {
@@ -295,6 +416,30 @@ impl Analytics {
                     .or_insert_with(Vec::new)
                     .push((time, problem));
             }
+            Event::PersonExposed(person, source, location) => {
+                self.detailed
+                    .exposures
+                    .push((time, location, person, source));
+            }
+            Event::SignalPreempted(trip, i) => {
+                self.detailed
+                    .signal_preemptions
+                    .entry(trip)
+                    .or_insert_with(Vec::new)
+                    .push((time, i));
+            }
+            Event::EmergencyVehicleArrived(trip, response_time) => {
+                self.detailed
+                    .emergency_responses
+                    .push((time, trip, response_time));
+            }
+            Event::DoubleParked(_, l, duration) => {
+                self.detailed
+                    .double_parking
+                    .entry(l.road)
+                    .or_insert_with(Vec::new)
+                    .push((time, duration));
+            }
             _ => {}
         }
     }
@@ -309,9 +454,70 @@ impl Analytics {
         }
     }
 
+    /// Don't forget the traversals of old trips, no matter how many pile up.
+    pub fn keep_all_trip_traversals(&mut self) {
+        self.detailed.keep_all_trip_traversals = true;
+    }
+
+    fn prune_trip_traversals(&mut self) {
+        if self.detailed.keep_all_trip_traversals {
+            return;
+        }
+        // Trips still underway are never pruned, so this might not get under the limit
+        while self.detailed.num_trip_traversals > MAX_TRIP_TRAVERSALS
+            && self.detailed.oldest_traversals < self.finished_trips.len()
+        {
+            let trip = self.finished_trips[self.detailed.oldest_traversals].1;
+            self.detailed.oldest_traversals += 1;
+            if let Some(list) = self.detailed.trip_traversals.remove(&trip) {
+                self.detailed.num_trip_traversals -= list.len();
+            }
+        }
+    }
+
     // TODO If these ever need to be speeded up, just cache the histogram and index in the events
     // list.
 
+    /// How many times queues spilled back into each intersection between two times, worst first.
+    pub fn worst_queue_spillbacks(&self, t1: Time, t2: Time) -> Vec<(IntersectionID, usize)> {
+        let mut results: Vec<(IntersectionID, usize)> = self
+            .detailed
+            .queue_spillbacks
+            .iter()
+            .map(|(i, list)| {
+                (
+                    *i,
+                    list.iter().filter(|(t, _)| *t >= t1 && *t <= t2).count(),
+                )
+            })
+            .filter(|(_, count)| *count > 0)
+            .collect();
+        results.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+        results
+    }
+
+    /// How long vehicles spent double-parked on each road, for stops that ended between two times.
+    /// Worst first.
+    pub fn double_parking_per_road(&self, t1: Time, t2: Time) -> Vec<(RoadID, Duration)> {
+        let mut results: Vec<(RoadID, Duration)> = self
+            .detailed
+            .double_parking
+            .iter()
+            .map(|(r, list)| {
+                (
+                    *r,
+                    list.iter()
+                        .filter(|(t, _)| *t >= t1 && *t <= t2)
+                        .map(|(_, duration)| *duration)
+                        .sum(),
+                )
+            })
+            .filter(|(_, total)| *total > Duration::ZERO)
+            .collect();
+        results.sort_by_key(|(_, total)| std::cmp::Reverse(*total));
+        results
+    }
+
     /// Ignores the current time. Returns None for cancelled trips.
     pub fn finished_trip_time(&self, trip: TripID) -> Option<Duration> {
         // TODO This is so inefficient!
@@ -473,6 +679,92 @@ impl Analytics {
         }
     }
 
+    /// Returns the total number of occupied on-street parking spots over time. Parking lots and
+    /// private spots in buildings aren't included.
+    pub fn onstreet_parking_occupancy(&self, now: Time) -> Vec<(Time, usize)> {
+        let mut changes: Vec<(Time, bool)> = self
+            .parking_lane_changes
+            .values()
+            .flatten()
+            .filter(|(t, _)| *t <= now)
+            .cloned()
+            .collect();
+        // A stable sort keeps the order of changes within each lane
+        changes.sort_by_key(|(t, _)| *t);
+
+        let mut pts = Vec::new();
+        let mut cnt = 0;
+        let mut last_t = Time::START_OF_DAY;
+        for (t, filled) in changes {
+            if t != last_t {
+                // Step functions. Don't interpolate.
+                pts.push((last_t, cnt));
+            }
+            last_t = t;
+            if filled {
+                cnt += 1;
+            } else {
+                cnt -= 1;
+            }
+        }
+        pts.push((last_t, cnt));
+        if last_t != now {
+            pts.push((now, cnt));
+        }
+        pts
+    }
+
+    /// For every road with on-street parking recorded, the most spots occupied along it at the
+    /// same time.
+    pub fn peak_onstreet_parking_per_road(&self, now: Time) -> BTreeMap<RoadID, usize> {
+        let mut changes_per_road: BTreeMap<RoadID, Vec<(Time, bool)>> = BTreeMap::new();
+        for (l, changes) in &self.parking_lane_changes {
+            changes_per_road
+                .entry(l.road)
+                .or_insert_with(Vec::new)
+                .extend(changes.iter().filter(|(t, _)| *t <= now).cloned());
+        }
+
+        let mut peaks = BTreeMap::new();
+        for (r, mut changes) in changes_per_road {
+            changes.sort_by_key(|(t, _)| *t);
+            let mut cnt: usize = 0;
+            let mut peak = 0;
+            for (_, filled) in changes {
+                if filled {
+                    cnt += 1;
+                    peak = peak.max(cnt);
+                } else {
+                    cnt -= 1;
+                }
+            }
+            peaks.insert(r, peak);
+        }
+        peaks
+    }
+
+    /// Returns the cumulative number of passengers who've boarded a route over time.
+    pub fn transit_boardings(&self, now: Time, route: BusRouteID) -> Vec<(Time, usize)> {
+        let mut times: Vec<Time> = self
+            .passengers_boarding
+            .values()
+            .flatten()
+            .filter(|(t, r, _)| *r == route && *t <= now)
+            .map(|(t, _, _)| *t)
+            .collect();
+        times.sort();
+
+        let mut pts = vec![(Time::START_OF_DAY, 0)];
+        for (idx, t) in times.into_iter().enumerate() {
+            // Step functions. Don't interpolate.
+            pts.push((t, idx));
+            pts.push((t, idx + 1));
+        }
+        let total = pts.last().unwrap().1;
+        pts.push((now, total));
+        pts
+    }
+
     fn parking_spot_availability(
         now: Time,
         changes: &[(Time, bool)],
@@ -661,6 +953,46 @@ impl<X: Ord + Clone> TimeSeriesCount<X> {
     }
 }
 
+/// Remembers when each vehicle entered the lane it's currently on, so that something can be
+/// measured once it reaches the end.
+#[derive(Clone, Default, Serialize, Deserialize)]
+struct CurrentLanes {
+    lanes: BTreeMap<CarID, (LaneID, Time)>,
+}
+
+/// A vehicle crossed an entire lane.
+pub(crate) struct FinishedLane {
+    pub car: CarID,
+    pub lane: LaneID,
+    pub entered: Time,
+    pub exited: Time,
+}
+
+impl CurrentLanes {
+    /// A vehicle entered a lane. If it was somehow still on another lane, that one counts as
+    /// finished.
+    fn enter_lane(&mut self, car: CarID, lane: LaneID, time: Time) -> Option<FinishedLane> {
+        let finished = self.finish_lane(car, time);
+        self.lanes.insert(car, (lane, time));
+        finished
+    }
+
+    /// A vehicle reached the end of its current lane, by starting a turn or leaving the map.
+    fn finish_lane(&mut self, car: CarID, time: Time) -> Option<FinishedLane> {
+        let (lane, entered) = self.lanes.remove(&car)?;
+        Some(FinishedLane {
+            car,
+            lane,
+            entered,
+            exited: time,
+        })
+    }
+
+    fn abandon_lane(&mut self, car: CarID) {
+        self.lanes.remove(&car);
+    }
+}
+
 /// A sliding window, used to count something over time
 pub struct SlidingWindow {
     times: VecDeque<Time>,
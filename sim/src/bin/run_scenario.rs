@@ -1,6 +1,8 @@
 //! A simple tool that just runs a simulation for the specified number of hours. Use for profiling
 //! and benchmarking.
 
+use std::collections::BTreeSet;
+
 use structopt::StructOpt;
 
 #[derive(StructOpt)]
@@ -12,6 +14,20 @@ struct Args {
     /// How many hours to simulate.
     #[structopt(long)]
     hours: usize,
+    /// Split the map into regions separated by bridges and simulate each on its own thread. Agents
+    /// crossing a bridge are handed to the next region every simulated minute, so results differ
+    /// from the normal simulation.
+    #[structopt(long)]
+    parallel_regions: bool,
+    /// Re-simulate only the trips that map edits could affect. If this file doesn't exist yet, the
+    /// scenario is simulated without edits and every trip is recorded here. After that, only people
+    /// whose trips touch the `--edits` are simulated again, along with people sharing their roads
+    /// as background traffic; everybody else keeps their recorded outcome.
+    #[structopt(long)]
+    recorded_trips: Option<String>,
+    /// A path to map edits to evaluate with `--recorded-trips`.
+    #[structopt(long)]
+    edits: Option<String>,
     #[structopt(flatten)]
     flags: sim::SimFlags,
 }
@@ -21,6 +37,21 @@ fn main() {
     let mut args = Args::from_args();
     args.flags.initialize();
     let hours = geom::Duration::hours(args.hours);
+
+    if args.parallel_regions {
+        let (map, mut sim) = args.flags.load_parallel(&mut abstutil::Timer::new("setup"));
+        sim.timed_step(&map, hours, &mut abstutil::Timer::new("run simulations"));
+        for line in sim.describe() {
+            println!("{}", line);
+        }
+        return;
+    }
+
+    if let Some(ref path) = args.recorded_trips {
+        resimulate_edits(&args, path.clone(), hours);
+        return;
+    }
+
     let (mut map, mut sim, _) = args
         .flags
         .load_synchronously(&mut abstutil::Timer::new("setup"));
@@ -71,3 +102,80 @@ fn main() {
         );
     }
 }
+
+fn resimulate_edits(args: &Args, path: String, hours: geom::Duration) {
+    let mut timer = abstutil::Timer::new("setup");
+    if !abstio::file_exists(&path) {
+        let (map, mut sim, _) = args.flags.load_synchronously(&mut timer);
+        sim.keep_all_trip_traversals();
+        sim.timed_step(&map, hours, &mut None, &mut timer);
+        let recorded = sim::RecordedTrips::new(&sim, &map);
+        abstio::write_binary(path.clone(), &recorded);
+        println!("Recorded {} trips to {}", recorded.num_trips(), path);
+        return;
+    }
+
+    let recorded: sim::RecordedTrips = abstio::must_read_object(path.clone(), &mut timer);
+    let mut scenario: sim::Scenario = abstio::must_read_object(args.flags.load.clone(), &mut timer);
+    let mut map = map_model::Map::load_synchronously(scenario.map_name.path(), &mut timer);
+    for m in &args.flags.scenario_modifiers {
+        scenario = m.apply(&map, scenario);
+    }
+    if let Err(err) = recorded.check_map(&map) {
+        panic!("Can't use {}: {}", path, err);
+    }
+    let effects = match args.edits {
+        Some(ref edits_path) => {
+            let edits = map_model::MapEdits::load_from_file(&map, edits_path.clone(), &mut timer)
+                .unwrap_or_else(|err| panic!("Couldn't load edits {}: {}", edits_path, err));
+            let effects = map.must_apply_edits(edits, &mut timer);
+            map.recalculate_pathfinding_after_edits(&mut timer);
+            effects
+        }
+        None => map.must_apply_edits(map.new_edits(), &mut timer),
+    };
+
+    let people = recorded
+        .affected_people(&map, &effects, &mut timer)
+        .unwrap();
+    let background = recorded
+        .background_people(&map, &people, &mut timer)
+        .unwrap();
+    println!(
+        "Re-simulating {} of {} people, with {} more as background traffic",
+        abstutil::prettyprint_usize(people.len()),
+        abstutil::prettyprint_usize(scenario.people.len()),
+        abstutil::prettyprint_usize(background.len())
+    );
+    let simulate: BTreeSet<sim::PersonID> = people.union(&background).cloned().collect();
+    let mut sim = sim::Sim::new(&map, args.flags.opts.clone());
+    sim.keep_all_trip_traversals();
+    recorded.only_people(&scenario, &simulate).instantiate(
+        &mut sim,
+        &map,
+        &mut args.flags.make_rng(),
+        &mut timer,
+    );
+    sim.timed_step(&map, hours, &mut None, &mut timer);
+
+    let updated = recorded.updated(&sim, &map, &people);
+    let (mut faster, mut slower) = (0, 0);
+    let mut total = geom::Duration::ZERO;
+    for id in updated.all_trips() {
+        if let (Some(before), Some(after)) = (recorded.trip_duration(id), updated.trip_duration(id))
+        {
+            if after < before {
+                faster += 1;
+            } else if after > before {
+                slower += 1;
+            }
+            total += after - before;
+        }
+    }
+    println!(
+        "{} trips got faster, {} got slower. Net change in travel time: {}",
+        abstutil::prettyprint_usize(faster),
+        abstutil::prettyprint_usize(slower),
+        total
+    );
+}
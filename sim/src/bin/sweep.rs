@@ -0,0 +1,393 @@
+//! Runs one scenario many times, once for every combination of map edits and random seed, and
+//! writes summary metrics for each run to a results directory. Each run happens in its own process,
+//! so a crash or a slow run doesn't take down the rest of the sweep.
+//!
+//! The results directory contains `manifest.json` describing every run, `summary.csv` with one row
+//! per run, and `run_N.json` plus `run_N.log` for each run.
+
+#[macro_use]
+extern crate log;
+
+use std::collections::{BTreeMap, VecDeque};
+use std::fs::File;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+
+use rand::SeedableRng;
+use rand_xorshift::XorShiftRng;
+use serde::{Deserialize, Serialize};
+use structopt::StructOpt;
+
+use abstutil::{prettyprint_usize, Timer};
+use geom::{Duration, Histogram, Statistic};
+use map_model::{Map, MapEdits};
+use sim::{Scenario, ScenarioModifier, Sim, SimOptions, TripMode};
+
+#[derive(StructOpt)]
+#[structopt(
+    name = "sweep",
+    about = "Simulates a scenario for every combination of map edits and random seed"
+)]
+struct Args {
+    /// The path to a scenario
+    #[structopt(long)]
+    scenario: String,
+    /// The path to some map edits. Repeat to compare several proposals. The scenario is always
+    /// also simulated without any edits, as a baseline.
+    #[structopt(long)]
+    edits: Vec<String>,
+    /// How many different random seeds to simulate each set of edits with
+    #[structopt(long, default_value = "1")]
+    num_seeds: u64,
+    /// Seeds count up from this one
+    #[structopt(long, default_value = "42")]
+    first_seed: u64,
+    /// How many hours to simulate
+    #[structopt(long, default_value = "24")]
+    hours: usize,
+    /// A JSON list of modifiers to transform the scenario. These can be generated with the GUI.
+    #[structopt(long, parse(try_from_str = parse_modifiers), default_value = "[]")]
+    scenario_modifiers: ModifierList,
+    /// The directory to write results to
+    #[structopt(long)]
+    output: String,
+    /// How many simulations to run at the same time. Defaults to the number of CPUs.
+    #[structopt(long)]
+    jobs: Option<usize>,
+    /// Internal: run just this one simulation, instead of launching all of them.
+    #[structopt(long, hidden = true)]
+    one_run: Option<usize>,
+    #[structopt(flatten)]
+    opts: SimOptions,
+}
+
+// See https://github.com/TeXitoi/structopt/issues/94
+type ModifierList = Vec<ScenarioModifier>;
+
+fn parse_modifiers(x: &str) -> anyhow::Result<ModifierList> {
+    abstutil::from_json(&x.to_string().into_bytes())
+}
+
+/// One combination of edits and seed
+#[derive(Clone, Serialize)]
+struct RunSpec {
+    idx: usize,
+    /// None is the baseline
+    edits: Option<String>,
+    rng_seed: u64,
+}
+
+#[derive(Serialize)]
+struct Manifest {
+    scenario: String,
+    hours: usize,
+    scenario_modifiers: ModifierList,
+    runs: Vec<ManifestEntry>,
+}
+
+#[derive(Serialize)]
+struct ManifestEntry {
+    #[serde(flatten)]
+    spec: RunSpec,
+    succeeded: bool,
+    results: String,
+    log: String,
+}
+
+/// Metrics about one finished simulation
+#[derive(Serialize, Deserialize)]
+struct RunResults {
+    finished_trips: usize,
+    cancelled_trips: usize,
+    unfinished_trips: usize,
+    /// The sum of all finished trip durations
+    total_trip_time: Duration,
+    /// Per mode, the number of finished trips
+    trips_per_mode: BTreeMap<String, usize>,
+    /// Statistics about finished trip durations, for "all" trips and then per mode
+    trip_times: BTreeMap<String, BTreeMap<String, Duration>>,
+}
+
+fn main() {
+    abstutil::logger::setup();
+    let args = Args::from_args();
+    let runs = all_runs(&args);
+
+    if let Some(idx) = args.one_run {
+        let results = simulate(&args, &runs[idx]);
+        abstio::write_json(results_path(&args, idx), &results);
+        return;
+    }
+
+    std::fs::create_dir_all(&args.output)
+        .unwrap_or_else(|err| panic!("Can't create {}: {}", args.output, err));
+    let num_jobs = args.jobs.unwrap_or_else(|| {
+        std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1)
+    });
+    println!(
+        "Simulating {} runs, {} at a time",
+        prettyprint_usize(runs.len()),
+        num_jobs
+    );
+
+    // Each worker thread launches one process at a time
+    let queue: Mutex<VecDeque<RunSpec>> = Mutex::new(runs.iter().cloned().collect());
+    let succeeded: Mutex<BTreeMap<usize, bool>> = Mutex::new(BTreeMap::new());
+    std::thread::scope(|scope| {
+        for _ in 0..num_jobs {
+            scope.spawn(|| loop {
+                let run = match queue.lock().unwrap().pop_front() {
+                    Some(run) => run,
+                    None => {
+                        return;
+                    }
+                };
+                let ok = launch(&args, &run);
+                println!(
+                    "Run {} ({}, seed {}) {}",
+                    run.idx,
+                    run.edits.as_deref().unwrap_or("no edits"),
+                    run.rng_seed,
+                    if ok { "finished" } else { "failed" }
+                );
+                succeeded.lock().unwrap().insert(run.idx, ok);
+            });
+        }
+    });
+
+    let succeeded = succeeded.into_inner().unwrap();
+    let manifest = Manifest {
+        scenario: args.scenario.clone(),
+        hours: args.hours,
+        scenario_modifiers: args.scenario_modifiers.clone(),
+        runs: runs
+            .iter()
+            .map(|run| ManifestEntry {
+                spec: run.clone(),
+                succeeded: succeeded[&run.idx],
+                results: results_path(&args, run.idx),
+                log: log_path(&args, run.idx),
+            })
+            .collect(),
+    };
+    abstio::write_json(format!("{}/manifest.json", args.output), &manifest);
+    if let Err(err) = write_summary(&args, &manifest) {
+        panic!("Couldn't write summary.csv: {}", err);
+    }
+
+    let num_failed = succeeded.values().filter(|ok| !**ok).count();
+    if num_failed > 0 {
+        println!(
+            "{} runs failed. See the logs in {}",
+            prettyprint_usize(num_failed),
+            args.output
+        );
+        std::process::exit(1);
+    }
+}
+
+fn all_runs(args: &Args) -> Vec<RunSpec> {
+    let mut runs = Vec::new();
+    for edits in std::iter::once(None).chain(args.edits.iter().cloned().map(Some)) {
+        for rng_seed in args.first_seed..args.first_seed + args.num_seeds {
+            runs.push(RunSpec {
+                idx: runs.len(),
+                edits: edits.clone(),
+                rng_seed,
+            });
+        }
+    }
+    runs
+}
+
+fn results_path(args: &Args, idx: usize) -> String {
+    format!("{}/run_{}.json", args.output, idx)
+}
+
+fn log_path(args: &Args, idx: usize) -> String {
+    format!("{}/run_{}.log", args.output, idx)
+}
+
+/// Runs this same program as a child process, simulating just one run. Returns true if it
+/// succeeds.
+fn launch(args: &Args, run: &RunSpec) -> bool {
+    let log = match File::create(log_path(args, run.idx)) {
+        Ok(f) => f,
+        Err(err) => {
+            error!("Can't create log for run {}: {}", run.idx, err);
+            return false;
+        }
+    };
+    let status = Command::new(std::env::current_exe().unwrap())
+        .args(std::env::args().skip(1))
+        .arg("--one-run")
+        .arg(run.idx.to_string())
+        .stdout(Stdio::from(log.try_clone().unwrap()))
+        .stderr(Stdio::from(log))
+        .status();
+    match status {
+        Ok(status) => status.success(),
+        Err(err) => {
+            error!("Couldn't launch run {}: {}", run.idx, err);
+            false
+        }
+    }
+}
+
+fn simulate(args: &Args, run: &RunSpec) -> RunResults {
+    let mut timer = Timer::new(format!("run {}", run.idx));
+    let mut scenario: Scenario = abstio::must_read_object(args.scenario.clone(), &mut timer);
+    let mut map = Map::load_synchronously(scenario.map_name.path(), &mut timer);
+    if let Some(ref path) = run.edits {
+        let edits = MapEdits::load_from_file(&map, path.clone(), &mut timer)
+            .unwrap_or_else(|err| panic!("Couldn't load edits {}: {}", path, err));
+        map.must_apply_edits(edits, &mut timer);
+        map.recalculate_pathfinding_after_edits(&mut timer);
+    }
+    for m in &args.scenario_modifiers {
+        scenario = m.apply(&map, scenario);
+    }
+
+    let mut opts = args.opts.clone();
+    opts.run_name = format!("{} run {}", scenario.scenario_name, run.idx);
+    let mut sim = Sim::new(&map, opts);
+    let mut rng = XorShiftRng::seed_from_u64(run.rng_seed);
+    scenario.instantiate(&mut sim, &map, &mut rng, &mut timer);
+    sim.timed_step(&map, Duration::hours(args.hours), &mut None, &mut timer);
+
+    summarize(&sim)
+}
+
+fn summarize(sim: &Sim) -> RunResults {
+    let mut cancelled_trips = 0;
+    let mut total_trip_time = Duration::ZERO;
+    let mut trips_per_mode = BTreeMap::new();
+    let mut all = Histogram::new();
+    let mut per_mode: BTreeMap<TripMode, Histogram<Duration>> = BTreeMap::new();
+    for (_, _, mode, maybe_dt) in &sim.get_analytics().finished_trips {
+        if let Some(dt) = maybe_dt {
+            total_trip_time += *dt;
+            *trips_per_mode.entry(mode_name(*mode)).or_insert(0) += 1;
+            all.add(*dt);
+            per_mode
+                .entry(*mode)
+                .or_insert_with(Histogram::new)
+                .add(*dt);
+        } else {
+            cancelled_trips += 1;
+        }
+    }
+
+    let describe = |hgram: &Histogram<Duration>| -> BTreeMap<String, Duration> {
+        Statistic::all()
+            .into_iter()
+            .filter_map(|stat| {
+                hgram
+                    .select(stat)
+                    .map(|dt| (stat_name(stat).to_string(), dt))
+            })
+            .collect()
+    };
+    let mut trip_times = BTreeMap::new();
+    trip_times.insert("all".to_string(), describe(&all));
+    for (mode, hgram) in &per_mode {
+        trip_times.insert(mode_name(*mode), describe(hgram));
+    }
+
+    RunResults {
+        finished_trips: all.count(),
+        cancelled_trips,
+        unfinished_trips: sim.num_trips().1,
+        total_trip_time,
+        trips_per_mode,
+        trip_times,
+    }
+}
+
+/// One row per run, for quickly loading everything into a spreadsheet or dataframe
+fn write_summary(args: &Args, manifest: &Manifest) -> anyhow::Result<()> {
+    let mut f = File::create(format!("{}/summary.csv", args.output))?;
+    write!(
+        f,
+        "run,edits,rng_seed,succeeded,finished_trips,cancelled_trips,unfinished_trips"
+    )?;
+    write!(f, ",total_trip_time_seconds")?;
+    for mode in TripMode::all() {
+        write!(f, ",{}_trips", mode_name(mode))?;
+    }
+    for stat in Statistic::all() {
+        write!(f, ",{}_trip_time_seconds", stat_name(stat))?;
+    }
+    writeln!(f)?;
+
+    for entry in &manifest.runs {
+        let run = &entry.spec;
+        write!(
+            f,
+            "{},{},{},{}",
+            run.idx,
+            run.edits.as_deref().unwrap_or(""),
+            run.rng_seed,
+            entry.succeeded
+        )?;
+        let results = if entry.succeeded {
+            abstio::maybe_read_json::<RunResults>(entry.results.clone(), &mut Timer::throwaway())
+                .ok()
+        } else {
+            None
+        };
+        if let Some(results) = results {
+            write!(
+                f,
+                ",{},{},{},{}",
+                results.finished_trips,
+                results.cancelled_trips,
+                results.unfinished_trips,
+                results.total_trip_time.inner_seconds()
+            )?;
+            for mode in TripMode::all() {
+                write!(
+                    f,
+                    ",{}",
+                    results
+                        .trips_per_mode
+                        .get(&mode_name(mode))
+                        .cloned()
+                        .unwrap_or(0)
+                )?;
+            }
+            for stat in Statistic::all() {
+                match results.trip_times["all"].get(stat_name(stat)) {
+                    Some(dt) => write!(f, ",{}", dt.inner_seconds())?,
+                    None => write!(f, ",")?,
+                }
+            }
+        } else {
+            write!(
+                f,
+                ",,,,{}",
+                ",".repeat(TripMode::all().len() + Statistic::all().len())
+            )?;
+        }
+        writeln!(f)?;
+    }
+    Ok(())
+}
+
+fn mode_name(mode: TripMode) -> String {
+    format!("{:?}", mode).to_lowercase()
+}
+
+fn stat_name(stat: Statistic) -> &'static str {
+    match stat {
+        Statistic::Min => "min",
+        Statistic::Mean => "mean",
+        Statistic::P50 => "p50",
+        Statistic::P90 => "p90",
+        Statistic::P99 => "p99",
+        Statistic::Max => "max",
+    }
+}
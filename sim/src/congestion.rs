@@ -0,0 +1,63 @@
+//! Measures how much longer than free-flow it takes vehicles to cross each road at different times
+//! of day. The result can be turned into a `CongestionProfile`, so that a later simulation routes
+//! vehicles around the congestion they'll probably hit when they depart. Repeating this a few times
+//! approximates an equilibrium, where people have learned which routes are usually slow.
+//!
+//! Time spent waiting at the end of a lane for a signal or a gap counts as congestion too, since
+//! the free-flow cost used for routing doesn't include it.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use geom::Duration;
+use map_model::{CongestionProfile, Map, RoadID};
+
+use crate::analytics::FinishedLane;
+use crate::VehicleType;
+
+/// Accumulates travel times per road, bucketed by the hour the vehicle entered the lane.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct TravelTimesPerRoad {
+    /// For each hour, the total time vehicles actually took to cross the road, and the total time
+    /// they would've taken at the speed limit
+    per_road: BTreeMap<RoadID, Vec<(Duration, Duration)>>,
+}
+
+impl TravelTimesPerRoad {
+    /// A vehicle reached the end of a lane
+    pub(crate) fn record(&mut self, lane: &FinishedLane, map: &Map) {
+        // Bikes aren't routed with the profile, and buses stop along the way.
+        if lane.car.vehicle_type != VehicleType::Car {
+            return;
+        }
+        let road = map.get_r(lane.lane.road);
+        let free_flow = map.get_l(lane.lane).length() / road.speed_limit;
+        if free_flow == Duration::ZERO {
+            return;
+        }
+        let hour = lane.entered.get_hours();
+        let per_hour = self.per_road.entry(road.id).or_insert_with(Vec::new);
+        if per_hour.len() <= hour {
+            per_hour.resize(hour + 1, (Duration::ZERO, Duration::ZERO));
+        }
+        per_hour[hour].0 += lane.exited - lane.entered;
+        per_hour[hour].1 += free_flow;
+    }
+
+    /// Summarize everything measured so far, to route a future simulation with.
+    pub fn congestion_profile(&self) -> CongestionProfile {
+        let mut per_hour: Vec<BTreeMap<RoadID, f64>> = Vec::new();
+        for (r, times) in &self.per_road {
+            if per_hour.len() < times.len() {
+                per_hour.resize(times.len(), BTreeMap::new());
+            }
+            for (hour, (actual, free_flow)) in times.iter().enumerate() {
+                if *free_flow > Duration::ZERO {
+                    per_hour[hour].insert(*r, *actual / *free_flow);
+                }
+            }
+        }
+        CongestionProfile::new(per_hour)
+    }
+}
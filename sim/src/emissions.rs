@@ -0,0 +1,146 @@
+//! Rough estimates of tailpipe emissions, so map edits can be judged by their environmental impact
+//! and not just travel time. Every time a vehicle finishes a lane, its average speed over the lane
+//! is plugged into a speed-dependent emission curve. Slow, congested traffic emits more per
+//! kilometer, and a vehicle stuck in a queue still emits while idling.
+//!
+//! The curves are loosely fit to published average-speed emission factors for a typical petrol
+//! passenger car, then scaled for heavier vehicles. They're meant for comparing scenarios against
+//! each other, not as absolute measurements.
+
+use std::collections::BTreeMap;
+use std::ops;
+
+use serde::{Deserialize, Serialize};
+
+use geom::{Distance, Duration};
+use map_model::{Map, RoadID};
+
+use crate::analytics::FinishedLane;
+use crate::VehicleType;
+
+/// Grams of pollutants emitted
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Emissions {
+    pub co2: f64,
+    pub nox: f64,
+}
+
+impl Emissions {
+    pub const ZERO: Emissions = Emissions { co2: 0.0, nox: 0.0 };
+
+    /// Estimate the emissions of a vehicle covering some distance in some amount of time. Bikes
+    /// and trains don't emit anything.
+    pub fn estimate(vehicle_type: VehicleType, dist: Distance, time: Duration) -> Emissions {
+        let multiplier = match vehicle_type {
+            VehicleType::Car => 1.0,
+            VehicleType::Truck => 3.0,
+            // A mix of police cars and larger ambulances and fire engines
+            VehicleType::Emergency => 2.0,
+            VehicleType::Bus => 6.0,
+            VehicleType::Bike | VehicleType::Train => {
+                return Emissions::ZERO;
+            }
+        };
+        let km = dist.inner_meters() / 1000.0;
+        let hours = time.inner_seconds() / 3600.0;
+        if km <= 0.0 || hours <= 0.0 {
+            return Emissions::ZERO;
+        }
+        let kmph = km / hours;
+
+        // Each curve has the form idle / speed + cruising + drag * speed^2, in grams per km. The
+        // idle term multiplied by distance is just idle * hours, so a stopped vehicle still
+        // emits at a constant rate.
+        let co2 = 1500.0 * hours + (150.0 + 0.008 * kmph * kmph) * km;
+        let nox = 6.0 * hours + (0.15 + 0.00002 * kmph * kmph) * km;
+        Emissions {
+            co2: multiplier * co2,
+            nox: multiplier * nox,
+        }
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.co2 == 0.0 && self.nox == 0.0
+    }
+}
+
+impl ops::Add for Emissions {
+    type Output = Emissions;
+
+    fn add(self, other: Emissions) -> Emissions {
+        Emissions {
+            co2: self.co2 + other.co2,
+            nox: self.nox + other.nox,
+        }
+    }
+}
+
+impl ops::AddAssign for Emissions {
+    fn add_assign(&mut self, other: Emissions) {
+        *self = *self + other;
+    }
+}
+
+/// Accumulates emissions per road, bucketed by the hour the vehicle finished the lane.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct EmissionsPerRoad {
+    per_road: BTreeMap<RoadID, Vec<Emissions>>,
+}
+
+impl EmissionsPerRoad {
+    /// A vehicle reached the end of a lane
+    pub(crate) fn record(&mut self, lane: &FinishedLane, map: &Map) {
+        let emissions = Emissions::estimate(
+            lane.car.vehicle_type,
+            map.get_l(lane.lane).length(),
+            lane.exited - lane.entered,
+        );
+        if emissions.is_zero() {
+            return;
+        }
+        let hour = lane.exited.get_hours();
+        let per_hour = self.per_road.entry(lane.lane.road).or_insert_with(Vec::new);
+        if per_hour.len() <= hour {
+            per_hour.resize(hour + 1, Emissions::ZERO);
+        }
+        per_hour[hour] += emissions;
+    }
+
+    /// The total emitted on every road so far. If `hour` is specified, only count that hour.
+    pub fn per_road(&self, hour: Option<usize>) -> BTreeMap<RoadID, Emissions> {
+        let mut results = BTreeMap::new();
+        for (r, per_hour) in &self.per_road {
+            let total = match hour {
+                Some(hour) => per_hour.get(hour).cloned().unwrap_or(Emissions::ZERO),
+                None => per_hour
+                    .iter()
+                    .fold(Emissions::ZERO, |sum, emissions| sum + *emissions),
+            };
+            if !total.is_zero() {
+                results.insert(*r, total);
+            }
+        }
+        results
+    }
+
+    /// The total emitted on all roads in each hour so far
+    pub fn total_per_hour(&self) -> Vec<Emissions> {
+        let mut results = Vec::new();
+        for per_hour in self.per_road.values() {
+            if results.len() < per_hour.len() {
+                results.resize(per_hour.len(), Emissions::ZERO);
+            }
+            for (hour, emissions) in per_hour.iter().enumerate() {
+                results[hour] += *emissions;
+            }
+        }
+        results
+    }
+
+    /// The total emitted everywhere so far
+    pub fn total(&self) -> Emissions {
+        self.total_per_hour()
+            .into_iter()
+            .fold(Emissions::ZERO, |sum, emissions| sum + emissions)
+    }
+}
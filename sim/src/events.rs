@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-use geom::Duration;
+use geom::{Duration, Time};
 use map_model::{
     BuildingID, BusRouteID, BusStopID, IntersectionID, LaneID, Map, Path, PathRequest, Traversable,
     TurnID,
@@ -23,6 +23,9 @@ pub enum Event {
 
     BusArrivedAtStop(CarID, BusRouteID, BusStopID),
     BusDepartedFromStop(CarID, BusRouteID, BusStopID),
+    /// Emitted alongside BusArrivedAtStop when the schedule says when the vehicle should've
+    /// arrived there.
+    BusArrivalVsSchedule(CarID, BusRouteID, BusStopID, Time),
     /// How long waiting at the stop?
     PassengerBoardsTransit(PersonID, CarID, BusRouteID, BusStopID, Duration),
     PassengerAlightsTransit(PersonID, CarID, BusRouteID, BusStopID),
@@ -54,11 +57,29 @@ pub enum Event {
     TripCancelled(TripID, TripMode),
     TripPhaseStarting(TripID, PersonID, Option<PathRequest>, TripPhaseType),
 
+    /// A vehicle started a turn into a lane without enough room for it, so the queue on that lane
+    /// spills back and blocks the box at the intersection.
+    QueueSpillback(IntersectionID, LaneID, CarID),
+
+    /// An emergency vehicle on this trip made a traffic signal cut its current stage short.
+    SignalPreempted(TripID, IntersectionID),
+    /// An emergency vehicle reached its destination, this long after it was dispatched.
+    EmergencyVehicleArrived(TripID, Duration),
+
+    /// A driver didn't find parking on their current lane, and is driving to the next one to look.
+    CruisingForParking(TripID),
+    /// A delivery or ride-hail vehicle had nowhere to pull over, so it stopped in this lane for
+    /// this long, blocking it.
+    DoubleParked(CarID, LaneID, Duration),
+
     /// Just use for parking replanning. Not happy about copying the full path in here, but the way
     /// to plumb info into Analytics is Event.
     PathAmended(Path),
 
     Alert(AlertLocation, String),
+
+    /// The pandemic model exposed the first person to the second, infectious person.
+    PersonExposed(PersonID, PersonID, ExposureLocation),
 }
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
@@ -69,6 +90,14 @@ pub enum AlertLocation {
     Building(BuildingID),
 }
 
+/// The shared space where the pandemic model exposed somebody
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum ExposureLocation {
+    Building(BuildingID),
+    BusStop(BusStopID),
+    Bus(CarID),
+}
+
 #[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
 pub enum TripPhaseType {
     Driving,
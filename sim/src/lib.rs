@@ -22,7 +22,7 @@ use std::fmt;
 use serde::{Deserialize, Serialize};
 
 use abstutil::{deserialize_usize, serialize_usize};
-use geom::{Distance, Speed, Time};
+use geom::{Distance, Duration, Speed, Time};
 use map_model::{
     BuildingID, BusRouteID, BusStopID, IntersectionID, LaneID, Map, ParkingLotID, Path,
     PathConstraints, Position,
@@ -33,13 +33,18 @@ pub use crate::render::{
     UnzoomedAgent,
 };
 
-pub use self::analytics::{Analytics, Problem, SlidingWindow, TripPhase};
+pub use self::analytics::{Analytics, DetailedAnalytics, Problem, SlidingWindow, TripPhase};
+pub use self::congestion::TravelTimesPerRoad;
+pub use self::emissions::{Emissions, EmissionsPerRoad};
 pub(crate) use self::events::Event;
-pub use self::events::{AlertLocation, TripPhaseType};
+pub use self::events::{AlertLocation, ExposureLocation, TripPhaseType};
+pub use self::live_metrics::LiveMetrics;
 pub use self::make::{
-    fork_rng, BorderSpawnOverTime, ExternalPerson, ExternalTrip, ExternalTripEndpoint, IndividTrip,
-    MapBorders, PersonSpec, Scenario, ScenarioGenerator, ScenarioModifier, SimFlags, SpawnOverTime,
-    TripEndpoint, TripPurpose,
+    fork_rng, ArrivalSurge, BorderSpawnOverTime, DayResults, DayToDayLearning, DemandProfile,
+    DriverBehaviorRange, ExternalPerson, ExternalTrip, ExternalTripEndpoint, IndividTrip,
+    LearnedDays, MapBorders, PersonSpec, RecordedTrips, Scenario, ScenarioGenerator,
+    ScenarioModifier, SimFlags, SpawnOverTime, TripEndpoint, TripPurpose, Weather,
+    DEFAULT_CURBSIDE_DWELL,
 };
 pub(crate) use self::make::{StartTripArgs, TripSpec};
 pub(crate) use self::mechanics::{
@@ -48,23 +53,32 @@ pub(crate) use self::mechanics::{
 pub(crate) use self::pandemic::PandemicModel;
 pub(crate) use self::recorder::TrafficRecorder;
 pub(crate) use self::router::{ActionAtEnd, Router};
+pub use self::routing_profile::RoutingProfile;
 pub(crate) use self::scheduler::{Command, Scheduler};
-pub use self::sim::{AgentProperties, AlertHandler, DelayCause, Sim, SimCallback, SimOptions};
+pub use self::sim::{
+    AgentProperties, AlertHandler, DelayCause, ParallelSim, Sim, SimCallback, SimOptions,
+};
+pub(crate) use self::trace::AgentTraces;
 pub(crate) use self::transit::TransitSimState;
 pub use self::trips::TripMode;
 pub use self::trips::{CommutersVehiclesCounts, Person, PersonState, TripInfo, TripResult};
 pub(crate) use self::trips::{TripLeg, TripManager};
 
 mod analytics;
+mod congestion;
+mod emissions;
 mod events;
+mod live_metrics;
 mod make;
 mod mechanics;
 mod pandemic;
 mod recorder;
 mod render;
 mod router;
+mod routing_profile;
 mod scheduler;
 mod sim;
+mod trace;
 mod transit;
 mod trips;
 
@@ -75,6 +89,9 @@ pub(crate) const MAX_CAR_LENGTH: Distance = Distance::const_meters(6.5);
 // Note this is more than MAX_CAR_LENGTH
 pub(crate) const BUS_LENGTH: Distance = Distance::const_meters(12.5);
 pub(crate) const LIGHT_RAIL_LENGTH: Distance = Distance::const_meters(60.0);
+// Box trucks for deliveries. Anything longer wouldn't fit in a street parking spot.
+pub(crate) const MIN_TRUCK_LENGTH: Distance = Distance::const_meters(7.0);
+pub(crate) const MAX_TRUCK_LENGTH: Distance = Distance::const_meters(8.0);
 
 /// At all speeds (including at rest), cars must be at least this far apart, measured from front of
 /// one car to the back of the other.
@@ -104,6 +121,8 @@ impl fmt::Display for CarID {
             VehicleType::Bus => write!(f, "Bus #{}", self.id),
             VehicleType::Train => write!(f, "Train #{}", self.id),
             VehicleType::Bike => write!(f, "Bike #{}", self.id),
+            VehicleType::Truck => write!(f, "Truck #{}", self.id),
+            VehicleType::Emergency => write!(f, "Emergency vehicle #{}", self.id),
         }
     }
 }
@@ -142,7 +161,8 @@ impl AgentID {
     pub fn to_type(self) -> AgentType {
         match self {
             AgentID::Car(c) => match c.vehicle_type {
-                VehicleType::Car => AgentType::Car,
+                // Trucks and emergency vehicles are counted with cars
+                VehicleType::Car | VehicleType::Truck | VehicleType::Emergency => AgentType::Car,
                 VehicleType::Bike => AgentType::Bike,
                 VehicleType::Bus => AgentType::Bus,
                 VehicleType::Train => AgentType::Train,
@@ -287,6 +307,11 @@ pub enum VehicleType {
     Bus,
     Train,
     Bike,
+    /// A freight vehicle. It routes and parks like a car, but is longer and slower.
+    Truck,
+    /// An ambulance, fire engine, or police car responding to a call. It routes and parks like a
+    /// car, but other drivers let it by, and traffic signals change to let it through.
+    Emergency,
 }
 
 impl fmt::Display for VehicleType {
@@ -296,6 +321,8 @@ impl fmt::Display for VehicleType {
             VehicleType::Bus => write!(f, "bus"),
             VehicleType::Train => write!(f, "train"),
             VehicleType::Bike => write!(f, "bike"),
+            VehicleType::Truck => write!(f, "truck"),
+            VehicleType::Emergency => write!(f, "emergency vehicle"),
         }
     }
 }
@@ -307,6 +334,8 @@ impl VehicleType {
             VehicleType::Bus => PathConstraints::Bus,
             VehicleType::Train => PathConstraints::Train,
             VehicleType::Bike => PathConstraints::Bike,
+            VehicleType::Truck => PathConstraints::Car,
+            VehicleType::Emergency => PathConstraints::Car,
         }
     }
 
@@ -316,6 +345,8 @@ impl VehicleType {
             VehicleType::Bus => true,
             VehicleType::Train => true,
             VehicleType::Bike => false,
+            VehicleType::Truck => false,
+            VehicleType::Emergency => false,
         }
     }
 }
@@ -327,6 +358,7 @@ pub struct Vehicle {
     pub vehicle_type: VehicleType,
     pub length: Distance,
     pub max_speed: Option<Speed>,
+    pub behavior: DriverBehavior,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
@@ -334,6 +366,26 @@ pub struct VehicleSpec {
     pub vehicle_type: VehicleType,
     pub length: Distance,
     pub max_speed: Option<Speed>,
+    pub behavior: DriverBehavior,
+}
+
+/// How one driver differs from the others. By default, everybody drives identically.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct DriverBehavior {
+    /// Multiplies the speed the driver would otherwise travel at. Still capped by the vehicle's
+    /// own max speed.
+    pub speed_factor: f64,
+    /// After the vehicle ahead starts moving, how long it takes this driver to follow
+    pub reaction_time: Duration,
+}
+
+impl Default for DriverBehavior {
+    fn default() -> DriverBehavior {
+        DriverBehavior {
+            speed_factor: 1.0,
+            reaction_time: Duration::ZERO,
+        }
+    }
 }
 
 impl VehicleSpec {
@@ -345,6 +397,7 @@ impl VehicleSpec {
             vehicle_type: self.vehicle_type,
             length: self.length,
             max_speed: self.max_speed,
+            behavior: self.behavior,
         }
     }
 }
@@ -369,12 +422,14 @@ pub struct ParkedCar {
 pub(crate) enum DrivingGoal {
     ParkNear(BuildingID),
     Border(IntersectionID, LaneID),
+    /// Stop at the curb in front of the building for some time, then leave the map
+    StopCurbside(BuildingID, Duration),
 }
 
 impl DrivingGoal {
     pub fn goal_pos(&self, constraints: PathConstraints, map: &Map) -> Option<Position> {
         match self {
-            DrivingGoal::ParkNear(b) => match constraints {
+            DrivingGoal::ParkNear(b) | DrivingGoal::StopCurbside(b, _) => match constraints {
                 PathConstraints::Car => {
                     let driving_lane = map.find_driving_lane_near_building(*b);
                     let sidewalk_pos = map.get_b(*b).sidewalk_pos;
@@ -405,6 +460,7 @@ impl DrivingGoal {
             DrivingGoal::Border(i, last_lane) => {
                 Router::end_at_border(owner, path, map.get_l(*last_lane).length(), *i)
             }
+            DrivingGoal::StopCurbside(b, dwell) => Router::stop_curbside(owner, path, *b, *dwell),
         }
     }
 }
@@ -611,7 +667,8 @@ pub(crate) struct CreateCar {
     pub maybe_parked_car: Option<ParkedCar>,
     /// None for buses
     pub trip_and_person: Option<(TripID, PersonID)>,
-    pub maybe_route: Option<BusRouteID>,
+    /// For buses, the route and when it's scheduled to depart from the first stop
+    pub maybe_route: Option<(BusRouteID, Time)>,
 }
 
 impl CreateCar {
@@ -0,0 +1,67 @@
+//! A small summary of what a simulation is doing right now, meant to be published periodically so
+//! external dashboards can monitor a long-running headless simulation.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use geom::{Duration, Time};
+use map_model::IntersectionID;
+
+use crate::{AgentType, Sim};
+
+#[derive(Clone, Debug, Serialize)]
+pub struct LiveMetrics {
+    pub time: Time,
+    /// How many agents of each type are currently on the map
+    pub active_agents: BTreeMap<AgentType, usize>,
+    /// How many trips finished since the previous report
+    pub trips_finished: usize,
+    /// For agents who finished waiting at an intersection since the previous report, their average
+    /// delay. Empty if the simulation isn't recording analytics.
+    pub avg_intersection_delay: BTreeMap<AgentType, Duration>,
+    /// How many agents are currently waiting at each requested intersection
+    pub queue_lengths: Vec<(IntersectionID, usize)>,
+}
+
+impl LiveMetrics {
+    /// Summarize the simulation now. Delays and finished trips are counted since the `since`
+    /// time, which is usually when the previous report was made.
+    pub fn new(sim: &Sim, since: Time, intersections: &[IntersectionID]) -> LiveMetrics {
+        let analytics = sim.get_analytics();
+
+        // Analytics are recorded in order, so only look at the most recent ones.
+        let trips_finished = analytics
+            .finished_trips
+            .iter()
+            .rev()
+            .take_while(|(t, _, _, _)| *t >= since)
+            .count();
+
+        let mut delays: BTreeMap<AgentType, (Duration, usize)> = BTreeMap::new();
+        for per_intersection in analytics.intersection_delays.values() {
+            for (_, t, delay, agent_type) in per_intersection.iter().rev() {
+                if *t < since {
+                    break;
+                }
+                let entry = delays.entry(*agent_type).or_insert((Duration::ZERO, 0));
+                entry.0 += *delay;
+                entry.1 += 1;
+            }
+        }
+
+        LiveMetrics {
+            time: sim.time(),
+            active_agents: sim.num_agents().consume(),
+            trips_finished,
+            avg_intersection_delay: delays
+                .into_iter()
+                .map(|(agent_type, (total, count))| (agent_type, total / (count as f64)))
+                .collect(),
+            queue_lengths: intersections
+                .iter()
+                .map(|i| (*i, sim.get_waiting_agents(*i).len()))
+                .collect(),
+        }
+    }
+}
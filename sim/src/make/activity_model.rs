@@ -14,12 +14,19 @@ use map_model::{BuildingID, BuildingType, Map, PathConstraints, PathRequest};
 
 use crate::make::fork_rng;
 use crate::{
-    IndividTrip, PersonSpec, Scenario, ScenarioGenerator, TripEndpoint, TripMode, TripPurpose,
+    DemandProfile, IndividTrip, PersonSpec, Scenario, ScenarioGenerator, TripEndpoint, TripMode,
+    TripPurpose,
 };
 
 impl ScenarioGenerator {
-    /// Designed in https://github.com/a-b-street/abstreet/issues/154
-    pub fn proletariat_robot(map: &Map, rng: &mut XorShiftRng, timer: &mut Timer) -> Scenario {
+    /// Designed in https://github.com/a-b-street/abstreet/issues/154. Besides commuting, people
+    /// visit amenities during the surges described by `demand`.
+    pub fn proletariat_robot(
+        map: &Map,
+        demand: &DemandProfile,
+        rng: &mut XorShiftRng,
+        timer: &mut Timer,
+    ) -> Scenario {
         let mut residents: Vec<BuildingID> = Vec::new();
         let mut workers: Vec<BuildingID> = Vec::new();
 
@@ -185,6 +192,11 @@ impl ScenarioGenerator {
 
         timer.stop("create people");
 
+        let num_commuters = s.people.len();
+        timer.start("create visitors");
+        s.people.extend(demand.make_visitors(map, rng, timer));
+        timer.stop("create visitors");
+
         info!(
             "TRIPS - total: {}, local: {}, commuting_in: {}, commuting_out: {}, passthru: {}, \
              errored: {}, leftover_resident_capacity: {}, leftover_worker_capacity: {}",
@@ -193,7 +205,7 @@ impl ScenarioGenerator {
             prettyprint_usize(num_trips_commuting_in),
             prettyprint_usize(num_trips_commuting_out),
             prettyprint_usize(num_trips_passthru),
-            prettyprint_usize(num_trips - s.people.len()),
+            prettyprint_usize(num_trips - num_commuters),
             prettyprint_usize(residents.len()),
             prettyprint_usize(workers.len()),
         );
@@ -277,7 +289,7 @@ fn create_prole(
     })
 }
 
-fn select_trip_mode(distance: Distance, rng: &mut XorShiftRng) -> TripMode {
+pub(crate) fn select_trip_mode(distance: Distance, rng: &mut XorShiftRng) -> TripMode {
     // TODO Make this probabilistic
     // for example probability of walking currently has massive differences
     // at thresholds, it would be nicer to change this gradually
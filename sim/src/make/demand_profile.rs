@@ -0,0 +1,224 @@
+//! Some amenities draw lots of people at nearly the same time, like parents dropping off kids at
+//! school or a restaurant's dinner rush. A `DemandProfile` describes these surges and creates the
+//! extra people visiting, so generated scenarios don't spread all trips evenly through the day.
+
+use anyhow::Result;
+use rand::seq::SliceRandom;
+use rand_distr::{Distribution, Normal};
+use rand_xorshift::XorShiftRng;
+use serde::{Deserialize, Serialize};
+
+use abstutil::{prettyprint_usize, Timer};
+use geom::{Duration, Time};
+use map_model::{
+    BuildingID, BuildingType, Map, PathConstraints, PathRequest, MAX_BIKE_SPEED, MAX_WALKING_SPEED,
+};
+
+use crate::make::activity_model::select_trip_mode;
+use crate::make::fork_rng;
+use crate::{IndividTrip, PersonSpec, TripEndpoint, TripMode, TripPurpose};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DemandProfile {
+    pub surges: Vec<ArrivalSurge>,
+}
+
+/// People visiting some kind of amenity, all trying to arrive around the same time
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ArrivalSurge {
+    /// Just for logging, like "school drop-off"
+    pub name: String,
+    /// The OSM amenity or shop types that draw visitors, like "school" or "restaurant"
+    pub amenity_types: Vec<String>,
+    /// Visitors try to arrive around this time
+    pub arrival: Time,
+    /// The standard deviation of arrival times. Smaller values make a sharper peak.
+    pub spread: Duration,
+    /// How many people visit each matching amenity
+    pub visitors_per_amenity: usize,
+    /// How long visitors stay before heading home. Dropping somebody off just takes a few
+    /// minutes.
+    pub stay: Duration,
+    pub purpose: TripPurpose,
+}
+
+impl DemandProfile {
+    /// Reads a demand profile from a JSON file.
+    pub fn load(path: String) -> Result<DemandProfile> {
+        abstio::maybe_read_json(path, &mut Timer::throwaway())
+    }
+
+    /// Creates the people visiting amenities during every surge. Visitors start from homes in the
+    /// map, or from borders if nobody lives here.
+    pub fn make_visitors(
+        &self,
+        map: &Map,
+        rng: &mut XorShiftRng,
+        timer: &mut Timer,
+    ) -> Vec<PersonSpec> {
+        let mut homes: Vec<TripEndpoint> = Vec::new();
+        for b in map.all_buildings() {
+            let num_residents = match b.bldg_type {
+                BuildingType::Residential { num_residents, .. } => num_residents,
+                BuildingType::ResidentialCommercial(num_residents, _) => num_residents,
+                BuildingType::Commercial(_) | BuildingType::Empty => 0,
+            };
+            for _ in 0..num_residents {
+                homes.push(TripEndpoint::Bldg(b.id));
+            }
+        }
+        if homes.is_empty() {
+            homes = map
+                .all_incoming_borders()
+                .into_iter()
+                .filter(|i| i.is_outgoing_border())
+                .map(|i| TripEndpoint::Border(i.id))
+                .collect();
+        }
+
+        let mut people = Vec::new();
+        for surge in &self.surges {
+            let destinations: Vec<BuildingID> = map
+                .all_buildings()
+                .iter()
+                .filter(|b| {
+                    b.amenities
+                        .iter()
+                        .any(|a| surge.amenity_types.contains(&a.amenity_type))
+                })
+                .map(|b| b.id)
+                .collect();
+            let mut params = Vec::new();
+            for b in &destinations {
+                for _ in 0..surge.visitors_per_amenity {
+                    if let Some(home) = homes.choose(rng) {
+                        params.push((*home, *b, fork_rng(rng)));
+                    }
+                }
+            }
+
+            let visitors: Vec<PersonSpec> = timer
+                .parallelize(
+                    &format!("create visitors for {}", surge.name),
+                    params,
+                    |(home, b, mut rng)| surge.make_visitor(home, b, map, &mut rng),
+                )
+                .into_iter()
+                .flatten()
+                .collect();
+            info!(
+                "{}: {} visitors to {} amenities",
+                surge.name,
+                prettyprint_usize(visitors.len()),
+                prettyprint_usize(destinations.len())
+            );
+            people.extend(visitors);
+        }
+        people
+    }
+}
+
+impl ArrivalSurge {
+    fn make_visitor(
+        &self,
+        home: TripEndpoint,
+        b: BuildingID,
+        map: &Map,
+        rng: &mut XorShiftRng,
+    ) -> Option<PersonSpec> {
+        let destination = TripEndpoint::Bldg(b);
+        if home == destination {
+            return None;
+        }
+        let mode = match home {
+            TripEndpoint::Bldg(home_bldg) => {
+                let path =
+                    PathRequest::between_buildings(map, home_bldg, b, PathConstraints::Pedestrian)
+                        .and_then(|req| map.pathfind(req).ok())?;
+                select_trip_mode(path.total_length(), rng)
+            }
+            // If you enter the map, we assume driving
+            _ => TripMode::Drive,
+        };
+
+        // Leave early enough to arrive on time in free-flowing traffic
+        let path = TripEndpoint::path_req(home, destination, mode, map)
+            .and_then(|req| map.pathfind(req).ok())?;
+        let speed = match mode {
+            TripMode::Walk => Some(MAX_WALKING_SPEED),
+            TripMode::Bike => Some(MAX_BIKE_SPEED),
+            TripMode::Drive | TripMode::Transit => None,
+        };
+        let offset = Normal::new(0.0, self.spread.inner_seconds())
+            .ok()?
+            .sample(rng);
+        let arrival = Time::START_OF_DAY
+            + Duration::seconds((self.arrival.inner_seconds() + offset).max(0.0));
+        let depart = arrival.clamped_sub(path.estimate_duration(map, speed));
+
+        Some(PersonSpec {
+            orig_id: None,
+            trips: vec![
+                IndividTrip::new(depart, self.purpose, home, destination, mode),
+                IndividTrip::new(
+                    arrival + self.stay,
+                    TripPurpose::Home,
+                    destination,
+                    home,
+                    mode,
+                ),
+            ],
+        })
+    }
+}
+
+impl Default for DemandProfile {
+    /// School drop-off and pick-up, plus lunch and dinner rushes
+    fn default() -> DemandProfile {
+        let school = vec!["school".to_string(), "kindergarten".to_string()];
+        DemandProfile {
+            surges: vec![
+                ArrivalSurge {
+                    name: "school drop-off".to_string(),
+                    amenity_types: school.clone(),
+                    arrival: Time::START_OF_DAY + Duration::hours(8),
+                    spread: Duration::minutes(10),
+                    visitors_per_amenity: 60,
+                    stay: Duration::minutes(5),
+                    purpose: TripPurpose::Escort,
+                },
+                ArrivalSurge {
+                    name: "school pick-up".to_string(),
+                    amenity_types: school,
+                    arrival: Time::START_OF_DAY + Duration::hours(15),
+                    spread: Duration::minutes(10),
+                    visitors_per_amenity: 60,
+                    stay: Duration::minutes(5),
+                    purpose: TripPurpose::Escort,
+                },
+                ArrivalSurge {
+                    name: "lunch rush".to_string(),
+                    amenity_types: vec![
+                        "cafe".to_string(),
+                        "fast_food".to_string(),
+                        "food_court".to_string(),
+                    ],
+                    arrival: Time::START_OF_DAY + Duration::hours(12) + Duration::minutes(15),
+                    spread: Duration::minutes(30),
+                    visitors_per_amenity: 10,
+                    stay: Duration::minutes(30),
+                    purpose: TripPurpose::Meal,
+                },
+                ArrivalSurge {
+                    name: "dinner rush".to_string(),
+                    amenity_types: vec!["restaurant".to_string()],
+                    arrival: Time::START_OF_DAY + Duration::hours(19),
+                    spread: Duration::minutes(30),
+                    visitors_per_amenity: 20,
+                    stay: Duration::minutes(75),
+                    purpose: TripPurpose::Meal,
+                },
+            ],
+        }
+    }
+}
@@ -0,0 +1,379 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use abstio::MapName;
+use abstutil::Timer;
+use geom::Duration;
+use map_model::{EditEffects, IntersectionID, Map, PathRequest, RoadID, Traversable};
+
+use crate::{PersonID, Scenario, Sim, TripID, TripMode};
+
+/// Where every trip in a finished simulation went and how long it took. After editing the map,
+/// this finds the people whose trips could be affected, so only they need to be simulated again.
+/// Everybody else keeps the outcome recorded here.
+///
+/// This trades accuracy for speed. The people who are re-simulated share the road with everybody
+/// whose recorded trips used the same roads and intersections, replayed as background traffic from
+/// their original departure times. Everyone further away is left out, so congestion that backs up
+/// onto other roads is underestimated. And only trips that passed through an edited road or
+/// intersection, or would now route through one, count as affected -- congestion spilling over
+/// from an edit to nearby roads isn't caught.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RecordedTrips {
+    pub map_name: MapName,
+    /// The edits in effect when the trips were recorded
+    pub edits_name: String,
+    num_people: usize,
+    trips: BTreeMap<TripID, RecordedTrip>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct RecordedTrip {
+    person: PersonID,
+    mode: TripMode,
+    roads: BTreeSet<RoadID>,
+    intersections: BTreeSet<IntersectionID>,
+    /// Every path the trip's agents searched for, in case a different route is now better
+    path_requests: Vec<PathRequest>,
+    /// None if the trip was cancelled or never finished
+    duration: Option<Duration>,
+}
+
+impl RecordedTrips {
+    /// Record the trips from a simulation that's finished running. Analytics must have been
+    /// collected while it ran, keeping all trip traversals.
+    pub fn new(sim: &Sim, map: &Map) -> RecordedTrips {
+        let analytics = sim.get_analytics();
+        let mut trips = BTreeMap::new();
+        for (id, info) in sim.all_trip_info() {
+            trips.insert(
+                id,
+                RecordedTrip {
+                    person: sim.trip_to_person(id).unwrap(),
+                    mode: info.mode,
+                    roads: BTreeSet::new(),
+                    intersections: BTreeSet::new(),
+                    path_requests: Vec::new(),
+                    duration: None,
+                },
+            );
+        }
+        for (id, traversals) in &analytics.detailed.trip_traversals {
+            if let Some(trip) = trips.get_mut(id) {
+                for (_, on) in traversals {
+                    match on {
+                        Traversable::Lane(l) => {
+                            trip.roads.insert(l.road);
+                        }
+                        Traversable::Turn(t) => {
+                            trip.intersections.insert(t.parent);
+                        }
+                    }
+                }
+            }
+        }
+        for (_, id, maybe_req, _) in &analytics.trip_log {
+            if let (Some(trip), Some(req)) = (trips.get_mut(id), maybe_req) {
+                trip.path_requests.push(req.clone());
+            }
+        }
+        for (_, id, _, duration) in &analytics.finished_trips {
+            if let Some(trip) = trips.get_mut(id) {
+                trip.duration = *duration;
+            }
+        }
+
+        RecordedTrips {
+            map_name: map.get_name().clone(),
+            edits_name: map.get_edits().edits_name.clone(),
+            num_people: sim.get_all_people().len(),
+            trips,
+        }
+    }
+
+    /// Fails unless the trips were recorded on this map, with the same edits in effect. Check
+    /// this before applying any new edits.
+    pub fn check_map(&self, map: &Map) -> Result<()> {
+        self.check_map_name(map)?;
+        if map.get_edits().edits_name != self.edits_name {
+            bail!(
+                "Trips were recorded with edits {}, but the map has {}",
+                self.edits_name,
+                map.get_edits().edits_name
+            );
+        }
+        Ok(())
+    }
+
+    fn check_map_name(&self, map: &Map) -> Result<()> {
+        if map.get_name() != &self.map_name {
+            bail!(
+                "Trips were recorded on {}, not {}",
+                self.map_name.describe(),
+                map.get_name().describe()
+            );
+        }
+        Ok(())
+    }
+
+    /// Find the people with at least one trip that the edits could affect. The map must already
+    /// have the edits applied, with pathfinding recalculated.
+    pub fn affected_people(
+        &self,
+        map: &Map,
+        effects: &EditEffects,
+        timer: &mut Timer,
+    ) -> Result<BTreeSet<PersonID>> {
+        self.check_map_name(map)?;
+        let changed_roads = &effects.changed_roads;
+        let changed_intersections = &effects.changed_intersections;
+        let nothing_changed = changed_roads.is_empty() && changed_intersections.is_empty();
+
+        let mut affected = BTreeSet::new();
+        let mut check_paths = Vec::new();
+        for trip in self.trips.values() {
+            if affected.contains(&trip.person) || nothing_changed {
+                continue;
+            }
+            // Riders aren't tracked while they're on a bus or train, so be safe
+            if trip.mode == TripMode::Transit
+                || !trip.roads.is_disjoint(changed_roads)
+                || !trip.intersections.is_disjoint(changed_intersections)
+                || trip.path_requests.iter().any(|req| {
+                    // The lanes at the endpoints might not exist anymore
+                    changed_roads.contains(&req.start.lane().road)
+                        || changed_roads.contains(&req.end.lane().road)
+                })
+            {
+                affected.insert(trip.person);
+            } else if !trip.path_requests.is_empty() {
+                check_paths.push(trip);
+            }
+        }
+
+        // Would any of the remaining trips now take a route through the edits?
+        let reroutes = timer.parallelize("check for new routes", check_paths, |trip| {
+            let reroutes = trip
+                .path_requests
+                .iter()
+                .any(|req| match map.pathfind(req.clone()) {
+                    Ok(path) => path
+                        .get_steps()
+                        .iter()
+                        .any(|step| match step.as_traversable() {
+                            Traversable::Lane(l) => changed_roads.contains(&l.road),
+                            Traversable::Turn(t) => changed_intersections.contains(&t.parent),
+                        }),
+                    // The edits broke the old route entirely
+                    Err(_) => true,
+                });
+            (trip.person, reroutes)
+        });
+        for (person, reroutes) in reroutes {
+            if reroutes {
+                affected.insert(person);
+            }
+        }
+        Ok(affected)
+    }
+
+    /// Find the people who aren't affected, but whose recorded trips shared a road or
+    /// intersection with the affected people's trips, either as recorded or along the route
+    /// they'd take now. They should be simulated too, as background traffic. The map must already
+    /// have the edits applied, with pathfinding recalculated.
+    pub fn background_people(
+        &self,
+        map: &Map,
+        affected: &BTreeSet<PersonID>,
+        timer: &mut Timer,
+    ) -> Result<BTreeSet<PersonID>> {
+        self.check_map_name(map)?;
+        let mut roads = BTreeSet::new();
+        let mut intersections = BTreeSet::new();
+        let mut requests = Vec::new();
+        for trip in self.trips.values() {
+            if affected.contains(&trip.person) {
+                roads.extend(trip.roads.iter().cloned());
+                intersections.extend(trip.intersections.iter().cloned());
+                requests.extend(trip.path_requests.iter().cloned());
+            }
+        }
+        for path in timer.parallelize("calculate new routes", requests, |req| {
+            map.pathfind(req).ok()
+        }) {
+            for step in path.iter().flat_map(|path| path.get_steps()) {
+                match step.as_traversable() {
+                    Traversable::Lane(l) => {
+                        roads.insert(l.road);
+                    }
+                    Traversable::Turn(t) => {
+                        intersections.insert(t.parent);
+                    }
+                }
+            }
+        }
+
+        let mut background = BTreeSet::new();
+        for trip in self.trips.values() {
+            if !affected.contains(&trip.person)
+                && (!trip.roads.is_disjoint(&roads)
+                    || !trip.intersections.is_disjoint(&intersections))
+            {
+                background.insert(trip.person);
+            }
+        }
+        Ok(background)
+    }
+
+    /// Transforms the scenario that was originally simulated, cancelling the trips of everybody
+    /// not in `people`. Everyone is still created in the same order, so people and trips keep the
+    /// same IDs when the result is instantiated with the same RNG seed.
+    pub fn only_people(&self, scenario: &Scenario, people: &BTreeSet<PersonID>) -> Scenario {
+        assert_eq!(
+            scenario.people.len(),
+            self.num_people,
+            "{} doesn't match the recorded simulation",
+            scenario.scenario_name
+        );
+        let mut scenario = scenario.clone();
+        for (idx, person) in scenario.people.iter_mut().enumerate() {
+            if !people.contains(&PersonID(idx)) {
+                for trip in &mut person.trips {
+                    trip.cancelled = true;
+                }
+            }
+        }
+        scenario
+    }
+
+    /// Combines the trips of `people` from a simulation of `only_people` with everyone else's
+    /// recorded trips. The result reflects the map that `replay` ran on. Only pass in the affected
+    /// people, not the background traffic -- they had less traffic around them than originally, so
+    /// their recorded outcome is better.
+    pub fn updated(&self, replay: &Sim, map: &Map, people: &BTreeSet<PersonID>) -> RecordedTrips {
+        let mut result = RecordedTrips::new(replay, map);
+        for (id, trip) in &self.trips {
+            if !people.contains(&trip.person) {
+                result.trips.insert(*id, trip.clone());
+            }
+        }
+        result
+    }
+
+    /// How long the trip took, or None if it was cancelled or never finished.
+    pub fn trip_duration(&self, id: TripID) -> Option<Duration> {
+        self.trips.get(&id)?.duration
+    }
+
+    pub fn all_trips(&self) -> Vec<TripID> {
+        self.trips.keys().cloned().collect()
+    }
+
+    pub fn num_trips(&self) -> usize {
+        self.trips.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trip(
+        person: usize,
+        mode: TripMode,
+        roads: &[usize],
+        intersections: &[usize],
+    ) -> RecordedTrip {
+        RecordedTrip {
+            person: PersonID(person),
+            mode,
+            roads: roads.iter().map(|r| RoadID(*r)).collect(),
+            intersections: intersections.iter().map(|i| IntersectionID(*i)).collect(),
+            path_requests: Vec::new(),
+            duration: Some(Duration::minutes(10)),
+        }
+    }
+
+    // Person 1 crosses person 0's path at intersection 1. Person 2 is somewhere else entirely, and
+    // person 3 rode transit.
+    fn recorded(map: &Map) -> RecordedTrips {
+        let trips = vec![
+            trip(0, TripMode::Drive, &[1, 2], &[1]),
+            trip(1, TripMode::Walk, &[3], &[1]),
+            trip(2, TripMode::Bike, &[4], &[2]),
+            trip(3, TripMode::Transit, &[], &[]),
+        ];
+        RecordedTrips {
+            map_name: map.get_name().clone(),
+            edits_name: map.get_edits().edits_name.clone(),
+            num_people: trips.len(),
+            trips: trips
+                .into_iter()
+                .enumerate()
+                .map(|(idx, trip)| (TripID(idx), trip))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_affected_and_background_people() {
+        let mut map = Map::blank();
+        let recorded = recorded(&map);
+        let mut timer = Timer::throwaway();
+
+        // Nothing changed
+        let mut effects = map.must_apply_edits(map.get_edits().clone(), &mut timer);
+        assert!(recorded
+            .affected_people(&map, &effects, &mut timer)
+            .unwrap()
+            .is_empty());
+
+        effects.changed_roads.insert(RoadID(2));
+        let affected = recorded
+            .affected_people(&map, &effects, &mut timer)
+            .unwrap();
+        assert_eq!(
+            affected,
+            vec![PersonID(0), PersonID(3)].into_iter().collect()
+        );
+        let background = recorded
+            .background_people(&map, &affected, &mut timer)
+            .unwrap();
+        assert_eq!(background, vec![PersonID(1)].into_iter().collect());
+
+        effects.changed_roads.clear();
+        effects.changed_intersections.insert(IntersectionID(1));
+        let affected = recorded
+            .affected_people(&map, &effects, &mut timer)
+            .unwrap();
+        assert_eq!(
+            affected,
+            vec![PersonID(0), PersonID(1), PersonID(3)]
+                .into_iter()
+                .collect()
+        );
+        assert!(recorded
+            .background_people(&map, &affected, &mut timer)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_check_map() {
+        let mut map = Map::blank();
+        let mut recorded = recorded(&map);
+        assert!(recorded.check_map(&map).is_ok());
+
+        recorded.edits_name = "something else".to_string();
+        assert!(recorded.check_map(&map).is_err());
+
+        recorded.map_name = MapName::new("zz", "other city", "other");
+        assert!(recorded.check_map(&map).is_err());
+        let effects = map.must_apply_edits(map.get_edits().clone(), &mut Timer::throwaway());
+        assert!(recorded
+            .affected_people(&map, &effects, &mut Timer::throwaway())
+            .is_err());
+    }
+}
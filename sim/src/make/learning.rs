@@ -0,0 +1,221 @@
+//! Simulates the same scenario for several days in a row, letting people learn from the day
+//! before. Everybody wants to arrive when they did on the first day. Each day, some fraction of
+//! people shift their departure to arrive on time, assuming their trip takes as long as it did
+//! yesterday. Drivers also learn which roads are congested, and route around them the next day.
+//!
+//! The simulation doesn't remember routes per person, so route choice is learned network-wide:
+//! every driver routes with the same `CongestionProfile`, which moves a little closer to the
+//! congestion measured each day.
+
+use rand::Rng;
+use rand_xorshift::XorShiftRng;
+use serde::{Deserialize, Serialize};
+
+use abstutil::{prettyprint_usize, Timer};
+use geom::{Duration, Time};
+use map_model::{CongestionProfile, Map};
+
+use crate::{fork_rng, AlertHandler, Scenario, Sim, SimOptions, TripID};
+
+/// Nobody reschedules a trip by more than this from its original departure, no matter how bad the
+/// delay was.
+const MAX_SHIFT: Duration = Duration::const_seconds(3600.0);
+
+/// How people learn from previous days. Set by `ScenarioModifier::DayToDayLearning`.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct DayToDayLearning {
+    /// How many days to simulate before the one that's actually played
+    pub days: usize,
+    /// Each day, this percent of people adjust their schedule, and the congestion drivers expect
+    /// moves this percent of the way towards what they hit.
+    pub learning_rate_pct: usize,
+}
+
+/// What happened on one simulated day
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DayResults {
+    pub finished_trips: usize,
+    /// The sum of all finished trip durations
+    pub total_trip_time: Duration,
+    /// On average, how different each trip's duration was from the day before. This shrinks as
+    /// people settle into an equilibrium. None on the first day.
+    pub mean_surprise: Option<Duration>,
+    /// How many trips left at a different time than the day before
+    pub rescheduled_trips: usize,
+}
+
+pub struct LearnedDays {
+    /// The scenario, with everybody's departures for the next day
+    pub scenario: Scenario,
+    /// The congestion drivers expect on the next day
+    pub congestion: CongestionProfile,
+    pub days: Vec<DayResults>,
+}
+
+/// What one person remembers about one of their trips
+struct TripMemory {
+    orig_depart: Time,
+    /// When the trip ended on the first day
+    desired_arrival: Option<Time>,
+    /// How long the trip took yesterday
+    yesterday: Option<Duration>,
+}
+
+impl DayToDayLearning {
+    /// Simulates every day with the same random seed, so that people only behave differently
+    /// because of what they learned. Drivers start with any congestion `opts` already routes with.
+    pub fn simulate(
+        &self,
+        map: &Map,
+        mut scenario: Scenario,
+        opts: &SimOptions,
+        rng: &XorShiftRng,
+        timer: &mut Timer,
+    ) -> LearnedDays {
+        let rate = ((self.learning_rate_pct as f64) / 100.0).min(1.0);
+        let mut learn_rng = fork_rng(&mut rng.clone());
+        let mut congestion = opts.route_with_congestion.clone().unwrap_or_default();
+        // Trips are created in order, so this is indexed by TripID
+        let mut memory: Vec<TripMemory> = scenario
+            .people
+            .iter()
+            .flat_map(|p| p.trips.iter())
+            .map(|trip| TripMemory {
+                orig_depart: trip.depart,
+                desired_arrival: None,
+                yesterday: None,
+            })
+            .collect();
+
+        let mut days = Vec::new();
+        let mut rescheduled_trips = 0;
+        for day in 1..=self.days {
+            timer.start(format!("simulate day {}", day));
+            let mut opts = opts.clone();
+            opts.alerts = AlertHandler::Silence;
+            opts.route_with_congestion = if congestion.is_empty() {
+                None
+            } else {
+                Some(congestion.clone())
+            };
+            let mut sim = Sim::new(map, opts);
+            scenario.instantiate(&mut sim, map, &mut rng.clone(), timer);
+            // Some trips start close to midnight, so run a bit past the end of the day
+            sim.timed_step(
+                map,
+                sim.get_end_of_day() - Time::START_OF_DAY + Duration::hours(3),
+                &mut None,
+                timer,
+            );
+
+            let mut finished_trips = 0;
+            let mut total_trip_time = Duration::ZERO;
+            let mut surprise = Duration::ZERO;
+            let mut num_remembered = 0;
+            for (idx, trip) in memory.iter_mut().enumerate() {
+                let dt = sim.finished_trip_details(TripID(idx)).map(|(dt, _, _)| dt);
+                if let Some(dt) = dt {
+                    finished_trips += 1;
+                    total_trip_time += dt;
+                    if let Some(yesterday) = trip.yesterday {
+                        surprise += (dt - yesterday).abs();
+                        num_remembered += 1;
+                    }
+                    if trip.desired_arrival.is_none() {
+                        trip.desired_arrival = Some(sim.trip_info(TripID(idx)).departure + dt);
+                    }
+                }
+                trip.yesterday = dt;
+            }
+            let results = DayResults {
+                finished_trips,
+                total_trip_time,
+                mean_surprise: if num_remembered == 0 {
+                    None
+                } else {
+                    Some(surprise / (num_remembered as f64))
+                },
+                rescheduled_trips,
+            };
+            info!(
+                "Day {}: {} trips finished, taking {} in total. {} trips were rescheduled.",
+                day,
+                prettyprint_usize(results.finished_trips),
+                results.total_trip_time,
+                prettyprint_usize(results.rescheduled_trips)
+            );
+            days.push(results);
+
+            rescheduled_trips = reschedule(&mut scenario, &memory, rate, &mut learn_rng);
+            congestion = congestion.blend(
+                &sim.get_analytics()
+                    .detailed
+                    .road_travel_times
+                    .congestion_profile(),
+                rate,
+            );
+            timer.stop(format!("simulate day {}", day));
+        }
+
+        LearnedDays {
+            scenario,
+            congestion,
+            days,
+        }
+    }
+}
+
+/// Some people shift their departures to arrive on time. Returns the number of trips that changed.
+fn reschedule(
+    scenario: &mut Scenario,
+    memory: &[TripMemory],
+    rate: f64,
+    rng: &mut XorShiftRng,
+) -> usize {
+    let mut changed = 0;
+    let mut idx = 0;
+    for person in &mut scenario.people {
+        let first = idx;
+        idx += person.trips.len();
+        if !rng.gen_bool(rate) {
+            continue;
+        }
+
+        let before: Vec<Time> = person.trips.iter().map(|trip| trip.depart).collect();
+        for (trip, memory) in person.trips.iter_mut().zip(&memory[first..idx]) {
+            if let (Some(arrival), Some(dt)) = (memory.desired_arrival, memory.yesterday) {
+                trip.depart = leave_by(memory.orig_depart, arrival, dt);
+            }
+        }
+        // Leaving earlier can't overtake the previous trip
+        if person.check_schedule().is_err() {
+            for (trip, depart) in person.trips.iter_mut().zip(before) {
+                trip.depart = depart;
+            }
+            continue;
+        }
+        for (trip, depart) in person.trips.iter_mut().zip(before) {
+            if trip.depart != depart {
+                trip.modified = true;
+                changed += 1;
+            }
+        }
+    }
+    changed
+}
+
+/// When to leave to arrive on time, if the trip takes `duration`, without straying too far from
+/// the original schedule.
+fn leave_by(orig_depart: Time, arrival: Time, duration: Duration) -> Time {
+    let earliest = if orig_depart - Time::START_OF_DAY > MAX_SHIFT {
+        orig_depart - MAX_SHIFT
+    } else {
+        Time::START_OF_DAY
+    };
+    let ideal = if arrival - Time::START_OF_DAY > duration {
+        arrival - duration
+    } else {
+        Time::START_OF_DAY
+    };
+    ideal.max(earliest).min(orig_depart + MAX_SHIFT)
+}
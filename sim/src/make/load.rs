@@ -6,7 +6,7 @@ use structopt::StructOpt;
 use abstio::MapName;
 use map_model::{Map, MapEdits};
 
-use crate::{Scenario, ScenarioModifier, Sim, SimOptions};
+use crate::{ParallelSim, Scenario, ScenarioModifier, Sim, SimOptions};
 
 /// SimFlags specifies a simulation to setup. After parsing from structopt, you must call
 /// `initialize`.
@@ -109,6 +109,7 @@ impl SimFlags {
             for m in &self.scenario_modifiers {
                 scenario = m.apply(&map, scenario);
             }
+            let scenario = learn_previous_days(&map, scenario, &mut opts, &rng, timer);
 
             if opts.run_name == "unnamed" {
                 opts.run_name = scenario.scenario_name.clone();
@@ -131,4 +132,51 @@ impl SimFlags {
             panic!("Don't know how to load {}", self.load);
         }
     }
+
+    /// Loads a map and a scenario, splitting the simulation into regions that run on separate
+    /// threads. Only scenarios can be loaded this way. See `ParallelSim` for the caveats.
+    pub fn load_parallel(&self, timer: &mut abstutil::Timer) -> (Map, ParallelSim) {
+        if self.load.is_empty() {
+            panic!("You forgot to call initialize on SimFlags after parsing from structopt");
+        }
+        if !self.load.contains("/scenarios/") {
+            panic!(
+                "Only scenarios can be simulated in parallel, not {}",
+                self.load
+            );
+        }
+        info!("Seeding parallel simulations from scenario {}", self.load);
+
+        let mut scenario: Scenario = abstio::must_read_object(self.load.clone(), timer);
+        let map = Map::load_synchronously(scenario.map_name.path(), timer);
+        for m in &self.scenario_modifiers {
+            scenario = m.apply(&map, scenario);
+        }
+
+        let mut opts = self.opts.clone();
+        let scenario = learn_previous_days(&map, scenario, &mut opts, &self.make_rng(), timer);
+        if opts.run_name == "unnamed" {
+            opts.run_name = scenario.scenario_name.clone();
+        }
+        let sim = ParallelSim::new(&map, &scenario, opts, self.rng_seed, timer);
+        (map, sim)
+    }
+}
+
+/// If people learn from previous days, simulate those first. Returns the scenario for the next
+/// day, and routes drivers with the congestion they learned about.
+fn learn_previous_days(
+    map: &Map,
+    scenario: Scenario,
+    opts: &mut SimOptions,
+    rng: &XorShiftRng,
+    timer: &mut abstutil::Timer,
+) -> Scenario {
+    if let Some(learning) = scenario.day_to_day {
+        let learned = learning.simulate(map, scenario, opts, rng, timer);
+        opts.route_with_congestion = Some(learned.congestion);
+        learned.scenario
+    } else {
+        scenario
+    }
 }
@@ -4,17 +4,24 @@
 use rand::{RngCore, SeedableRng};
 use rand_xorshift::XorShiftRng;
 
+pub use self::demand_profile::{ArrivalSurge, DemandProfile};
 pub use self::external::{ExternalPerson, ExternalTrip, ExternalTripEndpoint, MapBorders};
 pub use self::generator::{BorderSpawnOverTime, ScenarioGenerator, SpawnOverTime};
+pub use self::incremental::RecordedTrips;
+pub use self::learning::{DayResults, DayToDayLearning, LearnedDays};
 pub use self::load::SimFlags;
-pub use self::modifier::ScenarioModifier;
-pub use self::scenario::{IndividTrip, PersonSpec, Scenario, TripPurpose};
+pub use self::modifier::{DriverBehaviorRange, ScenarioModifier, Weather};
+pub(crate) use self::scenario::seed_parked_cars;
+pub use self::scenario::{IndividTrip, PersonSpec, Scenario, TripPurpose, DEFAULT_CURBSIDE_DWELL};
 pub use self::spawner::TripEndpoint;
 pub(crate) use self::spawner::{StartTripArgs, TripSpec};
 
 mod activity_model;
+mod demand_profile;
 mod external;
 mod generator;
+mod incremental;
+mod learning;
 mod load;
 mod modifier;
 mod scenario;
@@ -1,12 +1,18 @@
 use std::collections::BTreeSet;
+use std::fmt;
 
+use rand::Rng;
+use rand_xorshift::XorShiftRng;
 use serde::{Deserialize, Serialize};
 
 use abstutil::Timer;
 use geom::{Duration, Time};
-use map_model::Map;
+use map_model::{LevelOfTrafficStress, Map, PathStep};
 
-use crate::{Scenario, TripMode};
+use crate::{
+    DayToDayLearning, DriverBehavior, IndividTrip, PersonSpec, Scenario, TripEndpoint, TripMode,
+    TripPurpose,
+};
 
 /// Transforms an existing Scenario before instantiating it.
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Deserialize)]
@@ -21,6 +27,127 @@ pub enum ScenarioModifier {
     },
     /// Scenario name
     AddExtraTrips(String),
+    /// Turn all driving trips for this percent of drivers into freight trips, using trucks.
+    AddFreight(usize),
+    /// This percent of drivers switch all of their trips to biking, but only if every trip has a
+    /// low-stress route on the current map (including edits) taking at most `max_biking_time`.
+    ShiftToLowStressBiking {
+        pct_drivers: usize,
+        max_biking_time: Duration,
+    },
+    /// Drivers of cars and trucks stop behaving identically; each samples their own behavior from
+    /// these ranges.
+    DriverBehavior(DriverBehaviorRange),
+    /// Dispatch one emergency vehicle between two places. If it starts at a building, it's parked
+    /// there beforehand, like at a fire station.
+    EmergencyCall {
+        depart: Time,
+        from: TripEndpoint,
+        to: TripEndpoint,
+    },
+    /// Everybody drives and bikes in this weather all day.
+    Weather(Weather),
+    /// Turn this percent of driving trips to buildings into package deliveries, and this percent
+    /// into ride-hail drop-offs. These vehicles stop at the curb for `dwell_time`, double-parking
+    /// if there's no free spot nearby to use as a loading zone.
+    CurbsideStops {
+        pct_deliveries: usize,
+        pct_ride_hail: usize,
+        dwell_time: Duration,
+    },
+    /// Simulate some days before the one that's played, with people learning from each one.
+    DayToDayLearning(DayToDayLearning),
+}
+
+/// Each driver's behavior is sampled uniformly between these bounds.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Debug, Serialize, Deserialize)]
+pub struct DriverBehaviorRange {
+    /// Relative to how fast everybody drives by default, so (90, 110) means some drivers are 10%
+    /// slower and some 10% faster
+    pub speed_pct: (usize, usize),
+    /// How long drivers take to start moving after the vehicle ahead of them does
+    pub reaction_time: (Duration, Duration),
+}
+
+impl DriverBehaviorRange {
+    pub(crate) fn sample(&self, rng: &mut XorShiftRng) -> DriverBehavior {
+        let speed_pct = if self.speed_pct.0 < self.speed_pct.1 {
+            rng.gen_range(self.speed_pct.0..=self.speed_pct.1)
+        } else {
+            self.speed_pct.0
+        };
+        let reaction_time = if self.reaction_time.0 < self.reaction_time.1 {
+            Duration::seconds(rng.gen_range(
+                self.reaction_time.0.inner_seconds()..self.reaction_time.1.inner_seconds(),
+            ))
+        } else {
+            self.reaction_time.0
+        };
+        DriverBehavior {
+            speed_factor: (speed_pct as f64) / 100.0,
+            reaction_time,
+        }
+    }
+}
+
+/// Rain and snow make drivers and cyclists slow down and leave bigger gaps to the vehicle ahead.
+/// Pedestrians aren't affected.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum Weather {
+    Clear,
+    Rain,
+    Snow,
+}
+
+impl Weather {
+    pub fn all() -> Vec<Weather> {
+        vec![Weather::Clear, Weather::Rain, Weather::Snow]
+    }
+
+    /// Multiplies how fast everybody would otherwise drive or bike
+    pub fn speed_factor(self) -> f64 {
+        match self {
+            Weather::Clear => 1.0,
+            Weather::Rain => 0.85,
+            Weather::Snow => 0.7,
+        }
+    }
+
+    /// Added to every driver's reaction time, so queues start moving more slowly
+    pub fn extra_reaction_time(self) -> Duration {
+        match self {
+            Weather::Clear => Duration::ZERO,
+            Weather::Rain => Duration::seconds(1.0),
+            Weather::Snow => Duration::seconds(2.0),
+        }
+    }
+
+    pub(crate) fn adjust(self, behavior: DriverBehavior) -> DriverBehavior {
+        DriverBehavior {
+            speed_factor: behavior.speed_factor * self.speed_factor(),
+            reaction_time: behavior.reaction_time + self.extra_reaction_time(),
+        }
+    }
+}
+
+impl Default for Weather {
+    fn default() -> Weather {
+        Weather::Clear
+    }
+}
+
+impl fmt::Display for Weather {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Weather::Clear => "clear skies",
+                Weather::Rain => "rain",
+                Weather::Snow => "snow",
+            }
+        )
+    }
 }
 
 impl ScenarioModifier {
@@ -84,6 +211,41 @@ impl ScenarioModifier {
                 }
                 s
             }
+            ScenarioModifier::AddFreight(pct_drivers) => add_freight(s, *pct_drivers),
+            ScenarioModifier::ShiftToLowStressBiking {
+                pct_drivers,
+                max_biking_time,
+            } => shift_to_low_stress_biking(s, map, *pct_drivers, *max_biking_time),
+            ScenarioModifier::DriverBehavior(range) => {
+                s.driver_behavior = Some(range.clone());
+                s
+            }
+            ScenarioModifier::EmergencyCall { depart, from, to } => {
+                let mut trip =
+                    IndividTrip::new(*depart, TripPurpose::Emergency, *from, *to, TripMode::Drive);
+                trip.modified = true;
+                s.people.push(PersonSpec {
+                    orig_id: None,
+                    trips: vec![trip],
+                });
+                s
+            }
+            ScenarioModifier::Weather(weather) => {
+                s.weather = *weather;
+                s
+            }
+            ScenarioModifier::CurbsideStops {
+                pct_deliveries,
+                pct_ride_hail,
+                dwell_time,
+            } => {
+                s.curbside_dwell = Some(*dwell_time);
+                add_curbside_stops(s, *pct_deliveries, *pct_ride_hail)
+            }
+            ScenarioModifier::DayToDayLearning(learning) => {
+                s.day_to_day = Some(*learning);
+                s
+            }
         }
     }
 
@@ -105,6 +267,50 @@ impl ScenarioModifier {
                 to_mode.map(|m| m.verb())
             ),
             ScenarioModifier::AddExtraTrips(name) => format!("Add extra trips from {}", name),
+            ScenarioModifier::AddFreight(pct_drivers) => {
+                format!(
+                    "{}% of drivers make freight deliveries by truck",
+                    pct_drivers
+                )
+            }
+            ScenarioModifier::ShiftToLowStressBiking {
+                pct_drivers,
+                max_biking_time,
+            } => format!(
+                "{}% of drivers bike instead, if they have low-stress routes taking at most {}",
+                pct_drivers, max_biking_time
+            ),
+            ScenarioModifier::DriverBehavior(range) => format!(
+                "drivers go {}% to {}% of their usual speed and react to the vehicle ahead in {} \
+                 to {}",
+                range.speed_pct.0, range.speed_pct.1, range.reaction_time.0, range.reaction_time.1
+            ),
+            ScenarioModifier::EmergencyCall { depart, from, to } => format!(
+                "an emergency vehicle leaves {:?} for {:?} at {}",
+                from,
+                to,
+                depart.ampm_tostring()
+            ),
+            ScenarioModifier::Weather(weather) => format!(
+                "{} all day, so drivers and cyclists go {}% of their usual speed and react {} \
+                 later",
+                weather,
+                (weather.speed_factor() * 100.0).round(),
+                weather.extra_reaction_time()
+            ),
+            ScenarioModifier::CurbsideStops {
+                pct_deliveries,
+                pct_ride_hail,
+                dwell_time,
+            } => format!(
+                "{}% of driving trips deliver packages and {}% drop off ride-hail passengers, \
+                 stopping at the curb for {}",
+                pct_deliveries, pct_ride_hail, dwell_time
+            ),
+            ScenarioModifier::DayToDayLearning(learning) => format!(
+                "people learn from {} previous days, with {}% adjusting their departure each day",
+                learning.days, learning.learning_rate_pct
+            ),
         }
     }
 }
@@ -135,3 +341,215 @@ fn repeat_days(mut s: Scenario, days: usize) -> Scenario {
     }
     s
 }
+
+// Like ChangeMode, this is stable as the percentage increases. A person's driving trips are all
+// converted together, so they don't need both a car and a truck.
+fn add_freight(mut s: Scenario, pct_drivers: usize) -> Scenario {
+    for (idx, person) in s
+        .people
+        .iter_mut()
+        .filter(|p| p.trips.iter().any(|t| t.mode == TripMode::Drive))
+        .enumerate()
+    {
+        if idx % 100 >= pct_drivers {
+            continue;
+        }
+        for trip in &mut person.trips {
+            if trip.mode == TripMode::Drive {
+                trip.purpose = TripPurpose::Freight;
+                trip.modified = true;
+            }
+        }
+    }
+    s
+}
+
+// Stable as the percentages increase, like add_freight. Unlike freight, this works per trip, since
+// delivery vehicles and ride-hail cars aren't the person's own vehicle anyway.
+fn add_curbside_stops(mut s: Scenario, pct_deliveries: usize, pct_ride_hail: usize) -> Scenario {
+    for (idx, trip) in s
+        .people
+        .iter_mut()
+        .flat_map(|p| p.trips.iter_mut())
+        .filter(|t| t.mode == TripMode::Drive && matches!(t.destination, TripEndpoint::Bldg(_)))
+        .enumerate()
+    {
+        if idx % 100 < pct_deliveries {
+            trip.purpose = TripPurpose::Delivery;
+        } else if idx % 100 < pct_deliveries + pct_ride_hail {
+            trip.purpose = TripPurpose::RideHail;
+        } else {
+            continue;
+        }
+        trip.modified = true;
+    }
+    s
+}
+
+// Stable as the percentage increases, like add_freight. Only drivers who can make every trip by
+// bike switch, so nobody needs both a car and a bike.
+fn shift_to_low_stress_biking(
+    mut s: Scenario,
+    map: &Map,
+    pct_drivers: usize,
+    max_biking_time: Duration,
+) -> Scenario {
+    let candidates: Vec<usize> = s
+        .people
+        .iter()
+        .enumerate()
+        .filter(|(_, p)| p.trips.iter().any(|t| t.mode == TripMode::Drive))
+        .enumerate()
+        .filter(|(n, _)| n % 100 < pct_drivers)
+        .map(|(_, (idx, _))| idx)
+        .collect();
+
+    let people = &s.people;
+    let switch: Vec<usize> = Timer::throwaway()
+        .parallelize("find low-stress bike routes", candidates, |idx| {
+            if people[idx].trips.iter().all(|t| {
+                t.mode != TripMode::Drive
+                    || has_low_stress_bike_route(map, t.origin, t.destination, max_biking_time)
+            }) {
+                Some(idx)
+            } else {
+                None
+            }
+        })
+        .into_iter()
+        .flatten()
+        .collect();
+
+    for idx in switch {
+        for trip in &mut s.people[idx].trips {
+            if trip.mode == TripMode::Drive {
+                trip.mode = TripMode::Bike;
+                trip.modified = true;
+            }
+        }
+    }
+    s
+}
+
+fn has_low_stress_bike_route(
+    map: &Map,
+    from: TripEndpoint,
+    to: TripEndpoint,
+    max_biking_time: Duration,
+) -> bool {
+    // Somebody driving in from outside the map isn't going to bike the whole way
+    if !matches!((from, to), (TripEndpoint::Bldg(_), TripEndpoint::Bldg(_))) {
+        return false;
+    }
+    let path = match TripEndpoint::path_req(from, to, TripMode::Bike, map)
+        .and_then(|req| map.pathfind(req).ok())
+    {
+        Some(path) => path,
+        None => return false,
+    };
+    if path.estimate_duration(map, Some(map_model::MAX_BIKE_SPEED)) > max_biking_time {
+        return false;
+    }
+    path.get_steps().iter().all(|step| match step {
+        PathStep::Lane(l) | PathStep::ContraflowLane(l) => {
+            map.get_r(l.road).bike_stress(map, map.get_l(*l).dir) <= LevelOfTrafficStress::Lts2
+        }
+        PathStep::Turn(_) => true,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use abstio::MapName;
+    use map_model::BuildingID;
+
+    use super::*;
+
+    // Every third person only walks; the rest drive there and walk back
+    fn scenario(num_people: usize) -> Scenario {
+        let people = (0..num_people)
+            .map(|idx| {
+                let home = TripEndpoint::Bldg(BuildingID(idx));
+                let work = TripEndpoint::Bldg(BuildingID(num_people + idx));
+                let mode = if idx % 3 == 0 {
+                    TripMode::Walk
+                } else {
+                    TripMode::Drive
+                };
+                PersonSpec {
+                    orig_id: None,
+                    trips: vec![
+                        IndividTrip::new(
+                            Time::START_OF_DAY + Duration::hours(8),
+                            TripPurpose::Work,
+                            home,
+                            work,
+                            mode,
+                        ),
+                        IndividTrip::new(
+                            Time::START_OF_DAY + Duration::hours(17),
+                            TripPurpose::Home,
+                            work,
+                            home,
+                            TripMode::Walk,
+                        ),
+                    ],
+                }
+            })
+            .collect();
+        Scenario {
+            scenario_name: "test".to_string(),
+            map_name: MapName::new("zz", "test", "test"),
+            people,
+            only_seed_buses: None,
+            driver_behavior: None,
+            weather: Weather::Clear,
+            curbside_dwell: None,
+            day_to_day: None,
+        }
+    }
+
+    fn freight_people(s: &Scenario) -> BTreeSet<usize> {
+        s.people
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| {
+                p.trips
+                    .iter()
+                    .any(|t| matches!(t.purpose, TripPurpose::Freight))
+            })
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+
+    #[test]
+    fn test_add_freight() {
+        let num_drivers = 200;
+        let s = add_freight(scenario(300), 30);
+        let freight = freight_people(&s);
+        assert_eq!(freight.len(), 30 * num_drivers / 100);
+
+        for (idx, person) in s.people.iter().enumerate() {
+            for trip in &person.trips {
+                let converted = matches!(trip.purpose, TripPurpose::Freight);
+                // All of a person's driving trips are converted together, and nothing else is
+                assert_eq!(
+                    converted,
+                    freight.contains(&idx) && trip.mode == TripMode::Drive
+                );
+                assert_eq!(converted, trip.modified);
+            }
+        }
+
+        // The same drivers stay freight as the percentage increases
+        let more = freight_people(&add_freight(scenario(300), 60));
+        assert!(freight.is_subset(&more));
+        assert_eq!(more.len(), 60 * num_drivers / 100);
+
+        assert!(freight_people(&add_freight(scenario(300), 0)).is_empty());
+        assert_eq!(
+            freight_people(&add_freight(scenario(300), 100)).len(),
+            num_drivers
+        );
+    }
+}
@@ -9,13 +9,14 @@ use serde::{Deserialize, Serialize};
 
 use abstio::MapName;
 use abstutil::{prettyprint_usize, Counter, Timer};
-use geom::{Distance, Speed, Time};
+use geom::{Distance, Duration, Speed, Time};
 use map_model::{BuildingID, Map, OffstreetParking, RoadID};
 
 use crate::make::fork_rng;
 use crate::{
-    OrigPersonID, ParkingSpot, Sim, StartTripArgs, TripEndpoint, TripInfo, TripMode, Vehicle,
-    VehicleSpec, VehicleType, BIKE_LENGTH, MAX_CAR_LENGTH, MIN_CAR_LENGTH,
+    DayToDayLearning, DriverBehavior, DriverBehaviorRange, OrigPersonID, ParkingSpot, PersonID,
+    Sim, StartTripArgs, TripEndpoint, TripInfo, TripMode, Vehicle, VehicleSpec, VehicleType,
+    Weather, BIKE_LENGTH, MAX_CAR_LENGTH, MAX_TRUCK_LENGTH, MIN_CAR_LENGTH, MIN_TRUCK_LENGTH,
 };
 
 /// A Scenario describes all the input to a simulation. Usually a scenario covers one day.
@@ -27,8 +28,27 @@ pub struct Scenario {
     pub people: Vec<PersonSpec>,
     /// None means seed all buses. Otherwise the route name must be present here.
     pub only_seed_buses: Option<BTreeSet<String>>,
+    /// Set by `ScenarioModifier::DriverBehavior`. Modifiers are applied after loading, so this
+    /// isn't saved with the scenario.
+    #[serde(skip)]
+    pub driver_behavior: Option<DriverBehaviorRange>,
+    /// Set by `ScenarioModifier::Weather`, and likewise not saved.
+    #[serde(skip)]
+    pub weather: Weather,
+    /// How long delivery and ride-hail vehicles stop at the curb. Set by
+    /// `ScenarioModifier::CurbsideStops` and not saved; None means `DEFAULT_CURBSIDE_DWELL`.
+    #[serde(skip)]
+    pub curbside_dwell: Option<Duration>,
+    /// Set by `ScenarioModifier::DayToDayLearning` and not saved. Whoever runs the scenario has to
+    /// simulate the previous days first; see `DayToDayLearning::simulate`.
+    #[serde(skip)]
+    pub day_to_day: Option<DayToDayLearning>,
 }
 
+/// How long delivery and ride-hail vehicles stop at the curb, unless a ScenarioModifier says
+/// otherwise
+pub const DEFAULT_CURBSIDE_DWELL: Duration = Duration::const_seconds(120.0);
+
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct PersonSpec {
     /// Just used for debugging
@@ -85,6 +105,24 @@ pub enum TripPurpose {
     Recreation,
     Medical,
     ParkAndRideTransfer,
+    /// Delivering goods. Driving trips with this purpose use a truck instead of a car.
+    Freight,
+    /// Responding to an emergency call. Driving trips with this purpose use an emergency vehicle.
+    Emergency,
+    /// Dropping off a package. The vehicle stops at the curb in front of the destination, then
+    /// leaves.
+    Delivery,
+    /// Dropping off a passenger. The ride-hail car stops at the curb in front of the destination,
+    /// then leaves.
+    RideHail,
+}
+
+impl TripPurpose {
+    /// Driving trips with this purpose don't park. The vehicle stops curbside at the destination
+    /// for a while, then leaves the map. It isn't the person's own vehicle.
+    pub fn stops_curbside(self) -> bool {
+        matches!(self, TripPurpose::Delivery | TripPurpose::RideHail)
+    }
 }
 
 impl fmt::Display for TripPurpose {
@@ -105,6 +143,10 @@ impl fmt::Display for TripPurpose {
                 TripPurpose::Recreation => "recreation",
                 TripPurpose::Medical => "medical",
                 TripPurpose::ParkAndRideTransfer => "park-and-ride transfer",
+                TripPurpose::Freight => "freight",
+                TripPurpose::Emergency => "emergency response",
+                TripPurpose::Delivery => "package delivery",
+                TripPurpose::RideHail => "ride-hail",
             }
         )
     }
@@ -127,21 +169,54 @@ impl Scenario {
     ) {
         // Any case where map edits could change the calls to the RNG, we have to fork.
         sim.set_run_name(self.scenario_name.clone());
+        // Buses pick this up when they spawn
+        sim.set_weather(self.weather);
 
         timer.start(format!("Instantiating {}", self.scenario_name));
 
+        self.seed_buses(sim, map);
+
+        let (mut parked_cars, schedule_trips) =
+            self.create_people(sim, rng, retry_if_no_room, timer);
+
+        // parked_cars is stable over map edits, so don't fork.
+        parked_cars.shuffle(rng);
+        seed_parked_cars(parked_cars, sim, map, rng, timer);
+
+        sim.spawn_trips(schedule_trips, map, timer);
+        timer.stop(format!("Instantiating {}", self.scenario_name));
+    }
+
+    pub(crate) fn seed_buses(&self, sim: &mut Sim, map: &Map) {
         if let Some(ref routes) = self.only_seed_buses {
             for route in map.all_bus_routes() {
                 if routes.contains(&route.full_name) {
-                    sim.seed_bus_route(route);
+                    sim.seed_bus_route(route, map);
                 }
             }
         } else {
             // All of them
             for route in map.all_bus_routes() {
-                sim.seed_bus_route(route);
+                sim.seed_bus_route(route, map);
             }
         }
+    }
+
+    /// Creates everybody in the scenario, without starting any of their trips. Returns the cars
+    /// that should start parked at each building, and every trip to schedule.
+    pub(crate) fn create_people(
+        &self,
+        sim: &mut Sim,
+        rng: &mut XorShiftRng,
+        retry_if_no_room: bool,
+        timer: &mut Timer,
+    ) -> (
+        Vec<(Vehicle, BuildingID)>,
+        Vec<(PersonID, TripInfo, StartTripArgs)>,
+    ) {
+        // Only draw from a separate RNG when driver behavior varies, so that scenarios without it
+        // keep the same results.
+        let mut behavior_rng = self.driver_behavior.as_ref().map(|_| fork_rng(rng));
 
         timer.start_iter("trips for People", self.people.len());
         let mut parked_cars: Vec<(Vehicle, BuildingID)> = Vec::new();
@@ -153,8 +228,20 @@ impl Scenario {
                 panic!("{}", err);
             }
 
-            let (vehicle_specs, cars_initially_parked_at, vehicle_foreach_trip) =
+            let (mut vehicle_specs, cars_initially_parked_at, vehicle_foreach_trip) =
                 p.get_vehicles(rng);
+            if let (Some(range), Some(behavior_rng)) =
+                (self.driver_behavior.as_ref(), behavior_rng.as_mut())
+            {
+                for spec in &mut vehicle_specs {
+                    if matches!(spec.vehicle_type, VehicleType::Car | VehicleType::Truck) {
+                        spec.behavior = range.sample(behavior_rng);
+                    }
+                }
+            }
+            for spec in &mut vehicle_specs {
+                spec.behavior = self.weather.adjust(spec.behavior);
+            }
             let person = sim.new_person(p.orig_id, Scenario::rand_ped_speed(rng), vehicle_specs);
             for (idx, b) in cars_initially_parked_at {
                 parked_cars.push((person.vehicles[idx].clone(), b));
@@ -168,6 +255,11 @@ impl Scenario {
                         start: trip.origin,
                         end: trip.destination,
                         purpose: trip.purpose,
+                        curbside_dwell: if trip.purpose.stops_curbside() {
+                            Some(self.curbside_dwell.unwrap_or(DEFAULT_CURBSIDE_DWELL))
+                        } else {
+                            None
+                        },
                         modified: trip.modified,
                         cancellation_reason: if trip.cancelled {
                             Some("cancelled by ScenarioModifier".to_string())
@@ -183,12 +275,7 @@ impl Scenario {
             }
         }
 
-        // parked_cars is stable over map edits, so don't fork.
-        parked_cars.shuffle(rng);
-        seed_parked_cars(parked_cars, sim, map, rng, timer);
-
-        sim.spawn_trips(schedule_trips, map, timer);
-        timer.stop(format!("Instantiating {}", self.scenario_name));
+        (parked_cars, schedule_trips)
     }
 
     pub fn save(&self) {
@@ -204,6 +291,10 @@ impl Scenario {
             map_name: map.get_name().clone(),
             people: Vec::new(),
             only_seed_buses: Some(BTreeSet::new()),
+            driver_behavior: None,
+            weather: Weather::Clear,
+            curbside_dwell: None,
+            day_to_day: None,
         }
     }
 
@@ -213,6 +304,37 @@ impl Scenario {
             vehicle_type: VehicleType::Car,
             length,
             max_speed: None,
+            behavior: DriverBehavior::default(),
+        }
+    }
+
+    fn rand_truck(rng: &mut XorShiftRng) -> VehicleSpec {
+        let length = Scenario::rand_dist(rng, MIN_TRUCK_LENGTH, MAX_TRUCK_LENGTH);
+        // Trucks are governed or just take it easy on city streets
+        let max_speed = Some(Scenario::rand_speed(
+            rng,
+            Speed::miles_per_hour(40.0),
+            Speed::miles_per_hour(55.0),
+        ));
+        VehicleSpec {
+            vehicle_type: VehicleType::Truck,
+            length,
+            max_speed,
+            behavior: DriverBehavior::default(),
+        }
+    }
+
+    fn rand_emergency_vehicle(rng: &mut XorShiftRng) -> VehicleSpec {
+        let length = Scenario::rand_dist(rng, MIN_CAR_LENGTH, MAX_CAR_LENGTH);
+        VehicleSpec {
+            vehicle_type: VehicleType::Emergency,
+            length,
+            max_speed: None,
+            // Running with lights and sirens
+            behavior: DriverBehavior {
+                speed_factor: 1.3,
+                reaction_time: Duration::ZERO,
+            },
         }
     }
 
@@ -226,6 +348,7 @@ impl Scenario {
             vehicle_type: VehicleType::Bike,
             length: BIKE_LENGTH,
             max_speed,
+            behavior: DriverBehavior::default(),
         }
     }
 
@@ -284,7 +407,7 @@ impl Scenario {
     }
 }
 
-fn seed_parked_cars(
+pub(crate) fn seed_parked_cars(
     parked_cars: Vec<(Vehicle, BuildingID)>,
     sim: &mut Sim,
     map: &Map,
@@ -407,7 +530,7 @@ fn find_spot_near_building(
 
 impl PersonSpec {
     /// Verify that a person's trips make sense
-    fn check_schedule(&self) -> Result<()> {
+    pub fn check_schedule(&self) -> Result<()> {
         if self.trips.is_empty() {
             bail!("Person ({:?}) has no trips at all", self.orig_id);
         }
@@ -464,8 +587,8 @@ impl PersonSpec {
         let mut vehicle_foreach_trip = Vec::new();
 
         let mut bike_idx = None;
-        // For each indexed car, is it parked somewhere, or off-map?
-        let mut car_locations: Vec<(usize, Option<BuildingID>)> = Vec::new();
+        // For each indexed car, is it parked somewhere, or off-map? What type of vehicle is it?
+        let mut car_locations: Vec<(usize, Option<BuildingID>, VehicleType)> = Vec::new();
 
         // TODO If the trip is cancelled, this should be affected...
         for trip in &self.trips {
@@ -484,17 +607,33 @@ impl PersonSpec {
                         _ => None,
                     };
 
+                    let vehicle_type = match trip.purpose {
+                        TripPurpose::Freight => VehicleType::Truck,
+                        TripPurpose::Emergency => VehicleType::Emergency,
+                        _ => VehicleType::Car,
+                    };
+
+                    // Delivery vehicles and ride-hail cars don't belong to the person, so never
+                    // reuse one of their cars, and forget the vehicle after the trip.
+                    let stops_curbside = trip.purpose.stops_curbside();
+
                     // Any available cars in the right spot?
                     let idx = if let Some(idx) = car_locations
                         .iter()
-                        .find(|(_, parked_at)| *parked_at == need_parked_at)
-                        .map(|(idx, _)| *idx)
+                        .find(|(_, parked_at, vt)| {
+                            !stops_curbside && *parked_at == need_parked_at && *vt == vehicle_type
+                        })
+                        .map(|(idx, _, _)| *idx)
                     {
                         idx
                     } else {
                         // Need a new car, starting in the right spot
                         let idx = vehicle_specs.len();
-                        vehicle_specs.push(Scenario::rand_car(rng));
+                        vehicle_specs.push(match vehicle_type {
+                            VehicleType::Truck => Scenario::rand_truck(rng),
+                            VehicleType::Emergency => Scenario::rand_emergency_vehicle(rng),
+                            _ => Scenario::rand_car(rng),
+                        });
                         if let Some(b) = need_parked_at {
                             cars_initially_parked_at.push((idx, b));
                         }
@@ -502,13 +641,14 @@ impl PersonSpec {
                     };
 
                     // Where does this car wind up?
-                    car_locations.retain(|(i, _)| idx != *i);
+                    car_locations.retain(|(i, _, _)| idx != *i);
                     match trip.destination {
+                        _ if stops_curbside => {}
                         TripEndpoint::Bldg(b) => {
-                            car_locations.push((idx, Some(b)));
+                            car_locations.push((idx, Some(b), vehicle_type));
                         }
                         TripEndpoint::Border(_) | TripEndpoint::SuddenlyAppear(_) => {
-                            car_locations.push((idx, None));
+                            car_locations.push((idx, None, vehicle_type));
                         }
                     }
 
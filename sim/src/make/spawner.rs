@@ -4,7 +4,7 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
-use geom::Pt2D;
+use geom::{Duration, Pt2D};
 use map_model::{
     BuildingID, BusRouteID, BusStopID, IntersectionID, Map, PathConstraints, PathRequest, Position,
 };
@@ -116,7 +116,7 @@ impl TripSpec {
                     DrivingGoal::ParkNear(b) => {
                         legs.push(TripLeg::Walk(SidewalkSpot::building(*b, map)));
                     }
-                    DrivingGoal::Border(_, _) => {}
+                    DrivingGoal::Border(_, _) | DrivingGoal::StopCurbside(_, _) => {}
                 }
             }
             TripSpec::JustWalking { start, goal, .. } => {
@@ -131,10 +131,12 @@ impl TripSpec {
             TripSpec::UsingBike { start, goal, bike } => {
                 // TODO Might not be possible to walk to the same border if there's no sidewalk
                 let backup_plan = match goal {
-                    DrivingGoal::ParkNear(b) => Some(TripSpec::JustWalking {
-                        start: SidewalkSpot::building(*start, map),
-                        goal: SidewalkSpot::building(*b, map),
-                    }),
+                    DrivingGoal::ParkNear(b) | DrivingGoal::StopCurbside(b, _) => {
+                        Some(TripSpec::JustWalking {
+                            start: SidewalkSpot::building(*start, map),
+                            goal: SidewalkSpot::building(*b, map),
+                        })
+                    }
                     DrivingGoal::Border(i, _) => {
                         SidewalkSpot::end_at_border(*i, map).map(|goal| TripSpec::JustWalking {
                             start: SidewalkSpot::building(*start, map),
@@ -169,7 +171,7 @@ impl TripSpec {
                         DrivingGoal::ParkNear(b) => {
                             legs.push(TripLeg::Walk(SidewalkSpot::building(*b, map)));
                         }
-                        DrivingGoal::Border(_, _) => {}
+                        DrivingGoal::Border(_, _) | DrivingGoal::StopCurbside(_, _) => {}
                     }
                 } else if let Some(plan) = backup_plan {
                     info!("Can't start biking from {}. Walking instead", start);
@@ -209,13 +211,15 @@ impl TripSpec {
     }
 
     /// Turn an origin/destination pair and mode into a specific plan for instantiating a trip.
-    /// Decisions like how to use public transit happen here.
+    /// Decisions like how to use public transit happen here. Driving trips to a building with a
+    /// `curbside_dwell` stop there instead of parking.
     pub fn maybe_new(
         from: TripEndpoint,
         to: TripEndpoint,
         mode: TripMode,
         use_vehicle: Option<CarID>,
         retry_if_no_room: bool,
+        curbside_dwell: Option<Duration>,
         map: &Map,
     ) -> Result<TripSpec> {
         Ok(match mode {
@@ -225,7 +229,12 @@ impl TripSpec {
                 } else {
                     PathConstraints::Bike
                 };
-                let goal = to.driving_goal(constraints, map)?;
+                let mut goal = to.driving_goal(constraints, map)?;
+                if let (TripMode::Drive, Some(dwell), DrivingGoal::ParkNear(b)) =
+                    (mode, curbside_dwell, &goal)
+                {
+                    goal = DrivingGoal::StopCurbside(*b, dwell);
+                }
                 match from {
                     TripEndpoint::Bldg(start_bldg) => {
                         if mode == TripMode::Drive {
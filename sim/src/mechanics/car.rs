@@ -2,7 +2,7 @@ use std::collections::{BTreeSet, VecDeque};
 
 use serde::{Deserialize, Serialize};
 
-use geom::{Distance, Duration, PolyLine, Time, EPSILON_DIST};
+use geom::{Distance, Duration, PolyLine, Speed, Time, EPSILON_DIST};
 use map_model::{Direction, LaneID, Map, Traversable};
 
 use crate::{
@@ -60,6 +60,7 @@ impl Car {
                 self.vehicle.vehicle_type.to_constraints(),
                 map,
             );
+        let speed = self.driving_speed(speed);
         let dt = (dist_int.end - dist_int.start) / speed;
         CarState::Crossing {
             time_int: TimeInterval::new(start_time, start_time + dt),
@@ -68,6 +69,15 @@ impl Car {
         }
     }
 
+    /// Adjust the usual speed for this driver's behavior, never exceeding the vehicle's limit.
+    fn driving_speed(&self, speed: Speed) -> Speed {
+        let speed = speed * self.vehicle.behavior.speed_factor;
+        match self.vehicle.max_speed {
+            Some(max) if speed > max => max,
+            _ => speed,
+        }
+    }
+
     pub fn get_draw_car(
         &self,
         front: Distance,
@@ -4,7 +4,10 @@ use serde::{Deserialize, Serialize};
 
 use abstutil::{deserialize_hashmap, serialize_hashmap, FixedMap, IndexableKey};
 use geom::{Distance, Duration, PolyLine, Time};
-use map_model::{DrivingSide, IntersectionID, LaneID, Map, Path, PathStep, Position, Traversable};
+use map_model::{
+    DrivingSide, Intersection, IntersectionID, LaneID, Map, Path, PathStep, Position, Traversable,
+    TurnID,
+};
 
 use crate::mechanics::car::{Car, CarState};
 use crate::mechanics::queue::{Queue, QueueEntry, Queued};
@@ -13,11 +16,16 @@ use crate::{
     ActionAtEnd, AgentID, AgentProperties, CarID, CarStatus, Command, CreateCar, DelayCause,
     DistanceInterval, DrawCarInput, Event, IntersectionSimState, ParkedCar, ParkingSim,
     ParkingSpot, PersonID, Problem, SimOptions, TimeInterval, TransitSimState, TripID, TripManager,
-    UnzoomedAgent, Vehicle, VehicleType, WalkingSimState, FOLLOWING_DISTANCE, MAX_CAR_LENGTH,
+    UnzoomedAgent, Vehicle, VehicleType, WalkingSimState, FOLLOWING_DISTANCE, MAX_TRUCK_LENGTH,
 };
 
 const TIME_TO_WAIT_AT_BUS_STOP: Duration = Duration::const_seconds(10.0);
 const TIME_TO_CHANGE_LANES: Duration = Duration::const_seconds(1.0);
+/// A vehicle stuck in a queue only changes lanes midblock if the adjacent queue has at least this
+/// many fewer vehicles
+const MIN_QUEUE_ADVANTAGE: usize = 2;
+/// How far back from the stop line actuated traffic signals can sense vehicles
+const DETECTOR_LENGTH: Distance = Distance::const_meters(30.0);
 
 // TODO Do something else.
 pub const BLIND_RETRY_TO_CREEP_FORWARDS: Duration = Duration::const_seconds(0.1);
@@ -44,7 +52,9 @@ pub(crate) struct DrivingSimState {
     waiting_to_spawn: BTreeMap<CarID, (Position, Option<PersonID>)>,
 
     recalc_lanechanging: bool,
+    change_lanes_midblock: bool,
     handle_uber_turns: bool,
+    cruise_for_parking: bool,
 
     time_to_unpark_onstreet: Duration,
     time_to_park_onstreet: Duration,
@@ -60,7 +70,9 @@ impl DrivingSimState {
             queues: HashMap::new(),
             events: Vec::new(),
             recalc_lanechanging: !opts.dont_recalc_lanechanging,
+            change_lanes_midblock: !opts.dont_change_lanes_midblock,
             handle_uber_turns: !opts.dont_handle_uber_turns,
+            cruise_for_parking: opts.cruise_for_parking,
             waiting_to_spawn: BTreeMap::new(),
 
             time_to_unpark_onstreet: Duration::seconds(10.0),
@@ -122,15 +134,15 @@ impl DrivingSimState {
         // blockage's front and the index in that queue.
         let mut blocked_starts: Vec<(Position, usize)> = Vec::new();
         for lane in params.router.get_path().get_blocked_starts() {
-            // This buffer makes sure other vehicles can enter the queue behind a blockage very
-            // close to the start of the lane and not spillover.
+            // This buffer makes sure other vehicles (as long as the longest truck) can enter the
+            // queue behind a blockage very close to the start of the lane and not spillover.
             let pos = match params
                 .router
                 .get_path()
                 .get_req()
                 .start
                 .equiv_pos(lane, ctx.map)
-                .buffer_dist(MAX_CAR_LENGTH + FOLLOWING_DISTANCE, ctx.map)
+                .buffer_dist(MAX_TRUCK_LENGTH + FOLLOWING_DISTANCE, ctx.map)
             {
                 Some(pos) => pos,
                 None => {
@@ -237,6 +249,7 @@ impl DrivingSimState {
                         ctx.parking,
                         ctx.map,
                         car.trip_and_person,
+                        self.cruise_for_parking,
                         &mut self.events,
                     ) {
                         None | Some(ActionAtEnd::GotoLaneEnd) => {}
@@ -290,7 +303,7 @@ impl DrivingSimState {
     ///
     /// Crossing -> Queued or WaitingToAdvance
     /// Unparking -> Crossing
-    /// IdlingAtStop -> Crossing
+    /// IdlingAtStop -> Crossing (buses) or done (curbside stops)
     /// Queued -> last step handling (Parking or done)
     /// WaitingToAdvance -> try to advance to the next step of the path
     /// Parking -> done
@@ -392,6 +405,17 @@ impl DrivingSimState {
                         );
                     }
                     ctx.scheduler.push(now, Command::UpdateCar(car.vehicle.id));
+                } else if car.vehicle.vehicle_type == VehicleType::Emergency {
+                    // Drivers ahead pull aside to let emergency vehicles by. There's no room to
+                    // pass inside one lane, so take any gap in an adjacent lane going the same
+                    // way, even if that queue isn't shorter.
+                    if let Some(target_lane) = self.pick_overtaking_lane(car, ctx.map) {
+                        car.state = CarState::Queued {
+                            blocked_since: now,
+                            want_to_change_lanes: Some(target_lane),
+                        };
+                        return true;
+                    }
                 } else if let Some(slow_leader) = self.wants_to_overtake(car) {
                     // TODO This entire check kicks in a little late; we only enter Queued after
                     // spending the freeflow time possibly moving very slowly.
@@ -418,6 +442,16 @@ impl DrivingSimState {
                         };
                         return true;
                     }
+                } else if self.change_lanes_midblock {
+                    // Stuck behind a queue. Instead of waiting for it to clear, maybe merge into a
+                    // shorter queue that still leads to the same turn.
+                    if let Some(target_lane) = self.pick_shorter_queue(car, ctx.map) {
+                        car.state = CarState::Queued {
+                            blocked_since: now,
+                            want_to_change_lanes: Some(target_lane),
+                        };
+                        return true;
+                    }
                 }
             }
             CarState::Unparking {
@@ -455,6 +489,7 @@ impl DrivingSimState {
                         ctx.parking,
                         ctx.map,
                         car.trip_and_person,
+                        self.cruise_for_parking,
                         &mut self.events,
                     );
                 }
@@ -508,12 +543,22 @@ impl DrivingSimState {
                     ctx.parking,
                     ctx.map,
                     car.trip_and_person,
+                    self.cruise_for_parking,
                     &mut self.events,
                 );
                 car.total_blocked_time += now - blocked_since;
                 car.state = car.crossing_state(Distance::ZERO, now, ctx.map);
                 ctx.scheduler
                     .push(car.state.get_end_time(), Command::UpdateCar(car.vehicle.id));
+                if car.vehicle.vehicle_type == VehicleType::Emergency {
+                    // Ask the next traffic signal to change before the vehicle reaches it
+                    if let (Traversable::Lane(_), Some(Traversable::Turn(t))) =
+                        (goto, car.router.maybe_next())
+                    {
+                        ctx.intersections
+                            .preempt_signal(now, car, t, ctx.map, ctx.scheduler);
+                    }
+                }
                 self.events.push(Event::AgentEntersTraversable(
                     AgentID::Car(car.vehicle.id),
                     car.trip_and_person.map(|(t, _)| t),
@@ -623,6 +668,7 @@ impl DrivingSimState {
                     ctx.parking,
                     ctx.map,
                     car.trip_and_person,
+                    self.cruise_for_parking,
                     &mut self.events,
                 ) {
                     Some(ActionAtEnd::VanishAtBorder(i)) => {
@@ -653,7 +699,8 @@ impl DrivingSimState {
                         );
                         false
                     }
-                    Some(ActionAtEnd::StartParking(spot)) => {
+                    Some(ActionAtEnd::StartParking(spot))
+                    | Some(ActionAtEnd::StopCurbside(Some(spot), _)) => {
                         car.total_blocked_time += now - blocked_since;
                         let delay = match spot {
                             ParkingSpot::Onstreet(_, _) => self.time_to_park_onstreet,
@@ -690,12 +737,26 @@ impl DrivingSimState {
                         );
                         false
                     }
+                    Some(ActionAtEnd::StopCurbside(None, dwell)) => {
+                        // No loading zone, so double-park, blocking the lane
+                        car.total_blocked_time += now - blocked_since;
+                        car.state =
+                            CarState::IdlingAtStop(our_dist, TimeInterval::new(now, now + dwell));
+                        ctx.scheduler
+                            .push(car.state.get_end_time(), Command::UpdateCar(car.vehicle.id));
+                        true
+                    }
                     Some(ActionAtEnd::BusAtStop) => {
                         car.total_blocked_time += now - blocked_since;
-                        if transit.bus_arrived_at_stop(now, car.vehicle.id, trips, walking, ctx) {
+                        if let Some(depart_after) =
+                            transit.bus_arrived_at_stop(now, car.vehicle.id, trips, walking, ctx)
+                        {
                             car.state = CarState::IdlingAtStop(
                                 our_dist,
-                                TimeInterval::new(now, now + TIME_TO_WAIT_AT_BUS_STOP),
+                                TimeInterval::new(
+                                    now,
+                                    (now + TIME_TO_WAIT_AT_BUS_STOP).max(depart_after),
+                                ),
                             );
                             ctx.scheduler
                                 .push(car.state.get_end_time(), Command::UpdateCar(car.vehicle.id));
@@ -750,7 +811,23 @@ impl DrivingSimState {
                 );
                 false
             }
-            CarState::IdlingAtStop(dist, _) => {
+            CarState::IdlingAtStop(dist, ref time_int) => {
+                if car.router.is_stopping_curbside() {
+                    self.events.push(Event::DoubleParked(
+                        car.vehicle.id,
+                        car.router.head().as_lane(),
+                        time_int.end - time_int.start,
+                    ));
+                    trips.car_finished_curbside_stop(
+                        now,
+                        car.vehicle.id,
+                        car.total_blocked_time,
+                        car.router.get_path().total_length(),
+                        ctx,
+                    );
+                    return false;
+                }
+
                 car.router = transit.bus_departed_from_stop(car.vehicle.id, ctx.map);
                 self.events
                     .push(Event::PathAmended(car.router.get_path().clone()));
@@ -878,6 +955,14 @@ impl DrivingSimState {
                     // Prevent them from jumping forwards.
                     follower.total_blocked_time += now - blocked_since;
                     follower.state = follower.crossing_state(follower_dist, now, ctx.map);
+                    // Drivers don't start moving the instant the vehicle ahead does. Stretching
+                    // out the crossing keeps them behind the leader the whole time.
+                    if let CarState::Crossing {
+                        ref mut time_int, ..
+                    } = follower.state
+                    {
+                        time_int.end += follower.vehicle.behavior.reaction_time;
+                    }
                     ctx.scheduler.update(
                         follower.state.get_end_time(),
                         Command::UpdateCar(follower_id),
@@ -1082,8 +1167,37 @@ impl DrivingSimState {
     /// - Prefer passing on the left (for DrivingSide::Right)
     /// For now, just pick one candidate lane, even if both might be usable.
     fn pick_overtaking_lane(&self, car: &Car, map: &Map) -> Option<LaneID> {
-        // Don't overtake in the middle of a turn!
-        let current_lane = map.get_l(car.router.head().maybe_lane()?);
+        self.adjacent_lanes_for_path(car, map).into_iter().next()
+    }
+
+    /// If the car is stuck in a queue, is there an adjacent lane with a much shorter queue that
+    /// still leads to their upcoming turn? Whether there's actually a gap to merge into is checked
+    /// later, when the lane-change starts.
+    fn pick_shorter_queue(&self, car: &Car, map: &Map) -> Option<LaneID> {
+        let (current_vehicles, _) = self.queues[&car.router.head()].target_lane_penalty();
+        self.adjacent_lanes_for_path(car, map)
+            .into_iter()
+            .map(|l| {
+                (
+                    l,
+                    self.queues[&Traversable::Lane(l)].target_lane_penalty().0,
+                )
+            })
+            .filter(|(_, vehicles)| *vehicles + MIN_QUEUE_ADVANTAGE <= current_vehicles)
+            .min_by_key(|(_, vehicles)| *vehicles)
+            .map(|(l, _)| l)
+    }
+
+    /// Adjacent lanes the car could change into, without needing to change the rest of their
+    /// path. The lane on the passing side (left for DrivingSide::Right) comes first.
+    fn adjacent_lanes_for_path(&self, car: &Car, map: &Map) -> Vec<LaneID> {
+        // Don't change lanes in the middle of a turn!
+        let current_lane = match car.router.head().maybe_lane() {
+            Some(l) => map.get_l(l),
+            None => {
+                return Vec::new();
+            }
+        };
         let road = map.get_parent(current_lane.id);
         let idx = current_lane.id.offset;
 
@@ -1098,11 +1212,11 @@ impl DrivingSimState {
             candidates.reverse();
         }
 
-        for l in candidates {
-            let target_lane = map.get_l(l);
+        candidates.retain(|l| {
+            let target_lane = map.get_l(*l);
             // Must be the same direction -- no crossing into oncoming traffic yet
             if current_lane.dir != target_lane.dir {
-                continue;
+                return false;
             }
             // The lane types can differ, as long as the vehicle can use the target. Imagine
             // overtaking a slower cyclist in a bike lane using the rest of the road.
@@ -1112,20 +1226,14 @@ impl DrivingSimState {
                 .to_constraints()
                 .can_use(target_lane, map)
             {
-                continue;
+                return false;
             }
             // Is this other lane compatible with the path? We won't make any attempts to return to the
             // original lane after changing.
-            if !car
-                .router
+            car.router
                 .can_lanechange(current_lane.id, target_lane.id, map)
-            {
-                continue;
-            }
-            return Some(target_lane.id);
-        }
-
-        None
+        });
+        candidates
     }
 
     fn try_start_lc(
@@ -1508,6 +1616,33 @@ impl DrivingSimState {
         self.queues[&Traversable::Lane(l)].target_lane_penalty()
     }
 
+    /// Acts like a loop detector just before the stop line of every lane leading into an
+    /// intersection. Returns the turns that vehicles close to the end of those lanes will make
+    /// next.
+    pub fn detect_approaching_vehicles(&self, now: Time, i: &Intersection) -> BTreeSet<TurnID> {
+        let mut turns = BTreeSet::new();
+        for l in &i.incoming_lanes {
+            let queue = match self.queues.get(&Traversable::Lane(*l)) {
+                Some(q) => q,
+                None => continue,
+            };
+            // The farthest along are first, so stop once a vehicle is out of range
+            for entry in queue.get_car_positions(now, &self.cars, &self.queues) {
+                if entry.front < queue.geom_len - DETECTOR_LENGTH {
+                    break;
+                }
+                if let Queued::Vehicle(c) = entry.member {
+                    if let Some(Traversable::Turn(t)) = self.cars[&c].router.maybe_next() {
+                        if t.parent == i.id {
+                            turns.insert(t);
+                        }
+                    }
+                }
+            }
+        }
+        turns
+    }
+
     pub fn find_trips_to_edited_parking(
         &self,
         spots: BTreeSet<ParkingSpot>,
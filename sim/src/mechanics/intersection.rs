@@ -13,6 +13,7 @@ use crate::mechanics::car::{Car, CarState};
 use crate::mechanics::Queue;
 use crate::{
     AgentID, AlertLocation, CarID, Command, DelayCause, Event, Scheduler, SimOptions, Speed,
+    VehicleType,
 };
 
 const WAIT_AT_STOP_SIGN: Duration = Duration::const_seconds(0.5);
@@ -34,6 +35,7 @@ pub(crate) struct IntersectionSimState {
     break_turn_conflict_cycles: bool,
     handle_uber_turns: bool,
     disable_turn_conflicts: bool,
+    actuated_signals: bool,
     // (x, y) means x is blocked by y. It's a many-to-many relationship. TODO Better data
     // structure.
     blocked_by: BTreeSet<(CarID, CarID)>,
@@ -78,6 +80,8 @@ struct SignalState {
     stage_ends_at: Time,
     // The number of times a variable signal has been extended during the current stage.
     extensions_count: usize,
+    // An emergency vehicle wants to make this turn, so the current stage is being cut short.
+    preempt_for: Option<TurnID>,
 }
 
 #[derive(PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Clone, Debug)]
@@ -96,6 +100,7 @@ impl IntersectionSimState {
             break_turn_conflict_cycles: !opts.dont_break_turn_conflict_cycles,
             handle_uber_turns: !opts.dont_handle_uber_turns,
             disable_turn_conflicts: opts.disable_turn_conflicts,
+            actuated_signals: opts.actuated_signals,
             blocked_by: BTreeSet::new(),
             events: Vec::new(),
 
@@ -272,13 +277,28 @@ impl IntersectionSimState {
         }
     }
 
-    /// This is only triggered for traffic signals.
+    /// Should `update_intersection` be told about vehicles approaching this intersection? Only
+    /// variable stages react to them, and only when actuated signals are enabled.
+    pub fn detects_approaching_vehicles(&self, id: IntersectionID, map: &Map) -> bool {
+        self.actuated_signals
+            && map.maybe_get_traffic_signal(id).map_or(false, |signal| {
+                signal
+                    .stages
+                    .iter()
+                    .any(|stage| matches!(stage.stage_type, StageType::Variable(_, _, _)))
+            })
+    }
+
+    /// This is only triggered for traffic signals. `detected` are the turns that vehicles
+    /// approaching the intersection want to make, which actuated (variable) stages use to decide
+    /// whether to extend or skip.
     pub fn update_intersection(
         &mut self,
         now: Time,
         id: IntersectionID,
         map: &Map,
         scheduler: &mut Scheduler,
+        detected: &BTreeSet<TurnID>,
     ) {
         let i = map.get_i(id);
 
@@ -287,19 +307,43 @@ impl IntersectionSimState {
             signal_state: &mut SignalState,
             signal: &ControlTrafficSignal,
             i: &Intersection,
+            demand: &BTreeSet<TurnID>,
+            actuated: bool,
             allow_crosswalk_skip: bool,
         ) -> Duration {
-            signal_state.current_stage = (signal_state.current_stage + 1) % signal.stages.len();
-            let stage = &signal.stages[signal_state.current_stage];
-            // only skip for variable all-walk crosswalk
-            if let StageType::Variable(_, _, _) = stage.stage_type {
-                if allow_crosswalk_skip && stage.max_crosswalk_time(i).is_some() {
-                    // we can skip this stage, as its all walk and we're allowed to skip (no
-                    // pedestrian waiting).
-                    signal_state.current_stage =
-                        (signal_state.current_stage + 1) % signal.stages.len();
+            let num_stages = signal.stages.len();
+            let mut next_stage = (signal_state.current_stage + 1) % num_stages;
+            if !actuated {
+                // only skip for variable all-walk crosswalk
+                let stage = &signal.stages[next_stage];
+                if let StageType::Variable(_, _, _) = stage.stage_type {
+                    if allow_crosswalk_skip && stage.max_crosswalk_time(i).is_some() {
+                        // we can skip this stage, as its all walk and we're allowed to skip (no
+                        // pedestrian waiting).
+                        next_stage = (next_stage + 1) % num_stages;
+                    }
+                }
+                signal_state.current_stage = next_stage;
+                return signal.stages[next_stage].stage_type.simple_duration();
+            }
+            // Variable stages are skipped if nobody wants one of their protected movements. If
+            // there's no demand anywhere, just go to the next stage.
+            for offset in 1..=num_stages {
+                let idx = (signal_state.current_stage + offset) % num_stages;
+                let stage = &signal.stages[idx];
+                if let StageType::Fixed(_) = stage.stage_type {
+                    next_stage = idx;
+                    break;
+                }
+                if demand
+                    .iter()
+                    .any(|t| stage.get_priority_of_turn(*t, i) == TurnPriority::Protected)
+                {
+                    next_stage = idx;
+                    break;
                 }
             }
+            signal_state.current_stage = next_stage;
             signal.stages[signal_state.current_stage]
                 .stage_type
                 .simple_duration()
@@ -307,23 +351,54 @@ impl IntersectionSimState {
         let state = self.state.get_mut(&id).unwrap();
         let signal_state = state.signal.as_mut().unwrap();
         let signal = map.get_traffic_signal(id);
-        let ped_waiting = state.waiting.keys().any(|req| {
-            if let AgentID::Pedestrian(_) = req.agent {
-                return true;
+        // Everyone waiting at the intersection, plus vehicles about to arrive
+        let mut demand: BTreeSet<TurnID> = state.waiting.keys().map(|req| req.turn).collect();
+        demand.extend(detected.iter().cloned());
+        let ped_waiting = state
+            .waiting
+            .keys()
+            .any(|req| matches!(req.agent, AgentID::Pedestrian(_)));
+        if let Some(turn) = signal_state.preempt_for.take() {
+            // An emergency vehicle cut the stage short. Skip straight to the next stage that
+            // protects its turn.
+            assert_eq!(now, signal_state.stage_ends_at);
+            let num_stages = signal.stages.len();
+            if let Some(idx) = (1..num_stages)
+                .map(|offset| (signal_state.current_stage + offset) % num_stages)
+                .find(|idx| {
+                    signal.stages[*idx].get_priority_of_turn(turn, i) == TurnPriority::Protected
+                })
+            {
+                signal_state.current_stage = idx;
             }
-            false
-        });
+            signal_state.extensions_count = 0;
+            signal_state.stage_ends_at = now
+                + signal.stages[signal_state.current_stage]
+                    .stage_type
+                    .simple_duration();
+            scheduler.push(signal_state.stage_ends_at, Command::UpdateIntersection(id));
+            self.wakeup_waiting(now, id, scheduler, map);
+            return;
+        }
+
         let duration: Duration;
         // Switch to a new stage?
         assert_eq!(now, signal_state.stage_ends_at);
         let old_stage = &signal.stages[signal_state.current_stage];
         match old_stage.stage_type {
             StageType::Fixed(_) => {
-                duration = advance(signal_state, signal, i, !ped_waiting);
+                duration = advance(
+                    signal_state,
+                    signal,
+                    i,
+                    &demand,
+                    self.actuated_signals,
+                    !ped_waiting,
+                );
             }
             StageType::Variable(min, delay, additional) => {
-                // test if anyone is waiting in current stage, and if so, extend the signal cycle.
-                // Filter out pedestrians, as they've had their chance and the delay
+                // test if anyone is waiting or approaching in current stage, and if so, extend the
+                // signal cycle. Filter out pedestrians, as they've had their chance and the delay
                 // could be short enough to keep them on the curb.
                 let delay = std::cmp::max(Duration::const_seconds(1.0), delay);
                 // Only extend for the fixed additional time
@@ -337,18 +412,36 @@ impl IntersectionSimState {
                             min, delay, additional, signal_state.extensions_count
                         ),
                     ));
-                    duration = advance(signal_state, signal, i, !ped_waiting);
+                    duration = advance(
+                        signal_state,
+                        signal,
+                        i,
+                        &demand,
+                        self.actuated_signals,
+                        !ped_waiting,
+                    );
                     signal_state.extensions_count = 0;
-                } else if state.waiting.keys().all(|req| {
-                    if let AgentID::Pedestrian(_) = req.agent {
-                        return true;
-                    }
-                    // Should we only allow protected to extend or any not banned?
-                    // currently only the protected demand control extended.
-                    old_stage.get_priority_of_turn(req.turn, i) != TurnPriority::Protected
-                }) {
+                } else if state
+                    .waiting
+                    .keys()
+                    .filter(|req| !matches!(req.agent, AgentID::Pedestrian(_)))
+                    .map(|req| &req.turn)
+                    .chain(detected.iter())
+                    .all(|t| {
+                        // Should we only allow protected to extend or any not banned?
+                        // currently only the protected demand control extended.
+                        old_stage.get_priority_of_turn(*t, i) != TurnPriority::Protected
+                    })
+                {
                     signal_state.extensions_count = 0;
-                    duration = advance(signal_state, signal, i, !ped_waiting);
+                    duration = advance(
+                        signal_state,
+                        signal,
+                        i,
+                        &demand,
+                        self.actuated_signals,
+                        !ped_waiting,
+                    );
                 } else {
                     signal_state.extensions_count += 1;
                     duration = delay;
@@ -368,6 +461,57 @@ impl IntersectionSimState {
         self.wakeup_waiting(now, id, scheduler, map);
     }
 
+    /// An emergency vehicle approaching or waiting at a traffic signal cuts the current stage
+    /// short, so the signal switches right away to the next stage protecting its turn.
+    pub fn preempt_signal(
+        &mut self,
+        now: Time,
+        car: &Car,
+        turn: TurnID,
+        map: &Map,
+        scheduler: &mut Scheduler,
+    ) {
+        if self.use_freeform_policy_everywhere {
+            return;
+        }
+        let signal = match map.maybe_get_traffic_signal(turn.parent) {
+            Some(signal) => signal,
+            None => {
+                return;
+            }
+        };
+        let i = map.get_i(turn.parent);
+        let signal_state = self
+            .state
+            .get_mut(&turn.parent)
+            .unwrap()
+            .signal
+            .as_mut()
+            .unwrap();
+        if signal_state.preempt_for.is_some()
+            || signal.stages[signal_state.current_stage].get_priority_of_turn(turn, i)
+                == TurnPriority::Protected
+            || signal
+                .stages
+                .iter()
+                .all(|stage| stage.get_priority_of_turn(turn, i) != TurnPriority::Protected)
+        {
+            return;
+        }
+
+        signal_state.preempt_for = Some(turn);
+        // update_intersection will pick the new stage. Don't do it immediately, since the
+        // emergency vehicle might be in the middle of asking to start its turn.
+        signal_state.stage_ends_at = now + Duration::EPSILON;
+        scheduler.update(
+            signal_state.stage_ends_at,
+            Command::UpdateIntersection(turn.parent),
+        );
+        if let Some((trip, _)) = car.trip_and_person {
+            self.events.push(Event::SignalPreempted(trip, turn.parent));
+        }
+    }
+
     /// For cars: The head car calls this when they're at the end of the lane WaitingToAdvance. If
     /// this returns true, then the head car MUST actually start this turn.
     /// For peds: Likewise -- only called when the ped is at the start of the turn. They must
@@ -415,6 +559,12 @@ impl IntersectionSimState {
         let shared_sidewalk_corner =
             map.get_t(req.turn).turn_type == TurnType::SharedSidewalkCorner;
 
+        if let Some((car, _, _)) = maybe_cars_and_queues.as_ref() {
+            if car.vehicle.vehicle_type == VehicleType::Emergency {
+                self.preempt_signal(now, car, turn, map, scheduler);
+            }
+        }
+
         let readonly_pair = maybe_cars_and_queues.as_ref().map(|(_, c, q)| (*c, &**q));
         let started_uber_turn = |state: &Self, car: &Car| {
             state.handle_uber_turns && car.router.get_path().currently_inside_ut().is_some()
@@ -445,13 +595,17 @@ impl IntersectionSimState {
             }
 
             true
+        } else if self.must_yield_to_emergency_vehicle(&req, map) {
+            // Everybody waits for an emergency vehicle to go first. When it finishes its turn, the
+            // others waiting here get woken up.
+            false
         } else if self.use_freeform_policy_everywhere {
             // If we made it this far, we don't conflict with an accepted turn
             true
         } else if let Some(signal) = map.maybe_get_traffic_signal(turn.parent) {
             self.traffic_signal_policy(&req, map, signal, speed, now, Some(scheduler))
         } else if let Some(sign) = map.maybe_get_stop_sign(turn.parent) {
-            self.stop_sign_policy(&req, map, sign, now, scheduler)
+            self.stop_sign_policy(&req, map, sign, speed, now, scheduler)
         } else {
             unreachable!()
         };
@@ -511,6 +665,8 @@ impl IntersectionSimState {
                 && (car.router.get_path().currently_inside_ut().is_some()
                     || car.router.get_path().about_to_start_ut().is_some());
             let queue = queues.get_mut(&Traversable::Lane(turn.dst)).unwrap();
+            // If the car enters without room, the queue will spill back into this intersection.
+            let spills_back = !queue.room_for_car(car);
             if !queue.try_to_reserve_entry(
                 car,
                 !self.dont_block_the_box
@@ -554,6 +710,10 @@ impl IntersectionSimState {
                     return false;
                 }
             }
+            if spills_back {
+                self.events
+                    .push(Event::QueueSpillback(turn.parent, turn.dst, car.vehicle.id));
+            }
         }
 
         // TODO For now, we're only interested in signals, and there's too much raw data to store
@@ -808,6 +968,7 @@ impl IntersectionSimState {
         req: &Request,
         map: &Map,
         sign: &ControlStopSign,
+        speed: Speed,
         now: Time,
         scheduler: &mut Scheduler,
     ) -> bool {
@@ -815,13 +976,30 @@ impl IntersectionSimState {
         assert!(our_priority != TurnPriority::Banned);
         let (our_time, _) = self.state[&req.turn.parent].waiting[req];
 
-        if our_priority == TurnPriority::Yield && now < our_time + WAIT_AT_STOP_SIGN {
+        // Entering a roundabout, don't stop. Just give way to anybody waiting to make a
+        // conflicting turn with priority, like vehicles already circulating. When they finish
+        // their turn, the others waiting here get woken up.
+        if sign.yields_without_stopping(req.turn, map) {
+            let turn = map.get_t(req.turn);
+            return !self.state[&req.turn.parent].waiting.keys().any(|other| {
+                other != req
+                    && sign.get_priority(other.turn, map) == TurnPriority::Protected
+                    && map.get_t(other.turn).conflicts_with(turn)
+            });
+        }
+        // Pedestrians at an unmarked crossing wait for a gap in traffic. This is a deterministic
+        // approximation: everybody waits the expected time for a gap, estimated from the road
+        // type, no matter which vehicles are actually approaching.
+        let wait = WAIT_AT_STOP_SIGN.max(map.get_t(req.turn).unmarked_crossing_delay(map, speed));
+
+        // Emergency vehicles don't come to a full stop
+        if our_priority == TurnPriority::Yield
+            && !is_emergency_vehicle(req.agent)
+            && now < our_time + wait
+        {
             // Since we have "ownership" of scheduling for req.agent, don't need to use
             // scheduler.update.
-            scheduler.push(
-                our_time + WAIT_AT_STOP_SIGN,
-                Command::update_agent(req.agent),
-            );
+            scheduler.push(our_time + wait, Command::update_agent(req.agent));
             return false;
         }
 
@@ -844,6 +1022,16 @@ impl IntersectionSimState {
         true
     }
 
+    fn must_yield_to_emergency_vehicle(&self, req: &Request, map: &Map) -> bool {
+        if is_emergency_vehicle(req.agent) {
+            return false;
+        }
+        let turn = map.get_t(req.turn);
+        self.state[&req.turn.parent].waiting.keys().any(|other| {
+            is_emergency_vehicle(other.agent) && map.get_t(other.turn).conflicts_with(turn)
+        })
+    }
+
     fn traffic_signal_policy(
         &mut self,
         req: &Request,
@@ -1038,6 +1226,7 @@ impl SignalState {
             current_stage: 0,
             stage_ends_at: now,
             extensions_count: 0,
+            preempt_for: None,
         };
 
         let signal = map.get_traffic_signal(id);
@@ -1102,3 +1291,7 @@ fn allow_block_the_box(i: &Intersection) -> bool {
     }
     false
 }
+
+fn is_emergency_vehicle(agent: AgentID) -> bool {
+    matches!(agent, AgentID::Car(c) if c.vehicle_type == VehicleType::Emergency)
+}
@@ -12,7 +12,7 @@ use abstutil::{
 use geom::{Distance, PolyLine, Pt2D};
 use map_model::{
     BuildingID, Lane, LaneID, LaneType, Map, OffstreetParking, ParkingLotID, PathConstraints,
-    PathStep, Position, Traversable, TurnID,
+    PathStep, Position, Traversable, TurnID, PARKING_LOT_SPOT_LENGTH,
 };
 
 use crate::{CarID, CarStatus, DrawCarInput, Event, ParkedCar, ParkingSpot, PersonID, Vehicle};
@@ -477,13 +477,16 @@ impl ParkingSim for NormalParkingSimState {
             }
         }
 
-        for pl in self.driving_to_lots.get(driving_pos.lane()) {
-            let lot_dist = map.get_pl(*pl).driving_pos.dist_along();
-            if driving_pos.dist_along() < lot_dist {
-                for idx in 0..self.num_spots_per_lot[pl] {
-                    let spot = ParkingSpot::Lot(*pl, idx);
-                    if self.is_free(spot) {
-                        candidates.push(spot);
+        // Trucks don't fit in parking lot spots
+        if vehicle.length <= PARKING_LOT_SPOT_LENGTH {
+            for pl in self.driving_to_lots.get(driving_pos.lane()) {
+                let lot_dist = map.get_pl(*pl).driving_pos.dist_along();
+                if driving_pos.dist_along() < lot_dist {
+                    for idx in 0..self.num_spots_per_lot[pl] {
+                        let spot = ParkingSpot::Lot(*pl, idx);
+                        if self.is_free(spot) {
+                            candidates.push(spot);
+                        }
                     }
                 }
             }
@@ -10,12 +10,13 @@ pub use model::{Cmd, PandemicModel};
 use rand::Rng;
 use rand_distr::{Distribution, Exp, Normal};
 use rand_xorshift::XorShiftRng;
+use serde::{Deserialize, Serialize};
 
 use geom::{Duration, Time};
 
 mod model;
 
-#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub struct AnyTime(f64);
 
 impl AnyTime {
@@ -76,7 +77,7 @@ impl From<f64> for AnyTime {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum StateEvent {
     Exposition,
     Incubation,
@@ -85,7 +86,7 @@ pub enum StateEvent {
     Death,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Event {
     s: StateEvent,
     p_hosp: f64,  // probability of people being hospitalized after infection
@@ -157,7 +158,7 @@ impl Event {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum State {
     Sane((Event, Time)),
     Exposed((Event, Time)),
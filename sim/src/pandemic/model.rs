@@ -8,13 +8,13 @@ use geom::{Duration, Time};
 use map_model::{BuildingID, BusStopID};
 
 use crate::pandemic::{AnyTime, State};
-use crate::{CarID, Event, Person, PersonID, Scheduler, TripPhaseType};
+use crate::{CarID, Event, ExposureLocation, Person, PersonID, Scheduler, TripPhaseType};
 
 // TODO This does not model transmission by surfaces; only person-to-person.
 // TODO If two people are in the same shared space indefinitely and neither leaves, we don't model
 // transmission. It only occurs when people leave a space.
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct PandemicModel {
     pop: BTreeMap<PersonID, State>,
 
@@ -25,6 +25,7 @@ pub struct PandemicModel {
 
     rng: XorShiftRng,
     initialized: bool,
+    events: Vec<Event>,
 }
 
 // You can schedule callbacks in the future by doing scheduler.push(future time, one of these)
@@ -54,6 +55,7 @@ impl PandemicModel {
 
             rng,
             initialized: false,
+            events: Vec::new(),
         }
     }
 
@@ -147,7 +149,13 @@ impl PandemicModel {
             }
             Event::PersonLeavesBuilding(person, bldg) => {
                 if let Some(others) = self.bldgs.person_leaves_space(now, *person, *bldg) {
-                    self.transmission(now, *person, others, scheduler);
+                    self.transmission(
+                        now,
+                        *person,
+                        others,
+                        ExposureLocation::Building(*bldg),
+                        scheduler,
+                    );
                 } else {
                     panic!("{} left {}, but they weren't inside", person, bldg);
                 }
@@ -163,7 +171,13 @@ impl PandemicModel {
                             .bus_stops
                             .person_leaves_space(now, person, *stop)
                             .unwrap();
-                        self.transmission(now, person, others, scheduler);
+                        self.transmission(
+                            now,
+                            person,
+                            others,
+                            ExposureLocation::BusStop(*stop),
+                            scheduler,
+                        );
 
                         self.buses.person_enters_space(now, person, *bus);
                         self.person_to_bus.insert(person, *bus);
@@ -174,7 +188,13 @@ impl PandemicModel {
                         // of a bus ride.
                         if let Some(car) = self.person_to_bus.remove(&person) {
                             let others = self.buses.person_leaves_space(now, person, car).unwrap();
-                            self.transmission(now, person, others, scheduler);
+                            self.transmission(
+                                now,
+                                person,
+                                others,
+                                ExposureLocation::Bus(car),
+                                scheduler,
+                            );
                         }
                     }
                     _ => {
@@ -186,6 +206,10 @@ impl PandemicModel {
         }
     }
 
+    pub(crate) fn collect_events(&mut self) -> Vec<Event> {
+        std::mem::take(&mut self.events)
+    }
+
     pub(crate) fn handle_cmd(&mut self, _now: Time, cmd: Cmd, _scheduler: &mut Scheduler) {
         assert!(self.initialized);
 
@@ -244,11 +268,16 @@ impl PandemicModel {
         }
     }
 
-    fn infectious_contact(&self, person: PersonID, other: PersonID) -> Option<PersonID> {
+    // Returns (the person who might become exposed, the infectious person)
+    fn infectious_contact(
+        &self,
+        person: PersonID,
+        other: PersonID,
+    ) -> Option<(PersonID, PersonID)> {
         if self.is_sane(person) && self.is_infectious(other) {
-            return Some(person);
+            return Some((person, other));
         } else if self.is_infectious(person) && self.is_sane(other) {
-            return Some(other);
+            return Some((other, person));
         }
         None
     }
@@ -258,13 +287,19 @@ impl PandemicModel {
         now: Time,
         person: PersonID,
         other_occupants: Vec<(PersonID, Duration)>,
+        location: ExposureLocation,
         scheduler: &mut Scheduler,
     ) {
         // person has spent some duration in the same space as other people. Does transmission
         // occur?
         for (other, overlap) in other_occupants {
-            if let Some(pid) = self.infectious_contact(person, other) {
+            if let Some((pid, source)) = self.infectious_contact(person, other) {
                 self.become_exposed(now, overlap, pid, scheduler);
+                // Exposure depends on how long they overlapped, so it might not happen
+                if !self.is_sane(pid) {
+                    self.events
+                        .push(Event::PersonExposed(pid, source, location));
+                }
             }
         }
     }
@@ -311,7 +346,7 @@ impl PandemicModel {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 struct SharedSpace<T: Ord> {
     // Since when has a person been in some shared space?
     // TODO This is an awkward data structure; abstutil::MultiMap is also bad, because key removal
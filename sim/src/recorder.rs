@@ -5,7 +5,7 @@ use map_model::{IntersectionID, Map, PathStep, Position, Traversable};
 
 use crate::{
     AgentID, DrivingSimState, Event, IndividTrip, PersonSpec, Scenario, TripEndpoint, TripID,
-    TripMode, TripPurpose, VehicleType,
+    TripMode, TripPurpose, VehicleType, Weather,
 };
 
 /// Records trips beginning and ending at a specified set of intersections. This can be used to
@@ -78,6 +78,10 @@ impl TrafficRecorder {
             map_name: map.get_name().clone(),
             people,
             only_seed_buses: None,
+            driver_behavior: None,
+            weather: Weather::Clear,
+            curbside_dwell: None,
+            day_to_day: None,
         }
         .save();
     }
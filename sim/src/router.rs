@@ -3,9 +3,11 @@
 
 use std::collections::HashMap;
 
+use rand::{Rng, SeedableRng};
+use rand_xorshift::XorShiftRng;
 use serde::{Deserialize, Serialize};
 
-use geom::Distance;
+use geom::{Distance, Duration};
 use map_model::{
     BuildingID, IntersectionID, LaneID, Map, Path, PathConstraints, PathRequest, PathStep,
     Position, Traversable, Turn, TurnID,
@@ -17,6 +19,12 @@ use crate::{
     TripID, TripPhaseType, Vehicle, VehicleType,
 };
 
+/// When cruising for parking, give up after trying this many lanes and head for the nearest free
+/// spot instead.
+const MAX_CRUISING_LANES: usize = 30;
+/// A free on-street parking spot this close to a curbside stop can be used as a loading zone.
+const LOADING_ZONE_DIST: Distance = Distance::const_meters(30.0);
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub(crate) struct Router {
     /// Front is always the current step
@@ -33,6 +41,8 @@ pub(crate) enum ActionAtEnd {
     StopBiking(SidewalkSpot),
     BusAtStop,
     GiveUpOnParking,
+    /// Pull into the loading zone for this long, or double-park in the lane if there's none.
+    StopCurbside(Option<ParkingSpot>, Duration),
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
@@ -44,6 +54,8 @@ enum Goal {
         /// No parking available at all!
         stuck_end_dist: Option<Distance>,
         started_looking: bool,
+        /// When cruising for parking, every lane tried so far
+        cruised: Vec<LaneID>,
     },
     EndAtBorder {
         end_dist: Distance,
@@ -55,6 +67,15 @@ enum Goal {
     FollowBusRoute {
         end_dist: Distance,
     },
+    StopCurbside {
+        target: BuildingID,
+        /// Where to double-park, in front of the building
+        end_dist: Distance,
+        dwell: Duration,
+        /// A free spot and cached distance along the last driving lane
+        loading_zone: Option<(ParkingSpot, Distance)>,
+        looked_for_loading_zone: bool,
+    },
 }
 
 impl Router {
@@ -93,6 +114,7 @@ impl Router {
                 spot: None,
                 stuck_end_dist: None,
                 started_looking: false,
+                cruised: Vec::new(),
             },
             owner,
         }
@@ -116,6 +138,20 @@ impl Router {
         }
     }
 
+    pub fn stop_curbside(owner: CarID, path: Path, bldg: BuildingID, dwell: Duration) -> Router {
+        Router {
+            goal: Goal::StopCurbside {
+                target: bldg,
+                end_dist: path.get_req().end.dist_along(),
+                dwell,
+                loading_zone: None,
+                looked_for_loading_zone: false,
+            },
+            path,
+            owner,
+        }
+    }
+
     pub fn head(&self) -> Traversable {
         self.path.current_step().as_traversable()
     }
@@ -144,6 +180,11 @@ impl Router {
             } => stuck_end_dist.unwrap_or_else(|| spot.unwrap().1),
             Goal::BikeThenStop { ref goal } => goal.sidewalk_pos.dist_along(),
             Goal::FollowBusRoute { end_dist } => end_dist,
+            Goal::StopCurbside {
+                end_dist,
+                loading_zone,
+                ..
+            } => loading_zone.map(|(_, dist)| dist).unwrap_or(end_dist),
         }
     }
 
@@ -158,6 +199,7 @@ impl Router {
         parking: &ParkingSimState,
         map: &Map,
         trip_and_person: Option<(TripID, PersonID)>,
+        cruise_for_parking: bool,
         events: &mut Vec<Event>,
     ) -> Traversable {
         let prev = self.path.shift(map).as_traversable();
@@ -169,6 +211,7 @@ impl Router {
                 parking,
                 map,
                 trip_and_person,
+                cruise_for_parking,
                 events,
             );
         }
@@ -197,6 +240,7 @@ impl Router {
         map: &Map,
         // TODO Not so nice to plumb all of this here
         trip_and_person: Option<(TripID, PersonID)>,
+        cruise_for_parking: bool,
         events: &mut Vec<Event>,
     ) -> Option<ActionAtEnd> {
         assert!(self.path.is_last_step());
@@ -214,6 +258,7 @@ impl Router {
                 ref mut stuck_end_dist,
                 target,
                 ref mut started_looking,
+                ref mut cruised,
             } => {
                 if let Some(d) = stuck_end_dist {
                     if *d == front {
@@ -273,6 +318,27 @@ impl Router {
                         assert!(new_pos.dist_along() >= front);
                         *spot = Some((new_spot, new_pos.dist_along()));
                     } else {
+                        // Drive around the block, without knowing where free spots are. Once the
+                        // car reaches the next lane, this is called again to check it.
+                        if cruise_for_parking && cruised.len() < MAX_CRUISING_LANES {
+                            if cruised.is_empty() {
+                                cruised.push(current_lane);
+                            }
+                            if let Some(turn) =
+                                next_cruising_turn(vehicle, current_lane, target, cruised, map)
+                            {
+                                cruised.push(turn.dst);
+                                self.path.add(PathStep::Turn(turn), map);
+                                self.path.add(PathStep::Lane(turn.dst), map);
+                                *spot = None;
+                                events.push(Event::PathAmended(self.path.clone()));
+                                if let Some((t, _)) = trip_and_person {
+                                    events.push(Event::CruisingForParking(t));
+                                }
+                                return Some(ActionAtEnd::GotoLaneEnd);
+                            }
+                        }
+
                         if let Some((new_path_steps, new_spot, new_pos)) =
                             parking.path_to_free_parking_spot(current_lane, vehicle, target, map)
                         {
@@ -332,6 +398,52 @@ impl Router {
                     None
                 }
             }
+            Goal::StopCurbside {
+                target,
+                ref mut end_dist,
+                dwell,
+                ref mut loading_zone,
+                ref mut looked_for_loading_zone,
+            } => {
+                if !*looked_for_loading_zone {
+                    *looked_for_loading_zone = true;
+                    let current_lane = self.path.current_step().as_lane();
+                    let stop_dist = *end_dist;
+                    *loading_zone = parking
+                        .get_all_free_spots(
+                            Position::new(current_lane, front),
+                            vehicle,
+                            target,
+                            map,
+                        )
+                        .into_iter()
+                        .filter(|(spot, pos)| {
+                            matches!(spot, ParkingSpot::Onstreet(_, _))
+                                && (pos.dist_along() - stop_dist).abs() <= LOADING_ZONE_DIST
+                        })
+                        .min_by_key(|(_, pos)| (pos.dist_along() - stop_dist).abs())
+                        .map(|(spot, pos)| (spot, pos.dist_along()));
+                }
+
+                // Somebody else might take the spot first. If we've already passed the building,
+                // just double-park here.
+                if let Some((spot, _)) = *loading_zone {
+                    if !parking.is_free(spot) {
+                        *loading_zone = None;
+                        *end_dist = (*end_dist).max(front);
+                    }
+                }
+
+                let stop_dist = loading_zone.map(|(_, dist)| dist).unwrap_or(*end_dist);
+                if stop_dist == front {
+                    Some(ActionAtEnd::StopCurbside(
+                        loading_zone.map(|(spot, _)| spot),
+                        dwell,
+                    ))
+                } else {
+                    None
+                }
+            }
         }
     }
 
@@ -506,6 +618,10 @@ impl Router {
         }
     }
 
+    pub fn is_stopping_curbside(&self) -> bool {
+        matches!(self.goal, Goal::StopCurbside { .. })
+    }
+
     pub fn get_parking_spot_goal(&self) -> Option<&ParkingSpot> {
         match self.goal {
             Goal::ParkNearBuilding { ref spot, .. } => spot.as_ref().map(|(s, _)| s),
@@ -513,3 +629,33 @@ impl Router {
         }
     }
 }
+
+/// Drivers cruising for parking don't know where free spots are. At each intersection, they prefer
+/// lanes they haven't tried yet, then lanes close to their destination.
+fn next_cruising_turn(
+    vehicle: &Vehicle,
+    current: LaneID,
+    target: BuildingID,
+    cruised: &[LaneID],
+    map: &Map,
+) -> Option<TurnID> {
+    let goal_pt = map.get_b(target).polygon.center();
+    // Deterministic, but different between cars, so everybody searching the same area doesn't
+    // make the same choices
+    let mut rng =
+        XorShiftRng::seed_from_u64((vehicle.id.id + current.encode_u32() as usize) as u64);
+    map.get_turns_for(current, vehicle.vehicle_type.to_constraints())
+        .into_iter()
+        .filter(|turn| !map.get_l(turn.id.dst).driving_blackhole)
+        .map(|turn| {
+            let dist = map
+                .get_l(turn.id.dst)
+                .lane_center_pts
+                .middle()
+                .dist_to(goal_pt);
+            let jitter = rng.gen_range(0.8..1.2);
+            ((cruised.contains(&turn.id.dst), dist * jitter), turn.id)
+        })
+        .min_by_key(|(key, _)| *key)
+        .map(|(_, t)| t)
+}
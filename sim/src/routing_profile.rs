@@ -0,0 +1,109 @@
+use anyhow::Result;
+use rand::Rng;
+use rand_xorshift::XorShiftRng;
+use serde::{Deserialize, Serialize};
+
+use geom::Time;
+use map_model::{CongestionProfile, Map, Path, PathConstraints, PathRequest, RoutingParams};
+
+/// How somebody trades off travel time against other things when choosing a route to drive or
+/// bike. Walking routes aren't affected.
+///
+/// Everybody uses `Default` unless `SimOptions::enable_routing_profiles` is set. Each other profile
+/// needs its own pathfinder, built the first time it's used, so enabling this makes the start of a
+/// simulation slower.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RoutingProfile {
+    /// Use the map's usual routing parameters
+    Default,
+    /// A driver who'd rather go around the block than wait for a gap at an unprotected turn
+    AvoidUnprotectedTurns,
+    /// A driver who stays off arterials and highways when there's a reasonable local route
+    AvoidMainRoads,
+    /// A cyclist who goes out of their way to use bike lanes and calm streets
+    PreferBikeInfrastructure,
+}
+
+impl RoutingProfile {
+    /// Pick a profile for somebody, based on what vehicles they own. Most people keep the default.
+    pub(crate) fn sample(rng: &mut XorShiftRng, has_car: bool, has_bike: bool) -> RoutingProfile {
+        // TODO These proportions are a total guess. Ideally they'd come from a travel survey.
+        let x: f64 = rng.gen();
+        if has_bike && !has_car {
+            if x < 0.5 {
+                RoutingProfile::PreferBikeInfrastructure
+            } else {
+                RoutingProfile::Default
+            }
+        } else if has_car {
+            if x < 0.15 {
+                RoutingProfile::AvoidUnprotectedTurns
+            } else if x < 0.3 {
+                RoutingProfile::AvoidMainRoads
+            } else {
+                RoutingProfile::Default
+            }
+        } else {
+            RoutingProfile::Default
+        }
+    }
+
+    /// Adjusts the map's routing parameters for this profile. None means to use the map's
+    /// parameters unchanged.
+    pub fn routing_params(self, map: &Map) -> Option<RoutingParams> {
+        let mut params = map.routing_params().clone();
+        match self {
+            RoutingProfile::Default => {
+                return None;
+            }
+            RoutingProfile::AvoidUnprotectedTurns => {
+                params.unprotected_turn_penalty = params.unprotected_turn_penalty * 4.0;
+            }
+            RoutingProfile::AvoidMainRoads => {
+                params.main_road_penalty *= 2.0;
+            }
+            RoutingProfile::PreferBikeInfrastructure => {
+                params.driving_lane_penalty *= 2.0;
+                params.bus_lane_penalty *= 1.5;
+                params.avoid_high_stress *= 2.0;
+            }
+        }
+        Some(params)
+    }
+
+    /// Calculate a route for a vehicle departing now. The profiles only make sense for some
+    /// vehicles, so otherwise this uses the map's usual parameters. Cars also account for expected
+    /// congestion, if it's known.
+    pub(crate) fn pathfind(
+        self,
+        req: PathRequest,
+        congestion: Option<&CongestionProfile>,
+        now: Time,
+        map: &Map,
+    ) -> Result<Path> {
+        let applies = match self {
+            RoutingProfile::Default => false,
+            RoutingProfile::AvoidUnprotectedTurns | RoutingProfile::AvoidMainRoads => {
+                req.constraints == PathConstraints::Car
+            }
+            RoutingProfile::PreferBikeInfrastructure => req.constraints == PathConstraints::Bike,
+        };
+        let mut params = if applies {
+            self.routing_params(map)
+        } else {
+            None
+        };
+        if let Some(congestion) = congestion {
+            if req.constraints == PathConstraints::Car {
+                let base = params.as_ref().unwrap_or_else(|| map.routing_params());
+                if let Some(congested) = congestion.routing_params(base, now) {
+                    params = Some(congested);
+                }
+            }
+        }
+        match params {
+            Some(params) => map.pathfind_with_params(req, &params, true),
+            None => map.pathfind(req),
+        }
+    }
+}
@@ -25,8 +25,11 @@ pub(crate) enum Command {
     UpdateIntersection(IntersectionID),
     Callback(Duration),
     Pandemic(pandemic::Cmd),
-    /// The Time is redundant, just used to dedupe commands
+    /// The Time is when the vehicle is scheduled to depart from the first stop. It also dedupes
+    /// commands.
     StartBus(BusRouteID, Time),
+    /// A delivery or ride-hail vehicle waiting in a loading zone is done with its stop.
+    FinishCurbsideStop(CarID),
 }
 
 impl Command {
@@ -50,6 +53,7 @@ impl Command {
             Command::Callback(_) => CommandType::Callback,
             Command::Pandemic(ref p) => CommandType::Pandemic(p.clone()),
             Command::StartBus(r, t) => CommandType::StartBus(*r, *t),
+            Command::FinishCurbsideStop(id) => CommandType::CurbsideStop(*id),
         }
     }
 
@@ -65,6 +69,7 @@ impl Command {
             Command::Callback(_) => SimpleCommandType::Callback,
             Command::Pandemic(_) => SimpleCommandType::Pandemic,
             Command::StartBus(_, _) => SimpleCommandType::StartBus,
+            Command::FinishCurbsideStop(_) => SimpleCommandType::CurbsideStop,
         }
     }
 }
@@ -81,6 +86,7 @@ enum CommandType {
     Callback,
     Pandemic(pandemic::Cmd),
     StartBus(BusRouteID, Time),
+    CurbsideStop(CarID),
 }
 
 /// A more compressed form of CommandType, just used for keeping stats on event processing.
@@ -94,6 +100,7 @@ enum SimpleCommandType {
     Callback,
     Pandemic,
     StartBus,
+    CurbsideStop,
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Eq, Clone)]
@@ -1,6 +1,6 @@
 // This file has a jumbled mess of queries, setup, and mutating methods.
 
-use std::collections::{BTreeSet, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 
 use anyhow::Result;
 use instant::Instant;
@@ -11,21 +11,24 @@ use structopt::StructOpt;
 
 use abstio::{CityName, MapName};
 use abstutil::{prettyprint_usize, serialized_size_bytes, Timer};
-use geom::{Distance, Duration, Speed, Time};
+use geom::{Distance, Duration, Pt2D, Speed, Time};
 use map_model::{
-    BuildingID, BusRoute, IntersectionID, LaneID, Map, ParkingLotID, Path, PathConstraints,
-    PathRequest, Position, Traversable,
+    BuildingID, BusRoute, CongestionProfile, IntersectionID, LaneID, Map, ParkingLotID, Path,
+    PathConstraints, PathRequest, Position, Traversable,
 };
 
+pub use self::parallel::ParallelSim;
 pub use self::queries::{AgentProperties, DelayCause};
 use crate::{
-    AgentID, AlertLocation, Analytics, CarID, Command, CreateCar, DrivingSimState, Event,
-    IntersectionSimState, OrigPersonID, PandemicModel, ParkedCar, ParkingSim, ParkingSimState,
-    ParkingSpot, Person, PersonID, Router, Scheduler, SidewalkPOI, SidewalkSpot, StartTripArgs,
-    TrafficRecorder, TransitSimState, TripID, TripInfo, TripManager, TripPhaseType, Vehicle,
-    VehicleSpec, VehicleType, WalkingSimState, BUS_LENGTH, LIGHT_RAIL_LENGTH, MIN_CAR_LENGTH,
+    AgentID, AgentTraces, AlertLocation, Analytics, CarID, Command, CreateCar, DetailedAnalytics,
+    DriverBehavior, DrivingSimState, Event, IntersectionSimState, OrigPersonID, PandemicModel,
+    ParkedCar, ParkingSim, ParkingSimState, ParkingSpot, Person, PersonID, Router, Scheduler,
+    SidewalkPOI, SidewalkSpot, StartTripArgs, TrafficRecorder, TransitSimState, TripID, TripInfo,
+    TripManager, TripPhaseType, Vehicle, VehicleSpec, VehicleType, WalkingSimState, Weather,
+    BUS_LENGTH, LIGHT_RAIL_LENGTH, MIN_CAR_LENGTH,
 };
 
+mod parallel;
 mod queries;
 
 // TODO Do something else.
@@ -51,6 +54,7 @@ pub struct Sim {
     // Some tests deliberately set different scenario names for comparisons.
     // TODO Maybe get rid of this, now that savestates aren't used
     run_name: String,
+    weather: Weather,
     step_count: usize,
     highlighted_people: Option<BTreeSet<PersonID>>,
 
@@ -58,6 +62,9 @@ pub struct Sim {
     // This is created interactively, and there's no reason to preserve one for savestates.
     #[serde(skip_serializing, skip_deserializing)]
     recorder: Option<TrafficRecorder>,
+    // Also created interactively, for debugging or external visualization.
+    #[serde(skip_serializing, skip_deserializing)]
+    traces: AgentTraces,
 
     #[serde(skip_serializing, skip_deserializing)]
     alerts: AlertHandler,
@@ -92,6 +99,12 @@ pub struct SimOptions {
     /// based on some score of "least-loaded" lane. Disable this default behavior.
     #[structopt(long)]
     pub dont_recalc_lanechanging: bool,
+    /// Normally a vehicle stuck in a queue will change lanes partway along a road, if an adjacent
+    /// lane leading to the same turn has a much shorter queue and there's a gap to merge into.
+    /// Disable this default behavior, to compare against vehicles staying in their lane until the
+    /// next intersection.
+    #[structopt(long)]
+    pub dont_change_lanes_midblock: bool,
     /// Normally if a cycle of vehicles depending on each other to turn is detected, temporarily allow
     /// "blocking the box" to try to break gridlock. Disable this default behavior.
     #[structopt(long)]
@@ -105,6 +118,16 @@ pub struct SimOptions {
     /// same or different from the one used for the rest of the simulation.
     #[structopt(long, parse(try_from_str = parse_rng))]
     pub enable_pandemic_model: Option<XorShiftRng>,
+    /// Give some people a routing profile, so a few drivers avoid unprotected turns or main roads,
+    /// and some cyclists prefer bike lanes and calm streets. This requires an RNG seed, which is
+    /// only used to pick profiles.
+    #[structopt(long, parse(try_from_str = parse_rng))]
+    pub enable_routing_profiles: Option<XorShiftRng>,
+    /// Route vehicles using the congestion measured in a previous simulation, so the cost of each
+    /// road depends on when the vehicle departs. This is the path to a binary `CongestionProfile`,
+    /// which can be produced from `Analytics::road_travel_times`.
+    #[structopt(long, parse(try_from_str = parse_congestion_profile))]
+    pub route_with_congestion: Option<CongestionProfile>,
     /// When a warning is encountered during simulation, specifies how to respond.
     #[structopt(long, parse(try_from_str = parse_alert_handler), default_value = "print")]
     pub alerts: AlertHandler,
@@ -123,6 +146,18 @@ pub struct SimOptions {
     /// quickly.
     #[structopt(long)]
     pub skip_analytics: bool,
+    /// Normally drivers who don't find parking along their destination's road head straight for
+    /// the nearest free spot, as if they knew where it was. Instead, make them cruise around nearby
+    /// blocks until they happen to pass a free spot.
+    #[structopt(long)]
+    pub cruise_for_parking: bool,
+    /// Normally variable stages at traffic signals are only skipped when they're all-walk and no
+    /// pedestrian is waiting. Instead, detect vehicles approaching signals with variable stages,
+    /// skip any variable stage that nobody waiting or approaching wants, and keep extending a stage
+    /// while vehicles keep arriving for it. This is meant for signals made by the actuated
+    /// heuristic.
+    #[structopt(long)]
+    pub actuated_signals: bool,
 }
 
 impl SimOptions {
@@ -132,13 +167,18 @@ impl SimOptions {
             use_freeform_policy_everywhere: false,
             allow_block_the_box: false,
             dont_recalc_lanechanging: false,
+            dont_change_lanes_midblock: false,
             dont_break_turn_conflict_cycles: false,
             dont_handle_uber_turns: false,
             enable_pandemic_model: None,
+            enable_routing_profiles: None,
+            route_with_congestion: None,
             alerts: AlertHandler::Print,
             infinite_parking: false,
             disable_turn_conflicts: false,
             skip_analytics: false,
+            cruise_for_parking: false,
+            actuated_signals: false,
         }
     }
 }
@@ -154,6 +194,10 @@ fn parse_rng(x: &str) -> Result<XorShiftRng> {
     Ok(XorShiftRng::seed_from_u64(seed))
 }
 
+fn parse_congestion_profile(x: &str) -> Result<CongestionProfile> {
+    abstio::maybe_read_binary(x.to_string(), &mut Timer::throwaway())
+}
+
 #[derive(Clone)]
 pub enum AlertHandler {
     /// Just print the alert to STDOUT
@@ -207,7 +251,7 @@ impl Sim {
             walking: WalkingSimState::new(),
             intersections: IntersectionSimState::new(map, &mut scheduler, &opts),
             transit: TransitSimState::new(map),
-            trips: TripManager::new(),
+            trips: TripManager::new(opts.enable_routing_profiles, opts.route_with_congestion),
             pandemic: opts.enable_pandemic_model.map(PandemicModel::new),
             scheduler,
             time: Time::START_OF_DAY,
@@ -215,12 +259,14 @@ impl Sim {
             map_name: map.get_name().clone(),
             edits_name: map.get_edits().edits_name.clone(),
             run_name: opts.run_name,
+            weather: Weather::Clear,
             step_count: 0,
             highlighted_people: None,
             alerts: opts.alerts,
 
             analytics: Analytics::new(!opts.skip_analytics),
             recorder: None,
+            traces: AgentTraces::default(),
         }
     }
 
@@ -282,6 +328,7 @@ impl Sim {
             vehicle_type: VehicleType::Car,
             length: MIN_CAR_LENGTH,
             max_speed: None,
+            behavior: DriverBehavior::default(),
         };
         let driving_lane = map.find_driving_lane_near_building(b);
 
@@ -323,13 +370,22 @@ impl Sim {
         });
     }
 
-    pub(crate) fn seed_bus_route(&mut self, route: &BusRoute) {
+    pub(crate) fn seed_bus_route(&mut self, route: &BusRoute, map: &Map) {
+        // The schedule says when vehicles depart from the first stop, so spawn them early enough
+        // to get there
+        let lead_time = self
+            .transit
+            .create_empty_route(route, map)
+            .estimate_duration(map, None);
         for t in &route.spawn_times {
-            self.scheduler.push(*t, Command::StartBus(route.id, *t));
+            self.scheduler.push(
+                t.clamped_sub(lead_time).max(self.time),
+                Command::StartBus(route.id, *t),
+            );
         }
     }
 
-    fn start_bus(&mut self, route: &BusRoute, map: &Map) {
+    fn start_bus(&mut self, route: &BusRoute, departure: Time, map: &Map) {
         // Spawn one bus for the first leg.
         let path = self.transit.create_empty_route(route, map);
 
@@ -344,6 +400,7 @@ impl Sim {
             vehicle_type,
             length,
             max_speed: None,
+            behavior: self.weather.adjust(DriverBehavior::default()),
         }
         .make(
             CarID {
@@ -361,7 +418,7 @@ impl Sim {
                     vehicle,
                     maybe_parked_car: None,
                     trip_and_person: None,
-                    maybe_route: Some(route.id),
+                    maybe_route: Some((route.id, departure)),
                 },
                 true,
             ),
@@ -375,6 +432,15 @@ impl Sim {
     pub fn get_run_name(&self) -> &String {
         &self.run_name
     }
+
+    /// Only affects vehicles spawned after this
+    pub(crate) fn set_weather(&mut self, weather: Weather) {
+        self.weather = weather;
+    }
+
+    pub fn get_weather(&self) -> Weather {
+        self.weather
+    }
 }
 
 // Running
@@ -388,6 +454,7 @@ impl Sim {
         maybe_cb: &mut Option<Box<dyn SimCallback>>,
     ) -> bool {
         self.step_count += 1;
+        self.sample_agent_traces(map);
 
         let max_time = if let Some(t) = self.scheduler.peek_next_time() {
             if t > self.time + max_dt {
@@ -524,10 +591,10 @@ impl Sim {
                                 trip,
                                 person,
                                 Some(req),
-                                if id.vehicle_type == VehicleType::Car {
-                                    TripPhaseType::Driving
-                                } else {
+                                if id.vehicle_type == VehicleType::Bike {
                                     TripPhaseType::Biking
+                                } else {
+                                    TripPhaseType::Driving
                                 },
                             ));
                         }
@@ -541,8 +608,8 @@ impl Sim {
                             }
                             self.parking.remove_parked_car(parked_car);
                         }
-                        if let Some(route) = maybe_route {
-                            self.transit.bus_created(id, route);
+                        if let Some((route, departure)) = maybe_route {
+                            self.transit.bus_created(id, route, departure);
                         }
                         self.analytics
                             .record_demand(self.driving.get_path(id).unwrap(), map);
@@ -610,8 +677,19 @@ impl Sim {
                 );
             }
             Command::UpdateIntersection(i) => {
-                self.intersections
-                    .update_intersection(self.time, i, map, &mut self.scheduler);
+                let detected = if self.intersections.detects_approaching_vehicles(i, map) {
+                    self.driving
+                        .detect_approaching_vehicles(self.time, map.get_i(i))
+                } else {
+                    BTreeSet::new()
+                };
+                self.intersections.update_intersection(
+                    self.time,
+                    i,
+                    map,
+                    &mut self.scheduler,
+                    &detected,
+                );
             }
             Command::Callback(frequency) => {
                 self.scheduler
@@ -626,8 +704,17 @@ impl Sim {
                     .unwrap()
                     .handle_cmd(self.time, cmd, &mut self.scheduler);
             }
-            Command::StartBus(r, _) => {
-                self.start_bus(map.get_br(r), map);
+            Command::StartBus(r, departure) => {
+                self.start_bus(map.get_br(r), departure, map);
+            }
+            Command::FinishCurbsideStop(car) => {
+                self.trips.car_finished_curbside_stop(
+                    self.time,
+                    car,
+                    Duration::ZERO,
+                    Distance::ZERO,
+                    &mut ctx,
+                );
             }
         }
 
@@ -647,6 +734,9 @@ impl Sim {
         for ev in events {
             if let Some(ref mut m) = self.pandemic {
                 m.handle_event(self.time, &ev, &mut self.scheduler);
+                for exposure in m.collect_events() {
+                    self.analytics.event(exposure, self.time, map);
+                }
             }
             if let Some(ref mut r) = self.recorder {
                 r.handle_event(self.time, &ev, map, &self.driving);
@@ -822,6 +912,67 @@ impl Sim {
     pub fn load_savestate(path: String, timer: &mut Timer) -> Result<Sim> {
         abstio::maybe_read_binary(path, timer)
     }
+
+    /// Saves the full state of the simulation under a name, so a long run can be resumed later.
+    /// Unlike a savestate, this includes the detailed analytics, like the route each trip took,
+    /// and the experimental pandemic model. Saving with the same name again overwrites the
+    /// checkpoint. Any traffic recording isn't saved. Returns the path written.
+    pub fn save_checkpoint(&mut self, name: &str) -> String {
+        let path = abstio::path_checkpoint(&self.map_name, &self.edits_name, name);
+        let extras = self.take_checkpoint_extras();
+        abstio::write_binary(path.clone(), &(&*self, &extras));
+        self.restore_checkpoint_extras(extras);
+        path
+    }
+
+    /// Restores a checkpoint saved for this map and its current edits.
+    pub fn load_checkpoint(map: &Map, name: &str, timer: &mut Timer) -> Result<Sim> {
+        let edits_name = &map.get_edits().edits_name;
+        let path = abstio::path_checkpoint(map.get_name(), edits_name, name);
+        let (mut sim, extras): (Sim, CheckpointExtras) = abstio::maybe_read_binary(path, timer)?;
+        if &sim.map_name != map.get_name() || &sim.edits_name != edits_name {
+            bail!(
+                "The checkpoint is for {} with edits {}, not {} with edits {}",
+                sim.map_name.describe(),
+                sim.edits_name,
+                map.get_name().describe(),
+                edits_name
+            );
+        }
+        sim.restore_checkpoint_extras(extras);
+        Ok(sim)
+    }
+
+    /// Moves out everything that savestates skip, but that a checkpoint needs to resume exactly.
+    fn take_checkpoint_extras(&mut self) -> CheckpointExtras {
+        CheckpointExtras {
+            analytics: std::mem::take(&mut self.analytics.detailed),
+            pandemic: self.pandemic.take(),
+            routing_profile_rng: self.trips.routing_profile_rng.take(),
+        }
+    }
+
+    fn restore_checkpoint_extras(&mut self, extras: CheckpointExtras) {
+        self.analytics.detailed = extras.analytics;
+        self.pandemic = extras.pandemic;
+        self.trips.routing_profile_rng = extras.routing_profile_rng;
+    }
+
+    /// The names of all checkpoints saved for this map and its current edits.
+    pub fn list_checkpoints(map: &Map) -> Vec<String> {
+        abstio::list_all_objects(abstio::path_all_checkpoints(
+            map.get_name(),
+            &map.get_edits().edits_name,
+        ))
+    }
+}
+
+/// The parts of a Sim that savestates skip, saved next to it in a checkpoint
+#[derive(Serialize, Deserialize)]
+struct CheckpointExtras {
+    analytics: DetailedAnalytics,
+    pandemic: Option<PandemicModel>,
+    routing_profile_rng: Option<XorShiftRng>,
 }
 
 // Live edits
@@ -983,6 +1134,54 @@ impl Sim {
     }
 }
 
+// Handing people between simulations. See ParallelSim.
+impl Sim {
+    /// Removes an agent from the map. The rest of its trip is up to another simulation.
+    pub(crate) fn hand_off_agent(&mut self, id: AgentID, map: &Map) {
+        let mut ctx = Ctx {
+            parking: &mut self.parking,
+            intersections: &mut self.intersections,
+            scheduler: &mut self.scheduler,
+            map,
+            handling_live_edits: None,
+        };
+        match id {
+            AgentID::Car(car) => {
+                self.driving.delete_car(car, self.time, &mut ctx);
+            }
+            AgentID::Pedestrian(ped) => {
+                self.walking.delete_ped(ped, &mut ctx);
+            }
+            AgentID::BusPassenger(_, _) => unreachable!(),
+        }
+        self.trips.trip_handed_off(id);
+    }
+
+    /// Schedules one trip for somebody whose previous trip happened in another simulation. The
+    /// departure can't be in this simulation's past.
+    pub(crate) fn schedule_handed_off_trip(
+        &mut self,
+        person: PersonID,
+        mut info: TripInfo,
+        args: StartTripArgs,
+    ) -> TripID {
+        info.departure = info.departure.max(self.time);
+        self.trips.person_moved(person, info.start);
+        let trip = self.trips.new_trip(person, info.clone());
+        if let Some(msg) = info.cancellation_reason {
+            self.trips.cancel_unstarted_trip(trip, msg);
+        } else {
+            self.scheduler
+                .push(info.departure, Command::StartTrip(trip, args));
+        }
+        trip
+    }
+
+    pub(crate) fn is_trip_done(&self, id: TripID) -> bool {
+        self.trips.is_trip_done(id)
+    }
+}
+
 // Callbacks
 pub trait SimCallback: downcast_rs::Downcast {
     // Run at some scheduled time. If this returns true, halt simulation.
@@ -1018,6 +1217,45 @@ impl Sim {
     pub fn save_recorded_traffic(&mut self, map: &Map) {
         self.recorder.take().unwrap().save(map);
     }
+
+    /// Normally the routes of old trips are eventually forgotten. Keep all of them, so every trip
+    /// can be recorded at the end.
+    pub fn keep_all_trip_traversals(&mut self) {
+        self.analytics.keep_all_trip_traversals();
+    }
+}
+
+// Tracing agents
+impl Sim {
+    /// Start recording timestamped positions of an agent, sampled as the simulation runs.
+    pub fn record_agent_trace(&mut self, id: AgentID) {
+        self.traces.record(id);
+    }
+
+    /// Stop recording an agent, returning everything recorded so far.
+    pub fn stop_agent_trace(&mut self, id: AgentID) -> Option<Vec<(Time, Pt2D)>> {
+        self.traces.stop(id)
+    }
+
+    pub fn get_agent_traces(&self) -> &BTreeMap<AgentID, Vec<(Time, Pt2D)>> {
+        self.traces.get()
+    }
+
+    /// Every recorded trace as a LineString, with a `times` property giving the seconds since
+    /// midnight of each point.
+    pub fn agent_traces_to_geojson(&self, map: &Map) -> geojson::GeoJson {
+        self.traces.to_geojson(map)
+    }
+
+    fn sample_agent_traces(&mut self, map: &Map) {
+        if self.traces.is_empty() {
+            return;
+        }
+        // Borrow the pieces canonical_pt_for_agent needs separately from the traces
+        let mut traces = std::mem::take(&mut self.traces);
+        traces.sample(self.time, |id| self.canonical_pt_for_agent(id, map));
+        self.traces = traces;
+    }
 }
 
 // Managing highlighted people
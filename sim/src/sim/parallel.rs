@@ -0,0 +1,366 @@
+use std::collections::BTreeSet;
+
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rand_xorshift::XorShiftRng;
+
+use abstutil::{prettyprint_usize, Timer};
+use geom::{Duration, Time};
+use map_model::connectivity::MapRegions;
+use map_model::{LaneID, Map, Position, Traversable};
+
+use crate::make::seed_parked_cars;
+use crate::{
+    AgentID, PersonID, Scenario, Sim, SimOptions, StartTripArgs, TripEndpoint, TripID, TripInfo,
+    TripMode, SPAWN_DIST,
+};
+
+/// Regions with fewer intersections than this aren't worth simulating on their own
+const MIN_REGION_SIZE: usize = 50;
+/// How often all of the simulations wait for each other to catch up and exchange agents
+const SYNC_INTERVAL: Duration = Duration::const_seconds(60.0);
+
+/// Simulates one scenario on many threads, by splitting the map into regions that only connect to
+/// each other over bridges. Each region gets its own `Sim`, with only the cars initially parked
+/// inside of it. Every simulation knows about everybody in the scenario, but each person is only
+/// ever active in one of them.
+///
+/// A person's trip starts in the region of its origin. Every `SYNC_INTERVAL`, all of the
+/// simulations stop, and any agent that's left its region is removed and handed off to the
+/// simulation for the region it's headed into. It reappears there at the first lane its path
+/// reaches in that region, delayed by how long the rest of the way would take in free-flowing
+/// traffic. Anybody who finishes a trip starts their next one at the following sync, so schedules
+/// can slip by up to `SYNC_INTERVAL`.
+///
+/// This is an approximation. Agents on a bridge or otherwise outside their simulation's region
+/// don't interact with the local traffic there. Buses and trains aren't handed off; every region
+/// runs the full transit service, and riders only see the vehicles in their own region. People
+/// walking to or from their car or bike aren't handed off either, so that they don't lose track of
+/// it. Results are reproducible, though: all of the simulations advance in lockstep, and agents
+/// are exchanged in a fixed order while they're stopped.
+pub struct ParallelSim {
+    /// One per region
+    sims: Vec<Sim>,
+    regions: MapRegions,
+    /// Every person's trips through the day, in order
+    schedules: Vec<Vec<(TripInfo, StartTripArgs)>>,
+    progress: Vec<PersonProgress>,
+    /// Trips that haven't been scheduled in any simulation yet
+    unscheduled_trips: usize,
+    /// The part of a trip before it's handed off is recorded as cancelled in the simulation it
+    /// left, so remember which ones those are.
+    handed_off: BTreeSet<(usize, TripID)>,
+    time: Time,
+}
+
+/// Where somebody's day is being simulated
+struct PersonProgress {
+    sim: usize,
+    /// An index into the person's schedule
+    next_trip: usize,
+    /// The current trip, or the rest of it, in `sim`
+    current: Option<TripID>,
+}
+
+impl ParallelSim {
+    pub fn new(
+        map: &Map,
+        scenario: &Scenario,
+        opts: SimOptions,
+        rng_seed: u64,
+        timer: &mut Timer,
+    ) -> ParallelSim {
+        let regions = MapRegions::new(map, MIN_REGION_SIZE);
+
+        let mut sims = Vec::new();
+        let mut schedules: Vec<Vec<(TripInfo, StartTripArgs)>> = Vec::new();
+        for idx in 0..regions.len().max(1) {
+            let mut sim = Sim::new(map, opts.clone());
+            sim.set_run_name(scenario.scenario_name.clone());
+            sim.set_weather(scenario.weather);
+            scenario.seed_buses(&mut sim, map);
+
+            // Create the same people and vehicles, with the same IDs, in every simulation
+            let mut rng = XorShiftRng::seed_from_u64(rng_seed);
+            let (parked_cars, trips) = scenario.create_people(&mut sim, &mut rng, true, timer);
+            let mut parked_cars: Vec<_> = parked_cars
+                .into_iter()
+                .filter(|(_, b)| regions.lane(map, map.get_b(*b).sidewalk()).unwrap_or(0) == idx)
+                .collect();
+            parked_cars.shuffle(&mut rng);
+            seed_parked_cars(parked_cars, &mut sim, map, &mut rng, timer);
+
+            if idx == 0 {
+                schedules = vec![Vec::new(); sim.get_all_people().len()];
+                for (person, info, args) in trips {
+                    schedules[person.0].push((info, args));
+                }
+            }
+            sims.push(sim);
+        }
+
+        let mut parallel = ParallelSim {
+            progress: schedules
+                .iter()
+                .map(|_| PersonProgress {
+                    sim: 0,
+                    next_trip: 0,
+                    current: None,
+                })
+                .collect(),
+            unscheduled_trips: schedules.iter().map(|trips| trips.len()).sum(),
+            sims,
+            regions,
+            schedules,
+            handed_off: BTreeSet::new(),
+            time: Time::START_OF_DAY,
+        };
+        timer.start("schedule first trips");
+        parallel.start_next_trips(map);
+        for sim in &mut parallel.sims {
+            // Nothing more to spawn, but this sets up the pandemic model
+            sim.spawn_trips(Vec::new(), map, timer);
+        }
+        timer.stop("schedule first trips");
+        parallel
+    }
+
+    /// Advance every simulation by some amount of time, each on its own thread.
+    pub fn timed_step(&mut self, map: &Map, dt: Duration, timer: &mut Timer) {
+        let end_time = self.time + dt;
+        timer.start(format!(
+            "Advance {} simulations to {}",
+            self.sims.len(),
+            end_time
+        ));
+        while self.time < end_time {
+            let sync_time = (self.time + SYNC_INTERVAL).min(end_time);
+            std::thread::scope(|scope| {
+                for sim in &mut self.sims {
+                    scope.spawn(move || {
+                        sim.timed_step(
+                            map,
+                            sync_time - sim.time(),
+                            &mut None,
+                            &mut Timer::throwaway(),
+                        );
+                    });
+                }
+            });
+            self.time = sync_time;
+
+            self.hand_off_agents(map);
+            self.start_next_trips(map);
+        }
+        timer.stop(format!(
+            "Advance {} simulations to {}",
+            self.sims.len(),
+            end_time
+        ));
+    }
+
+    /// Moves every agent that's left the region of its simulation to the next region along its
+    /// path.
+    fn hand_off_agents(&mut self, map: &Map) {
+        let mut handoffs = Vec::new();
+        for (idx, sim) in self.sims.iter().enumerate() {
+            for agent in sim.active_agents() {
+                if let Some((to, lane, delay)) = find_handoff(map, &self.regions, sim, idx, agent) {
+                    handoffs.push((idx, agent, to, lane, delay));
+                }
+            }
+        }
+
+        for (from, agent, to, lane, delay) in handoffs {
+            let person = self.sims[from].agent_to_person(agent).unwrap();
+            let old_trip = self.sims[from].agent_to_trip(agent).unwrap();
+            let mut info = self.sims[from].trip_info(old_trip);
+            self.sims[from].hand_off_agent(agent, map);
+            self.handed_off.insert((from, old_trip));
+
+            info.departure = self.time + delay;
+            info.start = TripEndpoint::SuddenlyAppear(Position::new(lane, SPAWN_DIST));
+            // Vehicles keep the same ID in every simulation
+            let use_vehicle = match agent {
+                AgentID::Car(car) => Some(car),
+                _ => None,
+            };
+            let args = StartTripArgs {
+                retry_if_no_room: true,
+                use_vehicle,
+            };
+            let trip = self.sims[to].schedule_handed_off_trip(person, info, args);
+
+            let progress = &mut self.progress[person.0];
+            progress.sim = to;
+            progress.current = Some(trip);
+        }
+    }
+
+    /// Schedules the next trip for everybody who's finished their last one.
+    fn start_next_trips(&mut self, map: &Map) {
+        for (idx, progress) in self.progress.iter_mut().enumerate() {
+            if let Some(trip) = progress.current {
+                if !self.sims[progress.sim].is_trip_done(trip) {
+                    continue;
+                }
+            }
+            let (info, args) = match self.schedules[idx].get(progress.next_trip) {
+                Some(pair) => pair.clone(),
+                None => {
+                    progress.current = None;
+                    continue;
+                }
+            };
+
+            // Start where the vehicle is parked, or else wherever the trip begins
+            if let Some(sim) = args.use_vehicle.and_then(|car| {
+                self.sims
+                    .iter()
+                    .position(|sim| sim.lookup_parked_car(car).is_some())
+            }) {
+                progress.sim = sim;
+            } else if let Some(region) = endpoint_region(map, &self.regions, info.start) {
+                progress.sim = region;
+            }
+            progress.current =
+                Some(self.sims[progress.sim].schedule_handed_off_trip(PersonID(idx), info, args));
+            progress.next_trip += 1;
+            self.unscheduled_trips -= 1;
+        }
+    }
+
+    pub fn time(&self) -> Time {
+        self.time
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.unscheduled_trips == 0 && self.sims.iter().all(|sim| sim.is_done())
+    }
+
+    pub fn num_active_agents(&self) -> usize {
+        self.sims.iter().map(|sim| sim.num_active_agents()).sum()
+    }
+
+    /// One simulation per region. A trip crossing between regions is split up between them; the
+    /// part in the first region is recorded as cancelled. Use `trip_counts` for totals that don't
+    /// count these.
+    pub fn sims(&self) -> &Vec<Sim> {
+        &self.sims
+    }
+
+    /// How many trips have finished and been cancelled across all of the simulations. A trip
+    /// handed off between regions only counts once, wherever it ends up.
+    pub fn trip_counts(&self) -> (usize, usize) {
+        let mut finished = 0;
+        let mut cancelled = 0;
+        for (idx, sim) in self.sims.iter().enumerate() {
+            for (_, trip, _, dt) in &sim.get_analytics().finished_trips {
+                if dt.is_some() {
+                    finished += 1;
+                } else if !self.handed_off.contains(&(idx, *trip)) {
+                    cancelled += 1;
+                }
+            }
+        }
+        (finished, cancelled)
+    }
+
+    pub fn num_handoffs(&self) -> usize {
+        self.handed_off.len()
+    }
+
+    /// Summarizes the progress of all the simulations together, then each one.
+    pub fn describe(&self) -> Vec<String> {
+        let (finished, cancelled) = self.trip_counts();
+        let mut lines = vec![format!(
+            "{} trips finished, {} cancelled, {} agents still active",
+            prettyprint_usize(finished),
+            prettyprint_usize(cancelled),
+            prettyprint_usize(self.num_active_agents())
+        )];
+        for (idx, sim) in self.sims.iter().enumerate() {
+            let finished = sim
+                .get_analytics()
+                .finished_trips
+                .iter()
+                .filter(|(_, _, _, dt)| dt.is_some())
+                .count();
+            lines.push(format!(
+                "Region {}: {} trips finished, {} agents still active",
+                idx,
+                prettyprint_usize(finished),
+                prettyprint_usize(sim.num_active_agents())
+            ));
+        }
+        lines.push(format!(
+            "{} agents handed off between regions",
+            prettyprint_usize(self.num_handoffs())
+        ));
+        lines
+    }
+}
+
+/// If an agent has left the region simulated by `sim`, find the next region along its path, the
+/// lane where it enters that region, and roughly how long it'll take to get there.
+fn find_handoff(
+    map: &Map,
+    regions: &MapRegions,
+    sim: &Sim,
+    idx: usize,
+    agent: AgentID,
+) -> Option<(usize, LaneID, Duration)> {
+    let person = sim.get_person(sim.agent_to_person(agent)?);
+    let max_speed = match agent {
+        AgentID::Car(car) => person.vehicles.iter().find(|v| v.id == car)?.max_speed,
+        AgentID::Pedestrian(_) => {
+            // Don't leave a car or bike behind
+            let mode = sim.trip_info(sim.agent_to_trip(agent)?).mode;
+            if mode == TripMode::Drive || mode == TripMode::Bike {
+                return None;
+            }
+            Some(person.ped_speed)
+        }
+        // Riders stay with their bus or train
+        AgentID::BusPassenger(_, _) => {
+            return None;
+        }
+    };
+
+    let path = sim.get_path(agent)?;
+    let constraints = path.get_req().constraints;
+    let mut next_region = None;
+    let mut delay = Duration::ZERO;
+    for step in path.get_steps() {
+        let on = step.as_traversable();
+        if next_region.is_none() {
+            match regions.traversable(map, on) {
+                // Still inside the region, or heading back into it
+                Some(r) if r == idx => {
+                    return None;
+                }
+                Some(r) => {
+                    next_region = Some(r);
+                }
+                // On a bridge
+                None => {}
+            }
+        }
+        if let (Some(r), Traversable::Lane(l)) = (next_region, on) {
+            if regions.lane(map, l) == Some(r) {
+                return Some((r, l, delay));
+            }
+        }
+        delay += path.dist_crossed_from_step(map, step)
+            / step.max_speed_along(max_speed, constraints, map);
+    }
+    None
+}
+
+fn endpoint_region(map: &Map, regions: &MapRegions, endpt: TripEndpoint) -> Option<usize> {
+    match endpt {
+        TripEndpoint::Bldg(b) => regions.lane(map, map.get_b(b).sidewalk()),
+        TripEndpoint::Border(i) => regions.intersection(i),
+        TripEndpoint::SuddenlyAppear(pos) => regions.lane(map, pos.lane()),
+    }
+}
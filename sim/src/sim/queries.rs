@@ -204,6 +204,8 @@ impl Sim {
             VehicleType::Bike,
             VehicleType::Bus,
             VehicleType::Train,
+            VehicleType::Truck,
+            VehicleType::Emergency,
         ] {
             let id = CarID {
                 id: idx,
@@ -214,13 +216,15 @@ impl Sim {
             }
         }
 
-        let id = CarID {
-            id: idx,
-            vehicle_type: VehicleType::Car,
-        };
-        // Only cars can be parked.
-        if self.parking.lookup_parked_car(id).is_some() {
-            return Some(id);
+        // Only cars, trucks, and emergency vehicles can be parked.
+        for vehicle_type in [VehicleType::Car, VehicleType::Truck, VehicleType::Emergency] {
+            let id = CarID {
+                id: idx,
+                vehicle_type,
+            };
+            if self.parking.lookup_parked_car(id).is_some() {
+                return Some(id);
+            }
         }
 
         None
@@ -0,0 +1,110 @@
+use std::collections::BTreeMap;
+
+use geojson::{Feature, FeatureCollection, GeoJson};
+
+use geom::{Duration, Pt2D, Time};
+use map_model::Map;
+
+use crate::AgentID;
+
+/// Don't record an agent's position more often than this
+const SAMPLE_FREQUENCY: Duration = Duration::const_seconds(1.0);
+
+/// Records timestamped positions of a few chosen agents. This is useful for debugging strange
+/// routes and for visualizing movement outside of the game.
+#[derive(Clone, Default)]
+pub(crate) struct AgentTraces {
+    traces: BTreeMap<AgentID, Vec<(Time, Pt2D)>>,
+    last_sample: Option<Time>,
+}
+
+impl AgentTraces {
+    pub fn record(&mut self, id: AgentID) {
+        self.traces.entry(id).or_insert_with(Vec::new);
+    }
+
+    pub fn stop(&mut self, id: AgentID) -> Option<Vec<(Time, Pt2D)>> {
+        self.traces.remove(&id)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.traces.is_empty()
+    }
+
+    pub fn get(&self) -> &BTreeMap<AgentID, Vec<(Time, Pt2D)>> {
+        &self.traces
+    }
+
+    /// Record the current position of every traced agent, unless the last sample was very recent.
+    /// Agents that aren't on the map right now are skipped, and an agent that hasn't moved isn't
+    /// recorded again.
+    pub fn sample<F: Fn(AgentID) -> Option<Pt2D>>(&mut self, now: Time, get_pt: F) {
+        if let Some(last) = self.last_sample {
+            if now - last < SAMPLE_FREQUENCY {
+                return;
+            }
+        }
+        self.last_sample = Some(now);
+
+        for (id, trace) in &mut self.traces {
+            if let Some(pt) = get_pt(*id) {
+                if trace
+                    .last()
+                    .map(|(_, last_pt)| *last_pt != pt)
+                    .unwrap_or(true)
+                {
+                    trace.push((now, pt));
+                }
+            }
+        }
+    }
+
+    /// Each agent becomes a LineString feature. The time of each point, in seconds since
+    /// midnight, is stored in a `times` property with the same length as the coordinates.
+    pub fn to_geojson(&self, map: &Map) -> GeoJson {
+        let mut features = Vec::new();
+        for (id, trace) in &self.traces {
+            if trace.len() < 2 {
+                continue;
+            }
+            let pts: Vec<Pt2D> = trace.iter().map(|(_, pt)| *pt).collect();
+            let coordinates = map
+                .get_gps_bounds()
+                .convert_back(&pts)
+                .into_iter()
+                .map(|gps| vec![gps.x(), gps.y()])
+                .collect();
+
+            let mut properties = serde_json::Map::new();
+            properties.insert("agent".to_string(), id.to_string().into());
+            properties.insert(
+                "times".to_string(),
+                trace
+                    .iter()
+                    .map(|(time, _)| time.inner_seconds())
+                    .collect::<Vec<_>>()
+                    .into(),
+            );
+            properties.insert("start_time".to_string(), trace[0].0.ampm_tostring().into());
+            properties.insert(
+                "end_time".to_string(),
+                trace.last().unwrap().0.ampm_tostring().into(),
+            );
+            features.push(Feature {
+                bbox: None,
+                geometry: Some(geojson::Geometry::new(geojson::Value::LineString(
+                    coordinates,
+                ))),
+                id: None,
+                properties: Some(properties),
+                foreign_members: None,
+            });
+        }
+
+        GeoJson::from(FeatureCollection {
+            bbox: None,
+            features,
+            foreign_members: None,
+        })
+    }
+}
@@ -37,6 +37,8 @@ struct Bus {
     /// Where does each passenger want to deboard?
     passengers: Vec<(PersonID, Option<BusStopID>)>,
     state: BusState,
+    /// When the schedule says this vehicle departs from the first stop
+    scheduled_departure: Time,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -153,7 +155,7 @@ impl TransitSimState {
         self.routes[&bus_route.id].start.clone()
     }
 
-    pub fn bus_created(&mut self, bus: CarID, r: BusRouteID) {
+    pub fn bus_created(&mut self, bus: CarID, r: BusRouteID, scheduled_departure: Time) {
         let route = self.routes.get_mut(&r).unwrap();
         route.active_vehicles.insert(bus);
         self.buses.insert(
@@ -163,12 +165,13 @@ impl TransitSimState {
                 route: r,
                 passengers: Vec::new(),
                 state: BusState::DrivingToStop(0),
+                scheduled_departure,
             },
         );
     }
 
-    /// If true, the bus is idling. If false, the bus actually arrived at a border and should now
-    /// vanish.
+    /// If this returns a time, the bus is idling, and shouldn't leave before then. If None, the bus
+    /// actually arrived at a border and should now vanish.
     pub fn bus_arrived_at_stop(
         &mut self,
         now: Time,
@@ -176,7 +179,7 @@ impl TransitSimState {
         trips: &mut TripManager,
         walking: &mut WalkingSimState,
         ctx: &mut Ctx,
-    ) -> bool {
+    ) -> Option<Time> {
         let mut bus = self.buses.get_mut(&id).unwrap();
         match bus.state {
             BusState::DrivingToStop(stop_idx) => {
@@ -184,6 +187,14 @@ impl TransitSimState {
                 let stop1 = self.routes[&bus.route].stops[stop_idx].id;
                 self.events
                     .push(Event::BusArrivedAtStop(id, bus.route, stop1));
+                let scheduled = ctx
+                    .map
+                    .get_br(bus.route)
+                    .scheduled_arrival(bus.scheduled_departure, stop_idx);
+                if let Some(scheduled) = scheduled {
+                    self.events
+                        .push(Event::BusArrivalVsSchedule(id, bus.route, stop1, scheduled));
+                }
 
                 // Deboard existing passengers.
                 let mut still_riding = Vec::new();
@@ -244,7 +255,14 @@ impl TransitSimState {
                     }
                 }
                 self.peds_waiting.insert(stop1, still_waiting);
-                true
+
+                // Vehicles running early wait at the first stop to depart on time. Elsewhere,
+                // they keep going, so bunching can develop.
+                if stop_idx == 0 {
+                    Some(now.max(bus.scheduled_departure))
+                } else {
+                    Some(now)
+                }
             }
             BusState::DrivingOffMap => {
                 self.routes
@@ -263,7 +281,7 @@ impl TransitSimState {
                     }
                     trips.transit_rider_reached_border(now, person, id, ctx);
                 }
-                false
+                None
             }
             BusState::AtStop(_) | BusState::Done => unreachable!(),
         }
@@ -1,20 +1,22 @@
 use std::collections::{BTreeMap, VecDeque};
 
+use rand_xorshift::XorShiftRng;
 use serde::{Deserialize, Serialize};
 
 use abstutil::{deserialize_btreemap, serialize_btreemap, Counter};
 use geom::{Distance, Duration, Speed, Time};
 use map_model::{
-    BuildingID, BusRouteID, BusStopID, IntersectionID, Map, PathConstraints, PathRequest, Position,
+    BuildingID, BusRouteID, BusStopID, CongestionProfile, IntersectionID, Map, PathConstraints,
+    PathRequest, Position,
 };
 
 use crate::sim::Ctx;
 use crate::{
     AgentID, AgentType, AlertLocation, CarID, Command, CreateCar, CreatePedestrian, DrivingGoal,
     Event, IndividTrip, OrigPersonID, ParkedCar, ParkingSim, ParkingSpot, PedestrianID, PersonID,
-    PersonSpec, Scenario, SidewalkPOI, SidewalkSpot, StartTripArgs, TransitSimState, TripEndpoint,
-    TripID, TripPhaseType, TripPurpose, TripSpec, Vehicle, VehicleSpec, VehicleType,
-    WalkingSimState,
+    PersonSpec, RoutingProfile, Scenario, SidewalkPOI, SidewalkSpot, StartTripArgs,
+    TransitSimState, TripEndpoint, TripID, TripPhaseType, TripPurpose, TripSpec, Vehicle,
+    VehicleSpec, VehicleType, WalkingSimState,
 };
 
 /// Manages people, each of which executes some trips through the day. Each trip is further broken
@@ -38,11 +40,21 @@ pub(crate) struct TripManager {
     car_id_counter: usize,
 
     events: Vec<Event>,
+
+    /// If present, each new person gets a randomly chosen RoutingProfile
+    #[serde(skip_serializing, skip_deserializing)]
+    pub(crate) routing_profile_rng: Option<XorShiftRng>,
+    /// If present, vehicles route around the congestion expected when they depart. This is saved,
+    /// so a simulation resumed from a savestate keeps routing the same way.
+    congestion: Option<CongestionProfile>,
 }
 
 // Initialization
 impl TripManager {
-    pub fn new() -> TripManager {
+    pub fn new(
+        routing_profile_rng: Option<XorShiftRng>,
+        congestion: Option<CongestionProfile>,
+    ) -> TripManager {
         TripManager {
             trips: Vec::new(),
             people: Vec::new(),
@@ -50,6 +62,8 @@ impl TripManager {
             unfinished_trips: 0,
             car_id_counter: 0,
             events: Vec::new(),
+            routing_profile_rng,
+            congestion,
         }
     }
 
@@ -61,6 +75,18 @@ impl TripManager {
         vehicle_specs: Vec<VehicleSpec>,
     ) -> &Person {
         let id = PersonID(self.people.len());
+        let routing_profile = match self.routing_profile_rng {
+            Some(ref mut rng) => RoutingProfile::sample(
+                rng,
+                vehicle_specs
+                    .iter()
+                    .any(|v| v.vehicle_type != VehicleType::Bike),
+                vehicle_specs
+                    .iter()
+                    .any(|v| v.vehicle_type == VehicleType::Bike),
+            ),
+            None => RoutingProfile::Default,
+        };
         let vehicles = vehicle_specs
             .into_iter()
             .map(|v| {
@@ -80,6 +106,7 @@ impl TripManager {
             ped: PedestrianID(id.0),
             ped_speed,
             vehicles,
+            routing_profile,
             delayed_trips: Vec::new(),
             on_bus: None,
         });
@@ -163,6 +190,7 @@ impl TripManager {
             info.mode,
             args.use_vehicle,
             args.retry_if_no_room,
+            info.curbside_dwell,
             ctx.map,
         ) {
             Ok(spec) => spec,
@@ -203,9 +231,10 @@ impl TripManager {
                     goal.goal_pos(constraints, ctx.map).unwrap(),
                     constraints,
                 );
+                let routing_profile = person.routing_profile;
                 let person = person.id;
 
-                match ctx.map.pathfind(req) {
+                match routing_profile.pathfind(req, self.congestion.as_ref(), now, ctx.map) {
                     Ok(path) => {
                         let router = goal.make_router(vehicle.id, path, ctx.map);
                         ctx.scheduler.push(
@@ -458,16 +487,33 @@ impl TripManager {
         distance_crossed: Distance,
         ctx: &mut Ctx,
     ) {
-        let trip = &mut self.trips[self.active_trip_mode.remove(&AgentID::Car(car)).unwrap().0];
+        let id = self.active_trip_mode[&AgentID::Car(car)];
+        let trip = &mut self.trips[id.0];
         trip.total_blocked_time += blocked_time;
         trip.total_distance += distance_crossed;
 
+        // A delivery or ride-hail vehicle pulled into a loading zone. It stays there until the
+        // dwell time is up, then the trip finishes.
+        if let Some(TripLeg::Drive(c, DrivingGoal::StopCurbside(_, dwell))) = trip.legs.front() {
+            assert_eq!(car, *c);
+            ctx.scheduler
+                .push(now + *dwell, Command::FinishCurbsideStop(car));
+            return;
+        }
+
+        self.active_trip_mode.remove(&AgentID::Car(car));
         match trip.legs.pop_front() {
             Some(TripLeg::Drive(c, DrivingGoal::ParkNear(_))) => {
                 assert_eq!(car, c);
             }
             _ => unreachable!(),
         };
+        if car.vehicle_type == VehicleType::Emergency {
+            self.events.push(Event::EmergencyVehicleArrived(
+                trip.id,
+                now - trip.info.departure,
+            ));
+        }
 
         match &trip.legs[0] {
             TripLeg::Walk(to) => match (spot, &to.connection) {
@@ -496,6 +542,39 @@ impl TripManager {
         );
     }
 
+    /// A delivery or ride-hail vehicle finished its stop at the curb, either in a loading zone or
+    /// double-parked. The vehicle leaves the map, and the person goes inside.
+    pub fn car_finished_curbside_stop(
+        &mut self,
+        now: Time,
+        car: CarID,
+        blocked_time: Duration,
+        distance_crossed: Distance,
+        ctx: &mut Ctx,
+    ) {
+        let trip = &mut self.trips[self.active_trip_mode.remove(&AgentID::Car(car)).unwrap().0];
+        trip.total_blocked_time += blocked_time;
+        trip.total_distance += distance_crossed;
+
+        let b = match trip.legs.pop_front() {
+            Some(TripLeg::Drive(c, DrivingGoal::StopCurbside(b, _))) => {
+                assert_eq!(car, c);
+                b
+            }
+            _ => unreachable!(),
+        };
+        // If the vehicle used a loading zone, free it up
+        if let Some(parked_car) = ctx.parking.lookup_parked_car(car).cloned() {
+            ctx.parking.remove_parked_car(parked_car);
+        }
+
+        self.people[trip.person.0].state = PersonState::Inside(b);
+        self.events
+            .push(Event::PersonEntersBuilding(trip.person, b));
+        let id = trip.id;
+        self.trip_finished(now, id, ctx);
+    }
+
     pub fn ped_reached_parking_spot(
         &mut self,
         now: Time,
@@ -544,7 +623,12 @@ impl TripManager {
 
         let person = trip.person;
         let trip = trip.id;
-        match ctx.map.pathfind(req) {
+        match self.people[person.0].routing_profile.pathfind(
+            req,
+            self.congestion.as_ref(),
+            now,
+            ctx.map,
+        ) {
             Ok(path) => {
                 let router = drive_to.make_router(parked_car.vehicle.id, path, ctx.map);
                 ctx.scheduler.push(
@@ -612,8 +696,9 @@ impl TripManager {
                 req.start.lane()
             ))
         } else {
-            ctx.map
-                .pathfind(req)
+            self.people[trip.person.0]
+                .routing_profile
+                .pathfind(req, self.congestion.as_ref(), now, ctx.map)
                 .map(|path| drive_to.make_router(bike, path, ctx.map))
         };
         match maybe_router {
@@ -877,6 +962,12 @@ impl TripManager {
             }
             _ => unreachable!(),
         };
+        if car.vehicle_type == VehicleType::Emergency {
+            self.events.push(Event::EmergencyVehicleArrived(
+                trip.id,
+                now - trip.info.departure,
+            ));
+        }
 
         self.people[trip.person.0].state = PersonState::OffMap;
         if let TripEndpoint::Border(_) = trip.info.end {
@@ -1005,14 +1096,15 @@ impl TripManager {
 
         // Don't forget the car!
         if let Some(vehicle) = abandoned_vehicle {
-            if vehicle.vehicle_type == VehicleType::Car {
+            if vehicle.vehicle_type != VehicleType::Bike {
                 // First remove the parked car, if needed. Maybe the trip was cancelled while the
                 // car was parked in the starting building.
                 if let Some(parked_car) = ctx.parking.lookup_parked_car(vehicle.id).cloned() {
                     ctx.parking.remove_parked_car(parked_car);
                 }
 
-                if let TripEndpoint::Bldg(b) = trip.info.end {
+                // Delivery vehicles and ride-hail cars would've left after their stop anyway
+                if let (TripEndpoint::Bldg(b), None) = (trip.info.end, trip.info.curbside_dwell) {
                     let driving_lane = ctx.map.find_driving_lane_near_building(b);
                     if let Some(spot) = ctx
                         .parking
@@ -1067,6 +1159,43 @@ impl TripManager {
     pub fn trip_abruptly_cancelled(&mut self, trip: TripID, agent: AgentID) {
         assert_eq!(self.active_trip_mode.remove(&agent), Some(trip));
     }
+
+    /// The agent has already been removed from the map, and the rest of its trip continues in
+    /// another simulation. The person leaves this one, without warping anywhere.
+    pub fn trip_handed_off(&mut self, agent: AgentID) {
+        let id = self.active_trip_mode.remove(&agent).unwrap();
+        let trip = &mut self.trips[id.0];
+        trip.legs.clear();
+        self.unfinished_trips -= 1;
+        trip.info.cancellation_reason = Some("continued in another region".to_string());
+        self.events
+            .push(Event::TripCancelled(trip.id, trip.info.mode));
+        self.people[trip.person.0].state = PersonState::OffMap;
+    }
+
+    /// A person finished their last trip in another simulation, and their next one starts here.
+    /// Catch them up, without warping any of their vehicles.
+    pub fn person_moved(&mut self, id: PersonID, to: TripEndpoint) {
+        let person = &mut self.people[id.0];
+        // The first trip will set the state
+        if person.trips.is_empty() {
+            return;
+        }
+        let state = match to {
+            TripEndpoint::Bldg(b) => PersonState::Inside(b),
+            TripEndpoint::Border(_) | TripEndpoint::SuddenlyAppear(_) => PersonState::OffMap,
+        };
+        if person.state == state {
+            return;
+        }
+        if let PersonState::Inside(b) = person.state {
+            self.events.push(Event::PersonLeavesBuilding(id, b));
+        }
+        if let PersonState::Inside(b) = state {
+            self.events.push(Event::PersonEntersBuilding(id, b));
+        }
+        person.state = state;
+    }
 }
 
 // Queries
@@ -1166,7 +1295,7 @@ impl TripManager {
         for a in self.active_trip_mode.keys() {
             match a {
                 AgentID::Car(c) => match c.vehicle_type {
-                    VehicleType::Car => {
+                    VehicleType::Car | VehicleType::Truck | VehicleType::Emergency => {
                         cnt.sov_drivers += 1;
                     }
                     VehicleType::Bike => {
@@ -1181,7 +1310,10 @@ impl TripManager {
                     VehicleType::Train => {
                         cnt.train_riders += 1;
                     }
-                    VehicleType::Car | VehicleType::Bike => unreachable!(),
+                    VehicleType::Car
+                    | VehicleType::Bike
+                    | VehicleType::Truck
+                    | VehicleType::Emergency => unreachable!(),
                 },
                 // These're counted separately
                 AgentID::Pedestrian(_) => {}
@@ -1215,6 +1347,11 @@ impl TripManager {
     pub fn trip_info(&self, id: TripID) -> TripInfo {
         self.trips[id.0].info.clone()
     }
+    /// Finished, cancelled, or handed off to another simulation
+    pub fn is_trip_done(&self, id: TripID) -> bool {
+        let trip = &self.trips[id.0];
+        trip.finished_at.is_some() || trip.info.cancellation_reason.is_some()
+    }
     pub fn all_trip_info(&self) -> Vec<(TripID, TripInfo)> {
         self.trips.iter().map(|t| (t.id, t.info.clone())).collect()
     }
@@ -1324,6 +1461,8 @@ pub struct TripInfo {
     pub start: TripEndpoint,
     pub end: TripEndpoint,
     pub purpose: TripPurpose,
+    /// For deliveries and ride-hail trips, how long the vehicle stops at the curb
+    pub curbside_dwell: Option<Duration>,
     /// Did a ScenarioModifier apply to this?
     pub modified: bool,
     pub cancellation_reason: Option<String>,
@@ -1458,6 +1597,8 @@ pub struct Person {
     pub ped_speed: Speed,
     /// Both cars and bikes
     pub vehicles: Vec<Vehicle>,
+    /// How this person chooses routes when driving or biking
+    pub routing_profile: RoutingProfile,
 
     delayed_trips: Vec<(TripID, StartTripArgs)>,
     on_bus: Option<CarID>,
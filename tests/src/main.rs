@@ -3,21 +3,28 @@
 use std::fs::File;
 use std::io::Write;
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use rand::seq::SliceRandom;
 
 use abstio::{CityName, MapName};
 use abstutil::Timer;
 use geom::{Distance, Duration, Time};
-use map_model::{IntersectionID, Map};
+use map_model::raw::RawMap;
+use map_model::{ControlTrafficSignal, IntersectionID, Map, StageType, TurnType};
 use sim::{IndividTrip, PersonSpec, Scenario, TripEndpoint, TripMode, TripPurpose};
 
 fn main() -> Result<()> {
     abstutil::logger::setup();
-    test_lane_changing(&import_map(abstio::path(
-        "../tests/input/lane_selection.osm",
-    )))?;
+    let lane_selection = import_map(abstio::path("../tests/input/lane_selection.osm"));
+    test_lane_changing(&lane_selection)?;
+    test_traffic_signal_json(&lane_selection)?;
+    test_actuated_signals(&lane_selection)?;
+    test_checkpoint(&lane_selection)?;
     test_map_importer()?;
+    test_midblock_crossing()?;
+    test_alley_access()?;
+    test_parallel_regions()?;
+    test_protected_turns_need_own_lane()?;
     check_proposals()?;
     smoke_test()?;
     Ok(())
@@ -45,12 +52,454 @@ fn test_map_importer() -> Result<()> {
     Ok(())
 }
 
+/// Split a road with a crossing partway along it, and make sure people cross right there. Then
+/// remove the crosswalk markings with map edits, and make sure people still cross there, without a
+/// crosswalk.
+fn test_midblock_crossing() -> Result<()> {
+    let mut raw = import_raw_map(abstio::path("../tests/input/midblock_crossing.osm"));
+    let road = *raw.roads.keys().next().unwrap();
+    let start = raw.intersections[&road.i1].point;
+    let crossing = raw.add_crossing(road, Distance::meters(50.0))?;
+
+    let mut map = Map::create_from_raw(
+        raw,
+        map_model::RawToMapOptions::default(),
+        &mut Timer::new("convert synthetic map"),
+    );
+    let i = map.get_i(map.find_i_by_osm_id(crossing)?);
+    let crosswalks: Vec<_> = i
+        .turns
+        .iter()
+        .filter(|t| t.turn_type == TurnType::Crosswalk)
+        .collect();
+    if crosswalks.is_empty() {
+        bail!("No crosswalks at the midblock crossing {}", i.id);
+    }
+    for t in crosswalks {
+        let dist = t.geom.middle().dist_to(start);
+        if (dist - Distance::meters(50.0)).abs() > Distance::meters(1.0) {
+            bail!("{} is {} along the road, not 50m", t.id, dist);
+        }
+    }
+    if map.maybe_get_stop_sign(i.id).is_none() {
+        bail!("The midblock crossing {} has no stop sign", i.id);
+    }
+
+    let id = i.id;
+    let mut edits = map.get_edits().clone();
+    for r in &i.roads {
+        let fwd = map.get_r(*r).dst_i == id;
+        edits.commands.push(map.edit_road_cmd(*r, |new| {
+            if fwd {
+                new.crosswalk_forward = false;
+            } else {
+                new.crosswalk_backward = false;
+            }
+        }));
+    }
+    map.must_apply_edits(edits, &mut Timer::throwaway());
+    let i = map.get_i(id);
+    if i.turns.iter().any(|t| t.turn_type == TurnType::Crosswalk) {
+        bail!("Crosswalks are still marked at {} after editing", id);
+    }
+    if !i
+        .turns
+        .iter()
+        .any(|t| t.turn_type == TurnType::UnmarkedCrossing)
+    {
+        bail!("Nobody can cross at {} after removing the markings", id);
+    }
+    Ok(())
+}
+
+/// A building closer to an alley than to the street out front should be driven to along the alley.
+fn test_alley_access() -> Result<()> {
+    let map = import_map(abstio::path("../tests/input/alley_access.osm"));
+    let alley = match map
+        .all_roads()
+        .iter()
+        .find(|r| r.osm_tags.is("service", "alley"))
+    {
+        Some(r) => r.id,
+        None => bail!("The alley wasn't imported"),
+    };
+    let b = &map.all_buildings()[0];
+    if b.access_road != Some(alley) {
+        bail!(
+            "{} should be reached by the alley {}, but its access road is {:?}",
+            b.id,
+            alley,
+            b.access_road
+        );
+    }
+    match b.driving_connection(&map) {
+        Some((pos, _)) if pos.lane().road == alley => {}
+        x => bail!(
+            "{} should be driven to along the alley {}, not {:?}",
+            b.id,
+            alley,
+            x.map(|(pos, _)| pos)
+        ),
+    }
+    Ok(())
+}
+
+/// A traffic signal can only give turns a protected stage of their own if they have a lane to
+/// themselves. In this map, the left turn from the north has a dedicated lane, but the left turn
+/// from the west shares its only lane with straight and right turns.
+fn test_protected_turns_need_own_lane() -> Result<()> {
+    let map = import_map(abstio::path("../tests/input/left_turn_and_bike_lane.osm"));
+    let i = match map.all_intersections().iter().find(|i| i.roads.len() == 4) {
+        Some(i) => i,
+        None => bail!("The four-way intersection wasn't imported"),
+    };
+    let base = match ControlTrafficSignal::get_possible_policies(&map, i.id)
+        .into_iter()
+        .find(|(name, _)| name == "stage per road")
+    {
+        Some((_, ts)) => ts,
+        None => bail!("No stage per road signal for {}", i.id),
+    };
+
+    for (name, exclusive) in [("north", true), ("west", false)] {
+        let left = match i.movements.values().find(|m| {
+            m.turn_type == TurnType::Left
+                && !m.id.crosswalk
+                && map.get_r(m.id.from.road).osm_tags.is("name", name)
+        }) {
+            Some(m) => m.id,
+            None => bail!("No left turn from the {} road at {}", name, i.id),
+        };
+        let mut ts = base.clone();
+        let mut stage = map_model::Stage::new();
+        stage.protected_movements.insert(left);
+        ts.stages.push(stage);
+        match (ts.validate(i), exclusive) {
+            (Ok(()), false) => bail!(
+                "A protected left turn from the {} road was allowed, but it shares a lane",
+                name
+            ),
+            (Err(err), true) => bail!(
+                "A protected left turn from the {} road was rejected, but it has its own lane: {}",
+                name,
+                err
+            ),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Two grids connected by a bridge should be split into two regions and simulated in parallel.
+/// The same seed should produce the same results, and trips crossing the bridge shouldn't be
+/// counted as cancelled.
+fn test_parallel_regions() -> Result<()> {
+    let map = import_map(abstio::path("../tests/input/two_islands.osm"));
+
+    let regions = map_model::connectivity::MapRegions::new(&map, 50);
+    if regions.len() != 2 {
+        bail!("Expected 2 regions, but found {}", regions.len());
+    }
+    for r in map.all_roads() {
+        let region = regions.road(&map, r.id);
+        if r.osm_tags.is("bridge", "yes") {
+            if region.is_some() {
+                bail!("The bridge {} is in region {:?}", r.id, region);
+            }
+            continue;
+        }
+        // The western grid comes first in the .osm, so it should be numbered first
+        let expected = if r.center_pts.middle().x() < map.get_bounds().width() / 2.0 {
+            0
+        } else {
+            1
+        };
+        if region != Some(expected) {
+            bail!(
+                "{} should be in region {}, not {:?}",
+                r.id,
+                expected,
+                region
+            );
+        }
+    }
+    if !map_model::connectivity::MapRegions::new(&map, 1000).is_empty() {
+        bail!("Both grids are smaller than 1000 intersections, but a region was found");
+    }
+
+    // West, the north of the western grid, the north of the eastern grid, then east
+    let mut borders: Vec<&map_model::Intersection> = map
+        .all_intersections()
+        .iter()
+        .filter(|i| i.is_border())
+        .collect();
+    if borders.len() != 4 {
+        bail!("Expected 4 borders, but found {}", borders.len());
+    }
+    borders.sort_by_key(|i| i.polygon.center().x() as usize);
+    let (west, north_a, north_b, east) =
+        (borders[0].id, borders[1].id, borders[2].id, borders[3].id);
+    let od = [
+        (west, east),
+        (east, west),
+        (west, north_a),
+        (north_b, east),
+        (north_a, north_b),
+        (east, north_a),
+    ];
+
+    let mut scenario = Scenario::empty(&map, "parallel_regions");
+    for idx in 0..60 {
+        let (from, to) = od[idx % od.len()];
+        scenario.people.push(PersonSpec {
+            orig_id: None,
+            trips: vec![IndividTrip::new(
+                Time::START_OF_DAY + Duration::seconds(5.0 * idx as f64),
+                TripPurpose::Shopping,
+                TripEndpoint::Border(from),
+                TripEndpoint::Border(to),
+                if idx % 2 == 0 {
+                    TripMode::Drive
+                } else {
+                    TripMode::Bike
+                },
+            )],
+        });
+    }
+
+    let run = || {
+        let mut opts = sim::SimOptions::new("test_parallel_regions");
+        opts.alerts = sim::AlertHandler::Silence;
+        let mut sim = sim::ParallelSim::new(&map, &scenario, opts, 42, &mut Timer::throwaway());
+        while !sim.is_done() && sim.time() < Time::START_OF_DAY + Duration::hours(2) {
+            sim.timed_step(&map, Duration::minutes(10), &mut Timer::throwaway());
+        }
+        sim
+    };
+    let sim1 = run();
+    let sim2 = run();
+
+    if sim1.describe() != sim2.describe() {
+        bail!(
+            "Two runs with the same seed differ:\n{}\n\nvs\n\n{}",
+            sim1.describe().join("\n"),
+            sim2.describe().join("\n")
+        );
+    }
+    if !sim1.is_done() {
+        bail!("Simulation isn't done:\n{}", sim1.describe().join("\n"));
+    }
+    if sim1.num_handoffs() == 0 {
+        bail!("Nobody crossed the bridge between regions");
+    }
+    let (finished, cancelled) = sim1.trip_counts();
+    if finished + cancelled != scenario.people.len() {
+        bail!(
+            "{} trips finished and {} were cancelled, but there were only {}",
+            finished,
+            cancelled,
+            scenario.people.len()
+        );
+    }
+    Ok(())
+}
+
+/// Every traffic signal should survive being exported to JSON and imported again, and a file
+/// without any timing plans should be rejected.
+fn test_traffic_signal_json(map: &Map) -> Result<()> {
+    let mut num_signals = 0;
+    for i in map.all_intersections() {
+        let ts = match map.maybe_get_traffic_signal(i.id) {
+            Some(ts) => ts,
+            None => continue,
+        };
+        num_signals += 1;
+        let imported = ControlTrafficSignal::import_json(ts.export_json(map).as_bytes(), map)?;
+        if imported != *ts {
+            bail!(
+                "The signal at {} changed after exporting and importing it",
+                i.id
+            );
+        }
+
+        // Head starts for pedestrians have to survive the round trip to be removed later
+        let mut with_lpi = ts.clone();
+        if with_lpi.add_leading_pedestrian_intervals() && with_lpi.validate(i).is_ok() {
+            let mut imported =
+                ControlTrafficSignal::import_json(with_lpi.export_json(map).as_bytes(), map)?;
+            if imported != with_lpi {
+                bail!(
+                    "The signal at {} with leading pedestrian intervals changed after exporting \
+                     and importing it",
+                    i.id
+                );
+            }
+            imported.remove_leading_pedestrian_intervals();
+            if imported != *ts {
+                bail!(
+                    "Removing leading pedestrian intervals at {} didn't restore the signal",
+                    i.id
+                );
+            }
+        }
+
+        let no_plans = format!(
+            "{{\"intersection_osm_node_id\": {}, \"plans\": []}}",
+            i.orig_id.0
+        );
+        if ControlTrafficSignal::import_json(no_plans.as_bytes(), map).is_ok() {
+            bail!("Importing a signal at {} without any plans worked", i.id);
+        }
+    }
+    if num_signals == 0 {
+        bail!("No traffic signals to test");
+    }
+    Ok(())
+}
+
+/// The actuated heuristic should produce valid signals where every stage is variable, and no
+/// vehicle is left yielding when it could be protected, since only protected demand is detected.
+fn test_actuated_signals(map: &Map) -> Result<()> {
+    let mut num_signals = 0;
+    for i in map.all_intersections() {
+        if !i.is_traffic_signal() {
+            continue;
+        }
+        let ts = match ControlTrafficSignal::get_possible_policies(map, i.id)
+            .into_iter()
+            .find(|(name, _)| name == "actuated by demand")
+        {
+            Some((_, ts)) => ts,
+            None => continue,
+        };
+        num_signals += 1;
+        ts.validate(i)?;
+        for (idx, stage) in ts.stages.iter().enumerate() {
+            if !matches!(stage.stage_type, StageType::Variable(_, _, _)) {
+                bail!("Stage {} of the actuated signal at {} is fixed", idx, i.id);
+            }
+            for m in &stage.yield_movements {
+                if !m.crosswalk && stage.could_be_protected(*m, i) {
+                    bail!(
+                        "Stage {} of the actuated signal at {} leaves {:?} yielding",
+                        idx,
+                        i.id,
+                        m
+                    );
+                }
+            }
+        }
+    }
+    if num_signals == 0 {
+        bail!("No actuated traffic signals to test");
+    }
+    Ok(())
+}
+
+/// Save a checkpoint partway through a simulation with the pandemic model, resume it, and make
+/// sure both copies have the same state and keep running identically.
+fn test_checkpoint(map: &Map) -> Result<()> {
+    // The same borders as test_lane_changing
+    let north = IntersectionID(7);
+    let south = IntersectionID(0);
+    let east = IntersectionID(1);
+    let west = IntersectionID(3);
+    let od = [(north, south), (east, west), (north, west), (east, south)];
+
+    let mut scenario = Scenario::empty(map, "checkpoint");
+    for idx in 0..100 {
+        let (from, to) = od[idx % od.len()];
+        scenario.people.push(PersonSpec {
+            orig_id: None,
+            trips: vec![IndividTrip::new(
+                Time::START_OF_DAY + Duration::seconds(2.0 * idx as f64),
+                TripPurpose::Shopping,
+                TripEndpoint::Border(from),
+                TripEndpoint::Border(to),
+                if idx % 2 == 0 {
+                    TripMode::Drive
+                } else {
+                    TripMode::Bike
+                },
+            )],
+        });
+    }
+
+    let mut opts = sim::SimOptions::new("test_checkpoint");
+    opts.alerts = sim::AlertHandler::Silence;
+    opts.enable_pandemic_model = Some(sim::SimFlags::for_test("test_checkpoint").make_rng());
+    let mut sim = sim::Sim::new(map, opts);
+    let mut rng = sim::SimFlags::for_test("test_checkpoint").make_rng();
+    scenario.instantiate(&mut sim, map, &mut rng, &mut Timer::throwaway());
+    sim.timed_step(
+        map,
+        Duration::minutes(2),
+        &mut None,
+        &mut Timer::throwaway(),
+    );
+
+    let path = sim.save_checkpoint("test_checkpoint");
+    let mut resumed = sim::Sim::load_checkpoint(map, "test_checkpoint", &mut Timer::throwaway())?;
+    abstio::delete_file(path);
+
+    for _ in 0..2 {
+        if sim.time() != resumed.time()
+            || sim.num_trips() != resumed.num_trips()
+            || sim.active_agents() != resumed.active_agents()
+        {
+            bail!("Resumed checkpoint has different agents at {}", sim.time());
+        }
+        if abstutil::to_binary(sim.get_analytics()) != abstutil::to_binary(resumed.get_analytics())
+            || abstutil::to_binary(&sim.get_analytics().detailed)
+                != abstutil::to_binary(&resumed.get_analytics().detailed)
+        {
+            bail!(
+                "Resumed checkpoint has different analytics at {}",
+                sim.time()
+            );
+        }
+        if abstutil::to_binary(&sim.get_pandemic_model())
+            != abstutil::to_binary(&resumed.get_pandemic_model())
+        {
+            bail!(
+                "Resumed checkpoint has a different pandemic model at {}",
+                sim.time()
+            );
+        }
+
+        sim.timed_step(
+            map,
+            Duration::minutes(2),
+            &mut None,
+            &mut Timer::throwaway(),
+        );
+        resumed.timed_step(
+            map,
+            Duration::minutes(2),
+            &mut None,
+            &mut Timer::throwaway(),
+        );
+    }
+
+    Ok(())
+}
+
 /// Run the contents of a .osm through the full map importer with default options.
 fn import_map(path: String) -> Map {
+    let mut timer = Timer::new("convert synthetic map");
+    Map::create_from_raw(
+        import_raw_map(path),
+        map_model::RawToMapOptions::default(),
+        &mut timer,
+    )
+}
+
+/// Run the contents of a .osm through the OSM importer with default options.
+fn import_raw_map(path: String) -> RawMap {
     let mut timer = Timer::new("convert synthetic map");
     let name = MapName::new("zz", "oneshot", &abstutil::basename(&path));
     let clip = None;
-    let raw = convert_osm::convert(
+    convert_osm::convert(
         path,
         name,
         clip,
@@ -69,11 +518,11 @@ fn import_map(path: String) -> Map {
             extra_buildings: None,
             skip_local_roads: false,
             filter_crosswalks: false,
+            include_driveways: false,
             gtfs_url: None,
         },
         &mut timer,
-    );
-    Map::create_from_raw(raw, map_model::RawToMapOptions::default(), &mut timer)
+    )
 }
 
 /// Verify what turns are generated by writing (from lane, to lane, turn type).
@@ -95,7 +544,12 @@ fn smoke_test() -> Result<()> {
             abstio::read_binary(abstio::path_scenario(&name, "weekday"), &mut timer)
         } else {
             let mut rng = sim::SimFlags::for_test("smoke_test").make_rng();
-            sim::ScenarioGenerator::proletariat_robot(&map, &mut rng, &mut timer)
+            sim::ScenarioGenerator::proletariat_robot(
+                &map,
+                &sim::DemandProfile::default(),
+                &mut rng,
+                &mut timer,
+            )
         };
 
         let mut opts = sim::SimOptions::new("smoke_test");
@@ -46,6 +46,10 @@ pub struct Stage {
     pub permitted_turns: BTreeSet<Turn>,
     /// The stage lasts this long before moving to the next one.
     pub stage_type: StageType,
+    /// True if this stage is a leading pedestrian interval, giving people a head start on
+    /// crosswalks before the next stage lets vehicles go too. Defaults to false.
+    #[serde(default)]
+    pub leading_pedestrian_interval: bool,
 }
 
 /// How long a stage lasts before moving to the next one.
@@ -7,7 +7,8 @@
 
 use abstutil::CloneableAny;
 
-use crate::{Canvas, Color, EventCtx, GfxCtx, Outcome, Panel};
+use crate::tools::shortcut_help::{HotkeyGroup, ShortcutHelp};
+use crate::{Canvas, Color, EventCtx, GfxCtx, Key, Outcome, Panel};
 
 /// Any data that should last the entire lifetime of the application should be stored in the struct
 /// implementing this trait.
@@ -31,17 +32,33 @@ pub(crate) struct App<A: SharedAppState> {
     /// A stack of states
     pub(crate) states: Vec<Box<dyn State<A>>>,
     pub(crate) shared_app_state: A,
+    /// The hotkeys of every panel that handled the previous event
+    pub(crate) hotkeys: Vec<HotkeyGroup>,
 }
 
 impl<A: SharedAppState> App<A> {
     pub(crate) fn event(&mut self, ctx: &mut EventCtx) {
         self.shared_app_state.before_event();
 
-        let transition = self
-            .states
-            .last_mut()
-            .unwrap()
-            .event(ctx, &mut self.shared_app_state);
+        // Pressing "?" anywhere lists the keyboard shortcuts, unless the user is typing
+        let transition = if ctx.focus_owned_by.is_none()
+            && ctx.is_key_down(Key::LeftShift)
+            && !self.states.last().unwrap().is::<ShortcutHelp>()
+            && ctx.input.pressed(Key::Slash)
+        {
+            Transition::Push(ShortcutHelp::new_state(
+                ctx,
+                std::mem::take(&mut self.hotkeys),
+            ))
+        } else {
+            let transition = self
+                .states
+                .last_mut()
+                .unwrap()
+                .event(ctx, &mut self.shared_app_state);
+            self.hotkeys = std::mem::take(&mut ctx.hotkeys);
+            transition
+        };
         if self.execute_transition(ctx, transition) {
             // Let the new state initialize with a fake event. Usually these just return
             // Transition::Keep, but nothing stops them from doing whatever. (For example, entering
@@ -288,8 +288,7 @@ impl PrerenderInnards {
 
         for (color, poly, z) in batch.consume() {
             let idx_offset = vertices.len() as u32;
-            let (pts, raw_indices) = poly.raw_for_rendering();
-            for pt in pts {
+            for pt in poly.points() {
                 let style = color.shader_style(*pt);
                 vertices.push([
                     pt.x() as f32,
@@ -302,8 +301,8 @@ impl PrerenderInnards {
                     style[4],
                 ]);
             }
-            for idx in raw_indices {
-                indices.push(idx_offset + (*idx as u32));
+            for tri in poly.indexed_triangles() {
+                indices.extend(tri.iter().map(|idx| idx_offset + (*idx as u32)));
             }
         }
 
@@ -232,6 +232,7 @@ impl RenderOnly {
             canvas_movement_called: false,
             focus_owned_by: None,
             next_focus_owned_by: None,
+            hotkeys: Vec::new(),
         }
     }
 
@@ -5,6 +5,7 @@ use instant::Instant;
 use abstutil::{elapsed_seconds, Timer, TimerSink};
 use geom::{Percent, Polygon};
 
+use crate::tools::shortcut_help::HotkeyGroup;
 use crate::{
     svg, Canvas, CanvasSettings, Color, Drawable, Event, GeomBatch, GfxCtx, HorizontalAlignment,
     Key, Line, Panel, Prerender, ScreenDims, Style, Text, UserInput, VerticalAlignment, Widget,
@@ -37,6 +38,10 @@ pub struct EventCtx<'a> {
     /// While handling an event, this widget (in some panel) this widget declared that it owns
     /// focus. This will become `focus_owned_by` during the next event.
     pub(crate) next_focus_owned_by: Option<String>,
+
+    /// Every panel that handled this event records its hotkeys here, so the shortcut help overlay
+    /// can list them.
+    pub(crate) hotkeys: Vec<HotkeyGroup>,
 }
 
 impl<'a> EventCtx<'a> {
@@ -89,6 +94,7 @@ impl<'a> EventCtx<'a> {
             canvas_movement_called: false,
             focus_owned_by: None,
             next_focus_owned_by: None,
+            hotkeys: Vec::new(),
         };
         let result = cb(&mut tmp);
         self.updates_requested.extend(tmp.updates_requested);
@@ -275,6 +281,7 @@ impl<'a> LoadingScreen<'a> {
             canvas_movement_called: false,
             focus_owned_by: None,
             next_focus_owned_by: None,
+            hotkeys: Vec::new(),
         };
 
         let mut txt = Text::from(Line(&self.title).small_heading());
@@ -127,7 +127,7 @@ impl GeomBatch {
     pub fn unioned_polygon(&self) -> Polygon {
         let mut result = self.list[0].1.clone();
         for (_, p, _) in &self.list[1..] {
-            result = result.union(p.clone());
+            result = result.concat(p.clone());
         }
         result
     }
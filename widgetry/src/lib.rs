@@ -4,7 +4,8 @@
 //!
 //! TODO inline pictures of some of these
 //!
-//! * [`Autocomplete`] - select predefined value by combining text entry with menus
+//! * [`Autocomplete`] - select predefined value by combining text entry with a ranked menu of
+//!   suggestions
 //! * [`Button`] - clickable buttons with keybindings and tooltips
 //! * [`Toggle`] - checkboxes, switches, and other toggles
 //! * [`CompareTimes`] - a scatter plot specialized for comparing times
@@ -14,7 +15,7 @@
 //! * [`FanChart`] - visualize a range of values over time
 //! * [`Filler`] - just carve out space in the layout for something else
 //! * [`JustDraw`] (argh private) - just draw text, `GeomBatch`es, SVGs
-//! * [`LinePlot`] - visualize 2 variables with a line plot
+//! * [`LinePlot`] - visualize one or more series over a shared X axis with a line or area plot
 //! * [`Menu`] - select something from a menu, with keybindings
 //! * [`PersistentSplit`] - a button with a dropdown to change its state
 //! * [`ScatterPlot`] - visualize 2 variables with a scatter plot
@@ -50,7 +51,7 @@ pub use crate::style::{ButtonStyle, OutlineStyle, Style};
 pub use crate::text::{Font, Line, Text, TextExt, TextSpan};
 pub use crate::tools::warper::Warper;
 pub use crate::tools::Cached;
-pub use crate::widgets::autocomplete::Autocomplete;
+pub use crate::widgets::autocomplete::{match_quality, Autocomplete, MatchQuality};
 pub(crate) use crate::widgets::button::Button;
 pub use crate::widgets::button::ButtonBuilder;
 pub use crate::widgets::compare_times::CompareTimes;
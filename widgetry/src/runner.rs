@@ -115,6 +115,7 @@ impl<A: SharedAppState> State<A> {
                 // If the widget owning focus doesn't renew it, then it'll expire by the end of
                 // this event.
                 next_focus_owned_by: None,
+                hotkeys: Vec::new(),
             };
             let started = Instant::now();
             self.app.event(&mut ctx);
@@ -366,11 +367,13 @@ pub fn run<
         canvas_movement_called: false,
         focus_owned_by: None,
         next_focus_owned_by: None,
+        hotkeys: Vec::new(),
     });
     timer.stop("setup app");
     let app = App {
         states,
         shared_app_state,
+        hotkeys: Vec::new(),
     };
     timer.done();
 
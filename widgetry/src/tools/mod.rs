@@ -1,4 +1,5 @@
 pub mod screenshot;
+pub mod shortcut_help;
 pub mod warper;
 
 /// Store a cached key/value pair, only recalculating when the key changes.
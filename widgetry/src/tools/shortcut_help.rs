@@ -0,0 +1,82 @@
+//! Pressing "?" anywhere lists the keyboard shortcuts of the current state. Nothing needs to opt
+//! in: every panel handling an event records the hotkeys of its buttons, plus any hotkeys
+//! described with `PanelBuilder::describe_hotkey`.
+
+use geom::Percent;
+
+use crate::{
+    DrawBaselayer, EventCtx, GfxCtx, Line, MultiKey, Outcome, Panel, SharedAppState, State, Text,
+    Transition, Widget,
+};
+
+/// The hotkeys that one panel responds to
+pub(crate) struct HotkeyGroup {
+    pub title: Option<String>,
+    pub keys: Vec<(MultiKey, String)>,
+}
+
+pub(crate) struct ShortcutHelp {
+    panel: Panel,
+}
+
+impl ShortcutHelp {
+    pub fn new_state<A: SharedAppState>(
+        ctx: &mut EventCtx,
+        groups: Vec<HotkeyGroup>,
+    ) -> Box<dyn State<A>> {
+        let mut col = vec![Widget::row(vec![
+            Line("Keyboard shortcuts").small_heading().into_widget(ctx),
+            ctx.style().btn_close_widget(ctx),
+        ])];
+        if groups.is_empty() {
+            col.push(
+                Line("Nothing here has a keyboard shortcut")
+                    .secondary()
+                    .into_widget(ctx),
+            );
+        }
+        for group in groups {
+            col.push(Widget::horiz_separator(ctx, 1.0));
+            let mut txt = Text::new();
+            if let Some(title) = group.title {
+                txt.add_line(Line(title).small_heading());
+            }
+            for (key, description) in group.keys {
+                txt.add_line(Line(key.describe()).fg(ctx.style().text_hotkey_color));
+                txt.append(Line(format!(": {}", description)));
+            }
+            col.push(txt.into_widget(ctx));
+        }
+
+        Box::new(ShortcutHelp {
+            panel: Panel::new_builder(Widget::col(col))
+                .max_size(Percent::int(50), Percent::int(80))
+                .build(ctx),
+        })
+    }
+}
+
+impl<A: SharedAppState> State<A> for ShortcutHelp {
+    fn event(&mut self, ctx: &mut EventCtx, _: &mut A) -> Transition<A> {
+        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+            match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                _ => unreachable!(),
+            }
+        }
+        if self.panel.clicked_outside(ctx) {
+            return Transition::Pop;
+        }
+        Transition::Keep
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::PreviousState
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &A) {
+        self.panel.draw(g);
+    }
+}
@@ -65,23 +65,100 @@ impl<T: 'static + Clone> Autocomplete<T> {
             format!("anything matching \"{}\"", self.current_line),
             (),
         )];
-        let query = self.current_line.to_ascii_lowercase();
-        for (name, _) in &self.choices {
-            if name.to_ascii_lowercase().contains(&query) {
-                choices.push(Choice::new(name, ()));
-            }
+        for idx in self.ranked_matches() {
             if choices.len() == self.num_search_results {
                 break;
             }
+            choices.push(Choice::new(&self.choices[idx].0, ()));
         }
         // "anything matching" is silly if we've resolved to exactly one choice
         if choices.len() == 2 {
             choices.remove(0);
         }
+        let has_catchall = choices.len() > 2;
         self.menu = Menu::new(ctx, choices);
+        // Once something's typed, pressing enter should pick the best suggestion, not everything
+        if has_catchall && !self.current_line.is_empty() {
+            self.menu.set_current(1);
+        }
+    }
+
+    /// Indices into choices that match the current line, best first. If nothing contains the
+    /// query directly, fall back to fuzzier matches, where the query's characters just appear in
+    /// order.
+    fn ranked_matches(&self) -> Vec<usize> {
+        rank_matches(
+            self.choices.iter().map(|(name, _)| name.as_str()),
+            &self.current_line,
+        )
     }
 }
 
+/// The indices of the names matching the query, best first. Subsequence matches are only
+/// included when nothing better matches.
+fn rank_matches<'a, I: Iterator<Item = &'a str>>(names: I, query: &str) -> Vec<usize> {
+    if query.is_empty() {
+        return names.enumerate().map(|(idx, _)| idx).collect();
+    }
+    let mut scored: Vec<(MatchQuality, usize, usize)> = Vec::new();
+    for (idx, name) in names.enumerate() {
+        if let Some(quality) = match_quality(name, query) {
+            scored.push((quality, name.len(), idx));
+        }
+    }
+    if scored
+        .iter()
+        .any(|(q, _, _)| *q != MatchQuality::Subsequence)
+    {
+        scored.retain(|(q, _, _)| *q != MatchQuality::Subsequence);
+    }
+    // Among equally good matches, prefer shorter names; they're closer to what was typed.
+    scored.sort();
+    scored.into_iter().map(|(_, _, idx)| idx).collect()
+}
+
+/// How well a name matches a query, best first
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MatchQuality {
+    Exact,
+    Prefix,
+    WordPrefix,
+    Substring,
+    Subsequence,
+}
+
+/// Does the name match the query, ignoring case? If the query isn't a prefix or substring of the
+/// name, its characters (besides whitespace) still have to appear in order.
+pub fn match_quality(name: &str, query: &str) -> Option<MatchQuality> {
+    let name = name.to_ascii_lowercase();
+    let query = query.to_ascii_lowercase();
+    let query = query.as_str();
+    if name == query {
+        return Some(MatchQuality::Exact);
+    }
+    if name.starts_with(query) {
+        return Some(MatchQuality::Prefix);
+    }
+    if name
+        .split(|c: char| !c.is_alphanumeric())
+        .any(|word| word.starts_with(query))
+    {
+        return Some(MatchQuality::WordPrefix);
+    }
+    if name.contains(query) {
+        return Some(MatchQuality::Substring);
+    }
+    let mut name_chars = name.chars();
+    if query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .all(|q| name_chars.any(|c| c == q))
+    {
+        return Some(MatchQuality::Subsequence);
+    }
+    None
+}
+
 impl<T: 'static + Clone> WidgetImpl for Autocomplete<T> {
     fn get_dims(&self) -> ScreenDims {
         let d1 = self.tb.get_dims();
@@ -112,12 +189,9 @@ impl<T: 'static + Clone> WidgetImpl for Autocomplete<T> {
             self.menu.event(ctx, &mut tmp_output);
             if let Outcome::Clicked(ref choice) = tmp_output.outcome {
                 if choice.starts_with("anything matching") {
-                    let query = self.current_line.to_ascii_lowercase();
                     let mut matches = Vec::new();
-                    for (name, choices) in &self.choices {
-                        if name.to_ascii_lowercase().contains(&query) {
-                            matches.extend(choices.clone());
-                        }
+                    for idx in self.ranked_matches() {
+                        matches.extend(self.choices[idx].1.clone());
                     }
                     self.chosen_values = Some(matches);
                 } else {
@@ -139,3 +213,54 @@ impl<T: 'static + Clone> WidgetImpl for Autocomplete<T> {
         self.menu.draw(g);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_quality() {
+        let name = "Main Street";
+        assert_eq!(
+            match_quality(name, "main street"),
+            Some(MatchQuality::Exact)
+        );
+        assert_eq!(match_quality(name, "MAIN"), Some(MatchQuality::Prefix));
+        assert_eq!(match_quality(name, "str"), Some(MatchQuality::WordPrefix));
+        assert_eq!(match_quality(name, "ree"), Some(MatchQuality::Substring));
+        assert_eq!(
+            match_quality(name, "mn st"),
+            Some(MatchQuality::Subsequence)
+        );
+        assert_eq!(match_quality(name, "streetmain"), None);
+        assert_eq!(match_quality(name, "x"), None);
+    }
+
+    #[test]
+    fn test_rank_matches() {
+        let names = vec![
+            "Broadway East",
+            "East Broadway",
+            "Broad Street",
+            "Broadway",
+            "Abroad Avenue",
+            "Bridge Road",
+        ];
+        // Exact, then prefixes (shorter first), then the start of a word, then anywhere
+        assert_eq!(
+            rank_matches(names.iter().cloned(), "broadway"),
+            vec![3, 0, 1]
+        );
+        assert_eq!(
+            rank_matches(names.iter().cloned(), "broad"),
+            vec![3, 2, 0, 1, 4]
+        );
+        // Nothing contains this, so fall back to the letters in order
+        assert_eq!(rank_matches(names.iter().cloned(), "bdr"), vec![5, 2]);
+        // Everything matches an empty query, in the original order
+        assert_eq!(
+            rank_matches(names.iter().cloned(), ""),
+            vec![0, 1, 2, 3, 4, 5]
+        );
+    }
+}
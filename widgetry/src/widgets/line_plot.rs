@@ -1,18 +1,25 @@
-use geom::{Angle, Bounds, Circle, Distance, FindClosest, PolyLine, Pt2D, UnitFmt};
+use geom::{Angle, Circle, Distance, PolyLine, Pt2D, Ring, UnitFmt};
 
-use crate::widgets::plots::{make_legend, thick_lineseries, Axis, PlotOptions, Series};
+use crate::widgets::plots::{downsample, make_legend, thick_lineseries, Axis, PlotOptions, Series};
 use crate::{
-    Color, Drawable, EdgeInsets, EventCtx, GeomBatch, GfxCtx, ScreenDims, ScreenPt,
+    Color, Drawable, EdgeInsets, EventCtx, GeomBatch, GfxCtx, Line, ScreenDims, ScreenPt,
     ScreenRectangle, Text, TextExt, Widget, WidgetImpl, WidgetOutput,
 };
 
+/// Long simulations record far more points than a plot has pixels, so each series is reduced to
+/// this many points.
+const MAX_POINTS_PER_SERIES: usize = 300;
+
+/// Plots any number of series sharing one X axis. Hovering shows a crosshair with the value of
+/// every series at that X.
 pub struct LinePlot<X: Axis<X>, Y: Axis<Y>> {
     draw: Drawable,
 
     // The geometry here is in screen-space.
     max_x: X,
     max_y: Y,
-    closest: FindClosest<String>,
+    /// Only the enabled series, downsampled
+    series: Vec<Series<X, Y>>,
 
     hovering: Option<Hovering<X, Y>>,
 
@@ -55,6 +62,9 @@ impl<X: Axis<X>, Y: Axis<Y>> LinePlot<X, Y> {
                 .max()
                 .unwrap_or_else(Y::zero)
         });
+        for s in &mut series {
+            s.pts = downsample(std::mem::take(&mut s.pts), MAX_POINTS_PER_SERIES);
+        }
 
         // TODO: somehow stretch to fill their container.
         let default_dims = {
@@ -110,17 +120,13 @@ impl<X: Axis<X>, Y: Axis<Y>> LinePlot<X, Y> {
             }
         }
 
-        let mut closest = FindClosest::new(&Bounds::from(&[
-            Pt2D::new(0.0, 0.0),
-            Pt2D::new(width, height),
-        ]));
-        for s in series {
+        for s in &series {
             if max_x == X::zero() {
                 continue;
             }
 
             let mut pts = Vec::new();
-            for (t, y) in s.pts {
+            for (t, y) in s.pts.iter().cloned() {
                 let percent_x = t.to_percent(max_x);
                 let percent_y = y.to_percent(max_y);
                 pts.push(Pt2D::new(
@@ -133,7 +139,16 @@ impl<X: Axis<X>, Y: Axis<Y>> LinePlot<X, Y> {
             // and here "meters" is really pixels.
             pts = Pt2D::approx_dedupe(pts, Distance::meters(1.0));
             if pts.len() >= 2 {
-                closest.add(s.label.clone(), &pts);
+                if opts.fill_area {
+                    let mut ring = vec![Pt2D::new(pts[0].x(), height)];
+                    ring.extend(pts.iter().cloned());
+                    ring.push(Pt2D::new(pts.last().unwrap().x(), height));
+                    ring.push(ring[0]);
+                    ring.dedup();
+                    if let Ok(ring) = Ring::new(ring) {
+                        batch.push(s.color.alpha(0.3), ring.into_polygon());
+                    }
+                }
                 batch.push(s.color, thick_lineseries(pts, Distance::meters(5.0)));
             }
         }
@@ -175,9 +190,9 @@ impl<X: Axis<X>, Y: Axis<Y>> LinePlot<X, Y> {
 
         let plot = LinePlot {
             draw: ctx.upload(batch),
-            closest,
             max_x,
             max_y,
+            series,
             hovering: None,
 
             top_left: ScreenPt::new(0.0, 0.0),
@@ -194,6 +209,7 @@ impl<X: Axis<X>, Y: Axis<Y>> LinePlot<X, Y> {
         .container()
     }
 
+    /// Returns the (X, Y) of every enabled series under the crosshair.
     pub fn get_hovering(&self) -> Vec<(X, Y)> {
         if let Some(ref h) = self.hovering {
             h.hits.clone()
@@ -245,36 +261,46 @@ impl<X: Axis<X>, Y: Axis<Y>> WidgetImpl for LinePlot<X, Y> {
             self.hovering = None;
             if let Some(cursor) = ctx.canvas.get_cursor_in_screen_space() {
                 if ScreenRectangle::top_left(self.top_left, self.dims).contains(cursor) {
-                    let radius = Distance::meters(15.0);
-                    let mut txt = Text::new();
+                    let x = self
+                        .max_x
+                        .from_percent((cursor.x - self.top_left.x) / self.dims.width);
+                    let mut txt = Text::from(Line(x.prettyprint(&self.unit_fmt)));
                     let mut hits = Vec::new();
-                    for (label, pt, _) in self.closest.all_close_pts(
-                        Pt2D::new(cursor.x - self.top_left.x, cursor.y - self.top_left.y),
-                        radius,
-                    ) {
-                        // TODO If some/all of the matches have the same x, write it once?
-                        let x = self.max_x.from_percent(pt.x() / self.dims.width);
-                        let y_percent = 1.0 - (pt.y() / self.dims.height);
-                        let y = self.max_y.from_percent(y_percent);
-
-                        // TODO Draw this info in the ColorLegend
-                        txt.add_line(format!(
-                            "{}: at {}, {}",
-                            label,
-                            x.prettyprint(&self.unit_fmt),
-                            y.prettyprint(&self.unit_fmt)
-                        ));
-                        hits.push((x, y));
+                    let mut batch = GeomBatch::new();
+                    batch.push(
+                        Color::hex("#7C7C7C"),
+                        PolyLine::must_new(vec![
+                            Pt2D::new(cursor.x, self.top_left.y),
+                            Pt2D::new(cursor.x, self.top_left.y + self.dims.height),
+                        ])
+                        .make_polygons(Distance::meters(2.0)),
+                    );
+                    for s in &self.series {
+                        if let Some(y) = value_at(&s.pts, x) {
+                            txt.add_line(
+                                Line(format!("{}: {}", s.label, y.prettyprint(&self.unit_fmt)))
+                                    .fg(s.color),
+                            );
+                            hits.push((x, y));
+                            batch.push(
+                                s.color,
+                                Circle::new(
+                                    Pt2D::new(
+                                        cursor.x,
+                                        self.top_left.y
+                                            + (1.0 - y.to_percent(self.max_y)) * self.dims.height,
+                                    ),
+                                    Distance::meters(8.0),
+                                )
+                                .to_polygon(),
+                            );
+                        }
                     }
                     if !hits.is_empty() {
                         self.hovering = Some(Hovering {
                             hits,
                             tooltip: txt,
-                            draw_cursor: GeomBatch::from(vec![(
-                                Color::RED,
-                                Circle::new(cursor.to_pt(), radius).to_polygon(),
-                            )])
-                            .upload(ctx),
+                            draw_cursor: batch.upload(ctx),
                         });
                     }
                 }
@@ -299,3 +325,20 @@ struct Hovering<X: Axis<X>, Y: Axis<Y>> {
     tooltip: Text,
     draw_cursor: Drawable,
 }
+
+/// Linearly interpolates between the points surrounding `x`, matching how the line is drawn.
+/// `None` if `x` is outside the series.
+fn value_at<X: Axis<X>, Y: Axis<Y>>(pts: &[(X, Y)], x: X) -> Option<Y> {
+    let (first, last) = (pts.first()?, pts.last()?);
+    if x < first.0 || x > last.0 {
+        return None;
+    }
+    let idx = pts.partition_point(|(px, _)| *px <= x);
+    if idx == pts.len() {
+        return Some(last.1);
+    }
+    let (x1, y1) = pts[idx - 1];
+    let (x2, y2) = pts[idx];
+    let pct = (x.to_f64() - x1.to_f64()) / (x2.to_f64() - x1.to_f64());
+    Some(Y::zero().from_f64(y1.to_f64() + pct * (y2.to_f64() - y1.to_f64())))
+}
@@ -14,7 +14,7 @@ use crate::widgets::containers::{Container, Nothing};
 pub use crate::widgets::panel::Panel;
 use crate::{
     Button, Choice, Color, DeferDraw, Drawable, Dropdown, EventCtx, GeomBatch, GfxCtx, JustDraw,
-    OutlineStyle, ScreenDims, ScreenPt, ScreenRectangle, Toggle,
+    MultiKey, OutlineStyle, ScreenDims, ScreenPt, ScreenRectangle, Toggle,
 };
 
 pub mod autocomplete;
@@ -687,6 +687,27 @@ impl Widget {
         }
     }
 
+    /// Collects (hotkey, action) for every enabled button with a hotkey.
+    fn get_all_hotkeys(&self, hotkeys: &mut Vec<(MultiKey, String)>) {
+        let btn = if let Some(btn) = self.widget.downcast_ref::<Button>() {
+            btn
+        } else if let Some(checkbox) = self.widget.downcast_ref::<Toggle>() {
+            &checkbox.btn
+        } else {
+            if let Some(container) = self.widget.downcast_ref::<Container>() {
+                for w in &container.members {
+                    w.get_all_hotkeys(hotkeys);
+                }
+            }
+            return;
+        };
+        if let Some(ref key) = btn.hotkey {
+            if btn.is_enabled() {
+                hotkeys.push((key.clone(), btn.action.clone()));
+            }
+        }
+    }
+
     fn currently_hovering(&self) -> Option<&String> {
         if let Some(btn) = self.widget.downcast_ref::<Button>() {
             if btn.hovering {
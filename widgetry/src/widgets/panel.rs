@@ -9,13 +9,14 @@ use stretch::style::{Dimension, Style};
 
 use geom::{Percent, Polygon};
 
+use crate::tools::shortcut_help::HotkeyGroup;
 use crate::widgets::slider;
 use crate::widgets::spinner::SpinnerValue;
 use crate::widgets::Container;
 use crate::{
-    Autocomplete, Button, Color, Dropdown, EventCtx, GfxCtx, HorizontalAlignment, Menu, Outcome,
-    PersistentSplit, ScreenDims, ScreenPt, ScreenRectangle, Slider, Spinner, Stash, TextBox,
-    Toggle, VerticalAlignment, Widget, WidgetImpl, WidgetOutput,
+    Autocomplete, Button, Color, Dropdown, EventCtx, GfxCtx, HorizontalAlignment, Menu, MultiKey,
+    Outcome, PersistentSplit, ScreenDims, ScreenPt, ScreenRectangle, Slider, Spinner, Stash,
+    TextBox, Toggle, VerticalAlignment, Widget, WidgetImpl, WidgetOutput,
 };
 
 pub struct Panel {
@@ -31,6 +32,9 @@ pub struct Panel {
     contents_dims: ScreenDims,
     container_dims: ScreenDims,
     clip_rect: Option<ScreenRectangle>,
+
+    hotkey_group: Option<String>,
+    extra_hotkeys: Vec<(MultiKey, String)>,
 }
 
 impl Panel {
@@ -41,6 +45,8 @@ impl Panel {
             vert: VerticalAlignment::Center,
             dims: Dims::MaxPercent(Percent::int(100), Percent::int(100)),
             ignore_initial_events: false,
+            hotkey_group: None,
+            extra_hotkeys: Vec::new(),
         }
     }
 
@@ -320,6 +326,15 @@ impl Panel {
             ctx.next_focus_owned_by = Some(id.clone());
         }
 
+        let mut keys = self.extra_hotkeys.clone();
+        self.top_level.get_all_hotkeys(&mut keys);
+        if !keys.is_empty() {
+            ctx.hotkeys.push(HotkeyGroup {
+                title: self.hotkey_group.clone(),
+                keys,
+            });
+        }
+
         output.outcome
     }
 
@@ -580,6 +595,8 @@ pub struct PanelBuilder {
     vert: VerticalAlignment,
     dims: Dims,
     ignore_initial_events: bool,
+    hotkey_group: Option<String>,
+    extra_hotkeys: Vec<(MultiKey, String)>,
 }
 
 enum Dims {
@@ -610,6 +627,9 @@ impl PanelBuilder {
             container_dims: ScreenDims::new(0.0, 0.0),
             clip_rect: None,
             cached_flexbox: None,
+
+            hotkey_group: self.hotkey_group,
+            extra_hotkeys: self.extra_hotkeys,
         };
         match panel.dims {
             Dims::ExactPercent(w, h) => {
@@ -705,4 +725,22 @@ impl PanelBuilder {
         self.ignore_initial_events = true;
         self
     }
+
+    /// Lists this panel's hotkeys under a heading in the "?" shortcut help overlay. Panels without
+    /// a heading are still listed.
+    pub fn hotkey_group<I: Into<String>>(mut self, title: I) -> PanelBuilder {
+        self.hotkey_group = Some(title.into());
+        self
+    }
+
+    /// Buttons with hotkeys are automatically listed in the "?" shortcut help overlay. If the
+    /// state handles a key itself, describe it here so it's listed too.
+    pub fn describe_hotkey<MK: Into<MultiKey>, I: Into<String>>(
+        mut self,
+        key: MK,
+        description: I,
+    ) -> PanelBuilder {
+        self.extra_hotkeys.push((key.into(), description.into()));
+        self
+    }
 }
@@ -12,6 +12,8 @@ pub struct PlotOptions<X: Axis<X>, Y: Axis<Y>> {
     pub max_y: Option<Y>,
     pub disabled: HashSet<String>,
     pub dims: Option<ScreenDims>,
+    /// Shade the area under each line
+    pub fill_area: bool,
 }
 
 impl<X: Axis<X>, Y: Axis<Y>> PlotOptions<X, Y> {
@@ -151,6 +153,22 @@ pub struct Series<X, Y> {
     pub pts: Vec<(X, Y)>,
 }
 
+/// Reduces a series to at most `max_points`, preserving its visual shape with the
+/// largest-triangle-three-buckets algorithm. The points kept are from the original series.
+pub fn downsample<X: Axis<X>, Y: Axis<Y>>(pts: Vec<(X, Y)>, max_points: usize) -> Vec<(X, Y)> {
+    if pts.len() <= max_points {
+        return pts;
+    }
+    let raw = pts
+        .into_iter()
+        .map(|(x, y)| lttb::DataPoint::new(x.to_f64(), y.to_f64()))
+        .collect();
+    lttb::lttb(raw, max_points)
+        .into_iter()
+        .map(|pt| (X::zero().from_f64(pt.x), Y::zero().from_f64(pt.y)))
+        .collect()
+}
+
 pub fn make_legend<X: Axis<X>, Y: Axis<Y>>(
     ctx: &EventCtx,
     series: &[Series<X, Y>],
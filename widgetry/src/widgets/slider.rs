@@ -12,6 +12,8 @@ pub struct Slider {
 
     style: Style,
     label: Option<String>,
+    /// Keep dragging when the panel is rebuilt
+    restorable: bool,
 
     draw: Drawable,
 
@@ -101,6 +103,19 @@ impl Slider {
         .named(label)
     }
 
+    /// Like `area`, but if the panel is rebuilt while this slider is being dragged, the new slider
+    /// continues the drag. Use this when the panel is rebuilt in response to the slider changing.
+    pub fn restorable_area(
+        ctx: &EventCtx,
+        width: f64,
+        current_percent: f64,
+        label: &str,
+    ) -> Widget {
+        let mut widget = Slider::area(ctx, width, current_percent, label);
+        widget.widget.downcast_mut::<Slider>().unwrap().restorable = true;
+        widget
+    }
+
     fn new_widget(
         ctx: &EventCtx,
         style: Style,
@@ -114,6 +129,7 @@ impl Slider {
             style,
             draw: Drawable::empty(ctx),
             label,
+            restorable: false,
 
             top_left: ScreenPt::new(0.0, 0.0),
             dims: ScreenDims::new(0.0, 0.0),
@@ -329,4 +345,15 @@ impl WidgetImpl for Slider {
         g.canvas
             .mark_covered_area(ScreenRectangle::top_left(self.top_left, self.dims));
     }
+
+    fn can_restore(&self) -> bool {
+        self.restorable
+    }
+    fn restore(&mut self, ctx: &mut EventCtx, prev: &dyn WidgetImpl) {
+        let prev = prev.downcast_ref::<Slider>().unwrap();
+        self.current_percent = prev.current_percent;
+        self.mouse_on_slider = prev.mouse_on_slider;
+        self.dragging = prev.dragging;
+        self.recalc(ctx);
+    }
 }
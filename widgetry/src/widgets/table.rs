@@ -1,13 +1,19 @@
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
 use abstutil::prettyprint_usize;
 use geom::Polygon;
 
 use crate::{
     include_labeled_bytes, Color, ControlState, EventCtx, GeomBatch, Key, Line, Panel, Text,
-    TextExt, Widget,
+    TextBox, TextExt, Widget,
 };
 
 const ROWS: usize = 8;
 
+/// The label of the button that every table renders. Callers handle it with `to_csv`.
+pub const EXPORT_CSV: &str = "Export table to CSV";
+
 pub struct Table<A, T, F> {
     id: String,
     data: Vec<T>,
@@ -18,6 +24,8 @@ pub struct Table<A, T, F> {
     sort_by: String,
     descending: bool,
     skip: usize,
+    /// Per column name, only show rows whose text contains this (lowercased)
+    column_filters: BTreeMap<String, String>,
 }
 
 pub enum Col<T> {
@@ -29,6 +37,8 @@ struct Column<A, T> {
     name: String,
     render: Box<dyn Fn(&EventCtx, &A, &T) -> GeomBatch>,
     col: Col<T>,
+    /// Only columns with a plain text representation can be filtered and exported.
+    to_text: Option<Rc<dyn Fn(&A, &T) -> String>>,
 }
 
 pub struct Filter<A, T, F> {
@@ -56,6 +66,7 @@ impl<A, T, F> Table<A, T, F> {
             sort_by: default_sort_by.to_string(),
             descending: true,
             skip: 0,
+            column_filters: BTreeMap::new(),
         }
     }
 
@@ -69,12 +80,31 @@ impl<A, T, F> Table<A, T, F> {
             name: name.to_string(),
             render,
             col,
+            to_text: None,
         });
     }
 
     pub fn replace_render(&self, ctx: &mut EventCtx, app: &A, panel: &mut Panel) {
+        // Typing into a filter re-renders the table, so keep the same text box focused
+        let focused: Vec<String> = self
+            .columns
+            .iter()
+            .filter(|col| col.to_text.is_some())
+            .map(|col| self.filter_name(col))
+            .filter(|name| {
+                panel
+                    .maybe_find::<TextBox>(name)
+                    .map(|tb| tb.has_focus())
+                    .unwrap_or(false)
+            })
+            .collect();
+
         let new_widget = self.render(ctx, app);
         panel.replace(ctx, &self.id, new_widget);
+
+        for name in focused {
+            panel.find_mut::<TextBox>(&name).set_focus();
+        }
     }
 
     /// Get all entries, filtered and sorted according to the current settings.
@@ -82,8 +112,20 @@ impl<A, T, F> Table<A, T, F> {
         let mut data: Vec<&T> = Vec::new();
 
         // Filter
+        let column_filters: Vec<(&Rc<dyn Fn(&A, &T) -> String>, &String)> = self
+            .columns
+            .iter()
+            .filter_map(|col| {
+                let query = self.column_filters.get(&col.name)?;
+                Some((col.to_text.as_ref()?, query))
+            })
+            .collect();
         for row in &self.data {
-            if (self.filter.apply)(&self.filter.state, row, app) {
+            if (self.filter.apply)(&self.filter.state, row, app)
+                && column_filters
+                    .iter()
+                    .all(|(to_text, query)| (to_text)(app, row).to_lowercase().contains(*query))
+            {
                 data.push(row);
             }
         }
@@ -148,8 +190,16 @@ impl<A, T, F> Table<A, T, F> {
         // Put together the UI
         Widget::col(vec![
             (self.filter.to_controls)(ctx, app, &self.filter.state),
+            self.render_column_filters(ctx),
             make_table(ctx, headers, rows, 0.88 * ctx.canvas.window_width),
-            make_pagination(ctx, num_filtered, self.skip),
+            Widget::row(vec![
+                make_pagination(ctx, num_filtered, self.skip),
+                ctx.style()
+                    .btn_plain
+                    .text(EXPORT_CSV)
+                    .build_def(ctx)
+                    .align_right(),
+            ]),
         ])
         .named(&self.id)
         // return in separate container in case caller want to apply an outer-name
@@ -183,8 +233,88 @@ impl<A, T, F> Table<A, T, F> {
 
     pub fn panel_changed(&mut self, panel: &Panel) {
         self.filter.state = (self.filter.from_controls)(panel);
+        self.column_filters.clear();
+        for col in &self.columns {
+            if col.to_text.is_some() {
+                if let Some(tb) = panel.maybe_find::<TextBox>(&self.filter_name(col)) {
+                    let query = tb.get_line().to_lowercase();
+                    if !query.is_empty() {
+                        self.column_filters.insert(col.name.clone(), query);
+                    }
+                }
+            }
+        }
         self.skip = 0;
     }
+
+    /// Formats every filtered row, in the current order, as CSV. Only columns with a text
+    /// representation are included.
+    pub fn to_csv(&self, app: &A) -> String {
+        let columns: Vec<&Column<A, T>> = self
+            .columns
+            .iter()
+            .filter(|col| col.to_text.is_some())
+            .collect();
+
+        let mut out = String::new();
+        out.push_str(
+            &columns
+                .iter()
+                .map(|col| csv_field(&col.name))
+                .collect::<Vec<_>>()
+                .join(","),
+        );
+        out.push('\n');
+        for row in self.get_filtered_data(app) {
+            out.push_str(
+                &columns
+                    .iter()
+                    .map(|col| csv_field(&(col.to_text.as_ref().unwrap())(app, row)))
+                    .collect::<Vec<_>>()
+                    .join(","),
+            );
+            out.push('\n');
+        }
+        out
+    }
+
+    /// A filename for `to_csv`, named after the table.
+    pub fn csv_filename(&self) -> String {
+        format!("{}.csv", self.id)
+    }
+
+    fn filter_name(&self, col: &Column<A, T>) -> String {
+        format!("{} filter {}", self.id, col.name)
+    }
+
+    fn render_column_filters(&self, ctx: &mut EventCtx) -> Widget {
+        let mut row = Vec::new();
+        for col in &self.columns {
+            if col.to_text.is_some() {
+                row.push(
+                    Widget::row(vec![
+                        format!("{}:", col.name).text_widget(ctx).centered_vert(),
+                        TextBox::widget(
+                            ctx,
+                            self.filter_name(col),
+                            self.column_filters
+                                .get(&col.name)
+                                .cloned()
+                                .unwrap_or_default(),
+                            false,
+                            10,
+                        ),
+                    ])
+                    .margin_right(16),
+                );
+            }
+        }
+        if row.is_empty() {
+            Widget::nothing()
+        } else {
+            Widget::custom_row(row)
+        }
+    }
 }
 
 impl<A, T> Filter<A, T, ()> {
@@ -199,14 +329,29 @@ impl<A, T> Filter<A, T, ()> {
 }
 
 // Simpler wrappers than column(). The more generic case exists to allow for icons and non-text
-// things.
-impl<A, T: 'static, F> Table<A, T, F> {
+// things. Columns made this way can also be filtered and exported.
+impl<A: 'static, T: 'static, F> Table<A, T, F> {
     pub fn static_col(&mut self, name: &str, to_str: Box<dyn Fn(&T) -> String>) {
-        self.column(
-            name,
-            Box::new(move |ctx, _, x| Text::from((to_str)(x)).render(ctx)),
-            Col::Static,
-        );
+        self.text_col(name, Box::new(move |_, x| (to_str)(x)), Col::Static);
+    }
+
+    pub fn text_col(&mut self, name: &str, to_text: Box<dyn Fn(&A, &T) -> String>, col: Col<T>) {
+        let to_text: Rc<dyn Fn(&A, &T) -> String> = Rc::from(to_text);
+        let render_text = to_text.clone();
+        self.columns.push(Column {
+            name: name.to_string(),
+            render: Box::new(move |ctx, app, x| Text::from((render_text)(app, x)).render(ctx)),
+            col,
+            to_text: Some(to_text),
+        });
+    }
+}
+
+fn csv_field(x: &str) -> String {
+    if x.contains(',') || x.contains('"') || x.contains('\n') {
+        format!("\"{}\"", x.replace('"', "\"\""))
+    } else {
+        x.to_string()
     }
 }
 
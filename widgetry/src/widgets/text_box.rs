@@ -93,6 +93,14 @@ impl TextBox {
     pub fn get_line(&self) -> String {
         self.line.clone()
     }
+
+    pub(crate) fn has_focus(&self) -> bool {
+        self.has_focus
+    }
+
+    pub(crate) fn set_focus(&mut self) {
+        self.has_focus = true;
+    }
 }
 
 impl WidgetImpl for TextBox {